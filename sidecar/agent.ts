@@ -1,5 +1,5 @@
 // @ts-nocheck
-import { ChatOpenAI } from "@langchain/openai";
+import { AzureChatOpenAI, ChatOpenAI } from "@langchain/openai";
 import { CompositeBackend, FilesystemBackend, createDeepAgent, createSettings } from "deepagents";
 import * as readline from "readline";
 import fs from "node:fs";
@@ -13,6 +13,16 @@ import {
   createAgentBrowserTool,
   createRandomNumberTool,
   createRunNodeTool,
+  createTerminalTool,
+  resolveTerminalReply,
+  createMemoryTool,
+  resolveMemoryReply,
+  createMcpTools,
+  resolveMcpReply,
+  type McpToolInfo,
+  createCustomTools,
+  resolveCustomToolReply,
+  type CustomToolInfo,
   // File management
   createOrganizeFolderTool,
   createFileSearchTool,
@@ -38,10 +48,21 @@ import {
   createWebOperationsTool,
   // Format conversion
   createFormatConversionTool,
+  // Execution limits
+  killExecution,
+  killRequestExecutions,
+  withExecutionLimits,
+  // Permissions
+  askPermission,
+  resolvePermissionReply,
+  withPermission,
   // Types
   StatusEmitter,
+  ToolTimeouts,
 } from "./tools/index.js";
 import { createFolderOrganizerSubagent } from "./subagents/folder_organizer.js";
+import { createProxyFetch, type ProxyOptions } from "./proxy.js";
+import { createSigningFetch, resolveSignature } from "./signing.js";
 
 const PROVIDER_BASE_URLS: Record<string, string> = {
   openrouter: "https://openrouter.ai/api/v1",
@@ -54,7 +75,12 @@ const PROVIDER_BASE_URLS: Record<string, string> = {
   perplexity: "https://api.perplexity.ai",
   xai: "https://api.x.ai/v1",
   moonshot: "https://api.moonshot.cn/v1",
+  ollama: "http://localhost:11434/v1",
+  lmstudio: "http://localhost:1234/v1",
 };
+// Local servers need no key and older versions reject `stream_options` usage requests
+const LOCAL_PROVIDERS = new Set(["ollama", "lmstudio"]);
+const AZURE_API_VERSION = "2024-10-21";
 const AGENT_NAME = "OhMyCowork";
 
 interface FileInfo {
@@ -121,6 +147,30 @@ function emitAgentStatus(payload?: AgentStatusPayload): void {
   );
 }
 
+/** Reports provider token counts summed over the messages produced by one request. */
+function emitUsage(requestId: string | null | undefined, model: string, messages: Message[]): void {
+  let inputTokens = 0;
+  let outputTokens = 0;
+  let reported = false;
+  for (const message of messages) {
+    const usage = message?.usage_metadata;
+    if (!usage) continue;
+    reported = true;
+    inputTokens += usage.input_tokens ?? 0;
+    outputTokens += usage.output_tokens ?? 0;
+  }
+  if (!reported) return;
+  console.log(
+    JSON.stringify({
+      event: "usage",
+      requestId: requestId ?? null,
+      model,
+      inputTokens,
+      outputTokens,
+    })
+  );
+}
+
 function emitAssistantDelta(requestId: string | null | undefined, delta: string): void {
   if (!delta) return;
   console.log(
@@ -138,20 +188,84 @@ function resolveBaseUrl(provider?: string, baseUrl?: string): string {
   return PROVIDER_BASE_URLS[key] ?? PROVIDER_BASE_URLS.openrouter;
 }
 
-function createChatModel(
-  apiKey: string,
-  model: string,
-  provider?: string,
-  baseUrl?: string,
-  requestId?: string | null
-): ChatOpenAI {
+function isLocalProvider(provider?: string): boolean {
+  return typeof provider === "string" && LOCAL_PROVIDERS.has(provider.toLowerCase());
+}
+
+interface AzureDeployment {
+  deployment?: string;
+  apiVersion?: string;
+}
+
+interface EndpointOverrides {
+  chatPath?: string;
+  query?: Record<string, string>;
+}
+
+interface ProviderRequest {
+  provider?: string;
+  apiKey: string;
+  model: string;
+  baseUrl?: string;
+  headers?: Record<string, string>;
+  azure?: AzureDeployment;
+  endpoint?: EndpointOverrides;
+  proxy?: ProxyOptions;
+}
+
+/** `fetch` that sends chat requests to `chatPath` under `baseURL` instead of `/chat/completions`. */
+function createChatPathFetch(baseURL: string, chatPath: string, inner: typeof fetch = fetch): typeof fetch {
+  const root = baseURL.replace(/\/+$/, "");
+  const standard = `${root}/chat/completions`;
+  return (input, init) => {
+    // The OpenAI client always passes the URL as a string
+    if (typeof input !== "string" || !input.startsWith(standard)) return inner(input, init);
+    return inner(`${root}${chatPath}${input.slice(standard.length)}`, init);
+  };
+}
+
+/** Builds the chat model for `request`, with `fields` such as `streaming` or `maxTokens` on top. */
+function createProviderModel(request: ProviderRequest, fields: Record<string, unknown>): ChatOpenAI {
+  const { provider, apiKey, model, baseUrl, headers, azure, endpoint, proxy } = request;
+  const proxied = proxy ? createProxyFetch(proxy) : undefined;
+  if (typeof provider === "string" && provider.toLowerCase() === "azure") {
+    // Azure routes by deployment and authenticates with an `api-key` header
+    return new AzureChatOpenAI({
+      ...fields,
+      model,
+      azureOpenAIApiKey: apiKey,
+      azureOpenAIEndpoint: (baseUrl ?? "").trim().replace(/\/+$/, ""),
+      azureOpenAIApiDeploymentName: azure?.deployment || model,
+      azureOpenAIApiVersion: azure?.apiVersion || AZURE_API_VERSION,
+      configuration: {
+        defaultHeaders: headers,
+        defaultQuery: endpoint?.query,
+        ...(proxied ? { fetch: proxied } : {}),
+      },
+    });
+  }
+  const bedrock = typeof provider === "string" && provider.toLowerCase() === "bedrock";
+  const baseURL = resolveBaseUrl(provider, baseUrl);
+  // Bedrock is signed with SigV4 by the host, which also fills in the regional baseUrl
+  const transport = bedrock ? createSigningFetch(proxied) : proxied;
+  const customFetch = endpoint?.chatPath ? createChatPathFetch(baseURL, endpoint.chatPath, transport) : transport;
   return new ChatOpenAI({
+    ...fields,
     apiKey,
     model,
-    streaming: true,
     configuration: {
-      baseURL: resolveBaseUrl(provider, baseUrl),
+      baseURL,
+      defaultHeaders: headers,
+      defaultQuery: endpoint?.query,
+      ...(customFetch ? { fetch: customFetch } : {}),
     },
+  });
+}
+
+function createChatModel(request: ProviderRequest, requestId?: string | null): ChatOpenAI {
+  return createProviderModel(request, {
+    streaming: true,
+    streamUsage: !isLocalProvider(request.provider),
     callbacks: [
       {
         handleLLMNewToken(token: string) {
@@ -162,19 +276,6 @@ function createChatModel(
   });
 }
 
-function createWarmupModel(apiKey: string, model: string, provider?: string, baseUrl?: string): ChatOpenAI {
-  return new ChatOpenAI({
-    apiKey,
-    model,
-    streaming: false,
-    temperature: 0,
-    maxTokens: 1,
-    configuration: {
-      baseURL: resolveBaseUrl(provider, baseUrl),
-    },
-  });
-}
-
 interface ContentItem {
   text?: string;
   [key: string]: unknown;
@@ -202,7 +303,21 @@ interface SkillsConfig {
   skills: string[];
 }
 
-function buildSkillsConfig(workspacePath?: string): SkillsConfig {
+/** Virtual mount points for the extra folders of a multi-root workspace, e.g. `/roots/api/`. */
+function rootMounts(workspaceRoots: string[]): Array<{ mount: string; rootDir: string }> {
+  const used = new Set<string>();
+  return workspaceRoots.map((rootDir) => {
+    const base = path.basename(rootDir).replace(/[^\w.-]/g, "_") || "root";
+    let name = base;
+    for (let n = 2; used.has(name); n++) {
+      name = `${base}-${n}`;
+    }
+    used.add(name);
+    return { mount: `/roots/${name}/`, rootDir };
+  });
+}
+
+function buildSkillsConfig(workspacePath?: string, workspaceRoots: string[] = []): SkillsConfig {
   const settings = createSettings({
     startPath: workspacePath ?? process.cwd(),
   });
@@ -228,6 +343,9 @@ function buildSkillsConfig(workspacePath?: string): SkillsConfig {
   }
 
   const routeKeys = Object.keys(routes);
+  for (const { mount, rootDir } of rootMounts(workspaceRoots)) {
+    routes[mount] = new FilesystemBackend({ rootDir, virtualMode: true });
+  }
   let defaultBackend: FilesystemBackend | NoopBackend;
   try {
     defaultBackend = new FilesystemBackend({
@@ -252,23 +370,47 @@ interface Message {
   tool_calls?: Array<{ name: string; args: unknown }>;
 }
 
-interface SendMessageRequest {
-  provider?: string;
-  apiKey: string;
-  model: string;
-  baseUrl?: string;
+/** Restrictions from the workspace's `.ohmycowork/config.json`. */
+interface WorkspaceRules {
+  allowedTools?: string[];
+  ignoredPaths?: string[];
+}
+
+interface SendMessageRequest extends ProviderRequest {
   messages: Message[];
   workspacePath?: string;
+  /** Further folders of a multi-root workspace, canonical and excluding `workspacePath`. */
+  workspaceRoots?: string[];
   requestId?: string;
+  toolTimeouts?: ToolTimeouts;
+  workspaceRules?: WorkspaceRules;
+  /** Tools of the user's connected MCP servers. */
+  mcpTools?: McpToolInfo[];
+  /** Tools the user registered, run by the host as local commands. */
+  customTools?: CustomToolInfo[];
+  /** In plan mode the host refuses every call that writes or runs anything. */
+  mode?: "plan" | "act";
+}
+
+// Agent runs in progress, by request id, so `cancelRequest` can stop them
+const activeRuns = new Map<string, AbortController>();
+
+/** Stops the run `requestId` and the tool calls it has in flight. */
+function cancelRequest({ requestId }: { requestId: string }): string {
+  const run = activeRuns.get(requestId);
+  run?.abort("cancelled");
+  const killed = killRequestExecutions(requestId);
+  return JSON.stringify({ cancelled: !!run, killedExecutions: killed });
 }
 
 async function sendMessage(request: SendMessageRequest): Promise<string> {
-  const { provider, apiKey, model, baseUrl, messages, workspacePath, requestId } = request;
+  const { model, messages, workspacePath, requestId, toolTimeouts, workspaceRules } = request;
   const workspaceRoot = typeof workspacePath === "string" && workspacePath.trim().length > 0
     ? workspacePath
     : undefined;
+  const workspaceRoots = workspaceRoot ? request.workspaceRoots ?? [] : [];
 
-  const chatModel = createChatModel(apiKey, model, provider, baseUrl, requestId ?? null);
+  const chatModel = createChatModel(request, requestId ?? null);
 
   const emitStatus: StatusEmitter = (payload) => emitAgentStatus({ ...payload, requestId: payload?.requestId ?? requestId ?? null });
 
@@ -282,6 +424,8 @@ async function sendMessage(request: SendMessageRequest): Promise<string> {
     createCalculateExpressionTool({ requestId, emitStatus }),
     createRunNodeTool({ requestId, emitStatus }),
     createAgentBrowserTool({ requestId, emitStatus }),
+    createTerminalTool({ workspaceRoot, requestId, emitStatus }),
+    createMemoryTool({ workspaceRoot, requestId, emitStatus }),
 
     // File management
     createOrganizeFolderTool({ workspaceRoot, requestId, emitStatus }),
@@ -315,7 +459,17 @@ async function sendMessage(request: SendMessageRequest): Promise<string> {
 
     // Format conversion
     createFormatConversionTool({ workspaceRoot, requestId, emitStatus }),
-  ];
+
+    // MCP servers
+    ...createMcpTools(request.mcpTools ?? [], { requestId, emitStatus }),
+
+    // User-registered commands
+    ...createCustomTools(request.customTools ?? [], { workspaceRoot, requestId, emitStatus }),
+  ]
+    .filter((t) => !workspaceRules?.allowedTools || workspaceRules.allowedTools.includes(t.name))
+    .map((t) => withExecutionLimits(t, { requestId, timeouts: toolTimeouts, emitStatus }))
+    // Outside the limits so time spent waiting for the user is not counted against the tool
+    .map((t) => withPermission(t, { workspaceRoot, requestId }));
 
   let systemPrompt = `You are an expert assistant with powerful automation capabilities. You have access to a comprehensive set of tools for:
 
@@ -325,6 +479,8 @@ async function sendMessage(request: SendMessageRequest): Promise<string> {
 - \`calculate_expression\`: Mathematical calculations
 - \`run_node\`: Execute Node.js code
 - \`agent_browser\`: Browser automation via Playwright
+- \`terminal\`: Named terminal sessions that keep running between commands (dev servers, watchers)
+- \`memory\`: Notes about the workspace that last across sessions (conventions, commands, the user's corrections)
 
 ## File Management
 - \`file_search\`: Search files using glob patterns
@@ -359,10 +515,17 @@ async function sendMessage(request: SendMessageRequest): Promise<string> {
 ## Format Conversion
 - \`format_conversion\`: Convert between formats (Markdown/HTML/DOCX, JSON/CSV/YAML, Base64)
 
+## MCP Servers
+- \`mcp__<server>__<tool>\`: Tools of MCP servers the user connected; each description names its server
+
+## Custom Tools
+- \`custom__<name>\`: Commands the user registered, such as internal CLIs; each description says what it does
+
 ## Subagents
 - folder-organizer: Specialized agent for intelligent folder organization
 
 When using file tools, always use workspace-relative paths (e.g., "/file.txt", not absolute paths).
+Tools that change files, run code or reach the network ask the user first. A refused call returns an error; do not retry it unchanged.
 `;
 
   // Add workspace context
@@ -375,6 +538,21 @@ The user's workspace is mounted at virtual path "/". When using filesystem tools
 - The actual system path "${workspaceRoot}" is mapped to virtual path "/"
 
 IMPORTANT: Never use absolute system paths like "/Users/..." with filesystem tools. Always use virtual paths starting with "/".`;
+    const mounts = rootMounts(workspaceRoots);
+    if (mounts.length > 0) {
+      systemPrompt += `
+
+The workspace has more folders, mounted under "/roots/":
+${mounts.map(({ mount, rootDir }) => `- "${mount}" is "${rootDir}"`).join("\n")}
+The built-in filesystem tools reach them through these virtual paths.`;
+    }
+    const ignoredPaths = workspaceRules?.ignoredPaths ?? [];
+    if (ignoredPaths.length > 0) {
+      systemPrompt += `
+
+The project's configuration excludes these paths (gitignore-style patterns). Do not read, change or delete anything matching them:
+${ignoredPaths.map((pattern) => `- \`${pattern}\``).join("\n")}`;
+    }
   } else {
     systemPrompt += `
 ## Workspace
@@ -383,14 +561,29 @@ No workspace folder is currently selected.
 - Do not call filesystem tools until a workspace is provided.`;
   }
 
-  const { backend, skills } = buildSkillsConfig(workspaceRoot);
-  const instrumentedBackend = createSkillTraceBackend(backend, emitStatus, requestId);
+  if (request.mode === "plan") {
+    systemPrompt += `
+
+## Plan Mode
+The user wants to review a plan before anything changes. Read and explore as much as you need, but do not create, change, move or delete files and do not run commands; such calls are refused. Finish with a numbered plan of the changes you would make, naming the files involved, and ask the user to switch to act mode to carry it out.`;
+  }
+
+  // A leading system message (e.g. a saved prompt template) extends the built-in prompt
+  const leadingSystem = messages?.[0]?.role === "system" ? messages[0] : null;
+  if (leadingSystem) {
+    systemPrompt += `\n\n## Instructions\n${leadingSystem.content}`;
+  }
+
+  const { backend, skills } = buildSkillsConfig(workspaceRoot, workspaceRoots);
+  const tracedBackend = createSkillTraceBackend(backend, emitStatus, requestId);
+  const instrumentedBackend = createPermissionBackend(tracedBackend, { workspaceRoot, requestId });
   const subagents = [
     createFolderOrganizerSubagent({
       model: chatModel,
       workspaceRoot,
       requestId,
       emitStatus,
+      toolTimeouts,
     }),
   ];
 
@@ -404,7 +597,7 @@ No workspace folder is currently selected.
     systemPrompt,
   });
 
-  const runtimeMessages = [...messages];
+  const runtimeMessages = leadingSystem ? messages.slice(1) : [...messages];
 
   console.log(
     JSON.stringify({
@@ -415,7 +608,14 @@ No workspace folder is currently selected.
       detail: null,
     })
   );
-  const result = await agent.invoke({ messages: runtimeMessages });
+  const controller = new AbortController();
+  if (requestId) activeRuns.set(requestId, controller);
+  let result;
+  try {
+    result = await agent.invoke({ messages: runtimeMessages }, { signal: controller.signal });
+  } finally {
+    if (requestId && activeRuns.get(requestId) === controller) activeRuns.delete(requestId);
+  }
   const responseMessages = result.messages as Message[];
 
   // Debug: log message types and tool calls
@@ -438,17 +638,51 @@ No workspace folder is currently selected.
   const fallbackMessage = responseMessages?.[responseMessages.length - 1];
   const finalText = formatMessageContent((lastAssistantMessage ?? fallbackMessage)?.content) || "No response from model.";
   console.error(`[sidecar stderr] model_response ${JSON.stringify(finalText)}`);
+  emitUsage(requestId, model, (responseMessages ?? []).slice(runtimeMessages.length));
   return finalText;
 }
 
 async function warmup(request: SendMessageRequest): Promise<string> {
-  const { provider, apiKey, model, baseUrl } = request;
-  if (!apiKey || !model) return "skipped";
-  const warmupModel = createWarmupModel(apiKey, model, provider, baseUrl);
+  if (!request.apiKey || !request.model) return "skipped";
+  const warmupModel = createProviderModel(request, { streaming: false, temperature: 0, maxTokens: 1 });
   await warmupModel.invoke("ping");
   return "ok";
 }
 
+const TITLE_PROMPT =
+  "Write a title of at most 5 words for this conversation. Reply with the title only, without quotes or trailing punctuation.";
+
+/** Asks the model for a short conversation title from the first exchange. */
+async function generateTitle(request: SendMessageRequest): Promise<string> {
+  const { messages } = request;
+  const titleModel = createProviderModel(request, { streaming: false, temperature: 0, maxTokens: 24 });
+  const transcript = messages
+    .map((m: any) => `${m.role}: ${formatMessageContent(m.content).slice(0, 2000)}`)
+    .join("\n\n");
+  const reply = await titleModel.invoke([
+    { role: "system", content: TITLE_PROMPT },
+    { role: "user", content: transcript },
+  ]);
+  return formatMessageContent(reply.content).trim();
+}
+
+const SUMMARY_PROMPT =
+  "Summarize this conversation so it can replace the messages in a later request. Keep decisions, facts, file names, open questions and the user's goals. Reply with the summary only.";
+
+/** Condenses older turns that no longer fit the context window into one summary. */
+async function summarizeConversation(request: SendMessageRequest): Promise<string> {
+  const { messages } = request;
+  const summaryModel = createProviderModel(request, { streaming: false, temperature: 0, maxTokens: 1024 });
+  const transcript = messages
+    .map((m: any) => `${m.role}: ${formatMessageContent(m.content)}`)
+    .join("\n\n");
+  const reply = await summaryModel.invoke([
+    { role: "system", content: SUMMARY_PROMPT },
+    { role: "user", content: transcript },
+  ]);
+  return formatMessageContent(reply.content).trim();
+}
+
 function createSkillTraceBackend(backend: any, emitStatus?: StatusEmitter, requestId?: string) {
   const notify = (stage: "tool_start" | "tool_end" | "tool_error", detail?: unknown) => {
     if (typeof emitStatus === "function") {
@@ -490,8 +724,39 @@ function createSkillTraceBackend(backend: any, emitStatus?: StatusEmitter, reque
   };
 }
 
+/** Asks the host before each built-in file write, which it refuses outright in plan mode. */
+function createPermissionBackend(
+  backend: ReturnType<typeof createSkillTraceBackend>,
+  options: { workspaceRoot?: string; requestId?: string }
+) {
+  const ask = (tool: string, paths: string[]) =>
+    askPermission({ tool, access: "write", args: { paths }, ...options });
+
+  return {
+    ...backend,
+    write: async (...args: any[]) => {
+      const reply = await ask("write_file", [String(args[0] ?? "")]);
+      return reply.allowed ? backend.write(...args) : { error: reply.reason ?? "permission_denied", filesUpdate: null };
+    },
+    edit: async (...args: any[]) => {
+      const reply = await ask("edit_file", [String(args[0] ?? "")]);
+      return reply.allowed
+        ? backend.edit(...args)
+        : { error: reply.reason ?? "permission_denied", filesUpdate: null, occurrences: 0 };
+    },
+    uploadFiles: async (...args: any[]) => {
+      const files: [string, unknown][] = Array.isArray(args[0]) ? args[0] : [];
+      const reply = await ask("upload_files", files.map(([path]) => path));
+      return reply.allowed
+        ? backend.uploadFiles(...args)
+        : files.map(([path]) => ({ path, error: "permission_denied" }));
+    },
+  };
+}
+
 interface JsonRpcRequest {
   id: string | number | null;
+  session?: string;
   method: string;
   params: SendMessageRequest;
 }
@@ -499,10 +764,12 @@ interface JsonRpcRequest {
 interface JsonRpcError {
   code: number;
   message: string;
+  data?: { status?: number; retryAfterMs?: number };
 }
 
 interface JsonRpcResponse {
   id: string | number | null;
+  session?: string;
   result?: string;
   error?: JsonRpcError;
 }
@@ -529,6 +796,58 @@ function extractErrorMessage(err: unknown): string {
   }
 }
 
+function extractErrorStatus(err: unknown): number | undefined {
+  const anyErr = err as any;
+  const status = anyErr?.response?.status ?? anyErr?.status;
+  return typeof status === "number" ? status : undefined;
+}
+
+/** The provider's `retry-after-ms` / `Retry-After` hint in milliseconds, if it sent one. */
+function extractRetryAfter(err: unknown): number | undefined {
+  const anyErr = err as any;
+  const raw = anyErr?.headers ?? anyErr?.response?.headers;
+  if (!raw) return undefined;
+  const header = (name: string): string | undefined => {
+    const value = typeof raw.get === "function" ? raw.get(name) : raw[name];
+    return typeof value === "string" && value.trim() ? value.trim() : undefined;
+  };
+
+  const ms = Number(header("retry-after-ms"));
+  if (Number.isFinite(ms) && ms >= 0) return ms;
+  const retryAfter = header("retry-after");
+  if (!retryAfter) return undefined;
+  const seconds = Number(retryAfter);
+  if (Number.isFinite(seconds) && seconds >= 0) return seconds * 1000;
+  // Otherwise an HTTP date
+  const at = Date.parse(retryAfter);
+  return Number.isNaN(at) ? undefined : Math.max(0, at - Date.now());
+}
+
+// Results larger than this are split into `result_chunk` events so a single
+// stdout line never grows beyond what the host reads in one piece.
+const RESULT_CHUNK_SIZE = 32 * 1024;
+
+function writeResponse(response: JsonRpcResponse): void {
+  const { id, session, result } = response;
+  if (typeof result !== "string" || typeof id !== "number" || result.length <= RESULT_CHUNK_SIZE) {
+    console.log(JSON.stringify(response));
+    return;
+  }
+
+  const chunks: string[] = [];
+  for (let start = 0; start < result.length; ) {
+    let end = Math.min(start + RESULT_CHUNK_SIZE, result.length);
+    // A surrogate pair cut in half becomes a lone `\ud83d` escape, which the host rejects
+    const last = result.charCodeAt(end - 1);
+    if (end < result.length && last >= 0xd800 && last <= 0xdbff) end -= 1;
+    chunks.push(result.slice(start, end));
+    start = end;
+  }
+  chunks.forEach((data, seq) => {
+    console.log(JSON.stringify({ event: "result_chunk", id, session, seq, total: chunks.length, data }));
+  });
+}
+
 // JSON-RPC style communication via stdin/stdout
 const rl = readline.createInterface({
   input: process.stdin,
@@ -536,42 +855,77 @@ const rl = readline.createInterface({
   terminal: false,
 });
 
-rl.on("line", async (line: string) => {
-  try {
-    const request: JsonRpcRequest = JSON.parse(line);
-    const { id, method, params } = request;
+async function handleRequest(request: JsonRpcRequest): Promise<void> {
+  // The host namespaces ids per sidecar process; echo its session back so it can validate replies
+  const { id, session, method, params } = request;
 
-    let result: string | undefined;
-    let error: JsonRpcError | null = null;
+  let result: string | undefined;
+  let error: JsonRpcError | null = null;
 
-    try {
-      if (method === "sendMessage") {
-        result = await sendMessage(params);
-      } else if (method === "warmup") {
-        result = await warmup(params);
-      } else if (method === "ping") {
-        result = "pong";
-      } else {
-        error = { code: -32601, message: "Method not found" };
-      }
-    } catch (err) {
-      const message = extractErrorMessage(err);
-      console.error(
-        JSON.stringify({
-          event: "rpc_error",
-          method,
-          message,
-          raw: err,
-        })
-      );
-      error = { code: -32000, message };
+  try {
+    if (method === "sendMessage") {
+      result = await sendMessage(params);
+    } else if (method === "warmup") {
+      result = await warmup(params);
+    } else if (method === "generateTitle") {
+      result = await generateTitle(params);
+    } else if (method === "summarizeConversation") {
+      result = await summarizeConversation(params);
+    } else if (method === "signatureReady") {
+      result = resolveSignature(params as any);
+    } else if (method === "terminalReply") {
+      result = resolveTerminalReply(params as any);
+    } else if (method === "memoryReply") {
+      result = resolveMemoryReply(params as any);
+    } else if (method === "customToolReply") {
+      result = resolveCustomToolReply(params as any);
+    } else if (method === "mcpReply") {
+      result = resolveMcpReply(params as any);
+    } else if (method === "permissionReply") {
+      result = resolvePermissionReply(params as any);
+    } else if (method === "ping") {
+      result = "pong";
+    } else if (method === "cancelRequest") {
+      result = cancelRequest(params as any);
+    } else if (method === "killToolExecution") {
+      const { executionId } = params as unknown as { executionId: string };
+      result = JSON.stringify(killExecution(executionId));
+    } else {
+      error = { code: -32601, message: "Method not found" };
     }
+  } catch (err) {
+    const message = extractErrorMessage(err);
+    console.error(
+      JSON.stringify({
+        event: "rpc_error",
+        method,
+        message,
+        raw: err,
+      })
+    );
+    error = {
+      code: -32000,
+      message,
+      data: { status: extractErrorStatus(err), retryAfterMs: extractRetryAfter(err) },
+    };
+  }
 
-    const response: JsonRpcResponse = error
-      ? { id, error }
-      : { id, result };
+  const response: JsonRpcResponse = error
+    ? { id, session, error }
+    : { id, session, result };
 
-    console.log(JSON.stringify(response));
+  writeResponse(response);
+}
+
+rl.on("line", async (line: string) => {
+  try {
+    const parsed: JsonRpcRequest | JsonRpcRequest[] = JSON.parse(line);
+    // Batch frames carry independent requests; each one is answered on its own line
+    if (Array.isArray(parsed)) {
+      await Promise.all(parsed.map((request) => handleRequest(request)));
+    } else {
+      await handleRequest(parsed);
+    }
   } catch {
     console.log(JSON.stringify({ id: null, error: { code: -32700, message: "Parse error" } }));
   }
@@ -0,0 +1,161 @@
+/**
+ * Per-provider proxies for model traffic: HTTP(S) proxies through undici's ProxyAgent,
+ * SOCKS5 through a small connector, both with optional credentials and a bypass list
+ */
+
+import net from "node:net";
+import tls from "node:tls";
+import { Agent, ProxyAgent, fetch as undiciFetch, type Dispatcher } from "undici";
+
+const SOCKS_VERSION = 5;
+const SOCKS_NO_AUTH = 0x00;
+const SOCKS_USER_PASS = 0x02;
+
+export interface ProxyOptions {
+  url: string;
+  username?: string;
+  password?: string;
+  bypass?: string[];
+}
+
+const dispatchers = new Map<string, Dispatcher>();
+
+/** Whether `host` matches a bypass entry: `*`, an exact name or IP, or a `.domain` suffix. */
+function bypasses(host: string, bypass: string[] = []): boolean {
+  const name = host.toLowerCase().replace(/^\[|\]$/g, "");
+  return bypass.some((entry) => {
+    if (entry === "*") return true;
+    const suffix = entry.replace(/^\*/, "");
+    if (suffix.startsWith(".")) return name.endsWith(suffix) || name === suffix.slice(1);
+    return name === entry;
+  });
+}
+
+/** Reads exact byte counts from a socket during the SOCKS handshake. */
+function socketReader(socket: net.Socket) {
+  let buffered = Buffer.alloc(0);
+  let waiting: { size: number; resolve: (chunk: Buffer) => void; reject: (err: Error) => void } | null = null;
+  const flush = () => {
+    if (!waiting || buffered.length < waiting.size) return;
+    const chunk = buffered.subarray(0, waiting.size);
+    buffered = buffered.subarray(waiting.size);
+    const { resolve } = waiting;
+    waiting = null;
+    resolve(chunk);
+  };
+  const onData = (data: Buffer) => {
+    buffered = Buffer.concat([buffered, data]);
+    flush();
+  };
+  const onError = (err: Error) => {
+    waiting?.reject(err);
+    waiting = null;
+  };
+  const onClose = () => onError(new Error("SOCKS proxy closed the connection"));
+  socket.on("data", onData);
+  socket.on("error", onError);
+  socket.on("close", onClose);
+  return {
+    read: (size: number) =>
+      new Promise<Buffer>((resolve, reject) => {
+        waiting = { size, resolve, reject };
+        flush();
+      }),
+    release: () => {
+      socket.off("data", onData);
+      socket.off("error", onError);
+      socket.off("close", onClose);
+      // Whoever takes the socket over resumes it
+      socket.pause();
+    },
+  };
+}
+
+/** RFC 1928 CONNECT with optional RFC 1929 username/password authentication. */
+async function socksHandshake(socket: net.Socket, host: string, port: number, proxy: ProxyOptions): Promise<void> {
+  const reader = socketReader(socket);
+  try {
+    const methods = proxy.username ? [SOCKS_NO_AUTH, SOCKS_USER_PASS] : [SOCKS_NO_AUTH];
+    socket.write(Buffer.from([SOCKS_VERSION, methods.length, ...methods]));
+    const [version, method] = await reader.read(2);
+    if (version !== SOCKS_VERSION) throw new Error("Not a SOCKS5 proxy");
+    if (method === SOCKS_USER_PASS) {
+      const user = Buffer.from(proxy.username ?? "");
+      const pass = Buffer.from(proxy.password ?? "");
+      socket.write(Buffer.concat([Buffer.from([1, user.length]), user, Buffer.from([pass.length]), pass]));
+      const [, status] = await reader.read(2);
+      if (status !== 0) throw new Error("SOCKS proxy rejected the credentials");
+    } else if (method !== SOCKS_NO_AUTH) {
+      throw new Error("SOCKS proxy requires an unsupported authentication method");
+    }
+
+    // The proxy resolves the name, which also covers socks5h
+    const name = Buffer.from(host.replace(/^\[|\]$/g, ""));
+    socket.write(Buffer.concat([Buffer.from([SOCKS_VERSION, 1, 0, 3, name.length]), name, Buffer.from([port >> 8, port & 0xff])]));
+    const [, reply, , addressType] = await reader.read(4);
+    if (reply !== 0) throw new Error(`SOCKS proxy refused the connection (code ${reply})`);
+    const addressLength =
+      addressType === 1 ? 4 : addressType === 4 ? 16 : (await reader.read(1))[0];
+    await reader.read(addressLength + 2);
+  } finally {
+    reader.release();
+  }
+}
+
+function socksAgent(proxy: ProxyOptions): Agent {
+  const target = new URL(proxy.url);
+  return new Agent({
+    connect: (options, callback) => {
+      const https = options.protocol === "https:";
+      const port = Number(options.port) || (https ? 443 : 80);
+      const socket = net.connect(Number(target.port) || 1080, target.hostname);
+      socket.once("connect", () => {
+        socksHandshake(socket, options.hostname, port, proxy)
+          .then(() => {
+            if (!https) {
+              callback(null, socket);
+              return;
+            }
+            const secure = tls.connect({
+              socket,
+              servername: options.servername || options.hostname,
+              ALPNProtocols: ["http/1.1"],
+            });
+            secure.once("secureConnect", () => callback(null, secure));
+            secure.once("error", (err) => callback(err, null));
+          })
+          .catch((err: Error) => {
+            socket.destroy();
+            callback(err, null);
+          });
+      });
+      socket.once("error", (err) => callback(err, null));
+    },
+  });
+}
+
+function dispatcherFor(proxy: ProxyOptions): Dispatcher {
+  const key = JSON.stringify(proxy);
+  let dispatcher = dispatchers.get(key);
+  if (!dispatcher) {
+    if (proxy.url.startsWith("socks")) {
+      dispatcher = socksAgent(proxy);
+    } else {
+      const token = proxy.username
+        ? `Basic ${Buffer.from(`${proxy.username}:${proxy.password ?? ""}`).toString("base64")}`
+        : undefined;
+      dispatcher = new ProxyAgent({ uri: proxy.url, token });
+    }
+    dispatchers.set(key, dispatcher);
+  }
+  return dispatcher;
+}
+
+/** `fetch` that routes requests through `proxy` unless their host is on its bypass list. */
+export function createProxyFetch(proxy: ProxyOptions): typeof fetch {
+  return ((input: any, init?: any) => {
+    const url = typeof input === "string" ? input : input instanceof URL ? input.href : input.url;
+    if (bypasses(new URL(url).hostname, proxy.bypass)) return fetch(input, init);
+    return undiciFetch(input, { ...init, dispatcher: dispatcherFor(proxy) });
+  }) as typeof fetch;
+}
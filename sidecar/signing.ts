@@ -0,0 +1,66 @@
+/**
+ * Host-side request signing: the Rust host holds the AWS credentials and signs each
+ * request with SigV4, so only the body hash leaves this process
+ */
+
+import { createHash, randomUUID } from "node:crypto";
+
+const SIGN_TIMEOUT_MS = 30000;
+
+interface PendingSignature {
+  resolve: (headers: Record<string, string>) => void;
+  reject: (err: Error) => void;
+  timer: ReturnType<typeof setTimeout>;
+}
+
+export interface SignatureReady {
+  signId: string;
+  headers?: Record<string, string> | null;
+  error?: string | null;
+}
+
+const pending = new Map<string, PendingSignature>();
+
+function requestSignature(method: string, url: string, payloadHash: string): Promise<Record<string, string>> {
+  const signId = randomUUID();
+  return new Promise((resolve, reject) => {
+    const timer = setTimeout(() => {
+      pending.delete(signId);
+      reject(new Error("Timed out waiting for the host to sign the request"));
+    }, SIGN_TIMEOUT_MS);
+    pending.set(signId, { resolve, reject, timer });
+    console.log(JSON.stringify({ event: "sign_request", signId, method, url, payloadHash }));
+  });
+}
+
+/** Handles the host's `signatureReady` reply to a `sign_request` event. */
+export function resolveSignature(reply: SignatureReady): string {
+  const waiter = pending.get(reply.signId);
+  if (!waiter) return "unknown";
+  pending.delete(reply.signId);
+  clearTimeout(waiter.timer);
+  if (reply.headers) {
+    waiter.resolve(reply.headers);
+  } else {
+    waiter.reject(new Error(reply.error ?? "The host could not sign the request"));
+  }
+  return "ok";
+}
+
+/** `fetch` for OpenAI clients that has the host sign every request before sending it with `inner`. */
+export function createSigningFetch(inner: typeof fetch = fetch): typeof fetch {
+  return async (input, init = {}) => {
+    const url = typeof input === "string" ? input : input instanceof URL ? input.href : input.url;
+    const method = (init.method ?? (input instanceof Request ? input.method : "GET")).toUpperCase();
+    const body = typeof init.body === "string" ? init.body : init.body == null ? "" : await new Response(init.body).text();
+    const signed = await requestSignature(method, url, createHash("sha256").update(body).digest("hex"));
+
+    const headers = new Headers(init.headers);
+    // The OpenAI client always adds a bearer token; SigV4 replaces it
+    headers.delete("authorization");
+    for (const [name, value] of Object.entries(signed)) {
+      headers.set(name, value);
+    }
+    return inner(url, { ...init, method, headers, body: method === "GET" ? undefined : body });
+  };
+}
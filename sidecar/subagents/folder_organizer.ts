@@ -1,21 +1,20 @@
 // @ts-nocheck
-import { createOrganizeFolderTool } from "../tools/index.js";
+import { createOrganizeFolderTool, withExecutionLimits, withPermission } from "../tools/index.js";
 
 export function createFolderOrganizerSubagent(
-  { model, workspaceRoot, requestId, emitStatus }: any
+  { model, workspaceRoot, requestId, emitStatus, toolTimeouts }: any
 ): any {
+  const organize = withExecutionLimits(
+    createOrganizeFolderTool({ workspaceRoot, requestId, emitStatus }),
+    { requestId, timeouts: toolTimeouts, emitStatus }
+  );
   return {
     name: "folder-organizer",
     description:
       "Organize a folder into clean category-based subfolders using consistent naming and safe moves.",
     model,
-    tools: [
-      createOrganizeFolderTool({
-        workspaceRoot,
-        requestId,
-        emitStatus,
-      }),
-    ],
+    // Moves go through the same permission check as the main agent's tools
+    tools: [withPermission(organize, { workspaceRoot, requestId })],
     systemPrompt: `You are a folder organization specialist.
 
 Rules:
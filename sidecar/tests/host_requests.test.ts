@@ -0,0 +1,58 @@
+import { describe, it, expect, vi, afterEach } from "vitest";
+import { pendingReplies } from "../tools/host_requests.js";
+
+describe("Host requests", () => {
+  const events: any[] = [];
+
+  afterEach(() => {
+    vi.restoreAllMocks();
+    events.length = 0;
+  });
+
+  function capture() {
+    vi.spyOn(console, "log").mockImplementation((line: string) => events.push(JSON.parse(line)));
+  }
+
+  it("settles a call with the host's result, once", async () => {
+    capture();
+    const replies = pendingReplies<string>("");
+    const call = replies.request("memory_request", { key: "a" }, { timeoutMs: 1000, timeoutMessage: "late" });
+    expect(events[0]).toMatchObject({ event: "memory_request", key: "a" });
+
+    expect(replies.resolve({ callId: events[0].callId, result: null })).toBe("ok");
+    expect(replies.resolve({ callId: events[0].callId, result: "again" })).toBe("unknown");
+    expect(await call).toBe("");
+  });
+
+  it("rejects with the host's error", async () => {
+    capture();
+    const replies = pendingReplies<string>("");
+    const call = replies.request("mcp_request", {}, { timeoutMs: 1000, timeoutMessage: "late" });
+    replies.resolve({ callId: events[0].callId, error: "denied" });
+    await expect(call).rejects.toThrow("denied");
+  });
+
+  it("gives up on timeout or abort and ignores a late reply", async () => {
+    capture();
+    const replies = pendingReplies<unknown>(null);
+    const givenUp: string[] = [];
+    const onGiveUp = (callId: string) => givenUp.push(callId);
+
+    const slow = replies.request("terminal_request", {}, { timeoutMs: 10, timeoutMessage: "late", onGiveUp });
+    await expect(slow).rejects.toThrow("late");
+
+    const controller = new AbortController();
+    const aborted = replies.request("terminal_request", {}, {
+      timeoutMs: 1000,
+      timeoutMessage: "late",
+      signal: controller.signal,
+      cancelledMessage: "stopped",
+      onGiveUp,
+    });
+    controller.abort();
+    await expect(aborted).rejects.toThrow("stopped");
+
+    expect(givenUp).toEqual([events[0].callId, events[1].callId]);
+    expect(replies.resolve({ callId: events[0].callId, result: "late" })).toBe("unknown");
+  });
+});
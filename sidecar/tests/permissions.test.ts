@@ -0,0 +1,155 @@
+import { describe, it, expect, vi, afterEach } from "vitest";
+import {
+  createTestWorkspace,
+  cleanupTestWorkspace,
+  createMockEmitStatus,
+  createTestFiles,
+  fileExists,
+} from "./helpers.js";
+import { accessFor, resolvePermissionReply } from "../tools/permissions.js";
+import { createFolderOrganizerSubagent } from "../subagents/folder_organizer.js";
+import {
+  createCalculateExpressionTool,
+  createGenerateUuidTool,
+  createGetTimeTool,
+  createGetTimezoneTool,
+  createAgentBrowserTool,
+  createRandomNumberTool,
+  createRunNodeTool,
+  createTerminalTool,
+  createMemoryTool,
+  createOrganizeFolderTool,
+  createFileSearchTool,
+  createFileRenameTool,
+  createFindDuplicatesTool,
+  createFolderStructureTool,
+  createFileCopyMoveTool,
+  createFileDeleteTool,
+  createExcelOperationsTool,
+  createWordOperationsTool,
+  createPowerPointOperationsTool,
+  createPDFOperationsTool,
+  createImageOperationsTool,
+  createVideoOperationsTool,
+  createDataAnalysisTool,
+  createArchiveOperationsTool,
+  createWebOperationsTool,
+  createFormatConversionTool,
+} from "../tools/index.js";
+
+// What each built-in tool asks permission for when called without arguments
+const EXPECTED = {
+  get_time: null,
+  get_timezone: null,
+  random_number: null,
+  generate_uuid: null,
+  calculate_expression: null,
+  run_node: "execute",
+  agent_browser: "execute",
+  terminal: null,
+  memory: "write",
+  organize_folder: "write",
+  file_search: null,
+  file_rename: "write",
+  find_duplicates: null,
+  create_folders: "write",
+  file_copy_move: "write",
+  file_delete: "write",
+  excel_operations: "write",
+  word_operations: "write",
+  powerpoint_operations: "write",
+  pdf_operations: "write",
+  image_operations: "write",
+  video_operations: "write",
+  data_analysis: "write",
+  archive_operations: "write",
+  web_operations: "network",
+  format_conversion: "write",
+};
+
+describe("Permission classification", () => {
+  const { emitStatus } = createMockEmitStatus();
+  const context = { workspaceRoot: "/tmp/workspace", requestId: "test", emitStatus };
+  const registered = [
+    createGetTimeTool(context),
+    createGetTimezoneTool(context),
+    createRandomNumberTool(context),
+    createGenerateUuidTool(context),
+    createCalculateExpressionTool(context),
+    createRunNodeTool(context),
+    createAgentBrowserTool(context),
+    createTerminalTool(context),
+    createMemoryTool(context),
+    createOrganizeFolderTool(context),
+    createFileSearchTool(context),
+    createFileRenameTool(context),
+    createFindDuplicatesTool(context),
+    createFolderStructureTool(context),
+    createFileCopyMoveTool(context),
+    createFileDeleteTool(context),
+    createExcelOperationsTool(context),
+    createWordOperationsTool(context),
+    createPowerPointOperationsTool(context),
+    createPDFOperationsTool(context),
+    createImageOperationsTool(context),
+    createVideoOperationsTool(context),
+    createDataAnalysisTool(context),
+    createArchiveOperationsTool(context),
+    createWebOperationsTool(context),
+    createFormatConversionTool(context),
+  ].map((t) => t.name);
+
+  it("classifies every registered tool", () => {
+    expect([...registered].sort()).toEqual(Object.keys(EXPECTED).sort());
+    for (const name of registered) {
+      expect(accessFor(name, {}), name).toBe(EXPECTED[name]);
+    }
+  });
+
+  it("treats unknown tools as writes", () => {
+    expect(accessFor("some_new_tool", {})).toBe("write");
+  });
+
+  it("runs MCP and custom tools as commands", () => {
+    expect(accessFor("mcp__github__create_issue", {})).toBe("execute");
+    expect(accessFor("custom__lint", {})).toBe("execute");
+  });
+
+  it("looks at the arguments of tools that only sometimes write", () => {
+    expect(accessFor("find_duplicates", { deleteAction: "delete_duplicates" })).toBe("write");
+    expect(accessFor("find_duplicates", { deleteAction: "report" })).toBeNull();
+    expect(accessFor("web_operations", { operation: "download_file" })).toBe("write");
+    expect(accessFor("web_operations", { operation: "extract_text" })).toBe("network");
+  });
+});
+
+describe("Folder organizer subagent", () => {
+  let workspaceRoot;
+
+  afterEach(async () => {
+    vi.restoreAllMocks();
+    if (workspaceRoot) await cleanupTestWorkspace(workspaceRoot);
+  });
+
+  it("asks the host before moving files", async () => {
+    workspaceRoot = await createTestWorkspace();
+    await createTestFiles(workspaceRoot, { "photo.png": "png", "notes.txt": "text" });
+    const requests = [];
+    vi.spyOn(console, "log").mockImplementation((line) => {
+      const event = String(line).startsWith("{") ? JSON.parse(String(line)) : null;
+      if (event?.event === "permission_request") requests.push(event);
+    });
+
+    const { emitStatus } = createMockEmitStatus();
+    const subagent = createFolderOrganizerSubagent({ workspaceRoot, requestId: "plan-run", emitStatus });
+    const [organize] = subagent.tools;
+    const call = organize.invoke({ path: "/" });
+    await vi.waitFor(() => expect(requests).toHaveLength(1));
+    expect(requests[0]).toMatchObject({ tool: "organize_folder", access: "write", requestId: "plan-run" });
+
+    resolvePermissionReply({ callId: requests[0].callId, error: "plan mode" });
+    const result = await call;
+    expect(String(result)).toContain("plan mode");
+    expect(await fileExists(workspaceRoot, "photo.png")).toBe(true);
+  });
+});
@@ -5,6 +5,7 @@ import fs from "node:fs";
 import path from "node:path";
 import { fileURLToPath } from "node:url";
 import { ToolContext, createNotifier } from "./types.js";
+import { currentExecution } from "./executions.js";
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
@@ -68,10 +69,12 @@ export function createAgentBrowserTool({ requestId, emitStatus }: ToolContext) {
       }
 
       const result = await new Promise<AgentBrowserResult>((resolve, reject) => {
+        const execution = currentExecution();
         const child: ChildProcess = spawn(bin, args, {
           cwd: workingDir,
           env,
           stdio: ["ignore", "pipe", "pipe"],
+          signal: execution?.signal,
         });
 
         let stdout = "";
@@ -80,6 +83,7 @@ export function createAgentBrowserTool({ requestId, emitStatus }: ToolContext) {
           const text = chunk.toString();
           if (target === "stdout") stdout += text;
           else stderr += text;
+          execution?.appendOutput(text);
         };
 
         child.stdout?.on("data", (chunk: Buffer) => onData(chunk, "stdout"));
@@ -0,0 +1,76 @@
+/**
+ * Tools the user registered in the app, backed by local commands. The host runs them;
+ * calls go out as `custom_tool_request` events and come back as `customToolReply`
+ */
+
+import { tool } from "@langchain/core/tools";
+import { ToolContext, createNotifier } from "./types.js";
+import { currentExecution } from "./executions.js";
+import { HostReply, pendingReplies } from "./host_requests.js";
+
+// Beyond the tool's own timeout, for the host to kill the command and answer
+const REPLY_GRACE_MS = 10000;
+
+/** A registered tool as the host lists it in `SendMessageRequest.customTools`. */
+export interface CustomToolInfo {
+  /** `custom__<tool>`, apart from built-in and MCP tools */
+  name: string;
+  /** The name the user registered it under */
+  tool: string;
+  description: string;
+  inputSchema: Record<string, unknown>;
+  timeoutSecs: number;
+}
+
+const replies = pendingReplies<string>("");
+
+function callCustomTool(
+  info: CustomToolInfo,
+  args: unknown,
+  context: { workspace?: string; requestId?: string; signal?: AbortSignal }
+): Promise<string> {
+  return replies.request(
+    "custom_tool_request",
+    {
+      tool: info.tool,
+      arguments: args ?? {},
+      workspace: context.workspace,
+      requestId: context.requestId,
+    },
+    {
+      timeoutMs: info.timeoutSecs * 1000 + REPLY_GRACE_MS,
+      timeoutMessage: `Timed out waiting for ${info.name}`,
+      signal: context.signal,
+      cancelledMessage: `${info.name} was cancelled`,
+    }
+  );
+}
+
+/** Handles the host's `customToolReply` to a `custom_tool_request` event. */
+export function resolveCustomToolReply(reply: HostReply<string>): string {
+  return replies.resolve(reply);
+}
+
+export function createCustomTools(infos: CustomToolInfo[], { workspaceRoot, requestId, emitStatus }: ToolContext) {
+  return infos.map((info) => {
+    const notify = createNotifier(info.name, emitStatus, requestId);
+    return tool(
+      async (args: unknown) => {
+        notify("tool_start", { tool: info.tool });
+        const result = await callCustomTool(info, args, {
+          workspace: workspaceRoot,
+          requestId,
+          signal: currentExecution()?.signal,
+        });
+        notify("tool_end", { tool: info.tool });
+        return result;
+      },
+      {
+        name: info.name,
+        description: info.description,
+        // Registered with a JSON Schema, which tools accept as is
+        schema: info.inputSchema as any,
+      }
+    );
+  });
+}
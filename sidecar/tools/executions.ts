@@ -0,0 +1,204 @@
+/**
+ * Per-tool execution limits: every tool call gets an id, a timeout and a kill switch
+ */
+
+import { AsyncLocalStorage } from "node:async_hooks";
+import { randomUUID } from "node:crypto";
+import { tool, StructuredToolInterface } from "@langchain/core/tools";
+import { StatusEmitter } from "./types.js";
+
+export const DEFAULT_TOOL_TIMEOUT_MS = 120000;
+const MAX_PARTIAL_OUTPUT_CHARS = 16 * 1024;
+
+export interface ToolTimeouts {
+  defaultMs?: number;
+  tools?: Record<string, number>;
+}
+
+export interface ToolExecution {
+  id: string;
+  tool: string;
+  requestId: string | null;
+  signal: AbortSignal;
+  /** Records output as it is produced so a killed execution can still report it. */
+  appendOutput(text: string): void;
+}
+
+interface RunningExecution extends ToolExecution {
+  controller: AbortController;
+  output: string;
+}
+
+export interface KillReport {
+  executionId: string;
+  tool: string | null;
+  killed: boolean;
+  partialOutput: string;
+}
+
+const running = new Map<string, RunningExecution>();
+const storage = new AsyncLocalStorage<ToolExecution>();
+
+/** The execution the calling tool is running under; process-based tools pass its signal on. */
+export function currentExecution(): ToolExecution | undefined {
+  return storage.getStore();
+}
+
+function emitExecution(
+  execution: RunningExecution,
+  state: "started" | "finished" | "timed_out" | "killed",
+  extra: Record<string, unknown> = {}
+): void {
+  console.log(
+    JSON.stringify({
+      event: "tool_execution",
+      executionId: execution.id,
+      requestId: execution.requestId,
+      tool: execution.tool,
+      state,
+      ...extra,
+    })
+  );
+}
+
+/** Structured start/end events for the step-by-step tool view; ids match `tool_execution`. */
+function emitToolUse(
+  event: "tool_use_start" | "tool_use_end",
+  execution: RunningExecution,
+  fields: Record<string, unknown>
+): void {
+  console.log(
+    JSON.stringify({
+      event,
+      toolUseId: execution.id,
+      requestId: execution.requestId,
+      tool: execution.tool,
+      ...fields,
+    })
+  );
+}
+
+function resultText(value: unknown): string {
+  if (typeof value === "string") return value;
+  try {
+    return JSON.stringify(value) ?? "";
+  } catch {
+    return String(value);
+  }
+}
+
+function timeoutFor(toolName: string, timeouts?: ToolTimeouts): number {
+  const specific = timeouts?.tools?.[toolName];
+  const fallback = timeouts?.defaultMs;
+  const value = Number.isFinite(specific) ? specific! : Number.isFinite(fallback) ? fallback! : DEFAULT_TOOL_TIMEOUT_MS;
+  return Math.max(1000, value);
+}
+
+/** Aborts a running execution; its tool call resolves with whatever output it had produced. */
+export function killExecution(executionId: string): KillReport {
+  const execution = running.get(executionId);
+  if (!execution) {
+    return { executionId, tool: null, killed: false, partialOutput: "" };
+  }
+  execution.controller.abort("killed");
+  return { executionId, tool: execution.tool, killed: true, partialOutput: execution.output };
+}
+
+/** Aborts every running execution of the run `requestId`; returns how many there were. */
+export function killRequestExecutions(requestId: string): number {
+  const executions = [...running.values()].filter((execution) => execution.requestId === requestId);
+  executions.forEach((execution) => execution.controller.abort("killed"));
+  return executions.length;
+}
+
+/**
+ * Wraps a tool so each call runs under its own timeout and can be killed by id. A call
+ * that times out or is killed returns a report with its partial output instead of
+ * hanging, so the agent run can continue.
+ */
+export function withExecutionLimits(
+  inner: StructuredToolInterface,
+  options: { requestId?: string; timeouts?: ToolTimeouts; emitStatus?: StatusEmitter }
+): StructuredToolInterface {
+  return tool(
+    async (input: unknown) => {
+      const controller = new AbortController();
+      const execution: RunningExecution = {
+        id: randomUUID(),
+        tool: inner.name,
+        requestId: options.requestId ?? null,
+        signal: controller.signal,
+        controller,
+        output: "",
+        appendOutput(text: string) {
+          execution.output = (execution.output + text).slice(-MAX_PARTIAL_OUTPUT_CHARS);
+        },
+      };
+      const timeoutMs = timeoutFor(inner.name, options.timeouts);
+      const startedAt = Date.now();
+      running.set(execution.id, execution);
+      emitExecution(execution, "started", { timeoutMs });
+      emitToolUse("tool_use_start", execution, {
+        args: input && typeof input === "object" ? input : {},
+      });
+      const finish = (result: string, isError: boolean) =>
+        emitToolUse("tool_use_end", execution, {
+          durationMs: Date.now() - startedAt,
+          result: result.slice(0, 2000),
+          isError,
+        });
+
+      const timer = setTimeout(() => controller.abort("timeout"), timeoutMs);
+      const aborted = new Promise<"timeout" | "killed">((resolve) => {
+        controller.signal.addEventListener("abort", () => {
+          resolve(controller.signal.reason === "timeout" ? "timeout" : "killed");
+        });
+      });
+
+      const work = storage.run(execution, () => inner.invoke(input as any));
+      // A killed tool may still reject later; that must not surface as an unhandled rejection
+      work.catch(() => undefined);
+
+      try {
+        const outcome = await Promise.race([
+          work.then((value) => ({ value })),
+          aborted.then((reason) => ({ reason })),
+        ]);
+        if ("value" in outcome) {
+          emitExecution(execution, "finished");
+          finish(resultText(outcome.value), false);
+          return outcome.value;
+        }
+
+        const state = outcome.reason === "timeout" ? "timed_out" : "killed";
+        emitExecution(execution, state, { partialOutput: execution.output });
+        finish(execution.output, true);
+        options.emitStatus?.({
+          stage: "tool_error",
+          tool: inner.name,
+          requestId: options.requestId,
+          detail: {
+            error: outcome.reason === "timeout" ? `Timed out after ${timeoutMs}ms` : "Killed by user",
+            executionId: execution.id,
+          },
+        });
+        return JSON.stringify({
+          error: outcome.reason === "timeout" ? `Tool timed out after ${timeoutMs}ms` : "Tool was killed by the user",
+          partialOutput: execution.output,
+        });
+      } catch (err) {
+        emitExecution(execution, "finished", { error: true });
+        finish(err instanceof Error ? err.message : String(err), true);
+        throw err;
+      } finally {
+        clearTimeout(timer);
+        running.delete(execution.id);
+      }
+    },
+    {
+      name: inner.name,
+      description: inner.description,
+      schema: inner.schema as any,
+    }
+  );
+}
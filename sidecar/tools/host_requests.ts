@@ -0,0 +1,71 @@
+/**
+ * Requests the sidecar makes of the host: each goes out as an event line with a fresh
+ * `callId` and comes back as a `…Reply` RPC carrying the same id
+ */
+
+import { randomUUID } from "node:crypto";
+
+/** How the host answers a request. */
+export interface HostReply<T> {
+  callId: string;
+  result?: T | null;
+  error?: string | null;
+}
+
+export interface HostRequestOptions {
+  timeoutMs: number;
+  /** Why the call fails when no reply comes in time */
+  timeoutMessage: string;
+  signal?: AbortSignal;
+  cancelledMessage?: string;
+  /** Runs when the call is given up on, e.g. to withdraw a prompt the host shows for it */
+  onGiveUp?: (callId: string) => void;
+}
+
+interface PendingCall<T> {
+  resolve: (result: T) => void;
+  reject: (err: Error) => void;
+  timer: ReturnType<typeof setTimeout>;
+}
+
+/** Calls of one kind waiting for the host; `empty` stands in for a reply without a result. */
+export function pendingReplies<T>(empty: T) {
+  const pending = new Map<string, PendingCall<T>>();
+
+  return {
+    /** Logs `{event, callId, ...params}` and waits for the reply. */
+    request(event: string, params: Record<string, unknown>, options: HostRequestOptions): Promise<T> {
+      const callId = randomUUID();
+      return new Promise<T>((resolve, reject) => {
+        const giveUp = (message: string) => {
+          if (!pending.delete(callId)) return;
+          clearTimeout(timer);
+          options.onGiveUp?.(callId);
+          reject(new Error(message));
+        };
+        const timer = setTimeout(() => giveUp(options.timeoutMessage), options.timeoutMs);
+        options.signal?.addEventListener(
+          "abort",
+          () => giveUp(options.cancelledMessage ?? "The call was cancelled"),
+          { once: true }
+        );
+        pending.set(callId, { resolve, reject, timer });
+        console.log(JSON.stringify({ event, callId, ...params }));
+      });
+    },
+
+    /** Settles the call a reply is for; "unknown" when it was already given up on. */
+    resolve(reply: HostReply<T>): string {
+      const waiter = pending.get(reply.callId);
+      if (!waiter) return "unknown";
+      pending.delete(reply.callId);
+      clearTimeout(waiter.timer);
+      if (reply.error) {
+        waiter.reject(new Error(reply.error));
+      } else {
+        waiter.resolve(reply.result ?? empty);
+      }
+      return "ok";
+    },
+  };
+}
@@ -1,5 +1,7 @@
 // Shared types
 export * from "./types.js";
+export * from "./executions.js";
+export * from "./permissions.js";
 
 // Core utilities
 export { createGetTimeTool } from "./get_time.js";
@@ -9,6 +11,8 @@ export { createGenerateUuidTool } from "./generate_uuid.js";
 export { createCalculateExpressionTool } from "./calculate_expression.js";
 export { createRunNodeTool } from "./run_node.js";
 export { createAgentBrowserTool } from "./agent_browser.js";
+export { createTerminalTool, resolveTerminalReply } from "./terminal_sessions.js";
+export { createMemoryTool, resolveMemoryReply } from "./memory.js";
 
 // File management
 export { createOrganizeFolderTool } from "./organize_folder.js";
@@ -44,3 +48,7 @@ export { createWebOperationsTool } from "./web_operations.js";
 
 // Format conversion
 export { createFormatConversionTool } from "./format_conversion.js";
+
+// MCP servers
+export { createMcpTools, resolveMcpReply, type McpToolInfo } from "./mcp.js";
+export { createCustomTools, resolveCustomToolReply, type CustomToolInfo } from "./custom_tools.js";
@@ -0,0 +1,69 @@
+/**
+ * Tools of the user's MCP servers. The host owns the connections; calls go out as
+ * `mcp_request` events and come back as `mcpReply`
+ */
+
+import { tool } from "@langchain/core/tools";
+import { ToolContext, createNotifier } from "./types.js";
+import { currentExecution } from "./executions.js";
+import { HostReply, pendingReplies } from "./host_requests.js";
+
+// The host gives a server two minutes to answer
+const REPLY_TIMEOUT_MS = 125000;
+
+/** A server tool as the host lists it in `SendMessageRequest.mcpTools`. */
+export interface McpToolInfo {
+  /** Unique across servers, e.g. `mcp__github__create_issue` */
+  name: string;
+  /** Id of the server that runs the tool */
+  server: string;
+  /** The tool's own name on that server */
+  tool: string;
+  description: string;
+  inputSchema: Record<string, unknown>;
+}
+
+const replies = pendingReplies<string>("");
+
+function callMcpTool(
+  info: McpToolInfo,
+  args: unknown,
+  requestId?: string,
+  signal?: AbortSignal
+): Promise<string> {
+  return replies.request(
+    "mcp_request",
+    { server: info.server, tool: info.tool, arguments: args ?? {}, requestId },
+    {
+      timeoutMs: REPLY_TIMEOUT_MS,
+      timeoutMessage: `Timed out waiting for ${info.name}`,
+      signal,
+      cancelledMessage: `${info.name} was cancelled`,
+    }
+  );
+}
+
+/** Handles the host's `mcpReply` to an `mcp_request` event. */
+export function resolveMcpReply(reply: HostReply<string>): string {
+  return replies.resolve(reply);
+}
+
+export function createMcpTools(infos: McpToolInfo[], { requestId, emitStatus }: ToolContext) {
+  return infos.map((info) => {
+    const notify = createNotifier(info.name, emitStatus, requestId);
+    return tool(
+      async (args: unknown) => {
+        notify("tool_start", { server: info.server, tool: info.tool });
+        const result = await callMcpTool(info, args, requestId, currentExecution()?.signal);
+        notify("tool_end", { server: info.server, tool: info.tool });
+        return result;
+      },
+      {
+        name: info.name,
+        description: info.description,
+        // MCP servers describe their input with JSON Schema, which tools accept as is
+        schema: info.inputSchema as any,
+      }
+    );
+  });
+}
@@ -0,0 +1,58 @@
+/**
+ * Notes the agent keeps about a workspace across sessions. The host stores them and puts
+ * them in the system prompt; calls go out as `memory_request` events and come back as
+ * `memoryReply`
+ */
+
+import { tool } from "@langchain/core/tools";
+import { z } from "zod";
+import { ToolContext, createNotifier } from "./types.js";
+import { HostReply, pendingReplies } from "./host_requests.js";
+
+const REPLY_TIMEOUT_MS = 15000;
+
+const replies = pendingReplies<string>("");
+
+function requestMemory(workspace: string, params: Record<string, unknown>): Promise<string> {
+  return replies.request("memory_request", { workspace, ...params }, {
+    timeoutMs: REPLY_TIMEOUT_MS,
+    timeoutMessage: "Timed out waiting for the host memory store",
+  });
+}
+
+/** Handles the host's `memoryReply` to a `memory_request` event. */
+export function resolveMemoryReply(reply: HostReply<string>): string {
+  return replies.resolve(reply);
+}
+
+export function createMemoryTool({ workspaceRoot, requestId, emitStatus }: ToolContext) {
+  const notify = createNotifier("memory", emitStatus, requestId);
+
+  return tool(
+    async ({ action, key, value, query }: {
+      action: "set" | "get" | "search" | "delete";
+      key?: string;
+      value?: string;
+      query?: string;
+    }) => {
+      if (!workspaceRoot) {
+        throw new Error("workspaceRoot is required");
+      }
+      notify("tool_start", { action, key });
+      const result = await requestMemory(workspaceRoot, { action, key, value, query });
+      notify("tool_end", { action, key });
+      return result;
+    },
+    {
+      name: "memory",
+      description:
+        "Remember facts about this workspace across sessions, such as project conventions, preferred commands or corrections from the user. `set` saves `value` under a short `key` (replacing it), `get` reads one key, `search` finds entries containing every word of `query`, and `delete` forgets a key. Saved memory appears in the system prompt of later sessions.",
+      schema: z.object({
+        action: z.enum(["set", "get", "search", "delete"]).describe("What to do"),
+        key: z.string().optional().describe("Short name for the note, e.g. \"test-command\"; required for set, get and delete"),
+        value: z.string().optional().describe("What to remember, for set"),
+        query: z.string().optional().describe("Words to look for, for search; empty lists recent entries"),
+      }),
+    }
+  );
+}
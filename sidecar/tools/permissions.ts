@@ -0,0 +1,129 @@
+/**
+ * Tool calls that change files, run code or reach the network wait for the user's
+ * permission, which the host asks for
+ */
+
+import { tool, StructuredToolInterface } from "@langchain/core/tools";
+import { resolveWorkspacePath } from "./types.js";
+import { HostReply, pendingReplies } from "./host_requests.js";
+
+// The host denies a prompt nobody answers after five minutes
+const REPLY_TIMEOUT_MS = 5.5 * 60 * 1000;
+
+export type Access = "read" | "write" | "execute" | "network";
+
+/** The user's answer; the host's reply carries why a call was refused as its error. */
+export interface PermissionDecision {
+  allowed: boolean;
+  reason?: string | null;
+}
+
+const replies = pendingReplies<null>(null);
+
+// Tools that never change anything; every other tool is treated as a write
+export const READ_ONLY_TOOLS = new Set([
+  "get_time",
+  "get_timezone",
+  "random_number",
+  "generate_uuid",
+  "calculate_expression",
+  "file_search",
+]);
+// The host asks for approval of each command itself
+const HOST_APPROVED_TOOLS = new Set(["terminal"]);
+const EXECUTE_TOOLS = new Set(["run_node", "agent_browser"]);
+const PATH_KEY = /path|source|destination|target|folder|dir|file/i;
+
+/** What a call does that needs the user's permission, or null when it can just run. */
+export function accessFor(name: string, args: Record<string, unknown>): Access | null {
+  if (READ_ONLY_TOOLS.has(name) || HOST_APPROVED_TOOLS.has(name)) return null;
+  if (name === "find_duplicates") return args.deleteAction === "delete_duplicates" ? "write" : null;
+  if (EXECUTE_TOOLS.has(name) || name.startsWith("mcp__") || name.startsWith("custom__")) return "execute";
+  if (name === "web_operations") return args.operation === "download_file" ? "write" : "network";
+  return "write";
+}
+
+/** Paths named in the arguments, resolved against the workspace where possible. */
+function affectedPaths(args: Record<string, unknown>, workspaceRoot?: string): string[] {
+  const found: string[] = [];
+  const visit = (value: unknown, key: string) => {
+    if (typeof value === "string" && PATH_KEY.test(key) && value.trim()) {
+      if (!workspaceRoot) {
+        found.push(value);
+        return;
+      }
+      // Tools take workspace-relative paths; anything escaping the workspace is kept as is
+      try {
+        found.push(resolveWorkspacePath(workspaceRoot, value));
+      } catch {
+        found.push(value);
+      }
+    } else if (Array.isArray(value)) {
+      value.forEach((item) => visit(item, key));
+    }
+  };
+  Object.entries(args).forEach(([key, value]) => visit(value, key));
+  return [...new Set(found)];
+}
+
+function requestPermission(params: Record<string, unknown>): Promise<PermissionDecision> {
+  return replies
+    .request("permission_request", params, {
+      timeoutMs: REPLY_TIMEOUT_MS,
+      timeoutMessage: "Timed out waiting for permission",
+      onGiveUp: (callId) => console.log(JSON.stringify({ event: "permission_cancel", callId })),
+    })
+    .then(
+      () => ({ allowed: true }),
+      (err: Error) => ({ allowed: false, reason: err.message })
+    );
+}
+
+/** Handles the host's `permissionReply` to a `permission_request` event. */
+export function resolvePermissionReply(reply: HostReply<null>): string {
+  return replies.resolve(reply);
+}
+
+/** Asks the host whether a call may run; the answer is a denial when nobody replies. */
+export function askPermission(params: {
+  tool: string;
+  access: Access;
+  args: Record<string, unknown>;
+  workspaceRoot?: string;
+  requestId?: string;
+}): Promise<PermissionDecision> {
+  return requestPermission({
+    tool: params.tool,
+    access: params.access,
+    args: params.args,
+    paths: affectedPaths(params.args, params.workspaceRoot),
+    workspace: params.workspaceRoot,
+    requestId: params.requestId,
+  });
+}
+
+/** Asks the host before each call of `inner` that `accessFor` flags. */
+export function withPermission(
+  inner: StructuredToolInterface,
+  options: { workspaceRoot?: string; requestId?: string }
+): StructuredToolInterface {
+  return tool(
+    async (input: unknown) => {
+      const args = input && typeof input === "object" ? (input as Record<string, unknown>) : {};
+      const access = accessFor(inner.name, args);
+      if (access) {
+        const reply = await askPermission({ tool: inner.name, access, args, ...options });
+        if (!reply.allowed) {
+          // Returned rather than thrown so the agent can adjust its plan
+          return JSON.stringify({ error: reply.reason ?? `The user did not allow ${inner.name}` });
+        }
+      }
+      return inner.invoke(input as any);
+    },
+    {
+      name: inner.name,
+      description: inner.description,
+      schema: inner.schema as any,
+    }
+  );
+}
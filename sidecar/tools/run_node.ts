@@ -2,6 +2,7 @@ import { tool } from "@langchain/core/tools";
 import { z } from "zod";
 import { execFile, ExecFileException } from "node:child_process";
 import { ToolContext, createNotifier } from "./types.js";
+import { currentExecution } from "./executions.js";
 
 const MAX_BUFFER_BYTES = 1024 * 1024;
 
@@ -12,11 +13,12 @@ export function createRunNodeTool({ requestId, emitStatus }: ToolContext) {
     async ({ code, timeoutMs = 5000 }: { code: string; timeoutMs?: number }) => {
       notify("tool_start", { timeoutMs });
       const result = await new Promise<{ stdout: string; stderr: string }>((resolve, reject) => {
+        const execution = currentExecution();
         // execFile avoids shell interpolation for safety.
-        execFile(
+        const child = execFile(
           "node",
           ["-e", code],
-          { timeout: timeoutMs, maxBuffer: MAX_BUFFER_BYTES },
+          { timeout: timeoutMs, maxBuffer: MAX_BUFFER_BYTES, signal: execution?.signal },
           (error: ExecFileException | null, stdout: string, stderr: string) => {
             if (error) {
               const details = {
@@ -31,6 +33,8 @@ export function createRunNodeTool({ requestId, emitStatus }: ToolContext) {
             return resolve({ stdout, stderr });
           }
         );
+        child.stdout?.on("data", (chunk: Buffer | string) => execution?.appendOutput(chunk.toString()));
+        child.stderr?.on("data", (chunk: Buffer | string) => execution?.appendOutput(chunk.toString()));
       });
       notify("tool_end");
       return result;
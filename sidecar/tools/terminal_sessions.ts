@@ -0,0 +1,75 @@
+/**
+ * Named terminal sessions owned by the host: the agent can keep a dev server running in
+ * one while it runs commands in another
+ */
+
+import { tool } from "@langchain/core/tools";
+import { z } from "zod";
+import { ToolContext, createNotifier } from "./types.js";
+import { currentExecution } from "./executions.js";
+import { HostReply, pendingReplies } from "./host_requests.js";
+
+const REPLY_TIMEOUT_MS = 30000;
+// Commands wait for the user's approval on the host, which gives up after 10 minutes
+const APPROVAL_REPLY_TIMEOUT_MS = 11 * 60 * 1000;
+
+const replies = pendingReplies<unknown>(null);
+
+function requestTerminal(
+  workspace: string,
+  params: Record<string, unknown>,
+  timeoutMs: number,
+  signal?: AbortSignal
+): Promise<unknown> {
+  return replies.request("terminal_request", { workspace, ...params }, {
+    timeoutMs,
+    timeoutMessage: "Timed out waiting for the host terminal",
+    signal,
+    cancelledMessage: "The terminal call was cancelled",
+    // A command still waiting for approval must not start after the agent moved on
+    onGiveUp: (callId) => console.log(JSON.stringify({ event: "terminal_cancel", callId })),
+  });
+}
+
+/** Handles the host's `terminalReply` to a `terminal_request` event. */
+export function resolveTerminalReply(reply: HostReply<unknown>): string {
+  return replies.resolve(reply);
+}
+
+export function createTerminalTool({ workspaceRoot, requestId, emitStatus }: ToolContext) {
+  const notify = createNotifier("terminal", emitStatus, requestId);
+
+  return tool(
+    async ({ action, name, input, lines }: {
+      action: "list" | "start" | "send" | "read" | "kill";
+      name?: string;
+      input?: string;
+      lines?: number;
+    }) => {
+      if (!workspaceRoot) {
+        throw new Error("workspaceRoot is required");
+      }
+      notify("tool_start", { action, name });
+      const runsCommand = (action === "start" || action === "send") && !!input?.trim();
+      const result = await requestTerminal(
+        workspaceRoot,
+        { action, name, input, lines, requestId },
+        runsCommand ? APPROVAL_REPLY_TIMEOUT_MS : REPLY_TIMEOUT_MS,
+        currentExecution()?.signal
+      );
+      notify("tool_end", { action, name });
+      return result;
+    },
+    {
+      name: "terminal",
+      description:
+        "Manage named, long-lived terminal sessions in the workspace. `start` opens a shell (optionally running `input` in it), `send` types a command line into a session, `read` returns its latest output, `kill` ends it and `list` shows all sessions. Use a separate session for each long-running process such as a dev server. The user approves every command before it runs; a denied command returns an error.",
+      schema: z.object({
+        action: z.enum(["list", "start", "send", "read", "kill"]).describe("What to do"),
+        name: z.string().optional().describe("Session name, e.g. \"dev-server\"; required except for list"),
+        input: z.string().optional().describe("Command line to run, for start and send"),
+        lines: z.number().optional().describe("How many of the latest output lines read returns (default 100)"),
+      }),
+    }
+  );
+}
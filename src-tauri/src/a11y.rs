@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::app_data::{load_json, save_json};
+
+const A11Y_FILE: &str = "accessibility.json";
+const MIN_UPDATE_INTERVAL_MS: u64 = 250;
+
+fn default_update_interval_ms() -> u64 {
+    2000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct A11ySettings {
+    /// Coalesce streamed deltas and progress announcements into infrequent updates
+    #[serde(default)]
+    low_frequency: bool,
+    #[serde(default = "default_update_interval_ms")]
+    update_interval_ms: u64,
+}
+
+impl Default for A11ySettings {
+    fn default() -> Self {
+        Self {
+            low_frequency: false,
+            update_interval_ms: default_update_interval_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AnnouncementKind {
+    GenerationStarted,
+    Progress,
+    AwaitingApproval,
+    GenerationFinished,
+}
+
+/// Screen-reader oriented status update; `politeness` maps onto `aria-live`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Announcement {
+    kind: AnnouncementKind,
+    request_id: Option<String>,
+    message: String,
+    percent: Option<u8>,
+    politeness: &'static str,
+}
+
+#[derive(Default)]
+struct RequestThrottle {
+    last_delta: Option<Instant>,
+    pending_delta: String,
+    last_progress: Option<Instant>,
+    last_bucket: Option<u8>,
+    last_phase: Option<String>,
+}
+
+/// Settings plus per-request batching state for delta and progress updates.
+pub(crate) struct A11yState {
+    settings: Mutex<A11ySettings>,
+    throttles: Mutex<HashMap<String, RequestThrottle>>,
+}
+
+pub(crate) fn load(app: &tauri::AppHandle) -> Result<A11yState, String> {
+    Ok(A11yState {
+        settings: Mutex::new(load_json::<A11ySettings>(app, A11Y_FILE)?),
+        throttles: Mutex::new(HashMap::new()),
+    })
+}
+
+fn settings(app: &tauri::AppHandle) -> A11ySettings {
+    app.state::<A11yState>().settings.lock().unwrap().clone()
+}
+
+fn emit_announcement(
+    app: &tauri::AppHandle,
+    kind: AnnouncementKind,
+    request_id: Option<&str>,
+    message: String,
+    percent: Option<u8>,
+) {
+    let politeness = match kind {
+        AnnouncementKind::AwaitingApproval => "assertive",
+        _ => "polite",
+    };
+    let _ = app.emit(
+        "a11y:announce",
+        Announcement {
+            kind,
+            request_id: request_id.map(String::from),
+            message,
+            percent,
+            politeness,
+        },
+    );
+}
+
+/// Started/finished/approval announcements are rare and always delivered.
+pub(crate) fn announce(
+    app: &tauri::AppHandle,
+    kind: AnnouncementKind,
+    request_id: Option<&str>,
+    message: impl Into<String>,
+) {
+    emit_announcement(app, kind, request_id, message.into(), None);
+}
+
+/// Announces plan progress when it crosses a bucket boundary or changes phase.
+/// Low-frequency mode uses coarser buckets and also enforces the update interval.
+/// Progress without a request id has nothing to be batched under and is always announced.
+pub(crate) fn progress(
+    app: &tauri::AppHandle,
+    request_id: Option<&str>,
+    phase: &str,
+    percent: Option<f64>,
+) {
+    let settings = settings(app);
+    let percent = percent.map(|p| p.round() as u8);
+    let bucket_size = if settings.low_frequency { 25 } else { 10 };
+    let bucket = percent.map(|p| p / bucket_size);
+
+    if let Some(request_id) = request_id {
+        let state = app.state::<A11yState>();
+        let mut throttles = state.throttles.lock().unwrap();
+        let throttle = throttles.entry(request_id.to_string()).or_default();
+
+        let phase_changed = throttle.last_phase.as_deref() != Some(phase);
+        let bucket_changed = bucket.is_some() && bucket != throttle.last_bucket;
+        let interval = Duration::from_millis(settings.update_interval_ms);
+        let interval_ok = !settings.low_frequency
+            || throttle
+                .last_progress
+                .is_none_or(|t| t.elapsed() >= interval);
+        let finished = percent == Some(100);
+
+        if !(finished || (interval_ok && (phase_changed || bucket_changed))) {
+            return;
+        }
+        throttle.last_progress = Some(Instant::now());
+        throttle.last_bucket = bucket;
+        throttle.last_phase = Some(phase.to_string());
+        // Nothing is throttled after completion; keep the entry only for held-back deltas
+        if finished && throttle.pending_delta.is_empty() {
+            throttles.remove(request_id);
+        }
+    }
+
+    let message = match percent {
+        Some(p) => format!("{}: {}% complete", phase, p),
+        None => phase.to_string(),
+    };
+    emit_announcement(
+        app,
+        AnnouncementKind::Progress,
+        request_id,
+        message,
+        percent,
+    );
+}
+
+fn delta_payload(request_id: &str, delta: String) -> Value {
+    json!({ "event": "assistant_delta", "requestId": request_id, "delta": delta })
+}
+
+/// Forwards an `assistant_delta` to the webview, coalescing deltas per request in
+/// low-frequency mode so the transcript updates at most once per interval.
+pub(crate) fn emit_delta(app: &tauri::AppHandle, value: Value) {
+    let settings = settings(app);
+    let request_id = value.get("requestId").and_then(|v| v.as_str());
+    let delta = value.get("delta").and_then(|v| v.as_str());
+    let (Some(request_id), Some(delta), true) = (request_id, delta, settings.low_frequency) else {
+        let _ = app.emit("agent:delta", value);
+        return;
+    };
+
+    let batched = {
+        let state = app.state::<A11yState>();
+        let mut throttles = state.throttles.lock().unwrap();
+        let throttle = throttles.entry(request_id.to_string()).or_default();
+        throttle.pending_delta.push_str(delta);
+
+        let interval = Duration::from_millis(settings.update_interval_ms);
+        if throttle.last_delta.is_some_and(|t| t.elapsed() < interval) {
+            return;
+        }
+        throttle.last_delta = Some(Instant::now());
+        std::mem::take(&mut throttle.pending_delta)
+    };
+    let _ = app.emit("agent:delta", delta_payload(request_id, batched));
+}
+
+/// Emits any delta text still held back for `request_id` and drops its batching state.
+pub(crate) fn finish_request(app: &tauri::AppHandle, request_id: &str) {
+    let pending = app
+        .state::<A11yState>()
+        .throttles
+        .lock()
+        .unwrap()
+        .remove(request_id)
+        .map(|t| t.pending_delta)
+        .filter(|d| !d.is_empty());
+    if let Some(delta) = pending {
+        let _ = app.emit("agent:delta", delta_payload(request_id, delta));
+    }
+}
+
+#[tauri::command]
+pub fn get_a11y_settings(app: tauri::AppHandle) -> A11ySettings {
+    settings(&app)
+}
+
+#[tauri::command]
+pub fn set_a11y_settings(
+    app: tauri::AppHandle,
+    settings: A11ySettings,
+) -> Result<A11ySettings, String> {
+    let mut settings = settings;
+    settings.update_interval_ms = settings.update_interval_ms.max(MIN_UPDATE_INTERVAL_MS);
+    save_json(&app, A11Y_FILE, &settings)?;
+    *app.state::<A11yState>().settings.lock().unwrap() = settings.clone();
+    Ok(settings)
+}
@@ -0,0 +1,205 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Emitter;
+
+use crate::app_data::app_data_path;
+use crate::clock::now_millis;
+use crate::workspace::workspace_root;
+use crate::{rpc, sidecar, stats, tasks, todos};
+
+pub(crate) const AUDIT_LOG_FILE: &str = "action_audit.jsonl";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionDescriptor {
+    id: &'static str,
+    title: &'static str,
+    category: &'static str,
+    shortcut: Option<&'static str>,
+    /// Names of the arguments the action reads from `args`
+    args: &'static [&'static str],
+}
+
+/// Every built-in operation `invoke_action` can dispatch.
+const ACTIONS: &[ActionDescriptor] = &[
+    ActionDescriptor {
+        id: "chat.new",
+        title: "New Chat",
+        category: "Chat",
+        shortcut: Some("CmdOrCtrl+N"),
+        args: &[],
+    },
+    ActionDescriptor {
+        id: "chat.export",
+        title: "Export Conversation",
+        category: "Chat",
+        shortcut: Some("CmdOrCtrl+Shift+E"),
+        args: &["conversationId"],
+    },
+    ActionDescriptor {
+        id: "sidecar.restart",
+        title: "Restart Agent",
+        category: "Agent",
+        shortcut: None,
+        args: &[],
+    },
+    ActionDescriptor {
+        id: "sidecar.ping",
+        title: "Check Agent Health",
+        category: "Agent",
+        shortcut: None,
+        args: &[],
+    },
+    ActionDescriptor {
+        id: "workspace.stats",
+        title: "Show Workspace Statistics",
+        category: "Workspace",
+        shortcut: None,
+        args: &["workspace"],
+    },
+    ActionDescriptor {
+        id: "workspace.todos",
+        title: "Scan TODOs",
+        category: "Workspace",
+        shortcut: None,
+        args: &["workspace"],
+    },
+    ActionDescriptor {
+        id: "workspace.run_tests",
+        title: "Run Tests",
+        category: "Workspace",
+        shortcut: Some("CmdOrCtrl+Shift+T"),
+        args: &["workspace"],
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionInvokedEvent {
+    id: String,
+    args: Value,
+}
+
+fn string_arg(args: &Value, name: &str) -> Result<String, String> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| format!("Missing argument '{}'", name))
+}
+
+fn detect_test_command(root: &Path) -> Option<&'static str> {
+    if root.join("Cargo.toml").is_file() {
+        Some("cargo test")
+    } else if root.join("package.json").is_file() {
+        Some("npm test")
+    } else if root.join("go.mod").is_file() {
+        Some("go test ./...")
+    } else if root.join("pyproject.toml").is_file() || root.join("pytest.ini").is_file() {
+        Some("pytest")
+    } else {
+        None
+    }
+}
+
+/// Starts the workspace's detected test runner as a background task, which streams its
+/// output and can be stopped with `cancel_task`. The caller picks the workspace, never the
+/// command.
+fn run_tests(app: &tauri::AppHandle, workspace: &str) -> Result<Value, String> {
+    let root = workspace_root(workspace)?;
+    let command =
+        detect_test_command(&root).ok_or("Could not detect a test command for this workspace")?;
+    let run = tasks::run_task(
+        app.clone(),
+        command.to_string(),
+        root.to_string_lossy().into_owned(),
+    )?;
+    serde_json::to_value(run).map_err(|e| e.to_string())
+}
+
+/// Appends one line per invocation so every dispatched action leaves a trace.
+fn audit(app: &tauri::AppHandle, id: &str, source: &str, outcome: &Result<Value, String>) {
+    let Ok(path) = app_data_path(app, AUDIT_LOG_FILE) else {
+        return;
+    };
+    let entry = json!({
+        "timestamp": now_millis(),
+        "action": id,
+        "source": source,
+        "ok": outcome.is_ok(),
+        "error": outcome.as_ref().err(),
+    });
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = writeln!(file, "{}", entry);
+    }
+}
+
+async fn dispatch(app: &tauri::AppHandle, id: &str, args: Value) -> Result<Value, String> {
+    match id {
+        // Frontend-owned actions: the backend just routes them back as an event
+        "chat.new" | "chat.export" => {
+            app.emit(
+                "action:invoked",
+                ActionInvokedEvent {
+                    id: id.to_string(),
+                    args,
+                },
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(Value::Null)
+        }
+        "sidecar.restart" => {
+            sidecar::restart(app)?;
+            Ok(Value::Null)
+        }
+        "sidecar.ping" => rpc::call(app, "ping", &json!({}), Duration::from_secs(5))
+            .await
+            .map(Value::String)
+            .map_err(|e| e.message),
+        "workspace.stats" => {
+            let workspace = string_arg(&args, "workspace")?;
+            let stats = stats::get_workspace_stats(workspace).await?;
+            serde_json::to_value(stats).map_err(|e| e.to_string())
+        }
+        "workspace.todos" => {
+            let workspace = string_arg(&args, "workspace")?;
+            let scan = todos::scan_todos(workspace).await?;
+            serde_json::to_value(scan).map_err(|e| e.to_string())
+        }
+        "workspace.run_tests" => {
+            let workspace = string_arg(&args, "workspace")?;
+            run_tests(app, &workspace)
+        }
+        _ => Err(format!("Unknown action: {}", id)),
+    }
+}
+
+#[tauri::command]
+pub fn list_actions() -> Vec<ActionDescriptor> {
+    ACTIONS.to_vec()
+}
+
+/// Single audited entry point for built-in actions; `source` names the caller in the audit
+/// log and defaults to the palette.
+#[tauri::command]
+pub async fn invoke_action(
+    app: tauri::AppHandle,
+    id: String,
+    args: Option<Value>,
+    source: Option<String>,
+) -> Result<Value, String> {
+    let source = source.unwrap_or_else(|| "palette".to_string());
+    let outcome = if ACTIONS.iter().any(|a| a.id == id) {
+        dispatch(&app, &id, args.unwrap_or(Value::Null)).await
+    } else {
+        Err(format!("Unknown action: {}", id))
+    };
+    audit(&app, &id, &source, &outcome);
+    outcome
+}
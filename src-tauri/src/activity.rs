@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::recorder;
+
+// Repeats of the same activity inside this window are dropped
+const MIN_REPEAT_INTERVAL: Duration = Duration::from_millis(250);
+const DETAIL_MAX_CHARS: usize = 120;
+
+/// Tool actions (mostly the skills backend) that only read the workspace.
+const READ_ACTIONS: &[&str] = &[
+    "downloadFiles",
+    "globInfo",
+    "grepRaw",
+    "lsInfo",
+    "read",
+    "readRaw",
+];
+const WRITE_ACTIONS: &[&str] = &["edit", "uploadFiles", "write"];
+
+/// Raw `agent_status` payload from the sidecar.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentStatus {
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(default)]
+    stage: Option<String>,
+    #[serde(default)]
+    tool: Option<String>,
+    #[serde(default)]
+    detail: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityState {
+    Planning,
+    CallingTool,
+    ReadingFile,
+    WritingFile,
+    ToolFinished,
+    ToolFailed,
+    Idle,
+}
+
+/// Normalized `agent:activity` event; `data` keeps the tool's structured arguments or result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActivityEvent {
+    request_id: Option<String>,
+    state: ActivityState,
+    tool: Option<String>,
+    detail: String,
+    data: Value,
+}
+
+struct LastActivity {
+    state: ActivityState,
+    tool: Option<String>,
+    detail: String,
+    at: Instant,
+}
+
+/// Last activity emitted per request, for rate limiting.
+#[derive(Default)]
+pub(crate) struct ActivityTracker(Mutex<HashMap<String, LastActivity>>);
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= DETAIL_MAX_CHARS {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(DETAIL_MAX_CHARS - 1).collect();
+    out.push('…');
+    out
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\'])
+        .find(|s| !s.is_empty())
+        .unwrap_or(path)
+}
+
+fn normalize(status: &AgentStatus) -> (ActivityState, String) {
+    let tool = status.tool.as_deref();
+    let str_field = |key: &str| status.detail.get(key).and_then(Value::as_str);
+    let path = str_field("path")
+        .or_else(|| str_field("output"))
+        .or_else(|| str_field("file"));
+
+    match (status.stage.as_deref(), tool) {
+        (Some("tool_start"), Some(tool)) => {
+            let action = str_field("action").unwrap_or_default();
+            match path {
+                Some(path) if READ_ACTIONS.contains(&action) => (
+                    ActivityState::ReadingFile,
+                    format!("Reading {}", file_name(path)),
+                ),
+                Some(path) if WRITE_ACTIONS.contains(&action) => (
+                    ActivityState::WritingFile,
+                    format!("Writing {}", file_name(path)),
+                ),
+                _ => (ActivityState::CallingTool, format!("Calling {}", tool)),
+            }
+        }
+        (Some("tool_end"), Some(tool)) => {
+            (ActivityState::ToolFinished, format!("Finished {}", tool))
+        }
+        (Some("tool_error"), Some(tool)) => match str_field("error") {
+            Some(error) => (
+                ActivityState::ToolFailed,
+                format!("{} failed: {}", tool, error),
+            ),
+            None => (ActivityState::ToolFailed, format!("{} failed", tool)),
+        },
+        _ => (ActivityState::Planning, "Planning".to_string()),
+    }
+}
+
+fn emit(app: &tauri::AppHandle, event: ActivityEvent) {
+    recorder::record(app, "agent:activity", &event);
+    let _ = app.emit("agent:activity", event);
+}
+
+/// Translates a sidecar status update into `agent:activity`. State or tool changes and
+/// tool completions always go out; identical updates are limited to one per 250ms.
+pub(crate) fn handle_status(app: &tauri::AppHandle, status: AgentStatus) {
+    let (state, detail) = normalize(&status);
+    let detail = truncate(&detail);
+
+    if let Some(request_id) = status.request_id.as_deref() {
+        let tracker = app.state::<ActivityTracker>();
+        let mut last = tracker.0.lock().unwrap();
+        let now = Instant::now();
+        let always = matches!(
+            state,
+            ActivityState::ToolFinished | ActivityState::ToolFailed
+        );
+        if let Some(prev) = last.get(request_id) {
+            let repeat = prev.state == state && prev.tool == status.tool && prev.detail == detail;
+            if !always && repeat && now.duration_since(prev.at) < MIN_REPEAT_INTERVAL {
+                return;
+            }
+        }
+        last.insert(
+            request_id.to_string(),
+            LastActivity {
+                state,
+                tool: status.tool.clone(),
+                detail: detail.clone(),
+                at: now,
+            },
+        );
+    }
+
+    emit(
+        app,
+        ActivityEvent {
+            request_id: status.request_id,
+            state,
+            tool: status.tool,
+            detail,
+            data: status.detail,
+        },
+    );
+}
+
+/// Emits the final `idle` state and drops the request's rate-limit entry.
+pub(crate) fn finish_request(app: &tauri::AppHandle, request_id: &str) {
+    app.state::<ActivityTracker>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(request_id);
+    emit(
+        app,
+        ActivityEvent {
+            request_id: Some(request_id.to_string()),
+            state: ActivityState::Idle,
+            tool: None,
+            detail: "Idle".to_string(),
+            data: Value::Null,
+        },
+    );
+}
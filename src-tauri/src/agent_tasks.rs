@@ -0,0 +1,770 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::clock::now_millis;
+use crate::schedules;
+use crate::streams::StreamBuffers;
+use crate::tasks::{self, TaskKind, TaskRun, TaskStatus, TaskTranscript};
+use crate::tool_events::ToolEvent;
+use crate::workspace::workspace_root;
+use crate::workspace_locks::{self, WorkspaceLockGuard};
+use crate::ChatMessage;
+
+/// Label reported as the "window" holding the workspace lock for agent tasks.
+const TASKS_WINDOW: &str = "tasks";
+const MAX_QUEUED_TASKS: usize = 20;
+// Only the end of a longer log is kept, like command transcripts
+const MAX_LOG_BYTES: usize = 1024 * 1024;
+const MAX_SUBTASKS: usize = 8;
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+// How much of each sub-task's reply goes into its parallel task's combined result
+const MAX_SUMMARY_REPLY_BYTES: usize = 4 * 1024;
+
+/// One step of an agent task, as `get_task_log` returns it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TaskLogEntry {
+    /// Reply text, with consecutive deltas merged
+    Text { text: String, at: i64 },
+    #[serde(rename_all = "camelCase")]
+    ToolStart {
+        tool: String,
+        tool_use_id: String,
+        at: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolEnd {
+        tool: String,
+        tool_use_id: String,
+        duration_ms: u64,
+        is_error: bool,
+        at: i64,
+    },
+    Status {
+        status: TaskStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        at: i64,
+    },
+}
+
+impl TaskLogEntry {
+    fn size(&self) -> usize {
+        match self {
+            TaskLogEntry::Text { text, .. } => text.len(),
+            TaskLogEntry::ToolStart { tool, .. } | TaskLogEntry::ToolEnd { tool, .. } => tool.len(),
+            TaskLogEntry::Status { message, .. } => message.as_ref().map_or(0, String::len),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TaskLog {
+    entries: VecDeque<TaskLogEntry>,
+    bytes: usize,
+    truncated: bool,
+}
+
+impl TaskLog {
+    fn push(&mut self, entry: TaskLogEntry) {
+        self.bytes += entry.size();
+        self.entries.push_back(entry);
+        self.trim();
+    }
+
+    fn push_text(&mut self, delta: &str) {
+        self.bytes += delta.len();
+        match self.entries.back_mut() {
+            Some(TaskLogEntry::Text { text, .. }) => text.push_str(delta),
+            _ => self.entries.push_back(TaskLogEntry::Text {
+                text: delta.to_string(),
+                at: now_millis(),
+            }),
+        }
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.bytes > MAX_LOG_BYTES && self.entries.len() > 1 {
+            if let Some(dropped) = self.entries.pop_front() {
+                self.bytes -= dropped.size();
+                self.truncated = true;
+            }
+        }
+    }
+}
+
+/// Which model an agent task runs with; API keys come from the keychain when it starts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskModel {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile_id: Option<String>,
+}
+
+/// A sub-task as its parallel task last saw it, with its reply once it has finished.
+struct Subtask {
+    run: TaskRun,
+    reply: String,
+}
+
+struct AgentTask {
+    run: TaskRun,
+    model: TaskModel,
+    log: TaskLog,
+    handle: Option<tauri::async_runtime::JoinHandle<()>>,
+    /// The sub-tasks of a parallel task, in the order they were given
+    subtasks: Vec<Subtask>,
+    /// Held by a parallel task while its sub-tasks run
+    lock: Option<WorkspaceLockGuard>,
+    /// Set once the user cancels it; however the run then ends, it ends as cancelled
+    cancelling: bool,
+}
+
+/// Queued and running agent tasks, in submission order. Like scheduled messages they are
+/// kept in memory only; a task's log is stored with the command transcripts once it ends.
+#[derive(Default)]
+pub(crate) struct AgentTasks(Mutex<Vec<AgentTask>>);
+
+/// A parallel task with its sub-tasks, as `task:tree` events report it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTree {
+    #[serde(flatten)]
+    run: TaskRun,
+    subtasks: Vec<TaskRun>,
+}
+
+fn emit_update(app: &tauri::AppHandle, run: &TaskRun) {
+    let _ = app.emit("task:update", run);
+}
+
+fn tree_of(task: &AgentTask) -> TaskTree {
+    TaskTree {
+        run: task.run.clone(),
+        subtasks: task.subtasks.iter().map(|s| s.run.clone()).collect(),
+    }
+}
+
+fn emit_tree(app: &tauri::AppHandle, parent_id: &str) {
+    let tree = {
+        let tasks = app.state::<AgentTasks>();
+        let tasks = tasks.0.lock().unwrap();
+        tasks.iter().find(|t| t.run.id == parent_id).map(tree_of)
+    };
+    if let Some(tree) = tree {
+        let _ = app.emit("task:tree", tree);
+    }
+}
+
+/// The label sub-tasks of `parent_id` hold the workspace under.
+fn group_window(parent_id: &str) -> String {
+    format!("{}:{}", TASKS_WINDOW, parent_id)
+}
+
+fn mark_running(task: &mut AgentTask) {
+    task.run.status = TaskStatus::Running;
+    task.run.started_at = now_millis();
+    task.log.push(TaskLogEntry::Status {
+        status: TaskStatus::Running,
+        message: None,
+        at: task.run.started_at,
+    });
+}
+
+/// Starts the oldest queued task unless one is already running. Sub-tasks don't count;
+/// they run alongside each other while their parallel task holds the queue.
+fn start_next(app: &tauri::AppHandle) {
+    let (run, parallel) = {
+        let tasks = app.state::<AgentTasks>();
+        let mut tasks = tasks.0.lock().unwrap();
+        let running = tasks
+            .iter()
+            .any(|t| t.run.parent_id.is_none() && t.run.status == TaskStatus::Running);
+        if running {
+            return;
+        }
+        let Some(task) = tasks
+            .iter_mut()
+            .find(|t| t.run.parent_id.is_none() && t.run.status == TaskStatus::Queued)
+        else {
+            return;
+        };
+        mark_running(task);
+        let parallel = !task.subtasks.is_empty();
+        if !parallel {
+            task.handle = Some(tauri::async_runtime::spawn(execute(
+                app.clone(),
+                task.run.clone(),
+                task.run.command.clone(),
+                task.model.clone(),
+                TASKS_WINDOW.to_string(),
+            )));
+        }
+        (task.run.clone(), parallel)
+    };
+    emit_update(app, &run);
+    if parallel {
+        start_subtasks(app, &run);
+    }
+}
+
+fn subtask_prompt(parent: &str, subtask: &str, count: usize) -> String {
+    format!(
+        "{}\n\nThis is one of {} sub-tasks of a larger task: {}\nThe others run at the same \
+         time in the same workspace, so only work on this one and leave files it doesn't \
+         need alone.",
+        subtask, count, parent
+    )
+}
+
+/// Takes the workspace for a parallel task and starts all of its sub-tasks at once, each
+/// as its own request to the sidecar.
+fn start_subtasks(app: &tauri::AppHandle, parent: &TaskRun) {
+    let window = group_window(&parent.id);
+    let lock = match workspace_locks::acquire_shared(
+        app,
+        &parent.cwd,
+        &window,
+        parent.request_id.as_deref(),
+    ) {
+        Ok(lock) => lock,
+        Err(err) => {
+            finish(app, &parent.id, TaskStatus::Failed, Some(err));
+            return;
+        }
+    };
+    let started = {
+        let tasks = app.state::<AgentTasks>();
+        let mut tasks = tasks.0.lock().unwrap();
+        let count = tasks
+            .iter()
+            .filter(|t| t.run.parent_id.as_deref() == Some(parent.id.as_str()))
+            .count();
+        let mut started = Vec::new();
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.run.parent_id.as_deref() == Some(parent.id.as_str()))
+        {
+            mark_running(task);
+            task.handle = Some(tauri::async_runtime::spawn(execute(
+                app.clone(),
+                task.run.clone(),
+                subtask_prompt(&parent.command, &task.run.command, count),
+                task.model.clone(),
+                window.clone(),
+            )));
+            started.push(task.run.clone());
+        }
+        // Gone when the parallel task was cancelled meanwhile, which drops the lock
+        let Some(parent) = tasks.iter_mut().find(|t| t.run.id == parent.id) else {
+            return;
+        };
+        parent.lock = Some(lock);
+        for subtask in &mut parent.subtasks {
+            if let Some(run) = started.iter().find(|run| run.id == subtask.run.id) {
+                subtask.run = run.clone();
+            }
+        }
+        started
+    };
+    for run in &started {
+        emit_update(app, run);
+    }
+    emit_tree(app, &parent.id);
+}
+
+async fn execute(
+    app: tauri::AppHandle,
+    run: TaskRun,
+    prompt: String,
+    model: TaskModel,
+    window: String,
+) {
+    let result = crate::send_profiled(
+        app.clone(),
+        window,
+        model.provider,
+        None,
+        model.model,
+        model.base_url,
+        vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }],
+        Some(run.cwd.clone()),
+        None,
+        run.request_id.clone(),
+        None,
+        run.conversation_id.clone(),
+        None,
+        None,
+        model.profile_id,
+        None,
+    )
+    .await;
+    match result {
+        Ok(_) => finish(&app, &run.id, TaskStatus::Succeeded, None),
+        Err(err) => finish(&app, &run.id, TaskStatus::Failed, Some(err)),
+    };
+}
+
+/// Ends a task that is already off the queue: stores its log and announces the result.
+/// Returns the final run and its reply text.
+fn close(
+    app: &tauri::AppHandle,
+    task: AgentTask,
+    status: TaskStatus,
+    error: Option<String>,
+) -> (TaskRun, String) {
+    let mut run = task.run;
+    let mut log = task.log;
+    run.status = status;
+    run.finished_at = Some(now_millis());
+    run.error = error.clone();
+    log.push(TaskLogEntry::Status {
+        status,
+        message: error,
+        at: now_millis(),
+    });
+    if let Some(request_id) = &run.request_id {
+        app.state::<StreamBuffers>().take(request_id);
+    }
+    let truncated = log.truncated;
+    let log: Vec<TaskLogEntry> = log.entries.into();
+    let reply = log_text(&log);
+    let subtasks: Vec<TaskRun> = task.subtasks.into_iter().map(|s| s.run).collect();
+    let transcript = TaskTranscript {
+        run: run.clone(),
+        output: Vec::new(),
+        truncated,
+        log,
+        subtasks: subtasks.clone(),
+    };
+    if let Err(err) = tasks::save_transcript(app, &transcript) {
+        eprintln!("[tasks] {}", err);
+    }
+    schedules::record_result(app, &run);
+    emit_update(app, &run);
+    if !subtasks.is_empty() {
+        let tree = TaskTree {
+            run: run.clone(),
+            subtasks,
+        };
+        let _ = app.emit("task:tree", tree);
+    }
+    (run, reply)
+}
+
+/// Takes the task off the queue and ends it, with whatever of a parallel task is still
+/// running, then moves on to the next one. Returns false when it had already finished.
+fn finish(app: &tauri::AppHandle, id: &str, status: TaskStatus, error: Option<String>) -> bool {
+    let (mut task, children, status, error) = {
+        let tasks = app.state::<AgentTasks>();
+        let mut tasks = tasks.0.lock().unwrap();
+        let Some(index) = tasks.iter().position(|t| t.run.id == id) else {
+            return false;
+        };
+        let task = tasks.remove(index);
+        let (children, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut *tasks)
+            .into_iter()
+            .partition(|t| t.run.parent_id.as_deref() == Some(id));
+        *tasks = rest;
+        if task.cancelling {
+            (task, children, TaskStatus::Cancelled, None)
+        } else {
+            (task, children, status, error)
+        }
+    };
+    for child in children {
+        let (run, reply) = close(app, child, TaskStatus::Cancelled, None);
+        if let Some(subtask) = task.subtasks.iter_mut().find(|s| s.run.id == run.id) {
+            *subtask = Subtask { run, reply };
+        }
+    }
+    let parent_id = task.run.parent_id.clone();
+    let (run, reply) = close(app, task, status, error);
+    match parent_id {
+        Some(parent_id) => subtask_finished(app, &parent_id, run, reply),
+        None => start_next(app),
+    }
+    true
+}
+
+/// The combined result of a parallel task: every sub-task's outcome and reply.
+fn results_summary(subtasks: &[Subtask]) -> String {
+    let mut out = "## Sub-task results\n".to_string();
+    for (index, subtask) in subtasks.iter().enumerate() {
+        let status = format!("{:?}", subtask.run.status).to_lowercase();
+        out.push_str(&format!(
+            "\n### {}. {} ({})\n",
+            index + 1,
+            subtask.run.command,
+            status
+        ));
+        if let Some(error) = &subtask.run.error {
+            out.push_str(&format!("Error: {}\n", error));
+        }
+        let mut reply = subtask.reply.trim().to_string();
+        if reply.len() > MAX_SUMMARY_REPLY_BYTES {
+            let mut end = MAX_SUMMARY_REPLY_BYTES;
+            while !reply.is_char_boundary(end) {
+                end -= 1;
+            }
+            reply.truncate(end);
+            reply.push('…');
+        }
+        if !reply.is_empty() {
+            out.push_str(&reply);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Records a finished sub-task with its parallel task, and ends that once no sub-task
+/// is left: as succeeded when all of them did, and failed otherwise.
+fn subtask_finished(app: &tauri::AppHandle, parent_id: &str, run: TaskRun, reply: String) {
+    let outcome = {
+        let tasks = app.state::<AgentTasks>();
+        let mut tasks = tasks.0.lock().unwrap();
+        let remaining = tasks
+            .iter()
+            .any(|t| t.run.parent_id.as_deref() == Some(parent_id));
+        let Some(parent) = tasks.iter_mut().find(|t| t.run.id == parent_id) else {
+            return;
+        };
+        if let Some(subtask) = parent.subtasks.iter_mut().find(|s| s.run.id == run.id) {
+            *subtask = Subtask { run, reply };
+        }
+        if remaining {
+            None
+        } else {
+            parent.log.push(TaskLogEntry::Text {
+                text: results_summary(&parent.subtasks),
+                at: now_millis(),
+            });
+            let total = parent.subtasks.len();
+            let failed = parent
+                .subtasks
+                .iter()
+                .filter(|s| s.run.status != TaskStatus::Succeeded)
+                .count();
+            Some(if failed == 0 {
+                (TaskStatus::Succeeded, None)
+            } else {
+                (
+                    TaskStatus::Failed,
+                    Some(format!("{} of {} sub-tasks did not succeed", failed, total)),
+                )
+            })
+        }
+    };
+    match outcome {
+        Some((status, error)) => {
+            finish(app, parent_id, status, error);
+        }
+        None => emit_tree(app, parent_id),
+    }
+}
+
+fn with_task(app: &tauri::AppHandle, request_id: &str, apply: impl FnOnce(&mut AgentTask)) {
+    let tasks = app.state::<AgentTasks>();
+    let mut tasks = tasks.0.lock().unwrap();
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.run.request_id.as_deref() == Some(request_id));
+    if let Some(task) = task {
+        apply(task);
+    }
+}
+
+/// Adds streamed reply text to the log of the agent task running as `request_id`.
+pub(crate) fn record_text(app: &tauri::AppHandle, request_id: &str, delta: &str) {
+    with_task(app, request_id, |task| task.log.push_text(delta));
+}
+
+/// Adds a tool call step to the log of the agent task it belongs to.
+pub(crate) fn record_tool(app: &tauri::AppHandle, event: &ToolEvent) {
+    let (request_id, entry) = match event {
+        ToolEvent::Start {
+            request_id,
+            tool_use_id,
+            tool,
+            ..
+        } => (
+            request_id,
+            TaskLogEntry::ToolStart {
+                tool: tool.clone(),
+                tool_use_id: tool_use_id.clone(),
+                at: now_millis(),
+            },
+        ),
+        ToolEvent::End {
+            request_id,
+            tool_use_id,
+            tool,
+            duration_ms,
+            is_error,
+            ..
+        } => (
+            request_id,
+            TaskLogEntry::ToolEnd {
+                tool: tool.clone(),
+                tool_use_id: tool_use_id.clone(),
+                duration_ms: *duration_ms,
+                is_error: *is_error,
+                at: now_millis(),
+            },
+        ),
+    };
+    if let Some(request_id) = request_id {
+        with_task(app, request_id, |task| task.log.push(entry));
+    }
+}
+
+/// The running agent task and the queued ones.
+pub(crate) fn active(app: &tauri::AppHandle) -> Vec<TaskRun> {
+    let tasks = app.state::<AgentTasks>();
+    let tasks = tasks.0.lock().unwrap();
+    tasks.iter().map(|t| t.run.clone()).collect()
+}
+
+/// Cancels a queued or running agent task. Returns false when there is none by `id`.
+pub(crate) fn cancel(app: &tauri::AppHandle, id: &str) -> bool {
+    // The runs of the task and of its sub-tasks, which the sidecar is told to stop first
+    let runs = {
+        let tasks = app.state::<AgentTasks>();
+        let mut tasks = tasks.0.lock().unwrap();
+        if !tasks.iter().any(|t| t.run.id == id) {
+            return false;
+        }
+        let mut runs = Vec::new();
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.run.id == id || t.run.parent_id.as_deref() == Some(id))
+        {
+            task.cancelling = true;
+            if let Some(handle) = task.handle.take() {
+                runs.push((task.run.request_id.clone(), handle));
+            }
+        }
+        runs
+    };
+    if runs.is_empty() {
+        return finish(app, id, TaskStatus::Cancelled, None);
+    }
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        for (request_id, handle) in runs {
+            if let Some(request_id) = request_id {
+                let params = serde_json::json!({ "requestId": request_id });
+                if let Err(err) =
+                    crate::rpc::call(&app, "cancelRequest", &params, CANCEL_TIMEOUT).await
+                {
+                    eprintln!(
+                        "[agent_tasks] failed to cancel {}: {}",
+                        request_id, err.message
+                    );
+                }
+            }
+            // Only once the sidecar has stopped does dropping the run release its locks
+            handle.abort();
+            let _ = handle.await;
+        }
+        finish(&app, &id, TaskStatus::Cancelled, None);
+    });
+    true
+}
+
+/// The reply text of a log, for `get_task_context`.
+pub(crate) fn log_text(log: &[TaskLogEntry]) -> String {
+    log.iter()
+        .filter_map(|entry| match entry {
+            TaskLogEntry::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Queues `description` as a prompt for the agent in `workspace`. Tasks run one at a time
+/// in the background into a conversation of their own, so they keep going when the chat
+/// view is closed. Status changes arrive as `task:update` events.
+#[tauri::command]
+pub fn submit_task(
+    app: tauri::AppHandle,
+    description: String,
+    workspace: String,
+    model: Option<TaskModel>,
+) -> Result<TaskRun, String> {
+    let description = description.trim().to_string();
+    if description.is_empty() {
+        return Err("Task description is empty".to_string());
+    }
+    let root = workspace_root(&workspace)?;
+    let task = new_task(description, &root, None, model.unwrap_or_default());
+    let run = task.run.clone();
+    enqueue(&app, vec![task])?;
+    emit_update(&app, &run);
+    start_next(&app);
+    Ok(run)
+}
+
+fn new_task(
+    description: String,
+    root: &Path,
+    parent_id: Option<&str>,
+    model: TaskModel,
+) -> AgentTask {
+    let run = TaskRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: TaskKind::Agent,
+        command: description,
+        cwd: root.to_string_lossy().into_owned(),
+        status: TaskStatus::Queued,
+        started_at: now_millis(),
+        finished_at: None,
+        exit_code: None,
+        signal: None,
+        conversation_id: Some(uuid::Uuid::new_v4().to_string()),
+        request_id: Some(uuid::Uuid::new_v4().to_string()),
+        error: None,
+        parent_id: parent_id.map(String::from),
+    };
+    let mut log = TaskLog::default();
+    log.push(TaskLogEntry::Status {
+        status: TaskStatus::Queued,
+        message: None,
+        at: run.started_at,
+    });
+    AgentTask {
+        run,
+        model,
+        log,
+        handle: None,
+        subtasks: Vec::new(),
+        lock: None,
+        cancelling: false,
+    }
+}
+
+/// Adds a task, with its sub-tasks when it has any, to the end of the queue.
+fn enqueue(app: &tauri::AppHandle, new: Vec<AgentTask>) -> Result<(), String> {
+    let tasks = app.state::<AgentTasks>();
+    let mut tasks = tasks.0.lock().unwrap();
+    let queued = tasks.iter().filter(|t| t.run.parent_id.is_none()).count();
+    if queued >= MAX_QUEUED_TASKS {
+        return Err(format!(
+            "At most {} agent tasks can be queued",
+            MAX_QUEUED_TASKS
+        ));
+    }
+    tasks.extend(new);
+    Ok(())
+}
+
+/// Queues `description` as a task that runs each of `subtasks` as its own agent request
+/// at the same time, e.g. one per failing test. The task holds the workspace and the
+/// queue until all of them have ended, then finishes with their combined results.
+/// `task:tree` events report it with the state of every sub-task.
+#[tauri::command]
+pub fn submit_parallel_task(
+    app: tauri::AppHandle,
+    description: String,
+    workspace: String,
+    subtasks: Vec<String>,
+    model: Option<TaskModel>,
+) -> Result<TaskTree, String> {
+    let description = description.trim().to_string();
+    if description.is_empty() {
+        return Err("Task description is empty".to_string());
+    }
+    let subtasks: Vec<String> = subtasks
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if subtasks.is_empty() {
+        return Err("A parallel task needs at least one sub-task".to_string());
+    }
+    if subtasks.len() > MAX_SUBTASKS {
+        return Err(format!(
+            "A parallel task can have at most {} sub-tasks",
+            MAX_SUBTASKS
+        ));
+    }
+    let root = workspace_root(&workspace)?;
+    let model = model.unwrap_or_default();
+    let mut parent = new_task(description, &root, None, model.clone());
+    // Only the sub-tasks talk to the agent, each in a conversation of its own
+    parent.run.conversation_id = None;
+    let children: Vec<AgentTask> = subtasks
+        .into_iter()
+        .map(|subtask| new_task(subtask, &root, Some(&parent.run.id), model.clone()))
+        .collect();
+    parent.subtasks = children
+        .iter()
+        .map(|child| Subtask {
+            run: child.run.clone(),
+            reply: String::new(),
+        })
+        .collect();
+    let tree = tree_of(&parent);
+    enqueue(&app, std::iter::once(parent).chain(children).collect())?;
+    emit_update(&app, &tree.run);
+    let _ = app.emit("task:tree", tree.clone());
+    start_next(&app);
+    Ok(tree)
+}
+
+/// A parallel task with the current or final state of each of its sub-tasks.
+#[tauri::command]
+pub fn get_task_tree(app: tauri::AppHandle, id: String) -> Result<TaskTree, String> {
+    {
+        let tasks = app.state::<AgentTasks>();
+        let tasks = tasks.0.lock().unwrap();
+        if let Some(task) = tasks.iter().find(|t| t.run.id == id) {
+            if task.subtasks.is_empty() {
+                return Err(format!("Not a parallel task: {}", id));
+            }
+            return Ok(tree_of(task));
+        }
+    }
+    let transcript = tasks::load_transcript(&app, &id)?;
+    if transcript.subtasks.is_empty() {
+        return Err(format!("Not a parallel task: {}", id));
+    }
+    Ok(TaskTree {
+        run: transcript.run,
+        subtasks: transcript.subtasks,
+    })
+}
+
+/// What an agent task has done so far, or the stored log of a finished one.
+#[tauri::command]
+pub fn get_task_log(app: tauri::AppHandle, id: String) -> Result<Vec<TaskLogEntry>, String> {
+    {
+        let tasks = app.state::<AgentTasks>();
+        let tasks = tasks.0.lock().unwrap();
+        if let Some(task) = tasks.iter().find(|t| t.run.id == id) {
+            return Ok(task.log.entries.iter().cloned().collect());
+        }
+    }
+    let transcript = tasks::load_transcript(&app, &id)?;
+    if transcript.run.kind != TaskKind::Agent {
+        return Err(format!("Not an agent task: {}", id));
+    }
+    Ok(transcript.log)
+}
@@ -0,0 +1,42 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::Manager;
+
+pub(crate) fn app_data_path(app: &tauri::AppHandle, file: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join(file))
+}
+
+/// Loads a JSON document from the app data dir, falling back to `T::default()` if it
+/// doesn't exist yet.
+pub(crate) fn load_json<T: DeserializeOwned + Default>(
+    app: &tauri::AppHandle,
+    file: &str,
+) -> Result<T, String> {
+    let path = app_data_path(app, file)?;
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", file, e))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(format!("Failed to read {}: {}", file, err)),
+    }
+}
+
+/// Writes a JSON document atomically (temp file + rename) so a crash never leaves it half-written.
+pub(crate) fn save_json<T: Serialize>(
+    app: &tauri::AppHandle,
+    file: &str,
+    value: &T,
+) -> Result<(), String> {
+    let path = app_data_path(app, file)?;
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, content).map_err(|e| format!("Failed to write {}: {}", file, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to save {}: {}", file, e))
+}
@@ -0,0 +1,208 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::workspace::{relative_path, resolve_workspace_path, walk_files, workspace_root};
+
+const MAX_EXPORT_FILES: usize = 100_000;
+// Progress is reported after this many files, and after the last one
+const PROGRESS_EVERY: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipReport {
+    dest: String,
+    files: usize,
+    /// Uncompressed size of everything added
+    bytes: u64,
+    /// Size of the archive
+    archive_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZipProgress<'a> {
+    dest: &'a str,
+    files_done: usize,
+    files_total: usize,
+    bytes_done: u64,
+    done: bool,
+}
+
+fn exclusions(root: &Path, patterns: &[String]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns.iter().filter(|p| !p.trim().is_empty()) {
+        builder
+            .add_line(None, pattern.trim())
+            .map_err(|e| format!("Invalid exclude pattern {}: {}", pattern, e))?;
+    }
+    builder
+        .build()
+        .map_err(|e| format!("Invalid exclude patterns: {}", e))
+}
+
+/// The files to add: everything under each of `paths`, or the whole workspace, without
+/// what .gitignore, hidden-file and `exclude` rules leave out.
+fn collect_files(
+    root: &Path,
+    paths: &[String],
+    exclude: &Gitignore,
+    dest: &Path,
+) -> Result<BTreeSet<PathBuf>, String> {
+    let starts = if paths.is_empty() {
+        vec![root.to_path_buf()]
+    } else {
+        paths
+            .iter()
+            .map(|path| resolve_workspace_path(root, path))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let mut files = BTreeSet::new();
+    for start in starts {
+        let found: Box<dyn Iterator<Item = PathBuf>> = if start.is_file() {
+            Box::new(std::iter::once(start))
+        } else {
+            Box::new(walk_files(&start))
+        };
+        for file in found {
+            let relative = file.strip_prefix(root).unwrap_or(&file);
+            if file == dest
+                || exclude
+                    .matched_path_or_any_parents(relative, false)
+                    .is_ignore()
+            {
+                continue;
+            }
+            files.insert(file);
+            if files.len() > MAX_EXPORT_FILES {
+                return Err(format!(
+                    "Exports can have at most {} files",
+                    MAX_EXPORT_FILES
+                ));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn options_for(file: &Path) -> SimpleFileOptions {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(std::fs::metadata(file).is_ok_and(|m| m.len() >= u32::MAX as u64));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(file) {
+            return options.unix_permissions(metadata.permissions().mode());
+        }
+    }
+    options
+}
+
+fn write_zip(
+    app: &tauri::AppHandle,
+    root: &Path,
+    files: &BTreeSet<PathBuf>,
+    partial: &Path,
+    dest: &str,
+) -> Result<u64, String> {
+    // Entries sit under the workspace's folder name so unpacking makes one folder
+    let prefix = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "workspace".to_string());
+    let out = File::create(partial).map_err(|e| format!("Failed to create {}: {}", dest, e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(out));
+    let mut bytes = 0u64;
+    for (done, file) in files.iter().enumerate() {
+        let name = format!("{}/{}", prefix, relative_path(root, file));
+        let mut input = File::open(file).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        zip.start_file(name.as_str(), options_for(file))
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        bytes += std::io::copy(&mut input, &mut zip)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        if (done + 1) % PROGRESS_EVERY == 0 || done + 1 == files.len() {
+            let _ = app.emit(
+                "export-zip:progress",
+                ZipProgress {
+                    dest,
+                    files_done: done + 1,
+                    files_total: files.len(),
+                    bytes_done: bytes,
+                    done: false,
+                },
+            );
+        }
+    }
+    zip.finish()
+        .and_then(|mut out| out.flush().map_err(Into::into))
+        .map_err(|e| format!("Failed to finish {}: {}", dest, e))?;
+    Ok(bytes)
+}
+
+/// Packs `paths` (workspace-relative files or folders; the whole workspace when empty)
+/// into a zip at `dest`, an absolute path. Files ignored by .gitignore, hidden files and
+/// those matching the gitignore-style `exclude` patterns are left out. Progress arrives
+/// as `export-zip:progress` events.
+#[tauri::command]
+pub async fn export_zip(
+    app: tauri::AppHandle,
+    workspace: String,
+    paths: Option<Vec<String>>,
+    dest: String,
+    exclude: Option<Vec<String>>,
+) -> Result<ZipReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = workspace_root(&workspace)?;
+        let dest_path = PathBuf::from(dest.trim());
+        if !dest_path.is_absolute() {
+            return Err(format!("Expected an absolute path: {}", dest));
+        }
+        if dest_path.is_dir() {
+            return Err(format!("A folder already exists at {}", dest));
+        }
+        let exclude = exclusions(&root, &exclude.unwrap_or_default())?;
+        let files = collect_files(&root, &paths.unwrap_or_default(), &exclude, &dest_path)?;
+        if files.is_empty() {
+            return Err("Nothing to export".to_string());
+        }
+
+        // A failed export leaves any earlier archive at `dest` untouched
+        let mut partial = dest_path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let bytes = match write_zip(&app, &root, &files, &partial, &dest) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                let _ = std::fs::remove_file(&partial);
+                return Err(err);
+            }
+        };
+        std::fs::rename(&partial, &dest_path)
+            .map_err(|e| format!("Failed to write {}: {}", dest, e))?;
+        let _ = app.emit(
+            "export-zip:progress",
+            ZipProgress {
+                dest: &dest,
+                files_done: files.len(),
+                files_total: files.len(),
+                bytes_done: bytes,
+                done: true,
+            },
+        );
+        Ok(ZipReport {
+            dest: dest_path.to_string_lossy().into_owned(),
+            files: files.len(),
+            bytes,
+            archive_bytes: std::fs::metadata(&dest_path).map_or(0, |m| m.len()),
+        })
+    })
+    .await
+    .map_err(|e| format!("Export failed: {}", e))?
+}
@@ -0,0 +1,161 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::redaction::{self, Sensitivity};
+use crate::workspace::{is_binary_file, relative_path, resolve_workspace_path, workspace_root};
+
+const MAX_ATTACHMENTS: usize = 20;
+const MAX_FILE_BYTES: usize = 128 * 1024;
+const MAX_TOTAL_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentSecrets {
+    path: String,
+    sensitivity: Sensitivity,
+    /// How many values will be redacted when the file is sent
+    redacted: usize,
+}
+
+/// Resolves a workspace-relative path, rejecting anything that lands outside the workspace.
+fn resolve(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let cleaned = relative.trim().trim_start_matches(['/', '\\']);
+    if cleaned.is_empty() {
+        return Err("Attachment path is empty".to_string());
+    }
+    let path = resolve_workspace_path(root, cleaned)?;
+    if !path.exists() {
+        return Err(format!("Attachment not found: {}", relative));
+    }
+    if !path.is_file() {
+        return Err(format!("Attachment is not a file: {}", relative));
+    }
+    Ok(path)
+}
+
+/// A fence longer than any backtick run in `content`, so file contents can't close it.
+fn fence_for(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat((longest + 1).max(3))
+}
+
+fn truncate_at_char_boundary(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Reads the attached files and renders them as fenced context blocks for the agent.
+/// Binary files are listed but not inlined; large files are truncated, and files past
+/// the total budget are omitted with a note. Secrets are redacted first.
+pub(crate) fn context_block(workspace: &str, paths: &[String]) -> Result<String, String> {
+    if paths.len() > MAX_ATTACHMENTS {
+        return Err(format!(
+            "Too many attachments: {} (max {})",
+            paths.len(),
+            MAX_ATTACHMENTS
+        ));
+    }
+    let root = workspace_root(workspace)?;
+
+    let mut out = String::from("The user attached these workspace files for reference.\n");
+    let mut budget = MAX_TOTAL_BYTES;
+    for relative in paths {
+        let path = resolve(&root, relative)?;
+        let name = relative_path(&root, &path);
+
+        if is_binary_file(&path) {
+            out.push_str(&format!("\n{} (binary file, contents omitted)\n", name));
+            continue;
+        }
+        if budget == 0 {
+            out.push_str(&format!(
+                "\n{} (omitted: attachment size limit reached)\n",
+                name
+            ));
+            continue;
+        }
+
+        let limit = MAX_FILE_BYTES.min(budget);
+        let size = std::fs::metadata(&path)
+            .map(|m| m.len())
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        // Only read what can be used; huge files are never loaded whole
+        let mut bytes = Vec::with_capacity(limit.min(size as usize));
+        File::open(&path)
+            .and_then(|f| f.take(limit as u64).read_to_end(&mut bytes))
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let content = truncate_at_char_boundary(&text, limit);
+        budget = budget.saturating_sub(content.len());
+
+        // Secrets are masked here so they never reach the model
+        let masked = redaction::mask_file(&path, content);
+        let fence = fence_for(&masked.text);
+        out.push_str(&format!(
+            "\n{fence}{name}\n{}\n{fence}\n",
+            masked.text.trim_end_matches('\n')
+        ));
+        if masked.masked > 0 {
+            out.push_str(&format!(
+                "({}: {} secret value(s) redacted)\n",
+                name, masked.masked
+            ));
+        }
+        if (content.len() as u64) < size {
+            out.push_str(&format!(
+                "({} truncated to {} of {} bytes)\n",
+                name,
+                content.len(),
+                size
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Which attachments hold secrets that will be redacted before sending, so the user can
+/// be warned first. Files without any are left out.
+#[tauri::command]
+pub fn check_attachment_secrets(
+    workspace: String,
+    paths: Vec<String>,
+) -> Result<Vec<AttachmentSecrets>, String> {
+    let root = workspace_root(&workspace)?;
+    let mut found = Vec::new();
+    for relative in paths.iter().take(MAX_ATTACHMENTS) {
+        let path = resolve(&root, relative)?;
+        if is_binary_file(&path) {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        File::open(&path)
+            .and_then(|f| f.take(MAX_FILE_BYTES as u64).read_to_end(&mut bytes))
+            .map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        let masked = redaction::mask_file(&path, &String::from_utf8_lossy(&bytes));
+        if masked.masked > 0 {
+            found.push(AttachmentSecrets {
+                path: relative_path(&root, &path),
+                sensitivity: redaction::sensitivity(&path),
+                redacted: masked.masked,
+            });
+        }
+    }
+    Ok(found)
+}
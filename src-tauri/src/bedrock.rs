@@ -0,0 +1,593 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+
+const BEDROCK_SETTINGS_FILE: &str = "bedrock.json";
+const DEFAULT_REGION: &str = "us-east-1";
+const SIGNING_SERVICE: &str = "bedrock";
+const SIGNATURE_TIMEOUT: Duration = Duration::from_secs(5);
+// Credentials without an expiry (env, shared file) are re-read this often
+const STATIC_CREDENTIALS_TTL_MS: i64 = 5 * 60 * 1000;
+// Temporary credentials are refreshed this long before they expire
+const EXPIRY_MARGIN_MS: i64 = 5 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BedrockSettings {
+    /// AWS profile; `AWS_PROFILE` or `default` when absent
+    #[serde(default)]
+    profile: Option<String>,
+    /// AWS region; from the environment or the profile when absent
+    #[serde(default)]
+    region: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    /// Unix millis after which the credentials must be fetched again
+    valid_until: i64,
+}
+
+/// `credential_process` / `aws configure export-credentials` output.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProcessCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    #[serde(default)]
+    session_token: Option<String>,
+    #[serde(default)]
+    expiration: Option<String>,
+}
+
+/// The last credentials resolved, by profile.
+#[derive(Default)]
+pub(crate) struct AwsCredentialCache(Mutex<Option<(String, AwsCredentials)>>);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignRequest {
+    sign_id: String,
+    method: String,
+    url: String,
+    /// Hex SHA-256 of the body, so the body itself never crosses the pipe twice
+    payload_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureReply {
+    sign_id: String,
+    headers: Option<HashMap<String, String>>,
+    error: Option<String>,
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn aws_file(env_override: &str, name: &str) -> Option<PathBuf> {
+    match std::env::var_os(env_override) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => home_dir().map(|home| home.join(".aws").join(name)),
+    }
+}
+
+/// Keys of one `[section]` in an AWS-style INI file.
+fn ini_section(text: &str, section: &str) -> Option<HashMap<String, String>> {
+    let mut values = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if values.is_some() {
+                break;
+            }
+            if name.trim() == section {
+                values = Some(HashMap::new());
+            }
+            continue;
+        }
+        if let (Some(values), Some((key, value))) = (values.as_mut(), line.split_once('=')) {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// The profile's section in `~/.aws/config`, which names non-default profiles `profile x`.
+fn config_section(profile: &str) -> Option<HashMap<String, String>> {
+    let text = std::fs::read_to_string(aws_file("AWS_CONFIG_FILE", "config")?).ok()?;
+    let name = if profile == "default" {
+        "default".to_string()
+    } else {
+        format!("profile {}", profile)
+    };
+    ini_section(&text, &name)
+}
+
+fn profile_name(settings: &BedrockSettings) -> String {
+    settings
+        .profile
+        .clone()
+        .or_else(|| std::env::var("AWS_PROFILE").ok())
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+fn region(settings: &BedrockSettings) -> String {
+    settings
+        .region
+        .clone()
+        .or_else(|| std::env::var("AWS_REGION").ok())
+        .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+        .or_else(|| config_section(&profile_name(settings))?.remove("region"))
+        .filter(|r| !r.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_REGION.to_string())
+}
+
+/// The OpenAI-compatible Bedrock runtime endpoint for the configured region.
+pub(crate) fn runtime_base_url(app: &tauri::AppHandle) -> String {
+    let settings = load_json::<BedrockSettings>(app, BEDROCK_SETTINGS_FILE).unwrap_or_default();
+    format!(
+        "https://bedrock-runtime.{}.amazonaws.com/openai/v1",
+        region(&settings)
+    )
+}
+
+fn from_process_output(output: &[u8]) -> Result<AwsCredentials, String> {
+    let parsed: ProcessCredentials = serde_json::from_slice(output)
+        .map_err(|e| format!("Unexpected AWS credential output: {}", e))?;
+    let valid_until = match parsed.expiration.as_deref() {
+        Some(expiration) => {
+            chrono::DateTime::parse_from_rfc3339(expiration)
+                .map_err(|e| format!("Invalid AWS credential expiration: {}", e))?
+                .timestamp_millis()
+                - EXPIRY_MARGIN_MS
+        }
+        None => now_millis() + STATIC_CREDENTIALS_TTL_MS,
+    };
+    Ok(AwsCredentials {
+        access_key_id: parsed.access_key_id,
+        secret_access_key: parsed.secret_access_key,
+        session_token: parsed.session_token.filter(|t| !t.is_empty()),
+        valid_until,
+    })
+}
+
+fn run_credential_command(mut command: Command) -> Result<AwsCredentials, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run the AWS credential command: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "AWS credential command failed: {}",
+            stderr.trim().chars().take(300).collect::<String>()
+        ));
+    }
+    from_process_output(&output.stdout)
+}
+
+/// The AWS CLI also runs `credential_process` without a shell, splitting it with shell
+/// quoting rules so quoted arguments stay whole.
+fn credential_process_command(process: &str) -> Result<Command, String> {
+    let parts =
+        shell_words::split(process).map_err(|e| format!("Invalid credential_process: {}", e))?;
+    let (program, args) = parts.split_first().ok_or("credential_process is empty")?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+/// Resolves credentials the way the AWS SDKs do: environment, shared credentials file,
+/// `credential_process`, then the AWS CLI, which covers SSO and assumed roles.
+fn resolve_credentials(profile: &str) -> Result<AwsCredentials, String> {
+    if let (Ok(access_key_id), Ok(secret_access_key)) = (
+        std::env::var("AWS_ACCESS_KEY_ID"),
+        std::env::var("AWS_SECRET_ACCESS_KEY"),
+    ) {
+        return Ok(AwsCredentials {
+            access_key_id,
+            secret_access_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            valid_until: now_millis() + STATIC_CREDENTIALS_TTL_MS,
+        });
+    }
+
+    let shared = aws_file("AWS_SHARED_CREDENTIALS_FILE", "credentials")
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|text| ini_section(&text, profile));
+    if let Some(mut section) = shared {
+        if let (Some(access_key_id), Some(secret_access_key)) = (
+            section.remove("aws_access_key_id"),
+            section.remove("aws_secret_access_key"),
+        ) {
+            return Ok(AwsCredentials {
+                access_key_id,
+                secret_access_key,
+                session_token: section.remove("aws_session_token"),
+                valid_until: now_millis() + STATIC_CREDENTIALS_TTL_MS,
+            });
+        }
+    }
+
+    if let Some(process) = config_section(profile).and_then(|mut c| c.remove("credential_process"))
+    {
+        return run_credential_command(credential_process_command(&process)?);
+    }
+
+    let mut command = Command::new("aws");
+    command.args([
+        "configure",
+        "export-credentials",
+        "--profile",
+        profile,
+        "--format",
+        "process",
+    ]);
+    run_credential_command(command).map_err(|err| {
+        format!(
+            "No AWS credentials found for profile {} ({}). Set AWS_ACCESS_KEY_ID or run `aws sso login`.",
+            profile, err
+        )
+    })
+}
+
+fn credentials(app: &tauri::AppHandle, profile: &str) -> Result<AwsCredentials, String> {
+    let cache = app.state::<AwsCredentialCache>();
+    if let Some((cached_profile, creds)) = cache.0.lock().unwrap().as_ref() {
+        if cached_profile == profile && creds.valid_until > now_millis() {
+            return Ok(creds.clone());
+        }
+    }
+    let creds = resolve_credentials(profile)?;
+    *cache.0.lock().unwrap() = Some((profile.to_string(), creds.clone()));
+    Ok(creds)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let mut key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    key
+}
+
+/// RFC 3986 encoding as SigV4 wants it; `/` is kept when encoding paths.
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// SigV4 headers for a request whose body hashes to `payload_hash`, signing `headers`
+/// along with the host, date and session token.
+#[allow(clippy::too_many_arguments)]
+fn sign(
+    creds: &AwsCredentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    url: &str,
+    mut headers: BTreeMap<String, String>,
+    payload_hash: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<HashMap<String, String>, String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid request URL: {}", e))?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err("Request URL has no host".to_string()),
+    };
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    headers.insert("host".to_string(), host);
+    headers.insert("x-amz-date".to_string(), amz_date.clone());
+    if let Some(token) = &creds.session_token {
+        headers.insert("x-amz-security-token".to_string(), token.clone());
+    }
+    let signed_headers = headers.keys().cloned().collect::<Vec<_>>().join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k, false), uri_encode(&v, false)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    // Services other than S3 expect the already-encoded path to be encoded again
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        uri_encode(url.path(), true),
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(&creds.secret_access_key, &date, region, service);
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let mut out: HashMap<String, String> = headers.into_iter().collect();
+    // fetch derives Host from the URL itself
+    out.remove("host");
+    out.insert(
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            creds.access_key_id, scope, signed_headers, signature
+        ),
+    );
+    Ok(out)
+}
+
+/// Region and signing headers for an AWS `url`. The region comes from the host, e.g.
+/// `bedrock-runtime.us-west-2.amazonaws.com`, falling back to the configured one.
+pub(crate) fn signed_headers(
+    app: &tauri::AppHandle,
+    method: &str,
+    url: &str,
+    payload_hash: &str,
+) -> Result<HashMap<String, String>, String> {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    // Credentials are only ever used for AWS endpoints, whatever the sidecar asks
+    let Some(prefix) = host.strip_suffix(".amazonaws.com") else {
+        return Err(format!("Only AWS endpoints can be signed, not {}", host));
+    };
+    let settings = load_json::<BedrockSettings>(app, BEDROCK_SETTINGS_FILE)?;
+    let region = match prefix.split_once('.') {
+        Some((_, region)) if !region.contains('.') => region.to_string(),
+        _ => region(&settings),
+    };
+    let creds = credentials(app, &profile_name(&settings))?;
+    let headers = BTreeMap::from([("x-amz-content-sha256".to_string(), payload_hash.to_string())]);
+    sign(
+        &creds,
+        &region,
+        SIGNING_SERVICE,
+        method,
+        url,
+        headers,
+        payload_hash,
+        chrono::Utc::now(),
+    )
+}
+
+/// Signs a Bedrock request the sidecar is about to send and hands the headers back via
+/// `signatureReady`. Credential lookup may run the AWS CLI, so it stays off the reader.
+pub(crate) fn handle_sign_request(app: &tauri::AppHandle, value: serde_json::Value) {
+    let request: SignRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[bedrock] malformed sign request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let sign_id = request.sign_id.clone();
+        let signer = app.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            signed_headers(
+                &signer,
+                &request.method,
+                &request.url,
+                &request.payload_hash,
+            )
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Signing task failed: {}", e)));
+        let reply = match result {
+            Ok(headers) => SignatureReply {
+                sign_id,
+                headers: Some(headers),
+                error: None,
+            },
+            Err(err) => SignatureReply {
+                sign_id,
+                headers: None,
+                error: Some(err),
+            },
+        };
+        if let Err(err) = crate::rpc::call(&app, "signatureReady", &reply, SIGNATURE_TIMEOUT).await
+        {
+            eprintln!("[bedrock] failed to deliver signature: {}", err.message);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_bedrock_settings(app: tauri::AppHandle) -> Result<BedrockSettings, String> {
+    load_json::<BedrockSettings>(&app, BEDROCK_SETTINGS_FILE)
+}
+
+#[tauri::command]
+pub fn set_bedrock_settings(
+    app: tauri::AppHandle,
+    settings: BedrockSettings,
+) -> Result<BedrockSettings, String> {
+    let trim = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let settings = BedrockSettings {
+        profile: trim(settings.profile),
+        region: trim(settings.region),
+    };
+    if let Some(region) = &settings.region {
+        if !region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(format!("Invalid AWS region: {}", region));
+        }
+    }
+    save_json(&app, BEDROCK_SETTINGS_FILE, &settings)?;
+    // A different profile may resolve to different credentials
+    *app.state::<AwsCredentialCache>().0.lock().unwrap() = None;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Credentials and time of the AWS SigV4 test suite
+    const SECRET: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn suite_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: SECRET.to_string(),
+            session_token: None,
+            valid_until: 0,
+        }
+    }
+
+    fn suite_signature(method: &str, url: &str) -> String {
+        let now = chrono::Utc
+            .with_ymd_and_hms(2015, 8, 30, 12, 36, 0)
+            .unwrap();
+        let headers = sign(
+            &suite_credentials(),
+            "us-east-1",
+            "service",
+            method,
+            url,
+            BTreeMap::new(),
+            EMPTY_HASH,
+            now,
+        )
+        .unwrap();
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        headers["authorization"].clone()
+    }
+
+    fn suite_authorization(signature: &str) -> String {
+        format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, Signature={}",
+            signature
+        )
+    }
+
+    #[test]
+    fn derives_the_documented_signing_key() {
+        let key = signing_key(SECRET, "20120215", "us-east-1", "iam");
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn signs_get_vanilla() {
+        assert_eq!(
+            suite_signature("GET", "https://example.amazonaws.com/"),
+            suite_authorization("5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31")
+        );
+    }
+
+    #[test]
+    fn signs_get_vanilla_query_order_key_case() {
+        assert_eq!(
+            suite_signature(
+                "GET",
+                "https://example.amazonaws.com/?Param2=value2&Param1=value1"
+            ),
+            suite_authorization("b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500")
+        );
+    }
+
+    #[test]
+    fn signs_post_vanilla() {
+        assert_eq!(
+            suite_signature("POST", "https://example.amazonaws.com/"),
+            suite_authorization("5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b")
+        );
+    }
+
+    #[test]
+    fn signs_the_session_token() {
+        let mut creds = suite_credentials();
+        creds.session_token = Some("token".to_string());
+        let now = chrono::Utc
+            .with_ymd_and_hms(2015, 8, 30, 12, 36, 0)
+            .unwrap();
+        let headers = sign(
+            &creds,
+            "us-east-1",
+            "service",
+            "GET",
+            "https://example.amazonaws.com/",
+            BTreeMap::new(),
+            EMPTY_HASH,
+            now,
+        )
+        .unwrap();
+        assert_eq!(headers["x-amz-security-token"], "token");
+        assert!(headers["authorization"]
+            .contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+        assert!(!headers.contains_key("host"));
+    }
+
+    #[test]
+    fn splits_credential_process_with_shell_quoting() {
+        let command =
+            credential_process_command(r#"/opt/bin/creds --profile "my team" 'a b'"#).unwrap();
+        assert_eq!(command.get_program(), "/opt/bin/creds");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["--profile", "my team", "a b"]);
+        assert!(credential_process_command("  ").is_err());
+        assert!(credential_process_command("creds \"unterminated").is_err());
+    }
+}
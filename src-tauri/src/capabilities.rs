@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+/// Assumed for models not in the registry; small enough to be safe for most providers.
+const DEFAULT_CONTEXT_WINDOW: i64 = 32_768;
+
+/// What a model family can do, matched by longest model-name prefix.
+struct ModelSpec {
+    prefix: &'static str,
+    context_window: i64,
+    vision: bool,
+    tools: bool,
+    /// USD per million input and output tokens, when published
+    pricing: Option<(f64, f64)>,
+}
+
+const fn spec(
+    prefix: &'static str,
+    context_window: i64,
+    vision: bool,
+    tools: bool,
+    pricing: Option<(f64, f64)>,
+) -> ModelSpec {
+    ModelSpec {
+        prefix,
+        context_window,
+        vision,
+        tools,
+        pricing,
+    }
+}
+
+// Columns: prefix, context window, vision, tools, pricing
+#[rustfmt::skip]
+const REGISTRY: &[ModelSpec] = &[
+    spec("gpt-3.5-turbo",     16_385,     false, true, Some((0.50, 1.50))),
+    spec("gpt-4",             8_192,      false, true, Some((30.00, 60.00))),
+    spec("gpt-4-turbo",       128_000,    true,  true, Some((10.00, 30.00))),
+    spec("gpt-4o",            128_000,    true,  true, Some((2.50, 10.00))),
+    spec("gpt-4o-mini",       128_000,    true,  true, Some((0.15, 0.60))),
+    spec("gpt-4.1",           1_047_576,  true,  true, Some((2.00, 8.00))),
+    spec("gpt-4.1-mini",      1_047_576,  true,  true, Some((0.40, 1.60))),
+    spec("gpt-4.1-nano",      1_047_576,  true,  true, Some((0.10, 0.40))),
+    spec("o1",                200_000,    true,  true, Some((15.00, 60.00))),
+    spec("o3",                200_000,    true,  true, Some((2.00, 8.00))),
+    spec("o3-mini",           200_000,    false, true, Some((1.10, 4.40))),
+    spec("o4-mini",           200_000,    true,  true, Some((1.10, 4.40))),
+    spec("claude",            200_000,    true,  true, None),
+    spec("claude-3-haiku",    200_000,    true,  true, Some((0.25, 1.25))),
+    spec("claude-3-5-haiku",  200_000,    false, true, Some((0.80, 4.00))),
+    spec("claude-haiku-4",    200_000,    true,  true, Some((1.00, 5.00))),
+    spec("claude-3-5-sonnet", 200_000,    true,  true, Some((3.00, 15.00))),
+    spec("claude-3-7-sonnet", 200_000,    true,  true, Some((3.00, 15.00))),
+    spec("claude-sonnet-4",   200_000,    true,  true, Some((3.00, 15.00))),
+    spec("claude-opus-4",     200_000,    true,  true, Some((15.00, 75.00))),
+    spec("deepseek",          64_000,     false, true, None),
+    spec("qwen",              32_768,     false, true, None),
+];
+
+/// Capabilities of one model, as returned by `get_model_info`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCapabilities {
+    model: String,
+    /// Registry entry the model matched; `None` means the defaults below are guesses
+    matched: Option<String>,
+    pub(crate) context_window: i64,
+    /// Accepts image input
+    vision: bool,
+    /// Supports tool calling; assumed for unknown models since the agent needs it
+    tools: bool,
+    pub(crate) input_price_per_million: Option<f64>,
+    pub(crate) output_price_per_million: Option<f64>,
+}
+
+/// Looks `model` up by its name without any `vendor/` prefix, ignoring case.
+pub(crate) fn lookup(model: &str) -> ModelCapabilities {
+    let name = model.trim().to_lowercase();
+    let name = name.rsplit('/').next().unwrap_or(&name);
+    let spec = REGISTRY
+        .iter()
+        .filter(|spec| name.starts_with(spec.prefix))
+        .max_by_key(|spec| spec.prefix.len());
+    ModelCapabilities {
+        model: model.to_string(),
+        matched: spec.map(|s| s.prefix.to_string()),
+        context_window: spec.map_or(DEFAULT_CONTEXT_WINDOW, |s| s.context_window),
+        vision: spec.is_some_and(|s| s.vision),
+        tools: spec.is_none_or(|s| s.tools),
+        input_price_per_million: spec.and_then(|s| s.pricing).map(|(input, _)| input),
+        output_price_per_million: spec.and_then(|s| s.pricing).map(|(_, output)| output),
+    }
+}
+
+#[tauri::command]
+pub fn get_model_info(model: String) -> ModelCapabilities {
+    lookup(&model)
+}
@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::clock::now_millis;
+use crate::dry_run::{self, DryRunTranscript};
+use crate::file_history;
+use crate::workspace::{resolve_workspace_path, workspace_root};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Write,
+    Delete,
+}
+
+/// One proposed file edit, as sent by the agent.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedChange {
+    path: String,
+    action: ChangeAction,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Pending,
+    Applied,
+    Failed,
+    RolledBack,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    path: String,
+    action: ChangeAction,
+    #[serde(skip)]
+    content: Option<String>,
+    /// Hash of the file when the change was proposed; `None` if it didn't exist
+    #[serde(skip)]
+    base_hash: Option<u64>,
+    status: FileStatus,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Changeset {
+    id: String,
+    workspace: String,
+    title: String,
+    request_id: Option<String>,
+    created_at: i64,
+    files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangesetSummary {
+    id: String,
+    applied: usize,
+    failed: usize,
+    rolled_back: usize,
+    skipped: usize,
+    /// The first failure, which caused everything else to be rolled back
+    error: Option<String>,
+    files: Vec<FileChange>,
+}
+
+/// Changesets proposed during this session, by id.
+#[derive(Default)]
+pub(crate) struct Changesets(Mutex<HashMap<String, Changeset>>);
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(crate) fn current_hash(path: &Path) -> Result<Option<u64>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(hash_bytes(&bytes))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("Failed to read {}: {}", path.display(), err)),
+    }
+}
+
+/// Writes via a sibling temp file and rename so a failed write never leaves a half file.
+/// A file being replaced keeps its permissions, so scripts stay executable.
+pub(crate) fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.changeset-tmp", file_name));
+    std::fs::write(&tmp, content)?;
+    let replaced = match std::fs::metadata(path) {
+        Ok(metadata) => std::fs::set_permissions(&tmp, metadata.permissions()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    };
+    replaced
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+}
+
+fn apply_one(path: &Path, change: &FileChange) -> Result<(), String> {
+    if current_hash(path)? != change.base_hash {
+        return Err("File changed since the edit was proposed".to_string());
+    }
+    match change.action {
+        ChangeAction::Write => {
+            let content = change.content.as_deref().unwrap_or_default();
+            write_atomically(path, content.as_bytes()).map_err(|e| e.to_string())
+        }
+        ChangeAction::Delete => match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.to_string()),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Puts a file back the way it was before `apply_one`: original bytes, or absent.
+pub(crate) fn restore(path: &Path, original: Option<&[u8]>) -> std::io::Result<()> {
+    match original {
+        Some(bytes) => write_atomically(path, bytes),
+        None => match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Files that haven't been applied or skipped; a rolled-back or failed file can be retried.
+fn is_open(status: FileStatus) -> bool {
+    !matches!(status, FileStatus::Applied | FileStatus::Skipped)
+}
+
+/// Applies the selected open files all-or-nothing: on the first failure every file
+/// already written is restored from its snapshot.
+fn apply(
+    app: &tauri::AppHandle,
+    changeset: &mut Changeset,
+    only: Option<&[String]>,
+) -> Result<ChangesetSummary, String> {
+    let root = workspace_root(&changeset.workspace)?;
+    let selected = |path: &str| only.is_none_or(|files| files.iter().any(|f| f == path));
+
+    // Resolve every target before touching anything
+    let targets = changeset
+        .files
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| is_open(change.status) && selected(&change.path))
+        .map(|(index, change)| Ok((index, resolve_workspace_path(&root, &change.path)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut written: Vec<(usize, PathBuf, Option<Vec<u8>>)> = Vec::new();
+    let mut error = None;
+    for (index, path) in targets {
+        let change = &mut changeset.files[index];
+        let original = std::fs::read(&path).ok();
+        let applied =
+            file_history::snapshot(app, &path, "changeset").and_then(|_| apply_one(&path, change));
+        match applied {
+            Ok(()) => {
+                change.status = FileStatus::Applied;
+                change.error = None;
+                written.push((index, path, original));
+            }
+            Err(err) => {
+                change.status = FileStatus::Failed;
+                change.error = Some(err.clone());
+                error = Some(format!("{}: {}", change.path, err));
+                break;
+            }
+        }
+    }
+
+    if error.is_some() {
+        for (index, path, original) in written.into_iter().rev() {
+            let change = &mut changeset.files[index];
+            match restore(&path, original.as_deref()) {
+                Ok(()) => change.status = FileStatus::RolledBack,
+                Err(err) => change.error = Some(format!("Rollback failed: {}", err)),
+            }
+        }
+    } else if only.is_some() {
+        // Files left out of a partial apply are not offered again
+        for change in &mut changeset.files {
+            if is_open(change.status) && !selected(&change.path) {
+                change.status = FileStatus::Skipped;
+            }
+        }
+    }
+
+    let count = |status| {
+        changeset
+            .files
+            .iter()
+            .filter(|f| f.status == status)
+            .count()
+    };
+    Ok(ChangesetSummary {
+        id: changeset.id.clone(),
+        applied: count(FileStatus::Applied),
+        failed: count(FileStatus::Failed),
+        rolled_back: count(FileStatus::RolledBack),
+        skipped: count(FileStatus::Skipped),
+        error,
+        files: changeset.files.clone(),
+    })
+}
+
+fn apply_and_emit(
+    app: &tauri::AppHandle,
+    id: &str,
+    only: Option<&[String]>,
+) -> Result<ChangesetSummary, String> {
+    let summary = {
+        let changesets = app.state::<Changesets>();
+        let mut changesets = changesets.0.lock().unwrap();
+        let changeset = changesets
+            .get_mut(id)
+            .ok_or_else(|| format!("Changeset not found: {}", id))?;
+        apply(app, changeset, only)?
+    };
+    let _ = app.emit("changeset:applied", summary.clone());
+    Ok(summary)
+}
+
+/// Groups proposed edits into a changeset, snapshotting each file's current state so
+/// applying later can detect files that changed underneath it.
+pub(crate) fn propose(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    title: &str,
+    request_id: Option<&str>,
+    changes: Vec<ProposedChange>,
+) -> Result<Changeset, String> {
+    if changes.is_empty() {
+        return Err("A changeset needs at least one file".to_string());
+    }
+    let root = workspace_root(workspace)?;
+    let mut files: Vec<FileChange> = Vec::with_capacity(changes.len());
+    for change in changes {
+        if change.action == ChangeAction::Write && change.content.is_none() {
+            return Err(format!("Missing content for {}", change.path));
+        }
+        let path = resolve_workspace_path(&root, &change.path)?;
+        let relative = path
+            .strip_prefix(&root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if files.iter().any(|f| f.path == relative) {
+            return Err(format!("Duplicate file in changeset: {}", relative));
+        }
+        files.push(FileChange {
+            path: relative,
+            action: change.action,
+            content: change.content,
+            base_hash: current_hash(&path)?,
+            status: FileStatus::Pending,
+            error: None,
+        });
+    }
+
+    let changeset = Changeset {
+        id: uuid::Uuid::new_v4().to_string(),
+        workspace: root.to_string_lossy().to_string(),
+        title: title.to_string(),
+        request_id: request_id.map(String::from),
+        created_at: now_millis(),
+        files,
+    };
+    app.state::<Changesets>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(changeset.id.clone(), changeset.clone());
+    let _ = app.emit("changeset:proposed", changeset.clone());
+    Ok(changeset)
+}
+
+#[tauri::command]
+pub fn propose_changeset(
+    app: tauri::AppHandle,
+    workspace: String,
+    title: String,
+    changes: Vec<ProposedChange>,
+    request_id: Option<String>,
+) -> Result<Changeset, String> {
+    propose(&app, &workspace, &title, request_id.as_deref(), changes)
+}
+
+#[tauri::command]
+pub fn get_changeset(app: tauri::AppHandle, id: String) -> Result<Changeset, String> {
+    app.state::<Changesets>()
+        .0
+        .lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| format!("Changeset not found: {}", id))
+}
+
+/// Applies every pending file, rolling all of them back if any one fails.
+#[tauri::command]
+pub fn apply_changeset(app: tauri::AppHandle, id: String) -> Result<ChangesetSummary, String> {
+    apply_and_emit(&app, &id, None)
+}
+
+/// Applies only `files` (workspace-relative paths) atomically; the rest are skipped.
+#[tauri::command]
+pub fn apply_partial(
+    app: tauri::AppHandle,
+    id: String,
+    files: Vec<String>,
+) -> Result<ChangesetSummary, String> {
+    if files.is_empty() {
+        return Err("Select at least one file to apply".to_string());
+    }
+    apply_and_emit(&app, &id, Some(&files))
+}
+
+/// Simulates applying the open files (or just `files`) without writing anything.
+#[tauri::command]
+pub fn dry_run_changeset(
+    app: tauri::AppHandle,
+    id: String,
+    files: Option<Vec<String>>,
+) -> Result<DryRunTranscript, String> {
+    let changeset = get_changeset(app, id)?;
+    let root = workspace_root(&changeset.workspace)?;
+    let steps = changeset
+        .files
+        .iter()
+        .filter(|change| is_open(change.status))
+        .filter(|change| {
+            files
+                .as_deref()
+                .is_none_or(|only| only.contains(&change.path))
+        })
+        .map(|change| {
+            let content = match change.action {
+                ChangeAction::Write => Some(change.content.as_deref().unwrap_or_default()),
+                ChangeAction::Delete => None,
+            };
+            dry_run::check_file(&root, &change.path, content, Some(change.base_hash))
+        })
+        .collect();
+    Ok(dry_run::transcript(steps))
+}
+
+#[tauri::command]
+pub fn discard_changeset(app: tauri::AppHandle, id: String) -> bool {
+    app.state::<Changesets>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomically_keeps_the_exec_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new();
+        let script = dir.path().join("run.sh");
+        write_atomically(&script, b"echo one\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        write_atomically(&script, b"echo two\n").unwrap();
+
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(std::fs::read(&script).unwrap(), b"echo two\n");
+    }
+}
@@ -0,0 +1,510 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tauri::Manager;
+
+use crate::app_data::{app_data_path, load_json, save_json};
+use crate::changesets::write_atomically;
+use crate::clock::now_millis;
+use crate::workspace::{
+    relative_path, resolve_workspace_path, walk_all_files, walk_files, workspace_root,
+};
+
+const CHECKPOINTS_DIR: &str = "checkpoints";
+const INDEX_FILE: &str = "index.json";
+const CHECKPOINT_SETTINGS_FILE: &str = "checkpoint_settings.json";
+// Older checkpoints of a workspace are dropped once it has this many
+const MAX_CHECKPOINTS: usize = 20;
+// Workspaces bigger than this aren't checkpointed; restoring would be as slow as copying
+const MAX_CHECKPOINT_BYTES: u64 = 500 * 1024 * 1024;
+const MAX_CHECKPOINT_FILES: usize = 50_000;
+const RESTORE_LABEL: &str = "Before restoring a checkpoint";
+
+/// Serializes checkpoint writes, restores and pruning, which share the blob store.
+#[derive(Default)]
+pub(crate) struct CheckpointLock(Mutex<()>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointSettings {
+    /// Take a checkpoint before every agent run in a workspace
+    #[serde(default = "default_auto")]
+    auto: bool,
+}
+
+fn default_auto() -> bool {
+    true
+}
+
+impl Default for CheckpointSettings {
+    fn default() -> Self {
+        Self {
+            auto: default_auto(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointFile {
+    path: String,
+    /// sha256 of the content, which is stored once under `blobs/`
+    hash: String,
+    size: u64,
+    modified: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    id: String,
+    workspace: String,
+    label: Option<String>,
+    /// The agent run it was taken before, for automatic checkpoints
+    request_id: Option<String>,
+    created_at: i64,
+    file_count: usize,
+    total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    #[serde(flatten)]
+    checkpoint: Checkpoint,
+    files: Vec<CheckpointFile>,
+    /// Whether hidden files were recorded; older checkpoints left them out
+    #[serde(default)]
+    hidden_files: bool,
+}
+
+/// Checkpoints by workspace and how many of them use each blob, so taking, listing and
+/// pruning checkpoints reads only the manifests involved.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointIndex {
+    #[serde(default)]
+    workspaces: HashMap<String, Vec<Checkpoint>>,
+    #[serde(default)]
+    blob_refs: HashMap<String, usize>,
+}
+
+impl CheckpointIndex {
+    fn add(&mut self, manifest: &Manifest) {
+        let checkpoint = &manifest.checkpoint;
+        self.workspaces
+            .entry(checkpoint.workspace.clone())
+            .or_default()
+            .push(checkpoint.clone());
+        for hash in blob_hashes(manifest) {
+            *self.blob_refs.entry(hash.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Releases the manifest's blobs, returning those no checkpoint uses any more.
+    fn release(&mut self, manifest: &Manifest) -> Vec<String> {
+        let mut unused = Vec::new();
+        for hash in blob_hashes(manifest) {
+            let Some(refs) = self.blob_refs.get_mut(hash) else {
+                continue;
+            };
+            *refs -= 1;
+            if *refs == 0 {
+                self.blob_refs.remove(hash);
+                unused.push(hash.to_string());
+            }
+        }
+        unused
+    }
+
+    /// The newest checkpoint of `workspace`.
+    fn latest(&self, workspace: &str) -> Option<&Checkpoint> {
+        self.workspaces
+            .get(workspace)?
+            .iter()
+            .max_by_key(|c| c.created_at)
+    }
+}
+
+fn blob_hashes(manifest: &Manifest) -> HashSet<&str> {
+    manifest.files.iter().map(|f| f.hash.as_str()).collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    checkpoint: Checkpoint,
+    /// Files written back because they had changed or been deleted
+    restored: Vec<String>,
+    /// Files created since the checkpoint, now removed
+    removed: Vec<String>,
+}
+
+fn checkpoints_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_data_path(app, CHECKPOINTS_DIR)?;
+    std::fs::create_dir_all(dir.join("blobs"))
+        .map_err(|e| format!("Failed to create checkpoints folder: {}", e))?;
+    Ok(dir)
+}
+
+fn load_manifests(dir: &Path) -> Vec<Manifest> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "json"))
+        .filter(|entry| entry.file_name() != INDEX_FILE)
+        .filter_map(|entry| {
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            serde_json::from_str::<Manifest>(&content)
+                .inspect_err(|err| {
+                    eprintln!("[checkpoints] skipping {}: {}", entry.path().display(), err)
+                })
+                .ok()
+        })
+        .collect()
+}
+
+fn load_manifest(dir: &Path, id: &str) -> Result<Manifest, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid checkpoint id: {}", id));
+    }
+    let content = std::fs::read_to_string(dir.join(format!("{}.json", id)))
+        .map_err(|_| format!("Checkpoint not found: {}", id))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse checkpoint: {}", e))
+}
+
+/// The index, rebuilt from the manifests when it is missing, e.g. for checkpoints taken
+/// before it existed, or unreadable.
+fn load_index(dir: &Path) -> CheckpointIndex {
+    let path = dir.join(INDEX_FILE);
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        match serde_json::from_str(&content) {
+            Ok(index) => return index,
+            Err(err) => eprintln!("[checkpoints] rebuilding {}: {}", path.display(), err),
+        }
+    }
+    let mut index = CheckpointIndex::default();
+    for manifest in load_manifests(dir) {
+        index.add(&manifest);
+    }
+    index
+}
+
+fn save_index(dir: &Path, index: &CheckpointIndex) -> Result<(), String> {
+    let content = serde_json::to_string(index).map_err(|e| e.to_string())?;
+    write_atomically(&dir.join(INDEX_FILE), content.as_bytes())
+        .map_err(|e| format!("Failed to save checkpoint index: {}", e))
+}
+
+fn modified_millis(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// Drops the oldest checkpoints of `workspace` past the limit, along with the blobs only
+/// they referred to.
+fn prune(dir: &Path, index: &mut CheckpointIndex, workspace: &str) {
+    let Some(checkpoints) = index.workspaces.get_mut(workspace) else {
+        return;
+    };
+    checkpoints.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    let dropped = checkpoints.split_off(checkpoints.len().min(MAX_CHECKPOINTS));
+    for checkpoint in dropped {
+        if let Ok(manifest) = load_manifest(dir, &checkpoint.id) {
+            for hash in index.release(&manifest) {
+                let _ = std::fs::remove_file(dir.join("blobs").join(hash));
+            }
+        }
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", checkpoint.id)));
+    }
+}
+
+fn create_locked(
+    dir: &Path,
+    index: &mut CheckpointIndex,
+    workspace: &str,
+    label: Option<String>,
+    request_id: Option<String>,
+) -> Result<Checkpoint, String> {
+    let root = workspace_root(workspace)?;
+    let workspace = root.to_string_lossy().into_owned();
+
+    // Files whose size and mtime match the latest checkpoint keep its hash unread
+    let mut known: HashMap<String, CheckpointFile> = index
+        .latest(&workspace)
+        .and_then(|c| load_manifest(dir, &c.id).ok())
+        .map(|m| m.files.into_iter().map(|f| (f.path.clone(), f)).collect())
+        .unwrap_or_default();
+
+    let mut files = Vec::new();
+    let mut total_bytes = 0;
+    for path in walk_all_files(&root) {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let relative = relative_path(&root, &path);
+        let size = metadata.len();
+        let modified = modified_millis(&metadata);
+        total_bytes += size;
+        if files.len() >= MAX_CHECKPOINT_FILES || total_bytes > MAX_CHECKPOINT_BYTES {
+            return Err(format!(
+                "Workspace is too large to checkpoint (more than {} files or {} MB)",
+                MAX_CHECKPOINT_FILES,
+                MAX_CHECKPOINT_BYTES / 1024 / 1024
+            ));
+        }
+        let hash = match known.remove(&relative) {
+            Some(file) if file.size == size && file.modified == modified => file.hash,
+            _ => {
+                let bytes = std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+                let hash = hex::encode(Sha256::digest(&bytes));
+                let blob = dir.join("blobs").join(&hash);
+                if !blob.exists() {
+                    write_atomically(&blob, &bytes)
+                        .map_err(|e| format!("Failed to save checkpoint: {}", e))?;
+                }
+                hash
+            }
+        };
+        files.push(CheckpointFile {
+            path: relative,
+            hash,
+            size,
+            modified,
+        });
+    }
+
+    let checkpoint = Checkpoint {
+        id: uuid::Uuid::new_v4().to_string(),
+        workspace: workspace.clone(),
+        label: label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty()),
+        request_id,
+        created_at: now_millis(),
+        file_count: files.len(),
+        total_bytes,
+    };
+    let manifest = Manifest {
+        checkpoint: checkpoint.clone(),
+        files,
+        hidden_files: true,
+    };
+    let content = serde_json::to_string(&manifest).map_err(|e| e.to_string())?;
+    write_atomically(
+        &dir.join(format!("{}.json", checkpoint.id)),
+        content.as_bytes(),
+    )
+    .map_err(|e| format!("Failed to save checkpoint: {}", e))?;
+    index.add(&manifest);
+    Ok(checkpoint)
+}
+
+fn create_and_prune(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    label: Option<String>,
+    request_id: Option<String>,
+) -> Result<Checkpoint, String> {
+    let lock = app.state::<CheckpointLock>();
+    let _guard = lock.0.lock().unwrap();
+    let dir = checkpoints_dir(app)?;
+    let mut index = load_index(&dir);
+    let checkpoint = create_locked(&dir, &mut index, workspace, label, request_id)?;
+    prune(&dir, &mut index, &checkpoint.workspace);
+    save_index(&dir, &index)?;
+    Ok(checkpoint)
+}
+
+/// Takes the automatic checkpoint before an agent run in `workspace`, unless turned off.
+/// Failures are logged; they never stop the run.
+pub(crate) async fn before_run(app: &tauri::AppHandle, workspace: &str, request_id: Option<&str>) {
+    let enabled = load_json::<CheckpointSettings>(app, CHECKPOINT_SETTINGS_FILE)
+        .map(|s| s.auto)
+        .unwrap_or(true);
+    if !enabled {
+        return;
+    }
+    let app = app.clone();
+    let workspace = workspace.to_string();
+    let request_id = request_id.map(str::to_string);
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        create_and_prune(&app, &workspace, None, request_id)
+    })
+    .await;
+    match result {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => eprintln!("[checkpoints] automatic checkpoint skipped: {}", err),
+        Err(err) => eprintln!("[checkpoints] checkpoint task failed: {}", err),
+    }
+}
+
+#[tauri::command]
+pub fn get_checkpoint_settings(app: tauri::AppHandle) -> Result<CheckpointSettings, String> {
+    load_json::<CheckpointSettings>(&app, CHECKPOINT_SETTINGS_FILE)
+}
+
+#[tauri::command]
+pub fn set_checkpoint_settings(
+    app: tauri::AppHandle,
+    settings: CheckpointSettings,
+) -> Result<CheckpointSettings, String> {
+    save_json(&app, CHECKPOINT_SETTINGS_FILE, &settings)?;
+    Ok(settings)
+}
+
+/// Records the state of every file in `workspace` that .gitignore doesn't exclude, hidden
+/// ones like `.env` included; only `.git`, `node_modules` and build output folders are left
+/// out. Unchanged content is stored once across checkpoints.
+#[tauri::command]
+pub async fn create_checkpoint(
+    app: tauri::AppHandle,
+    workspace: String,
+    label: Option<String>,
+) -> Result<Checkpoint, String> {
+    tauri::async_runtime::spawn_blocking(move || create_and_prune(&app, &workspace, label, None))
+        .await
+        .map_err(|e| format!("Checkpoint task failed: {}", e))?
+}
+
+/// Checkpoints of `workspace`, newest first.
+#[tauri::command]
+pub fn list_checkpoints(
+    app: tauri::AppHandle,
+    workspace: String,
+) -> Result<Vec<Checkpoint>, String> {
+    let workspace = workspace_root(&workspace)?.to_string_lossy().into_owned();
+    let mut checkpoints = load_index(&checkpoints_dir(&app)?)
+        .workspaces
+        .remove(&workspace)
+        .unwrap_or_default();
+    checkpoints.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    Ok(checkpoints)
+}
+
+/// Puts the workspace back as the checkpoint found it: changed and deleted files are
+/// written back and files created since are removed. Gitignored files are left alone, as
+/// are hidden ones when the checkpoint predates recording them.
+/// A checkpoint of the current state is taken first, so the restore can be undone.
+#[tauri::command]
+pub async fn restore_checkpoint(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<RestoreReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let lock = app.state::<CheckpointLock>();
+        let _guard = lock.0.lock().unwrap();
+        let dir = checkpoints_dir(&app)?;
+        let manifest = load_manifest(&dir, id.trim())?;
+        let root = workspace_root(&manifest.checkpoint.workspace)?;
+        let mut index = load_index(&dir);
+        create_locked(
+            &dir,
+            &mut index,
+            &manifest.checkpoint.workspace,
+            Some(RESTORE_LABEL.to_string()),
+            None,
+        )?;
+        save_index(&dir, &index)?;
+
+        let mut restored = Vec::new();
+        let mut wanted = HashSet::new();
+        for file in &manifest.files {
+            wanted.insert(file.path.as_str());
+            let path = resolve_workspace_path(&root, &file.path)?;
+            let current = std::fs::read(&path).ok();
+            if current
+                .as_deref()
+                .is_some_and(|bytes| hex::encode(Sha256::digest(bytes)) == file.hash)
+            {
+                continue;
+            }
+            let bytes = std::fs::read(dir.join("blobs").join(&file.hash))
+                .map_err(|e| format!("Checkpoint is missing {}: {}", file.path, e))?;
+            write_atomically(&path, &bytes)
+                .map_err(|e| format!("Failed to restore {}: {}", file.path, e))?;
+            restored.push(file.path.clone());
+        }
+
+        let mut removed = Vec::new();
+        let current: Vec<PathBuf> = if manifest.hidden_files {
+            walk_all_files(&root).collect()
+        } else {
+            walk_files(&root).collect()
+        };
+        for path in current {
+            let relative = relative_path(&root, &path);
+            if wanted.contains(relative.as_str()) {
+                continue;
+            }
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", relative, e))?;
+            removed.push(relative);
+        }
+
+        prune(&dir, &mut index, &manifest.checkpoint.workspace);
+        save_index(&dir, &index)?;
+        Ok(RestoreReport {
+            checkpoint: manifest.checkpoint,
+            restored,
+            removed,
+        })
+    })
+    .await
+    .map_err(|e| format!("Checkpoint task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(id: &str, created_at: i64, hashes: &[&str]) -> Manifest {
+        Manifest {
+            checkpoint: Checkpoint {
+                id: id.to_string(),
+                workspace: "/w".to_string(),
+                label: None,
+                request_id: None,
+                created_at,
+                file_count: hashes.len(),
+                total_bytes: 0,
+            },
+            files: hashes
+                .iter()
+                .enumerate()
+                .map(|(n, hash)| CheckpointFile {
+                    path: format!("f{}", n),
+                    hash: hash.to_string(),
+                    size: 0,
+                    modified: 0,
+                })
+                .collect(),
+            hidden_files: true,
+        }
+    }
+
+    #[test]
+    fn index_frees_a_blob_once_no_checkpoint_uses_it() {
+        let mut index = CheckpointIndex::default();
+        let first = manifest("a", 1, &["x", "y", "y"]);
+        let second = manifest("b", 2, &["y", "z"]);
+        index.add(&first);
+        index.add(&second);
+        assert_eq!(index.latest("/w").unwrap().id, "b");
+        assert!(index.latest("/other").is_none());
+
+        assert_eq!(index.release(&first), ["x"]);
+        let mut unused = index.release(&second);
+        unused.sort();
+        assert_eq!(unused, ["y", "z"]);
+        assert!(index.blob_refs.is_empty());
+    }
+}
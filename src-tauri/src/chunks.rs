@@ -0,0 +1,176 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Upper bound for a reassembled result; anything larger is rejected instead of buffered
+const MAX_ASSEMBLED_BYTES: usize = 64 * 1024 * 1024;
+const MAX_CHUNKS: usize = 16 * 1024;
+
+/// One piece of an oversized RPC result, sent by the sidecar as a `result_chunk` event.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ResultChunk {
+    pub id: u64,
+    #[serde(default)]
+    pub session: Option<String>,
+    pub seq: usize,
+    pub total: usize,
+    pub data: String,
+}
+
+pub(crate) enum ChunkOutcome {
+    Pending,
+    Complete(String),
+    Failed(String),
+}
+
+struct PartialResult {
+    parts: Vec<Option<String>>,
+    received: usize,
+    bytes: usize,
+}
+
+#[derive(Default)]
+pub(crate) struct ChunkAssembler {
+    partial: HashMap<u64, PartialResult>,
+}
+
+impl ChunkAssembler {
+    pub fn push(&mut self, chunk: ResultChunk) -> ChunkOutcome {
+        if chunk.total == 0 || chunk.total > MAX_CHUNKS || chunk.seq >= chunk.total {
+            self.partial.remove(&chunk.id);
+            return ChunkOutcome::Failed(format!(
+                "Invalid result chunk {}/{} for request {}",
+                chunk.seq, chunk.total, chunk.id
+            ));
+        }
+
+        let entry = self.partial.entry(chunk.id).or_insert_with(|| PartialResult {
+            parts: vec![None; chunk.total],
+            received: 0,
+            bytes: 0,
+        });
+
+        if entry.parts.len() != chunk.total {
+            self.partial.remove(&chunk.id);
+            return ChunkOutcome::Failed(format!(
+                "Inconsistent chunk count for request {}",
+                chunk.id
+            ));
+        }
+
+        if entry.parts[chunk.seq].is_none() {
+            entry.bytes += chunk.data.len();
+            entry.received += 1;
+            entry.parts[chunk.seq] = Some(chunk.data);
+        }
+
+        if entry.bytes > MAX_ASSEMBLED_BYTES {
+            self.partial.remove(&chunk.id);
+            return ChunkOutcome::Failed(format!(
+                "Response exceeds the {} MB limit",
+                MAX_ASSEMBLED_BYTES / (1024 * 1024)
+            ));
+        }
+
+        if entry.received < entry.parts.len() {
+            return ChunkOutcome::Pending;
+        }
+
+        let entry = self.partial.remove(&chunk.id).expect("entry exists");
+        let mut result = String::with_capacity(entry.bytes);
+        for part in entry.parts.into_iter().flatten() {
+            result.push_str(&part);
+        }
+        ChunkOutcome::Complete(result)
+    }
+
+    pub fn discard(&mut self, id: u64) {
+        self.partial.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: u64, seq: usize, total: usize, data: &str) -> ResultChunk {
+        ResultChunk {
+            id,
+            session: None,
+            seq,
+            total,
+            data: data.to_string(),
+        }
+    }
+
+    fn complete(outcome: ChunkOutcome) -> String {
+        match outcome {
+            ChunkOutcome::Complete(result) => result,
+            ChunkOutcome::Pending => panic!("still pending"),
+            ChunkOutcome::Failed(err) => panic!("failed: {}", err),
+        }
+    }
+
+    #[test]
+    fn reassembles_chunks_in_any_order() {
+        let mut assembler = ChunkAssembler::default();
+        assert!(matches!(assembler.push(chunk(1, 2, 3, "c")), ChunkOutcome::Pending));
+        assert!(matches!(assembler.push(chunk(2, 0, 2, "x")), ChunkOutcome::Pending));
+        assert!(matches!(assembler.push(chunk(1, 0, 3, "a")), ChunkOutcome::Pending));
+        assert_eq!(complete(assembler.push(chunk(1, 1, 3, "b"))), "abc");
+        assert_eq!(complete(assembler.push(chunk(2, 1, 2, "y"))), "xy");
+        assert!(assembler.partial.is_empty());
+    }
+
+    #[test]
+    fn ignores_a_repeated_chunk() {
+        let mut assembler = ChunkAssembler::default();
+        assert!(matches!(assembler.push(chunk(1, 0, 2, "a")), ChunkOutcome::Pending));
+        assert!(matches!(assembler.push(chunk(1, 0, 2, "z")), ChunkOutcome::Pending));
+        assert_eq!(complete(assembler.push(chunk(1, 1, 2, "b"))), "ab");
+    }
+
+    #[test]
+    fn rejects_invalid_counts() {
+        let mut assembler = ChunkAssembler::default();
+        for (seq, total) in [(0, 0), (2, 2), (0, MAX_CHUNKS + 1)] {
+            assert!(matches!(
+                assembler.push(chunk(1, seq, total, "a")),
+                ChunkOutcome::Failed(_)
+            ));
+        }
+        assert!(assembler.partial.is_empty());
+    }
+
+    #[test]
+    fn fails_when_the_total_changes() {
+        let mut assembler = ChunkAssembler::default();
+        assert!(matches!(assembler.push(chunk(1, 0, 3, "a")), ChunkOutcome::Pending));
+        assert!(matches!(
+            assembler.push(chunk(1, 1, 2, "b")),
+            ChunkOutcome::Failed(_)
+        ));
+        // The request starts over rather than mixing the two
+        assert!(matches!(assembler.push(chunk(1, 0, 2, "a")), ChunkOutcome::Pending));
+        assert_eq!(complete(assembler.push(chunk(1, 1, 2, "b"))), "ab");
+    }
+
+    #[test]
+    fn fails_past_the_size_limit() {
+        let mut assembler = ChunkAssembler::default();
+        let half = "x".repeat(MAX_ASSEMBLED_BYTES / 2 + 1);
+        assert!(matches!(assembler.push(chunk(1, 0, 3, &half)), ChunkOutcome::Pending));
+        assert!(matches!(
+            assembler.push(chunk(1, 1, 3, &half)),
+            ChunkOutcome::Failed(_)
+        ));
+        assert!(assembler.partial.is_empty());
+    }
+
+    #[test]
+    fn discard_forgets_a_partial_result() {
+        let mut assembler = ChunkAssembler::default();
+        assert!(matches!(assembler.push(chunk(1, 0, 2, "a")), ChunkOutcome::Pending));
+        assembler.discard(1);
+        assert!(matches!(assembler.push(chunk(1, 1, 2, "b")), ChunkOutcome::Pending));
+    }
+}
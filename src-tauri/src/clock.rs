@@ -0,0 +1,53 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the Unix epoch (UTC).
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Formats epoch milliseconds as an RFC 3339 UTC timestamp, e.g. `2024-05-01T12:30:00Z`.
+pub(crate) fn format_utc(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Parses an RFC 3339 timestamp (`2024-05-01T12:30:00.123Z`, `...+02:00`) into epoch
+/// milliseconds. One without an offset is taken as UTC.
+pub(crate) fn parse_rfc3339(text: &str) -> Option<i64> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .map(|t| t.timestamp_millis())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|t| t.and_utc().timestamp_millis())
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_utc, parse_rfc3339};
+
+    #[test]
+    fn round_trips_rfc3339() {
+        assert_eq!(format_utc(1_714_566_600_123), "2024-05-01T12:30:00Z");
+        assert_eq!(
+            parse_rfc3339("2024-05-01T12:30:00.123Z"),
+            Some(1_714_566_600_123)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-05-01T14:30:00+02:00"),
+            Some(1_714_566_600_000)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-05-01T12:30:00.123456"),
+            Some(1_714_566_600_123)
+        );
+        assert_eq!(parse_rfc3339("yesterday"), None);
+    }
+}
@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+use crate::workspace::{is_binary_file, relative_path, walk_files, workspace_root};
+
+// Number of leading lines searched for the required source header
+const HEADER_SCAN_LINES: usize = 15;
+const MAX_HEADER_FINDINGS: usize = 500;
+
+fn default_allowed() -> Vec<String> {
+    [
+        "MIT",
+        "Apache-2.0",
+        "BSD-2-Clause",
+        "BSD-3-Clause",
+        "ISC",
+        "Zlib",
+        "Unicode-3.0",
+        "Unicode-DFS-2016",
+        "0BSD",
+        "CC0-1.0",
+        "Unlicense",
+        "MPL-2.0",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_denied() -> Vec<String> {
+    [
+        "GPL-2.0", "GPL-3.0", "AGPL-3.0", "LGPL-2.1", "LGPL-3.0", "SSPL-1.0",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_header_extensions() -> Vec<String> {
+    [
+        "rs", "ts", "tsx", "js", "jsx", "py", "go", "java", "swift", "c", "cpp", "h",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CompliancePolicy {
+    allowed_licenses: Vec<String>,
+    denied_licenses: Vec<String>,
+    /// Text every source file must contain near the top, e.g. "SPDX-License-Identifier"
+    required_header: Option<String>,
+    header_extensions: Vec<String>,
+}
+
+impl Default for CompliancePolicy {
+    fn default() -> Self {
+        Self {
+            allowed_licenses: default_allowed(),
+            denied_licenses: default_denied(),
+            required_header: None,
+            header_extensions: default_header_extensions(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyLicense {
+    name: String,
+    version: Option<String>,
+    ecosystem: String,
+    license: Option<String>,
+    status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceFinding {
+    kind: String,
+    severity: String,
+    subject: String,
+    detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComplianceReport {
+    dependencies: Vec<DependencyLicense>,
+    findings: Vec<ComplianceFinding>,
+    files_checked: usize,
+}
+
+fn matches_license(id: &str, list: &[String]) -> bool {
+    let id = id.trim().trim_matches(|c| c == '(' || c == ')');
+    list.iter().any(|entry| {
+        entry.eq_ignore_ascii_case(id)
+            || id
+                .strip_prefix(entry.as_str())
+                .is_some_and(|rest| rest.starts_with('-') || rest.starts_with('+'))
+    })
+}
+
+/// Evaluates an SPDX-ish expression: any `OR` alternative whose `AND` terms are all
+/// allowed is enough.
+fn license_status(license: Option<&str>, policy: &CompliancePolicy) -> &'static str {
+    let Some(expr) = license.map(str::trim).filter(|l| !l.is_empty()) else {
+        return "missing";
+    };
+
+    let normalized = expr.replace('/', " OR ");
+    let alternatives: Vec<Vec<&str>> = normalized
+        .split(" OR ")
+        .map(|alt| alt.split(" AND ").collect())
+        .collect();
+
+    if alternatives.iter().any(|terms| {
+        terms
+            .iter()
+            .all(|t| matches_license(t, &policy.allowed_licenses))
+    }) {
+        return "allowed";
+    }
+    if alternatives.iter().all(|terms| {
+        terms
+            .iter()
+            .any(|t| matches_license(t, &policy.denied_licenses))
+    }) {
+        return "denied";
+    }
+    "unknown"
+}
+
+// (name, version, declared license)
+type PackageLicense = (String, Option<String>, Option<String>);
+
+fn cargo_dependencies(root: &Path) -> Result<Vec<PackageLicense>, String> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .output()
+        .map_err(|e| format!("Failed to run cargo metadata: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let metadata: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Invalid cargo metadata output: {}", e))?;
+    let workspace_members: Vec<&str> = metadata["workspace_members"]
+        .as_array()
+        .map(|m| m.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    Ok(metadata["packages"]
+        .as_array()
+        .map(|packages| {
+            packages
+                .iter()
+                .filter(|p| {
+                    !p["id"]
+                        .as_str()
+                        .is_some_and(|id| workspace_members.contains(&id))
+                })
+                .map(|p| {
+                    (
+                        p["name"].as_str().unwrap_or_default().to_string(),
+                        p["version"].as_str().map(String::from),
+                        p["license"].as_str().map(String::from),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn npm_dependencies(root: &Path) -> Result<Vec<PackageLicense>, String> {
+    let manifest = std::fs::read_to_string(root.join("package.json"))
+        .map_err(|e| format!("Failed to read package.json: {}", e))?;
+    let manifest: serde_json::Value =
+        serde_json::from_str(&manifest).map_err(|e| format!("Invalid package.json: {}", e))?;
+
+    let mut deps = Vec::new();
+    for section in ["dependencies", "devDependencies"] {
+        let Some(entries) = manifest[section].as_object() else {
+            continue;
+        };
+        for name in entries.keys() {
+            // Installed packages carry the authoritative license; fall back to unknown otherwise
+            let installed =
+                std::fs::read_to_string(root.join("node_modules").join(name).join("package.json"))
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+            let version = installed
+                .as_ref()
+                .and_then(|p| p["version"].as_str().map(String::from))
+                .or_else(|| entries[name].as_str().map(String::from));
+            let license = installed.as_ref().and_then(|p| match &p["license"] {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Object(o) => {
+                    o.get("type").and_then(|t| t.as_str()).map(String::from)
+                }
+                _ => None,
+            });
+            deps.push((name.clone(), version, license));
+        }
+    }
+    Ok(deps)
+}
+
+fn has_header(path: &Path, header: &str) -> bool {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return true;
+    };
+    let header = header.to_lowercase();
+    content
+        .lines()
+        .take(HEADER_SCAN_LINES)
+        .any(|line| line.to_lowercase().contains(&header))
+}
+
+pub(crate) fn run_compliance_check(
+    workspace: &str,
+    policy: &CompliancePolicy,
+) -> Result<ComplianceReport, String> {
+    let root = workspace_root(workspace)?;
+    let mut dependencies = Vec::new();
+    let mut findings = Vec::new();
+
+    let mut inventories = Vec::new();
+    if root.join("Cargo.toml").is_file() {
+        inventories.push(("cargo", cargo_dependencies(&root)));
+    }
+    if root.join("package.json").is_file() {
+        inventories.push(("npm", npm_dependencies(&root)));
+    }
+
+    for (ecosystem, inventory) in inventories {
+        let packages = match inventory {
+            Ok(packages) => packages,
+            Err(err) => {
+                findings.push(ComplianceFinding {
+                    kind: "inventory_failed".to_string(),
+                    severity: "warning".to_string(),
+                    subject: ecosystem.to_string(),
+                    detail: err,
+                });
+                continue;
+            }
+        };
+
+        for (name, version, license) in packages {
+            let status = license_status(license.as_deref(), policy);
+            let finding = match status {
+                "denied" => Some(("license_denied", "error")),
+                "unknown" => Some(("license_unknown", "warning")),
+                "missing" => Some(("license_missing", "warning")),
+                _ => None,
+            };
+            if let Some((kind, severity)) = finding {
+                findings.push(ComplianceFinding {
+                    kind: kind.to_string(),
+                    severity: severity.to_string(),
+                    subject: format!("{} ({})", name, ecosystem),
+                    detail: license
+                        .clone()
+                        .unwrap_or_else(|| "no license declared".to_string()),
+                });
+            }
+            dependencies.push(DependencyLicense {
+                name,
+                version,
+                ecosystem: ecosystem.to_string(),
+                license,
+                status: status.to_string(),
+            });
+        }
+    }
+
+    let mut files_checked = 0;
+    if let Some(header) = policy
+        .required_header
+        .as_deref()
+        .filter(|h| !h.trim().is_empty())
+    {
+        let mut missing = 0;
+        for path in walk_files(&root) {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_ascii_lowercase());
+            if !ext.is_some_and(|e| {
+                policy
+                    .header_extensions
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(&e))
+            }) {
+                continue;
+            }
+            if is_binary_file(&path) {
+                continue;
+            }
+            files_checked += 1;
+            if !has_header(&path, header) {
+                missing += 1;
+                if missing <= MAX_HEADER_FINDINGS {
+                    findings.push(ComplianceFinding {
+                        kind: "header_missing".to_string(),
+                        severity: "error".to_string(),
+                        subject: relative_path(&root, &path),
+                        detail: format!("Missing required header \"{}\"", header),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(ComplianceReport {
+        dependencies,
+        findings,
+        files_checked,
+    })
+}
+
+#[tauri::command]
+pub async fn check_license_compliance(
+    workspace: String,
+    policy: Option<CompliancePolicy>,
+) -> Result<ComplianceReport, String> {
+    let policy = policy.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || run_compliance_check(&workspace, &policy))
+        .await
+        .map_err(|e| format!("Compliance check failed: {}", e))?
+}
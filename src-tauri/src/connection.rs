@@ -0,0 +1,163 @@
+use serde::Serialize;
+use std::error::Error as _;
+use std::time::Instant;
+
+use crate::models::{self, FetchError};
+use crate::onboarding::{self, OnboardingStep};
+use crate::providers::{self, ProviderKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionErrorKind {
+    /// Missing key or credentials, or a malformed base URL; nothing was sent
+    Config,
+    /// The provider rejected the key (401/403)
+    Auth,
+    /// Nothing answers at this path, usually a wrong base URL
+    NotFound,
+    RateLimited,
+    Timeout,
+    /// Certificate or handshake failure, common behind intercepting proxies
+    Tls,
+    /// DNS, refused connection or other transport failure
+    Network,
+    /// Any other HTTP error status
+    Server,
+    /// The endpoint answered, but not with a model list
+    InvalidResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionError {
+    kind: ConnectionErrorKind,
+    message: String,
+    status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionReport {
+    ok: bool,
+    base_url: String,
+    latency_ms: Option<u64>,
+    model_count: Option<usize>,
+    /// Whether `model` is in the provider's list; `None` when no model was given
+    model_available: Option<bool>,
+    error: Option<ConnectionError>,
+}
+
+fn is_tls(err: &reqwest::Error) -> bool {
+    // reqwest doesn't expose TLS failures as a kind, so look through the source chain
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let text = cause.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|needle| text.contains(needle))
+        {
+            return true;
+        }
+        source = cause.source();
+    }
+    false
+}
+
+fn classify(err: &FetchError) -> ConnectionError {
+    let (kind, status) = match err {
+        FetchError::Request(err) if err.is_timeout() => (ConnectionErrorKind::Timeout, None),
+        FetchError::Request(err) if is_tls(err) => (ConnectionErrorKind::Tls, None),
+        FetchError::Request(_) => (ConnectionErrorKind::Network, None),
+        FetchError::Status(status, _) => {
+            let kind = match status.as_u16() {
+                401 | 403 => ConnectionErrorKind::Auth,
+                404 => ConnectionErrorKind::NotFound,
+                429 => ConnectionErrorKind::RateLimited,
+                _ => ConnectionErrorKind::Server,
+            };
+            (kind, Some(status.as_u16()))
+        }
+        FetchError::Parse(_) => (ConnectionErrorKind::InvalidResponse, None),
+        FetchError::Signing(_) => (ConnectionErrorKind::Config, None),
+    };
+    ConnectionError {
+        kind,
+        message: err.to_string(),
+        status,
+    }
+}
+
+fn config_error(base_url: String, message: String) -> ConnectionReport {
+    ConnectionReport {
+        ok: false,
+        base_url,
+        latency_ms: None,
+        model_count: None,
+        model_available: None,
+        error: Some(ConnectionError {
+            kind: ConnectionErrorKind::Config,
+            message,
+            status: None,
+        }),
+    }
+}
+
+/// Makes one authenticated model list request against the provider. Failures come back
+/// as a report with a typed `error` rather than as a command error. A successful list
+/// also refreshes the `list_models` cache.
+#[tauri::command]
+pub async fn test_connection(
+    app: tauri::AppHandle,
+    provider: Option<String>,
+    base_url: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+) -> ConnectionReport {
+    let kind = ProviderKind::of(provider.as_deref());
+    let base_url = match providers::base_url(&app, provider.as_deref(), base_url.as_deref()) {
+        Ok(url) => url,
+        Err(err) => return config_error(base_url.unwrap_or_default(), err),
+    };
+    let api_key = if kind.needs_key() {
+        match crate::secrets::resolve_api_key(&app, provider.as_deref(), None, api_key) {
+            Ok(key) => Some(key),
+            Err(err) => return config_error(base_url, err),
+        }
+    } else {
+        api_key.filter(|k| !k.trim().is_empty())
+    };
+
+    let started = Instant::now();
+    let result = models::fetch(&app, kind, &base_url, api_key.as_deref()).await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+    match result {
+        Ok(list) => {
+            let model_available = model
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .map(|m| list.iter().any(|info| info.id == m));
+            let report = ConnectionReport {
+                ok: true,
+                base_url: base_url.clone(),
+                latency_ms,
+                model_count: Some(list.len()),
+                model_available,
+                error: None,
+            };
+            if api_key.is_some() {
+                let _ = onboarding::complete_step(&app, OnboardingStep::ApiKey);
+            }
+            let key = models::cache_key(kind, &base_url, api_key.as_deref());
+            models::cache(&app, key, list);
+            report
+        }
+        Err(err) => ConnectionReport {
+            ok: false,
+            base_url,
+            latency_ms,
+            model_count: None,
+            model_available: None,
+            error: Some(classify(&err)),
+        },
+    }
+}
@@ -0,0 +1,243 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::capabilities;
+use crate::providers::ProviderOptions;
+use crate::rpc;
+use crate::usage::estimate_tokens;
+use crate::{ChatMessage, SendMessageParams};
+
+/// Left free for the reply and the sidecar's own system prompt and tool schemas.
+const RESERVED_TOKENS: i64 = 8_192;
+/// Per-message overhead for role markers and separators.
+const MESSAGE_OVERHEAD_TOKENS: i64 = 4;
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+// Clips each message sent for summarizing so the request itself stays well under the limit
+const SUMMARY_MESSAGE_MAX_CHARS: usize = 8_000;
+const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:\n\n";
+
+/// A summary standing in for the first `covered` non-system messages of a conversation.
+struct CachedSummary {
+    covered: usize,
+    /// Hash of the covered messages, so an edited or switched history isn't summarized wrong
+    fingerprint: u64,
+    text: String,
+}
+
+/// Rolling summaries by conversation id, plus the conversations being summarized now.
+#[derive(Default)]
+pub(crate) struct SummaryCache {
+    summaries: Mutex<HashMap<String, CachedSummary>>,
+    pending: Mutex<HashSet<String>>,
+}
+
+/// What was cut to fit the model's context window, emitted as `context:trimmed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrimReport {
+    request_id: Option<String>,
+    conversation_id: Option<String>,
+    context_limit: i64,
+    tokens_before: i64,
+    tokens_after: i64,
+    /// Oldest messages left out entirely
+    dropped_messages: usize,
+    /// Messages replaced by a cached summary
+    summarized_messages: usize,
+    /// Whether a summary of the dropped messages is being generated for next time
+    summary_pending: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryRequest {
+    provider: Option<String>,
+    api_key: String,
+    model: String,
+    base_url: Option<String>,
+    #[serde(flatten)]
+    options: ProviderOptions,
+    messages: Vec<ChatMessage>,
+}
+
+fn message_tokens(message: &ChatMessage) -> i64 {
+    estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS
+}
+
+fn total_tokens(messages: &[ChatMessage]) -> i64 {
+    messages.iter().map(message_tokens).sum()
+}
+
+fn fingerprint(messages: &[ChatMessage]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for message in messages {
+        message.role.hash(&mut hasher);
+        message.content.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Fits `params.messages` into the model's context window, less `reserved` tokens for
+/// content added later. Leading system messages and the latest message are always kept.
+/// Older turns are replaced by the conversation's cached summary when one matches, then
+/// dropped oldest first; dropped turns are summarized in the background for next time.
+pub(crate) fn fit(
+    app: &tauri::AppHandle,
+    params: &mut SendMessageParams,
+    conversation_id: Option<&str>,
+    reserved: i64,
+) -> Option<TrimReport> {
+    let limit = capabilities::lookup(&params.model).context_window;
+    let budget = (limit - RESERVED_TOKENS - reserved).max(limit / 4);
+    let tokens_before = total_tokens(&params.messages);
+    if tokens_before <= budget {
+        return None;
+    }
+
+    let system_len = params
+        .messages
+        .iter()
+        .take_while(|m| m.role == "system")
+        .count();
+    let mut body = params.messages.split_off(system_len);
+    let system_tokens = total_tokens(&params.messages);
+
+    // Swap the covered prefix for its summary when the history still matches it
+    let mut summarized = 0;
+    let mut summary_text = None;
+    if let Some(conversation_id) = conversation_id {
+        let cache = app.state::<SummaryCache>();
+        let summaries = cache.summaries.lock().unwrap();
+        if let Some(cached) = summaries.get(conversation_id) {
+            if cached.covered < body.len()
+                && fingerprint(&body[..cached.covered]) == cached.fingerprint
+            {
+                summarized = cached.covered;
+                summary_text = Some(cached.text.clone());
+            }
+        }
+    }
+    let mut covered: Vec<ChatMessage> = body.drain(..summarized).collect();
+    let mut summary = summary_text.map(|text| ChatMessage {
+        role: "user".to_string(),
+        content: format!("{}{}", SUMMARY_PREFIX, text),
+    });
+
+    let fits = |summary: &Option<ChatMessage>, body: &[ChatMessage]| {
+        system_tokens + summary.as_ref().map_or(0, message_tokens) + total_tokens(body) <= budget
+    };
+    let mut dropped = 0;
+    while body.len() > 1 && !fits(&summary, &body) {
+        covered.push(body.remove(0));
+        dropped += 1;
+    }
+    if !fits(&summary, &body) {
+        // Even the summary is too much next to the latest message
+        summary = None;
+    }
+
+    let summary_pending = dropped > 0 && conversation_id.is_some();
+    if let Some(conversation_id) = conversation_id.filter(|_| summary_pending) {
+        refresh_summary(app, params, conversation_id, covered);
+    }
+    params.messages.extend(summary);
+    params.messages.append(&mut body);
+
+    let report = TrimReport {
+        request_id: params.request_id.clone(),
+        conversation_id: conversation_id.map(String::from),
+        context_limit: limit,
+        tokens_before,
+        tokens_after: total_tokens(&params.messages),
+        dropped_messages: dropped,
+        summarized_messages: summarized,
+        summary_pending,
+    };
+    let _ = app.emit("context:trimmed", report.clone());
+    Some(report)
+}
+
+/// Summarizes `covered` (the non-system prefix now left out) in the background and caches
+/// it, unless a summary for this conversation is already being generated.
+fn refresh_summary(
+    app: &tauri::AppHandle,
+    params: &SendMessageParams,
+    conversation_id: &str,
+    covered: Vec<ChatMessage>,
+) {
+    if !app
+        .state::<SummaryCache>()
+        .pending
+        .lock()
+        .unwrap()
+        .insert(conversation_id.to_string())
+    {
+        return;
+    }
+
+    // The previous summary is folded in, so each summary covers everything before it
+    let input = match app
+        .state::<SummaryCache>()
+        .summaries
+        .lock()
+        .unwrap()
+        .get(conversation_id)
+    {
+        Some(cached)
+            if cached.covered <= covered.len()
+                && fingerprint(&covered[..cached.covered]) == cached.fingerprint =>
+        {
+            let mut input = vec![ChatMessage {
+                role: "user".to_string(),
+                content: format!("{}{}", SUMMARY_PREFIX, cached.text),
+            }];
+            input.extend_from_slice(&covered[cached.covered..]);
+            input
+        }
+        _ => covered.clone(),
+    };
+
+    let request = SummaryRequest {
+        provider: params.provider.clone(),
+        api_key: params.api_key.clone(),
+        model: params.model.clone(),
+        base_url: params.base_url.clone(),
+        options: params.options.clone(),
+        messages: input
+            .into_iter()
+            .map(|m| ChatMessage {
+                role: m.role,
+                content: m.content.chars().take(SUMMARY_MESSAGE_MAX_CHARS).collect(),
+            })
+            .collect(),
+    };
+    let app = app.clone();
+    let conversation_id = conversation_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = rpc::call(&app, "summarizeConversation", &request, SUMMARY_TIMEOUT).await;
+        let cache = app.state::<SummaryCache>();
+        match result {
+            Ok(text) if !text.trim().is_empty() => {
+                cache.summaries.lock().unwrap().insert(
+                    conversation_id.clone(),
+                    CachedSummary {
+                        covered: covered.len(),
+                        fingerprint: fingerprint(&covered),
+                        text: text.trim().to_string(),
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!(
+                "[context] failed to summarize conversation: {}",
+                err.message
+            ),
+        }
+        cache.pending.lock().unwrap().remove(&conversation_id);
+    });
+}
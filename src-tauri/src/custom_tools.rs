@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::permissions::{self, Access};
+use crate::rpc;
+use crate::tasks::{kill, shell};
+use crate::workspace::workspace_root;
+
+const CUSTOM_TOOLS_FILE: &str = "custom_tools.json";
+const MAX_CUSTOM_TOOLS: usize = 50;
+const MAX_NAME_CHARS: usize = 48;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const MAX_TIMEOUT_SECS: u64 = 600;
+// Output past this is cut; it goes back to the model as the tool result
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A tool the user registered, run as a shell command with the arguments as JSON on
+/// stdin and in `OHMYCOWORK_TOOL_ARGS`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomTool {
+    id: String,
+    name: String,
+    description: String,
+    /// JSON Schema of the arguments, an object schema
+    input_schema: Value,
+    command: String,
+    /// Where the command runs; the run's workspace when unset
+    #[serde(default)]
+    cwd: Option<String>,
+    timeout_secs: u64,
+    enabled: bool,
+    added_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CustomToolFile {
+    tools: Vec<CustomTool>,
+}
+
+/// A custom tool as the agent sees it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentCustomTool {
+    /// `custom__<name>`, apart from built-in and MCP tools
+    name: String,
+    tool: String,
+    description: String,
+    input_schema: Value,
+    timeout_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CustomToolRequest {
+    call_id: String,
+    tool: String,
+    #[serde(default)]
+    arguments: Value,
+    #[serde(default)]
+    workspace: Option<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+fn load_tools(app: &tauri::AppHandle) -> Result<Vec<CustomTool>, String> {
+    Ok(load_json::<CustomToolFile>(app, CUSTOM_TOOLS_FILE)?.tools)
+}
+
+fn save_tools(app: &tauri::AppHandle, tools: Vec<CustomTool>) -> Result<(), String> {
+    save_json(app, CUSTOM_TOOLS_FILE, &CustomToolFile { tools })
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!(
+            "Tool names need 1 to {} characters",
+            MAX_NAME_CHARS
+        ));
+    }
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-';
+    if !name.chars().all(allowed) {
+        return Err(format!(
+            "Tool names can only have lowercase letters, digits, '-' and '_': {}",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn validate_schema(schema: Option<Value>) -> Result<Value, String> {
+    let Some(schema) = schema else {
+        return Ok(json!({ "type": "object", "properties": {} }));
+    };
+    match schema.get("type").and_then(|t| t.as_str()) {
+        Some("object") if schema.is_object() => Ok(schema),
+        _ => Err("The input schema must be a JSON Schema with \"type\": \"object\"".to_string()),
+    }
+}
+
+fn read_capped(mut reader: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut out = Vec::new();
+        let _ = reader.by_ref().take(MAX_OUTPUT_BYTES).read_to_end(&mut out);
+        // Drained so a chatty script doesn't block on a full pipe
+        let _ = std::io::copy(&mut reader, &mut std::io::sink());
+        out
+    })
+}
+
+/// Runs the tool's command with `arguments`, returning what it printed. A non-zero exit,
+/// or running past the tool's timeout, is an error carrying the output.
+fn run(tool: &CustomTool, arguments: &Value, cwd: &Path) -> Result<String, String> {
+    let args = arguments.to_string();
+    let mut cmd = shell(&tool.command);
+    cmd.current_dir(cwd)
+        .env("OHMYCOWORK_TOOL_ARGS", &args)
+        .env("OHMYCOWORK_TOOL_NAME", &tool.name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so a timeout ends everything the script started
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", tool.name, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written on its own thread so a script that never reads stdin can't stall this
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(args.as_bytes());
+        });
+    }
+    let stdout = child.stdout.take().map(read_capped);
+    let stderr = child.stderr.take().map(read_capped);
+
+    let deadline = Instant::now() + Duration::from_secs(tool.timeout_secs);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                kill(&mut child);
+                let _ = child.wait();
+                break None;
+            }
+            Err(e) => return Err(format!("Failed to wait for {}: {}", tool.name, e)),
+        }
+    };
+    let collect = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        let bytes = reader.and_then(|r| r.join().ok()).unwrap_or_default();
+        String::from_utf8_lossy(&bytes).trim_end().to_string()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+    let output = match (stdout.is_empty(), stderr.is_empty()) {
+        (_, true) => stdout,
+        (true, false) => stderr,
+        (false, false) => format!("{}\n\n[stderr]\n{}", stdout, stderr),
+    };
+    match status {
+        Some(status) if status.success() => Ok(if output.is_empty() {
+            "(no output)".to_string()
+        } else {
+            output
+        }),
+        Some(status) => Err(format!(
+            "{} exited with {}\n{}",
+            tool.name,
+            status
+                .code()
+                .map_or("a signal".to_string(), |code| format!("code {}", code)),
+            output
+        )),
+        None => Err(format!(
+            "{} timed out after {}s\n{}",
+            tool.name, tool.timeout_secs, output
+        )),
+    }
+}
+
+async fn call(app: &tauri::AppHandle, request: &CustomToolRequest) -> Result<String, String> {
+    permissions::check_mode(
+        app,
+        request.request_id.as_deref(),
+        &request.tool,
+        Access::Execute,
+        &format!("running {}", request.tool),
+    )?;
+    let tool = load_tools(app)?
+        .into_iter()
+        .find(|t| t.enabled && t.name == request.tool)
+        .ok_or_else(|| format!("No enabled custom tool named {}", request.tool))?;
+    let cwd: PathBuf = match (&tool.cwd, request.workspace.as_deref()) {
+        (Some(cwd), _) => PathBuf::from(cwd),
+        (None, Some(workspace)) => workspace_root(workspace)?,
+        (None, None) => return Err(format!("{} needs a workspace to run in", tool.name)),
+    };
+    let arguments = match &request.arguments {
+        Value::Null => json!({}),
+        arguments => arguments.clone(),
+    };
+    tauri::async_runtime::spawn_blocking(move || run(&tool, &arguments, &cwd))
+        .await
+        .map_err(|e| format!("Custom tool failed: {}", e))?
+}
+
+/// The enabled custom tools, for the agent to call back through the host.
+pub(crate) fn agent_tools(app: &tauri::AppHandle) -> Vec<AgentCustomTool> {
+    load_tools(app)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| t.enabled)
+        .map(|t| AgentCustomTool {
+            name: format!("custom__{}", t.name),
+            tool: t.name,
+            description: t.description,
+            input_schema: t.input_schema,
+            timeout_secs: t.timeout_secs,
+        })
+        .collect()
+}
+
+/// `{event:"custom_tool_request", callId, tool, arguments, workspace, requestId}` from the
+/// sidecar: runs the tool's command and answers with `customToolReply`.
+pub(crate) fn handle_request(app: &tauri::AppHandle, value: Value) {
+    let request: CustomToolRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[custom_tools] malformed request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = call(&app, &request).await;
+        rpc::reply_to_sidecar(&app, "customToolReply", &request.call_id, result).await;
+    });
+}
+
+/// Registers a tool the agent can call, backed by `command`, e.g. an internal CLI. The
+/// command runs through the shell with the call's arguments as JSON on stdin and in
+/// the `OHMYCOWORK_TOOL_ARGS` variable; what it prints is the result. Registering an
+/// existing name replaces that tool.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn register_custom_tool(
+    app: tauri::AppHandle,
+    name: String,
+    description: String,
+    input_schema: Option<Value>,
+    command: String,
+    cwd: Option<String>,
+    timeout_secs: Option<u64>,
+    enabled: Option<bool>,
+) -> Result<CustomTool, String> {
+    let name = name.trim().to_string();
+    validate_name(&name)?;
+    let description = description.trim().to_string();
+    if description.is_empty() {
+        return Err("A description is required so the agent knows when to use the tool".into());
+    }
+    let command = command.trim().to_string();
+    if command.is_empty() {
+        return Err("A command is required".to_string());
+    }
+    let input_schema = validate_schema(input_schema)?;
+    let cwd = match cwd.map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) {
+        Some(cwd) if Path::new(&cwd).is_absolute() && Path::new(&cwd).is_dir() => Some(cwd),
+        Some(cwd) => return Err(format!("Expected an absolute folder path: {}", cwd)),
+        None => None,
+    };
+    let timeout_secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+    if timeout_secs == 0 || timeout_secs > MAX_TIMEOUT_SECS {
+        return Err(format!(
+            "The timeout must be between 1 and {} seconds",
+            MAX_TIMEOUT_SECS
+        ));
+    }
+
+    let mut tools = load_tools(&app)?;
+    let existing = tools.iter().position(|t| t.name == name);
+    if existing.is_none() && tools.len() >= MAX_CUSTOM_TOOLS {
+        return Err(format!(
+            "At most {} custom tools can be registered",
+            MAX_CUSTOM_TOOLS
+        ));
+    }
+    let tool = CustomTool {
+        id: existing.map_or_else(|| uuid::Uuid::new_v4().to_string(), |i| tools[i].id.clone()),
+        name,
+        description,
+        input_schema,
+        command,
+        cwd,
+        timeout_secs,
+        enabled: enabled.unwrap_or(true),
+        added_at: existing.map_or_else(now_millis, |i| tools[i].added_at),
+    };
+    match existing {
+        Some(index) => tools[index] = tool.clone(),
+        None => tools.push(tool.clone()),
+    }
+    save_tools(&app, tools)?;
+    Ok(tool)
+}
+
+#[tauri::command]
+pub fn list_custom_tools(app: tauri::AppHandle) -> Result<Vec<CustomTool>, String> {
+    load_tools(&app)
+}
+
+/// Returns `false` when there was no tool by `id`.
+#[tauri::command]
+pub fn remove_custom_tool(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut tools = load_tools(&app)?;
+    let before = tools.len();
+    tools.retain(|t| t.id != id);
+    if tools.len() == before {
+        return Ok(false);
+    }
+    save_tools(&app, tools)?;
+    Ok(true)
+}
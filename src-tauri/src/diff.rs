@@ -0,0 +1,103 @@
+// Line-level LCS is quadratic; past this many line pairs only a summary is shown
+const MAX_DIFF_CELLS: usize = 4_000_000;
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Edit script from `old` to `new` as (op, old index, new index) triples.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] = LCS length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push((Op::Equal, i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions first, matching `diff -u`
+            ops.push((Op::Delete, i, j));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, i, j));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Unified diff between two texts, labelled with `path`. Empty when they're identical.
+pub(crate) fn unified(path: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    if old_lines.len().saturating_mul(new_lines.len()) > MAX_DIFF_CELLS {
+        out.push_str(&format!(
+            "@@ {} lines -> {} lines (too large to diff) @@\n",
+            old_lines.len(),
+            new_lines.len()
+        ));
+        return out;
+    }
+
+    let ops = edit_script(&old_lines, &new_lines);
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _, _))| *op != Op::Equal)
+        .map(|(index, _)| index)
+        .collect();
+
+    // Group changes whose context windows overlap into hunks
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let slice = &ops[start..end];
+        let old_count = slice.iter().filter(|(op, _, _)| *op != Op::Insert).count();
+        let new_count = slice.iter().filter(|(op, _, _)| *op != Op::Delete).count();
+        let (_, old_start, new_start) = slice[0];
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_count > 0),
+            old_count,
+            new_start + usize::from(new_count > 0),
+            new_count
+        ));
+        for (op, i, j) in slice {
+            match op {
+                Op::Equal => out.push_str(&format!(" {}\n", old_lines[*i])),
+                Op::Delete => out.push_str(&format!("-{}\n", old_lines[*i])),
+                Op::Insert => out.push_str(&format!("+{}\n", new_lines[*j])),
+            }
+        }
+    }
+    out
+}
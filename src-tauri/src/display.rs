@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, WindowEvent};
+
+use crate::app_data::{load_json, save_json};
+
+const DISPLAY_FILE: &str = "display.json";
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DisplayFile {
+    /// Zoom level per workspace path, or per window label for windows without one
+    zoom: HashMap<String, f64>,
+}
+
+/// Which persistence key each open window uses, so scale changes can re-apply its zoom.
+#[derive(Default)]
+pub(crate) struct DisplayState(Mutex<HashMap<String, String>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScaleChangedEvent {
+    label: String,
+    scale_factor: f64,
+    zoom: f64,
+}
+
+fn zoom_key(window: &tauri::WebviewWindow, workspace: Option<String>) -> String {
+    workspace
+        .filter(|w| !w.trim().is_empty())
+        .unwrap_or_else(|| window.label().to_string())
+}
+
+fn stored_zoom(app: &tauri::AppHandle, key: &str) -> f64 {
+    load_json::<DisplayFile>(app, DISPLAY_FILE)
+        .ok()
+        .and_then(|file| file.zoom.get(key).copied())
+        .unwrap_or(1.0)
+}
+
+/// Re-applies the saved zoom after a window moves to a monitor with a different DPI.
+pub(crate) fn handle_window_event(window: &tauri::Window, event: &WindowEvent) {
+    let WindowEvent::ScaleFactorChanged { scale_factor, .. } = event else {
+        return;
+    };
+    let app = window.app_handle();
+    let label = window.label().to_string();
+    let key = app
+        .state::<DisplayState>()
+        .0
+        .lock()
+        .unwrap()
+        .get(&label)
+        .cloned()
+        .unwrap_or_else(|| label.clone());
+    let zoom = stored_zoom(app, &key);
+
+    if let Some(webview) = app.get_webview_window(&label) {
+        let _ = webview.set_zoom(zoom);
+    }
+    let _ = app.emit(
+        "display:scale_changed",
+        ScaleChangedEvent {
+            label,
+            scale_factor: *scale_factor,
+            zoom,
+        },
+    );
+}
+
+/// Applies and returns the saved zoom for the calling window; call when a workspace opens.
+#[tauri::command]
+pub fn restore_window_zoom(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    workspace: Option<String>,
+) -> Result<f64, String> {
+    let key = zoom_key(&window, workspace);
+    let zoom = stored_zoom(&app, &key);
+    app.state::<DisplayState>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string(), key);
+    window
+        .set_zoom(zoom)
+        .map_err(|e| format!("Failed to set zoom: {}", e))?;
+    Ok(zoom)
+}
+
+#[tauri::command]
+pub fn set_window_zoom(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    zoom: f64,
+    workspace: Option<String>,
+) -> Result<f64, String> {
+    if !zoom.is_finite() {
+        return Err("Zoom must be a finite number".to_string());
+    }
+    let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    let key = zoom_key(&window, workspace);
+    window
+        .set_zoom(zoom)
+        .map_err(|e| format!("Failed to set zoom: {}", e))?;
+
+    let mut file = load_json::<DisplayFile>(&app, DISPLAY_FILE)?;
+    file.zoom.insert(key.clone(), zoom);
+    save_json(&app, DISPLAY_FILE, &file)?;
+    app.state::<DisplayState>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string(), key);
+    Ok(zoom)
+}
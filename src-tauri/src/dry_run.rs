@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::changesets::current_hash;
+use crate::diff;
+use crate::workspace::{resolve_workspace_path, workspace_root};
+
+/// Substrings that make a shell command too destructive to run from an agent plan.
+const BLOCKED_COMMANDS: &[&str] = &[
+    "rm -rf /",
+    "rm -rf ~",
+    "rm -fr /",
+    "mkfs",
+    "dd if=",
+    ":(){",
+    "chmod -r 777 /",
+    "shutdown",
+    "reboot",
+    "> /dev/sd",
+];
+/// Substrings that are allowed but worth calling out before approval.
+const RISKY_COMMANDS: &[&str] = &[
+    "sudo ",
+    "rm -rf",
+    "rm -r",
+    "git push --force",
+    "git push -f",
+    "git reset --hard",
+    "curl ",
+    "wget ",
+    "| sh",
+    "| bash",
+    "chmod ",
+    "chown ",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Ok,
+    Warning,
+    Blocked,
+}
+
+/// What one operation would do, and why it would or wouldn't.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunStep {
+    action: &'static str,
+    target: String,
+    verdict: Verdict,
+    message: String,
+    diff: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunTranscript {
+    steps: Vec<DryRunStep>,
+    /// False when any step is blocked
+    would_succeed: bool,
+    warnings: usize,
+}
+
+/// One operation of an agent plan to simulate.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PlanStep {
+    WriteFile { path: String, content: String },
+    DeleteFile { path: String },
+    RunCommand { command: String },
+}
+
+pub(crate) fn transcript(steps: Vec<DryRunStep>) -> DryRunTranscript {
+    DryRunTranscript {
+        would_succeed: steps.iter().all(|s| s.verdict != Verdict::Blocked),
+        warnings: steps
+            .iter()
+            .filter(|s| s.verdict == Verdict::Warning)
+            .count(),
+        steps,
+    }
+}
+
+fn blocked(action: &'static str, target: &str, message: String) -> DryRunStep {
+    DryRunStep {
+        action,
+        target: target.to_string(),
+        verdict: Verdict::Blocked,
+        message,
+        diff: None,
+    }
+}
+
+/// Nearest existing ancestor, which decides whether a new file could be created.
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().skip(1).find(|p| p.exists())
+}
+
+fn is_read_only(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .map(|m| m.permissions().readonly())
+        .unwrap_or(false)
+}
+
+/// Simulates writing (`content`) or deleting (`None`) a workspace file. `base_hash`, when
+/// given, is the state the change was proposed against and flags conflicting edits.
+pub(crate) fn check_file(
+    root: &Path,
+    relative: &str,
+    content: Option<&str>,
+    base_hash: Option<Option<u64>>,
+) -> DryRunStep {
+    let action = if content.is_some() { "write" } else { "delete" };
+    let path = match resolve_workspace_path(root, relative) {
+        Ok(path) => path,
+        Err(err) => return blocked(action, relative, err),
+    };
+    let current = match current_hash(&path) {
+        Ok(hash) => hash,
+        Err(err) => return blocked(action, relative, err),
+    };
+    if base_hash.is_some_and(|base| base != current) {
+        return blocked(
+            action,
+            relative,
+            "File changed since the edit was proposed".to_string(),
+        );
+    }
+
+    let exists = current.is_some();
+    if exists && is_read_only(&path) {
+        return blocked(action, relative, "File is read-only".to_string());
+    }
+    if !exists && content.is_some() {
+        match existing_ancestor(&path) {
+            Some(dir) if is_read_only(dir) => {
+                return blocked(
+                    action,
+                    relative,
+                    format!("Directory {} is read-only", dir.display()),
+                );
+            }
+            None => return blocked(action, relative, "No parent directory".to_string()),
+            _ => {}
+        }
+    }
+
+    let old = if exists {
+        std::fs::read(&path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    } else {
+        String::new()
+    };
+    let (verdict, message) = match (content, exists) {
+        (Some(new), true) if new == old => (Verdict::Warning, "No changes".to_string()),
+        (Some(new), true) => (
+            Verdict::Ok,
+            format!("Would overwrite ({} -> {} bytes)", old.len(), new.len()),
+        ),
+        (Some(new), false) => (Verdict::Ok, format!("Would create ({} bytes)", new.len())),
+        (None, true) => (Verdict::Ok, "Would delete".to_string()),
+        (None, false) => (Verdict::Warning, "Already absent".to_string()),
+    };
+    let diff = diff::unified(relative, &old, content.unwrap_or_default());
+    DryRunStep {
+        action,
+        target: relative.to_string(),
+        verdict,
+        message,
+        diff: (!diff.is_empty()).then_some(diff),
+    }
+}
+
+/// Command policy: blocks obviously destructive commands and flags risky ones. Every
+/// shell command still needs the user's approval when the plan actually runs.
+pub(crate) fn check_command(command: &str) -> DryRunStep {
+    let normalized = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lowered = normalized.to_lowercase();
+    let step = |verdict, message: String| DryRunStep {
+        action: "run",
+        target: normalized.clone(),
+        verdict,
+        message,
+        diff: None,
+    };
+
+    if lowered.is_empty() {
+        return step(Verdict::Blocked, "Empty command".to_string());
+    }
+    if let Some(pattern) = BLOCKED_COMMANDS.iter().find(|p| lowered.contains(*p)) {
+        return step(
+            Verdict::Blocked,
+            format!("Blocked by command policy ('{}')", pattern),
+        );
+    }
+    match RISKY_COMMANDS.iter().find(|p| lowered.contains(*p)) {
+        Some(pattern) => step(
+            Verdict::Warning,
+            format!("Needs approval; flagged as risky ('{}')", pattern.trim()),
+        ),
+        None => step(Verdict::Ok, "Needs approval before it runs".to_string()),
+    }
+}
+
+/// Simulates an agent plan against the workspace without changing anything.
+#[tauri::command]
+pub fn dry_run_plan(workspace: String, steps: Vec<PlanStep>) -> Result<DryRunTranscript, String> {
+    let root = workspace_root(&workspace)?;
+    let steps = steps
+        .iter()
+        .map(|step| match step {
+            PlanStep::WriteFile { path, content } => check_file(&root, path, Some(content), None),
+            PlanStep::DeleteFile { path } => check_file(&root, path, None, None),
+            PlanStep::RunCommand { command } => check_command(command),
+        })
+        .collect();
+    Ok(transcript(steps))
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::app_data::{load_json, save_json};
+
+const EDITOR_SETTINGS_FILE: &str = "editor_settings.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorKind {
+    Vscode,
+    Cursor,
+    Jetbrains,
+    Sublime,
+    Zed,
+}
+
+/// Detection order when no editor is configured.
+const EDITORS: [EditorKind; 5] = [
+    EditorKind::Vscode,
+    EditorKind::Cursor,
+    EditorKind::Jetbrains,
+    EditorKind::Sublime,
+    EditorKind::Zed,
+];
+
+impl EditorKind {
+    fn name(self) -> &'static str {
+        match self {
+            EditorKind::Vscode => "VS Code",
+            EditorKind::Cursor => "Cursor",
+            EditorKind::Jetbrains => "A JetBrains IDE",
+            EditorKind::Sublime => "Sublime Text",
+            EditorKind::Zed => "Zed",
+        }
+    }
+
+    /// Command-line launchers, most likely first.
+    fn programs(self) -> &'static [&'static str] {
+        match self {
+            EditorKind::Vscode => &["code"],
+            EditorKind::Cursor => &["cursor"],
+            EditorKind::Jetbrains => &[
+                "idea",
+                "webstorm",
+                "pycharm",
+                "goland",
+                "clion",
+                "rustrover",
+                "phpstorm",
+            ],
+            EditorKind::Sublime => &["subl"],
+            EditorKind::Zed => &["zed"],
+        }
+    }
+
+    /// Where the macOS app bundles keep their launcher when it isn't on PATH.
+    fn bundled(self) -> &'static [&'static str] {
+        if !cfg!(target_os = "macos") {
+            return &[];
+        }
+        match self {
+            EditorKind::Vscode => {
+                &["/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code"]
+            }
+            EditorKind::Cursor => &["/Applications/Cursor.app/Contents/Resources/app/bin/cursor"],
+            EditorKind::Jetbrains => &[],
+            EditorKind::Sublime => {
+                &["/Applications/Sublime Text.app/Contents/SharedSupport/bin/subl"]
+            }
+            EditorKind::Zed => &["/Applications/Zed.app/Contents/MacOS/cli"],
+        }
+    }
+
+    fn args(self, file: &Path, line: Option<u32>) -> Vec<String> {
+        let file = file.to_string_lossy().into_owned();
+        match (self, line) {
+            (_, None) => vec![file],
+            (EditorKind::Vscode | EditorKind::Cursor, Some(line)) => {
+                vec!["--goto".to_string(), format!("{}:{}", file, line)]
+            }
+            (EditorKind::Jetbrains, Some(line)) => {
+                vec!["--line".to_string(), line.to_string(), file]
+            }
+            (EditorKind::Sublime | EditorKind::Zed, Some(line)) => {
+                vec![format!("{}:{}", file, line)]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorSettings {
+    /// `None` picks the first editor found
+    #[serde(default)]
+    editor: Option<EditorKind>,
+    /// Launcher to run instead of the editor's usual one, e.g. a full path
+    #[serde(default)]
+    command: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedEditor {
+    editor: EditorKind,
+    program: String,
+}
+
+pub(crate) fn find_program(name: &str) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) {
+        &[".exe", ".cmd", ".bat"]
+    } else {
+        &[""]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", name, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn locate(editor: EditorKind) -> Option<PathBuf> {
+    editor
+        .programs()
+        .iter()
+        .find_map(|name| find_program(name))
+        .or_else(|| {
+            editor
+                .bundled()
+                .iter()
+                .map(PathBuf::from)
+                .find(|path| path.is_file())
+        })
+}
+
+fn resolve(settings: &EditorSettings) -> Result<(EditorKind, PathBuf), String> {
+    let command = settings
+        .command
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    match (settings.editor, command) {
+        (Some(editor), Some(command)) => Ok((editor, PathBuf::from(command))),
+        (None, Some(_)) => Err("Choose which editor the custom command runs".to_string()),
+        (Some(editor), None) => locate(editor)
+            .map(|program| (editor, program))
+            .ok_or_else(|| format!("{} was not found on PATH", editor.name())),
+        (None, None) => EDITORS
+            .iter()
+            .find_map(|&editor| locate(editor).map(|program| (editor, program)))
+            .ok_or_else(|| "No supported editor found; configure one in settings".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_editor_settings(app: tauri::AppHandle) -> Result<EditorSettings, String> {
+    load_json::<EditorSettings>(&app, EDITOR_SETTINGS_FILE)
+}
+
+#[tauri::command]
+pub fn set_editor_settings(
+    app: tauri::AppHandle,
+    settings: EditorSettings,
+) -> Result<EditorSettings, String> {
+    save_json(&app, EDITOR_SETTINGS_FILE, &settings)?;
+    Ok(settings)
+}
+
+/// The supported editors installed on this machine, in detection order.
+#[tauri::command]
+pub fn detect_editors() -> Vec<DetectedEditor> {
+    EDITORS
+        .iter()
+        .filter_map(|&editor| {
+            locate(editor).map(|program| DetectedEditor {
+                editor,
+                program: program.to_string_lossy().into_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Opens `path` (absolute) in the configured editor, or the first one found, at `line`
+/// (1-based) when given.
+#[tauri::command]
+pub fn open_in_editor(
+    app: tauri::AppHandle,
+    path: String,
+    line: Option<u32>,
+) -> Result<DetectedEditor, String> {
+    let file = Path::new(path.trim());
+    if !file.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path));
+    }
+    if !file.exists() {
+        return Err(format!("File not found: {}", path));
+    }
+    let settings = load_json::<EditorSettings>(&app, EDITOR_SETTINGS_FILE)?;
+    let (editor, program) = resolve(&settings)?;
+    let mut child = Command::new(&program)
+        .args(editor.args(file, line.filter(|l| *l > 0)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", program.display(), e))?;
+    // Some launchers stay up as long as the editor does; reap them off the command thread
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(DetectedEditor {
+        editor,
+        program: program.to_string_lossy().into_owned(),
+    })
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use crate::profiles;
+use crate::providers::{self, ProviderKind};
+use crate::proxy;
+use crate::secrets;
+use crate::vertex;
+
+const MAX_TEXTS: usize = 512;
+const MAX_TEXT_CHARS: usize = 32_000;
+// Providers cap inputs per request; larger calls are split into batches this size
+const BATCH_SIZE: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_ERROR_CHARS: usize = 300;
+
+/// Used when neither the call nor the profile names an embedding model.
+const DEFAULT_EMBEDDING_MODELS: &[(&str, &str)] = &[
+    ("openai", "text-embedding-3-small"),
+    ("ollama", "nomic-embed-text"),
+    ("vertex", "google/text-embedding-005"),
+];
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingUsage {
+    #[serde(default)]
+    prompt_tokens: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: Option<EmbeddingUsage>,
+}
+
+/// Vectors for `embed_texts`, in the order of the texts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Embeddings {
+    pub(crate) model: String,
+    pub(crate) dimensions: usize,
+    pub(crate) vectors: Vec<Vec<f32>>,
+    /// Input tokens, when the provider reports them
+    prompt_tokens: Option<i64>,
+}
+
+fn default_model(provider: Option<&str>) -> Option<String> {
+    let provider = provider.unwrap_or(providers::DEFAULT_PROVIDER);
+    DEFAULT_EMBEDDING_MODELS
+        .iter()
+        .find(|(id, _)| *id == provider)
+        .map(|(_, model)| model.to_string())
+}
+
+/// The requested model, else the provider's default embedding model, else the profile's.
+fn pick_model(
+    model: Option<String>,
+    provider: Option<&str>,
+    profile_model: Option<String>,
+) -> Result<String, String> {
+    model
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .or_else(|| default_model(provider))
+        .or(profile_model)
+        .ok_or_else(|| "Choose an embedding model for this profile".to_string())
+}
+
+/// The model `embed` would use for `profile_id`.
+pub(crate) fn model_for(
+    app: &tauri::AppHandle,
+    profile_id: &str,
+    model: Option<String>,
+) -> Result<String, String> {
+    let target = profiles::apply(app, Some(profile_id), None, None, None)?;
+    pick_model(model, target.provider.as_deref(), target.model)
+}
+
+/// Embeds `texts` with the provider of `profile_id`, through its OpenAI-compatible
+/// `/embeddings` endpoint; local servers such as Ollama and LM Studio work the same way.
+/// `model` falls back to a per-provider default embedding model.
+pub(crate) async fn embed(
+    app: &tauri::AppHandle,
+    profile_id: &str,
+    texts: Vec<String>,
+    model: Option<String>,
+) -> Result<Embeddings, String> {
+    if texts.is_empty() {
+        return Err("Nothing to embed".to_string());
+    }
+    if texts.len() > MAX_TEXTS {
+        return Err(format!(
+            "At most {} texts can be embedded at once",
+            MAX_TEXTS
+        ));
+    }
+    if texts.iter().any(|t| t.chars().count() > MAX_TEXT_CHARS) {
+        return Err(format!(
+            "Texts to embed must be at most {} characters",
+            MAX_TEXT_CHARS
+        ));
+    }
+
+    let target = profiles::apply(app, Some(profile_id), None, None, None)?;
+    let provider = target.provider;
+    if matches!(
+        ProviderKind::of(provider.as_deref()),
+        ProviderKind::Azure | ProviderKind::Bedrock
+    ) {
+        return Err(format!(
+            "Embeddings are not supported for {}",
+            provider.as_deref().unwrap_or_default()
+        ));
+    }
+    let model = pick_model(model, provider.as_deref(), target.model)?;
+    let api_key =
+        secrets::resolve_api_key(app, provider.as_deref(), target.key_ref.as_deref(), None)?;
+    let api_key = vertex::authorize(app, provider.as_deref(), api_key).await?;
+    let base_url = providers::base_url(app, provider.as_deref(), target.base_url.as_deref())?;
+    let proxy = match target.proxy {
+        Some(proxy) => Some(proxy),
+        None => proxy::default_proxy(app)?,
+    };
+    let client = proxy::client(proxy.as_ref());
+
+    let mut vectors = Vec::with_capacity(texts.len());
+    let mut prompt_tokens = None;
+    for batch in texts.chunks(BATCH_SIZE) {
+        let mut request = client
+            .post(format!("{}/embeddings", base_url))
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&api_key)
+            .json(&json!({ "model": model, "input": batch }));
+        for (name, value) in target.headers.iter().flatten() {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Embeddings request failed: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let detail: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(MAX_ERROR_CHARS)
+                .collect();
+            return Err(format!(
+                "Embeddings request failed ({}): {}",
+                status, detail
+            ));
+        }
+        let mut reply: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Unexpected embeddings response: {}", e))?;
+        if reply.data.len() != batch.len() {
+            return Err(format!(
+                "Expected {} embeddings, got {}",
+                batch.len(),
+                reply.data.len()
+            ));
+        }
+        reply.data.sort_by_key(|d| d.index);
+        vectors.extend(reply.data.into_iter().map(|d| d.embedding));
+        if let Some(usage) = reply.usage {
+            *prompt_tokens.get_or_insert(0) += usage.prompt_tokens;
+        }
+    }
+
+    let dimensions = vectors.first().map_or(0, Vec::len);
+    if vectors.iter().any(|v| v.len() != dimensions) {
+        return Err("The provider returned vectors of different sizes".to_string());
+    }
+    Ok(Embeddings {
+        model,
+        dimensions,
+        vectors,
+        prompt_tokens,
+    })
+}
+
+#[tauri::command]
+pub async fn embed_texts(
+    app: tauri::AppHandle,
+    profile_id: String,
+    texts: Vec<String>,
+    model: Option<String>,
+) -> Result<Embeddings, String> {
+    embed(&app, &profile_id, texts, model).await
+}
@@ -0,0 +1,382 @@
+use regex::Regex;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::clock::now_millis;
+use crate::editor::find_program;
+use crate::workspace::workspace_root;
+
+// Installed toolchains rarely change, so probes are reused for this long
+const PROBE_CACHE_MS: i64 = 5 * 60 * 1000;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const MAX_PIN_BYTES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolKind {
+    Runtime,
+    PackageManager,
+}
+
+struct Probe {
+    name: &'static str,
+    kind: ToolKind,
+    /// Tried in order; the first one on `PATH` is run
+    programs: &'static [&'static str],
+    args: &'static [&'static str],
+}
+
+const PROBES: &[Probe] = &[
+    Probe {
+        name: "node",
+        kind: ToolKind::Runtime,
+        programs: &["node"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "python",
+        kind: ToolKind::Runtime,
+        programs: &["python3", "python"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "rust",
+        kind: ToolKind::Runtime,
+        programs: &["rustc"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "go",
+        kind: ToolKind::Runtime,
+        programs: &["go"],
+        args: &["version"],
+    },
+    Probe {
+        name: "npm",
+        kind: ToolKind::PackageManager,
+        programs: &["npm"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "pnpm",
+        kind: ToolKind::PackageManager,
+        programs: &["pnpm"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "yarn",
+        kind: ToolKind::PackageManager,
+        programs: &["yarn"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "bun",
+        kind: ToolKind::PackageManager,
+        programs: &["bun"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "cargo",
+        kind: ToolKind::PackageManager,
+        programs: &["cargo"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "pip",
+        kind: ToolKind::PackageManager,
+        programs: &["pip3", "pip"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "uv",
+        kind: ToolKind::PackageManager,
+        programs: &["uv"],
+        args: &["--version"],
+    },
+    Probe {
+        name: "poetry",
+        kind: ToolKind::PackageManager,
+        programs: &["poetry"],
+        args: &["--version"],
+    },
+];
+
+/// Lockfiles at the workspace root and the package manager each one belongs to.
+const LOCKFILES: &[(&str, &str)] = &[
+    ("package-lock.json", "npm"),
+    ("npm-shrinkwrap.json", "npm"),
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("Cargo.lock", "cargo"),
+    ("uv.lock", "uv"),
+    ("poetry.lock", "poetry"),
+    ("Pipfile.lock", "pipenv"),
+    ("go.sum", "go"),
+    ("Gemfile.lock", "bundler"),
+    ("composer.lock", "composer"),
+];
+
+/// Project manifests at the workspace root and the language each one implies.
+const MANIFESTS: &[(&str, &str)] = &[
+    ("package.json", "node"),
+    ("tsconfig.json", "typescript"),
+    ("Cargo.toml", "rust"),
+    ("pyproject.toml", "python"),
+    ("requirements.txt", "python"),
+    ("setup.py", "python"),
+    ("go.mod", "go"),
+    ("Gemfile", "ruby"),
+    ("composer.json", "php"),
+];
+
+/// Files that pin a toolchain version for the project.
+const VERSION_PINS: &[&str] = &[
+    ".nvmrc",
+    ".node-version",
+    ".python-version",
+    "rust-toolchain",
+    "rust-toolchain.toml",
+    ".tool-versions",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tool {
+    name: String,
+    kind: ToolKind,
+    /// `None` when the program is installed but its version could not be read
+    version: Option<String>,
+    path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFile {
+    /// Relative to the workspace root
+    path: String,
+    /// The package manager, language or toolchain the file is for
+    tool: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionPin {
+    path: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentReport {
+    workspace: String,
+    os: String,
+    /// Only the toolchains found on `PATH`
+    tools: Vec<Tool>,
+    lockfiles: Vec<ProjectFile>,
+    manifests: Vec<ProjectFile>,
+    version_pins: Vec<VersionPin>,
+    detected_at: i64,
+}
+
+/// The last toolchain probe and when it ran.
+#[derive(Default)]
+pub(crate) struct EnvironmentCache(Mutex<Option<(i64, Vec<Tool>)>>);
+
+fn parse_version(output: &str) -> Option<String> {
+    let version = Regex::new(r"\d+(\.\d+)+").unwrap();
+    version.find(output).map(|m| m.as_str().to_string())
+}
+
+/// Runs `program args` and reads a version from what it prints, giving up after a few
+/// seconds so a stuck shim cannot hold up a message.
+fn probe_version(program: &Path, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if started.elapsed() < PROBE_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(20))
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+    // Older Pythons print their version to stderr
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut output);
+    }
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut output);
+    }
+    parse_version(&output)
+}
+
+fn probe_tools() -> Vec<Tool> {
+    std::thread::scope(|scope| {
+        let handles = PROBES
+            .iter()
+            .filter_map(|probe| {
+                let path = probe.programs.iter().find_map(|p| find_program(p))?;
+                Some(scope.spawn(move || Tool {
+                    name: probe.name.to_string(),
+                    kind: probe.kind,
+                    version: probe_version(&path, probe.args),
+                    path: path.to_string_lossy().into_owned(),
+                }))
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+fn tools(app: &tauri::AppHandle, refresh: bool) -> Vec<Tool> {
+    let cache = app.state::<EnvironmentCache>();
+    if !refresh {
+        if let Some((at, tools)) = cache.0.lock().unwrap().as_ref() {
+            if now_millis() - at < PROBE_CACHE_MS {
+                return tools.clone();
+            }
+        }
+    }
+    let tools = probe_tools();
+    *cache.0.lock().unwrap() = Some((now_millis(), tools.clone()));
+    tools
+}
+
+fn project_files(root: &Path, known: &[(&str, &str)]) -> Vec<ProjectFile> {
+    known
+        .iter()
+        .filter(|(name, _)| root.join(name).is_file())
+        .map(|(name, tool)| ProjectFile {
+            path: name.to_string(),
+            tool: tool.to_string(),
+        })
+        .collect()
+}
+
+fn version_pins(root: &Path) -> Vec<VersionPin> {
+    VERSION_PINS
+        .iter()
+        .filter_map(|name| {
+            let content = std::fs::read_to_string(root.join(name)).ok()?;
+            let mut content = content.trim().to_string();
+            if content.len() > MAX_PIN_BYTES {
+                let mut end = MAX_PIN_BYTES;
+                while !content.is_char_boundary(end) {
+                    end -= 1;
+                }
+                content.truncate(end);
+            }
+            Some(VersionPin {
+                path: name.to_string(),
+                content,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn detect(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    refresh: bool,
+) -> Result<EnvironmentReport, String> {
+    let root = workspace_root(workspace)?;
+    Ok(EnvironmentReport {
+        workspace: root.to_string_lossy().into_owned(),
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        tools: tools(app, refresh),
+        lockfiles: project_files(&root, LOCKFILES),
+        manifests: project_files(&root, MANIFESTS),
+        version_pins: version_pins(&root),
+        detected_at: now_millis(),
+    })
+}
+
+/// A Markdown summary of the environment for the agent's system prompt.
+pub(crate) fn context_block(report: &EnvironmentReport) -> String {
+    let mut out = format!("## Development environment\n- OS: {}\n", report.os);
+    let list = |kind: ToolKind| {
+        report
+            .tools
+            .iter()
+            .filter(|t| t.kind == kind)
+            .map(|t| match &t.version {
+                Some(version) => format!("{} {}", t.name, version),
+                None => t.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let runtimes = list(ToolKind::Runtime);
+    let managers = list(ToolKind::PackageManager);
+    out.push_str(&format!(
+        "- Installed runtimes: {}\n",
+        if runtimes.is_empty() {
+            "none found"
+        } else {
+            &runtimes
+        }
+    ));
+    out.push_str(&format!(
+        "- Installed package managers: {}\n",
+        if managers.is_empty() {
+            "none found"
+        } else {
+            &managers
+        }
+    ));
+    if !report.manifests.is_empty() {
+        let names = report.manifests.iter().map(|f| f.path.as_str());
+        out.push_str(&format!(
+            "- Project files: {}\n",
+            names.collect::<Vec<_>>().join(", ")
+        ));
+    }
+    if !report.lockfiles.is_empty() {
+        let locks = report
+            .lockfiles
+            .iter()
+            .map(|f| format!("{} ({})", f.path, f.tool));
+        out.push_str(&format!(
+            "- Lockfiles: {}. Use the package manager they belong to.\n",
+            locks.collect::<Vec<_>>().join(", ")
+        ));
+    }
+    for pin in &report.version_pins {
+        let content = pin.content.lines().collect::<Vec<_>>().join("; ");
+        out.push_str(&format!("- {} pins: {}\n", pin.path, content));
+    }
+    out
+}
+
+/// Which runtimes and package managers are installed, and which lockfiles, manifests and
+/// version pins the workspace has. Toolchain probes are reused for a few minutes unless
+/// `refresh` is set.
+#[tauri::command]
+pub async fn detect_environment(
+    app: tauri::AppHandle,
+    workspace: String,
+    refresh: Option<bool>,
+) -> Result<EnvironmentReport, String> {
+    tauri::async_runtime::spawn_blocking(move || detect(&app, &workspace, refresh.unwrap_or(false)))
+        .await
+        .map_err(|e| format!("Environment detection failed: {}", e))?
+}
@@ -0,0 +1,243 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::clock::format_utc;
+use crate::storage::{Conversation, Storage, StoredMessage};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+fn role_label(role: &str) -> &str {
+    match role {
+        "user" => "You",
+        "assistant" => "Assistant",
+        "system" => "System",
+        "tool" => "Tool call",
+        other => other,
+    }
+}
+
+fn title_of(conversation: &Conversation) -> &str {
+    conversation.title.as_deref().unwrap_or("Conversation")
+}
+
+fn render_markdown(conversation: &Conversation) -> String {
+    let mut out = format!(
+        "# {}\n\n_Last active {} · {} messages_\n",
+        title_of(conversation),
+        format_utc(conversation.updated_at),
+        conversation.messages.len()
+    );
+    for message in &conversation.messages {
+        out.push_str(&format!(
+            "\n## {} · {}\n\n",
+            role_label(&message.role),
+            format_utc(message.created_at)
+        ));
+        if message.role == "tool" {
+            // Tool payloads are usually JSON; keep them verbatim in a fence
+            out.push_str(&format!("```\n{}\n```\n", message.content.trim_end()));
+        } else {
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Renders message text as paragraphs, turning fenced blocks into `<pre><code>`.
+fn render_html_body(content: &str) -> String {
+    let mut out = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<(String, Vec<&str>)> = None;
+
+    let flush_paragraph = |paragraph: &mut Vec<&str>, out: &mut String| {
+        if !paragraph.is_empty() {
+            let lines: Vec<String> = paragraph.iter().map(|l| escape_html(l)).collect();
+            out.push_str(&format!("<p>{}</p>\n", lines.join("<br>")));
+            paragraph.clear();
+        }
+    };
+
+    for line in content.lines() {
+        let fence = line.trim_start().strip_prefix("```");
+        match (&mut code, fence) {
+            (Some((lang, lines)), Some(_)) => {
+                let class = if lang.is_empty() {
+                    String::new()
+                } else {
+                    format!(" class=\"language-{}\"", escape_html(lang))
+                };
+                out.push_str(&format!(
+                    "<pre><code{}>{}</code></pre>\n",
+                    class,
+                    escape_html(&lines.join("\n"))
+                ));
+                code = None;
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, Some(lang)) => {
+                flush_paragraph(&mut paragraph, &mut out);
+                code = Some((lang.trim().to_string(), Vec::new()));
+            }
+            (None, None) if line.trim().is_empty() => flush_paragraph(&mut paragraph, &mut out),
+            (None, None) => paragraph.push(line),
+        }
+    }
+    // An unterminated fence still renders as code
+    if let Some((_, lines)) = code {
+        out.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            escape_html(&lines.join("\n"))
+        ));
+    }
+    flush_paragraph(&mut paragraph, &mut out);
+    out
+}
+
+fn render_html_message(message: &StoredMessage) -> String {
+    let body = if message.role == "tool" {
+        format!(
+            "<pre><code>{}</code></pre>\n",
+            escape_html(message.content.trim_end())
+        )
+    } else {
+        render_html_body(&message.content)
+    };
+    format!(
+        "<section class=\"message {role}\">\n<header><strong>{label}</strong> <time datetime=\"{ts}\">{ts}</time></header>\n{body}</section>\n",
+        role = escape_html(&message.role),
+        label = escape_html(role_label(&message.role)),
+        ts = format_utc(message.created_at),
+        body = body,
+    )
+}
+
+fn render_html(conversation: &Conversation) -> String {
+    let title = escape_html(title_of(conversation));
+    let messages: String = conversation
+        .messages
+        .iter()
+        .map(render_html_message)
+        .collect();
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; color: #1f2328; }}
+.message {{ border-left: 3px solid #d0d7de; padding: 0.25rem 1rem; margin: 1.5rem 0; }}
+.message.user {{ border-color: #0969da; }}
+.message.assistant {{ border-color: #1a7f37; }}
+.message.tool {{ border-color: #9a6700; }}
+header time {{ color: #656d76; font-size: 0.85em; margin-left: 0.5rem; }}
+pre {{ background: #f6f8fa; padding: 0.75rem; overflow-x: auto; border-radius: 6px; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{messages}</body>
+</html>
+"#,
+        title = title,
+        messages = messages,
+    )
+}
+
+fn render(conversation: &Conversation, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(conversation)),
+        ExportFormat::Json => serde_json::to_string_pretty(conversation)
+            .map_err(|e| format!("Failed to serialize conversation: {}", e)),
+        ExportFormat::Html => Ok(render_html(conversation)),
+    }
+}
+
+fn suggested_file_name(conversation: &Conversation, format: ExportFormat) -> String {
+    let stem: String = title_of(conversation)
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let stem = if stem.is_empty() {
+        "conversation".to_string()
+    } else {
+        stem.chars().take(60).collect()
+    };
+    format!("{}.{}", stem, format.extension())
+}
+
+/// Writes a stored conversation where the user picks in a save dialog, which also asks
+/// before replacing an existing file. The webview never names the destination itself.
+/// Returns the written path, or `None` if the user cancelled the dialog.
+#[tauri::command]
+pub async fn export_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    format: ExportFormat,
+) -> Result<Option<String>, String> {
+    let conversation = app
+        .state::<Storage>()
+        .conversation(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let contents = render(&conversation, format)?;
+
+    let file_name = suggested_file_name(&conversation, format);
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title("Export Conversation")
+            .set_file_name(file_name)
+            .add_filter(format.extension(), &[format.extension()])
+            .blocking_save_file()
+    })
+    .await
+    .map_err(|e| format!("Save dialog failed: {}", e))?;
+    let path: PathBuf = match picked {
+        Some(picked) => picked
+            .into_path()
+            .map_err(|e| format!("Invalid save location: {}", e))?,
+        None => return Ok(None),
+    };
+
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
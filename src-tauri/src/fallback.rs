@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::{load_json, save_json};
+use crate::profiles;
+use crate::providers::{self, ProviderOptions};
+use crate::proxy;
+use crate::retry;
+use crate::rpc::{RpcError, ERR_CANCELLED, ERR_OVERSIZED, ERR_TIMEOUT, ERR_TRANSPORT};
+use crate::secrets;
+use crate::vertex;
+
+const FALLBACK_FILE: &str = "fallbacks.json";
+const MAX_FALLBACKS: usize = 5;
+
+/// One step of the chain: a provider profile and the model to ask there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FallbackEntry {
+    profile_id: String,
+    /// The profile's default model when absent
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Providers tried in order when the requested one is unavailable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FallbackChain {
+    #[serde(default)]
+    entries: Vec<FallbackEntry>,
+}
+
+/// A provider that failed before the one that answered.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FailedAttempt {
+    pub(crate) provider: Option<String>,
+    pub(crate) model: String,
+    pub(crate) error: String,
+}
+
+/// Provider settings for a fallback, resolved like a `send_message` with its profile.
+pub(crate) struct FallbackTarget {
+    pub(crate) profile_id: String,
+    pub(crate) provider: Option<String>,
+    pub(crate) api_key: String,
+    pub(crate) model: String,
+    pub(crate) base_url: Option<String>,
+    pub(crate) options: ProviderOptions,
+}
+
+/// Whether another provider might succeed where this one failed. Host-side failures and
+/// cancellations would fail the same way anywhere, and a timeout after text streamed is
+/// returned as a partial reply instead.
+pub(crate) fn is_availability_error(err: &RpcError, streamed: bool) -> bool {
+    match err.code {
+        ERR_CANCELLED | ERR_TRANSPORT | ERR_OVERSIZED => false,
+        ERR_TIMEOUT => !streamed,
+        _ => retry::retryable_class(err).is_some(),
+    }
+}
+
+pub(crate) fn chain(app: &tauri::AppHandle) -> Result<Vec<FallbackEntry>, String> {
+    Ok(load_json::<FallbackChain>(app, FALLBACK_FILE)?.entries)
+}
+
+pub(crate) async fn resolve(
+    app: &tauri::AppHandle,
+    entry: &FallbackEntry,
+) -> Result<FallbackTarget, String> {
+    let target = profiles::apply(
+        app,
+        Some(&entry.profile_id),
+        None,
+        entry.model.clone(),
+        None,
+    )?;
+    let model = target
+        .model
+        .ok_or_else(|| format!("Fallback profile {} has no model", entry.profile_id))?;
+    let provider = target.provider;
+    let api_key =
+        secrets::resolve_api_key(app, provider.as_deref(), target.key_ref.as_deref(), None)?;
+    let api_key = vertex::authorize(app, provider.as_deref(), api_key).await?;
+    let base_url = providers::host_base_url(app, provider.as_deref(), target.base_url)?;
+    let proxy = match target.proxy {
+        Some(proxy) => Some(proxy),
+        None => proxy::default_proxy(app)?,
+    };
+    Ok(FallbackTarget {
+        profile_id: entry.profile_id.clone(),
+        provider,
+        api_key,
+        model,
+        base_url,
+        options: ProviderOptions {
+            headers: target.headers,
+            azure: target.azure,
+            endpoint: target.endpoint,
+            proxy,
+            key_ref: target.key_ref,
+            native: target.native_streaming,
+        },
+    })
+}
+
+#[tauri::command]
+pub fn get_fallback_chain(app: tauri::AppHandle) -> Result<FallbackChain, String> {
+    load_json::<FallbackChain>(&app, FALLBACK_FILE)
+}
+
+/// Replaces the chain. Every entry must name an existing profile; an empty list turns
+/// fallbacks off.
+#[tauri::command]
+pub fn set_fallback_chain(
+    app: tauri::AppHandle,
+    chain: FallbackChain,
+) -> Result<FallbackChain, String> {
+    if chain.entries.len() > MAX_FALLBACKS {
+        return Err(format!("At most {} fallbacks are allowed", MAX_FALLBACKS));
+    }
+    let mut entries = Vec::with_capacity(chain.entries.len());
+    for entry in chain.entries {
+        let profile_id = entry.profile_id.trim().to_string();
+        profiles::find(&app, &profile_id)?;
+        entries.push(FallbackEntry {
+            profile_id,
+            model: entry
+                .model
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty()),
+        });
+    }
+    let chain = FallbackChain { entries };
+    save_json(&app, FALLBACK_FILE, &chain)?;
+    Ok(chain)
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::actions::AUDIT_LOG_FILE;
+use crate::app_data::{app_data_path, load_json, save_json};
+use crate::clock::now_millis;
+use crate::onboarding;
+use crate::proxy;
+use crate::redaction;
+use crate::rpc::{PendingRequests, SidecarGeneration};
+
+const FEEDBACK_CONFIG_FILE: &str = "feedback.json";
+const FEEDBACK_DIR: &str = "feedback";
+const MAX_AUDIT_LINES: usize = 50;
+const MAX_FEEDBACK_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackConfig {
+    /// When set, reports are POSTed here as JSON instead of only being written to disk
+    #[serde(default)]
+    endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackReceipt {
+    id: String,
+    /// "endpoint" or "file"
+    delivered_to: &'static str,
+    /// Endpoint URL or path of the saved report
+    location: String,
+    /// Why delivery to the endpoint failed, if it was attempted
+    endpoint_error: Option<String>,
+}
+
+/// Masks secrets the way text shown to a model is masked and replaces the home directory
+/// with `~`.
+pub(crate) fn sanitize(text: &str, home: Option<&Path>) -> String {
+    let mut text = text.to_string();
+    if let Some(home) = home.and_then(|h| h.to_str()).filter(|h| h.len() > 1) {
+        text = text.replace(home, "~");
+    }
+    redaction::mask_tokens(&text).text
+}
+
+/// Sanitizes every string in `value`. Run on the values themselves, not their JSON, where
+/// backslashes are escaped and a Windows home path would no longer match.
+fn sanitize_value(value: Value, home: Option<&Path>) -> Value {
+    match value {
+        Value::String(text) => Value::String(sanitize(&text, home)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| sanitize_value(item, home))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, field)| (key, sanitize_value(field, home)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn recent_audit_entries(app: &tauri::AppHandle) -> Vec<Value> {
+    let Ok(path) = app_data_path(app, AUDIT_LOG_FILE) else {
+        return Vec::new();
+    };
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(MAX_AUDIT_LINES)..]
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn collect_diagnostics(app: &tauri::AppHandle) -> Value {
+    let generation = app.state::<SidecarGeneration>().0.lock().unwrap().clone();
+    let pending = app.state::<PendingRequests>().lock().unwrap().len();
+
+    json!({
+        "appVersion": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "sidecarGeneration": generation,
+        "pendingRequests": pending,
+        "onboarding": onboarding::get_onboarding_state(app.clone()).ok(),
+        "recentActions": recent_audit_entries(app),
+    })
+}
+
+async fn post_report(app: &tauri::AppHandle, endpoint: &str, report: &Value) -> Result<(), String> {
+    proxy::http_client(app)
+        .post(endpoint)
+        .timeout(Duration::from_secs(15))
+        .json(report)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map(|_| ())
+        .map_err(|e| format!("Failed to send feedback: {}", e))
+}
+
+#[tauri::command]
+pub fn get_feedback_config(app: tauri::AppHandle) -> Result<FeedbackConfig, String> {
+    load_json::<FeedbackConfig>(&app, FEEDBACK_CONFIG_FILE)
+}
+
+#[tauri::command]
+pub fn set_feedback_config(
+    app: tauri::AppHandle,
+    config: FeedbackConfig,
+) -> Result<FeedbackConfig, String> {
+    let mut config = config;
+    config.endpoint = config
+        .endpoint
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    if let Some(endpoint) = &config.endpoint {
+        if !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+            return Err("Feedback endpoint must be an http(s) URL".to_string());
+        }
+    }
+    save_json(&app, FEEDBACK_CONFIG_FILE, &config)?;
+    Ok(config)
+}
+
+/// Packages a feedback message, optionally with sanitized diagnostics, and delivers it
+/// to the configured endpoint. The report is always kept on disk when posting fails.
+#[tauri::command]
+pub async fn submit_feedback(
+    app: tauri::AppHandle,
+    text: String,
+    include_diagnostics: Option<bool>,
+) -> Result<FeedbackReceipt, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Feedback message is empty".to_string());
+    }
+    let text: String = text.chars().take(MAX_FEEDBACK_CHARS).collect();
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let home = app.path().home_dir().ok();
+    let diagnostics = if include_diagnostics.unwrap_or(false) {
+        Some(sanitize_value(collect_diagnostics(&app), home.as_deref()))
+    } else {
+        None
+    };
+    let report = json!({
+        "id": id,
+        "createdAt": now_millis(),
+        "message": sanitize(&text, home.as_deref()),
+        "diagnostics": diagnostics,
+    });
+
+    let config = load_json::<FeedbackConfig>(&app, FEEDBACK_CONFIG_FILE)?;
+    let endpoint_error = match &config.endpoint {
+        Some(endpoint) => match post_report(&app, endpoint, &report).await {
+            Ok(()) => {
+                return Ok(FeedbackReceipt {
+                    id,
+                    delivered_to: "endpoint",
+                    location: endpoint.clone(),
+                    endpoint_error: None,
+                })
+            }
+            Err(err) => Some(err),
+        },
+        None => None,
+    };
+
+    let path = app_data_path(&app, &format!("{}/{}.json", FEEDBACK_DIR, id))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create feedback directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize feedback: {}", e))?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to save feedback: {}", e))?;
+
+    Ok(FeedbackReceipt {
+        id,
+        delivered_to: "file",
+        location: path.to_string_lossy().to_string(),
+        endpoint_error,
+    })
+}
@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::app_data::app_data_path;
+use crate::changesets::restore;
+use crate::clock::now_millis;
+
+const HISTORY_DIR: &str = "file_history";
+// Older versions of a file are dropped once it has this many
+const MAX_VERSIONS: usize = 50;
+// Bigger files aren't snapshotted; overwriting them is refused instead of going unrecorded
+const MAX_SNAPSHOT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Serializes history updates so concurrent writes don't lose each other's index entries.
+#[derive(Default)]
+pub(crate) struct FileHistoryLock(Mutex<()>);
+
+/// A saved earlier state of a file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    version: u32,
+    saved_at: i64,
+    /// `false` when the file didn't exist yet; reverting to it removes the file
+    existed: bool,
+    size: u64,
+    /// What was about to change the file, e.g. `patch` or `changeset`
+    reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryIndex {
+    path: String,
+    next_version: u32,
+    versions: Vec<FileVersion>,
+}
+
+/// Each file's history lives in a folder named after a hash of its absolute path.
+fn history_dir(app: &tauri::AppHandle, path: &Path) -> Result<PathBuf, String> {
+    let key = hex::encode(&Sha256::digest(path.to_string_lossy().as_bytes())[..12]);
+    Ok(app_data_path(app, HISTORY_DIR)?.join(key))
+}
+
+fn load_index(dir: &Path) -> Result<HistoryIndex, String> {
+    match std::fs::read_to_string(dir.join("index.json")) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse file history: {}", e)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(HistoryIndex::default()),
+        Err(err) => Err(format!("Failed to read file history: {}", err)),
+    }
+}
+
+fn save_index(dir: &Path, index: &HistoryIndex) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    let tmp = dir.join("index.json.tmp");
+    std::fs::write(&tmp, content)
+        .and_then(|_| std::fs::rename(&tmp, dir.join("index.json")))
+        .map_err(|e| format!("Failed to save file history: {}", e))
+}
+
+fn snapshot_locked(app: &tauri::AppHandle, path: &Path, reason: &str) -> Result<(), String> {
+    let current = match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() > MAX_SNAPSHOT_BYTES => {
+            return Err(format!(
+                "{} is too large to keep a copy of before changing it",
+                path.display()
+            ))
+        }
+        Ok(_) => Some(
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+    };
+
+    let dir = history_dir(app, path)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create file history: {}", e))?;
+    let mut index = load_index(&dir)?;
+    // Nothing to record if the file is still as the last snapshot left it
+    if let Some(last) = index.versions.last() {
+        let previous = std::fs::read(dir.join(last.version.to_string())).ok();
+        if last.existed == current.is_some() && previous == current {
+            return Ok(());
+        }
+    }
+
+    let version = index.next_version.max(1);
+    if let Some(bytes) = &current {
+        std::fs::write(dir.join(version.to_string()), bytes)
+            .map_err(|e| format!("Failed to save file history: {}", e))?;
+    }
+    index.path = path.to_string_lossy().into_owned();
+    index.next_version = version + 1;
+    index.versions.push(FileVersion {
+        version,
+        saved_at: now_millis(),
+        existed: current.is_some(),
+        size: current.as_ref().map_or(0, |b| b.len() as u64),
+        reason: reason.to_string(),
+    });
+    while index.versions.len() > MAX_VERSIONS {
+        let dropped = index.versions.remove(0);
+        let _ = std::fs::remove_file(dir.join(dropped.version.to_string()));
+    }
+    save_index(&dir, &index)
+}
+
+/// Saves the current content of `path` (or that it doesn't exist) before an agent
+/// changes it. Writers must not go ahead when this fails.
+pub(crate) fn snapshot(app: &tauri::AppHandle, path: &Path, reason: &str) -> Result<(), String> {
+    let lock = app.state::<FileHistoryLock>();
+    let _guard = lock.0.lock().unwrap();
+    snapshot_locked(app, path, reason)
+}
+
+fn absolute(path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path.display()));
+    }
+    // The file may be deleted; its folder still identifies it
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => Ok(parent
+            .canonicalize()
+            .map(|p| p.join(name))
+            .unwrap_or_else(|_| path.to_path_buf())),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+/// Saved versions of `path` (absolute), newest first.
+#[tauri::command]
+pub fn list_file_history(app: tauri::AppHandle, path: String) -> Result<Vec<FileVersion>, String> {
+    let path = absolute(&path)?;
+    let mut versions = load_index(&history_dir(&app, &path)?)?.versions;
+    versions.reverse();
+    Ok(versions)
+}
+
+/// Puts `path` back to a saved version. What it holds now is saved first, so a revert
+/// can itself be undone.
+#[tauri::command]
+pub fn revert_file(
+    app: tauri::AppHandle,
+    path: String,
+    version: u32,
+) -> Result<FileVersion, String> {
+    let path = absolute(&path)?;
+    let lock = app.state::<FileHistoryLock>();
+    let _guard = lock.0.lock().unwrap();
+    let dir = history_dir(&app, &path)?;
+    let target = load_index(&dir)?
+        .versions
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| format!("Version {} not found for {}", version, path.display()))?;
+    let content = if target.existed {
+        Some(
+            std::fs::read(dir.join(version.to_string()))
+                .map_err(|e| format!("Failed to read saved version: {}", e))?,
+        )
+    } else {
+        None
+    };
+    snapshot_locked(&app, &path, "revert")?;
+    restore(&path, content.as_deref())
+        .map_err(|e| format!("Failed to revert {}: {}", path.display(), e))?;
+    Ok(target)
+}
@@ -0,0 +1,204 @@
+use base64::Engine;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// Images up to this size are sent whole; the webview scales them down for display
+const MAX_IMAGE_BYTES: u64 = 2 * 1024 * 1024;
+const MAX_PREVIEW_BYTES: usize = 64 * 1024;
+const MAX_PREVIEW_LINES: usize = 200;
+// Enough for every image header this module reads, and for spotting binary content
+const SNIFF_BYTES: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Image,
+    Binary,
+    Dir,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    path: String,
+    kind: FileKind,
+    size: u64,
+    modified_at: Option<i64>,
+    /// Best guess from the content, then the extension
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime: Option<String>,
+    /// Pixel size, for the formats whose header is understood
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    /// The start of a text file; never set for binaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    /// `data:` URL of an image no bigger than 2 MB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    /// Whether `text` stops before the end of the file
+    truncated: bool,
+}
+
+fn image_mime(head: &[u8], path: &Path) -> Option<&'static str> {
+    let by_content = match head {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'B', b'M', ..] => Some("image/bmp"),
+        [0, 0, 1, 0, ..] => Some("image/x-icon"),
+        _ => None,
+    };
+    by_content.or_else(|| {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        (extension == "svg").then_some("image/svg+xml")
+    })
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Reads the pixel size from the header of a PNG, GIF, BMP, WebP or JPEG.
+fn dimensions(mime: &str, head: &[u8]) -> Option<(u32, u32)> {
+    match mime {
+        "image/png" => Some((be_u32(head, 16)?, be_u32(head, 20)?)),
+        "image/gif" => Some((le_u16(head, 6)?, le_u16(head, 8)?)),
+        // Heights are negative for top-down bitmaps
+        "image/bmp" => Some((le_u32(head, 18)?, (le_u32(head, 22)? as i32).unsigned_abs())),
+        "image/webp" => match head.get(12..16)? {
+            b"VP8X" => Some((
+                (le_u32(head, 24)? & 0xFF_FFFF) + 1,
+                (le_u32(head, 27)? & 0xFF_FFFF) + 1,
+            )),
+            b"VP8 " => Some((le_u16(head, 26)? & 0x3FFF, le_u16(head, 28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = le_u32(head, 21)?;
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            _ => None,
+        },
+        "image/jpeg" => {
+            // Walk the segments up to the start-of-frame marker that holds the size
+            let mut at = 2;
+            loop {
+                if *head.get(at)? != 0xFF {
+                    return None;
+                }
+                let marker = *head.get(at + 1)?;
+                if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                    return Some((be_u16(head, at + 7)?, be_u16(head, at + 5)?));
+                }
+                at += 2 + be_u16(head, at + 2)? as usize;
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The first lines of `bytes`, within the preview limits, and whether anything was cut.
+fn preview_text(bytes: &[u8]) -> (String, bool) {
+    let limited = bytes.len() > MAX_PREVIEW_BYTES;
+    let mut text =
+        String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_PREVIEW_BYTES)]).into_owned();
+    // A character split by the byte limit decodes as a replacement character
+    if limited && text.ends_with('\u{FFFD}') {
+        text.pop();
+    }
+    match text.match_indices('\n').nth(MAX_PREVIEW_LINES - 1) {
+        Some((index, _)) if index + 1 < text.len() => {
+            text.truncate(index + 1);
+            (text, true)
+        }
+        _ => (text, limited),
+    }
+}
+
+/// Classifies `path` (absolute) as text, image or binary and returns what can be shown
+/// of it: the first lines of a text file, or a small image inline. Binary files only get
+/// their metadata.
+#[tauri::command]
+pub fn stat_and_preview(path: String) -> Result<FilePreview, String> {
+    let file = Path::new(path.trim());
+    if !file.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path));
+    }
+    let metadata =
+        std::fs::metadata(file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut preview = FilePreview {
+        path: file.to_string_lossy().into_owned(),
+        kind: FileKind::Dir,
+        size: metadata.len(),
+        modified_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64),
+        mime: None,
+        width: None,
+        height: None,
+        text: None,
+        thumbnail: None,
+        truncated: false,
+    };
+    if metadata.is_dir() {
+        return Ok(preview);
+    }
+
+    let mut head = Vec::with_capacity(MAX_PREVIEW_BYTES.max(SNIFF_BYTES));
+    std::fs::File::open(file)
+        .and_then(|f| {
+            f.take(MAX_PREVIEW_BYTES.max(SNIFF_BYTES) as u64 + 1)
+                .read_to_end(&mut head)
+        })
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+    if let Some(mime) = image_mime(&head, file) {
+        preview.kind = FileKind::Image;
+        preview.mime = Some(mime.to_string());
+        if let Some((width, height)) = dimensions(mime, &head) {
+            preview.width = Some(width);
+            preview.height = Some(height);
+        }
+        if metadata.len() <= MAX_IMAGE_BYTES {
+            let bytes =
+                std::fs::read(file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            preview.thumbnail = Some(format!(
+                "data:{};base64,{}",
+                mime,
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            ));
+        }
+        return Ok(preview);
+    }
+
+    if head[..head.len().min(SNIFF_BYTES)].contains(&0) {
+        preview.kind = FileKind::Binary;
+        preview.mime = Some("application/octet-stream".to_string());
+        return Ok(preview);
+    }
+    preview.kind = FileKind::Text;
+    preview.mime = Some("text/plain".to_string());
+    let (text, truncated) = preview_text(&head);
+    preview.text = Some(text);
+    preview.truncated = truncated;
+    Ok(preview)
+}
@@ -0,0 +1,176 @@
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::workspace::{is_always_skipped, workspace_root, workspace_roots};
+
+const DEFAULT_DEPTH: usize = 3;
+const MAX_DEPTH: usize = 12;
+// Enough for any explorer view; deeper levels load when a folder is expanded
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeNodeKind {
+    File,
+    Dir,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNode {
+    name: String,
+    /// Absolute path
+    path: String,
+    kind: TreeNodeKind,
+    /// File size in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// `None` for files and for directories below the requested depth, which the
+    /// explorer lists on demand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<TreeNode>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceTree {
+    root: String,
+    entries: Vec<TreeNode>,
+    /// Whether entries were left out after `MAX_ENTRIES`
+    truncated: bool,
+}
+
+/// One walked entry before the tree is assembled.
+struct Entry {
+    path: PathBuf,
+    /// Levels below the listed directory, starting at 1
+    depth: usize,
+    is_dir: bool,
+    size: Option<u64>,
+}
+
+fn build(
+    parent: &Path,
+    entries: &mut HashMap<PathBuf, Vec<Entry>>,
+    max_depth: usize,
+) -> Vec<TreeNode> {
+    let mut children = entries.remove(parent).unwrap_or_default();
+    // Folders first, then by name regardless of case
+    children.sort_by_cached_key(|e| {
+        let name = e.path.file_name().unwrap_or_default().to_string_lossy();
+        (!e.is_dir, name.to_lowercase())
+    });
+    children
+        .into_iter()
+        .map(|entry| {
+            let children = (entry.is_dir && entry.depth < max_depth)
+                .then(|| build(&entry.path, entries, max_depth));
+            TreeNode {
+                name: entry
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                path: entry.path.to_string_lossy().into_owned(),
+                kind: if entry.is_dir {
+                    TreeNodeKind::Dir
+                } else {
+                    TreeNodeKind::File
+                },
+                size: entry.size,
+                children,
+            }
+        })
+        .collect()
+}
+
+fn workspace_tree(root: &Path, depth: usize, include_hidden: bool) -> WorkspaceTree {
+    let mut entries: HashMap<PathBuf, Vec<Entry>> = HashMap::new();
+    let mut count = 0;
+    let mut truncated = false;
+    let walker = WalkBuilder::new(root)
+        .max_depth(Some(depth))
+        .hidden(!include_hidden)
+        .git_ignore(true)
+        .require_git(false)
+        .parents(true)
+        .filter_entry(|entry| !is_always_skipped(entry.file_name()))
+        .build();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        if count >= MAX_ENTRIES {
+            truncated = true;
+            break;
+        }
+        count += 1;
+        let depth = entry.depth();
+        let size = file_type
+            .is_file()
+            .then(|| entry.metadata().ok().map(|m| m.len()))
+            .flatten();
+        let path = entry.into_path();
+        let Some(parent) = path.parent().map(Path::to_path_buf) else {
+            continue;
+        };
+        entries.entry(parent).or_default().push(Entry {
+            depth,
+            is_dir: file_type.is_dir(),
+            size,
+            path,
+        });
+    }
+
+    WorkspaceTree {
+        entries: build(root, &mut entries, depth),
+        root: root.to_string_lossy().into_owned(),
+        truncated,
+    }
+}
+
+/// Lists the files and folders under `path`, `depth` levels deep, leaving out what
+/// .gitignore ignores and build output such as node_modules and target. Hidden entries
+/// are left out unless `include_hidden` is set.
+#[tauri::command]
+pub async fn list_workspace_tree(
+    path: String,
+    depth: Option<usize>,
+    include_hidden: Option<bool>,
+) -> Result<WorkspaceTree, String> {
+    let depth = depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let include_hidden = include_hidden.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = workspace_root(&path)?;
+        Ok(workspace_tree(&root, depth, include_hidden))
+    })
+    .await
+    .map_err(|e| format!("Tree task failed: {}", e))?
+}
+
+/// `list_workspace_tree` for each folder of a multi-root workspace, in the given order.
+/// Folders listed twice are returned once.
+#[tauri::command]
+pub async fn list_workspace_trees(
+    paths: Vec<String>,
+    depth: Option<usize>,
+    include_hidden: Option<bool>,
+) -> Result<Vec<WorkspaceTree>, String> {
+    let depth = depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let include_hidden = include_hidden.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        let (primary, extra) = paths.split_first().ok_or("No workspace folders given")?;
+        Ok(workspace_roots(primary, extra)?
+            .iter()
+            .map(|root| workspace_tree(root, depth, include_hidden))
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Tree task failed: {}", e))?
+}
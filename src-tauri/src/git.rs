@@ -0,0 +1,653 @@
+use git2::{
+    Branch, DiffFormat, DiffOptions, Index, IndexAddOption, Repository, Status, StatusOptions,
+};
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+
+use crate::workspace::{git_output, workspace_root};
+
+// Larger diffs are cut off; the UI shows the file list and links out instead
+const MAX_DIFF_BYTES: usize = 2 * 1024 * 1024;
+const MAX_COMMIT_PATHS: usize = 1_000;
+
+/// How a file differs on one side of the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitChange {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    TypeChanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileStatus {
+    /// Relative to the repository root
+    path: String,
+    /// The path before a rename or copy
+    original_path: Option<String>,
+    /// Change staged in the index
+    staged: Option<GitChange>,
+    /// Change in the working tree not yet staged
+    unstaged: Option<GitChange>,
+    untracked: bool,
+    conflicted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// Repository root, which the file paths are relative to
+    root: String,
+    /// `None` on a detached HEAD
+    branch: Option<String>,
+    upstream: Option<String>,
+    ahead: u32,
+    behind: u32,
+    files: Vec<GitFileStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiff {
+    diff: String,
+    /// Whether the diff was cut off at `MAX_DIFF_BYTES`
+    truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommit {
+    hash: String,
+    summary: String,
+}
+
+/// The repository containing `workspace`.
+fn open(workspace: &str) -> Result<Repository, String> {
+    let workspace = workspace_root(workspace)?;
+    let repo = Repository::discover(&workspace)
+        .map_err(|_| format!("Not a git repository: {}", workspace.display()))?;
+    if repo.is_bare() {
+        return Err(format!("Not a git repository: {}", workspace.display()));
+    }
+    Ok(repo)
+}
+
+/// The top-level directory of `repo`'s working tree.
+fn workdir(repo: &Repository) -> PathBuf {
+    let dir = repo.workdir().unwrap_or_else(|| repo.path());
+    dir.components().collect()
+}
+
+/// The repository containing `workspace`, by its top-level directory.
+pub(crate) fn repo_root(workspace: &str) -> Result<PathBuf, String> {
+    open(workspace).map(|repo| workdir(&repo))
+}
+
+fn git_error(err: git2::Error) -> String {
+    err.message().to_string()
+}
+
+/// Checks that `path` stays inside the repository and returns it relative to the root,
+/// with `/` separators. It is matched literally, so names with glob characters aren't
+/// expanded.
+fn repo_path(path: &str) -> Result<String, String> {
+    let path = path.trim().replace('\\', "/");
+    let relative = Path::new(&path);
+    let inside = !path.is_empty()
+        && relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(format!("Path must be inside the repository: {}", path));
+    }
+    Ok(path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Whether `entry` is one of `paths` or inside one of them.
+fn under_any(entry: &str, paths: &[String]) -> bool {
+    paths.iter().any(|path| {
+        entry == path
+            || entry
+                .strip_prefix(path.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+fn staged_change(status: Status) -> Option<GitChange> {
+    if status.is_index_renamed() {
+        Some(GitChange::Renamed)
+    } else if status.is_index_new() {
+        Some(GitChange::Added)
+    } else if status.is_index_deleted() {
+        Some(GitChange::Deleted)
+    } else if status.is_index_typechange() {
+        Some(GitChange::TypeChanged)
+    } else if status.is_index_modified() {
+        Some(GitChange::Modified)
+    } else {
+        None
+    }
+}
+
+fn unstaged_change(status: Status) -> Option<GitChange> {
+    if status.is_wt_renamed() {
+        Some(GitChange::Renamed)
+    } else if status.is_wt_deleted() {
+        Some(GitChange::Deleted)
+    } else if status.is_wt_typechange() {
+        Some(GitChange::TypeChanged)
+    } else if status.is_wt_modified() {
+        Some(GitChange::Modified)
+    } else {
+        None
+    }
+}
+
+fn delta_path(file: git2::DiffFile) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().replace('\\', "/"))
+}
+
+/// Name of the checked-out branch, also before its first commit; `None` when detached.
+fn branch_name(repo: &Repository) -> Option<String> {
+    if repo.head_detached().unwrap_or(false) {
+        return None;
+    }
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+fn status(workspace: &str) -> Result<GitStatus, String> {
+    let repo = open(workspace)?;
+    let mut status = GitStatus {
+        root: workdir(&repo).to_string_lossy().into_owned(),
+        branch: branch_name(&repo),
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        files: Vec::new(),
+    };
+
+    if let Some(head) = repo.head().ok().filter(|head| head.is_branch()) {
+        let local = head.target();
+        if let Ok(upstream) = Branch::wrap(head).upstream() {
+            status.upstream = upstream.name().ok().flatten().map(str::to_string);
+            if let (Some(local), Some(remote)) = (local, upstream.get().target()) {
+                if let Ok((ahead, behind)) = repo.graph_ahead_behind(local, remote) {
+                    status.ahead = ahead as u32;
+                    status.behind = behind as u32;
+                }
+            }
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+    let entries = repo.statuses(Some(&mut options)).map_err(git_error)?;
+    for entry in entries.iter() {
+        let flags = entry.status();
+        if flags.is_ignored() {
+            continue;
+        }
+        let path = String::from_utf8_lossy(entry.path_bytes()).into_owned();
+        let conflicted = flags.is_conflicted();
+        let untracked = flags.is_wt_new() && staged_change(flags).is_none();
+        let (path, original_path) = match entry.head_to_index() {
+            Some(delta) if flags.is_index_renamed() => (
+                delta_path(delta.new_file()).unwrap_or(path),
+                delta_path(delta.old_file()),
+            ),
+            _ => (path, None),
+        };
+        let tracked = !conflicted && !untracked;
+        status.files.push(GitFileStatus {
+            path,
+            original_path,
+            staged: staged_change(flags).filter(|_| tracked),
+            unstaged: unstaged_change(flags).filter(|_| tracked),
+            untracked,
+            conflicted,
+        });
+    }
+    Ok(status)
+}
+
+/// Branch, upstream and changed files of the repository containing `workspace`.
+#[tauri::command]
+pub async fn git_status(workspace: String) -> Result<GitStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || status(&workspace))
+        .await
+        .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+fn diff(workspace: &str, path: Option<&str>, staged: bool) -> Result<GitDiff, String> {
+    let repo = open(workspace)?;
+    let mut options = DiffOptions::new();
+    options.disable_pathspec_match(true);
+    if let Some(path) = path {
+        options.pathspec(repo_path(path)?);
+    }
+    let diff = if staged {
+        // Before the first commit everything staged is new
+        let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+        repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    }
+    .map_err(git_error)?;
+
+    let mut text = Vec::new();
+    let mut truncated = false;
+    let printed = diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin() as u8);
+        }
+        text.extend_from_slice(line.content());
+        truncated = text.len() > MAX_DIFF_BYTES;
+        !truncated
+    });
+    // Stopping early at the limit surfaces as an error from `print`
+    if !truncated {
+        printed.map_err(git_error)?;
+    }
+    let mut diff = String::from_utf8_lossy(&text).into_owned();
+    if truncated {
+        let mut end = MAX_DIFF_BYTES;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+    }
+    Ok(GitDiff { diff, truncated })
+}
+
+/// Unified diff of unstaged changes, or of staged ones with `staged`, limited to `path`
+/// (relative to the repository root) when given.
+#[tauri::command]
+pub async fn git_diff(
+    workspace: String,
+    path: Option<String>,
+    staged: Option<bool>,
+) -> Result<GitDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        diff(&workspace, path.as_deref(), staged.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+/// Stages the current contents of `paths`, including new and deleted files, and returns
+/// the tree to commit: HEAD with only those paths taken from the index, as
+/// `git commit -- <paths>` does.
+fn stage_only(repo: &Repository, paths: &[String]) -> Result<git2::Oid, String> {
+    let root = workdir(repo);
+    let mut index = repo.index().map_err(git_error)?;
+    index
+        .add_all(paths, IndexAddOption::DISABLE_PATHSPEC_MATCH, None)
+        .map_err(git_error)?;
+    // Adding never drops entries, so files deleted from the working tree go here
+    let gone: Vec<String> = index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .filter(|path| under_any(path, paths) && root.join(path).symlink_metadata().is_err())
+        .collect();
+    for path in &gone {
+        index.remove_path(Path::new(path)).map_err(git_error)?;
+    }
+    index.write().map_err(git_error)?;
+
+    let mut only = Index::new().map_err(git_error)?;
+    if let Ok(tree) = repo.head().and_then(|head| head.peel_to_tree()) {
+        only.read_tree(&tree).map_err(git_error)?;
+    }
+    let stale: Vec<String> = only
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).into_owned())
+        .filter(|path| under_any(path, paths))
+        .collect();
+    for path in &stale {
+        only.remove_path(Path::new(path)).map_err(git_error)?;
+    }
+    for entry in index.iter() {
+        if under_any(&String::from_utf8_lossy(&entry.path), paths) {
+            only.add(&entry).map_err(git_error)?;
+        }
+    }
+    only.write_tree_to(repo).map_err(git_error)
+}
+
+fn commit(workspace: &str, message: &str, paths: &[String]) -> Result<GitCommit, String> {
+    let repo = open(workspace)?;
+    let paths = paths
+        .iter()
+        .map(|p| repo_path(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tree = if paths.is_empty() {
+        let mut index = repo.index().map_err(git_error)?;
+        if index.has_conflicts() {
+            return Err("Resolve the merge conflicts before committing".to_string());
+        }
+        index.write_tree().map_err(git_error)?
+    } else {
+        stage_only(&repo, &paths)?
+    };
+    let tree = repo.find_tree(tree).map_err(git_error)?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let unchanged = match &parent {
+        Some(parent) => parent.tree_id() == tree.id(),
+        None => tree.is_empty(),
+    };
+    if unchanged {
+        return Err("Nothing to commit".to_string());
+    }
+    let signature = repo
+        .signature()
+        .map_err(|_| "Set user.name and user.email in your git config to commit".to_string())?;
+    let message = git2::message_prettify(message, None).map_err(git_error)?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parents,
+        )
+        .map_err(git_error)?;
+    Ok(GitCommit {
+        hash: oid.to_string(),
+        summary: message.lines().next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Commits `paths` (relative to the repository root), staging their current contents
+/// first, including new and deleted files; other staged changes stay staged. With no
+/// paths, commits what is already staged. The author comes from the user's git config;
+/// commit hooks and signing don't run.
+#[tauri::command]
+pub async fn git_commit(
+    workspace: String,
+    message: String,
+    paths: Vec<String>,
+) -> Result<GitCommit, String> {
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    if paths.len() > MAX_COMMIT_PATHS {
+        return Err(format!(
+            "At most {} paths can be committed at once",
+            MAX_COMMIT_PATHS
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || commit(&workspace, &message, &paths))
+        .await
+        .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranch {
+    /// Short name, e.g. `main` or `origin/main`
+    name: String,
+    remote: bool,
+    /// Whether this is the checked-out branch
+    current: bool,
+    upstream: Option<String>,
+    /// Abbreviated hash of the tip
+    commit: String,
+    subject: String,
+    /// Unix seconds of the tip's commit
+    committed_at: i64,
+}
+
+/// Rejects names git wouldn't accept for a branch, and ones that would read as an option.
+fn check_branch_name(root: &Path, name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    git_output(root, &["check-ref-format", "--branch", name])
+        .map(|_| ())
+        .map_err(|_| format!("Invalid branch name: {}", name))
+}
+
+/// Local branches, then remote-tracking ones, each newest first.
+#[tauri::command]
+pub async fn git_branches(workspace: String) -> Result<Vec<GitBranch>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = repo_root(&workspace)?;
+        let output = git_output(
+            &root,
+            &[
+                "for-each-ref",
+                "--sort=-committerdate",
+                "--format=%(refname)%00%(refname:short)%00%(HEAD)%00%(upstream:short)%00%(objectname:short)%00%(committerdate:unix)%00%(subject)",
+                "refs/heads",
+                "refs/remotes",
+            ],
+        )?;
+        let mut branches: Vec<GitBranch> = output
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(7, '\0').collect();
+                let [refname, name, head, upstream, commit, date, subject] = fields[..] else {
+                    return None;
+                };
+                // `origin/HEAD` only points at another remote branch
+                if refname.ends_with("/HEAD") {
+                    return None;
+                }
+                Some(GitBranch {
+                    name: name.to_string(),
+                    remote: refname.starts_with("refs/remotes/"),
+                    current: head == "*",
+                    upstream: (!upstream.is_empty()).then(|| upstream.to_string()),
+                    commit: commit.to_string(),
+                    subject: subject.to_string(),
+                    committed_at: date.parse().unwrap_or(0),
+                })
+            })
+            .collect();
+        branches.sort_by_key(|b| b.remote);
+        Ok(branches)
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+/// Creates branch `name` at `start_point` (HEAD by default) and, with `checkout`, switches
+/// to it. Uncommitted changes carry over to the new branch.
+#[tauri::command]
+pub async fn git_create_branch(
+    workspace: String,
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = repo_root(&workspace)?;
+        let name = name.trim();
+        check_branch_name(&root, name)?;
+        let start = start_point
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        if let Some(start) = start {
+            if start.starts_with('-') {
+                return Err(format!("Invalid start point: {}", start));
+            }
+        }
+        let mut args = if checkout.unwrap_or(false) {
+            vec!["checkout", "-b", name]
+        } else {
+            vec!["branch", name]
+        };
+        args.extend(start);
+        git_output(&root, &args)?;
+        Ok(name.to_string())
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+/// Switches to `branch`. A remote-only name such as `feature` checks out a local branch
+/// tracking `origin/feature`. Git refuses when uncommitted changes would be overwritten,
+/// and its message is returned as the error.
+#[tauri::command]
+pub async fn git_checkout(workspace: String, branch: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = repo_root(&workspace)?;
+        let branch = branch.trim();
+        check_branch_name(&root, branch)?;
+        // The trailing `--` keeps git from reading the name as a file to restore
+        git_output(&root, &["checkout", branch, "--"])?;
+        Ok(branch.to_string())
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A fresh repository with an author configured and one committed file.
+    struct Fixture {
+        root: PathBuf,
+        dir: TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = TempDir::new();
+            let repo = Repository::init(dir.path()).unwrap();
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Test").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+            let fixture = Fixture {
+                root: dir.path().to_path_buf(),
+                dir,
+            };
+            fixture.write("README.md", "hello\n");
+            fixture.commit("Initial commit", &["README.md"]).unwrap();
+            fixture
+        }
+
+        fn workspace(&self) -> String {
+            self.root.to_string_lossy().into_owned()
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            self.dir.write(path, contents);
+        }
+
+        fn stage(&self, path: &str) {
+            let repo = Repository::open(&self.root).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            index.write().unwrap();
+        }
+
+        fn commit(&self, message: &str, paths: &[&str]) -> Result<GitCommit, String> {
+            let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+            commit(&self.workspace(), message, &paths)
+        }
+
+        fn file(&self, path: &str) -> Option<GitFileStatus> {
+            status(&self.workspace())
+                .unwrap()
+                .files
+                .into_iter()
+                .find(|f| f.path == path)
+        }
+    }
+
+    #[test]
+    fn reports_untracked_modified_and_staged_files() {
+        let fixture = Fixture::new();
+        fixture.write("README.md", "hello again\n");
+        fixture.write("src/new.rs", "fn main() {}\n");
+
+        let status = status(&fixture.workspace()).unwrap();
+        // The initial branch name follows the user's init.defaultBranch
+        assert!(status.branch.is_some());
+        assert_eq!(status.upstream, None);
+        let readme = fixture.file("README.md").unwrap();
+        assert_eq!(readme.unstaged, Some(GitChange::Modified));
+        assert_eq!(readme.staged, None);
+        assert!(fixture.file("src/new.rs").unwrap().untracked);
+
+        fixture.stage("src/new.rs");
+        let added = fixture.file("src/new.rs").unwrap();
+        assert_eq!(added.staged, Some(GitChange::Added));
+        assert!(!added.untracked);
+    }
+
+    #[test]
+    fn diffs_unstaged_changes_of_one_path() {
+        let fixture = Fixture::new();
+        fixture.write("README.md", "hello again\n");
+        fixture.write("other.txt", "x\n");
+        fixture.commit("Add other", &["other.txt"]).unwrap();
+        fixture.write("other.txt", "y\n");
+
+        let readme = diff(&fixture.workspace(), Some("README.md"), false).unwrap();
+        assert!(!readme.truncated);
+        assert!(readme.diff.contains("diff --git a/README.md b/README.md"));
+        assert!(readme.diff.contains("-hello\n+hello again\n"));
+        assert!(!readme.diff.contains("other.txt"));
+        assert!(diff(&fixture.workspace(), Some("../outside"), false).is_err());
+    }
+
+    #[test]
+    fn commits_only_the_given_paths() {
+        let fixture = Fixture::new();
+        fixture.write("a.txt", "a\n");
+        fixture.write("b.txt", "b\n");
+        fixture.stage("b.txt");
+
+        let commit = fixture.commit("Add a\n\n", &["a.txt"]).unwrap();
+        assert_eq!(commit.summary, "Add a");
+        assert!(fixture.file("a.txt").is_none());
+        // Staged before, left out of the commit and still staged after
+        assert_eq!(
+            fixture.file("b.txt").unwrap().staged,
+            Some(GitChange::Added)
+        );
+
+        let repo = Repository::open(&fixture.root).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.id().to_string(), commit.hash);
+        assert_eq!(head.message(), Some("Add a\n"));
+        assert!(head.tree().unwrap().get_name("b.txt").is_none());
+    }
+
+    #[test]
+    fn commits_deleted_files_and_refuses_empty_commits() {
+        let fixture = Fixture::new();
+        std::fs::remove_file(fixture.root.join("README.md")).unwrap();
+        fixture.commit("Remove readme", &["README.md"]).unwrap();
+        assert!(fixture.file("README.md").is_none());
+        let repo = Repository::open(&fixture.root).unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        assert!(tree.get_name("README.md").is_none());
+
+        assert_eq!(
+            fixture.commit("Again", &["README.md"]).unwrap_err(),
+            "Nothing to commit"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::Manager;
+
+use crate::clock::parse_rfc3339;
+use crate::storage::{ImportedConversation, ImportedMessage, Storage};
+
+const MAX_REPORTED_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    /// ChatGPT `conversations.json` from OpenAI's data export
+    Openai,
+    /// Claude `conversations.json` from Anthropic's data export
+    Anthropic,
+    Auto,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    imported: usize,
+    /// Conversations already present from an earlier import
+    duplicates: usize,
+    failed: usize,
+    errors: Vec<String>,
+}
+
+fn seconds_to_millis(value: Option<&Value>) -> Option<i64> {
+    value.and_then(|v| v.as_f64()).map(|s| (s * 1000.0) as i64)
+}
+
+/// Guesses the export format from the first conversation's shape.
+fn detect_source(conversations: &[Value]) -> Option<ImportSource> {
+    let first = conversations.first()?;
+    if first.get("mapping").is_some() {
+        Some(ImportSource::Openai)
+    } else if first.get("chat_messages").is_some() {
+        Some(ImportSource::Anthropic)
+    } else {
+        None
+    }
+}
+
+/// ChatGPT stores a message tree; the visible thread is the path from `current_node` to the root.
+fn parse_openai(value: &Value) -> Result<ImportedConversation, String> {
+    let id = value
+        .get("id")
+        .or_else(|| value.get("conversation_id"))
+        .and_then(|v| v.as_str())
+        .ok_or("Conversation has no id")?;
+    let mapping = value
+        .get("mapping")
+        .and_then(|v| v.as_object())
+        .ok_or("Conversation has no message mapping")?;
+    let created_at = seconds_to_millis(value.get("create_time")).unwrap_or(0);
+    let updated_at = seconds_to_millis(value.get("update_time")).unwrap_or(created_at);
+
+    let mut chain = Vec::new();
+    let mut node_id = value.get("current_node").and_then(|v| v.as_str());
+    while let Some(id) = node_id {
+        let Some(node) = mapping.get(id) else {
+            break;
+        };
+        chain.push(node);
+        // Guard against malformed cycles
+        if chain.len() > mapping.len() {
+            return Err("Message tree contains a cycle".to_string());
+        }
+        node_id = node.get("parent").and_then(|v| v.as_str());
+    }
+    chain.reverse();
+
+    let messages = chain
+        .into_iter()
+        .filter_map(|node| {
+            let message = node.get("message")?;
+            let role = message.pointer("/author/role")?.as_str()?;
+            let hidden = message
+                .pointer("/metadata/is_visually_hidden_from_conversation")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if hidden {
+                return None;
+            }
+            let content = message
+                .pointer("/content/parts")
+                .and_then(|v| v.as_array())
+                .map(|parts| {
+                    parts
+                        .iter()
+                        .filter_map(|p| p.as_str())
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .or_else(|| {
+                    message
+                        .pointer("/content/text")
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                })?;
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some(ImportedMessage {
+                role: role.to_string(),
+                content,
+                created_at: seconds_to_millis(message.get("create_time")).unwrap_or(created_at),
+            })
+        })
+        .collect();
+
+    Ok(ImportedConversation {
+        id: format!("openai:{}", id),
+        title: value
+            .get("title")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        created_at,
+        updated_at,
+        messages,
+    })
+}
+
+fn parse_anthropic(value: &Value) -> Result<ImportedConversation, String> {
+    let id = value
+        .get("uuid")
+        .and_then(|v| v.as_str())
+        .ok_or("Conversation has no uuid")?;
+    let timestamp =
+        |v: &Value, key: &str| v.get(key).and_then(|t| t.as_str()).and_then(parse_rfc3339);
+    let created_at = timestamp(value, "created_at").unwrap_or(0);
+    let updated_at = timestamp(value, "updated_at").unwrap_or(created_at);
+
+    let messages = value
+        .get("chat_messages")
+        .and_then(|v| v.as_array())
+        .ok_or("Conversation has no chat_messages")?
+        .iter()
+        .filter_map(|message| {
+            let role = match message.get("sender").and_then(|v| v.as_str())? {
+                "human" => "user",
+                other => other,
+            };
+            // Newer exports split text into typed content blocks; older ones only have `text`
+            let blocks = message
+                .get("content")
+                .and_then(|v| v.as_array())
+                .map(|blocks| {
+                    blocks
+                        .iter()
+                        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+                        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .filter(|text| !text.is_empty());
+            let content = blocks.or_else(|| {
+                message
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+            })?;
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some(ImportedMessage {
+                role: role.to_string(),
+                content,
+                created_at: timestamp(message, "created_at").unwrap_or(created_at),
+            })
+        })
+        .collect();
+
+    Ok(ImportedConversation {
+        id: format!("anthropic:{}", id),
+        title: value
+            .get("name")
+            .and_then(|v| v.as_str())
+            .filter(|n| !n.is_empty())
+            .map(String::from),
+        created_at,
+        updated_at,
+        messages,
+    })
+}
+
+/// Imports ChatGPT or Claude exports into local storage. Conversations keep their
+/// source ids, so importing the same export twice only reports duplicates.
+#[tauri::command]
+pub async fn import_conversations(
+    app: tauri::AppHandle,
+    path: String,
+    source: Option<ImportSource>,
+) -> Result<ImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let root: Value = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+        let conversations = root
+            .as_array()
+            .ok_or("Expected the export's conversations.json (a JSON array)")?;
+
+        let source = match source.unwrap_or(ImportSource::Auto) {
+            ImportSource::Auto => detect_source(conversations)
+                .ok_or("Could not recognise the export format; pick the source explicitly")?,
+            source => source,
+        };
+
+        let storage = app.state::<Storage>();
+        let mut report = ImportReport::default();
+        for (index, value) in conversations.iter().enumerate() {
+            let parsed = match source {
+                ImportSource::Openai => parse_openai(value),
+                _ => parse_anthropic(value),
+            };
+            let outcome = parsed.and_then(|c| storage.import_conversation(&c));
+            match outcome {
+                Ok(true) => report.imported += 1,
+                Ok(false) => report.duplicates += 1,
+                Err(err) => {
+                    report.failed += 1;
+                    if report.errors.len() < MAX_REPORTED_ERRORS {
+                        report
+                            .errors
+                            .push(format!("Conversation {}: {}", index + 1, err));
+                    }
+                }
+            }
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?
+}
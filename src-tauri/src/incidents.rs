@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::Manager;
+
+use crate::app_data::{app_data_path, load_json, save_json};
+use crate::clock::{format_utc, now_millis};
+use crate::rpc::{RpcError, RpcLog, ERR_CANCELLED, ERR_TIMEOUT};
+
+const INCIDENT_SETTINGS_FILE: &str = "incidents.json";
+const INCIDENTS_DIR: &str = "incidents";
+const INCIDENT_FILE: &str = "incident.json";
+const RPC_LOG_FILE: &str = "rpc_log.json";
+const SCREENSHOT_FILE: &str = "screenshot.png";
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentSettings {
+    #[serde(default = "default_true")]
+    enabled: bool,
+    /// Off by default: the window may show private conversation content
+    #[serde(default)]
+    capture_screenshot: bool,
+}
+
+impl Default for IncidentSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capture_screenshot: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentSummary {
+    id: String,
+    created_at: i64,
+    command: String,
+    error: RpcError,
+    #[serde(default)]
+    has_screenshot: bool,
+    #[serde(default)]
+    path: String,
+}
+
+/// Timeouts, cancellations and provider HTTP errors are expected failure modes;
+/// anything else (transport failures, sidecar crashes, oversized results) is a bug.
+fn is_unexpected(err: &RpcError) -> bool {
+    err.code != ERR_TIMEOUT && err.code != ERR_CANCELLED && err.status().is_none()
+}
+
+fn incidents_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_data_path(app, INCIDENTS_DIR)
+}
+
+/// Captures the main window's on-screen rectangle with the platform's screenshot tool.
+fn capture_window(app: &tauri::AppHandle, dest: &Path) -> Result<(), String> {
+    let windows = app.webview_windows();
+    let window = windows
+        .values()
+        .find(|w| w.is_focused().unwrap_or(false))
+        .or_else(|| windows.values().next())
+        .ok_or("No window to capture")?;
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+
+    let status = if cfg!(target_os = "macos") {
+        // screencapture works in points, not physical pixels
+        let scale = window.scale_factor().unwrap_or(1.0);
+        let rect = format!(
+            "{},{},{},{}",
+            (position.x as f64 / scale).round(),
+            (position.y as f64 / scale).round(),
+            (size.width as f64 / scale).round(),
+            (size.height as f64 / scale).round()
+        );
+        Command::new("screencapture")
+            .arg("-x")
+            .arg(format!("-R{}", rect))
+            .arg(dest)
+            .status()
+    } else if cfg!(target_os = "linux") {
+        let geometry = format!(
+            "{},{} {}x{}",
+            position.x, position.y, size.width, size.height
+        );
+        Command::new("grim")
+            .arg("-g")
+            .arg(geometry)
+            .arg(dest)
+            .status()
+            .or_else(|_| {
+                let crop = format!(
+                    "{}x{}+{}+{}",
+                    size.width, size.height, position.x, position.y
+                );
+                Command::new("import")
+                    .args(["-window", "root", "-crop", &crop])
+                    .arg(dest)
+                    .status()
+            })
+    } else {
+        return Err("Screenshots are not supported on this platform".to_string());
+    };
+
+    match status {
+        Ok(status) if status.success() && dest.is_file() => Ok(()),
+        Ok(status) => Err(format!("Screenshot tool exited with {}", status)),
+        Err(err) => Err(format!("Failed to run screenshot tool: {}", err)),
+    }
+}
+
+fn write_incident(app: &tauri::AppHandle, command: &str, err: &RpcError) -> Result<(), String> {
+    let settings = load_json::<IncidentSettings>(app, INCIDENT_SETTINGS_FILE)?;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let created_at = now_millis();
+    // Colons are not allowed in Windows paths
+    let id = format!("{}-{}", format_utc(created_at).replace(':', "-"), command);
+    let dir = incidents_dir(app)?.join(&id);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create incident folder: {}", e))?;
+
+    let rpc_log = app.state::<RpcLog>().recent();
+    let rpc_log = serde_json::to_string_pretty(&rpc_log).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(RPC_LOG_FILE), rpc_log)
+        .map_err(|e| format!("Failed to write RPC log: {}", e))?;
+
+    let has_screenshot = settings.capture_screenshot
+        && match capture_window(app, &dir.join(SCREENSHOT_FILE)) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("[incidents] screenshot failed: {}", err);
+                false
+            }
+        };
+
+    let summary = json!({
+        "id": id,
+        "createdAt": created_at,
+        "command": command,
+        "error": err,
+        "hasScreenshot": has_screenshot,
+        "appVersion": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+    });
+    let summary = serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(INCIDENT_FILE), summary)
+        .map_err(|e| format!("Failed to write incident: {}", e))
+}
+
+/// Records an incident folder for unexpected RPC failures, off the caller's task.
+pub(crate) fn on_rpc_error(app: &tauri::AppHandle, command: &str, err: &RpcError) {
+    if !is_unexpected(err) {
+        return;
+    }
+    let app = app.clone();
+    let command = command.to_string();
+    let err = err.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = write_incident(&app, &command, &err) {
+            eprintln!("[incidents] failed to record incident: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_incident_settings(app: tauri::AppHandle) -> Result<IncidentSettings, String> {
+    load_json::<IncidentSettings>(&app, INCIDENT_SETTINGS_FILE)
+}
+
+#[tauri::command]
+pub fn set_incident_settings(
+    app: tauri::AppHandle,
+    settings: IncidentSettings,
+) -> Result<IncidentSettings, String> {
+    save_json(&app, INCIDENT_SETTINGS_FILE, &settings)?;
+    Ok(settings)
+}
+
+/// Incident folders, newest first.
+#[tauri::command]
+pub fn list_incidents(app: tauri::AppHandle) -> Result<Vec<IncidentSummary>, String> {
+    let dir = incidents_dir(&app)?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("Failed to read incidents: {}", err)),
+    };
+
+    let mut incidents: Vec<IncidentSummary> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            let contents = std::fs::read_to_string(path.join(INCIDENT_FILE)).ok()?;
+            let mut summary: IncidentSummary = serde_json::from_str(&contents).ok()?;
+            summary.path = path.to_string_lossy().to_string();
+            Some(summary)
+        })
+        .collect();
+    incidents.sort_by_key(|i| std::cmp::Reverse(i.created_at));
+    Ok(incidents)
+}
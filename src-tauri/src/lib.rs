@@ -1,237 +1,1338 @@
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::Duration;
 use tauri::{Emitter, Manager};
-use tauri_plugin_shell::{process::CommandEvent, ShellExt};
-use tokio::sync::oneshot;
 
-static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+use a11y::AnnouncementKind;
+use activity::ActivityTracker;
+use agent_tasks::AgentTasks;
+use approvals::Approvals;
+use bedrock::AwsCredentialCache;
+use changesets::Changesets;
+use checkpoints::CheckpointLock;
+use chunks::ChunkAssembler;
+use context_window::SummaryCache;
+use fallback::FailedAttempt;
+use file_history::FileHistoryLock;
+use display::DisplayState;
+use environment::EnvironmentCache;
+use mcp::McpServers;
+use models::ModelCache;
+use onboarding::{OnboardingLock, OnboardingStep};
+use permissions::Permissions;
+use provider_status::ProviderStatusCache;
+use providers::{AzureDeployment, ProviderOptions};
+use recorder::SessionRecorder;
+use retry::RateLimits;
+use rpc::{
+    PendingRequests, RpcError, RpcLog, SidecarGeneration, SidecarProcess, ERR_CANCELLED,
+    ERR_TIMEOUT,
+};
+use scheduler::Scheduler;
+use schedules::ScheduleLock;
+use secrets::KeyRotation;
+use speech::Speech;
+use storage::{ConversationMode, Storage, StoredMessage};
+use streams::StreamBuffers;
+use tasks::Tasks;
+use terminal::Terminals;
+use tool_limits::ToolTimeouts;
+use unread::ReadTracker;
+use workspace_config::WorkspaceRules;
+use workspace_locks::WorkspaceLocks;
+use usage::UsageReports;
+use vertex::VertexTokenCache;
+use watcher::WorkspaceWatchers;
 
-#[derive(Debug, Serialize, Deserialize)]
+mod a11y;
+mod actions;
+mod activity;
+mod agent_tasks;
+mod app_data;
+mod approvals;
+mod archive;
+mod attachments;
+mod bedrock;
+mod capabilities;
+mod changesets;
+mod checkpoints;
+mod chunks;
+mod clock;
+mod compliance;
+mod connection;
+mod context_window;
+mod custom_tools;
+mod diff;
+mod display;
+mod dry_run;
+mod editor;
+mod embeddings;
+mod environment;
+mod export;
+mod fallback;
+mod feedback;
+mod file_history;
+mod file_preview;
+mod file_tree;
+mod git;
+mod import;
+mod incidents;
+mod mcp;
+mod memory;
+mod models;
+mod native_chat;
+mod onboarding;
+mod patch;
+mod permissions;
+mod profiles;
+mod prompt_templates;
+mod prompts;
+mod provider_status;
+mod providers;
+mod proxy;
+mod recent_workspaces;
+mod recorder;
+mod redaction;
+mod retry;
+mod reveal;
+mod rpc;
+mod scaffold;
+mod scheduler;
+mod schedules;
+mod search;
+mod secrets;
+mod semantic_index;
+mod sidecar;
+mod slash_commands;
+mod snippets;
+mod speech;
+mod stats;
+mod storage;
+mod streams;
+mod tasks;
+mod template;
+mod terminal;
+#[cfg(test)]
+mod test_support;
+mod timezone;
+mod titles;
+mod todos;
+mod tool_events;
+mod tool_limits;
+mod transcription;
+mod unread;
+mod usage;
+mod vertex;
+mod watcher;
+mod workspace;
+mod workspace_config;
+mod workspace_files;
+mod workspace_locks;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ChatMessage {
     role: String,
     content: String,
 }
 
-#[derive(Debug, Serialize)]
-struct RpcRequest {
-    id: u64,
-    method: String,
-    params: SendMessageParams,
-}
-
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SendMessageParams {
     provider: Option<String>,
+    /// Always filled in by the host; one sent by the webview is ignored
+    #[serde(default, skip_deserializing)]
     api_key: String,
     model: String,
     base_url: Option<String>,
     messages: Vec<ChatMessage>,
     workspace_path: Option<String>,
+    /// Folders of a multi-root workspace besides `workspace_path`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    workspace_roots: Vec<String>,
     request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_timeouts: Option<ToolTimeouts>,
+    /// Tool and path restrictions from the workspace's config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    workspace_rules: Option<WorkspaceRules>,
+    /// Tools of the connected MCP servers, called back through the host
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mcp_tools: Vec<mcp::AgentMcpTool>,
+    /// Tools the user registered, run by the host as local commands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    custom_tools: Vec<custom_tools::AgentCustomTool>,
+    /// In plan mode the agent proposes changes and the host refuses to make them
+    #[serde(default)]
+    mode: ConversationMode,
+    #[serde(flatten)]
+    options: ProviderOptions,
 }
 
-#[derive(Debug, Deserialize)]
-struct RpcResponse {
-    id: Option<u64>,
-    result: Option<String>,
-    error: Option<RpcError>,
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryingEvent {
+    request_id: Option<String>,
+    attempt: u32,
+    max_retries: u32,
+    delay_ms: u64,
+    reason: String,
+    error: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct RpcError {
-    #[allow(dead_code)]
-    code: i32,
-    message: String,
+/// A request waiting out a provider rate limit. `queued` is set when another request
+/// hit the limit and this one is held back before being sent.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RateLimitedEvent {
+    request_id: Option<String>,
+    provider: Option<String>,
+    wait_ms: u64,
+    /// Whether the wait comes from the provider's `Retry-After` rather than backoff
+    retry_after: bool,
+    attempt: u32,
+    max_retries: u32,
+    queued: bool,
+    /// Whether another of the provider's keys is tried at once instead of waiting
+    key_rotated: bool,
 }
 
-type PendingRequests = Mutex<HashMap<u64, oneshot::Sender<Result<String, String>>>>;
+/// What the user already saw when a request timed out or was cancelled mid-stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PartialResult {
+    content: String,
+    truncated: bool,
+    reason: String,
+}
 
-#[tauri::command]
-async fn ping_sidecar(app: tauri::AppHandle) -> Result<String, String> {
-    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+/// A reply served by a fallback provider because the requested one was unavailable.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FallbackResult {
+    content: String,
+    profile_id: String,
+    provider: Option<String>,
+    model: String,
+    /// The providers that failed first, in the order they were tried
+    failed: Vec<FailedAttempt>,
+}
 
-    let request = serde_json::json!({
-        "id": id,
-        "method": "ping",
-        "params": {}
-    });
+/// Completed responses stay a plain string so existing callers are unaffected.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum SendMessageOutcome {
+    Complete(String),
+    Partial(PartialResult),
+    Fallback(FallbackResult),
+}
 
-    let request_json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FallbackEvent {
+    request_id: Option<String>,
+    from_provider: Option<String>,
+    from_model: String,
+    profile_id: String,
+    to_provider: Option<String>,
+    to_model: String,
+    error: String,
+}
 
-    let (tx, rx) = oneshot::channel();
-    {
-        let pending = app.state::<PendingRequests>();
-        let mut map = pending.lock().unwrap();
-        map.insert(id, tx);
-    }
+/// The forked conversation and the reply to the edited prompt.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResendOutcome {
+    conversation_id: String,
+    result: SendMessageOutcome,
+}
 
-    let sidecar_stdin = app.state::<Mutex<Option<tauri_plugin_shell::process::CommandChild>>>();
-    {
-        let mut stdin_guard = sidecar_stdin.lock().unwrap();
-        if let Some(ref mut child) = *stdin_guard {
-            let data = (request_json + "\n").into_bytes();
-            child.write(&data).map_err(|e| format!("Failed to write to sidecar: {}", e))?;
-        } else {
-            return Err("Sidecar not running".to_string());
-        }
-    }
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchResult {
+    request_id: Option<String>,
+    result: Option<String>,
+    error: Option<String>,
+}
 
-    match tokio::time::timeout(std::time::Duration::from_secs(5), rx).await {
-        Ok(Ok(result)) => result,
-        Ok(Err(_)) => Err("Request cancelled".to_string()),
-        Err(_) => Err("Request timed out".to_string()),
-    }
+/// One provider/model configuration for `send_message_multi`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelTarget {
+    #[serde(default)]
+    provider: Option<String>,
+    /// Falls back to the keychain entry for `provider`
+    #[serde(default)]
+    api_key: Option<String>,
+    model: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    azure: Option<AzureDeployment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MultiResult {
+    request_id: String,
+    provider: Option<String>,
+    model: String,
+    result: Option<String>,
+    error: Option<String>,
+}
+
+const MAX_MULTI_TARGETS: usize = 8;
+
+#[tauri::command]
+async fn ping_sidecar(app: tauri::AppHandle) -> Result<String, String> {
+    let result = rpc::call(&app, "ping", &serde_json::json!({}), Duration::from_secs(5))
+        .await
+        .map_err(|e| e.message)?;
+    let _ = onboarding::complete_step(&app, OnboardingStep::SidecarHealthy);
+    Ok(result)
 }
 
 #[tauri::command]
 async fn warmup_model(
     app: tauri::AppHandle,
     provider: Option<String>,
-    api_key: String,
+    api_key: Option<String>,
     model: String,
     base_url: Option<String>,
 ) -> Result<String, String> {
-    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
-
-    let request = RpcRequest {
-        id,
-        method: "warmup".to_string(),
-        params: SendMessageParams {
-            provider,
-            api_key,
-            model,
-            base_url,
-            messages: Vec::new(),
-            workspace_path: None,
-            request_id: None,
+    let api_key = secrets::resolve_api_key(&app, provider.as_deref(), None, api_key)?;
+    let api_key = vertex::authorize(&app, provider.as_deref(), api_key).await?;
+    let base_url = providers::host_base_url(&app, provider.as_deref(), base_url)?;
+    let params = SendMessageParams {
+        provider,
+        api_key,
+        model,
+        base_url,
+        messages: Vec::new(),
+        workspace_path: None,
+        workspace_roots: Vec::new(),
+        request_id: None,
+        tool_timeouts: None,
+        workspace_rules: None,
+        mcp_tools: Vec::new(),
+        custom_tools: Vec::new(),
+        mode: ConversationMode::Act,
+        options: ProviderOptions {
+            proxy: proxy::default_proxy(&app)?,
+            ..ProviderOptions::default()
         },
     };
 
-    let request_json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
-    let (tx, rx) = oneshot::channel();
-
-    {
-        let pending = app.state::<PendingRequests>();
-        let mut map = pending.lock().unwrap();
-        map.insert(id, tx);
+    let result = rpc::call(&app, "warmup", &params, Duration::from_secs(10))
+        .await
+        .map_err(|e| e.message)?;
+    // A successful warmup is a real authenticated round-trip, so the key is known good
+    if result == "ok" {
+        let _ = onboarding::complete_step(&app, OnboardingStep::ApiKey);
     }
+    Ok(result)
+}
 
-    let sidecar_stdin = app.state::<Mutex<Option<tauri_plugin_shell::process::CommandChild>>>();
-    {
-        let mut stdin_guard = sidecar_stdin.lock().unwrap();
-        if let Some(ref mut child) = *stdin_guard {
-            let data = (request_json + "\n").into_bytes();
-            child
-                .write(&data)
-                .map_err(|e| format!("Failed to write to sidecar: {}", e))?;
+/// Sends `params` to the sidecar, transparently retrying transient provider failures.
+/// Rate limits are waited out as the provider asks, on their own retry budget, and hold
+/// back other requests using the same key meanwhile. With several keys saved for the
+/// provider, a rate-limited key is swapped for a spare one without waiting.
+async fn dispatch_with_retries(
+    app: &tauri::AppHandle,
+    params: &mut SendMessageParams,
+    max_retries: u32,
+) -> Result<String, RpcError> {
+    let mut attempt = 0;
+    let mut rate_limited = 0;
+    loop {
+        let limit_key = retry::rate_limit_key(
+            params.provider.as_deref(),
+            params.base_url.as_deref(),
+            &params.api_key,
+        );
+        if let Some(wait) = retry::rate_limit_remaining(app, &limit_key) {
+            let _ = app.emit(
+                "agent:rate_limited",
+                RateLimitedEvent {
+                    request_id: params.request_id.clone(),
+                    provider: params.provider.clone(),
+                    wait_ms: wait.as_millis() as u64,
+                    retry_after: false,
+                    attempt: rate_limited,
+                    max_retries: retry::RATE_LIMIT_RETRIES,
+                    queued: true,
+                    key_rotated: false,
+                },
+            );
+            tokio::time::sleep(wait).await;
+        }
+        // Each attempt streams from scratch, so only the latest deltas are kept
+        if let Some(request_id) = &params.request_id {
+            app.state::<StreamBuffers>().begin(request_id);
+        }
+
+        let result = if native_chat::should_use(app, params) {
+            native_chat::send(app, params).await
         } else {
-            return Err("Sidecar not running".to_string());
+            rpc::call(app, "sendMessage", &*params, Duration::from_secs(60)).await
+        };
+        let mut err = match result {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+
+        let Some(reason) = retry::retryable_class(&err) else {
+            return Err(err);
+        };
+        if reason == "rate_limit" && rate_limited < retry::RATE_LIMIT_RETRIES {
+            let cooldown = err
+                .retry_after()
+                .unwrap_or_else(|| retry::backoff_delay(rate_limited));
+            let spare = secrets::rotate_after_rate_limit(
+                app,
+                params.provider.as_deref(),
+                params.options.key_ref.as_deref(),
+                &params.api_key,
+                cooldown,
+            );
+            if let Some(spare) = spare {
+                retry::note_rate_limited(app, &limit_key, cooldown);
+                rate_limited += 1;
+                params.api_key = spare;
+                let _ = app.emit(
+                    "agent:rate_limited",
+                    RateLimitedEvent {
+                        request_id: params.request_id.clone(),
+                        provider: params.provider.clone(),
+                        wait_ms: 0,
+                        retry_after: false,
+                        attempt: rate_limited,
+                        max_retries: retry::RATE_LIMIT_RETRIES,
+                        queued: false,
+                        key_rotated: true,
+                    },
+                );
+                continue;
+            }
+        }
+        if reason == "rate_limit" {
+            let provider = params.provider.as_deref().unwrap_or("the provider");
+            let delay = retry::rate_limit_delay(&err, rate_limited)
+                .filter(|_| rate_limited < retry::RATE_LIMIT_RETRIES);
+            let Some(delay) = delay else {
+                err.message = match err.retry_after() {
+                    Some(wait) => format!(
+                        "Rate limited by {}. Try again in {}s.",
+                        provider,
+                        wait.as_secs().max(1)
+                    ),
+                    None => format!("Rate limited by {}. Try again shortly.", provider),
+                };
+                return Err(err);
+            };
+            rate_limited += 1;
+            retry::note_rate_limited(app, &limit_key, delay);
+            let _ = app.emit(
+                "agent:rate_limited",
+                RateLimitedEvent {
+                    request_id: params.request_id.clone(),
+                    provider: params.provider.clone(),
+                    wait_ms: delay.as_millis() as u64,
+                    retry_after: err.retry_after().is_some(),
+                    attempt: rate_limited,
+                    max_retries: retry::RATE_LIMIT_RETRIES,
+                    queued: false,
+                    key_rotated: false,
+                },
+            );
+            tokio::time::sleep(delay).await;
+            continue;
+        }
+        if attempt >= max_retries {
+            return Err(err);
         }
+
+        attempt += 1;
+        let delay = retry::backoff_delay(attempt - 1);
+        let _ = app.emit(
+            "agent:retrying",
+            RetryingEvent {
+                request_id: params.request_id.clone(),
+                attempt,
+                max_retries,
+                delay_ms: delay.as_millis() as u64,
+                reason: reason.to_string(),
+                error: err.message,
+            },
+        );
+        tokio::time::sleep(delay).await;
     }
+}
 
-    match tokio::time::timeout(std::time::Duration::from_secs(10), rx).await {
-        Ok(Ok(result)) => result,
-        Ok(Err(_)) => Err("Request cancelled".to_string()),
-        Err(_) => Err("Request timed out".to_string()),
+/// `dispatch_with_retries`, moving down the fallback chain while providers are unavailable.
+/// Returns the params of the provider that answered last, plus the serving profile and
+/// the failed attempts when a fallback was used.
+async fn dispatch_with_fallbacks(
+    app: &tauri::AppHandle,
+    mut params: SendMessageParams,
+    max_retries: u32,
+) -> (
+    Result<String, RpcError>,
+    SendMessageParams,
+    Option<(String, Vec<FailedAttempt>)>,
+) {
+    let mut result = dispatch_with_retries(app, &mut params, max_retries).await;
+    if result.is_ok() {
+        return (result, params, None);
+    }
+    let chain = fallback::chain(app).unwrap_or_else(|err| {
+        eprintln!("[fallback] failed to load the fallback chain: {}", err);
+        Vec::new()
+    });
+    let mut failed = Vec::new();
+    let mut served_by = None;
+    for entry in chain {
+        let Err(err) = &result else {
+            break;
+        };
+        let streamed = params
+            .request_id
+            .as_deref()
+            .is_some_and(|id| app.state::<StreamBuffers>().has_content(id));
+        if !fallback::is_availability_error(err, streamed) {
+            break;
+        }
+        let target = match fallback::resolve(app, &entry).await {
+            Ok(target) => target,
+            Err(err) => {
+                eprintln!("[fallback] skipping an unusable fallback: {}", err);
+                continue;
+            }
+        };
+        // A chain may list the primary too; asking it again won't help
+        if target.provider == params.provider
+            && target.model == params.model
+            && target.base_url == params.base_url
+        {
+            continue;
+        }
+        let _ = app.emit(
+            "agent:fallback",
+            FallbackEvent {
+                request_id: params.request_id.clone(),
+                from_provider: params.provider.clone(),
+                from_model: params.model.clone(),
+                profile_id: target.profile_id.clone(),
+                to_provider: target.provider.clone(),
+                to_model: target.model.clone(),
+                error: err.message.clone(),
+            },
+        );
+        failed.push(FailedAttempt {
+            provider: params.provider.clone(),
+            model: params.model.clone(),
+            error: err.message.clone(),
+        });
+        params.provider = target.provider;
+        params.api_key = target.api_key;
+        params.model = target.model;
+        params.base_url = target.base_url;
+        params.options = target.options;
+        served_by = Some(target.profile_id);
+        result = dispatch_with_retries(app, &mut params, max_retries).await;
     }
+    match &mut result {
+        Err(err) if !failed.is_empty() => {
+            let tried: Vec<String> = failed
+                .iter()
+                .map(|f| format!("{}/{}", f.provider.as_deref().unwrap_or("default"), f.model))
+                .collect();
+            err.message = format!("{} (after {} failed)", err.message, tried.join(", "));
+            (result, params, None)
+        }
+        _ => (result, params, served_by.map(|id| (id, failed))),
+    }
+}
+
+/// Provider, model and base URL for a request: the conversation's overrides, then the
+/// app-wide values the webview sent. A different provider doesn't inherit the global
+/// base URL, which belongs to the global provider.
+fn effective_model_settings(
+    app: &tauri::AppHandle,
+    conversation_id: Option<&str>,
+    provider: Option<String>,
+    model: String,
+    base_url: Option<String>,
+) -> (Option<String>, String, Option<String>) {
+    let settings = conversation_id
+        .map(|id| app.state::<Storage>().conversation_settings(id))
+        .transpose()
+        .unwrap_or_else(|err| {
+            eprintln!("[storage] failed to load conversation settings: {}", err);
+            None
+        })
+        .unwrap_or_default();
+    let base_url = match &settings.provider {
+        Some(_) => settings.base_url,
+        None => settings.base_url.or(base_url),
+    };
+    (
+        settings.provider.or(provider),
+        settings.model.unwrap_or(model),
+        base_url,
+    )
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn send_message(
     app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    base_url: Option<String>,
+    messages: Vec<ChatMessage>,
+    workspace_path: Option<String>,
+    workspace_roots: Option<Vec<String>>,
+    request_id: Option<String>,
+    max_retries: Option<u32>,
+    conversation_id: Option<String>,
+    template_id: Option<String>,
+    attachments: Option<Vec<String>>,
+    profile_id: Option<String>,
+    azure: Option<AzureDeployment>,
+) -> Result<SendMessageOutcome, String> {
+    send_profiled(
+        app,
+        window.label().to_string(),
+        provider,
+        api_key,
+        model,
+        base_url,
+        messages,
+        workspace_path,
+        workspace_roots,
+        request_id,
+        max_retries,
+        conversation_id,
+        template_id,
+        attachments,
+        profile_id,
+        azure,
+    )
+    .await
+}
+
+/// `send_message` for a caller without a webview, such as a background task. `window`
+/// labels the runs it holds workspace locks for.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn send_profiled(
+    app: tauri::AppHandle,
+    window: String,
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    base_url: Option<String>,
+    messages: Vec<ChatMessage>,
+    workspace_path: Option<String>,
+    workspace_roots: Option<Vec<String>>,
+    request_id: Option<String>,
+    max_retries: Option<u32>,
+    conversation_id: Option<String>,
+    template_id: Option<String>,
+    attachments: Option<Vec<String>>,
+    profile_id: Option<String>,
+    azure: Option<AzureDeployment>,
+) -> Result<SendMessageOutcome, String> {
+    let target = profiles::apply(&app, profile_id.as_deref(), provider, model, base_url)?;
+    // A project's committed model replaces the app's choice, but not a conversation's own
+    let model = workspace_config::load_or_default(workspace_path.as_deref())
+        .model
+        .or(target.model)
+        .ok_or("A model is required")?;
+    let (provider, model, base_url) = effective_model_settings(
+        &app,
+        conversation_id.as_deref(),
+        target.provider.clone(),
+        model,
+        target.base_url,
+    );
+    // A profile's key, headers and paths belong to its provider, not to a conversation override.
+    // Without an explicit key the keychain entry for the provider is used
+    let same_provider = provider == target.provider;
+    let key_ref = target.key_ref.filter(|_| same_provider);
+    let api_key =
+        secrets::resolve_api_key(&app, provider.as_deref(), key_ref.as_deref(), api_key)?;
+    send_resolved(
+        app,
+        window,
+        provider,
+        api_key,
+        model,
+        base_url,
+        ProviderOptions {
+            headers: target.headers.filter(|_| same_provider),
+            azure: azure
+                .and_then(AzureDeployment::normalized)
+                .or(target.azure.filter(|_| same_provider)),
+            endpoint: target.endpoint.filter(|_| same_provider),
+            proxy: target.proxy.filter(|_| same_provider),
+            key_ref,
+            native: target.native_streaming && same_provider,
+        },
+        messages,
+        workspace_path,
+        workspace_roots.unwrap_or_default(),
+        request_id,
+        max_retries,
+        conversation_id,
+        template_id,
+        attachments,
+    )
+    .await
+}
+
+/// Appends `addition` to the leading system message, adding one if there is none.
+fn extend_system(messages: &mut Vec<ChatMessage>, addition: &str) {
+    match messages.first_mut().filter(|m| m.role == "system") {
+        Some(system) => {
+            system.content.push_str("\n\n");
+            system.content.push_str(addition);
+        }
+        None => messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: addition.to_string(),
+            },
+        ),
+    }
+}
+
+/// `send_message` with provider settings already resolved. `window` is the label of the
+/// window the request belongs to.
+#[allow(clippy::too_many_arguments)]
+async fn send_resolved(
+    app: tauri::AppHandle,
+    window: String,
     provider: Option<String>,
     api_key: String,
     model: String,
     base_url: Option<String>,
+    mut options: ProviderOptions,
     messages: Vec<ChatMessage>,
     workspace_path: Option<String>,
+    workspace_roots: Vec<String>,
     request_id: Option<String>,
-) -> Result<String, String> {
-    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
-
-    let request = RpcRequest {
-        id,
-        method: "sendMessage".to_string(),
-        params: SendMessageParams {
-            provider,
-            api_key,
-            model,
-            base_url,
-            messages,
-            workspace_path,
-            request_id,
-        },
+    max_retries: Option<u32>,
+    conversation_id: Option<String>,
+    template_id: Option<String>,
+    attachments: Option<Vec<String>>,
+) -> Result<SendMessageOutcome, String> {
+    // The extra folders go to the agent canonical, without the primary root or duplicates
+    let workspace_roots = match workspace_path.as_deref() {
+        Some(primary) if !workspace_roots.is_empty() => {
+            workspace::workspace_roots(primary, &workspace_roots)?
+                .iter()
+                .skip(1)
+                .map(|root| root.to_string_lossy().into_owned())
+                .collect()
+        }
+        None if !workspace_roots.is_empty() => {
+            return Err("Additional workspace folders require a workspace".to_string())
+        }
+        _ => Vec::new(),
+    };
+    let mut messages = messages;
+    if let Some(template_id) = &template_id {
+        let system =
+            prompt_templates::expand(&app, template_id, workspace_path.as_deref(), &model)?;
+        // The template replaces any system message the webview supplied
+        if messages.first().is_some_and(|m| m.role == "system") {
+            messages.remove(0);
+        }
+        messages.insert(
+            0,
+            ChatMessage {
+                role: "system".to_string(),
+                content: system,
+            },
+        );
+    }
+    let workspace_config = workspace_config::load_or_default(workspace_path.as_deref());
+    if let Some(addition) = &workspace_config.system_prompt {
+        extend_system(&mut messages, addition);
+    }
+    if let Some(memory) = workspace_path.as_deref().and_then(|w| memory::context_block(&app, w)) {
+        extend_system(&mut messages, &memory);
+    }
+    // Telling the agent which toolchains exist keeps it from guessing
+    if let Some(workspace) = workspace_path.clone() {
+        let detector = app.clone();
+        let report = tauri::async_runtime::spawn_blocking(move || {
+            environment::detect(&detector, &workspace, false)
+        })
+        .await;
+        match report {
+            Ok(Ok(report)) => extend_system(&mut messages, &environment::context_block(&report)),
+            Ok(Err(err)) => eprintln!("[environment] detection failed: {}", err),
+            Err(err) => eprintln!("[environment] detection failed: {}", err),
+        }
+    }
+    let attachment_context = match attachments.filter(|a| !a.is_empty()) {
+        Some(attachments) => {
+            let workspace = workspace_path
+                .as_deref()
+                .ok_or("Attachments require a workspace")?;
+            Some(attachments::context_block(workspace, &attachments)?)
+        }
+        None => None,
+    };
+    let semantic_context = match (
+        workspace_path.as_deref(),
+        messages.iter().rev().find(|m| m.role == "user"),
+    ) {
+        (Some(workspace), Some(prompt)) => {
+            semantic_index::context_for(&app, workspace, &prompt.content).await
+        }
+        _ => None,
+    };
+    let attachment_context = match (attachment_context, semantic_context) {
+        (Some(attached), Some(related)) => Some(format!("{}\n{}", attached, related)),
+        (attached, related) => attached.or(related),
     };
 
-    let request_json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
-
-    let (tx, rx) = oneshot::channel();
+    let mode = match conversation_id.as_deref() {
+        Some(id) => app.state::<Storage>().conversation_mode(id)?,
+        None => ConversationMode::Act,
+    };
+    // Plan mode is enforced per run, so such a run needs an id its tool calls carry
+    let request_id = match (mode, request_id) {
+        (ConversationMode::Plan, None) => Some(uuid::Uuid::new_v4().to_string()),
+        (_, request_id) => request_id,
+    };
 
-    // Store the sender for this request
-    {
-        let pending = app.state::<PendingRequests>();
-        let mut map = pending.lock().unwrap();
-        map.insert(id, tx);
+    let base_url = providers::host_base_url(&app, provider.as_deref(), base_url)?;
+    let api_key = vertex::authorize(&app, provider.as_deref(), api_key).await?;
+    if options.proxy.is_none() {
+        options.proxy = proxy::default_proxy(&app)?;
     }
-
-    // Get the sidecar stdin and write the request
-    let sidecar_stdin = app.state::<Mutex<Option<tauri_plugin_shell::process::CommandChild>>>();
+    let mut params = SendMessageParams {
+        provider,
+        api_key,
+        model,
+        base_url,
+        messages,
+        workspace_path,
+        workspace_roots,
+        request_id,
+        tool_timeouts: Some(tool_limits::load(&app)),
+        workspace_rules: Some(workspace_config.rules).filter(|r| !r.is_empty()),
+        mcp_tools: mcp::agent_tools(&app),
+        custom_tools: custom_tools::agent_tools(&app),
+        mode,
+        options,
+    };
+    // Trimmed before the attachments go in so they are never the part that gets dropped
+    let reserved = attachment_context.as_deref().map_or(0, usage::estimate_tokens);
+    if let Some(report) =
+        context_window::fit(&app, &mut params, conversation_id.as_deref(), reserved)
     {
-        let mut stdin_guard = sidecar_stdin.lock().unwrap();
-        if let Some(ref mut child) = *stdin_guard {
-            let data = (request_json + "\n").into_bytes();
-            child.write(&data).map_err(|e| format!("Failed to write to sidecar: {}", e))?;
-        } else {
-            return Err("Sidecar not running".to_string());
+        recorder::record(&app, "context:trimmed", &report);
+    }
+    if let Some(context) = attachment_context {
+        // File context goes after any system message so it reads as conversation input
+        let at = usize::from(params.messages.first().is_some_and(|m| m.role == "system"));
+        params.messages.insert(
+            at,
+            ChatMessage {
+                role: "user".to_string(),
+                content: context,
+            },
+        );
+    }
+    let max_retries = retry::effective_max_retries(max_retries);
+    let _run_mode = params
+        .request_id
+        .as_deref()
+        .map(|request_id| permissions::enter_mode(&app, request_id, params.mode));
+    // Held until this function returns so runs in other windows can't mutate the same tree
+    let _workspace_locks = params
+        .workspace_path
+        .iter()
+        .filter(|workspace| !workspace.trim().is_empty())
+        .chain(&params.workspace_roots)
+        .map(|workspace| {
+            workspace_locks::acquire(&app, workspace, &window, params.request_id.as_deref())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // Taken under the lock so no other run is changing the files meanwhile; a run in plan
+    // mode can't change them at all
+    if params.mode == ConversationMode::Act {
+        for workspace in params
+            .workspace_path
+            .iter()
+            .filter(|workspace| !workspace.trim().is_empty())
+            .chain(&params.workspace_roots)
+        {
+            checkpoints::before_run(&app, workspace, params.request_id.as_deref()).await;
         }
     }
 
-    // Wait for response with timeout
-    match tokio::time::timeout(std::time::Duration::from_secs(60), rx).await {
-        Ok(Ok(result)) => result,
-        Ok(Err(_)) => Err("Request cancelled".to_string()),
-        Err(_) => Err("Request timed out".to_string()),
+    if let Some(conversation_id) = &conversation_id {
+        if let Some(prompt) = params.messages.iter().rev().find(|m| m.role == "user") {
+            // Regenerating after `switch_branch` leaves the prompt itself as the active
+            // leaf; the new answer branches from it instead of repeating it
+            let already_stored = app
+                .state::<Storage>()
+                .active_message(conversation_id)
+                .ok()
+                .flatten()
+                .is_some_and(|m| m.role == "user" && m.content == prompt.content);
+            if !already_stored {
+                persist_message(&app, conversation_id, prompt, params.request_id.as_deref());
+            }
+        }
     }
-}
 
-fn handle_sidecar_output(app: &tauri::AppHandle, line: &str) {
-    // Skip empty lines
-    if line.trim().is_empty() {
-        return;
+    recorder::record(
+        &app,
+        "session:prompt",
+        &serde_json::json!({ "requestId": params.request_id, "messages": params.messages }),
+    );
+    a11y::announce(
+        &app,
+        AnnouncementKind::GenerationStarted,
+        params.request_id.as_deref(),
+        "Generating a response",
+    );
+    let (result, params, fell_back) = dispatch_with_fallbacks(&app, params, max_retries).await;
+    if let Some(request_id) = &params.request_id {
+        a11y::finish_request(&app, request_id);
+        activity::finish_request(&app, request_id);
     }
+    let streamed = params
+        .request_id
+        .as_deref()
+        .and_then(|id| app.state::<StreamBuffers>().take(id))
+        .filter(|content| !content.is_empty());
+    let reported_usage = usage::take_report(&app, params.request_id.as_deref());
 
-    if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
-        if let Some(event_name) = value.get("event").and_then(|v| v.as_str()) {
-            if event_name == "agent_status" {
-                let _ = app.emit("agent:status", value);
-                return;
+    let outcome = match result {
+        Ok(content) => match fell_back {
+            Some((profile_id, failed)) => SendMessageOutcome::Fallback(FallbackResult {
+                content,
+                profile_id,
+                provider: params.provider.clone(),
+                model: params.model.clone(),
+                failed,
+            }),
+            None => SendMessageOutcome::Complete(content),
+        },
+        Err(err) => match (err.code, streamed) {
+            (ERR_TIMEOUT | ERR_CANCELLED, Some(content)) => {
+                SendMessageOutcome::Partial(PartialResult {
+                    content,
+                    truncated: true,
+                    reason: if err.code == ERR_TIMEOUT {
+                        "timeout".to_string()
+                    } else {
+                        "cancelled".to_string()
+                    },
+                })
             }
-            if event_name == "assistant_delta" {
-                let _ = app.emit("agent:delta", value);
-                return;
+            _ => {
+                a11y::announce(
+                    &app,
+                    AnnouncementKind::GenerationFinished,
+                    params.request_id.as_deref(),
+                    "The response failed",
+                );
+                return Err(err.message);
             }
+        },
+    };
+    recorder::record(
+        &app,
+        "session:result",
+        &serde_json::json!({ "requestId": params.request_id, "result": outcome }),
+    );
+    let finished = match &outcome {
+        SendMessageOutcome::Complete(_) | SendMessageOutcome::Fallback(_) => "Response ready",
+        SendMessageOutcome::Partial(_) => "Response stopped early; partial text is shown",
+    };
+    a11y::announce(
+        &app,
+        AnnouncementKind::GenerationFinished,
+        params.request_id.as_deref(),
+        finished,
+    );
+    match &outcome {
+        SendMessageOutcome::Complete(reply) => speech::after_response(&app, reply),
+        SendMessageOutcome::Fallback(fallback) => speech::after_response(&app, &fallback.content),
+        SendMessageOutcome::Partial(_) => {}
+    }
+
+    if let Some(conversation_id) = &conversation_id {
+        let content = match &outcome {
+            SendMessageOutcome::Complete(text) => text.clone(),
+            SendMessageOutcome::Partial(partial) => partial.content.clone(),
+            SendMessageOutcome::Fallback(fallback) => fallback.content.clone(),
+        };
+        let reply = ChatMessage {
+            role: "assistant".to_string(),
+            content,
+        };
+        if let Some(stored) =
+            persist_message(&app, conversation_id, &reply, params.request_id.as_deref())
+        {
+            let prompt: String = params
+                .messages
+                .iter()
+                .map(|m| m.content.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            usage::record_for_message(
+                &app,
+                &stored,
+                reported_usage,
+                params.provider.as_deref(),
+                &params.model,
+                &prompt,
+            );
+            unread::on_message_stored(&app, conversation_id);
+            titles::after_exchange(
+                &app,
+                conversation_id,
+                titles::TitleProvider {
+                    provider: params.provider.clone(),
+                    api_key: params.api_key.clone(),
+                    model: params.model.clone(),
+                    base_url: params.base_url.clone(),
+                    options: params.options.clone(),
+                },
+            );
         }
     }
+    Ok(outcome)
+}
 
-    // Try to parse as RPC response
-    if let Ok(response) = serde_json::from_str::<RpcResponse>(line) {
-        if let Some(id) = response.id {
-            let pending = app.state::<PendingRequests>();
-            let mut map = pending.lock().unwrap();
-            if let Some(tx) = map.remove(&id) {
-                let result = if let Some(err) = response.error {
-                    Err(err.message)
-                } else {
-                    Ok(response.result.unwrap_or_default())
-                };
-                let _ = tx.send(result);
+/// History is best-effort: a storage failure must never fail the chat request itself.
+fn persist_message(
+    app: &tauri::AppHandle,
+    conversation_id: &str,
+    message: &ChatMessage,
+    request_id: Option<&str>,
+) -> Option<StoredMessage> {
+    app.state::<Storage>()
+        .append_message(conversation_id, &message.role, &message.content, request_id)
+        .map_err(|err| eprintln!("[storage] failed to save message: {}", err))
+        .ok()
+}
+
+/// Forks the conversation just before the user message at `message_index`, replaces that
+/// message with `new_content` and sends it again. The original conversation is kept.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn resend_from(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    conversation_id: String,
+    message_index: usize,
+    new_content: String,
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: String,
+    base_url: Option<String>,
+    workspace_path: Option<String>,
+    request_id: Option<String>,
+    max_retries: Option<u32>,
+    template_id: Option<String>,
+) -> Result<ResendOutcome, String> {
+    if new_content.trim().is_empty() {
+        return Err("Message content is required".to_string());
+    }
+    let storage = app.state::<Storage>();
+    let original = storage
+        .conversation(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    match original.messages.get(message_index) {
+        Some(message) if message.role == "user" => {}
+        Some(_) => return Err("Only user messages can be edited and resent".to_string()),
+        None => return Err(format!("No message at index {}", message_index)),
+    }
+
+    let fork = storage
+        .fork_conversation(&conversation_id, message_index)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let mut messages: Vec<ChatMessage> = fork
+        .messages
+        .into_iter()
+        .map(|m| ChatMessage {
+            role: m.role,
+            content: m.content,
+        })
+        .collect();
+    messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: new_content,
+    });
+
+    let result = send_message(
+        app.clone(),
+        window,
+        provider,
+        api_key,
+        Some(model),
+        base_url,
+        messages,
+        workspace_path,
+        None,
+        request_id,
+        max_retries,
+        Some(fork.id.clone()),
+        template_id,
+        None,
+        None,
+        None,
+    )
+    .await?;
+    Ok(ResendOutcome {
+        conversation_id: fork.id,
+        result,
+    })
+}
+
+/// Sends the conversation again and stores the new reply as a sibling branch of the last
+/// one, with `model` or, when omitted, the conversation's model override or the model that
+/// produced the last reply. A path that already ends in an unanswered prompt is simply
+/// sent again.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn regenerate_response(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    conversation_id: String,
+    provider: Option<String>,
+    api_key: Option<String>,
+    model: Option<String>,
+    base_url: Option<String>,
+    workspace_path: Option<String>,
+    request_id: Option<String>,
+    max_retries: Option<u32>,
+    template_id: Option<String>,
+) -> Result<SendMessageOutcome, String> {
+    let storage = app.state::<Storage>();
+    let conversation = storage
+        .conversation(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let prompt_at = conversation
+        .messages
+        .iter()
+        .rposition(|m| m.role == "user")
+        .ok_or("There is no prompt to regenerate a response for")?;
+    let reply = match &conversation.messages[prompt_at + 1..] {
+        [] => None,
+        [reply] => Some(reply),
+        _ => return Err("Only the latest reply can be regenerated".to_string()),
+    };
+    let explicit = model
+        .filter(|m| !m.trim().is_empty())
+        .or(storage.conversation_settings(&conversation_id)?.model);
+    let model = match explicit {
+        Some(model) => model,
+        None => reply
+            .map(|r| storage.message_model(&r.id))
+            .transpose()?
+            .flatten()
+            .ok_or("No model recorded for the last reply; pass one explicitly")?,
+    };
+    // Only the provider and base URL come from the conversation; the model is chosen above
+    let (provider, _, base_url) =
+        effective_model_settings(&app, Some(&conversation_id), provider, String::new(), base_url);
+    let api_key = secrets::resolve_api_key(&app, provider.as_deref(), None, api_key)?;
+    // The new reply branches off the prompt; the old one stays until the send succeeds
+    let previous = reply.map(|r| r.id.clone());
+    if previous.is_some() {
+        storage.switch_branch(&conversation_id, &conversation.messages[prompt_at].id, false)?;
+    }
+    let messages = conversation
+        .messages
+        .into_iter()
+        .take(prompt_at + 1)
+        .map(|m| ChatMessage {
+            role: m.role,
+            content: m.content,
+        })
+        .collect();
+
+    let outcome = send_resolved(
+        app.clone(),
+        window.label().to_string(),
+        provider,
+        api_key,
+        model,
+        base_url,
+        ProviderOptions::default(),
+        messages,
+        workspace_path,
+        Vec::new(),
+        request_id,
+        max_retries,
+        Some(conversation_id.clone()),
+        template_id,
+        None,
+    )
+    .await;
+    if let (Err(_), Some(previous)) = (&outcome, previous) {
+        if let Err(err) = storage.switch_branch(&conversation_id, &previous, true) {
+            eprintln!("[storage] failed to restore the previous reply: {}", err);
+        }
+    }
+    outcome
+}
+
+/// Sends the same messages to every target at once. Each target streams under
+/// `{request_id}:{index}`, and results come back in target order tagged by model.
+#[tauri::command]
+async fn send_message_multi(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    targets: Vec<ModelTarget>,
+    messages: Vec<ChatMessage>,
+    workspace_path: Option<String>,
+    request_id: String,
+) -> Result<Vec<MultiResult>, String> {
+    if targets.is_empty() {
+        return Err("Pick at least one model".to_string());
+    }
+    if targets.len() > MAX_MULTI_TARGETS {
+        return Err(format!("At most {} models can be compared at once", MAX_MULTI_TARGETS));
+    }
+    // One lock for the whole fan-out; the runs share the workspace with each other only
+    let _workspace_lock = match workspace_path.as_deref() {
+        Some(workspace) if !workspace.trim().is_empty() => Some(workspace_locks::acquire(
+            &app,
+            workspace,
+            window.label(),
+            Some(&request_id),
+        )?),
+        _ => None,
+    };
+
+    let timeouts = tool_limits::load(&app);
+    let default_proxy = proxy::default_proxy(&app)?;
+    let mut requests = targets
+        .into_iter()
+        .enumerate()
+        .map(|(index, target)| {
+            Ok(SendMessageParams {
+                api_key: secrets::resolve_api_key(
+                    &app,
+                    target.provider.as_deref(),
+                    None,
+                    target.api_key,
+                )?,
+                base_url: providers::host_base_url(
+                    &app,
+                    target.provider.as_deref(),
+                    target.base_url,
+                )?,
+                provider: target.provider,
+                model: target.model,
+                messages: messages.clone(),
+                workspace_path: workspace_path.clone(),
+                workspace_roots: Vec::new(),
+                request_id: Some(format!("{}:{}", request_id, index)),
+                tool_timeouts: Some(timeouts.clone()),
+                workspace_rules: None,
+                mcp_tools: Vec::new(),
+                custom_tools: Vec::new(),
+                mode: ConversationMode::Act,
+                options: ProviderOptions {
+                    azure: target.azure.and_then(AzureDeployment::normalized),
+                    proxy: default_proxy.clone(),
+                    ..ProviderOptions::default()
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    for request in &mut requests {
+        let api_key = std::mem::take(&mut request.api_key);
+        request.api_key = vertex::authorize(&app, request.provider.as_deref(), api_key).await?;
+    }
+    for request in &requests {
+        if let Some(id) = &request.request_id {
+            app.state::<StreamBuffers>().begin(id);
+        }
+    }
+
+    let results = rpc::call_batch(&app, "sendMessage", &requests, Duration::from_secs(60)).await;
+    Ok(requests
+        .into_iter()
+        .zip(results)
+        .map(|(params, result)| {
+            let id = params.request_id.unwrap_or_default();
+            // Streamed text and usage were only needed while the request was running
+            app.state::<StreamBuffers>().take(&id);
+            usage::take_report(&app, Some(&id));
+            let (result, error) = match result {
+                Ok(result) => (Some(result), None),
+                Err(err) => (None, Some(err.message)),
+            };
+            MultiResult {
+                request_id: id,
+                provider: params.provider,
+                model: params.model,
+                result,
+                error,
             }
+        })
+        .collect())
+}
+
+/// Submits several independent prompts in one round-trip; results come back in input order.
+/// Each request is sent with its provider's saved key.
+#[tauri::command]
+async fn send_batch(
+    app: tauri::AppHandle,
+    requests: Vec<SendMessageParams>,
+) -> Result<Vec<BatchResult>, String> {
+    if requests.is_empty() {
+        return Ok(Vec::new());
+    }
+    let timeouts = tool_limits::load(&app);
+    let default_proxy = proxy::default_proxy(&app)?;
+    let mut requests = requests;
+    for request in &mut requests {
+        let provider = request.provider.as_deref();
+        let api_key = secrets::resolve_api_key(&app, provider, None, None)?;
+        request.api_key = vertex::authorize(&app, provider, api_key).await?;
+        request.base_url = providers::host_base_url(&app, provider, request.base_url.take())?;
+        request.tool_timeouts.get_or_insert_with(|| timeouts.clone());
+        if request.options.proxy.is_none() {
+            request.options.proxy = default_proxy.clone();
         }
-        // Ignore messages without id (like {ready: true})
     }
+
+    let results = rpc::call_batch(&app, "sendMessage", &requests, Duration::from_secs(60)).await;
+    Ok(requests
+        .into_iter()
+        .zip(results)
+        .map(|(params, result)| match result {
+            Ok(result) => BatchResult {
+                request_id: params.request_id,
+                result: Some(result),
+                error: None,
+            },
+            Err(err) => BatchResult {
+                request_id: params.request_id,
+                result: None,
+                error: Some(err.message),
+            },
+        })
+        .collect())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -243,73 +1344,258 @@ pub fn run() {
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_shell::init())
         .manage(PendingRequests::new(HashMap::new()))
-        .manage(Mutex::new(None::<tauri_plugin_shell::process::CommandChild>))
+        .manage(Mutex::new(ChunkAssembler::default()))
+        .manage(SidecarProcess::new(None))
+        .manage(SidecarGeneration::default())
+        .manage(OnboardingLock::default())
+        .manage(StreamBuffers::default())
+        .manage(DisplayState::default())
+        .manage(SessionRecorder::default())
+        .manage(RpcLog::default())
+        .manage(ProviderStatusCache::default())
+        .manage(UsageReports::default())
+        .manage(ReadTracker::default())
+        .manage(ActivityTracker::default())
+        .manage(WorkspaceLocks::default())
+        .manage(Changesets::default())
+        .manage(FileHistoryLock::default())
+        .manage(CheckpointLock::default())
+        .manage(Tasks::default())
+        .manage(AgentTasks::default())
+        .manage(ScheduleLock::default())
+        .manage(Terminals::default())
+        .manage(Approvals::default())
+        .manage(EnvironmentCache::default())
+        .manage(McpServers::default())
+        .manage(Permissions::default())
+        .manage(SummaryCache::default())
+        .manage(Scheduler::default())
+        .manage(Speech::default())
+        .manage(ModelCache::default())
+        .manage(AwsCredentialCache::default())
+        .manage(VertexTokenCache::default())
+        .manage(RateLimits::default())
+        .manage(KeyRotation::default())
+        .manage(WorkspaceWatchers::default())
+        .on_window_event(|window, event| {
+            display::handle_window_event(window, event);
+            unread::handle_window_event(window, event);
+        })
         .setup(|app| {
             let app_handle = app.handle().clone();
 
-            // Spawn the sidecar
-            let (mut rx, child) = app_handle
-                .shell()
-                .sidecar("agent")
-                .map_err(|e| format!("Failed to create sidecar command: {}", e))?
-                .spawn()
-                .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
-
-            // Store the child process for writing
-            {
-                let sidecar_state = app_handle.state::<Mutex<Option<tauri_plugin_shell::process::CommandChild>>>();
-                let mut guard = sidecar_state.lock().unwrap();
-                *guard = Some(child);
+            app.manage(storage::open(&app_handle)?);
+            app.manage(a11y::load(&app_handle)?);
+            app.manage(semantic_index::open(&app_handle)?);
+            // Without a sidecar, plain chat still works over the native client
+            if let Err(err) = sidecar::spawn(&app_handle) {
+                eprintln!("[sidecar] {}", err);
             }
-
-            // Handle sidecar output in background
-            let app_handle_clone = app_handle.clone();
-            tauri::async_runtime::spawn(async move {
-                let mut stdout_buf = String::new();
-                let mut stderr_buf = String::new();
-
-                while let Some(event) = rx.recv().await {
-                    match event {
-                        CommandEvent::Stdout(line_bytes) => {
-                            if let Ok(chunk) = String::from_utf8(line_bytes) {
-                                stdout_buf.push_str(&chunk);
-                                while let Some(pos) = stdout_buf.find('\n') {
-                                    let mut line = stdout_buf[..pos].to_string();
-                                    stdout_buf = stdout_buf[pos + 1..].to_string();
-                                    if line.ends_with('\r') {
-                                        line.pop();
-                                    }
-                                    handle_sidecar_output(&app_handle_clone, &line);
-                                }
-                            }
-                        }
-                        CommandEvent::Stderr(line_bytes) => {
-                            if let Ok(chunk) = String::from_utf8(line_bytes) {
-                                stderr_buf.push_str(&chunk);
-                                while let Some(pos) = stderr_buf.find('\n') {
-                                    let mut line = stderr_buf[..pos].to_string();
-                                    stderr_buf = stderr_buf[pos + 1..].to_string();
-                                    if line.ends_with('\r') {
-                                        line.pop();
-                                    }
-                                    eprintln!("[sidecar stderr] {}", line);
-                                }
-                            }
-                        }
-                        CommandEvent::Error(err) => {
-                            eprintln!("[sidecar error] {}", err);
-                        }
-                        CommandEvent::Terminated(status) => {
-                            eprintln!("[sidecar terminated] {:?}", status);
-                        }
-                        _ => {}
-                    }
-                }
-            });
+            provider_status::start_monitor(&app_handle);
+            schedules::start(&app_handle);
+            mcp::start(&app_handle);
+            unread::refresh_badge(&app_handle);
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![send_message, ping_sidecar, warmup_model])
+        .invoke_handler(tauri::generate_handler![
+            send_message,
+            ping_sidecar,
+            warmup_model,
+            stats::get_workspace_stats,
+            todos::scan_todos,
+            compliance::check_license_compliance,
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::expand_snippet,
+            send_batch,
+            resend_from,
+            regenerate_response,
+            send_message_multi,
+            scheduler::schedule_message,
+            scheduler::list_scheduled_messages,
+            scheduler::cancel_scheduled_message,
+            slash_commands::list_slash_commands,
+            slash_commands::save_slash_command,
+            slash_commands::delete_slash_command,
+            slash_commands::run_slash_command,
+            actions::list_actions,
+            actions::invoke_action,
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            onboarding::reset_onboarding,
+            feedback::get_feedback_config,
+            feedback::set_feedback_config,
+            feedback::submit_feedback,
+            storage::save_message,
+            storage::get_conversation,
+            storage::delete_conversation,
+            a11y::get_a11y_settings,
+            a11y::set_a11y_settings,
+            storage::list_conversations,
+            storage::save_draft,
+            storage::get_draft,
+            storage::get_conversation_settings,
+            storage::set_conversation_settings,
+            storage::tag_conversation,
+            storage::list_tags,
+            storage::pin_conversation,
+            storage::archive_conversation,
+            storage::load_conversation,
+            storage::list_branches,
+            storage::switch_branch,
+            export::export_conversation,
+            display::restore_window_zoom,
+            display::set_window_zoom,
+            import::import_conversations,
+            recorder::start_recording,
+            recorder::stop_recording,
+            recorder::record_session_event,
+            recorder::replay_session,
+            recorder::stop_replay,
+            incidents::get_incident_settings,
+            incidents::set_incident_settings,
+            incidents::list_incidents,
+            provider_status::get_provider_status,
+            usage::get_usage_summary,
+            prompt_templates::list_prompt_templates,
+            prompt_templates::save_prompt_template,
+            prompt_templates::delete_prompt_template,
+            timezone::get_time_zone,
+            timezone::local_time_to_utc,
+            timezone::utc_to_local_time,
+            unread::report_conversation_view,
+            unread::get_unread_summary,
+            tool_limits::get_tool_timeouts,
+            tool_limits::set_tool_timeouts,
+            tool_limits::kill_tool_execution,
+            transcription::get_stt_settings,
+            transcription::set_stt_settings,
+            transcription::transcribe_audio,
+            speech::speak_text,
+            speech::stop_speaking,
+            speech::get_speech_settings,
+            speech::set_speech_settings,
+            workspace_locks::get_workspace_locks,
+            workspace_locks::force_unlock,
+            changesets::propose_changeset,
+            changesets::get_changeset,
+            changesets::apply_changeset,
+            changesets::apply_partial,
+            changesets::dry_run_changeset,
+            changesets::discard_changeset,
+            dry_run::dry_run_plan,
+            secrets::set_api_key,
+            secrets::has_api_key,
+            models::list_models,
+            connection::test_connection,
+            profiles::list_provider_profiles,
+            profiles::create_provider_profile,
+            profiles::update_provider_profile,
+            profiles::delete_provider_profile,
+            models::discover_local_models,
+            bedrock::get_bedrock_settings,
+            bedrock::set_bedrock_settings,
+            vertex::get_vertex_settings,
+            vertex::set_vertex_settings,
+            proxy::get_default_proxy,
+            proxy::set_default_proxy,
+            usage::get_usage_report,
+            fallback::get_fallback_chain,
+            fallback::set_fallback_chain,
+            secrets::add_api_key,
+            secrets::list_api_keys,
+            secrets::remove_api_key,
+            secrets::set_key_rotation,
+            capabilities::get_model_info,
+            embeddings::embed_texts,
+            file_tree::list_workspace_tree,
+            watcher::watch_workspace,
+            watcher::unwatch_workspace,
+            search::search_workspace,
+            git::git_status,
+            git::git_diff,
+            git::git_commit,
+            git::git_branches,
+            git::git_create_branch,
+            git::git_checkout,
+            patch::preview_patch,
+            patch::apply_patch,
+            workspace_files::read_workspace_file,
+            workspace_files::write_workspace_file,
+            file_history::list_file_history,
+            file_history::revert_file,
+            semantic_index::index_workspace,
+            semantic_index::get_index_status,
+            semantic_index::semantic_search,
+            recent_workspaces::open_workspace,
+            recent_workspaces::get_recent_workspaces,
+            recent_workspaces::remove_recent_workspace,
+            recent_workspaces::set_workspace_pinned,
+            workspace_config::get_workspace_config,
+            file_tree::list_workspace_trees,
+            editor::get_editor_settings,
+            editor::set_editor_settings,
+            editor::detect_editors,
+            editor::open_in_editor,
+            reveal::reveal_in_file_manager,
+            file_preview::stat_and_preview,
+            scaffold::list_scaffold_templates,
+            scaffold::scaffold_from_template,
+            checkpoints::get_checkpoint_settings,
+            checkpoints::set_checkpoint_settings,
+            checkpoints::create_checkpoint,
+            checkpoints::list_checkpoints,
+            checkpoints::restore_checkpoint,
+            tasks::run_task,
+            tasks::cancel_task,
+            tasks::list_tasks,
+            tasks::get_task_transcript,
+            tasks::get_task_context,
+            terminal::terminal_spawn,
+            terminal::terminal_write,
+            terminal::terminal_resize,
+            terminal::list_terminals,
+            terminal::kill_terminal,
+            terminal::get_terminal_scrollback,
+            approvals::get_approval_settings,
+            approvals::set_approval_settings,
+            approvals::list_pending_approvals,
+            approvals::respond_approval,
+            environment::detect_environment,
+            workspace_files::read_file_range,
+            workspace_files::read_file_lines,
+            archive::export_zip,
+            attachments::check_attachment_secrets,
+            stats::get_language_stats,
+            mcp::list_mcp_servers,
+            mcp::add_mcp_server,
+            mcp::toggle_mcp_server,
+            mcp::remove_mcp_server,
+            mcp::read_mcp_resource,
+            permissions::get_permission_settings,
+            permissions::set_permission_settings,
+            permissions::list_pending_permissions,
+            permissions::respond_permission,
+            storage::get_conversation_mode,
+            storage::set_conversation_mode,
+            agent_tasks::submit_task,
+            agent_tasks::get_task_log,
+            schedules::create_schedule,
+            schedules::list_schedules,
+            schedules::pause_schedule,
+            schedules::delete_schedule,
+            agent_tasks::submit_parallel_task,
+            agent_tasks::get_task_tree,
+            memory::memory_set,
+            memory::memory_get,
+            memory::memory_search,
+            memory::memory_delete,
+            custom_tools::register_custom_tool,
+            custom_tools::list_custom_tools,
+            custom_tools::remove_custom_tool
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
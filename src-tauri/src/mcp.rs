@@ -0,0 +1,986 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::editor::find_program;
+use crate::permissions::{self, Access};
+use crate::{proxy, rpc, secrets};
+
+const MCP_SERVERS_FILE: &str = "mcp_servers.json";
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CALL_TIMEOUT: Duration = Duration::from_secs(120);
+// Stops paging through a server that never runs out of tools or resources
+const MAX_LISTED: usize = 1000;
+// Tool names the model APIs accept
+const MAX_TOOL_NAME: usize = 64;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// How to reach a server. `env` and `headers` usually carry tokens, so once the server is
+/// saved they live in the keychain and the config keeps only their names.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum McpTransport {
+    /// A local process speaking JSON-RPC over stdin and stdout
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        env: HashMap<String, String>,
+        #[serde(default)]
+        cwd: Option<String>,
+    },
+    /// A server reached over HTTP: events stream from `url`, messages are posted to the
+    /// endpoint it announces
+    Sse {
+        url: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        headers: HashMap<String, String>,
+    },
+}
+
+impl McpTransport {
+    fn secrets_mut(&mut self) -> &mut HashMap<String, String> {
+        match self {
+            McpTransport::Stdio { env, .. } => env,
+            McpTransport::Sse { headers, .. } => headers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    id: String,
+    name: String,
+    transport: McpTransport,
+    /// The environment variables or headers kept in the keychain, sorted
+    #[serde(default)]
+    secret_names: Vec<String>,
+    enabled: bool,
+    added_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct McpServerFile {
+    servers: Vec<McpServerConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default = "empty_schema")]
+    input_schema: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    uri: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum McpState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatus {
+    #[serde(flatten)]
+    config: McpServerConfig,
+    state: McpState,
+    error: Option<String>,
+    tools: Vec<McpTool>,
+    resources: Vec<McpResource>,
+}
+
+/// A server tool as the agent sees it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AgentMcpTool {
+    /// Unique across servers, e.g. `mcp__github__create_issue`
+    name: String,
+    server: String,
+    tool: String,
+    description: String,
+    input_schema: Value,
+}
+
+enum Link {
+    Stdio {
+        stdin: Arc<Mutex<ChildStdin>>,
+        child: Mutex<Child>,
+    },
+    Sse {
+        client: reqwest::Client,
+        endpoint: Url,
+        headers: HeaderMap,
+        reader: tauri::async_runtime::JoinHandle<()>,
+    },
+}
+
+struct Connection {
+    /// Tells a stale reader apart from the one of the current connection
+    session: String,
+    link: Link,
+    next_id: AtomicU64,
+    pending: Pending,
+}
+
+#[derive(Default)]
+struct ServerState {
+    connection: Option<Arc<Connection>>,
+    connecting: bool,
+    error: Option<String>,
+    tools: Vec<McpTool>,
+    resources: Vec<McpResource>,
+}
+
+/// Live connections, by server id. A server without an entry was never connected.
+#[derive(Default)]
+pub(crate) struct McpServers(Mutex<HashMap<String, ServerState>>);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct McpRequest {
+    call_id: String,
+    server: String,
+    tool: String,
+    #[serde(default)]
+    arguments: Value,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object", "properties": {} })
+}
+
+fn write_line(stdin: &Mutex<ChildStdin>, message: &Value) -> Result<(), String> {
+    let mut line = serde_json::to_string(message)
+        .map_err(|e| format!("Failed to encode MCP message: {}", e))?;
+    line.push('\n');
+    let mut stdin = stdin.lock().unwrap();
+    stdin
+        .write_all(line.as_bytes())
+        .and_then(|_| stdin.flush())
+        .map_err(|e| format!("Failed to write to the MCP server: {}", e))
+}
+
+async fn post(
+    client: &reqwest::Client,
+    endpoint: &Url,
+    headers: &HeaderMap,
+    message: &Value,
+) -> Result<(), String> {
+    let response = client
+        .post(endpoint.clone())
+        .headers(headers.clone())
+        .json(message)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the MCP server: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("The MCP server returned {}", response.status()));
+    }
+    Ok(())
+}
+
+impl Link {
+    async fn send(&self, message: &Value) -> Result<(), String> {
+        match self {
+            Link::Stdio { stdin, .. } => write_line(stdin, message),
+            Link::Sse {
+                client,
+                endpoint,
+                headers,
+                ..
+            } => post(client, endpoint, headers, message).await,
+        }
+    }
+}
+
+impl Connection {
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(err) = self.link.send(&message).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(err);
+        }
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err("The MCP server disconnected".to_string()),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(format!("The MCP server did not answer {} in time", method))
+            }
+        }
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        self.link.send(&message).await
+    }
+
+    fn close(&self) {
+        match &self.link {
+            Link::Stdio { child, .. } => {
+                let mut child = child.lock().unwrap();
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            Link::Sse { reader, .. } => reader.abort(),
+        }
+        fail_pending(&self.pending, "The MCP server was disconnected");
+    }
+}
+
+/// Settles the request a response answers. Requests from the server get the reply to
+/// send back: servers may ping, and nothing else they can ask for is supported.
+fn handle_message(pending: &Pending, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    if message.get("method").and_then(Value::as_str).is_some() {
+        let id = id?;
+        return Some(if message["method"] == "ping" {
+            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        } else {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": "Method not found" }
+            })
+        });
+    }
+    let waiter = pending.lock().unwrap().remove(&id?.as_u64()?)?;
+    let result = match message.get("error") {
+        Some(error) => Err(error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("The MCP request failed")
+            .to_string()),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let _ = waiter.send(result);
+    None
+}
+
+fn fail_pending(pending: &Pending, reason: &str) {
+    for (_, waiter) in pending.lock().unwrap().drain() {
+        let _ = waiter.send(Err(reason.to_string()));
+    }
+}
+
+fn spawn_stdio(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    cwd: Option<&str>,
+    pending: Pending,
+    on_close: impl FnOnce(String) + Send + 'static,
+) -> Result<Link, String> {
+    // Apps started from the desktop get a short PATH, so npx and friends are looked up
+    let program = find_program(command).unwrap_or_else(|| PathBuf::from(command));
+    let mut process = Command::new(program);
+    process
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    if let Some(cwd) = cwd.filter(|c| !c.trim().is_empty()) {
+        process.current_dir(cwd);
+    }
+    let mut child = process
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", command, e))?;
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        let _ = child.kill();
+        return Err(format!("Failed to connect to {}", command));
+    };
+    let stdin = Arc::new(Mutex::new(stdin));
+    let replies = stdin.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            // Some servers log to stdout; only JSON lines are messages
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if let Some(reply) = handle_message(&pending, message) {
+                let _ = write_line(&replies, &reply);
+            }
+        }
+        fail_pending(&pending, "The MCP server exited");
+        on_close("The MCP server exited".to_string());
+    });
+    Ok(Link::Stdio {
+        stdin,
+        child: Mutex::new(child),
+    })
+}
+
+/// Splits one server-sent event into its name and data.
+fn parse_event(block: &str) -> (String, String) {
+    let mut name = "message".to_string();
+    let mut data = Vec::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    (name, data.join("\n"))
+}
+
+async fn read_events(
+    client: &reqwest::Client,
+    url: &Url,
+    headers: &HeaderMap,
+    pending: &Pending,
+    endpoint_tx: &mut Option<oneshot::Sender<Result<Url, String>>>,
+) -> Result<(), String> {
+    let mut response = client
+        .get(url.clone())
+        .headers(headers.clone())
+        .header(ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    let mut endpoint: Option<Url> = None;
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("The MCP event stream failed: {}", e))?
+    {
+        buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+        while let Some(at) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = buffer.drain(..at + 2).collect();
+            let (name, data) = parse_event(&String::from_utf8_lossy(&block));
+            if name == "endpoint" {
+                let joined = url
+                    .join(data.trim())
+                    .map_err(|e| format!("Invalid MCP endpoint {}: {}", data, e))?;
+                // Messages must not be posted anywhere but the server that was configured
+                if joined.origin() != url.origin() {
+                    return Err(format!(
+                        "The MCP server announced a foreign endpoint {}",
+                        joined
+                    ));
+                }
+                if let Some(tx) = endpoint_tx.take() {
+                    let _ = tx.send(Ok(joined.clone()));
+                }
+                endpoint = Some(joined);
+            } else if let Ok(message) = serde_json::from_str::<Value>(&data) {
+                if let (Some(reply), Some(endpoint)) = (handle_message(pending, message), &endpoint)
+                {
+                    let _ = post(client, endpoint, headers, &reply).await;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_events(
+    client: reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    pending: Pending,
+    endpoint_tx: oneshot::Sender<Result<Url, String>>,
+    on_close: impl FnOnce(String),
+) {
+    let mut endpoint_tx = Some(endpoint_tx);
+    let reason = match read_events(&client, &url, &headers, &pending, &mut endpoint_tx).await {
+        Ok(()) => "The MCP server closed the connection".to_string(),
+        Err(err) => err,
+    };
+    fail_pending(&pending, &reason);
+    match endpoint_tx {
+        Some(tx) => {
+            let _ = tx.send(Err(reason));
+        }
+        None => on_close(reason),
+    }
+}
+
+fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| format!("Invalid header name {}: {}", name, e))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+async fn list_all<T: DeserializeOwned>(
+    connection: &Connection,
+    method: &str,
+    key: &str,
+) -> Result<Vec<T>, String> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let mut page = connection.request(method, params, CONNECT_TIMEOUT).await?;
+        let batch = page.get_mut(key).map(Value::take).unwrap_or(json!([]));
+        items.extend(
+            serde_json::from_value::<Vec<T>>(batch)
+                .map_err(|e| format!("Invalid {} reply: {}", method, e))?,
+        );
+        cursor = page
+            .get("nextCursor")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        if cursor.is_none() || items.len() >= MAX_LISTED {
+            return Ok(items);
+        }
+    }
+}
+
+async fn handshake(connection: &Connection) -> Result<(Vec<McpTool>, Vec<McpResource>), String> {
+    let init = connection
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "OhMyCowork", "version": env!("CARGO_PKG_VERSION") }
+            }),
+            CONNECT_TIMEOUT,
+        )
+        .await?;
+    connection
+        .notify("notifications/initialized", json!({}))
+        .await?;
+    let offers = |capability: &str| init["capabilities"].get(capability).is_some();
+    let tools = if offers("tools") {
+        list_all(connection, "tools/list", "tools").await?
+    } else {
+        Vec::new()
+    };
+    let resources = if offers("resources") {
+        list_all(connection, "resources/list", "resources").await?
+    } else {
+        Vec::new()
+    };
+    Ok((tools, resources))
+}
+
+/// Forgets a connection whose process exited or whose stream ended, unless it was
+/// replaced in the meantime.
+fn connection_lost(app: &tauri::AppHandle, id: &str, session: &str, reason: String) {
+    {
+        let servers = app.state::<McpServers>();
+        let mut servers = servers.0.lock().unwrap();
+        let Some(state) = servers.get_mut(id) else {
+            return;
+        };
+        if state
+            .connection
+            .as_ref()
+            .is_none_or(|c| c.session != session)
+        {
+            return;
+        }
+        state.connection = None;
+        state.error = Some(reason);
+    }
+    emit_status(app, id);
+}
+
+async fn open(
+    app: &tauri::AppHandle,
+    config: &McpServerConfig,
+) -> Result<(Arc<Connection>, Vec<McpTool>, Vec<McpResource>), String> {
+    let session = Uuid::new_v4().to_string();
+    let pending: Pending = Arc::default();
+    let on_close = {
+        let app = app.clone();
+        let id = config.id.clone();
+        let session = session.clone();
+        move |reason: String| connection_lost(&app, &id, &session, reason)
+    };
+    let transport = transport_with_secrets(config)?;
+    let link = match &transport {
+        McpTransport::Stdio {
+            command,
+            args,
+            env,
+            cwd,
+        } => spawn_stdio(
+            command,
+            args,
+            env,
+            cwd.as_deref(),
+            pending.clone(),
+            on_close,
+        )?,
+        McpTransport::Sse { url, headers } => {
+            let url = Url::parse(url.trim()).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+            let headers = header_map(headers)?;
+            let client = proxy::http_client(app);
+            let (tx, rx) = oneshot::channel();
+            let reader = tauri::async_runtime::spawn(run_events(
+                client.clone(),
+                url.clone(),
+                headers.clone(),
+                pending.clone(),
+                tx,
+                on_close,
+            ));
+            let endpoint = match tokio::time::timeout(CONNECT_TIMEOUT, rx).await {
+                Ok(Ok(Ok(endpoint))) => endpoint,
+                Ok(Ok(Err(err))) => return Err(err),
+                _ => {
+                    reader.abort();
+                    return Err(format!("{} did not announce a message endpoint", url));
+                }
+            };
+            Link::Sse {
+                client,
+                endpoint,
+                headers,
+                reader,
+            }
+        }
+    };
+    let connection = Arc::new(Connection {
+        session,
+        link,
+        next_id: AtomicU64::new(1),
+        pending,
+    });
+    match handshake(&connection).await {
+        Ok((tools, resources)) => Ok((connection, tools, resources)),
+        Err(err) => {
+            connection.close();
+            Err(err)
+        }
+    }
+}
+
+/// Keychain entry for the environment variables or headers of server `id`.
+fn secrets_entry(id: &str) -> String {
+    format!("mcp-{}", id)
+}
+
+/// Moves the values of the transport's `env` or `headers` into the keychain.
+fn store_secrets(config: &mut McpServerConfig) -> Result<(), String> {
+    let values = std::mem::take(config.transport.secrets_mut());
+    if values.is_empty() {
+        return Ok(());
+    }
+    let mut names: Vec<String> = values.keys().cloned().collect();
+    names.sort();
+    let json = serde_json::to_string(&values).map_err(|e| e.to_string())?;
+    secrets::store_api_key(&secrets_entry(&config.id), &json)?;
+    config.secret_names = names;
+    Ok(())
+}
+
+/// The transport of `config` with its `env` or `headers` values back from the keychain.
+fn transport_with_secrets(config: &McpServerConfig) -> Result<McpTransport, String> {
+    let mut transport = config.transport.clone();
+    if config.secret_names.is_empty() {
+        return Ok(transport);
+    }
+    let stored = secrets::api_key(&secrets_entry(&config.id))?
+        .ok_or_else(|| format!("The saved credentials for {} are missing", config.name))?;
+    *transport.secrets_mut() = serde_json::from_str(&stored)
+        .map_err(|e| format!("The saved credentials for {} are invalid: {}", config.name, e))?;
+    Ok(transport)
+}
+
+fn load_servers(app: &tauri::AppHandle) -> Result<Vec<McpServerConfig>, String> {
+    let mut servers = load_json::<McpServerFile>(app, MCP_SERVERS_FILE)?.servers;
+    // Files from before credentials moved to the keychain still hold them in plain text
+    if servers
+        .iter_mut()
+        .any(|s| !s.transport.secrets_mut().is_empty())
+    {
+        for server in &mut servers {
+            store_secrets(server)?;
+        }
+        save_servers(app, servers.clone())?;
+    }
+    Ok(servers)
+}
+
+fn save_servers(app: &tauri::AppHandle, servers: Vec<McpServerConfig>) -> Result<(), String> {
+    save_json(app, MCP_SERVERS_FILE, &McpServerFile { servers })
+}
+
+fn status(app: &tauri::AppHandle, config: McpServerConfig) -> McpServerStatus {
+    let servers = app.state::<McpServers>();
+    let servers = servers.0.lock().unwrap();
+    let state = servers.get(&config.id);
+    McpServerStatus {
+        state: match state {
+            Some(s) if s.connecting => McpState::Connecting,
+            Some(s) if s.connection.is_some() => McpState::Connected,
+            Some(s) if s.error.is_some() => McpState::Failed,
+            _ => McpState::Disconnected,
+        },
+        error: state.and_then(|s| s.error.clone()),
+        tools: state.map(|s| s.tools.clone()).unwrap_or_default(),
+        resources: state.map(|s| s.resources.clone()).unwrap_or_default(),
+        config,
+    }
+}
+
+fn emit_status(app: &tauri::AppHandle, id: &str) {
+    let config = load_servers(app)
+        .ok()
+        .and_then(|servers| servers.into_iter().find(|s| s.id == id));
+    if let Some(config) = config {
+        let _ = app.emit("mcp:status", status(app, config));
+    }
+}
+
+/// Connects to `config` unless a connection is up or on its way. Failures are kept on
+/// the server's status rather than returned.
+async fn connect(app: &tauri::AppHandle, config: &McpServerConfig) {
+    {
+        let servers = app.state::<McpServers>();
+        let mut servers = servers.0.lock().unwrap();
+        let state = servers.entry(config.id.clone()).or_default();
+        if state.connecting || state.connection.is_some() {
+            return;
+        }
+        state.connecting = true;
+        state.error = None;
+    }
+    emit_status(app, &config.id);
+    let opened = open(app, config).await;
+    {
+        let servers = app.state::<McpServers>();
+        let mut servers = servers.0.lock().unwrap();
+        // Disabled or removed while connecting
+        let Some(state) = servers.get_mut(&config.id) else {
+            if let Ok((connection, _, _)) = opened {
+                connection.close();
+            }
+            return;
+        };
+        state.connecting = false;
+        match opened {
+            Ok((connection, tools, resources)) => {
+                state.connection = Some(connection);
+                state.tools = tools;
+                state.resources = resources;
+            }
+            Err(err) => {
+                eprintln!("[mcp] failed to connect to {}: {}", config.name, err);
+                state.error = Some(err);
+            }
+        }
+    }
+    emit_status(app, &config.id);
+}
+
+fn disconnect(app: &tauri::AppHandle, id: &str) {
+    let removed = app.state::<McpServers>().0.lock().unwrap().remove(id);
+    if let Some(connection) = removed.and_then(|state| state.connection) {
+        connection.close();
+    }
+}
+
+fn connection(app: &tauri::AppHandle, id: &str) -> Result<Arc<Connection>, String> {
+    app.state::<McpServers>()
+        .0
+        .lock()
+        .unwrap()
+        .get(id)
+        .and_then(|state| state.connection.clone())
+        .ok_or_else(|| "The MCP server is not connected".to_string())
+}
+
+fn validate(name: &str, transport: &McpTransport) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("MCP server name is required".to_string());
+    }
+    match transport {
+        McpTransport::Stdio { command, .. } if command.trim().is_empty() => {
+            Err("MCP server command is required".to_string())
+        }
+        McpTransport::Sse { url, .. } => match Url::parse(url.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+            Ok(_) => Err(format!("Expected an http or https URL: {}", url)),
+            Err(e) => Err(format!("Invalid URL {}: {}", url, e)),
+        },
+        McpTransport::Stdio { .. } => Ok(()),
+    }
+}
+
+/// `mcp__<server>__<tool>`, cut to what model APIs accept.
+fn agent_tool_name(server: &str, tool: &str) -> String {
+    let clean = |s: &str| {
+        s.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>()
+    };
+    let mut name = format!("mcp__{}__{}", clean(server), clean(tool));
+    name.truncate(MAX_TOOL_NAME);
+    name
+}
+
+/// Connects the enabled servers in the background at startup.
+pub(crate) fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let configs = load_servers(&app).unwrap_or_default();
+        for config in configs.iter().filter(|s| s.enabled) {
+            connect(&app, config).await;
+        }
+    });
+}
+
+/// Tools of the connected servers for the agent. A server that failed is skipped until
+/// it is toggled again.
+pub(crate) fn agent_tools(app: &tauri::AppHandle) -> Vec<AgentMcpTool> {
+    let configs: Vec<McpServerConfig> = load_servers(app)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.enabled)
+        .collect();
+    let servers = app.state::<McpServers>();
+    let servers = servers.0.lock().unwrap();
+    let mut tools: Vec<AgentMcpTool> = Vec::new();
+    for config in &configs {
+        let Some(state) = servers.get(&config.id).filter(|s| s.connection.is_some()) else {
+            continue;
+        };
+        for tool in &state.tools {
+            let name = agent_tool_name(&config.name, &tool.name);
+            if tools.iter().any(|t| t.name == name) {
+                continue;
+            }
+            tools.push(AgentMcpTool {
+                name,
+                server: config.id.clone(),
+                tool: tool.name.clone(),
+                description: format!(
+                    "[{}] {}",
+                    config.name,
+                    tool.description.as_deref().unwrap_or(&tool.name)
+                ),
+                input_schema: tool.input_schema.clone(),
+            });
+        }
+    }
+    tools
+}
+
+/// The text of a `tools/call` result; other content kinds are named, not passed on.
+fn tool_output(result: &Value) -> Result<String, String> {
+    let text = result["content"]
+        .as_array()
+        .map(|parts| {
+            parts
+                .iter()
+                .map(|part| match part["type"].as_str() {
+                    Some("text") => part["text"].as_str().unwrap_or_default().to_string(),
+                    Some("resource") => part["resource"]["text"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("[resource {}]", part["resource"]["uri"])),
+                    Some(kind) => format!("[{} content omitted]", kind),
+                    None => String::new(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_else(|| result.to_string());
+    if result["isError"].as_bool() == Some(true) {
+        Err(text)
+    } else {
+        Ok(text)
+    }
+}
+
+/// `{event:"mcp_request", callId, server, tool, arguments}` from the sidecar: runs the
+/// tool on its server and answers with `mcpReply`.
+pub(crate) fn handle_request(app: &tauri::AppHandle, value: Value) {
+    let request: McpRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[mcp] malformed request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match permissions::check_mode(
+            &app,
+            request.request_id.as_deref(),
+            &request.tool,
+            Access::Execute,
+            &format!("running {}", request.tool),
+        )
+        .and_then(|()| connection(&app, &request.server))
+        {
+            Ok(connection) => connection
+                .request(
+                    "tools/call",
+                    json!({ "name": request.tool, "arguments": request.arguments }),
+                    CALL_TIMEOUT,
+                )
+                .await
+                .and_then(|result| tool_output(&result)),
+            Err(err) => Err(err),
+        };
+        rpc::reply_to_sidecar(&app, "mcpReply", &request.call_id, result).await;
+    });
+}
+
+/// Configured servers with their connection state and what they offer. Changes arrive as
+/// `mcp:status` events.
+#[tauri::command]
+pub fn list_mcp_servers(app: tauri::AppHandle) -> Result<Vec<McpServerStatus>, String> {
+    Ok(load_servers(&app)?
+        .into_iter()
+        .map(|config| status(&app, config))
+        .collect())
+}
+
+/// Saves a server and, unless `enabled` is false, connects to it.
+#[tauri::command]
+pub async fn add_mcp_server(
+    app: tauri::AppHandle,
+    name: String,
+    transport: McpTransport,
+    enabled: Option<bool>,
+) -> Result<McpServerStatus, String> {
+    validate(&name, &transport)?;
+    let mut servers = load_servers(&app)?;
+    if servers
+        .iter()
+        .any(|s| s.name.eq_ignore_ascii_case(name.trim()))
+    {
+        return Err(format!(
+            "An MCP server named {} already exists",
+            name.trim()
+        ));
+    }
+    let mut config = McpServerConfig {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        transport,
+        secret_names: Vec::new(),
+        enabled: enabled.unwrap_or(true),
+        added_at: now_millis(),
+    };
+    store_secrets(&mut config)?;
+    servers.push(config.clone());
+    save_servers(&app, servers)?;
+    if config.enabled {
+        connect(&app, &config).await;
+    }
+    Ok(status(&app, config))
+}
+
+/// Enables and connects a server, or disables and disconnects it. Enabling a server that
+/// failed to connect tries again.
+#[tauri::command]
+pub async fn toggle_mcp_server(
+    app: tauri::AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<McpServerStatus, String> {
+    let mut servers = load_servers(&app)?;
+    let config = servers
+        .iter_mut()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Unknown MCP server: {}", id))?;
+    config.enabled = enabled;
+    let config = config.clone();
+    save_servers(&app, servers)?;
+    if enabled {
+        connect(&app, &config).await;
+    } else {
+        disconnect(&app, &id);
+        let _ = app.emit("mcp:status", status(&app, config.clone()));
+    }
+    Ok(status(&app, config))
+}
+
+#[tauri::command]
+pub fn remove_mcp_server(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut servers = load_servers(&app)?;
+    let before = servers.len();
+    servers.retain(|s| s.id != id);
+    if servers.len() == before {
+        return Ok(false);
+    }
+    save_servers(&app, servers)?;
+    disconnect(&app, &id);
+    secrets::store_api_key(&secrets_entry(&id), "")?;
+    Ok(true)
+}
+
+/// Reads one of a connected server's resources: `{contents:[{uri, mimeType, text|blob}]}`.
+#[tauri::command]
+pub async fn read_mcp_resource(
+    app: tauri::AppHandle,
+    id: String,
+    uri: String,
+) -> Result<Value, String> {
+    connection(&app, &id)?
+        .request("resources/read", json!({ "uri": uri }), CALL_TIMEOUT)
+        .await
+}
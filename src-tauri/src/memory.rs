@@ -0,0 +1,269 @@
+use serde::Deserialize;
+use tauri::Manager;
+
+use crate::redaction;
+use crate::rpc;
+use crate::storage::{MemoryEntry, Storage};
+use crate::workspace::workspace_root;
+
+const MAX_KEY_CHARS: usize = 120;
+const MAX_VALUE_BYTES: usize = 8 * 1024;
+const MAX_ENTRIES: usize = 500;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+// How much memory goes into the system prompt; the agent searches for the rest
+const MAX_CONTEXT_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MemoryAction {
+    Set,
+    Get,
+    Search,
+    Delete,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryRequest {
+    call_id: String,
+    workspace: String,
+    action: MemoryAction,
+    #[serde(default)]
+    key: Option<String>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    query: Option<String>,
+}
+
+/// Memory is kept per canonical workspace root, so every path to a folder shares it.
+fn memory_scope(workspace: &str) -> Result<String, String> {
+    Ok(workspace_root(workspace)?.to_string_lossy().into_owned())
+}
+
+fn normalize_key(key: &str) -> Result<String, String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("Memory key is empty".to_string());
+    }
+    if key.chars().count() > MAX_KEY_CHARS {
+        return Err(format!(
+            "Memory keys can have at most {} characters",
+            MAX_KEY_CHARS
+        ));
+    }
+    Ok(key.to_string())
+}
+
+pub(crate) fn set(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    key: &str,
+    value: &str,
+) -> Result<MemoryEntry, String> {
+    let scope = memory_scope(workspace)?;
+    let key = normalize_key(key)?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("Memory value is empty".to_string());
+    }
+    if value.len() > MAX_VALUE_BYTES {
+        return Err(format!(
+            "Memory values can have at most {} KB",
+            MAX_VALUE_BYTES / 1024
+        ));
+    }
+    let storage = app.state::<Storage>();
+    if storage.memory(&scope, &key)?.is_none() && storage.memories(&scope)?.len() >= MAX_ENTRIES {
+        return Err(format!(
+            "A workspace can remember at most {} entries; delete some first",
+            MAX_ENTRIES
+        ));
+    }
+    // Notes end up in system prompts, so they never hold a recognizable secret
+    let value = redaction::mask_tokens(value).text;
+    storage.set_memory(&scope, &key, &value)
+}
+
+/// Entries matching every word of `query` in their key or value, those matching in the
+/// key first; with an empty query, the most recently updated.
+pub(crate) fn search(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<MemoryEntry>, String> {
+    let scope = memory_scope(workspace)?;
+    let entries = app.state::<Storage>().memories(&scope)?;
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut scored: Vec<(usize, MemoryEntry)> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let key = entry.key.to_lowercase();
+            let value = entry.value.to_lowercase();
+            if !terms
+                .iter()
+                .all(|term| key.contains(term) || value.contains(term))
+            {
+                return None;
+            }
+            let in_key = terms.iter().filter(|term| key.contains(*term)).count();
+            Some((in_key, entry))
+        })
+        .collect();
+    // Stable, so entries with the same score stay most recent first
+    scored.sort_by_key(|(in_key, _)| std::cmp::Reverse(*in_key));
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, entry)| entry)
+        .collect())
+}
+
+/// The workspace's memory as a Markdown section for the agent's system prompt, most
+/// recently updated first. `None` when nothing has been remembered.
+pub(crate) fn context_block(app: &tauri::AppHandle, workspace: &str) -> Option<String> {
+    let scope = memory_scope(workspace).ok()?;
+    let entries = match app.state::<Storage>().memories(&scope) {
+        Ok(entries) if !entries.is_empty() => entries,
+        Ok(_) => return None,
+        Err(err) => {
+            eprintln!("[memory] {}", err);
+            return None;
+        }
+    };
+    let mut out = String::from(
+        "## Project memory\nNotes saved in earlier sessions about this workspace. Keep them \
+         current with the `memory` tool when you learn a convention or the user corrects \
+         you.\n",
+    );
+    let mut omitted = 0;
+    for entry in &entries {
+        let line = format!("- **{}**: {}\n", entry.key, entry.value.replace('\n', " "));
+        if out.len() + line.len() > MAX_CONTEXT_BYTES {
+            omitted += 1;
+            continue;
+        }
+        out.push_str(&line);
+    }
+    if omitted > 0 {
+        out.push_str(&format!(
+            "- ({} older entries left out; search memory to find them)\n",
+            omitted
+        ));
+    }
+    Some(out)
+}
+
+fn describe(entries: &[MemoryEntry]) -> String {
+    if entries.is_empty() {
+        return "No matching memories".to_string();
+    }
+    entries
+        .iter()
+        .map(|entry| format!("{}: {}", entry.key, entry.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn answer(app: &tauri::AppHandle, request: &MemoryRequest) -> Result<String, String> {
+    let key = || request.key.as_deref().ok_or("A key is required");
+    match request.action {
+        MemoryAction::Set => {
+            let value = request.value.as_deref().ok_or("A value is required")?;
+            let entry = set(app, &request.workspace, key()?, value)?;
+            Ok(format!("Remembered {}", entry.key))
+        }
+        MemoryAction::Get => {
+            let scope = memory_scope(&request.workspace)?;
+            let key = normalize_key(key()?)?;
+            Ok(match app.state::<Storage>().memory(&scope, &key)? {
+                Some(entry) => entry.value,
+                None => format!("Nothing is remembered under {}", key),
+            })
+        }
+        MemoryAction::Search => {
+            let query = request.query.as_deref().unwrap_or_default();
+            let entries = search(app, &request.workspace, query, DEFAULT_SEARCH_LIMIT)?;
+            Ok(describe(&entries))
+        }
+        MemoryAction::Delete => {
+            let scope = memory_scope(&request.workspace)?;
+            let key = normalize_key(key()?)?;
+            Ok(if app.state::<Storage>().delete_memory(&scope, &key)? {
+                format!("Forgot {}", key)
+            } else {
+                format!("Nothing is remembered under {}", key)
+            })
+        }
+    }
+}
+
+/// `{event:"memory_request", callId, workspace, action, key, value, query}` from the
+/// sidecar's `memory` tool, answered with `memoryReply`.
+pub(crate) fn handle_request(app: &tauri::AppHandle, value: serde_json::Value) {
+    let request: MemoryRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[memory] malformed request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = answer(&app, &request);
+        rpc::reply_to_sidecar(&app, "memoryReply", &request.call_id, result).await;
+    });
+}
+
+/// Remembers `value` under `key` for the workspace, replacing an earlier value. Saved
+/// memory goes into the system prompt of every run in the workspace.
+#[tauri::command]
+pub fn memory_set(
+    app: tauri::AppHandle,
+    workspace: String,
+    key: String,
+    value: String,
+) -> Result<MemoryEntry, String> {
+    set(&app, &workspace, &key, &value)
+}
+
+#[tauri::command]
+pub fn memory_get(
+    app: tauri::AppHandle,
+    workspace: String,
+    key: String,
+) -> Result<Option<MemoryEntry>, String> {
+    let scope = memory_scope(&workspace)?;
+    app.state::<Storage>().memory(&scope, &normalize_key(&key)?)
+}
+
+/// Entries whose key or value contain every word of `query`; all of them, most recent
+/// first, when it is empty.
+#[tauri::command]
+pub fn memory_search(
+    app: tauri::AppHandle,
+    workspace: String,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<MemoryEntry>, String> {
+    let limit = limit.unwrap_or(MAX_ENTRIES).clamp(1, MAX_ENTRIES);
+    search(
+        &app,
+        &workspace,
+        query.as_deref().unwrap_or_default(),
+        limit,
+    )
+}
+
+/// Forgets `key`. Returns `false` when nothing was remembered under it.
+#[tauri::command]
+pub fn memory_delete(
+    app: tauri::AppHandle,
+    workspace: String,
+    key: String,
+) -> Result<bool, String> {
+    let scope = memory_scope(&workspace)?;
+    app.state::<Storage>()
+        .delete_memory(&scope, &normalize_key(&key)?)
+}
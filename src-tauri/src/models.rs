@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::clock::now_millis;
+use crate::providers::{self, ProviderKind};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+// Model lists change rarely; the picker can force a refresh
+const CACHE_TTL_MS: i64 = 10 * 60 * 1000;
+// SHA-256 of an empty body, for signing GET requests
+const EMPTY_PAYLOAD_SHA256: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+// Local servers answer at once or not at all
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Local servers probed on their default ports, as provider id and kind.
+const LOCAL_SERVERS: &[(&str, ProviderKind)] = &[
+    ("lmstudio", ProviderKind::LmStudio),
+    ("ollama", ProviderKind::Ollama),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub(crate) id: String,
+    /// Display name, when the provider reports one
+    name: Option<String>,
+    context_length: Option<u64>,
+    owned_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiModels {
+    data: Vec<OpenAiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiModel {
+    id: String,
+    // OpenRouter extensions
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    context_length: Option<u64>,
+    #[serde(default)]
+    owned_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModels {
+    data: Vec<AnthropicModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModel {
+    id: String,
+    #[serde(default)]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BedrockModels {
+    model_summaries: Vec<BedrockModel>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BedrockModel {
+    model_id: String,
+    #[serde(default)]
+    model_name: Option<String>,
+    #[serde(default)]
+    provider_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VertexModels {
+    // Omitted entirely when there are none
+    #[serde(default)]
+    publisher_models: Vec<VertexModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VertexModel {
+    /// `publishers/google/models/<id>`
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTags {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModel {
+    name: String,
+}
+
+/// A local model server that answered on its default port.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalProvider {
+    provider: &'static str,
+    base_url: String,
+    models: Vec<ModelInfo>,
+}
+
+/// Model lists by provider kind, base URL and key fingerprint, with when they were fetched.
+#[derive(Default)]
+pub(crate) struct ModelCache(Mutex<HashMap<String, (i64, Vec<ModelInfo>)>>);
+
+pub(crate) fn cache_key(kind: ProviderKind, base_url: &str, api_key: Option<&str>) -> String {
+    // Keys are fingerprinted so a changed key refetches without keeping the key around
+    let mut hasher = DefaultHasher::new();
+    api_key.hash(&mut hasher);
+    format!("{:?}|{}|{:x}", kind, base_url, hasher.finish())
+}
+
+fn endpoint(kind: ProviderKind, base_url: &str) -> String {
+    match kind {
+        ProviderKind::OpenAi | ProviderKind::LmStudio => format!("{}/models", base_url),
+        ProviderKind::Anthropic => format!("{}/models?limit=1000", base_url),
+        // The resource's model catalogue; deployments need the management API
+        ProviderKind::Azure => format!(
+            "{}/openai/models?api-version={}",
+            base_url,
+            providers::AZURE_API_VERSION
+        ),
+        // Ollama's native API lives beside its OpenAI-compatible `/v1`
+        ProviderKind::Ollama => format!("{}/api/tags", base_url.trim_end_matches("/v1")),
+        // Models are listed by the control plane, not the runtime endpoint
+        ProviderKind::Bedrock => format!(
+            "{}/foundation-models?byOutputModality=TEXT",
+            base_url
+                .split("/openai")
+                .next()
+                .unwrap_or(base_url)
+                .replacen("bedrock-runtime.", "bedrock.", 1)
+        ),
+        // Google's publisher catalogue, outside the project-scoped endpoint
+        ProviderKind::Vertex => format!(
+            "{}/v1beta1/publishers/google/models",
+            base_url.split("/v1beta1/").next().unwrap_or(base_url)
+        ),
+    }
+}
+
+/// Why a model list request failed, kept apart so a connection test can classify it.
+#[derive(Debug)]
+pub(crate) enum FetchError {
+    Request(reqwest::Error),
+    Status(reqwest::StatusCode, String),
+    Parse(reqwest::Error),
+    /// The request couldn't be signed or authorized, e.g. no cloud credentials were found
+    Signing(String),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Request(err) => write!(f, "Model list request failed: {}", err),
+            FetchError::Status(status, detail) => {
+                write!(f, "Model list request failed ({}): {}", status, detail)
+            }
+            FetchError::Parse(err) => write!(f, "Unexpected model list response: {}", err),
+            FetchError::Signing(err) => write!(f, "Failed to sign the request: {}", err),
+        }
+    }
+}
+
+pub(crate) async fn fetch(
+    app: &tauri::AppHandle,
+    kind: ProviderKind,
+    base_url: &str,
+    api_key: Option<&str>,
+) -> Result<Vec<ModelInfo>, FetchError> {
+    fetch_within(app, kind, base_url, api_key, REQUEST_TIMEOUT).await
+}
+
+async fn fetch_within(
+    app: &tauri::AppHandle,
+    kind: ProviderKind,
+    base_url: &str,
+    api_key: Option<&str>,
+    timeout: Duration,
+) -> Result<Vec<ModelInfo>, FetchError> {
+    let vertex_token;
+    let api_key = match kind {
+        ProviderKind::Vertex => {
+            vertex_token = crate::vertex::access_token(app)
+                .await
+                .map_err(FetchError::Signing)?;
+            Some(vertex_token.as_str())
+        }
+        _ => api_key,
+    };
+    let url = endpoint(kind, base_url);
+    let mut request = crate::proxy::http_client(app).get(&url).timeout(timeout);
+    if kind == ProviderKind::Bedrock {
+        // Credential lookup may run the AWS CLI
+        let signer = app.clone();
+        let signed = tauri::async_runtime::spawn_blocking(move || {
+            crate::bedrock::signed_headers(&signer, "GET", &url, EMPTY_PAYLOAD_SHA256)
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+        .map_err(FetchError::Signing)?;
+        for (name, value) in signed {
+            request = request.header(name, value);
+        }
+    }
+    let response = providers::authorize(request, kind, api_key)
+        .send()
+        .await
+        .map_err(FetchError::Request)?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        return Err(FetchError::Status(
+            status,
+            detail.chars().take(300).collect(),
+        ));
+    }
+
+    let parse_error = FetchError::Parse;
+    let mut models: Vec<ModelInfo> = match kind {
+        ProviderKind::OpenAi | ProviderKind::LmStudio | ProviderKind::Azure => response
+            .json::<OpenAiModels>()
+            .await
+            .map_err(parse_error)?
+            .data
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.id,
+                name: m.name,
+                context_length: m.context_length,
+                owned_by: m.owned_by,
+            })
+            .collect(),
+        ProviderKind::Anthropic => response
+            .json::<AnthropicModels>()
+            .await
+            .map_err(parse_error)?
+            .data
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.id,
+                name: m.display_name,
+                context_length: None,
+                owned_by: Some("anthropic".to_string()),
+            })
+            .collect(),
+        ProviderKind::Bedrock => response
+            .json::<BedrockModels>()
+            .await
+            .map_err(parse_error)?
+            .model_summaries
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.model_id,
+                name: m.model_name,
+                context_length: None,
+                owned_by: m.provider_name,
+            })
+            .collect(),
+        ProviderKind::Vertex => response
+            .json::<VertexModels>()
+            .await
+            .map_err(parse_error)?
+            .publisher_models
+            .into_iter()
+            .map(|m| ModelInfo {
+                // The OpenAI-compatible endpoint expects `google/<id>`
+                id: format!("google/{}", m.name.rsplit('/').next().unwrap_or(&m.name)),
+                name: None,
+                context_length: None,
+                owned_by: Some("google".to_string()),
+            })
+            .collect(),
+        ProviderKind::Ollama => response
+            .json::<OllamaTags>()
+            .await
+            .map_err(parse_error)?
+            .models
+            .into_iter()
+            .map(|m| ModelInfo {
+                id: m.name,
+                name: None,
+                context_length: None,
+                owned_by: None,
+            })
+            .collect(),
+    };
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models.dedup_by(|a, b| a.id == b.id);
+    Ok(models)
+}
+
+/// Models the provider currently offers, cached for ten minutes unless `refresh` is set.
+/// Without `api_key` the keychain entry for `provider` is used.
+#[tauri::command]
+pub async fn list_models(
+    app: tauri::AppHandle,
+    provider: Option<String>,
+    base_url: Option<String>,
+    api_key: Option<String>,
+    refresh: Option<bool>,
+) -> Result<Vec<ModelInfo>, String> {
+    let kind = ProviderKind::of(provider.as_deref());
+    let base_url = providers::base_url(&app, provider.as_deref(), base_url.as_deref())?;
+    let api_key = if kind.needs_key() {
+        Some(crate::secrets::resolve_api_key(
+            &app,
+            provider.as_deref(),
+            None,
+            api_key,
+        )?)
+    } else {
+        api_key.filter(|k| !k.trim().is_empty())
+    };
+
+    let key = cache_key(kind, &base_url, api_key.as_deref());
+    if !refresh.unwrap_or(false) {
+        let cached = app
+            .state::<ModelCache>()
+            .0
+            .lock()
+            .unwrap()
+            .get(&key)
+            .cloned();
+        if let Some((fetched_at, models)) = cached {
+            if now_millis() - fetched_at < CACHE_TTL_MS {
+                return Ok(models);
+            }
+        }
+    }
+
+    let models = fetch(&app, kind, &base_url, api_key.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    cache(&app, key, models.clone());
+    Ok(models)
+}
+
+pub(crate) fn cache(app: &tauri::AppHandle, key: String, models: Vec<ModelInfo>) {
+    app.state::<ModelCache>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(key, (now_millis(), models));
+}
+
+/// Probes for Ollama and LM Studio on localhost and lists their models. Servers that
+/// aren't running are left out; both can then be used with `send_message` without a key.
+#[tauri::command]
+pub async fn discover_local_models(app: tauri::AppHandle) -> Vec<LocalProvider> {
+    let mut found = Vec::new();
+    for (provider, kind) in LOCAL_SERVERS {
+        let Ok(base_url) = providers::base_url(&app, Some(provider), None) else {
+            continue;
+        };
+        match fetch_within(&app, *kind, &base_url, None, PROBE_TIMEOUT).await {
+            Ok(models) => {
+                cache(&app, cache_key(*kind, &base_url, None), models.clone());
+                found.push(LocalProvider {
+                    provider,
+                    base_url,
+                    models,
+                });
+            }
+            Err(FetchError::Request(_)) => {}
+            Err(err) => eprintln!("[models] {} answered unexpectedly: {}", provider, err),
+        }
+    }
+    found
+}
@@ -0,0 +1,232 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::providers::{self, ProviderKind};
+use crate::rpc::{self, RpcError, ERR_TIMEOUT};
+use crate::{proxy, usage, SendMessageParams};
+
+/// The code the sidecar uses for provider failures, so retries treat both paths alike.
+const PROVIDER_ERROR: i32 = -32000;
+const DEFAULT_CHAT_PATH: &str = "/chat/completions";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// Matches the sidecar request timeout, but counted between chunks so long replies finish
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const NO_RESPONSE: &str = "No response from model.";
+const MAX_ERROR_CHARS: usize = 300;
+
+#[derive(Debug, Deserialize)]
+struct ChunkUsage {
+    prompt_tokens: i64,
+    completion_tokens: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkError {
+    message: String,
+}
+
+/// One `data:` payload of a streamed chat completion.
+#[derive(Debug, Deserialize)]
+struct Chunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<ChunkUsage>,
+    #[serde(default)]
+    error: Option<ChunkError>,
+}
+
+/// Whether the native client can talk to `provider`. Azure's deployment routing and
+/// Bedrock's request signing stay with the sidecar.
+fn supports(provider: Option<&str>) -> bool {
+    !matches!(
+        ProviderKind::of(provider),
+        ProviderKind::Azure | ProviderKind::Bedrock
+    )
+}
+
+/// Whether `params` should go over the native client: when its profile asks for it, or
+/// when no sidecar is running to take the request.
+pub(crate) fn should_use(app: &tauri::AppHandle, params: &SendMessageParams) -> bool {
+    supports(params.provider.as_deref()) && (params.options.native || !rpc::sidecar_running(app))
+}
+
+fn provider_error(message: String, status: Option<u16>, retry_after: Option<Duration>) -> RpcError {
+    let mut err = RpcError::new(PROVIDER_ERROR, message);
+    err.data = Some(json!({
+        "status": status,
+        "retryAfterMs": retry_after.map(|wait| wait.as_millis() as u64),
+    }));
+    err
+}
+
+/// `retry-after-ms`, or `Retry-After` in seconds or as an HTTP date.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return (ms.is_finite() && ms >= 0.0).then(|| Duration::from_millis(ms.ceil() as u64));
+    }
+    let value = header("retry-after")?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.timestamp_millis() - crate::clock::now_millis();
+    Some(Duration::from_millis(wait.max(0) as u64))
+}
+
+/// Forwards one streamed text fragment exactly like a sidecar `assistant_delta`.
+fn emit_delta(app: &tauri::AppHandle, request_id: Option<&str>, delta: &str) {
+    if let Some(request_id) = request_id {
+        rpc::forward_delta(
+            app,
+            json!({ "event": "assistant_delta", "requestId": request_id, "delta": delta }),
+        );
+    }
+}
+
+/// Handles one SSE event; returns `Ok(true)` once the stream says it is done.
+fn handle_event(
+    app: &tauri::AppHandle,
+    params: &SendMessageParams,
+    event: &str,
+    text: &mut String,
+) -> Result<bool, RpcError> {
+    let data: Vec<&str> = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    if data.is_empty() {
+        return Ok(false);
+    }
+    let data = data.join("\n");
+    if data.trim() == "[DONE]" {
+        return Ok(true);
+    }
+    let Ok(chunk) = serde_json::from_str::<Chunk>(&data) else {
+        return Ok(false);
+    };
+    if let Some(err) = chunk.error {
+        return Err(provider_error(err.message, None, None));
+    }
+    for choice in chunk.choices {
+        if let Some(delta) = choice.delta.content.filter(|d| !d.is_empty()) {
+            emit_delta(app, params.request_id.as_deref(), &delta);
+            text.push_str(&delta);
+        }
+    }
+    if let (Some(usage), Some(request_id)) = (chunk.usage, &params.request_id) {
+        let report = json!({
+            "requestId": request_id,
+            "model": params.model,
+            "inputTokens": usage.prompt_tokens,
+            "outputTokens": usage.completion_tokens,
+        });
+        if let Ok(report) = serde_json::from_value(report) {
+            usage::handle_report(app, report);
+        }
+    }
+    Ok(false)
+}
+
+/// Sends `params` as a plain streamed chat completion, without tools or the agent's
+/// system prompt, and returns the reply text like the sidecar's `sendMessage`.
+pub(crate) async fn send(
+    app: &tauri::AppHandle,
+    params: &SendMessageParams,
+) -> Result<String, RpcError> {
+    let base_url = providers::base_url(app, params.provider.as_deref(), params.base_url.as_deref())
+        .map_err(|e| provider_error(e, None, None))?;
+    let endpoint = params.options.endpoint.as_ref();
+    let chat_path = endpoint
+        .and_then(|e| e.chat_path.as_deref())
+        .unwrap_or(DEFAULT_CHAT_PATH);
+    let local = matches!(
+        ProviderKind::of(params.provider.as_deref()),
+        ProviderKind::Ollama | ProviderKind::LmStudio
+    );
+    let mut body = json!({
+        "model": params.model,
+        "messages": params.messages,
+        "stream": true,
+    });
+    if !local {
+        body["stream_options"] = json!({ "include_usage": true });
+    }
+
+    let mut request = proxy::client(params.options.proxy.as_ref())
+        .post(format!("{}{}", base_url, chat_path))
+        .bearer_auth(&params.api_key)
+        .json(&body);
+    if let Some(query) = endpoint.map(|e| &e.query).filter(|q| !q.is_empty()) {
+        request = request.query(query);
+    }
+    for (name, value) in params.options.headers.iter().flatten() {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let mut response = tokio::time::timeout(CONNECT_TIMEOUT, request.send())
+        .await
+        .map_err(|_| RpcError::new(ERR_TIMEOUT, "Request timed out"))?
+        .map_err(|e| provider_error(format!("Connection error: {}", e), None, None))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let wait = retry_after(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        let detail: String = serde_json::from_str::<Chunk>(&body)
+            .ok()
+            .and_then(|chunk| chunk.error)
+            .map_or(body, |err| err.message)
+            .chars()
+            .take(MAX_ERROR_CHARS)
+            .collect();
+        return Err(provider_error(
+            format!("{} (status {})", detail.trim(), status.as_u16()),
+            Some(status.as_u16()),
+            wait,
+        ));
+    }
+
+    let mut text = String::new();
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(IDLE_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| RpcError::new(ERR_TIMEOUT, "Request timed out"))?
+            .map_err(|e| provider_error(format!("Connection error: {}", e), None, None))?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        // Events split on blank lines; bytes are kept until then so no character is cut
+        pending.extend(chunk.iter().filter(|b| **b != b'\r'));
+        while let Some(end) = pending.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = pending.drain(..end + 2).collect();
+            if handle_event(app, params, &String::from_utf8_lossy(&event), &mut text)? {
+                return Ok(finish(text));
+            }
+        }
+    }
+    handle_event(app, params, &String::from_utf8_lossy(&pending), &mut text)?;
+    Ok(finish(text))
+}
+
+fn finish(text: String) -> String {
+    if text.trim().is_empty() {
+        NO_RESPONSE.to_string()
+    } else {
+        text
+    }
+}
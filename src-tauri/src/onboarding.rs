@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+
+const ONBOARDING_FILE: &str = "onboarding.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    ApiKey,
+    Workspace,
+    SidecarHealthy,
+    IndexBuilt,
+}
+
+impl OnboardingStep {
+    const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::ApiKey,
+        OnboardingStep::Workspace,
+        OnboardingStep::SidecarHealthy,
+        OnboardingStep::IndexBuilt,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            OnboardingStep::ApiKey => "Enter and validate an API key",
+            OnboardingStep::Workspace => "Choose a workspace folder",
+            OnboardingStep::SidecarHealthy => "Start the agent",
+            OnboardingStep::IndexBuilt => "Index the workspace",
+        }
+    }
+
+    fn optional(self) -> bool {
+        matches!(self, OnboardingStep::IndexBuilt)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OnboardingFile {
+    /// Completion time (ms since epoch) per finished step
+    completed: HashMap<OnboardingStep, i64>,
+}
+
+/// Serializes read-modify-write cycles on the onboarding file.
+#[derive(Default)]
+pub(crate) struct OnboardingLock(Mutex<()>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepState {
+    id: OnboardingStep,
+    title: &'static str,
+    optional: bool,
+    completed: bool,
+    completed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    steps: Vec<StepState>,
+    /// First required step that is still open
+    current_step: Option<OnboardingStep>,
+    complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StepCompletedEvent {
+    step: OnboardingStep,
+    state: OnboardingState,
+}
+
+fn build_state(file: &OnboardingFile) -> OnboardingState {
+    let steps: Vec<StepState> = OnboardingStep::ALL
+        .iter()
+        .map(|step| StepState {
+            id: *step,
+            title: step.title(),
+            optional: step.optional(),
+            completed: file.completed.contains_key(step),
+            completed_at: file.completed.get(step).copied(),
+        })
+        .collect();
+    let current_step = steps
+        .iter()
+        .find(|s| !s.optional && !s.completed)
+        .map(|s| s.id);
+
+    OnboardingState {
+        complete: current_step.is_none(),
+        current_step,
+        steps,
+    }
+}
+
+/// Marks a step as done, emitting `onboarding:step_completed` the first time only.
+pub(crate) fn complete_step(app: &tauri::AppHandle, step: OnboardingStep) -> Result<(), String> {
+    let lock = app.state::<OnboardingLock>();
+    let _guard = lock.0.lock().unwrap();
+
+    let mut file = load_json::<OnboardingFile>(app, ONBOARDING_FILE)?;
+    if file.completed.contains_key(&step) {
+        return Ok(());
+    }
+    file.completed.insert(step, now_millis());
+    save_json(app, ONBOARDING_FILE, &file)?;
+
+    let _ = app.emit(
+        "onboarding:step_completed",
+        StepCompletedEvent {
+            step,
+            state: build_state(&file),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_onboarding_state(app: tauri::AppHandle) -> Result<OnboardingState, String> {
+    let file = load_json::<OnboardingFile>(&app, ONBOARDING_FILE)?;
+    Ok(build_state(&file))
+}
+
+/// For steps only the frontend can observe, such as the workspace folder being granted.
+#[tauri::command]
+pub fn complete_onboarding_step(
+    app: tauri::AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingState, String> {
+    complete_step(&app, step)?;
+    get_onboarding_state(app)
+}
+
+#[tauri::command]
+pub fn reset_onboarding(app: tauri::AppHandle) -> Result<OnboardingState, String> {
+    let lock = app.state::<OnboardingLock>();
+    let _guard = lock.0.lock().unwrap();
+    let file = OnboardingFile::default();
+    save_json(&app, ONBOARDING_FILE, &file)?;
+    Ok(build_state(&file))
+}
@@ -0,0 +1,776 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::changesets::{restore, write_atomically};
+use crate::file_history;
+use crate::workspace::{is_binary_file, resolve_workspace_path, workspace_root};
+
+const MAX_PATCH_BYTES: usize = 5 * 1024 * 1024;
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchAction {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
+
+/// What a patch does to one file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFile {
+    /// Relative to the workspace root; the new path for renames
+    path: String,
+    old_path: Option<String>,
+    action: PatchAction,
+    additions: usize,
+    deletions: usize,
+    hunks: usize,
+    /// Hunks whose context was found away from the line the diff gives
+    shifted_hunks: usize,
+}
+
+/// Why part of a patch doesn't apply to the current files.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchConflict {
+    path: String,
+    /// 1-based hunk number; `None` when the whole file is the problem
+    hunk: Option<usize>,
+    /// 1-based line the hunk expected to start at
+    line: Option<usize>,
+    reason: String,
+    /// Lines the hunk expected to find
+    expected: Vec<String>,
+    /// Lines actually at that position
+    actual: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchReport {
+    files: Vec<PatchFile>,
+    conflicts: Vec<PatchConflict>,
+    /// Whether every hunk applies
+    clean: bool,
+    /// Whether the files were written; never for a preview or a patch with conflicts
+    applied: bool,
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    /// (' ' | '-' | '+', text without the line ending)
+    lines: Vec<(char, String)>,
+    /// `\ No newline at end of file` after the old or new side's last line
+    old_missing_newline: bool,
+    new_missing_newline: bool,
+}
+
+impl Hunk {
+    /// Records a `\ No newline at end of file` marker, which applies to the line before it.
+    fn mark_missing_newline(&mut self) {
+        match self.lines.last().map(|(kind, _)| *kind) {
+            Some('-') => self.old_missing_newline = true,
+            Some('+') => self.new_missing_newline = true,
+            _ => {
+                self.old_missing_newline = true;
+                self.new_missing_newline = true;
+            }
+        }
+    }
+
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter(|(kind, _)| *kind != '+')
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter(|(kind, _)| *kind != '-')
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+}
+
+/// One file section of a unified diff. `None` paths are `/dev/null`.
+#[derive(Debug, Default)]
+struct FileDiff {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+    /// Set by `diff --git` until the `---`/`+++` or rename lines name the files
+    pending: bool,
+}
+
+/// Path from a `---`/`+++` line: timestamps dropped, git quoting undone and the
+/// `a/`/`b/` prefix removed.
+fn header_path(raw: &str, prefix: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or_default().trim_end();
+    if raw == "/dev/null" {
+        return None;
+    }
+    let path = match raw.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(quoted) => quoted
+            .replace("\\\"", "\"")
+            .replace("\\t", "\t")
+            .replace("\\\\", "\\"),
+        None => raw.to_string(),
+    };
+    Some(path.strip_prefix(prefix).unwrap_or(&path).to_string())
+}
+
+/// Parses `@@ -start,count +start,count @@`, where a missing count means 1.
+fn hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old)?;
+    let (_, new_count) = range(new)?;
+    Some((old_start, old_count, new_count))
+}
+
+fn parse(diff: &str) -> Result<Vec<FileDiff>, String> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        if line.starts_with("diff --git ") {
+            files.push(FileDiff {
+                pending: true,
+                ..FileDiff::default()
+            });
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            return Err("Binary patches are not supported".to_string());
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            if let Some(file) = files.last_mut().filter(|f| f.pending) {
+                file.old_path = header_path(from, "");
+            }
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            if let Some(file) = files.last_mut().filter(|f| f.pending) {
+                file.new_path = header_path(to, "");
+            }
+        } else if let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(index).and_then(|l| l.strip_prefix("+++ ")),
+        ) {
+            index += 1;
+            // Plain diffs have no `diff --git` line; each `---` starts a new file
+            let file = match files.last_mut().filter(|f| f.pending) {
+                Some(file) => file,
+                None => {
+                    files.push(FileDiff::default());
+                    files.last_mut().unwrap()
+                }
+            };
+            file.old_path = header_path(old, "a/");
+            file.new_path = header_path(new, "b/");
+            file.pending = false;
+        } else if line.starts_with("@@ ") {
+            let (old_start, mut old_left, mut new_left) = hunk_header(line)
+                .ok_or_else(|| format!("Malformed hunk header on line {}: {}", index, line))?;
+            let file = files
+                .last_mut()
+                .filter(|f| f.old_path.is_some() || f.new_path.is_some())
+                .ok_or_else(|| format!("Hunk without a file header on line {}", index))?;
+            let mut hunk = Hunk {
+                old_start,
+                ..Hunk::default()
+            };
+            while old_left > 0 || new_left > 0 {
+                let Some(&body) = lines.get(index) else {
+                    return Err(format!("Hunk ends early in {}", display_path(file)));
+                };
+                index += 1;
+                // Some tools trim the space off empty context lines
+                let (kind, text) = match body.chars().next() {
+                    Some(kind @ (' ' | '-' | '+')) => (kind, &body[1..]),
+                    None => (' ', ""),
+                    Some('\\') => {
+                        hunk.mark_missing_newline();
+                        continue;
+                    }
+                    Some(_) => {
+                        return Err(format!(
+                            "Unexpected line {} in {}: {}",
+                            index,
+                            display_path(file),
+                            body
+                        ))
+                    }
+                };
+                if kind != '+' {
+                    old_left = old_left.checked_sub(1).ok_or_else(|| {
+                        format!("Hunk is longer than its header on line {}", index)
+                    })?;
+                }
+                if kind != '-' {
+                    new_left = new_left.checked_sub(1).ok_or_else(|| {
+                        format!("Hunk is longer than its header on line {}", index)
+                    })?;
+                }
+                hunk.lines
+                    .push((kind, text.trim_end_matches('\r').to_string()));
+            }
+            if lines.get(index).is_some_and(|l| l.starts_with('\\')) {
+                index += 1;
+                hunk.mark_missing_newline();
+            }
+            file.hunks.push(hunk);
+        }
+    }
+    let files: Vec<FileDiff> = files
+        .into_iter()
+        .filter(|f| f.old_path.is_some() || f.new_path.is_some())
+        .collect();
+    if files.is_empty() {
+        return Err("No file changes found in the patch".to_string());
+    }
+    Ok(files)
+}
+
+fn display_path(file: &FileDiff) -> &str {
+    file.new_path
+        .as_deref()
+        .or(file.old_path.as_deref())
+        .unwrap_or_default()
+}
+
+/// A file's lines without endings, remembering how to write them back.
+struct Text {
+    lines: Vec<String>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl Text {
+    fn parse(content: &str) -> Self {
+        let mut lines: Vec<String> = content
+            .split('\n')
+            .map(|l| l.trim_end_matches('\r').to_string())
+            .collect();
+        let trailing_newline = content.ends_with('\n');
+        if trailing_newline || content.is_empty() {
+            lines.pop();
+        }
+        Text {
+            lines,
+            crlf: content.contains("\r\n"),
+            trailing_newline,
+        }
+    }
+
+    fn render(&self) -> String {
+        let ending = if self.crlf { "\r\n" } else { "\n" };
+        let mut content = self.lines.join(ending);
+        if self.trailing_newline && !self.lines.is_empty() {
+            content.push_str(ending);
+        }
+        content
+    }
+}
+
+/// Where `expected` occurs in `lines` at or after `from`, nearest to `target`.
+fn locate(lines: &[String], expected: &[&str], from: usize, target: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(expected.len())?;
+    if from > last {
+        return None;
+    }
+    let target = target.clamp(from, last);
+    let matches = |at: usize| {
+        lines[at..at + expected.len()]
+            .iter()
+            .zip(expected)
+            .all(|(line, want)| line == want)
+    };
+    (0..=last - from).find_map(|distance| {
+        [target.checked_add(distance), target.checked_sub(distance)]
+            .into_iter()
+            .flatten()
+            .filter(|&at| at >= from && at <= last)
+            .find(|&at| matches(at))
+    })
+}
+
+/// Applies the hunks of one file in order. Each hunk is tried at its stated line first,
+/// shifted by how far earlier hunks moved, then at the nearest place its context matches.
+fn apply_hunks(
+    path: &str,
+    text: &Text,
+    hunks: &[Hunk],
+    conflicts: &mut Vec<PatchConflict>,
+) -> (Text, usize) {
+    let mut result = Vec::with_capacity(text.lines.len());
+    let mut trailing_newline = text.trailing_newline;
+    let mut cursor = 0;
+    let mut offset: isize = 0;
+    let mut shifted = 0;
+    for (number, hunk) in hunks.iter().enumerate() {
+        let expected = hunk.old_lines();
+        // A hunk adding to an empty range names the line after which it inserts
+        let stated = if expected.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let target = stated.saturating_add_signed(offset);
+        let Some(at) = locate(&text.lines, &expected, cursor, target) else {
+            let start = target.min(text.lines.len());
+            let end = (start + expected.len()).min(text.lines.len());
+            conflicts.push(PatchConflict {
+                path: path.to_string(),
+                hunk: Some(number + 1),
+                line: Some(stated + 1),
+                reason: "The lines this hunk changes no longer match the file".to_string(),
+                expected: expected.iter().map(|l| l.to_string()).collect(),
+                actual: text.lines[start..end].to_vec(),
+            });
+            continue;
+        };
+        if at != stated {
+            shifted += 1;
+        }
+        offset = at as isize - stated as isize;
+        result.extend_from_slice(&text.lines[cursor..at]);
+        result.extend(hunk.new_lines().into_iter().map(String::from));
+        cursor = at + expected.len();
+        if cursor == text.lines.len() {
+            if hunk.new_missing_newline {
+                trailing_newline = false;
+            } else if hunk.old_missing_newline {
+                trailing_newline = true;
+            }
+        }
+    }
+    result.extend_from_slice(&text.lines[cursor..]);
+    let patched = Text {
+        lines: result,
+        crlf: text.crlf,
+        trailing_newline,
+    };
+    (patched, shifted)
+}
+
+/// A write or removal that applying the patch performs.
+enum Operation {
+    Write(PathBuf, String),
+    Remove(PathBuf),
+}
+
+fn read_text(path: &Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size > MAX_FILE_BYTES {
+        return Err("File is too large to patch".to_string());
+    }
+    if is_binary_file(path) {
+        return Err("Binary files can't be patched".to_string());
+    }
+    std::fs::read_to_string(path)
+        .map(Some)
+        .map_err(|e| format!("Failed to read file: {}", e))
+}
+
+fn file_conflict(path: &str, reason: impl Into<String>) -> PatchConflict {
+    PatchConflict {
+        path: path.to_string(),
+        hunk: None,
+        line: None,
+        reason: reason.into(),
+        expected: Vec::new(),
+        actual: Vec::new(),
+    }
+}
+
+/// Checks every file of the patch against the workspace and works out the writes,
+/// touching nothing.
+fn plan(root: &Path, diff: &str) -> Result<(PatchReport, Vec<Operation>), String> {
+    if diff.len() > MAX_PATCH_BYTES {
+        return Err(format!(
+            "Patch is larger than {} MB",
+            MAX_PATCH_BYTES / 1024 / 1024
+        ));
+    }
+    let mut files = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut operations = Vec::new();
+    for file in parse(diff)? {
+        let old = file
+            .old_path
+            .as_deref()
+            .map(|p| resolve_workspace_path(root, p))
+            .transpose()?;
+        let new = file
+            .new_path
+            .as_deref()
+            .map(|p| resolve_workspace_path(root, p))
+            .transpose()?;
+        let action = match (&old, &new) {
+            (None, Some(_)) => PatchAction::Create,
+            (Some(_), None) => PatchAction::Delete,
+            (Some(old), Some(new)) if old != new => PatchAction::Rename,
+            _ => PatchAction::Modify,
+        };
+        let path = display_path(&file).to_string();
+        let count = |kind| {
+            file.hunks
+                .iter()
+                .flat_map(|h| &h.lines)
+                .filter(|(k, _)| *k == kind)
+                .count()
+        };
+        let mut summary = PatchFile {
+            path: path.clone(),
+            old_path: (action == PatchAction::Rename)
+                .then(|| file.old_path.clone().unwrap_or_default()),
+            action,
+            additions: count('+'),
+            deletions: count('-'),
+            hunks: file.hunks.len(),
+            shifted_hunks: 0,
+        };
+
+        let source = old.as_ref().or(new.as_ref()).unwrap();
+        let current = match read_text(source) {
+            Ok(current) => current,
+            Err(err) => {
+                conflicts.push(file_conflict(&path, err));
+                files.push(summary);
+                continue;
+            }
+        };
+        let exists_error = match action {
+            PatchAction::Create if current.is_some() => Some("File already exists"),
+            PatchAction::Rename if new.as_ref().is_some_and(|n| n.exists()) => {
+                Some("The rename target already exists")
+            }
+            PatchAction::Modify | PatchAction::Delete | PatchAction::Rename
+                if current.is_none() =>
+            {
+                Some("File not found")
+            }
+            _ => None,
+        };
+        if let Some(reason) = exists_error {
+            conflicts.push(file_conflict(&path, reason));
+            files.push(summary);
+            continue;
+        }
+
+        let before = conflicts.len();
+        let text = Text::parse(current.as_deref().unwrap_or_default());
+        let (mut patched, shifted) = apply_hunks(&path, &text, &file.hunks, &mut conflicts);
+        summary.shifted_hunks = shifted;
+        files.push(summary);
+        if conflicts.len() > before {
+            continue;
+        }
+        if action == PatchAction::Create {
+            patched.trailing_newline = !file.hunks.iter().any(|h| h.new_missing_newline);
+        }
+        match (action, old, new) {
+            (PatchAction::Delete, Some(old), _) => {
+                if !patched.lines.is_empty() {
+                    conflicts.push(file_conflict(
+                        &path,
+                        "File has lines the patch doesn't remove",
+                    ));
+                    continue;
+                }
+                operations.push(Operation::Remove(old));
+            }
+            (PatchAction::Rename, Some(old), Some(new)) => {
+                operations.push(Operation::Write(new, patched.render()));
+                operations.push(Operation::Remove(old));
+            }
+            (_, _, Some(new)) => operations.push(Operation::Write(new, patched.render())),
+            _ => {}
+        }
+    }
+    let clean = conflicts.is_empty();
+    Ok((
+        PatchReport {
+            files,
+            conflicts,
+            clean,
+            applied: false,
+        },
+        operations,
+    ))
+}
+
+/// Performs every operation or none: on the first failure the files already touched are
+/// put back as they were.
+fn execute(app: &tauri::AppHandle, operations: Vec<Operation>) -> Result<(), String> {
+    execute_with(operations, |path| {
+        file_history::snapshot(app, path, "patch")
+    })
+}
+
+/// `execute`, calling `snapshot` on each file before touching it.
+fn execute_with(
+    operations: Vec<Operation>,
+    mut snapshot: impl FnMut(&Path) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut done: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+    for operation in operations {
+        let path = match &operation {
+            Operation::Write(path, _) | Operation::Remove(path) => path.clone(),
+        };
+        let original = std::fs::read(&path).ok();
+        let result = snapshot(&path).and_then(|_| {
+            match &operation {
+                Operation::Write(path, content) => write_atomically(path, content.as_bytes()),
+                Operation::Remove(path) => std::fs::remove_file(path),
+            }
+            .map_err(|e| e.to_string())
+        });
+        if let Err(err) = result {
+            for (path, original) in done.into_iter().rev() {
+                let _ = restore(&path, original.as_deref());
+            }
+            return Err(format!("Failed to write {}: {}", path.display(), err));
+        }
+        done.push((path, original));
+    }
+    Ok(())
+}
+
+/// Checks a unified diff against the workspace without writing anything: which files it
+/// touches and which hunks no longer match.
+#[tauri::command]
+pub async fn preview_patch(workspace: String, diff: String) -> Result<PatchReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = workspace_root(&workspace)?;
+        plan(&root, &diff).map(|(report, _)| report)
+    })
+    .await
+    .map_err(|e| format!("Patch task failed: {}", e))?
+}
+
+/// Applies a unified diff to the workspace, all files or none. A patch with conflicts is
+/// not applied; the report says where it diverges from the files.
+#[tauri::command]
+pub async fn apply_patch(
+    app: tauri::AppHandle,
+    workspace: String,
+    diff: String,
+) -> Result<PatchReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = workspace_root(&workspace)?;
+        let (mut report, operations) = plan(&root, &diff)?;
+        if report.clean {
+            execute(&app, operations)?;
+            report.applied = true;
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Patch task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A fresh workspace directory, removed when dropped.
+    struct Fixture {
+        root: PathBuf,
+        dir: TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = TempDir::new();
+            Fixture {
+                root: dir.path().to_path_buf(),
+                dir,
+            }
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            self.dir.write(path, contents);
+        }
+
+        fn read(&self, path: &str) -> Option<String> {
+            std::fs::read_to_string(self.root.join(path)).ok()
+        }
+
+        /// Plans `diff` and, when it is clean, applies it.
+        fn apply(&self, diff: &str) -> PatchReport {
+            let (mut report, operations) = plan(&self.root, diff).unwrap();
+            if report.clean {
+                execute_with(operations, |_| Ok(())).unwrap();
+                report.applied = true;
+            }
+            report
+        }
+    }
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|n| format!("line {}\n", n)).collect()
+    }
+
+    fn patched(text: &str, diff: &str) -> (String, usize, Vec<PatchConflict>) {
+        let file = parse(diff).unwrap().remove(0);
+        let mut conflicts = Vec::new();
+        let (patched, shifted) =
+            apply_hunks("f.txt", &Text::parse(text), &file.hunks, &mut conflicts);
+        (patched.render(), shifted, conflicts)
+    }
+
+    #[test]
+    fn parses_git_headers_renames_and_missing_newlines() {
+        let files = parse(
+            "diff --git a/old.txt b/new.txt\n\
+             similarity index 90%\n\
+             rename from old.txt\n\
+             rename to new.txt\n\
+             --- a/old.txt\n\
+             +++ b/new.txt\n\
+             @@ -1 +1 @@\n\
+             -a\n\
+             \\ No newline at end of file\n\
+             +b\n\
+             diff --git a/gone.txt b/gone.txt\n\
+             deleted file mode 100644\n\
+             --- a/gone.txt\n\
+             +++ /dev/null\n\
+             @@ -1,2 +0,0 @@\n\
+             -x\n\
+             -y\n",
+        )
+        .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].old_path.as_deref(), Some("old.txt"));
+        assert_eq!(files[0].new_path.as_deref(), Some("new.txt"));
+        assert!(files[0].hunks[0].old_missing_newline);
+        assert!(!files[0].hunks[0].new_missing_newline);
+        assert_eq!(files[1].new_path, None);
+        assert_eq!(files[1].hunks[0].old_lines(), ["x", "y"]);
+    }
+
+    #[test]
+    fn rejects_malformed_patches() {
+        assert!(parse("just some text\n").is_err());
+        assert!(parse("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n").is_err());
+        assert!(parse("--- a/f\n+++ b/f\n@@ -one +1 @@\n-a\n+b\n").is_err());
+        assert!(parse("@@ -1 +1 @@\n-a\n+b\n").is_err());
+        assert!(parse("diff --git a/f b/f\nBinary files a/f and b/f differ\n").is_err());
+    }
+
+    #[test]
+    fn applies_multiple_hunks() {
+        let diff = "--- a/f.txt\n+++ b/f.txt\n\
+                    @@ -1,3 +1,3 @@\n line 1\n-line 2\n+two\n line 3\n\
+                    @@ -8,3 +8,4 @@\n line 8\n line 9\n+nine and a half\n line 10\n";
+        let (text, shifted, conflicts) = patched(&numbered(10), diff);
+        assert!(conflicts.is_empty());
+        assert_eq!(shifted, 0);
+        assert_eq!(
+            text,
+            "line 1\ntwo\nline 3\nline 4\nline 5\nline 6\nline 7\nline 8\nline 9\n\
+             nine and a half\nline 10\n"
+        );
+    }
+
+    #[test]
+    fn finds_hunks_whose_lines_moved() {
+        // The file gained three lines at the top since the diff was made
+        let text = format!("new a\nnew b\nnew c\n{}", numbered(10));
+        let diff = "--- a/f.txt\n+++ b/f.txt\n\
+                    @@ -5,3 +5,3 @@\n line 5\n-line 6\n+six\n line 7\n\
+                    @@ -9,2 +9,2 @@\n line 9\n-line 10\n+ten\n";
+        let (text, shifted, conflicts) = patched(&text, diff);
+        assert!(conflicts.is_empty());
+        assert_eq!(shifted, 2);
+        assert!(text.contains("line 5\nsix\nline 7\n"));
+        assert!(text.ends_with("line 9\nten\n"));
+    }
+
+    #[test]
+    fn reports_context_that_no_longer_matches() {
+        let diff = "--- a/f.txt\n+++ b/f.txt\n\
+                    @@ -2,2 +2,2 @@\n line 2\n-line 3\n+three\n\
+                    @@ -5,1 +5,1 @@\n-line five\n+five\n";
+        let (text, _, conflicts) = patched(&numbered(6), diff);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].hunk, Some(2));
+        assert_eq!(conflicts[0].line, Some(5));
+        assert_eq!(conflicts[0].expected, ["line five"]);
+        assert_eq!(conflicts[0].actual, ["line 5"]);
+        // The hunk that matched still applies in the returned text
+        assert!(text.contains("line 2\nthree\n"));
+    }
+
+    #[test]
+    fn creates_and_deletes_files() {
+        let fixture = Fixture::new();
+        fixture.write("old.txt", "x\ny\n");
+        let report = fixture.apply(
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n\
+             --- a/old.txt\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-x\n-y\n",
+        );
+        assert!(report.applied, "{:?}", report.conflicts);
+        assert_eq!(report.files[0].action, PatchAction::Create);
+        assert_eq!(report.files[1].action, PatchAction::Delete);
+        assert_eq!(fixture.read("new.txt").as_deref(), Some("hello\nworld\n"));
+        assert_eq!(fixture.read("old.txt"), None);
+
+        // Creating over an existing file, or deleting one with lines the patch keeps, conflicts
+        fixture.write("kept.txt", "x\ny\nz\n");
+        let report = fixture.apply(
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+again\n\
+             --- a/kept.txt\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-x\n-y\n",
+        );
+        assert!(!report.applied);
+        assert_eq!(report.conflicts.len(), 2);
+        assert_eq!(fixture.read("kept.txt").as_deref(), Some("x\ny\nz\n"));
+    }
+
+    #[test]
+    fn rolls_back_when_a_later_operation_fails() {
+        let fixture = Fixture::new();
+        fixture.write("a.txt", "original\n");
+        let operations = vec![
+            Operation::Write(fixture.root.join("a.txt"), "patched\n".to_string()),
+            Operation::Write(fixture.root.join("b.txt"), "created\n".to_string()),
+            Operation::Remove(fixture.root.join("missing.txt")),
+        ];
+        assert!(execute_with(operations, |_| Ok(())).is_err());
+        assert_eq!(fixture.read("a.txt").as_deref(), Some("original\n"));
+        assert_eq!(fixture.read("b.txt"), None);
+
+        // A failed snapshot stops the patch before its file is touched
+        let operations = vec![
+            Operation::Write(fixture.root.join("a.txt"), "patched\n".to_string()),
+            Operation::Write(fixture.root.join("b.txt"), "created\n".to_string()),
+        ];
+        let result = execute_with(operations, |path| {
+            if path.ends_with("b.txt") {
+                Err("history unavailable".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(fixture.read("a.txt").as_deref(), Some("original\n"));
+        assert_eq!(fixture.read("b.txt"), None);
+    }
+}
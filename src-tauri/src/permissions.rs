@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::prompts::Prompts;
+use crate::rpc;
+use crate::storage::ConversationMode;
+
+const PERMISSION_SETTINGS_FILE: &str = "permission_settings.json";
+const PERMISSION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const MAX_ALWAYS_ALLOWED: usize = 200;
+// The sidecar's tools that never change anything, the only ones a run in plan mode may call
+const READ_ONLY_TOOLS: &[&str] = &[
+    "get_time",
+    "get_timezone",
+    "random_number",
+    "generate_uuid",
+    "calculate_expression",
+    "file_search",
+    "find_duplicates",
+    "read_file",
+    "ls",
+    "glob",
+    "grep",
+];
+
+/// What a tool call is about to do, as the sidecar judges it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+    Network,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionSettings {
+    /// Tools whose calls run without asking
+    #[serde(default)]
+    always_allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingPermission {
+    id: String,
+    tool: String,
+    access: Access,
+    args: serde_json::Value,
+    /// Files and folders the call touches, absolute when the sidecar could resolve them
+    paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    requested_at: i64,
+    /// When the prompt is denied for lack of an answer
+    expires_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PermissionRequest {
+    call_id: String,
+    tool: String,
+    access: Access,
+    #[serde(default)]
+    args: serde_json::Value,
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    workspace: Option<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PermissionResolved<'a> {
+    id: &'a str,
+    allowed: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct Permissions {
+    /// Prompts waiting for `respond_permission`, by id
+    pending: Prompts<PendingPermission>,
+    /// Request ids of the runs in plan mode
+    plan_runs: Mutex<HashSet<String>>,
+}
+
+/// Keeps a run's mode on record until it is dropped at the end of the run.
+pub(crate) struct RunMode {
+    app: tauri::AppHandle,
+    request_id: Option<String>,
+}
+
+impl Drop for RunMode {
+    fn drop(&mut self) {
+        if let Some(request_id) = &self.request_id {
+            let permissions = self.app.state::<Permissions>();
+            permissions.plan_runs.lock().unwrap().remove(request_id);
+        }
+    }
+}
+
+/// Records the mode of the run `request_id` for the permission checks of its tool calls.
+pub(crate) fn enter_mode(
+    app: &tauri::AppHandle,
+    request_id: &str,
+    mode: ConversationMode,
+) -> RunMode {
+    if mode == ConversationMode::Act {
+        return RunMode {
+            app: app.clone(),
+            request_id: None,
+        };
+    }
+    app.state::<Permissions>()
+        .plan_runs
+        .lock()
+        .unwrap()
+        .insert(request_id.to_string());
+    RunMode {
+        app: app.clone(),
+        request_id: Some(request_id.to_string()),
+    }
+}
+
+/// Lets runs in plan mode call only tools known to be read-only, refusing everything else,
+/// including tools nobody has classified.
+pub(crate) fn check_mode(
+    app: &tauri::AppHandle,
+    request_id: Option<&str>,
+    tool: &str,
+    access: Access,
+    what: &str,
+) -> Result<(), String> {
+    let in_plan = request_id.is_some_and(|id| {
+        app.state::<Permissions>()
+            .plan_runs
+            .lock()
+            .unwrap()
+            .contains(id)
+    });
+    if in_plan && !allowed_in_plan(tool, access) {
+        return Err(format!(
+            "The conversation is in plan mode, so {} is not allowed. Describe the change \
+             instead; the user switches to act mode to carry it out.",
+            what
+        ));
+    }
+    Ok(())
+}
+
+fn allowed_in_plan(tool: &str, access: Access) -> bool {
+    access == Access::Read && READ_ONLY_TOOLS.contains(&tool)
+}
+
+fn load_settings(app: &tauri::AppHandle) -> Result<PermissionSettings, String> {
+    load_json::<PermissionSettings>(app, PERMISSION_SETTINGS_FILE)
+}
+
+fn resolve(app: &tauri::AppHandle, id: &str, allowed: bool) -> bool {
+    if !app.state::<Permissions>().pending.answer(id, allowed) {
+        return false;
+    }
+    let _ = app.emit(
+        "agent:permission-resolved",
+        PermissionResolved { id, allowed },
+    );
+    true
+}
+
+/// Asks the user whether the tool call may run, unless the tool is always allowed or the
+/// run's mode rules it out.
+async fn decide(app: &tauri::AppHandle, request: PermissionRequest) -> Result<(), String> {
+    check_mode(
+        app,
+        request.request_id.as_deref(),
+        &request.tool,
+        request.access,
+        &request.tool,
+    )?;
+    let settings = load_settings(app)?;
+    if settings.always_allow.contains(&request.tool) {
+        return Ok(());
+    }
+
+    let id = request.call_id;
+    let tool = request.tool;
+    let requested_at = now_millis();
+    let prompt = PendingPermission {
+        id: id.clone(),
+        tool: tool.clone(),
+        access: request.access,
+        args: request.args,
+        paths: request.paths,
+        workspace: request.workspace,
+        request_id: request.request_id,
+        requested_at,
+        expires_at: requested_at + PERMISSION_TIMEOUT.as_millis() as i64,
+    };
+    let _ = app.emit("agent:permission", prompt.clone());
+    let answer = app
+        .state::<Permissions>()
+        .pending
+        .wait(&id, prompt, PERMISSION_TIMEOUT)
+        .await;
+    match answer {
+        Some(true) => Ok(()),
+        Some(false) => Err(format!("The user did not allow {}", tool)),
+        None => {
+            resolve(app, &id, false);
+            Err(format!("No one allowed {} in time", tool))
+        }
+    }
+}
+
+/// `{event:"permission_request", callId, tool, access, args, paths}` from the sidecar:
+/// holds the tool call until the user decides and answers with `permissionReply`.
+pub(crate) fn handle_request(app: &tauri::AppHandle, value: serde_json::Value) {
+    let request: PermissionRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[permissions] malformed request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let call_id = request.call_id.clone();
+        let result = decide(&app, request).await;
+        rpc::reply_to_sidecar(&app, "permissionReply", &call_id, result).await;
+    });
+}
+
+/// `{event:"permission_cancel", callId}`: the tool call was abandoned, so its prompt is
+/// withdrawn as a denial.
+pub(crate) fn handle_cancel(app: &tauri::AppHandle, value: serde_json::Value) {
+    if let Some(call_id) = value.get("callId").and_then(|v| v.as_str()) {
+        resolve(app, call_id, false);
+    }
+}
+
+#[tauri::command]
+pub fn get_permission_settings(app: tauri::AppHandle) -> Result<PermissionSettings, String> {
+    load_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_permission_settings(
+    app: tauri::AppHandle,
+    settings: PermissionSettings,
+) -> Result<PermissionSettings, String> {
+    let mut always_allow: Vec<String> = Vec::new();
+    for tool in settings.always_allow {
+        let tool = tool.trim().to_string();
+        if !tool.is_empty() && !always_allow.contains(&tool) {
+            always_allow.push(tool);
+        }
+    }
+    if always_allow.len() > MAX_ALWAYS_ALLOWED {
+        return Err(format!(
+            "At most {} tools can be always allowed",
+            MAX_ALWAYS_ALLOWED
+        ));
+    }
+    let settings = PermissionSettings { always_allow };
+    save_json(&app, PERMISSION_SETTINGS_FILE, &settings)?;
+    Ok(settings)
+}
+
+/// Tool calls waiting for an answer, oldest first.
+#[tauri::command]
+pub fn list_pending_permissions(app: tauri::AppHandle) -> Vec<PendingPermission> {
+    let mut pending = app.state::<Permissions>().pending.pending();
+    pending.sort_by_key(|prompt| prompt.requested_at);
+    pending
+}
+
+/// Answers an `agent:permission` prompt; with `remember`, an allowed tool stops asking.
+/// Returns false when the prompt was already answered or has expired.
+#[tauri::command]
+pub fn respond_permission(
+    app: tauri::AppHandle,
+    id: String,
+    allow: bool,
+    remember: Option<bool>,
+) -> Result<bool, String> {
+    let tool = app
+        .state::<Permissions>()
+        .pending
+        .get(&id)
+        .map(|prompt| prompt.tool);
+    if let (Some(tool), true, Some(true)) = (tool, allow, remember) {
+        let mut settings = load_settings(&app)?;
+        if !settings.always_allow.contains(&tool) {
+            settings.always_allow.push(tool);
+            set_permission_settings(app.clone(), settings)?;
+        }
+    }
+    Ok(resolve(&app, &id, allow))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_mode_allows_only_read_only_tools() {
+        assert!(allowed_in_plan("file_search", Access::Read));
+        assert!(allowed_in_plan("read_file", Access::Read));
+        assert!(!allowed_in_plan("file_search", Access::Write));
+        assert!(!allowed_in_plan("find_duplicates", Access::Write));
+        assert!(!allowed_in_plan("web_operations", Access::Network));
+        assert!(!allowed_in_plan("some_new_tool", Access::Read));
+        assert!(!allowed_in_plan("terminal", Access::Execute));
+    }
+}
@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::providers::{self, AzureDeployment, EndpointOverrides};
+use crate::proxy::{self, ProxyConfig, ResolvedProxy};
+use crate::secrets;
+
+const PROFILES_FILE: &str = "provider_profiles.json";
+const MAX_NAME_CHARS: usize = 80;
+const MAX_HEADERS: usize = 20;
+
+/// A named provider configuration. The key itself lives in the keychain under `key_ref`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderProfile {
+    id: String,
+    name: String,
+    provider: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    default_model: Option<String>,
+    /// Extra headers sent with every request, e.g. for gateways or org routing
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    /// Keychain entry holding the key; the provider's own entry when absent
+    #[serde(default)]
+    key_ref: Option<String>,
+    /// Deployment overrides for the `azure` provider
+    #[serde(default)]
+    azure: Option<AzureDeployment>,
+    /// Chat path and query overrides for non-standard OpenAI-compatible gateways
+    #[serde(default)]
+    endpoint: Option<EndpointOverrides>,
+    /// Overrides the default proxy for this profile's requests
+    #[serde(default)]
+    proxy: Option<ProxyConfig>,
+    /// Plain chat streamed by the app itself, skipping the sidecar and its tools
+    #[serde(default)]
+    native_streaming: bool,
+    created_at: i64,
+    updated_at: i64,
+}
+
+/// The editable part of a profile.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileInput {
+    name: String,
+    provider: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    #[serde(default)]
+    default_model: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    key_ref: Option<String>,
+    #[serde(default)]
+    azure: Option<AzureDeployment>,
+    #[serde(default)]
+    endpoint: Option<EndpointOverrides>,
+    #[serde(default)]
+    proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    native_streaming: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileFile {
+    profiles: Vec<ProviderProfile>,
+}
+
+/// Provider settings for one request after applying a profile.
+pub(crate) struct ProfileTarget {
+    pub(crate) provider: Option<String>,
+    pub(crate) model: Option<String>,
+    pub(crate) base_url: Option<String>,
+    pub(crate) headers: Option<HashMap<String, String>>,
+    pub(crate) azure: Option<AzureDeployment>,
+    pub(crate) endpoint: Option<EndpointOverrides>,
+    pub(crate) proxy: Option<ResolvedProxy>,
+    pub(crate) key_ref: Option<String>,
+    pub(crate) native_streaming: bool,
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Keychain entry for a key saved together with the profile.
+fn own_key_ref(id: &str) -> String {
+    format!("profile-{}", id)
+}
+
+/// Keychain entry for the password of the profile's proxy.
+fn proxy_password_ref(id: &str) -> String {
+    format!("profile-{}-proxy", id)
+}
+
+fn validate(app: &tauri::AppHandle, input: ProfileInput) -> Result<ProfileInput, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!(
+            "Profile name must be 1-{} characters",
+            MAX_NAME_CHARS
+        ));
+    }
+    let provider = input.provider.trim().to_lowercase();
+    if provider.is_empty() {
+        return Err("Profile provider is required".to_string());
+    }
+    let base_url = trimmed(input.base_url);
+    if base_url.is_some() {
+        providers::base_url(app, Some(&provider), base_url.as_deref())?;
+    }
+    if input.headers.len() > MAX_HEADERS {
+        return Err(format!(
+            "At most {} custom headers are allowed",
+            MAX_HEADERS
+        ));
+    }
+    let mut headers = HashMap::new();
+    for (header, value) in input.headers {
+        let header = header.trim().to_string();
+        let valid_name = !header.is_empty()
+            && header
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name || value.contains(['\r', '\n']) {
+            return Err(format!("Invalid header: {}", header));
+        }
+        headers.insert(header, value.trim().to_string());
+    }
+    let endpoint = match input.endpoint {
+        Some(endpoint) => endpoint.normalized()?,
+        None => None,
+    };
+    let proxy = match input.proxy {
+        Some(proxy) => proxy::normalize(proxy)?,
+        None => None,
+    };
+    Ok(ProfileInput {
+        name,
+        provider,
+        base_url,
+        default_model: trimmed(input.default_model),
+        headers,
+        key_ref: trimmed(input.key_ref),
+        azure: input.azure.and_then(AzureDeployment::normalized),
+        endpoint,
+        proxy,
+        native_streaming: input.native_streaming,
+    })
+}
+
+fn ensure_unique_name(file: &ProfileFile, name: &str, except: Option<&str>) -> Result<(), String> {
+    let taken = file
+        .profiles
+        .iter()
+        .any(|p| Some(p.id.as_str()) != except && p.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(format!("A profile named {} already exists", name));
+    }
+    Ok(())
+}
+
+/// Stores or, when empty, removes the proxy password; `None` keeps the saved one.
+fn save_proxy_password(id: &str, password: Option<String>) -> Result<(), String> {
+    match password {
+        Some(password) => secrets::store_api_key(&proxy_password_ref(id), &password),
+        None => Ok(()),
+    }
+}
+
+/// Stores `api_key` for the profile when given and returns the key reference to keep.
+fn save_key(
+    id: &str,
+    key_ref: Option<String>,
+    api_key: Option<String>,
+) -> Result<Option<String>, String> {
+    match api_key.filter(|k| !k.trim().is_empty()) {
+        Some(api_key) => {
+            let own = own_key_ref(id);
+            secrets::store_api_key(&own, &api_key)?;
+            Ok(Some(own))
+        }
+        None => Ok(key_ref),
+    }
+}
+
+pub(crate) fn find(app: &tauri::AppHandle, id: &str) -> Result<ProviderProfile, String> {
+    load_json::<ProfileFile>(app, PROFILES_FILE)?
+        .profiles
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Provider profile not found: {}", id))
+}
+
+/// Applies `profile_id`, if given, over the provider settings the webview sent. The
+/// webview's model still wins over the profile's default model.
+pub(crate) fn apply(
+    app: &tauri::AppHandle,
+    profile_id: Option<&str>,
+    provider: Option<String>,
+    model: Option<String>,
+    base_url: Option<String>,
+) -> Result<ProfileTarget, String> {
+    let model = trimmed(model);
+    let Some(profile_id) = profile_id.map(str::trim).filter(|id| !id.is_empty()) else {
+        return Ok(ProfileTarget {
+            provider,
+            model,
+            base_url,
+            headers: None,
+            azure: None,
+            endpoint: None,
+            proxy: None,
+            key_ref: None,
+            native_streaming: false,
+        });
+    };
+    let profile = find(app, profile_id)?;
+    let proxy = profile
+        .proxy
+        .map(|config| proxy::resolve(config, &proxy_password_ref(&profile.id)))
+        .transpose()?;
+    Ok(ProfileTarget {
+        model: model.or(profile.default_model),
+        base_url: profile.base_url,
+        headers: Some(profile.headers).filter(|h| !h.is_empty()),
+        azure: profile.azure,
+        endpoint: profile.endpoint,
+        proxy,
+        native_streaming: profile.native_streaming,
+        key_ref: Some(profile.key_ref.unwrap_or_else(|| profile.provider.clone())),
+        provider: Some(profile.provider),
+    })
+}
+
+#[tauri::command]
+pub fn list_provider_profiles(app: tauri::AppHandle) -> Result<Vec<ProviderProfile>, String> {
+    let mut profiles = load_json::<ProfileFile>(&app, PROFILES_FILE)?.profiles;
+    profiles.sort_by_key(|p| p.name.to_lowercase());
+    Ok(profiles)
+}
+
+/// Creates a profile. `api_key`, when given, is saved to the keychain for this profile
+/// alone and takes precedence over `keyRef`. `proxy_password` is saved the same way for
+/// the profile's proxy.
+#[tauri::command]
+pub fn create_provider_profile(
+    app: tauri::AppHandle,
+    profile: ProfileInput,
+    api_key: Option<String>,
+    proxy_password: Option<String>,
+) -> Result<ProviderProfile, String> {
+    let input = validate(&app, profile)?;
+    let mut file = load_json::<ProfileFile>(&app, PROFILES_FILE)?;
+    ensure_unique_name(&file, &input.name, None)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = now_millis();
+    let profile = ProviderProfile {
+        key_ref: save_key(&id, input.key_ref, api_key)?,
+        proxy: input.proxy,
+        id,
+        name: input.name,
+        provider: input.provider,
+        base_url: input.base_url,
+        default_model: input.default_model,
+        headers: input.headers,
+        azure: input.azure,
+        endpoint: input.endpoint,
+        native_streaming: input.native_streaming,
+        created_at: now,
+        updated_at: now,
+    };
+    save_proxy_password(&profile.id, proxy_password)?;
+    file.profiles.push(profile.clone());
+    save_json(&app, PROFILES_FILE, &file)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn update_provider_profile(
+    app: tauri::AppHandle,
+    id: String,
+    profile: ProfileInput,
+    api_key: Option<String>,
+    proxy_password: Option<String>,
+) -> Result<ProviderProfile, String> {
+    let input = validate(&app, profile)?;
+    let mut file = load_json::<ProfileFile>(&app, PROFILES_FILE)?;
+    ensure_unique_name(&file, &input.name, Some(&id))?;
+    let existing = file
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| format!("Provider profile not found: {}", id))?;
+    let key_ref = save_key(&id, input.key_ref, api_key)?;
+    save_proxy_password(&id, proxy_password)?;
+
+    existing.name = input.name;
+    existing.provider = input.provider;
+    existing.base_url = input.base_url;
+    existing.default_model = input.default_model;
+    existing.headers = input.headers;
+    existing.azure = input.azure;
+    existing.endpoint = input.endpoint;
+    existing.proxy = input.proxy;
+    existing.native_streaming = input.native_streaming;
+    existing.key_ref = key_ref;
+    existing.updated_at = now_millis();
+    let updated = existing.clone();
+    save_json(&app, PROFILES_FILE, &file)?;
+    Ok(updated)
+}
+
+/// Deletes the profile and any key saved for it alone. Returns `false` if it didn't exist.
+#[tauri::command]
+pub fn delete_provider_profile(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut file = load_json::<ProfileFile>(&app, PROFILES_FILE)?;
+    let before = file.profiles.len();
+    file.profiles.retain(|p| p.id != id);
+    if file.profiles.len() == before {
+        return Ok(false);
+    }
+    save_json(&app, PROFILES_FILE, &file)?;
+    // Shared provider entries stay; only the profile's own key goes with it
+    for entry in [own_key_ref(&id), proxy_password_ref(&id)] {
+        if let Err(err) = secrets::store_api_key(&entry, "") {
+            eprintln!("[profiles] failed to remove {} for {}: {}", entry, id, err);
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::{format_utc, now_millis};
+use crate::template;
+
+const PROMPT_TEMPLATES_FILE: &str = "prompt_templates.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    /// System prompt text; supports `{{workspace}}`, `{{model}}` and `{{date}}`
+    content: String,
+    updated_at: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PromptTemplateFile {
+    templates: Vec<PromptTemplate>,
+}
+
+/// Renders a stored template into system prompt text for `send_message`.
+pub(crate) fn expand(
+    app: &tauri::AppHandle,
+    template_id: &str,
+    workspace: Option<&str>,
+    model: &str,
+) -> Result<String, String> {
+    let file = load_json::<PromptTemplateFile>(app, PROMPT_TEMPLATES_FILE)?;
+    let prompt = file
+        .templates
+        .into_iter()
+        .find(|t| t.id == template_id)
+        .ok_or_else(|| format!("Unknown prompt template: {}", template_id))?;
+
+    let mut vars = HashMap::new();
+    vars.insert("model".to_string(), model.to_string());
+    vars.insert(
+        "date".to_string(),
+        format_utc(now_millis())[..10].to_string(),
+    );
+    if let Some(workspace) = workspace {
+        vars.insert("workspace".to_string(), workspace.to_string());
+    }
+    Ok(template::render(&prompt.content, &vars).text)
+}
+
+#[tauri::command]
+pub fn list_prompt_templates(app: tauri::AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    Ok(load_json::<PromptTemplateFile>(&app, PROMPT_TEMPLATES_FILE)?.templates)
+}
+
+/// Creates a template, or updates it in place when `id` names an existing one.
+#[tauri::command]
+pub fn save_prompt_template(
+    app: tauri::AppHandle,
+    id: Option<String>,
+    name: String,
+    content: String,
+    description: Option<String>,
+) -> Result<PromptTemplate, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    if content.trim().is_empty() {
+        return Err("Template content is required".to_string());
+    }
+
+    let prompt = PromptTemplate {
+        id: id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name,
+        description,
+        content,
+        updated_at: now_millis(),
+    };
+
+    let mut file = load_json::<PromptTemplateFile>(&app, PROMPT_TEMPLATES_FILE)?;
+    match file.templates.iter_mut().find(|t| t.id == prompt.id) {
+        Some(existing) => *existing = prompt.clone(),
+        None => file.templates.push(prompt.clone()),
+    }
+    save_json(&app, PROMPT_TEMPLATES_FILE, &file)?;
+    Ok(prompt)
+}
+
+#[tauri::command]
+pub fn delete_prompt_template(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut file = load_json::<PromptTemplateFile>(&app, PROMPT_TEMPLATES_FILE)?;
+    let before = file.templates.len();
+    file.templates.retain(|t| t.id != id);
+    let removed = file.templates.len() != before;
+    if removed {
+        save_json(&app, PROMPT_TEMPLATES_FILE, &file)?;
+    }
+    Ok(removed)
+}
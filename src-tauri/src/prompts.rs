@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Questions put to the user that a tool call waits on, by id, each answered with
+/// allow or deny.
+pub(crate) struct Prompts<T>(Mutex<HashMap<String, (T, oneshot::Sender<bool>)>>);
+
+impl<T> Default for Prompts<T> {
+    fn default() -> Self {
+        Prompts(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<T: Clone> Prompts<T> {
+    /// Records `prompt` under `id` and waits for its answer. `None` when nobody answers
+    /// within `timeout`, which counts as a denial; the prompt stays recorded until the
+    /// caller withdraws it with `answer`.
+    pub async fn wait(&self, id: &str, prompt: T, timeout: Duration) -> Option<bool> {
+        let (tx, rx) = oneshot::channel();
+        self.0.lock().unwrap().insert(id.to_string(), (prompt, tx));
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(answer) => Some(answer.unwrap_or(false)),
+            Err(_) => None,
+        }
+    }
+
+    /// Settles the prompt `id`. Returns false when it was already answered or withdrawn.
+    pub fn answer(&self, id: &str, allowed: bool) -> bool {
+        let Some((_, tx)) = self.0.lock().unwrap().remove(id) else {
+            return false;
+        };
+        let _ = tx.send(allowed);
+        true
+    }
+
+    pub fn get(&self, id: &str) -> Option<T> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(prompt, _)| prompt.clone())
+    }
+
+    /// Every prompt still waiting for an answer, in no particular order.
+    pub fn pending(&self) -> Vec<T> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(prompt, _)| prompt.clone())
+            .collect()
+    }
+}
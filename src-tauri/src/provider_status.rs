@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::clock::now_millis;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// A manual refresh within this window returns the cached result
+const CACHE_TTL_MS: i64 = 60 * 1000;
+
+/// Statuspage.io summary endpoints for the hosted providers, sorted by provider.
+const STATUS_PAGES: &[(&str, &str)] = &[
+    (
+        "anthropic",
+        "https://status.anthropic.com/api/v2/status.json",
+    ),
+    ("openai", "https://status.openai.com/api/v2/status.json"),
+];
+
+#[derive(Debug, Deserialize)]
+struct StatusPageResponse {
+    status: StatusPageStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusPageStatus {
+    indicator: String,
+    description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    provider: &'static str,
+    /// Statuspage indicator: none, minor, major, critical; "unknown" if the check failed
+    indicator: String,
+    description: String,
+    degraded: bool,
+    checked_at: i64,
+}
+
+/// Last known status per provider.
+#[derive(Default)]
+pub(crate) struct ProviderStatusCache(Mutex<HashMap<&'static str, ProviderStatus>>);
+
+async fn fetch_status(
+    client: &reqwest::Client,
+    provider: &'static str,
+    url: &str,
+) -> ProviderStatus {
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    let parsed = match response {
+        Ok(response) => response
+            .json::<StatusPageResponse>()
+            .await
+            .map_err(|e| e.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+
+    match parsed {
+        Ok(page) => ProviderStatus {
+            provider,
+            degraded: page.status.indicator != "none",
+            indicator: page.status.indicator,
+            description: page.status.description,
+            checked_at: now_millis(),
+        },
+        // An unreachable status page says nothing about the provider itself
+        Err(err) => ProviderStatus {
+            provider,
+            indicator: "unknown".to_string(),
+            description: format!("Status check failed: {}", err),
+            degraded: false,
+            checked_at: now_millis(),
+        },
+    }
+}
+
+/// Checks every status page, emitting `provider:degraded` / `provider:recovered` on transitions.
+async fn refresh(app: &tauri::AppHandle) -> Vec<ProviderStatus> {
+    let client = crate::proxy::http_client(app);
+    let mut results = Vec::with_capacity(STATUS_PAGES.len());
+    for (provider, url) in STATUS_PAGES {
+        results.push(fetch_status(&client, provider, url).await);
+    }
+
+    let cache = app.state::<ProviderStatusCache>();
+    let mut cache = cache.0.lock().unwrap();
+    for status in &mut results {
+        let was_degraded = cache.get(status.provider).is_some_and(|p| p.degraded);
+        // Failed checks keep the previous degraded state rather than flapping
+        if status.indicator == "unknown" {
+            status.degraded = was_degraded;
+        } else if status.degraded && !was_degraded {
+            let _ = app.emit("provider:degraded", status.clone());
+        } else if !status.degraded && was_degraded {
+            let _ = app.emit("provider:recovered", status.clone());
+        }
+        cache.insert(status.provider, status.clone());
+    }
+    results
+}
+
+/// Polls provider status pages for the lifetime of the app.
+pub(crate) fn start_monitor(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            refresh(&app).await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_provider_status(
+    app: tauri::AppHandle,
+    force: Option<bool>,
+) -> Result<Vec<ProviderStatus>, String> {
+    let cached: Vec<ProviderStatus> = app
+        .state::<ProviderStatusCache>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect();
+    let fresh = cached.len() == STATUS_PAGES.len()
+        && cached
+            .iter()
+            .all(|s| now_millis() - s.checked_at < CACHE_TTL_MS);
+
+    if fresh && !force.unwrap_or(false) {
+        let mut cached = cached;
+        cached.sort_by_key(|s| s.provider);
+        return Ok(cached);
+    }
+    Ok(refresh(&app).await)
+}
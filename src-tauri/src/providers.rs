@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::proxy::ResolvedProxy;
+
+/// Default base URLs for the built-in providers, mirroring `src/lib/providers.ts`.
+const DEFAULT_BASE_URLS: &[(&str, &str)] = &[
+    ("anthropic", "https://api.anthropic.com/v1"),
+    ("deepseek", "https://api.deepseek.com/v1"),
+    ("fireworks", "https://api.fireworks.ai/inference/v1"),
+    ("groq", "https://api.groq.com/openai/v1"),
+    ("lmstudio", "http://localhost:1234/v1"),
+    ("mistral", "https://api.mistral.ai/v1"),
+    ("moonshot", "https://api.moonshot.cn/v1"),
+    ("ollama", "http://localhost:11434/v1"),
+    ("openai", "https://api.openai.com/v1"),
+    ("openrouter", "https://openrouter.ai/api/v1"),
+    ("perplexity", "https://api.perplexity.ai"),
+    ("together", "https://api.together.xyz/v1"),
+    ("xai", "https://api.x.ai/v1"),
+];
+/// Provider assumed when a request doesn't name one, matching the webview's default.
+pub(crate) const DEFAULT_PROVIDER: &str = "openrouter";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Azure OpenAI data-plane API version used unless a deployment names another.
+pub(crate) const AZURE_API_VERSION: &str = "2024-10-21";
+/// Sent where no API key is used (local servers, SigV4-signed Bedrock, Vertex before its
+/// token is minted), since OpenAI clients insist on one.
+pub(crate) const LOCAL_API_KEY: &str = "local";
+const MAX_QUERY_PARAMS: usize = 20;
+
+/// The wire protocol a provider speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ProviderKind {
+    /// OpenAI-compatible `/chat/completions` with bearer auth
+    OpenAi,
+    Anthropic,
+    Ollama,
+    /// LM Studio's OpenAI-compatible server, which needs no key
+    LmStudio,
+    /// Azure OpenAI: per-deployment URLs, `api-version` query and `api-key` header
+    Azure,
+    /// AWS Bedrock's OpenAI-compatible runtime, signed with SigV4 by the host
+    Bedrock,
+    /// Vertex AI's OpenAI-compatible endpoint, with service-account access tokens
+    Vertex,
+}
+
+impl ProviderKind {
+    pub(crate) fn of(provider: Option<&str>) -> Self {
+        match provider.map(str::trim) {
+            Some("anthropic") => ProviderKind::Anthropic,
+            Some("azure") => ProviderKind::Azure,
+            Some("bedrock") => ProviderKind::Bedrock,
+            Some("ollama") => ProviderKind::Ollama,
+            Some("lmstudio") => ProviderKind::LmStudio,
+            Some("vertex") => ProviderKind::Vertex,
+            _ => ProviderKind::OpenAi,
+        }
+    }
+
+    /// Whether requests need an API key at all.
+    pub(crate) fn needs_key(self) -> bool {
+        !matches!(
+            self,
+            ProviderKind::Ollama
+                | ProviderKind::LmStudio
+                | ProviderKind::Bedrock
+                | ProviderKind::Vertex
+        )
+    }
+}
+
+/// Azure OpenAI overrides. The deployment defaults to the model name and the API version
+/// to `AZURE_API_VERSION`, so most setups only need the resource URL as base URL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzureDeployment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deployment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_version: Option<String>,
+}
+
+impl AzureDeployment {
+    /// Trimmed copy; `None` when nothing is overridden.
+    pub(crate) fn normalized(self) -> Option<Self> {
+        let trim = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let normalized = Self {
+            deployment: trim(self.deployment),
+            api_version: trim(self.api_version),
+        };
+        (normalized.deployment.is_some() || normalized.api_version.is_some()).then_some(normalized)
+    }
+}
+
+/// Paths and query parameters for OpenAI-compatible gateways that don't follow the
+/// standard layout under their base URL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointOverrides {
+    /// Replaces `/chat/completions` after the base URL, e.g. `/v2/chat`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) chat_path: Option<String>,
+    /// Added to every request, e.g. a gateway's `api-version` or tenant id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) query: HashMap<String, String>,
+}
+
+impl EndpointOverrides {
+    /// Trimmed and checked copy; `None` when nothing is overridden.
+    pub(crate) fn normalized(self) -> Result<Option<Self>, String> {
+        let chat_path = self
+            .chat_path
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty());
+        if let Some(path) = &chat_path {
+            let valid = path.starts_with('/')
+                && !path.contains(['?', '#'])
+                && !path.chars().any(char::is_whitespace);
+            if !valid {
+                return Err(format!(
+                    "Chat path must start with / and have no query: {}",
+                    path
+                ));
+            }
+        }
+        if self.query.len() > MAX_QUERY_PARAMS {
+            return Err(format!(
+                "At most {} query parameters are allowed",
+                MAX_QUERY_PARAMS
+            ));
+        }
+        let mut query = HashMap::new();
+        for (name, value) in self.query {
+            let name = name.trim().to_string();
+            if name.is_empty() || name.contains(['&', '=', '?', '#']) {
+                return Err(format!("Invalid query parameter: {}", name));
+            }
+            query.insert(name, value.trim().to_string());
+        }
+        let normalized = Self { chat_path, query };
+        Ok((normalized.chat_path.is_some() || !normalized.query.is_empty()).then_some(normalized))
+    }
+}
+
+/// Per-request provider extras beyond provider, key, model and base URL, sent to the
+/// sidecar alongside them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProviderOptions {
+    /// Extra headers from a profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) headers: Option<HashMap<String, String>>,
+    /// Deployment overrides for the `azure` provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) azure: Option<AzureDeployment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) endpoint: Option<EndpointOverrides>,
+    /// The profile's proxy, or the default proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) proxy: Option<ResolvedProxy>,
+    /// Keychain entry the key came from, so a rate-limited key can be rotated out
+    #[serde(skip)]
+    pub(crate) key_ref: Option<String>,
+    /// Stream over the host's own HTTP client instead of the sidecar, without tools
+    #[serde(skip)]
+    pub(crate) native: bool,
+}
+
+/// `base_url` without a trailing slash, or the provider's default.
+pub(crate) fn base_url(
+    app: &tauri::AppHandle,
+    provider: Option<&str>,
+    base_url: Option<&str>,
+) -> Result<String, String> {
+    if let Some(url) = base_url.map(str::trim).filter(|u| !u.is_empty()) {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("Base URL must be an http(s) URL: {}", url));
+        }
+        return Ok(url.trim_end_matches('/').to_string());
+    }
+    let provider = provider.unwrap_or(DEFAULT_PROVIDER);
+    match ProviderKind::of(Some(provider)) {
+        ProviderKind::Bedrock => return Ok(crate::bedrock::runtime_base_url(app)),
+        ProviderKind::Vertex => return crate::vertex::runtime_base_url(app),
+        _ => {}
+    }
+    DEFAULT_BASE_URLS
+        .iter()
+        .find(|(id, _)| *id == provider)
+        .map(|(_, url)| url.to_string())
+        .ok_or_else(|| format!("No base URL configured for {}", provider))
+}
+
+/// `base_url` for the sidecar. Bedrock and Vertex AI endpoints depend on the region and
+/// project configured here, so those are filled in when the webview sent none.
+pub(crate) fn host_base_url(
+    app: &tauri::AppHandle,
+    provider: Option<&str>,
+    base_url: Option<String>,
+) -> Result<Option<String>, String> {
+    match ProviderKind::of(provider) {
+        ProviderKind::Bedrock | ProviderKind::Vertex if base_url.is_none() => {
+            self::base_url(app, provider, None).map(Some)
+        }
+        _ => Ok(base_url),
+    }
+}
+
+/// Adds the provider's authentication headers; a missing key sends none.
+pub(crate) fn authorize(
+    request: reqwest::RequestBuilder,
+    kind: ProviderKind,
+    api_key: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let request = match kind {
+        ProviderKind::Anthropic => request.header("anthropic-version", ANTHROPIC_VERSION),
+        _ => request,
+    };
+    match (kind, api_key.map(str::trim).filter(|k| !k.is_empty())) {
+        (_, None) => request,
+        (ProviderKind::Anthropic, Some(key)) => request.header("x-api-key", key),
+        (ProviderKind::Azure, Some(key)) => request.header("api-key", key),
+        (_, Some(key)) => request.bearer_auth(key),
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::{load_json, save_json};
+use crate::secrets;
+
+const PROXY_SETTINGS_FILE: &str = "proxy.json";
+/// Keychain entry for the default proxy's password.
+const DEFAULT_PASSWORD_REF: &str = "proxy-default";
+const SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+const MAX_BYPASS_ENTRIES: usize = 50;
+
+/// A proxy for provider traffic. The password lives in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` with host and port
+    url: String,
+    #[serde(default)]
+    username: Option<String>,
+    /// Hosts reached directly: names, `.domain` suffixes, IP addresses, or `*`
+    #[serde(default)]
+    bypass: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProxyFile {
+    default: Option<ProxyConfig>,
+}
+
+/// A proxy with its password, as sent to the sidecar.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResolvedProxy {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bypass: Vec<String>,
+}
+
+// The password stays out of logs
+impl std::fmt::Debug for ResolvedProxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResolvedProxy")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("bypass", &self.bypass)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Checks and trims `config`; `None` when its URL is empty, which means no proxy.
+pub(crate) fn normalize(config: ProxyConfig) -> Result<Option<ProxyConfig>, String> {
+    let url = config.url.trim().trim_end_matches('/').to_string();
+    if url.is_empty() {
+        return Ok(None);
+    }
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+    if !SCHEMES.contains(&parsed.scheme()) {
+        return Err(format!(
+            "Proxy URL must use one of {}: {}",
+            SCHEMES.join(", "),
+            url
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) || parsed.port_or_known_default().is_none() {
+        return Err(format!("Proxy URL needs a host and port: {}", url));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err("Put proxy credentials in the username and password fields".to_string());
+    }
+    if parsed.path() != "/" && !parsed.path().is_empty() {
+        return Err(format!("Proxy URL must not have a path: {}", url));
+    }
+    if config.bypass.len() > MAX_BYPASS_ENTRIES {
+        return Err(format!(
+            "At most {} bypass entries are allowed",
+            MAX_BYPASS_ENTRIES
+        ));
+    }
+    let mut bypass = Vec::new();
+    for host in config.bypass {
+        let host = host.trim().to_lowercase();
+        if host.is_empty() {
+            continue;
+        }
+        if host.contains(|c: char| c.is_whitespace() || c == '/' || c == ',') {
+            return Err(format!("Invalid bypass entry: {}", host));
+        }
+        if !bypass.contains(&host) {
+            bypass.push(host);
+        }
+    }
+    Ok(Some(ProxyConfig {
+        url,
+        username: config
+            .username
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty()),
+        bypass,
+    }))
+}
+
+/// `config` with the password saved under `password_ref`.
+pub(crate) fn resolve(config: ProxyConfig, password_ref: &str) -> Result<ResolvedProxy, String> {
+    let password = match config.username {
+        Some(_) => secrets::api_key(password_ref)?,
+        None => None,
+    };
+    Ok(ResolvedProxy {
+        url: config.url,
+        username: config.username,
+        password,
+        bypass: config.bypass,
+    })
+}
+
+/// The default proxy, used for every provider without one of its own.
+pub(crate) fn default_proxy(app: &tauri::AppHandle) -> Result<Option<ResolvedProxy>, String> {
+    load_json::<ProxyFile>(app, PROXY_SETTINGS_FILE)?
+        .default
+        .map(|config| resolve(config, DEFAULT_PASSWORD_REF))
+        .transpose()
+}
+
+/// HTTP client for requests the host makes itself, such as model lists and token
+/// exchanges, routed through the default proxy. Falls back to a direct client if the
+/// proxy can't be set up, so those requests fail with a network error rather than not
+/// at all.
+pub(crate) fn http_client(app: &tauri::AppHandle) -> reqwest::Client {
+    match default_proxy(app) {
+        Ok(proxy) => client(proxy.as_ref()),
+        Err(err) => {
+            eprintln!("[proxy] failed to load the default proxy: {}", err);
+            reqwest::Client::new()
+        }
+    }
+}
+
+/// HTTP client routed through `proxy`, or a direct one without it or if it can't be set up.
+pub(crate) fn client(proxy: Option<&ResolvedProxy>) -> reqwest::Client {
+    let Some(proxy) = proxy else {
+        return reqwest::Client::new();
+    };
+    match build_client(proxy) {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("[proxy] failed to configure {}: {}", proxy.url, err);
+            reqwest::Client::new()
+        }
+    }
+}
+
+fn build_client(proxy: &ResolvedProxy) -> Result<reqwest::Client, String> {
+    let mut url = reqwest::Url::parse(&proxy.url).map_err(|e| e.to_string())?;
+    let socks = url.scheme().starts_with("socks");
+    // SOCKS credentials are only read from the URL
+    if socks {
+        if let Some(username) = &proxy.username {
+            url.set_username(username)
+                .map_err(|_| "Invalid proxy username")?;
+            url.set_password(proxy.password.as_deref())
+                .map_err(|_| "Invalid proxy password")?;
+        }
+    }
+    let mut config = reqwest::Proxy::all(url.as_str()).map_err(|e| e.to_string())?;
+    if let (false, Some(username)) = (socks, &proxy.username) {
+        config = config.basic_auth(username, proxy.password.as_deref().unwrap_or_default());
+    }
+    config = config.no_proxy(reqwest::NoProxy::from_string(&proxy.bypass.join(",")));
+    reqwest::Client::builder()
+        .proxy(config)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Environment for the sidecar process, so tool traffic that honours the usual proxy
+/// variables follows the default proxy too.
+pub(crate) fn sidecar_env(app: &tauri::AppHandle) -> Vec<(String, String)> {
+    let proxy = match default_proxy(app) {
+        Ok(Some(proxy)) => proxy,
+        Ok(None) => return Vec::new(),
+        Err(err) => {
+            eprintln!("[proxy] failed to load the default proxy: {}", err);
+            return Vec::new();
+        }
+    };
+    let Ok(mut url) = reqwest::Url::parse(&proxy.url) else {
+        return Vec::new();
+    };
+    if let Some(username) = &proxy.username {
+        let _ = url.set_username(username);
+        let _ = url.set_password(proxy.password.as_deref());
+    }
+    let url = url.as_str().trim_end_matches('/').to_string();
+    let mut env: Vec<(String, String)> = if proxy.url.starts_with("socks") {
+        vec![("ALL_PROXY".to_string(), url)]
+    } else {
+        vec![
+            ("HTTP_PROXY".to_string(), url.clone()),
+            ("HTTPS_PROXY".to_string(), url),
+        ]
+    };
+    if !proxy.bypass.is_empty() {
+        env.push(("NO_PROXY".to_string(), proxy.bypass.join(",")));
+    }
+    env
+}
+
+#[tauri::command]
+pub fn get_default_proxy(app: tauri::AppHandle) -> Result<Option<ProxyConfig>, String> {
+    Ok(load_json::<ProxyFile>(&app, PROXY_SETTINGS_FILE)?.default)
+}
+
+/// Sets or, with an empty URL, clears the default proxy. `password` replaces the saved
+/// one when given; an empty string removes it. Provider requests use the new proxy
+/// at once; tools in the sidecar pick it up when it next starts.
+#[tauri::command]
+pub fn set_default_proxy(
+    app: tauri::AppHandle,
+    proxy: Option<ProxyConfig>,
+    password: Option<String>,
+) -> Result<Option<ProxyConfig>, String> {
+    let proxy = match proxy {
+        Some(proxy) => normalize(proxy)?,
+        None => None,
+    };
+    if proxy.is_none() {
+        secrets::store_api_key(DEFAULT_PASSWORD_REF, "")?;
+    } else if let Some(password) = password {
+        secrets::store_api_key(DEFAULT_PASSWORD_REF, &password)?;
+    }
+    save_json(
+        &app,
+        PROXY_SETTINGS_FILE,
+        &ProxyFile {
+            default: proxy.clone(),
+        },
+    )?;
+    Ok(proxy)
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::workspace::workspace_root;
+
+const RECENT_WORKSPACES_FILE: &str = "recent_workspaces.json";
+// Unpinned entries beyond this are dropped, oldest first
+const MAX_RECENT: usize = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentWorkspace {
+    path: String,
+    name: String,
+    last_opened_at: i64,
+    #[serde(default)]
+    pinned: bool,
+    /// Whether the folder is still there, checked again on every listing
+    #[serde(skip_deserializing)]
+    exists: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecentWorkspacesFile {
+    workspaces: Vec<RecentWorkspace>,
+}
+
+fn load(app: &tauri::AppHandle) -> Result<RecentWorkspacesFile, String> {
+    load_json(app, RECENT_WORKSPACES_FILE)
+}
+
+/// Pinned first, then most recently opened.
+fn sorted(mut workspaces: Vec<RecentWorkspace>) -> Vec<RecentWorkspace> {
+    for workspace in &mut workspaces {
+        workspace.exists = std::path::Path::new(&workspace.path).is_dir();
+    }
+    workspaces.sort_by(|a, b| {
+        b.pinned
+            .cmp(&a.pinned)
+            .then(b.last_opened_at.cmp(&a.last_opened_at))
+    });
+    workspaces
+}
+
+/// Records that `path` was opened and returns its entry.
+#[tauri::command]
+pub fn open_workspace(app: tauri::AppHandle, path: String) -> Result<RecentWorkspace, String> {
+    let root = workspace_root(&path)?;
+    let path = root.to_string_lossy().into_owned();
+    let mut file = load(&app)?;
+    let pinned = file.workspaces.iter().any(|w| w.path == path && w.pinned);
+    file.workspaces.retain(|w| w.path != path);
+    let entry = RecentWorkspace {
+        name: root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone()),
+        path,
+        last_opened_at: now_millis(),
+        pinned,
+        exists: true,
+    };
+    file.workspaces.push(entry.clone());
+    let mut workspaces = sorted(std::mem::take(&mut file.workspaces));
+    let mut unpinned = 0;
+    workspaces.retain(|w| {
+        unpinned += usize::from(!w.pinned);
+        w.pinned || unpinned <= MAX_RECENT
+    });
+    file.workspaces = workspaces;
+    save_json(&app, RECENT_WORKSPACES_FILE, &file)?;
+    Ok(entry)
+}
+
+/// Recently opened workspaces, pinned first. Folders that were moved or deleted are kept
+/// with `exists: false` so the launch screen can offer to remove them.
+#[tauri::command]
+pub fn get_recent_workspaces(app: tauri::AppHandle) -> Result<Vec<RecentWorkspace>, String> {
+    Ok(sorted(load(&app)?.workspaces))
+}
+
+#[tauri::command]
+pub fn remove_recent_workspace(app: tauri::AppHandle, path: String) -> Result<bool, String> {
+    let mut file = load(&app)?;
+    let before = file.workspaces.len();
+    file.workspaces.retain(|w| w.path != path);
+    let removed = file.workspaces.len() != before;
+    if removed {
+        save_json(&app, RECENT_WORKSPACES_FILE, &file)?;
+    }
+    Ok(removed)
+}
+
+/// Pinned workspaces stay at the top and are never dropped from the list.
+#[tauri::command]
+pub fn set_workspace_pinned(
+    app: tauri::AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<bool, String> {
+    let mut file = load(&app)?;
+    let Some(workspace) = file.workspaces.iter_mut().find(|w| w.path == path) else {
+        return Ok(false);
+    };
+    workspace.pinned = pinned;
+    save_json(&app, RECENT_WORKSPACES_FILE, &file)?;
+    Ok(true)
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::app_data::app_data_path;
+use crate::clock::now_millis;
+
+const RECORDINGS_DIR: &str = "recordings";
+const RECORDING_VERSION: u32 = 1;
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 20.0;
+// Long idle stretches are compressed so a replay never stalls on a coffee break
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(5);
+
+struct Recording {
+    path: PathBuf,
+    started: Instant,
+    writer: BufWriter<File>,
+    events: u64,
+}
+
+/// Active recording, if any, plus a counter that invalidates running replays.
+#[derive(Default)]
+pub(crate) struct SessionRecorder {
+    recording: Mutex<Option<Recording>>,
+    replay_generation: AtomicU64,
+}
+
+/// One line of a recording: the event name and payload as they were emitted to the UI.
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedEvent {
+    /// Milliseconds since the recording started
+    t: u64,
+    event: String,
+    payload: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    path: String,
+    events: u64,
+}
+
+/// Appends an event to the active recording; a no-op when nothing is being recorded.
+pub(crate) fn record<P: Serialize>(app: &tauri::AppHandle, event: &str, payload: &P) {
+    let recorder = app.state::<SessionRecorder>();
+    let mut guard = recorder.recording.lock().unwrap();
+    let Some(recording) = guard.as_mut() else {
+        return;
+    };
+    let Ok(payload) = serde_json::to_value(payload) else {
+        return;
+    };
+    let line = RecordedEvent {
+        t: recording.started.elapsed().as_millis() as u64,
+        event: event.to_string(),
+        payload,
+    };
+    if let Ok(line) = serde_json::to_string(&line) {
+        if writeln!(recording.writer, "{}", line).is_ok() {
+            recording.events += 1;
+        }
+    }
+}
+
+#[tauri::command]
+pub fn start_recording(app: tauri::AppHandle) -> Result<RecordingInfo, String> {
+    let recorder = app.state::<SessionRecorder>();
+    let mut guard = recorder.recording.lock().unwrap();
+    if let Some(recording) = guard.as_ref() {
+        return Err(format!("Already recording to {}", recording.path.display()));
+    }
+
+    let path = app_data_path(
+        &app,
+        &format!("{}/session-{}.jsonl", RECORDINGS_DIR, now_millis()),
+    )?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+    }
+    let file = File::create(&path).map_err(|e| format!("Failed to create recording: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    let header = json!({ "version": RECORDING_VERSION, "startedAt": now_millis() });
+    writeln!(writer, "{}", header).map_err(|e| format!("Failed to write recording: {}", e))?;
+
+    let info = RecordingInfo {
+        path: path.to_string_lossy().to_string(),
+        events: 0,
+    };
+    *guard = Some(Recording {
+        path,
+        started: Instant::now(),
+        writer,
+        events: 0,
+    });
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn stop_recording(app: tauri::AppHandle) -> Result<Option<RecordingInfo>, String> {
+    let recorder = app.state::<SessionRecorder>();
+    let Some(mut recording) = recorder.recording.lock().unwrap().take() else {
+        return Ok(None);
+    };
+    recording
+        .writer
+        .flush()
+        .map_err(|e| format!("Failed to finish recording: {}", e))?;
+    Ok(Some(RecordingInfo {
+        path: recording.path.to_string_lossy().to_string(),
+        events: recording.events,
+    }))
+}
+
+/// Lets the frontend capture events only it sees, such as edits to a message.
+#[tauri::command]
+pub fn record_session_event(app: tauri::AppHandle, event: String, payload: Value) {
+    record(&app, &format!("session:{}", event), &payload);
+}
+
+/// Re-emits a recording's events with their original timing divided by `speed`.
+/// Returns the number of events scheduled; the replay runs in the background.
+#[tauri::command]
+pub fn replay_session(
+    app: tauri::AppHandle,
+    path: String,
+    speed: Option<f64>,
+) -> Result<usize, String> {
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() {
+        return Err("Replay speed must be a finite number".to_string());
+    }
+    let speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+
+    let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let events: Vec<RecordedEvent> = BufReader::new(file)
+        .lines()
+        .skip(1)
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    let count = events.len();
+
+    let recorder = app.state::<SessionRecorder>();
+    let generation = recorder.replay_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    tauri::async_runtime::spawn(async move {
+        let _ = app.emit("replay:started", json!({ "path": path, "events": count }));
+        let mut previous = 0;
+        for event in events {
+            let gap = Duration::from_millis(event.t.saturating_sub(previous)).min(MAX_REPLAY_GAP);
+            previous = event.t;
+            tokio::time::sleep(gap.div_f64(speed)).await;
+
+            // A newer replay or `stop_replay` supersedes this one
+            if app
+                .state::<SessionRecorder>()
+                .replay_generation
+                .load(Ordering::SeqCst)
+                != generation
+            {
+                return;
+            }
+            let _ = app.emit(&event.event, event.payload);
+        }
+        let _ = app.emit("replay:finished", json!({ "path": path }));
+    });
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn stop_replay(app: tauri::AppHandle) {
+    app.state::<SessionRecorder>()
+        .replay_generation
+        .fetch_add(1, Ordering::SeqCst);
+}
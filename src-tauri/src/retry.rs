@@ -0,0 +1,131 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::clock::now_millis;
+use crate::rpc::{RpcError, ERR_CANCELLED, ERR_OVERSIZED, ERR_TIMEOUT, ERR_TRANSPORT};
+
+pub(crate) const DEFAULT_MAX_RETRIES: u32 = 2;
+// Hard cap so a misconfigured client can't keep a request alive forever
+const MAX_RETRIES_LIMIT: u32 = 6;
+const BASE_DELAY_MS: u64 = 500;
+const MAX_DELAY_MS: u64 = 15_000;
+/// Rate-limited attempts are retried on their own budget, apart from `max_retries`.
+pub(crate) const RATE_LIMIT_RETRIES: u32 = 4;
+// Longer waits are reported to the user instead of being sat out
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(120);
+
+const CONNECTION_ERROR_HINTS: &[&str] = &[
+    "econnreset",
+    "econnrefused",
+    "etimedout",
+    "socket hang up",
+    "connection reset",
+    "connection error",
+    "network error",
+    "fetch failed",
+];
+
+pub(crate) fn effective_max_retries(requested: Option<u32>) -> u32 {
+    requested
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+        .min(MAX_RETRIES_LIMIT)
+}
+
+/// Returns the retryable error class for a sidecar failure, or `None` if retrying won't help.
+pub(crate) fn retryable_class(err: &RpcError) -> Option<&'static str> {
+    // Host-side failures (sidecar gone, our own timeout) are not the provider's fault
+    if matches!(
+        err.code,
+        ERR_TRANSPORT | ERR_TIMEOUT | ERR_CANCELLED | ERR_OVERSIZED
+    ) {
+        return None;
+    }
+
+    match err.status() {
+        Some(429) => return Some("rate_limit"),
+        Some(500..=599) => return Some("server_error"),
+        Some(_) => return None,
+        None => {}
+    }
+
+    let message = err.message.to_lowercase();
+    if message.contains("rate limit") || message.contains("status 429") {
+        return Some("rate_limit");
+    }
+    if CONNECTION_ERROR_HINTS
+        .iter()
+        .any(|hint| message.contains(hint))
+    {
+        return Some("connection");
+    }
+    None
+}
+
+/// Exponential backoff with jitter: a random delay between half and all of `base * 2^attempt`.
+pub(crate) fn backoff_delay(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(MAX_DELAY_MS);
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+    let half = ceiling / 2;
+    Duration::from_millis(half + seed % (half + 1))
+}
+
+/// When each provider endpoint stops rate limiting us, as Unix millis, so other requests
+/// to it wait their turn instead of drawing another 429.
+#[derive(Default)]
+pub(crate) struct RateLimits(Mutex<HashMap<String, i64>>);
+
+/// Rate limits are tracked per key: other keys for the same endpoint have their own.
+/// The key is only hashed here, never kept.
+pub(crate) fn rate_limit_key(
+    provider: Option<&str>,
+    base_url: Option<&str>,
+    api_key: &str,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    api_key.hash(&mut hasher);
+    format!(
+        "{}|{}|{:x}",
+        provider.unwrap_or_default(),
+        base_url.unwrap_or_default(),
+        hasher.finish()
+    )
+}
+
+/// How long to wait after a 429: the provider's `Retry-After`, or backoff without one.
+/// `None` when the provider wants a longer pause than is worth holding the request for.
+pub(crate) fn rate_limit_delay(err: &RpcError, attempt: u32) -> Option<Duration> {
+    match err.retry_after() {
+        Some(wait) if wait > MAX_RATE_LIMIT_WAIT => None,
+        Some(wait) => Some(wait),
+        None => Some(backoff_delay(attempt)),
+    }
+}
+
+/// Holds requests to `key` back for `wait`.
+pub(crate) fn note_rate_limited(app: &tauri::AppHandle, key: &str, wait: Duration) {
+    let until = now_millis() + wait.as_millis() as i64;
+    let state = app.state::<RateLimits>();
+    let mut limits = state.0.lock().unwrap();
+    let entry = limits.entry(key.to_string()).or_insert(until);
+    *entry = (*entry).max(until);
+}
+
+/// The time left before `key` may be called again, if it is currently rate limited.
+pub(crate) fn rate_limit_remaining(app: &tauri::AppHandle, key: &str) -> Option<Duration> {
+    let state = app.state::<RateLimits>();
+    let mut limits = state.0.lock().unwrap();
+    let now = now_millis();
+    limits.retain(|_, until| *until > now);
+    limits
+        .get(key)
+        .map(|until| Duration::from_millis((*until - now) as u64))
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn spawn(mut command: Command) -> std::io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    let mut command = Command::new("open");
+    command.arg("-R").arg(path);
+    spawn(command)
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    // Explorer wants `/select,` and the path as one argument; it also exits with 1 on success,
+    // so its status says nothing
+    let mut arg = std::ffi::OsString::from("/select,");
+    arg.push(path);
+    let mut command = Command::new("explorer");
+    command.arg(arg);
+    spawn(command)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal(path: &Path) -> std::io::Result<()> {
+    // File managers implementing the freedesktop interface select the item; otherwise the
+    // folder holding it is opened
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    let selected = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", uri))
+        .arg("string:")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if selected {
+        return Ok(());
+    }
+    let folder = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    let mut command = Command::new("xdg-open");
+    command.arg(folder);
+    spawn(command)
+}
+
+/// Shows `path` (absolute) selected in Finder, Explorer or the desktop's file manager.
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let path = Path::new(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path.display()));
+    }
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    reveal(path).map_err(|e| format!("Failed to open the file manager: {}", e))
+}
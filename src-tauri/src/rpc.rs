@@ -0,0 +1,561 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+use tauri_plugin_shell::process::CommandChild;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use crate::a11y;
+use crate::activity;
+use crate::agent_tasks;
+use crate::bedrock;
+use crate::changesets::{self, ProposedChange};
+use crate::chunks::{ChunkAssembler, ChunkOutcome, ResultChunk};
+use crate::clock::now_millis;
+use crate::custom_tools;
+use crate::incidents;
+use crate::mcp;
+use crate::memory;
+use crate::permissions;
+use crate::recorder;
+use crate::streams::StreamBuffers;
+use crate::terminal;
+use crate::tool_events;
+use crate::usage;
+
+static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+// For the sidecar to take a reply to one of its own requests
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Host-side error codes, kept clear of the JSON-RPC range the sidecar uses
+pub(crate) const ERR_TRANSPORT: i32 = -33001;
+pub(crate) const ERR_TIMEOUT: i32 = -33002;
+pub(crate) const ERR_CANCELLED: i32 = -33003;
+pub(crate) const ERR_OVERSIZED: i32 = -33004;
+
+#[derive(Debug, Serialize)]
+struct RpcRequest<'a, P: Serialize> {
+    id: u64,
+    session: &'a str,
+    method: &'a str,
+    params: &'a P,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    id: Option<u64>,
+    #[serde(default)]
+    session: Option<String>,
+    result: Option<String>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(default)]
+    pub data: Option<serde_json::Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// HTTP status reported by the sidecar for provider errors, if any.
+    pub fn status(&self) -> Option<u64> {
+        self.data
+            .as_ref()
+            .and_then(|d| d.get("status"))
+            .and_then(|s| s.as_u64())
+    }
+
+    /// How long the provider asked us to wait (`Retry-After`), if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.data
+            .as_ref()
+            .and_then(|d| d.get("retryAfterMs"))
+            .and_then(|ms| ms.as_f64())
+            .filter(|ms| ms.is_finite() && *ms >= 0.0)
+            .map(|ms| Duration::from_millis(ms.ceil() as u64))
+    }
+}
+
+/// `{event:"progress", request_id, phase, percent, detail}` reported during long multi-step tasks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+struct ProgressEvent {
+    #[serde(default, alias = "requestId")]
+    request_id: Option<String>,
+    phase: String,
+    #[serde(default)]
+    percent: Option<f64>,
+    #[serde(default)]
+    detail: Option<String>,
+}
+
+/// `{event:"changeset_proposed", requestId, workspace, title, changes}` from the agent.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangesetProposal {
+    #[serde(default)]
+    request_id: Option<String>,
+    workspace: String,
+    #[serde(default)]
+    title: String,
+    changes: Vec<ProposedChange>,
+}
+
+/// `{callId, result, error}` answering a request the sidecar logged as an event.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarReply<'a, T: Serialize> {
+    call_id: &'a str,
+    result: Option<T>,
+    error: Option<String>,
+}
+
+pub(crate) struct PendingRequest {
+    /// Sidecar generation the request was written to
+    pub generation: String,
+    pub tx: oneshot::Sender<Result<String, RpcError>>,
+}
+
+pub(crate) type PendingRequests = Mutex<HashMap<u64, PendingRequest>>;
+pub(crate) type SidecarProcess = Mutex<Option<CommandChild>>;
+
+/// Session id of the currently running sidecar process, regenerated on every spawn.
+#[derive(Default)]
+pub(crate) struct SidecarGeneration(pub Mutex<String>);
+
+const RPC_LOG_CAPACITY: usize = 200;
+
+/// Metadata of a finished call; params are deliberately left out since they carry API keys.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RpcLogEntry {
+    id: u64,
+    method: String,
+    started_at: i64,
+    duration_ms: u64,
+    error_code: Option<i32>,
+    error: Option<String>,
+}
+
+/// Ring buffer of the most recent calls, bundled into incident reports.
+#[derive(Default)]
+pub(crate) struct RpcLog(Mutex<VecDeque<RpcLogEntry>>);
+
+impl RpcLog {
+    pub fn recent(&self) -> Vec<RpcLogEntry> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn push(&self, entry: RpcLogEntry) {
+        let mut log = self.0.lock().unwrap();
+        if log.len() >= RPC_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(entry);
+    }
+}
+
+fn log_call(
+    app: &tauri::AppHandle,
+    id: u64,
+    method: &str,
+    started_at: i64,
+    started: Instant,
+    result: &Result<String, RpcError>,
+) {
+    app.state::<RpcLog>().push(RpcLogEntry {
+        id,
+        method: method.to_string(),
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        error_code: result.as_ref().err().map(|e| e.code),
+        error: result.as_ref().err().map(|e| e.message.clone()),
+    });
+}
+
+fn current_generation(app: &tauri::AppHandle) -> String {
+    app.state::<SidecarGeneration>().0.lock().unwrap().clone()
+}
+
+/// Sends one JSON-RPC request to the sidecar and waits for its response.
+pub(crate) async fn call<P: Serialize>(
+    app: &tauri::AppHandle,
+    method: &str,
+    params: &P,
+    timeout: Duration,
+) -> Result<String, RpcError> {
+    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    let started_at = now_millis();
+    let started = Instant::now();
+
+    let result = send_request(app, id, method, params, timeout).await;
+    log_call(app, id, method, started_at, started, &result);
+    if let Err(err) = &result {
+        incidents::on_rpc_error(app, method, err);
+    }
+    result
+}
+
+async fn send_request<P: Serialize>(
+    app: &tauri::AppHandle,
+    id: u64,
+    method: &str,
+    params: &P,
+    timeout: Duration,
+) -> Result<String, RpcError> {
+    let generation = current_generation(app);
+
+    let request = RpcRequest {
+        id,
+        session: &generation,
+        method,
+        params,
+    };
+    let request_json =
+        serde_json::to_string(&request).map_err(|e| RpcError::new(ERR_TRANSPORT, e.to_string()))?;
+
+    let rx = register(app, id, &generation);
+    if let Err(err) = write_line(app, request_json) {
+        forget(app, id);
+        return Err(err);
+    }
+
+    wait_for(app, id, rx, Instant::now() + timeout).await
+}
+
+/// Answers the sidecar's request `call_id` by calling `method`, e.g. `memoryReply`, with
+/// the outcome. A reply that can't be delivered is only logged: the sidecar times out.
+pub(crate) async fn reply_to_sidecar<T: Serialize>(
+    app: &tauri::AppHandle,
+    method: &str,
+    call_id: &str,
+    outcome: Result<T, String>,
+) {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(err) => (None, Some(err)),
+    };
+    let reply = SidecarReply {
+        call_id,
+        result,
+        error,
+    };
+    if let Err(err) = call(app, method, &reply, REPLY_TIMEOUT).await {
+        eprintln!("[rpc] failed to deliver {}: {}", method, err.message);
+    }
+}
+
+/// Sends several independent requests as one batch frame; each resolves through its own
+/// pending entry.
+pub(crate) async fn call_batch<P: Serialize>(
+    app: &tauri::AppHandle,
+    method: &str,
+    params: &[P],
+    timeout: Duration,
+) -> Vec<Result<String, RpcError>> {
+    let ids: Vec<u64> = params
+        .iter()
+        .map(|_| REQUEST_ID.fetch_add(1, Ordering::SeqCst))
+        .collect();
+    let generation = current_generation(app);
+    let requests: Vec<RpcRequest<P>> = ids
+        .iter()
+        .zip(params)
+        .map(|(id, params)| RpcRequest {
+            id: *id,
+            session: &generation,
+            method,
+            params,
+        })
+        .collect();
+
+    let batch_json = match serde_json::to_string(&requests) {
+        Ok(json) => json,
+        Err(e) => {
+            return ids
+                .iter()
+                .map(|_| Err(RpcError::new(ERR_TRANSPORT, e.to_string())))
+                .collect()
+        }
+    };
+
+    let receivers: Vec<_> = ids
+        .iter()
+        .map(|id| register(app, *id, &generation))
+        .collect();
+    if let Err(err) = write_line(app, batch_json) {
+        for id in &ids {
+            forget(app, *id);
+        }
+        return ids.iter().map(|_| Err(err.clone())).collect();
+    }
+
+    // The sidecar processes batch entries concurrently, so one shared deadline covers them all
+    let deadline = Instant::now() + timeout;
+    let mut results = Vec::with_capacity(ids.len());
+    for (id, rx) in ids.into_iter().zip(receivers) {
+        results.push(wait_for(app, id, rx, deadline).await);
+    }
+    results
+}
+
+fn register(
+    app: &tauri::AppHandle,
+    id: u64,
+    generation: &str,
+) -> oneshot::Receiver<Result<String, RpcError>> {
+    let (tx, rx) = oneshot::channel();
+    let pending = app.state::<PendingRequests>();
+    pending.lock().unwrap().insert(
+        id,
+        PendingRequest {
+            generation: generation.to_string(),
+            tx,
+        },
+    );
+    rx
+}
+
+/// Removes and returns the pending entry for `id` only if it belongs to `generation`.
+fn take_pending(
+    app: &tauri::AppHandle,
+    id: u64,
+    generation: &str,
+) -> Option<oneshot::Sender<Result<String, RpcError>>> {
+    let pending = app.state::<PendingRequests>();
+    let mut map = pending.lock().unwrap();
+    if map.get(&id)?.generation != generation {
+        eprintln!(
+            "[sidecar] dropping response {} from a previous generation",
+            id
+        );
+        return None;
+    }
+    map.remove(&id).map(|entry| entry.tx)
+}
+
+/// Buffers, records and emits an `assistant_delta` event as `agent:delta`.
+pub(crate) fn forward_delta(app: &tauri::AppHandle, value: serde_json::Value) {
+    if let (Some(request_id), Some(delta)) = (
+        value.get("requestId").and_then(|v| v.as_str()),
+        value.get("delta").and_then(|v| v.as_str()),
+    ) {
+        app.state::<StreamBuffers>().append(request_id, delta);
+        agent_tasks::record_text(app, request_id, delta);
+    }
+    recorder::record(app, "agent:delta", &value);
+    a11y::emit_delta(app, value);
+}
+
+/// Whether a sidecar process has been started and not torn down.
+pub(crate) fn sidecar_running(app: &tauri::AppHandle) -> bool {
+    app.state::<SidecarProcess>().lock().unwrap().is_some()
+}
+
+fn write_line(app: &tauri::AppHandle, json: String) -> Result<(), RpcError> {
+    let sidecar = app.state::<SidecarProcess>();
+    let mut guard = sidecar.lock().unwrap();
+    if let Some(ref mut child) = *guard {
+        let data = (json + "\n").into_bytes();
+        child
+            .write(&data)
+            .map_err(|e| RpcError::new(ERR_TRANSPORT, format!("Failed to write to sidecar: {}", e)))
+    } else {
+        Err(RpcError::new(ERR_TRANSPORT, "Sidecar not running"))
+    }
+}
+
+async fn wait_for(
+    app: &tauri::AppHandle,
+    id: u64,
+    rx: oneshot::Receiver<Result<String, RpcError>>,
+    deadline: Instant,
+) -> Result<String, RpcError> {
+    match tokio::time::timeout_at(deadline, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(RpcError::new(ERR_CANCELLED, "Request cancelled")),
+        Err(_) => {
+            forget(app, id);
+            Err(RpcError::new(ERR_TIMEOUT, "Request timed out"))
+        }
+    }
+}
+
+fn forget(app: &tauri::AppHandle, id: u64) {
+    app.state::<PendingRequests>().lock().unwrap().remove(&id);
+    app.state::<Mutex<ChunkAssembler>>()
+        .lock()
+        .unwrap()
+        .discard(id);
+}
+
+fn handle_result_chunk(app: &tauri::AppHandle, generation: &str, chunk: ResultChunk) {
+    let id = chunk.id;
+    let pending = app.state::<PendingRequests>();
+    let assembler = app.state::<Mutex<ChunkAssembler>>();
+    let mut assembler = assembler.lock().unwrap();
+
+    // The request already timed out, was cancelled, or belongs to another generation
+    let owned = pending
+        .lock()
+        .unwrap()
+        .get(&id)
+        .is_some_and(|entry| entry.generation == generation);
+    if !owned {
+        assembler.discard(id);
+        return;
+    }
+
+    let result = match assembler.push(chunk) {
+        ChunkOutcome::Pending => return,
+        ChunkOutcome::Complete(result) => Ok(result),
+        ChunkOutcome::Failed(message) => Err(RpcError::new(ERR_OVERSIZED, message)),
+    };
+
+    if let Some(tx) = take_pending(app, id, generation) {
+        let _ = tx.send(result);
+    }
+}
+
+/// Routes one stdout line from the sidecar process identified by `generation`.
+pub(crate) fn handle_sidecar_output(app: &tauri::AppHandle, generation: &str, line: &str) {
+    // Skip empty lines
+    if line.trim().is_empty() {
+        return;
+    }
+
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(line) {
+        if let Some(event_name) = value.get("event").and_then(|v| v.as_str()) {
+            if event_name == "agent_status" {
+                if let Ok(status) = serde_json::from_value::<activity::AgentStatus>(value) {
+                    activity::handle_status(app, status);
+                }
+                return;
+            }
+            if event_name == "changeset_proposed" {
+                if let Ok(proposal) = serde_json::from_value::<ChangesetProposal>(value) {
+                    if let Err(err) = changesets::propose(
+                        app,
+                        &proposal.workspace,
+                        &proposal.title,
+                        proposal.request_id.as_deref(),
+                        proposal.changes,
+                    ) {
+                        eprintln!("[changesets] rejected proposal: {}", err);
+                    }
+                }
+                return;
+            }
+            if event_name == "tool_execution" {
+                recorder::record(app, "tool:execution", &value);
+                let _ = app.emit("tool:execution", value);
+                return;
+            }
+            if event_name == "sign_request" {
+                bedrock::handle_sign_request(app, value);
+                return;
+            }
+            if event_name == "mcp_request" {
+                mcp::handle_request(app, value);
+                return;
+            }
+            if event_name == "custom_tool_request" {
+                custom_tools::handle_request(app, value);
+                return;
+            }
+            if event_name == "memory_request" {
+                memory::handle_request(app, value);
+                return;
+            }
+            if event_name == "permission_request" {
+                permissions::handle_request(app, value);
+                return;
+            }
+            if event_name == "permission_cancel" {
+                permissions::handle_cancel(app, value);
+                return;
+            }
+            if event_name == "terminal_request" {
+                terminal::handle_request(app, value);
+                return;
+            }
+            if event_name == "terminal_cancel" {
+                terminal::handle_cancel(app, value);
+                return;
+            }
+            if event_name == "tool_use_start" {
+                tool_events::handle_start(app, value);
+                return;
+            }
+            if event_name == "tool_use_end" {
+                tool_events::handle_end(app, value);
+                return;
+            }
+            if event_name == "assistant_delta" {
+                forward_delta(app, value);
+                return;
+            }
+            if event_name == "progress" {
+                if let Ok(mut progress) = serde_json::from_value::<ProgressEvent>(value) {
+                    progress.percent = progress.percent.map(|p| p.clamp(0.0, 100.0));
+                    a11y::progress(
+                        app,
+                        progress.request_id.as_deref(),
+                        &progress.phase,
+                        progress.percent,
+                    );
+                    recorder::record(app, "agent:progress", &progress);
+                    let _ = app.emit("agent:progress", progress);
+                }
+                return;
+            }
+            if event_name == "usage" {
+                if let Ok(report) = serde_json::from_value(value) {
+                    usage::handle_report(app, report);
+                }
+                return;
+            }
+            if event_name == "result_chunk" {
+                if let Ok(chunk) = serde_json::from_value::<ResultChunk>(value) {
+                    if chunk.session.as_deref().is_none_or(|s| s == generation) {
+                        handle_result_chunk(app, generation, chunk);
+                    }
+                }
+                return;
+            }
+        }
+    }
+
+    // Try to parse as RPC response
+    if let Ok(response) = serde_json::from_str::<RpcResponse>(line) {
+        if let Some(id) = response.id {
+            // A session echoed back by the sidecar must match the process we read it from
+            if response.session.as_deref().is_some_and(|s| s != generation) {
+                eprintln!("[sidecar] dropping response {} with a foreign session", id);
+                return;
+            }
+            if let Some(tx) = take_pending(app, id, generation) {
+                let result = if let Some(err) = response.error {
+                    Err(err)
+                } else {
+                    Ok(response.result.unwrap_or_default())
+                };
+                let _ = tx.send(result);
+            }
+        }
+        // Ignore messages without id (like {ready: true})
+    }
+}
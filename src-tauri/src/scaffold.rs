@@ -0,0 +1,338 @@
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::app_data::app_data_path;
+use crate::template;
+use crate::workspace::{is_binary_file, relative_path};
+
+// User templates are folders in here, named by their id
+const TEMPLATES_DIR: &str = "scaffold_templates";
+// Optional name and description of a user template; not copied
+const MANIFEST_FILE: &str = "template.json";
+const MAX_TEMPLATE_FILES: usize = 1_000;
+
+struct Builtin {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    files: &'static [(&'static str, &'static str)],
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        id: "rust-cli",
+        name: "Rust CLI",
+        description: "A Cargo binary crate with a main.rs",
+        files: &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\
+                 description = \"{{description|}}\"\n\n[dependencies]\n",
+            ),
+            (
+                "src/main.rs",
+                "fn main() {\n    println!(\"Hello from {{name}}!\");\n}\n",
+            ),
+            (".gitignore", "/target\n"),
+            ("README.md", "# {{name}}\n\n{{description|}}\n"),
+        ],
+    },
+    Builtin {
+        id: "node-ts",
+        name: "Node.js + TypeScript",
+        description: "A TypeScript package compiled with tsc",
+        files: &[
+            (
+                "package.json",
+                "{\n  \"name\": \"{{name}}\",\n  \"version\": \"0.1.0\",\n  \
+                 \"description\": \"{{description|}}\",\n  \"type\": \"module\",\n  \
+                 \"main\": \"dist/index.js\",\n  \"scripts\": {\n    \"build\": \"tsc\",\n    \
+                 \"start\": \"node dist/index.js\"\n  },\n  \"devDependencies\": {\n    \
+                 \"typescript\": \"^5.0.0\"\n  }\n}\n",
+            ),
+            (
+                "tsconfig.json",
+                "{\n  \"compilerOptions\": {\n    \"target\": \"ES2022\",\n    \
+                 \"module\": \"NodeNext\",\n    \"moduleResolution\": \"NodeNext\",\n    \
+                 \"outDir\": \"dist\",\n    \"strict\": true\n  },\n  \"include\": [\"src\"]\n}\n",
+            ),
+            ("src/index.ts", "console.log(\"Hello from {{name}}!\");\n"),
+            (".gitignore", "node_modules\ndist\n"),
+            ("README.md", "# {{name}}\n\n{{description|}}\n"),
+        ],
+    },
+    Builtin {
+        id: "python-package",
+        name: "Python package",
+        description: "A pyproject-based package with a src layout",
+        files: &[
+            (
+                "pyproject.toml",
+                "[project]\nname = \"{{name}}\"\nversion = \"0.1.0\"\n\
+                 description = \"{{description|}}\"\nrequires-python = \">=3.9\"\n\n\
+                 [build-system]\nrequires = [\"setuptools>=61\"]\n\
+                 build-backend = \"setuptools.build_meta\"\n",
+            ),
+            (
+                "src/{{module|package}}/__init__.py",
+                "\"\"\"{{name}}.\"\"\"\n",
+            ),
+            (".gitignore", "__pycache__/\n*.egg-info/\n.venv/\n"),
+            ("README.md", "# {{name}}\n\n{{description|}}\n"),
+        ],
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaffoldTemplate {
+    id: String,
+    name: String,
+    description: Option<String>,
+    builtin: bool,
+    /// Placeholders in the files or their paths that have no default
+    variables: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaffoldReport {
+    root: String,
+    /// Relative to `root`
+    files: Vec<String>,
+    /// Placeholders that had no value and were left empty
+    missing: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// What a template file holds: text to render, or bytes copied as they are.
+enum Source {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+fn user_templates_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_data_path(app, TEMPLATES_DIR)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create templates folder: {}", e))?;
+    Ok(dir)
+}
+
+fn user_template_files(dir: &Path) -> Result<Vec<(String, Source)>, String> {
+    let mut files = Vec::new();
+    // Dotfiles and .gitignore files are part of the template, not rules for reading it
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.into_path();
+        let relative = relative_path(dir, &path);
+        if relative == MANIFEST_FILE {
+            continue;
+        }
+        if files.len() >= MAX_TEMPLATE_FILES {
+            return Err(format!(
+                "Templates can have at most {} files",
+                MAX_TEMPLATE_FILES
+            ));
+        }
+        let bytes =
+            std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        // Binary files such as icons are copied as they are
+        let source = if is_binary_file(&path) {
+            Source::Bytes(bytes)
+        } else {
+            match String::from_utf8(bytes) {
+                Ok(text) => Source::Text(text),
+                Err(err) => Source::Bytes(err.into_bytes()),
+            }
+        };
+        files.push((relative, source));
+    }
+    Ok(files)
+}
+
+fn template_files(
+    app: &tauri::AppHandle,
+    template_id: &str,
+) -> Result<Vec<(String, Source)>, String> {
+    let is_folder_name = matches!(
+        Path::new(template_id).components().collect::<Vec<_>>()[..],
+        [Component::Normal(_)]
+    );
+    if is_folder_name {
+        let dir = user_templates_dir(app)?.join(template_id);
+        if dir.is_dir() {
+            return user_template_files(&dir);
+        }
+    }
+    BUILTINS
+        .iter()
+        .find(|b| b.id == template_id)
+        .map(|b| {
+            b.files
+                .iter()
+                .map(|(path, content)| (path.to_string(), Source::Text(content.to_string())))
+                .collect()
+        })
+        .ok_or_else(|| format!("Unknown template: {}", template_id))
+}
+
+fn variables(files: &[(String, Source)]) -> Vec<String> {
+    let empty = HashMap::new();
+    let mut names: Vec<String> = Vec::new();
+    for (path, source) in files {
+        let mut missing = template::render(path, &empty).missing;
+        if let Source::Text(text) = source {
+            missing.extend(template::render(text, &empty).missing);
+        }
+        for name in missing {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// A rendered relative path, refused if it would leave the target folder.
+fn target_path(root: &Path, rendered: &str) -> Result<PathBuf, String> {
+    let mut path = root.to_path_buf();
+    for component in Path::new(rendered).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("Template path leaves the project: {}", rendered)),
+        }
+    }
+    if path == root {
+        return Err(format!("Template path is empty: {}", rendered));
+    }
+    Ok(path)
+}
+
+/// The bundled templates, then those in the app's `scaffold_templates` folder. A user
+/// template with a bundled one's id replaces it.
+#[tauri::command]
+pub fn list_scaffold_templates(app: tauri::AppHandle) -> Result<Vec<ScaffoldTemplate>, String> {
+    let mut templates = Vec::new();
+    let dir = user_templates_dir(&app)?;
+    let mut user_ids = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read templates folder: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    user_ids.sort();
+
+    for builtin in BUILTINS
+        .iter()
+        .filter(|b| !user_ids.iter().any(|id| id == b.id))
+    {
+        templates.push(ScaffoldTemplate {
+            id: builtin.id.to_string(),
+            name: builtin.name.to_string(),
+            description: Some(builtin.description.to_string()),
+            builtin: true,
+            variables: variables(&template_files(&app, builtin.id)?),
+        });
+    }
+    for id in user_ids {
+        let folder = dir.join(&id);
+        let manifest = match std::fs::read_to_string(folder.join(MANIFEST_FILE)) {
+            Ok(content) => serde_json::from_str::<Manifest>(&content)
+                .map_err(|e| format!("Failed to parse {}/{}: {}", id, MANIFEST_FILE, e))?,
+            Err(_) => Manifest::default(),
+        };
+        templates.push(ScaffoldTemplate {
+            name: manifest.name.unwrap_or_else(|| id.clone()),
+            description: manifest.description,
+            builtin: false,
+            variables: variables(&user_template_files(&folder)?),
+            id,
+        });
+    }
+    Ok(templates)
+}
+
+/// Creates a project at `path` (absolute) from a template, filling `{{name}}`-style
+/// placeholders in file contents and paths from `vars`. `name` defaults to the new
+/// folder's name. The folder must not exist yet or be empty.
+#[tauri::command]
+pub fn scaffold_from_template(
+    app: tauri::AppHandle,
+    template_id: String,
+    path: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<ScaffoldReport, String> {
+    let root = PathBuf::from(path.trim());
+    if !root.is_absolute() {
+        return Err(format!("Expected an absolute path: {}", path));
+    }
+    if root.is_file() {
+        return Err(format!("A file already exists at {}", path));
+    }
+    let not_empty = std::fs::read_dir(&root).is_ok_and(|mut entries| entries.next().is_some());
+    if not_empty {
+        return Err(format!("Folder is not empty: {}", path));
+    }
+
+    let files = template_files(&app, &template_id)?;
+    let mut vars = vars.unwrap_or_default();
+    if let Some(name) = root.file_name() {
+        vars.entry("name".to_string())
+            .or_insert_with(|| name.to_string_lossy().into_owned());
+    }
+
+    // Everything is rendered and checked before the first file is written
+    let mut missing: Vec<String> = Vec::new();
+    let mut outputs = Vec::with_capacity(files.len());
+    for (relative, source) in files {
+        let rendered_path = template::render(&relative, &vars);
+        let target = target_path(&root, &rendered_path.text)?;
+        let mut names = rendered_path.missing;
+        let bytes = match source {
+            Source::Text(text) => {
+                let rendered = template::render(&text, &vars);
+                names.extend(rendered.missing);
+                rendered.text.into_bytes()
+            }
+            Source::Bytes(bytes) => bytes,
+        };
+        for name in names {
+            if !missing.contains(&name) {
+                missing.push(name);
+            }
+        }
+        outputs.push((target, bytes));
+    }
+
+    let mut written = Vec::with_capacity(outputs.len());
+    for (target, bytes) in outputs {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&target, bytes)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        written.push(relative_path(&root, &target));
+    }
+    written.sort();
+    Ok(ScaffoldReport {
+        root: root.to_string_lossy().into_owned(),
+        files: written,
+        missing,
+    })
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::clock::now_millis;
+use crate::providers::ProviderOptions;
+use crate::{ChatMessage, SendMessageOutcome};
+
+// Sleeping in short steps against the wall clock keeps the send time right across
+// system sleep, which pauses monotonic timers
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How far in the past `send_at` may be before it's treated as a mistake.
+const PAST_TOLERANCE_MS: i64 = 60_000;
+/// Label reported as the "window" holding the workspace lock for scheduled runs.
+const SCHEDULER_WINDOW: &str = "scheduler";
+
+/// `send_message` parameters for a scheduled request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRequest {
+    #[serde(default)]
+    provider: Option<String>,
+    /// Falls back to the keychain entry for the provider when the request runs
+    #[serde(default)]
+    api_key: Option<String>,
+    model: String,
+    #[serde(default)]
+    base_url: Option<String>,
+    messages: Vec<ChatMessage>,
+    #[serde(default)]
+    workspace_path: Option<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(default)]
+    max_retries: Option<u32>,
+    #[serde(default)]
+    template_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleStatus {
+    Pending,
+    Running,
+    Complete,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledMessage {
+    id: String,
+    conversation_id: String,
+    request_id: Option<String>,
+    model: String,
+    send_at: i64,
+    created_at: i64,
+    status: ScheduleStatus,
+    result: Option<SendMessageOutcome>,
+    error: Option<String>,
+    /// Carries the API key, so it never goes back to the webview
+    #[serde(skip)]
+    request: ScheduledRequest,
+}
+
+/// Scheduled requests for this session. They are kept in memory only so API keys are
+/// never written to disk; anything still pending when the app quits is dropped.
+#[derive(Default)]
+pub(crate) struct Scheduler(Mutex<HashMap<String, ScheduledMessage>>);
+
+fn update(app: &tauri::AppHandle, id: &str, apply: impl FnOnce(&mut ScheduledMessage)) {
+    if let Some(scheduled) = app.state::<Scheduler>().0.lock().unwrap().get_mut(id) {
+        apply(scheduled);
+    }
+}
+
+/// Claims a due request for running; `None` if it was cancelled in the meantime.
+fn start(app: &tauri::AppHandle, id: &str) -> Option<ScheduledMessage> {
+    let scheduler = app.state::<Scheduler>();
+    let mut scheduled = scheduler.0.lock().unwrap();
+    let entry = scheduled.get_mut(id)?;
+    if entry.status != ScheduleStatus::Pending {
+        return None;
+    }
+    entry.status = ScheduleStatus::Running;
+    Some(entry.clone())
+}
+
+async fn run(app: tauri::AppHandle, id: String, send_at: i64) {
+    loop {
+        let remaining = send_at - now_millis();
+        if remaining <= 0 {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL.min(Duration::from_millis(remaining as u64))).await;
+    }
+    let Some(scheduled) = start(&app, &id) else {
+        return;
+    };
+
+    let request = scheduled.request;
+    let (provider, model, base_url) = crate::effective_model_settings(
+        &app,
+        Some(&scheduled.conversation_id),
+        request.provider,
+        request.model,
+        request.base_url,
+    );
+    let outcome =
+        match crate::secrets::resolve_api_key(&app, provider.as_deref(), None, request.api_key) {
+            Ok(api_key) => {
+                crate::send_resolved(
+                    app.clone(),
+                    SCHEDULER_WINDOW.to_string(),
+                    provider,
+                    api_key,
+                    model,
+                    base_url,
+                    ProviderOptions::default(),
+                    request.messages,
+                    request.workspace_path,
+                    Vec::new(),
+                    request.request_id,
+                    request.max_retries,
+                    Some(scheduled.conversation_id),
+                    request.template_id,
+                    None,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+
+    update(&app, &id, |entry| match outcome {
+        Ok(result) => {
+            entry.status = ScheduleStatus::Complete;
+            entry.result = Some(result);
+        }
+        Err(err) => {
+            entry.status = ScheduleStatus::Failed;
+            entry.error = Some(err);
+        }
+    });
+    let finished = app.state::<Scheduler>().0.lock().unwrap().get(&id).cloned();
+    if let Some(finished) = finished {
+        let _ = app.emit("agent:scheduled_complete", finished);
+    }
+}
+
+/// Sends `params` into the conversation at `send_at` (Unix millis) from the backend, so
+/// it runs even while the webview is idle. The reply is stored with the conversation
+/// and announced with `agent:scheduled_complete`.
+#[tauri::command]
+pub fn schedule_message(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    params: ScheduledRequest,
+    send_at: i64,
+) -> Result<ScheduledMessage, String> {
+    if conversation_id.trim().is_empty() {
+        return Err("Conversation id is required".to_string());
+    }
+    if !params.messages.iter().any(|m| m.role == "user") {
+        return Err("A scheduled message needs a user prompt".to_string());
+    }
+    let now = now_millis();
+    if send_at < now - PAST_TOLERANCE_MS {
+        return Err("The send time is in the past".to_string());
+    }
+
+    let scheduled = ScheduledMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        conversation_id,
+        request_id: params.request_id.clone(),
+        model: params.model.clone(),
+        send_at,
+        created_at: now,
+        status: ScheduleStatus::Pending,
+        result: None,
+        error: None,
+        request: params,
+    };
+    app.state::<Scheduler>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(scheduled.id.clone(), scheduled.clone());
+    tauri::async_runtime::spawn(run(app.clone(), scheduled.id.clone(), send_at));
+    Ok(scheduled)
+}
+
+#[tauri::command]
+pub fn list_scheduled_messages(app: tauri::AppHandle) -> Vec<ScheduledMessage> {
+    let mut scheduled: Vec<ScheduledMessage> = app
+        .state::<Scheduler>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect();
+    scheduled.sort_by_key(|s| (s.send_at, s.created_at));
+    scheduled
+}
+
+/// Cancels a request that hasn't started yet. Returns `false` if it already ran.
+#[tauri::command]
+pub fn cancel_scheduled_message(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let scheduler = app.state::<Scheduler>();
+    let mut scheduled = scheduler.0.lock().unwrap();
+    let entry = scheduled
+        .get_mut(&id)
+        .ok_or_else(|| format!("Scheduled message not found: {}", id))?;
+    if entry.status != ScheduleStatus::Pending {
+        return Ok(false);
+    }
+    entry.status = ScheduleStatus::Cancelled;
+    Ok(true)
+}
@@ -0,0 +1,578 @@
+use chrono::{Datelike, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::agent_tasks::{self, TaskModel};
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::tasks::{TaskKind, TaskRun, TaskStatus};
+use crate::timezone::{local_at, local_to_utc_millis};
+use crate::workspace::workspace_root;
+
+const SCHEDULES_FILE: &str = "schedules.json";
+const MAX_SCHEDULES: usize = 100;
+// Cron times are whole minutes, so checking twice a minute starts runs close to on time
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// How far ahead the next run is looked for; covers leap-day schedules
+const SEARCH_DAYS: u64 = 8 * 366;
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A five-field cron expression (minute, hour, day of month, month, weekday) in the
+/// user's time zone. Each field is a bit set of the values it allows.
+#[derive(Debug, Clone, Copy)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// When neither day field starts with `*`, a day matching either one runs, as in cron
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let lower = value.to_lowercase();
+    if let Some(index) = names.iter().position(|name| *name == lower) {
+        return Ok(min + index as u32);
+    }
+    let number: u32 = value
+        .parse()
+        .map_err(|_| format!("Invalid cron value: {}", value))?;
+    if number < min || number > max {
+        return Err(format!("Cron value {} is outside {}-{}", number, min, max));
+    }
+    Ok(number)
+}
+
+/// Parses one field into a bit set: `*`, values, `a-b` ranges and `/n` steps, separated
+/// by commas.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid cron step: {}", part))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/15` means every 15 starting at 5
+            (value, if step.is_some() { max } else { value })
+        };
+        if start > end {
+            return Err(format!("Invalid cron range: {}", range));
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Cron, String> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "Expected five cron fields (minute hour day month weekday): {}",
+                expression
+            ));
+        };
+        let mut weekday_bits = parse_field(weekdays, 0, 7, WEEKDAY_NAMES)?;
+        // Both 0 and 7 are Sunday
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            minutes: parse_field(minutes, 0, 59, &[])?,
+            hours: parse_field(hours, 0, 23, &[])?,
+            days: parse_field(days, 1, 31, &[])?,
+            months: parse_field(months, 1, 12, MONTH_NAMES)?,
+            weekdays: weekday_bits,
+            days_restricted: !days.starts_with('*'),
+            weekdays_restricted: !weekdays.starts_with('*'),
+        })
+    }
+
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// The first run strictly after `after` (Unix millis), or `None` if the expression can
+    /// never match, such as February 30th.
+    fn next_after(&self, after: i64) -> Option<i64> {
+        let start = local_at(after).naive_local();
+        for offset in 0..SEARCH_DAYS {
+            let date = start.date().checked_add_days(chrono::Days::new(offset))?;
+            if !self.runs_on(date) {
+                continue;
+            }
+            let first_hour = if offset == 0 { start.hour() } else { 0 };
+            for hour in (first_hour..24).filter(|h| self.hours & (1 << h) != 0) {
+                for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                    let at = local_to_utc_millis(date.and_hms_opt(hour, minute, 0)?);
+                    if at > after {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleRun {
+    /// `None` when the task could not be queued
+    task_id: Option<String>,
+    started_at: i64,
+    status: TaskStatus,
+    finished_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    id: String,
+    name: String,
+    /// The prompt each run gives the agent
+    description: String,
+    workspace: String,
+    cron: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<TaskModel>,
+    paused: bool,
+    created_at: i64,
+    /// `None` while paused
+    next_run_at: Option<i64>,
+    #[serde(default)]
+    last_run: Option<ScheduleRun>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleFile {
+    schedules: Vec<Schedule>,
+}
+
+/// Serializes read-modify-write cycles on the schedules file.
+#[derive(Default)]
+pub(crate) struct ScheduleLock(Mutex<()>);
+
+fn update_file<T>(
+    app: &tauri::AppHandle,
+    apply: impl FnOnce(&mut ScheduleFile) -> Result<T, String>,
+) -> Result<T, String> {
+    let lock = app.state::<ScheduleLock>();
+    let _guard = lock.0.lock().unwrap();
+    let mut file: ScheduleFile = load_json(app, SCHEDULES_FILE)?;
+    let result = apply(&mut file)?;
+    save_json(app, SCHEDULES_FILE, &file)?;
+    Ok(result)
+}
+
+/// Queues a task for every schedule that is due. Runs missed while the app was closed
+/// collapse into one, and a run is skipped while the previous one is still unfinished.
+fn run_due(app: &tauri::AppHandle) -> Result<(), String> {
+    let now = now_millis();
+    update_file(app, |file| {
+        for schedule in &mut file.schedules {
+            if schedule.paused || schedule.next_run_at.is_none_or(|at| at > now) {
+                continue;
+            }
+            let cron = Cron::parse(&schedule.cron)?;
+            schedule.next_run_at = cron.next_after(now);
+            let unfinished = schedule
+                .last_run
+                .as_ref()
+                .is_some_and(|run| matches!(run.status, TaskStatus::Queued | TaskStatus::Running));
+            if unfinished {
+                continue;
+            }
+            let submitted = agent_tasks::submit_task(
+                app.clone(),
+                schedule.description.clone(),
+                schedule.workspace.clone(),
+                schedule.model.clone(),
+            );
+            schedule.last_run = Some(match submitted {
+                Ok(run) => ScheduleRun {
+                    task_id: Some(run.id),
+                    started_at: now,
+                    status: run.status,
+                    finished_at: None,
+                    error: None,
+                },
+                Err(err) => ScheduleRun {
+                    task_id: None,
+                    started_at: now,
+                    status: TaskStatus::Failed,
+                    finished_at: Some(now),
+                    error: Some(err),
+                },
+            });
+        }
+        Ok(())
+    })
+}
+
+/// Fails last runs still queued or running from before a restart. Agent tasks live in
+/// memory only, so nothing else would ever finish them and their schedules would never
+/// run again.
+fn fail_interrupted(schedules: &mut [Schedule], now: i64) {
+    for run in schedules.iter_mut().filter_map(|s| s.last_run.as_mut()) {
+        if matches!(run.status, TaskStatus::Queued | TaskStatus::Running) {
+            run.status = TaskStatus::Failed;
+            run.finished_at = Some(now);
+            run.error = Some("The app quit before the run finished".to_string());
+        }
+    }
+}
+
+/// Checks for due schedules for as long as the app runs.
+pub(crate) fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let interrupted = update_file(&app, |file| {
+            fail_interrupted(&mut file.schedules, now_millis());
+            Ok(())
+        });
+        if let Err(err) = interrupted {
+            eprintln!("[schedules] {}", err);
+        }
+        loop {
+            if let Err(err) = run_due(&app) {
+                eprintln!("[schedules] {}", err);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Records how a scheduled agent task ended as its schedule's last run.
+pub(crate) fn record_result(app: &tauri::AppHandle, run: &TaskRun) {
+    if run.kind != TaskKind::Agent {
+        return;
+    }
+    let result = update_file(app, |file| {
+        let last_run = file
+            .schedules
+            .iter_mut()
+            .filter_map(|schedule| schedule.last_run.as_mut())
+            .find(|last| last.task_id.as_deref() == Some(run.id.as_str()));
+        if let Some(last) = last_run {
+            last.status = run.status;
+            last.finished_at = run.finished_at;
+            last.error = run.error.clone();
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        eprintln!("[schedules] {}", err);
+    }
+}
+
+/// Runs `description` as an agent task in `workspace` whenever `cron` matches, e.g.
+/// `0 9 * * 1-5` for weekdays at 9am in the user's time zone.
+#[tauri::command]
+pub fn create_schedule(
+    app: tauri::AppHandle,
+    name: String,
+    description: String,
+    workspace: String,
+    cron: String,
+    model: Option<TaskModel>,
+) -> Result<Schedule, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Schedule name is empty".to_string());
+    }
+    let description = description.trim().to_string();
+    if description.is_empty() {
+        return Err("Task description is empty".to_string());
+    }
+    let root = workspace_root(&workspace)?;
+    let cron = cron.trim().to_string();
+    let now = now_millis();
+    let next_run_at = Cron::parse(&cron)?
+        .next_after(now)
+        .ok_or_else(|| format!("'{}' never runs", cron))?;
+    let schedule = Schedule {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description,
+        workspace: root.to_string_lossy().into_owned(),
+        cron,
+        model,
+        paused: false,
+        created_at: now,
+        next_run_at: Some(next_run_at),
+        last_run: None,
+    };
+    update_file(&app, |file| {
+        if file.schedules.len() >= MAX_SCHEDULES {
+            return Err(format!("At most {} schedules can be saved", MAX_SCHEDULES));
+        }
+        file.schedules.push(schedule.clone());
+        Ok(())
+    })?;
+    Ok(schedule)
+}
+
+/// Active schedules by their next run, then paused ones. A last run that is still on
+/// the task queue shows whether it has started.
+#[tauri::command]
+pub fn list_schedules(app: tauri::AppHandle) -> Result<Vec<Schedule>, String> {
+    let mut schedules = load_json::<ScheduleFile>(&app, SCHEDULES_FILE)?.schedules;
+    let active: HashMap<String, TaskStatus> = agent_tasks::active(&app)
+        .into_iter()
+        .map(|run| (run.id, run.status))
+        .collect();
+    for last in schedules.iter_mut().filter_map(|s| s.last_run.as_mut()) {
+        if let Some(status) = last.task_id.as_ref().and_then(|id| active.get(id)) {
+            last.status = *status;
+        }
+    }
+    schedules.sort_by_key(|s| (s.paused, s.next_run_at, s.created_at));
+    Ok(schedules)
+}
+
+/// Pauses a schedule, or resumes it with `paused: false` from its next matching time.
+#[tauri::command]
+pub fn pause_schedule(
+    app: tauri::AppHandle,
+    id: String,
+    paused: Option<bool>,
+) -> Result<Schedule, String> {
+    let paused = paused.unwrap_or(true);
+    update_file(&app, |file| {
+        let schedule = file
+            .schedules
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("Schedule not found: {}", id))?;
+        schedule.paused = paused;
+        schedule.next_run_at = if paused {
+            None
+        } else {
+            Cron::parse(&schedule.cron)?.next_after(now_millis())
+        };
+        Ok(schedule.clone())
+    })
+}
+
+/// Deletes a schedule; a run it already queued keeps going. Returns `false` if there
+/// was none by `id`.
+#[tauri::command]
+pub fn delete_schedule(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    update_file(&app, |file| {
+        let before = file.schedules.len();
+        file.schedules.retain(|s| s.id != id);
+        Ok(file.schedules.len() != before)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        local_to_utc_millis(date.and_hms_opt(hour, minute, 0).unwrap())
+    }
+
+    fn next(expression: &str, after: i64) -> Option<NaiveDateTime> {
+        let cron = Cron::parse(expression).unwrap();
+        cron.next_after(after)
+            .map(|millis| local_at(millis).naive_local())
+    }
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn bits(values: &[u32]) -> u64 {
+        values.iter().fold(0, |bits, value| bits | 1 << value)
+    }
+
+    #[test]
+    fn fails_runs_left_unfinished_by_a_restart() {
+        let schedule = |id: &str, status: &str| -> Schedule {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": id,
+                "description": "Tidy up",
+                "workspace": "/tmp",
+                "cron": "@daily",
+                "paused": false,
+                "createdAt": 0,
+                "nextRunAt": 0,
+                "lastRun": {
+                    "taskId": "task",
+                    "startedAt": 0,
+                    "status": status,
+                    "finishedAt": null,
+                },
+            }))
+            .unwrap()
+        };
+        let mut schedules = vec![
+            schedule("queued", "queued"),
+            schedule("running", "running"),
+            schedule("done", "succeeded"),
+        ];
+        fail_interrupted(&mut schedules, 42);
+        let runs: Vec<&ScheduleRun> = schedules
+            .iter()
+            .map(|s| s.last_run.as_ref().unwrap())
+            .collect();
+        assert_eq!(runs[0].status, TaskStatus::Failed);
+        assert_eq!(runs[1].status, TaskStatus::Failed);
+        assert_eq!(runs[1].finished_at, Some(42));
+        assert!(runs[1].error.is_some());
+        assert_eq!(runs[2].status, TaskStatus::Succeeded);
+        assert_eq!(runs[2].finished_at, None);
+    }
+
+    #[test]
+    fn parses_ranges_and_steps() {
+        let cron = Cron::parse("*/15 9-17/4 1,15-16 * *").unwrap();
+        assert_eq!(cron.minutes, bits(&[0, 15, 30, 45]));
+        assert_eq!(cron.hours, bits(&[9, 13, 17]));
+        assert_eq!(cron.days, bits(&[1, 15, 16]));
+        assert_eq!(
+            Cron::parse("5/20 * * * *").unwrap().minutes,
+            bits(&[5, 25, 45])
+        );
+    }
+
+    #[test]
+    fn parses_month_and_weekday_names() {
+        let cron = Cron::parse("0 12 * JAN-mar Mon,fri").unwrap();
+        assert_eq!(cron.months, bits(&[1, 2, 3]));
+        assert_eq!(cron.weekdays, bits(&[1, 5]));
+        // Both 0 and 7 are Sunday
+        assert_eq!(Cron::parse("0 0 * * 7").unwrap().weekdays, bits(&[0]));
+        assert_eq!(
+            Cron::parse("0 0 * * 5-7").unwrap().weekdays,
+            bits(&[0, 5, 6])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 * * funday",
+            "0 0 0 * *",
+        ] {
+            assert!(Cron::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn next_run_is_strictly_later() {
+        let noon = at(2026, 1, 5, 12, 0);
+        assert_eq!(next("0 12 * * *", noon), Some(time(2026, 1, 6, 12, 0)));
+        assert_eq!(
+            next("0 12 * * *", at(2026, 1, 5, 9, 30)),
+            Some(time(2026, 1, 5, 12, 0))
+        );
+        assert_eq!(
+            next("*/15 * * * *", at(2026, 1, 5, 9, 50)),
+            Some(time(2026, 1, 5, 10, 0))
+        );
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_restricted() {
+        // 2026-01-01 is a Thursday
+        let expression = "0 12 13 * fri";
+        assert_eq!(
+            next(expression, at(2026, 1, 1, 0, 0)),
+            Some(time(2026, 1, 2, 12, 0))
+        );
+        assert_eq!(
+            next(expression, at(2026, 1, 10, 0, 0)),
+            Some(time(2026, 1, 13, 12, 0))
+        );
+        // With the weekday left as `*`, only the day of the month counts
+        assert_eq!(
+            next("0 12 13 * *", at(2026, 1, 1, 0, 0)),
+            Some(time(2026, 1, 13, 12, 0))
+        );
+        assert_eq!(
+            next("0 12 * * fri", at(2026, 1, 3, 0, 0)),
+            Some(time(2026, 1, 9, 12, 0))
+        );
+    }
+
+    #[test]
+    fn finds_the_next_leap_day() {
+        assert_eq!(
+            next("0 12 29 feb *", at(2026, 3, 1, 0, 0)),
+            Some(time(2028, 2, 29, 12, 0))
+        );
+        // 2100 is not a leap year, so the gap is almost eight years
+        assert_eq!(
+            next("0 12 29 2 *", at(2096, 3, 1, 0, 0)),
+            Some(time(2104, 2, 29, 12, 0))
+        );
+    }
+
+    #[test]
+    fn gives_up_after_the_search_window() {
+        // Eight years of days without a match, the longest a leap-day schedule can wait
+        assert!(
+            SEARCH_DAYS >= (time(2104, 2, 29, 0, 0) - time(2096, 2, 29, 0, 0)).num_days() as u64
+        );
+        assert_eq!(next("0 0 30 2 *", at(2026, 1, 1, 0, 0)), None);
+        assert_eq!(next("0 0 31 apr,jun,sep,nov *", at(2026, 1, 1, 0, 0)), None);
+    }
+}
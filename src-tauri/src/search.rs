@@ -0,0 +1,282 @@
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::workspace::{is_always_skipped, is_binary_file, relative_path, workspace_roots};
+
+const DEFAULT_CONTEXT_LINES: usize = 2;
+const MAX_CONTEXT_LINES: usize = 10;
+const DEFAULT_MAX_RESULTS: usize = 500;
+const MAX_RESULTS_LIMIT: usize = 5_000;
+const MAX_QUERY_CHARS: usize = 1_000;
+const MAX_GLOBS: usize = 50;
+// Bigger files are almost always generated or data, not code worth searching
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+// Minified lines would flood the results; they are clipped around the match instead
+const MAX_LINE_CHARS: usize = 500;
+const CONTEXT_WINDOW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression rather than literal text
+    #[serde(default)]
+    regex: bool,
+    /// Smart case when absent: case-sensitive only if the query has an uppercase letter
+    #[serde(default)]
+    case_sensitive: Option<bool>,
+    #[serde(default)]
+    whole_word: bool,
+    /// Only search files matching one of these globs, e.g. `*.rs` or `src/**`
+    #[serde(default)]
+    include: Vec<String>,
+    /// Skip files matching any of these globs
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    context_lines: Option<usize>,
+    #[serde(default)]
+    max_results: Option<usize>,
+    #[serde(default)]
+    include_hidden: bool,
+    /// Further folders of a multi-root workspace, searched after the main one
+    #[serde(default)]
+    roots: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// The workspace folder the file is in
+    root: String,
+    /// Relative to `root`, with `/` separators
+    path: String,
+    /// 1-based
+    line_number: usize,
+    line: String,
+    /// Character ranges of the matches within `line`, end exclusive
+    ranges: Vec<[usize; 2]>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    matches: Vec<SearchMatch>,
+    files_searched: usize,
+    files_matched: usize,
+    /// Whether the search stopped at `maxResults`
+    truncated: bool,
+}
+
+fn build_regex(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    if query.chars().count() > MAX_QUERY_CHARS {
+        return Err(format!(
+            "Search query must be at most {} characters",
+            MAX_QUERY_CHARS
+        ));
+    }
+    let mut pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    if options.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    let case_sensitive = options
+        .case_sensitive
+        .unwrap_or_else(|| query.chars().any(char::is_uppercase));
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Cuts long lines down to a window around the first match, shifting `ranges` to match.
+fn clip(line: &str, ranges: &mut [[usize; 2]]) -> String {
+    let chars = line.chars().count();
+    if chars <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let first = ranges.first().map_or(0, |r| r[0]);
+    let start = first.saturating_sub(CONTEXT_WINDOW_CHARS);
+    let end = (start + MAX_LINE_CHARS).min(chars);
+    for range in ranges.iter_mut() {
+        range[0] = range[0].clamp(start, end) - start;
+        range[1] = range[1].clamp(start, end) - start;
+    }
+    line.chars().skip(start).take(end - start).collect()
+}
+
+fn context_line(line: &str) -> String {
+    line.chars().take(MAX_LINE_CHARS).collect()
+}
+
+/// Matches in one file, stopping once `remaining` is used up.
+fn search_file(
+    root: &Path,
+    path: &Path,
+    regex: &Regex,
+    context: usize,
+    remaining: usize,
+) -> Vec<SearchMatch> {
+    let Ok(bytes) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if matches.len() >= remaining {
+            break;
+        }
+        let mut ranges: Vec<[usize; 2]> = regex
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                let start = line[..m.start()].chars().count();
+                [start, start + m.as_str().chars().count()]
+            })
+            .collect();
+        if ranges.is_empty() {
+            continue;
+        }
+        let line = clip(line, &mut ranges);
+        matches.push(SearchMatch {
+            root: root.to_string_lossy().into_owned(),
+            path: relative_path(root, path),
+            line_number: index + 1,
+            line,
+            ranges,
+            before: lines[index.saturating_sub(context)..index]
+                .iter()
+                .map(|l| context_line(l))
+                .collect(),
+            after: lines[index + 1..(index + 1 + context).min(lines.len())]
+                .iter()
+                .map(|l| context_line(l))
+                .collect(),
+        });
+    }
+    matches
+}
+
+fn walker(root: &Path, options: &SearchOptions) -> Result<ignore::Walk, String> {
+    let mut globs = OverrideBuilder::new(root);
+    for glob in options
+        .include
+        .iter()
+        .map(|g| g.trim())
+        .filter(|g| !g.is_empty())
+    {
+        globs
+            .add(glob)
+            .map_err(|e| format!("Invalid glob {}: {}", glob, e))?;
+    }
+    for glob in options
+        .exclude
+        .iter()
+        .map(|g| g.trim())
+        .filter(|g| !g.is_empty())
+    {
+        globs
+            .add(&format!("!{}", glob))
+            .map_err(|e| format!("Invalid glob {}: {}", glob, e))?;
+    }
+    let globs = globs.build().map_err(|e| format!("Invalid globs: {}", e))?;
+
+    Ok(WalkBuilder::new(root)
+        .hidden(!options.include_hidden)
+        .git_ignore(true)
+        .require_git(false)
+        .overrides(globs)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| !is_always_skipped(entry.file_name()))
+        .build())
+}
+
+pub(crate) fn search(
+    path: &str,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<SearchResults, String> {
+    let roots = workspace_roots(path, &options.roots)?;
+    let regex = build_regex(query, options)?;
+    let context = options
+        .context_lines
+        .unwrap_or(DEFAULT_CONTEXT_LINES)
+        .min(MAX_CONTEXT_LINES);
+    let max_results = options
+        .max_results
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS_LIMIT);
+
+    if options.include.len() + options.exclude.len() > MAX_GLOBS {
+        return Err(format!("At most {} globs are allowed", MAX_GLOBS));
+    }
+    // Globs are relative to each root, so every root gets its own walker
+    let walkers = roots
+        .iter()
+        .map(|root| walker(root, options).map(|walk| (root, walk)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut matches = Vec::new();
+    let mut files_searched = 0;
+    let mut files_matched = 0;
+    let mut truncated = false;
+    'roots: for (root, walk) in walkers {
+        for entry in walk.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if size > MAX_FILE_BYTES || is_binary_file(entry.path()) {
+                continue;
+            }
+            if matches.len() >= max_results {
+                truncated = true;
+                break 'roots;
+            }
+            files_searched += 1;
+            let found = search_file(
+                root,
+                entry.path(),
+                &regex,
+                context,
+                max_results - matches.len(),
+            );
+            if !found.is_empty() {
+                files_matched += 1;
+                matches.extend(found);
+            }
+        }
+    }
+
+    Ok(SearchResults {
+        matches,
+        files_searched,
+        files_matched,
+        truncated,
+    })
+}
+
+/// Searches the text files under `path` like ripgrep: .gitignore is honoured, build
+/// output and binary files are skipped, and each match comes with surrounding lines.
+#[tauri::command]
+pub async fn search_workspace(
+    path: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<SearchResults, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || search(&path, &query, &options))
+        .await
+        .map_err(|e| format!("Search task failed: {}", e))?
+}
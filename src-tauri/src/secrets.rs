@@ -0,0 +1,413 @@
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::providers::{ProviderKind, DEFAULT_PROVIDER, LOCAL_API_KEY};
+
+// One entry per provider id under the app identifier
+const KEYCHAIN_SERVICE: &str = "com.ohmyco.work";
+const MAX_PROVIDER_CHARS: usize = 64;
+const KEY_POOLS_FILE: &str = "key_pools.json";
+const MAX_POOL_KEYS: usize = 10;
+const MAX_LABEL_CHARS: usize = 80;
+/// Id of the key saved under the entry itself with `set_api_key`.
+const PRIMARY_KEY_ID: &str = "primary";
+
+/// How requests spread over several keys for one entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStrategy {
+    /// A different key for every request
+    #[default]
+    RoundRobin,
+    /// One key until it is rate limited, then the next
+    OnRateLimit,
+}
+
+/// An extra key registered for an entry. The key itself lives in the keychain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PooledKey {
+    id: String,
+    #[serde(default)]
+    label: Option<String>,
+    added_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyPool {
+    #[serde(default)]
+    strategy: RotationStrategy,
+    #[serde(default)]
+    keys: Vec<PooledKey>,
+}
+
+/// Key pools by keychain entry: a provider id or a profile's key reference.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeyPoolFile {
+    #[serde(default)]
+    pools: HashMap<String, KeyPool>,
+}
+
+/// A key as listed to the webview, without the key itself.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyInfo {
+    /// `primary` for the key saved with `set_api_key`
+    id: String,
+    label: Option<String>,
+    added_at: Option<i64>,
+    /// Unix millis until which the key is skipped after a 429
+    cooling_until: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct RotationState {
+    next: usize,
+    /// Key id to the Unix millis its rate limit ends
+    cooling: HashMap<String, i64>,
+}
+
+/// Where each entry's rotation stands, in memory only; a restart starts from the
+/// primary key again.
+#[derive(Default)]
+pub(crate) struct KeyRotation(Mutex<HashMap<String, RotationState>>);
+
+fn entry(provider: &str) -> Result<Entry, String> {
+    let valid = !provider.is_empty()
+        && provider.len() <= MAX_PROVIDER_CHARS
+        && provider
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("Invalid provider id: {}", provider));
+    }
+    Entry::new(KEYCHAIN_SERVICE, provider).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+/// The key saved for `provider`, if any.
+pub(crate) fn api_key(provider: &str) -> Result<Option<String>, String> {
+    match entry(provider)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Failed to read the API key for {}: {}",
+            provider, e
+        )),
+    }
+}
+
+/// The key for a request to `provider`: one passed explicitly, otherwise the keychain
+/// entry `entry` (a profile's key reference) or the provider's own entry. Local servers
+/// run without one and get a placeholder. Entries with a key pool hand out its keys in
+/// turn.
+pub(crate) fn resolve_api_key(
+    app: &tauri::AppHandle,
+    provider: Option<&str>,
+    entry: Option<&str>,
+    explicit: Option<String>,
+) -> Result<String, String> {
+    if let Some(key) = explicit.filter(|k| !k.trim().is_empty()) {
+        return Ok(key);
+    }
+    let name = entry.or(provider).unwrap_or(DEFAULT_PROVIDER);
+    let key = match load_json::<KeyPoolFile>(app, KEY_POOLS_FILE)?
+        .pools
+        .get(name)
+    {
+        Some(pool) if !pool.keys.is_empty() => next_key(app, name, pool, false)?,
+        _ => api_key(name)?,
+    };
+    match key {
+        Some(key) => Ok(key),
+        None if !ProviderKind::of(provider).needs_key() => Ok(LOCAL_API_KEY.to_string()),
+        None => Err(format!("No API key saved for {}", name)),
+    }
+}
+
+/// Keychain entry holding pooled key `id` of `name`. Names too long to fit the entry
+/// limit with the suffix are replaced by a digest, which stays the same across runs.
+fn pooled_entry(name: &str, id: &str) -> String {
+    if id == PRIMARY_KEY_ID {
+        return name.to_string();
+    }
+    let entry = format!("{}-pool-{}", name, id);
+    if entry.len() <= MAX_PROVIDER_CHARS {
+        return entry;
+    }
+    let digest = hex::encode(&Sha256::digest(name.as_bytes())[..8]);
+    format!("pool-{}-{}", digest, id)
+}
+
+/// Key ids in rotation order, the primary key first when one is saved.
+fn rotation_ids(name: &str, pool: &KeyPool) -> Result<Vec<String>, String> {
+    let mut ids = Vec::new();
+    if api_key(name)?.is_some() {
+        ids.push(PRIMARY_KEY_ID.to_string());
+    }
+    ids.extend(pool.keys.iter().map(|k| k.id.clone()));
+    Ok(ids)
+}
+
+/// The next key of `name`'s pool, skipping keys that are cooling down after a 429. When
+/// all of them are, `spare_only` gives up; otherwise the one that recovers first is used.
+fn next_key(
+    app: &tauri::AppHandle,
+    name: &str,
+    pool: &KeyPool,
+    spare_only: bool,
+) -> Result<Option<String>, String> {
+    let ids = rotation_ids(name, pool)?;
+    if ids.is_empty() {
+        return Ok(None);
+    }
+    let chosen = {
+        let rotation = app.state::<KeyRotation>();
+        let mut states = rotation.0.lock().unwrap();
+        let state = states.entry(name.to_string()).or_default();
+        let now = now_millis();
+        state.cooling.retain(|_, until| *until > now);
+        let start = state.next % ids.len();
+        let free = (0..ids.len())
+            .map(|i| (start + i) % ids.len())
+            .find(|&i| !state.cooling.contains_key(&ids[i]));
+        let index = match free {
+            Some(index) => index,
+            None if spare_only => return Ok(None),
+            None => (0..ids.len())
+                .min_by_key(|&i| state.cooling.get(&ids[i]).copied().unwrap_or(0))
+                .unwrap_or(start),
+        };
+        state.next = match pool.strategy {
+            RotationStrategy::RoundRobin => index + 1,
+            RotationStrategy::OnRateLimit => index,
+        };
+        ids[index].clone()
+    };
+    api_key(&pooled_entry(name, &chosen))
+}
+
+/// After `failed_key` drew a 429, benches it for `cooldown` and returns another key from
+/// the same pool to retry with at once. `None` when the entry has no pool or every other
+/// key is cooling down too.
+pub(crate) fn rotate_after_rate_limit(
+    app: &tauri::AppHandle,
+    provider: Option<&str>,
+    entry: Option<&str>,
+    failed_key: &str,
+    cooldown: Duration,
+) -> Option<String> {
+    let name = entry.or(provider).unwrap_or(DEFAULT_PROVIDER);
+    let pool = load_json::<KeyPoolFile>(app, KEY_POOLS_FILE)
+        .ok()?
+        .pools
+        .remove(name)
+        .filter(|pool| !pool.keys.is_empty())?;
+    let ids = rotation_ids(name, &pool).ok()?;
+    let failed = ids.iter().position(|id| {
+        api_key(&pooled_entry(name, id)).ok().flatten().as_deref() == Some(failed_key)
+    })?;
+    {
+        let rotation = app.state::<KeyRotation>();
+        let mut states = rotation.0.lock().unwrap();
+        let state = states.entry(name.to_string()).or_default();
+        state.cooling.insert(
+            ids[failed].clone(),
+            now_millis() + cooldown.as_millis() as i64,
+        );
+        state.next = failed + 1;
+    }
+    next_key(app, name, &pool, true)
+        .ok()
+        .flatten()
+        .filter(|key| key != failed_key)
+}
+
+/// Stores `api_key` under the keychain entry `name`; an empty key removes the entry.
+pub(crate) fn store_api_key(name: &str, api_key: &str) -> Result<(), String> {
+    let entry = entry(name)?;
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove the API key for {}: {}", name, e)),
+        };
+    }
+    entry
+        .set_password(api_key)
+        .map_err(|e| format!("Failed to save the API key for {}: {}", name, e))
+}
+
+/// Stores the key for `provider` in the OS keychain. An empty key removes it.
+#[tauri::command]
+pub fn set_api_key(provider: String, api_key: String) -> Result<(), String> {
+    store_api_key(provider.trim(), &api_key)
+}
+
+/// Whether a key is saved for `provider`; the key itself never leaves the backend.
+#[tauri::command]
+pub fn has_api_key(provider: String) -> Result<bool, String> {
+    Ok(api_key(provider.trim())?.is_some())
+}
+
+/// The keys registered for `provider` (a provider id or key reference), primary first.
+#[tauri::command]
+pub fn list_api_keys(app: tauri::AppHandle, provider: String) -> Result<Vec<KeyInfo>, String> {
+    let name = provider.trim();
+    let pool = load_json::<KeyPoolFile>(&app, KEY_POOLS_FILE)?
+        .pools
+        .remove(name)
+        .unwrap_or_default();
+    let cooling = {
+        let rotation = app.state::<KeyRotation>();
+        let states = rotation.0.lock().unwrap();
+        states
+            .get(name)
+            .map(|state| state.cooling.clone())
+            .unwrap_or_default()
+    };
+    let now = now_millis();
+    let cooling_until = |id: &str| cooling.get(id).copied().filter(|until| *until > now);
+    let mut keys = Vec::new();
+    if api_key(name)?.is_some() {
+        keys.push(KeyInfo {
+            id: PRIMARY_KEY_ID.to_string(),
+            label: None,
+            added_at: None,
+            cooling_until: cooling_until(PRIMARY_KEY_ID),
+        });
+    }
+    keys.extend(pool.keys.into_iter().map(|key| KeyInfo {
+        cooling_until: cooling_until(&key.id),
+        id: key.id,
+        label: key.label,
+        added_at: Some(key.added_at),
+    }));
+    Ok(keys)
+}
+
+/// Registers another key for `provider`, to be rotated with the ones already saved.
+#[tauri::command]
+pub fn add_api_key(
+    app: tauri::AppHandle,
+    provider: String,
+    api_key: String,
+    label: Option<String>,
+) -> Result<KeyInfo, String> {
+    let name = provider.trim();
+    let key = api_key.trim();
+    if key.is_empty() {
+        return Err("API key is empty".to_string());
+    }
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty());
+    if label
+        .as_ref()
+        .is_some_and(|l| l.chars().count() > MAX_LABEL_CHARS)
+    {
+        return Err(format!(
+            "Key label must be at most {} characters",
+            MAX_LABEL_CHARS
+        ));
+    }
+    let mut file = load_json::<KeyPoolFile>(&app, KEY_POOLS_FILE)?;
+    let pool = file.pools.entry(name.to_string()).or_default();
+    if pool.keys.len() >= MAX_POOL_KEYS {
+        return Err(format!(
+            "At most {} extra keys are allowed per provider",
+            MAX_POOL_KEYS
+        ));
+    }
+    for id in rotation_ids(name, pool)? {
+        if self::api_key(&pooled_entry(name, &id))?.as_deref() == Some(key) {
+            return Err(format!("This key is already saved for {}", name));
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    store_api_key(&pooled_entry(name, &id), key)?;
+    let added = PooledKey {
+        id,
+        label,
+        added_at: now_millis(),
+    };
+    pool.keys.push(added.clone());
+    save_json(&app, KEY_POOLS_FILE, &file)?;
+    Ok(KeyInfo {
+        id: added.id,
+        label: added.label,
+        added_at: Some(added.added_at),
+        cooling_until: None,
+    })
+}
+
+/// Removes key `id` of `provider`; `primary` removes the key saved with `set_api_key`.
+/// Returns `false` if there was no such key.
+#[tauri::command]
+pub fn remove_api_key(app: tauri::AppHandle, provider: String, id: String) -> Result<bool, String> {
+    let name = provider.trim();
+    if id == PRIMARY_KEY_ID {
+        let existed = api_key(name)?.is_some();
+        store_api_key(name, "")?;
+        return Ok(existed);
+    }
+    let mut file = load_json::<KeyPoolFile>(&app, KEY_POOLS_FILE)?;
+    let Some(pool) = file.pools.get_mut(name) else {
+        return Ok(false);
+    };
+    let before = pool.keys.len();
+    pool.keys.retain(|k| k.id != id);
+    if pool.keys.len() == before {
+        return Ok(false);
+    }
+    if pool.keys.is_empty() && pool.strategy == RotationStrategy::default() {
+        file.pools.remove(name);
+    }
+    store_api_key(&pooled_entry(name, &id), "")?;
+    save_json(&app, KEY_POOLS_FILE, &file).map(|_| true)
+}
+
+/// Chooses how requests to `provider` rotate through its keys, starting over from the
+/// primary key.
+#[tauri::command]
+pub fn set_key_rotation(
+    app: tauri::AppHandle,
+    provider: String,
+    strategy: RotationStrategy,
+) -> Result<(), String> {
+    let name = provider.trim();
+    entry(name)?;
+    let mut file = load_json::<KeyPoolFile>(&app, KEY_POOLS_FILE)?;
+    file.pools.entry(name.to_string()).or_default().strategy = strategy;
+    save_json(&app, KEY_POOLS_FILE, &file)?;
+    app.state::<KeyRotation>().0.lock().unwrap().remove(name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pooled_entries_fit_the_entry_limit() {
+        assert_eq!(pooled_entry("openai", PRIMARY_KEY_ID), "openai");
+        assert_eq!(pooled_entry("openai", "1a2b3c4d"), "openai-pool-1a2b3c4d");
+
+        let long = "p".repeat(MAX_PROVIDER_CHARS);
+        let entry = pooled_entry(&long, "1a2b3c4d");
+        assert!(entry.len() <= MAX_PROVIDER_CHARS);
+        assert_eq!(entry, pooled_entry(&long, "1a2b3c4d"));
+        assert_ne!(
+            entry,
+            pooled_entry(&"q".repeat(MAX_PROVIDER_CHARS), "1a2b3c4d")
+        );
+    }
+}
@@ -0,0 +1,652 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::app_data::app_data_path;
+use crate::clock::now_millis;
+use crate::embeddings;
+use crate::redaction::{self, Sensitivity};
+use crate::workspace::{is_binary_file, relative_path, walk_files, workspace_root};
+
+const DATABASE_FILE: &str = "semantic_index.db";
+const CHUNK_LINES: usize = 40;
+// Consecutive chunks share a few lines so code split at a boundary is still found whole
+const CHUNK_OVERLAP: usize = 8;
+const MAX_CHUNK_CHARS: usize = 4_000;
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+const MAX_FILES: usize = 5_000;
+// Chunks from several files are embedded together, in requests about this size
+const EMBED_BATCH: usize = 128;
+const DEFAULT_RESULTS: usize = 8;
+const MAX_RESULTS: usize = 50;
+// What `context_for` adds to a prompt
+const CONTEXT_RESULTS: usize = 5;
+const MIN_CONTEXT_SCORE: f32 = 0.3;
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+
+CREATE TABLE IF NOT EXISTS indexes (
+    workspace TEXT PRIMARY KEY,
+    profile_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL DEFAULT 0,
+    auto_context INTEGER NOT NULL DEFAULT 1,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS indexed_files (
+    workspace TEXT NOT NULL,
+    path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (workspace, path)
+);
+
+CREATE TABLE IF NOT EXISTS chunks (
+    id INTEGER PRIMARY KEY,
+    workspace TEXT NOT NULL,
+    path TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    vector BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chunks_by_file ON chunks (workspace, path);
+";
+
+/// Pending index work for one workspace. An entry exists while its runner is alive.
+#[derive(Default)]
+struct Job {
+    full: bool,
+    paths: HashSet<PathBuf>,
+}
+
+/// Chunk embeddings per workspace, in their own database so the vectors don't bloat
+/// the conversation store.
+pub(crate) struct SemanticIndex {
+    db: Mutex<Connection>,
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStatus {
+    workspace: String,
+    profile_id: String,
+    model: String,
+    dimensions: i64,
+    files: i64,
+    chunks: i64,
+    /// Whether matches are added to prompts sent from this workspace
+    auto_context: bool,
+    updated_at: i64,
+    /// Whether files are being indexed right now
+    indexing: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticMatch {
+    /// Relative to the workspace root
+    path: String,
+    /// 1-based, inclusive
+    start_line: i64,
+    end_line: i64,
+    content: String,
+    /// Cosine similarity to the query
+    score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexProgress {
+    workspace: String,
+    indexed: usize,
+    total: usize,
+    done: bool,
+    error: Option<String>,
+}
+
+struct Meta {
+    profile_id: String,
+    model: String,
+    auto_context: bool,
+}
+
+struct Chunk {
+    start_line: usize,
+    end_line: usize,
+    content: String,
+}
+
+/// A file whose chunks need embedding.
+struct PendingFile {
+    path: String,
+    hash: String,
+    chunks: Vec<Chunk>,
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Semantic index error: {}", e)
+}
+
+pub(crate) fn open(app: &tauri::AppHandle) -> Result<SemanticIndex, String> {
+    let path = app_data_path(app, DATABASE_FILE)?;
+    let conn =
+        Connection::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    conn.execute_batch(SCHEMA).map_err(db_error)?;
+    Ok(SemanticIndex {
+        db: Mutex::new(conn),
+        jobs: Mutex::new(HashMap::new()),
+    })
+}
+
+fn meta(app: &tauri::AppHandle, workspace: &str) -> Result<Option<Meta>, String> {
+    let index = app.state::<SemanticIndex>();
+    let conn = index.db.lock().unwrap();
+    conn.query_row(
+        "SELECT profile_id, model, auto_context FROM indexes WHERE workspace = ?1",
+        params![workspace],
+        |row| {
+            Ok(Meta {
+                profile_id: row.get(0)?,
+                model: row.get(1)?,
+                auto_context: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+/// Overlapping windows of `CHUNK_LINES` lines; blank windows are dropped.
+fn chunk(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let content: String = lines[start..end].join("\n");
+        if !content.trim().is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                content: content.chars().take(MAX_CHUNK_CHARS).collect(),
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start = end - CHUNK_OVERLAP;
+    }
+    chunks
+}
+
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+fn forget_file(conn: &Connection, workspace: &str, path: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM chunks WHERE workspace = ?1 AND path = ?2",
+        params![workspace, path],
+    )?;
+    conn.execute(
+        "DELETE FROM indexed_files WHERE workspace = ?1 AND path = ?2",
+        params![workspace, path],
+    )?;
+    Ok(())
+}
+
+/// Reads and chunks `path` unless it is unchanged since it was indexed. Files that can't
+/// be indexed any more (deleted, binary, too large) are dropped from the index.
+fn prepare(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    root: &Path,
+    path: &Path,
+) -> Result<Option<PendingFile>, String> {
+    let relative = relative_path(root, path);
+    // Credential files are never embedded; secrets elsewhere are masked before they are
+    let indexable = path.is_file()
+        && std::fs::metadata(path).is_ok_and(|m| m.len() <= MAX_FILE_BYTES)
+        && !is_binary_file(path)
+        && redaction::sensitivity(path) == Sensitivity::Normal;
+    let text = indexable
+        .then(|| std::fs::read_to_string(path).ok())
+        .flatten()
+        .map(|text| redaction::mask_tokens(&text).text);
+    let index = app.state::<SemanticIndex>();
+    let conn = index.db.lock().unwrap();
+    let Some(text) = text else {
+        forget_file(&conn, workspace, &relative).map_err(db_error)?;
+        return Ok(None);
+    };
+    let hash = hex::encode(Sha256::digest(text.as_bytes()));
+    let known: Option<String> = conn
+        .query_row(
+            "SELECT hash FROM indexed_files WHERE workspace = ?1 AND path = ?2",
+            params![workspace, relative],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)?;
+    if known.as_deref() == Some(hash.as_str()) {
+        return Ok(None);
+    }
+    Ok(Some(PendingFile {
+        path: relative,
+        hash,
+        chunks: chunk(&text),
+    }))
+}
+
+/// Embeds the chunks of `files` in one go and replaces what the index held for them.
+async fn embed_files(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    meta: &Meta,
+    files: Vec<PendingFile>,
+) -> Result<(), String> {
+    // The path is part of the embedded text; file names carry a lot of meaning in code
+    let texts: Vec<String> = files
+        .iter()
+        .flat_map(|f| {
+            f.chunks
+                .iter()
+                .map(move |c| format!("{}\n\n{}", f.path, c.content))
+        })
+        .collect();
+    let vectors = if texts.is_empty() {
+        Vec::new()
+    } else {
+        let embeddings =
+            embeddings::embed(app, &meta.profile_id, texts, Some(meta.model.clone())).await?;
+        embeddings.vectors
+    };
+
+    let index = app.state::<SemanticIndex>();
+    let mut conn = index.db.lock().unwrap();
+    let tx = conn.transaction().map_err(db_error)?;
+    let mut vectors = vectors.into_iter();
+    for file in files {
+        forget_file(&tx, workspace, &file.path).map_err(db_error)?;
+        for chunk in &file.chunks {
+            let vector = vectors.next().unwrap_or_default();
+            tx.execute(
+                "INSERT INTO chunks (workspace, path, start_line, end_line, content, vector)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    workspace,
+                    file.path,
+                    chunk.start_line as i64,
+                    chunk.end_line as i64,
+                    chunk.content,
+                    encode(&vector)
+                ],
+            )
+            .map_err(db_error)?;
+        }
+        tx.execute(
+            "INSERT INTO indexed_files (workspace, path, hash) VALUES (?1, ?2, ?3)",
+            params![workspace, file.path, file.hash],
+        )
+        .map_err(db_error)?;
+    }
+    tx.commit().map_err(db_error)
+}
+
+/// Brings the index up to date for every file (`full`) or just `paths`.
+async fn process(app: &tauri::AppHandle, workspace: &str, job: Job) -> Result<(), String> {
+    let Some(meta) = meta(app, workspace)? else {
+        return Ok(());
+    };
+    let root = PathBuf::from(workspace);
+    let candidates: Vec<PathBuf> = if job.full {
+        let files: Vec<PathBuf> = walk_files(&root).take(MAX_FILES).collect();
+        // Drop files that were indexed but are gone or now ignored
+        let present: HashSet<String> = files.iter().map(|f| relative_path(&root, f)).collect();
+        let index = app.state::<SemanticIndex>();
+        let conn = index.db.lock().unwrap();
+        let indexed: Vec<String> = conn
+            .prepare("SELECT path FROM indexed_files WHERE workspace = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![workspace], |row| row.get(0))?
+                    .collect()
+            })
+            .map_err(db_error)?;
+        for path in indexed.iter().filter(|p| !present.contains(*p)) {
+            forget_file(&conn, workspace, path).map_err(db_error)?;
+        }
+        files
+    } else {
+        job.paths.into_iter().collect()
+    };
+
+    let total = candidates.len();
+    let progress = |indexed: usize, done: bool, error: Option<String>| {
+        let _ = app.emit(
+            "semantic-index:progress",
+            IndexProgress {
+                workspace: workspace.to_string(),
+                indexed,
+                total,
+                done,
+                error,
+            },
+        );
+    };
+    let mut batch = Vec::new();
+    let mut batch_chunks = 0;
+    for (done, path) in candidates.iter().enumerate() {
+        if let Some(file) = prepare(app, workspace, &root, path)? {
+            batch_chunks += file.chunks.len();
+            batch.push(file);
+        }
+        if batch_chunks >= EMBED_BATCH || done + 1 == total {
+            embed_files(app, workspace, &meta, std::mem::take(&mut batch)).await?;
+            batch_chunks = 0;
+            progress(done + 1, false, None);
+        }
+    }
+
+    let index = app.state::<SemanticIndex>();
+    let conn = index.db.lock().unwrap();
+    let dimensions: Option<Vec<u8>> = conn
+        .query_row(
+            "SELECT vector FROM chunks WHERE workspace = ?1 LIMIT 1",
+            params![workspace],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)?;
+    conn.execute(
+        "UPDATE indexes SET updated_at = ?2, dimensions = ?3 WHERE workspace = ?1",
+        params![
+            workspace,
+            now_millis(),
+            dimensions.map_or(0, |v| v.len() as i64 / 4)
+        ],
+    )
+    .map_err(db_error)?;
+    drop(conn);
+    progress(total, true, None);
+    Ok(())
+}
+
+/// Works through a workspace's queued jobs until none are left.
+async fn run(app: tauri::AppHandle, workspace: String) {
+    loop {
+        let job = {
+            let index = app.state::<SemanticIndex>();
+            let mut jobs = index.jobs.lock().unwrap();
+            let Some(job) = jobs.get_mut(&workspace) else {
+                return;
+            };
+            if !job.full && job.paths.is_empty() {
+                jobs.remove(&workspace);
+                return;
+            }
+            std::mem::take(job)
+        };
+        if let Err(err) = process(&app, &workspace, job).await {
+            eprintln!("[semantic-index] {}: {}", workspace, err);
+            let _ = app.emit(
+                "semantic-index:progress",
+                IndexProgress {
+                    workspace: workspace.clone(),
+                    indexed: 0,
+                    total: 0,
+                    done: true,
+                    error: Some(err),
+                },
+            );
+        }
+    }
+}
+
+fn schedule(app: &tauri::AppHandle, workspace: &str, full: bool, paths: Vec<PathBuf>) {
+    let index = app.state::<SemanticIndex>();
+    let mut jobs = index.jobs.lock().unwrap();
+    let running = jobs.contains_key(workspace);
+    let job = jobs.entry(workspace.to_string()).or_default();
+    job.full |= full;
+    job.paths.extend(paths);
+    if !running {
+        tauri::async_runtime::spawn(run(app.clone(), workspace.to_string()));
+    }
+}
+
+/// Re-indexes files the watcher saw change, if the workspace has an index.
+pub(crate) fn refresh(app: &tauri::AppHandle, root: &Path, paths: Vec<PathBuf>) {
+    let workspace = root.to_string_lossy();
+    if paths.is_empty() || !matches!(meta(app, &workspace), Ok(Some(_))) {
+        return;
+    }
+    schedule(app, &workspace, false, paths);
+}
+
+async fn search(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    meta: &Meta,
+    query: &str,
+    k: usize,
+) -> Result<Vec<SemanticMatch>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let embedded = embeddings::embed(
+        app,
+        &meta.profile_id,
+        vec![query.to_string()],
+        Some(meta.model.clone()),
+    )
+    .await?;
+    let Some(target) = embedded.vectors.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+
+    let index = app.state::<SemanticIndex>();
+    let conn = index.db.lock().unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, start_line, end_line, content, vector FROM chunks
+             WHERE workspace = ?1",
+        )
+        .map_err(db_error)?;
+    let mut matches: Vec<SemanticMatch> = stmt
+        .query_map(params![workspace], |row| {
+            let vector: Vec<u8> = row.get(4)?;
+            Ok(SemanticMatch {
+                path: row.get(0)?,
+                start_line: row.get(1)?,
+                end_line: row.get(2)?,
+                content: row.get(3)?,
+                score: cosine(&target, &decode(&vector)),
+            })
+        })
+        .map_err(db_error)?
+        .collect::<Result<_, _>>()
+        .map_err(db_error)?;
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(k);
+    Ok(matches)
+}
+
+/// The best matches for `prompt` as a context block, when the workspace is indexed with
+/// automatic context. Failures only cost the extra context, never the message.
+pub(crate) async fn context_for(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    prompt: &str,
+) -> Option<String> {
+    let root = workspace_root(workspace).ok()?;
+    let workspace = root.to_string_lossy();
+    let meta = meta(app, &workspace).ok().flatten()?;
+    if !meta.auto_context {
+        return None;
+    }
+    let matches = match search(app, &workspace, &meta, prompt, CONTEXT_RESULTS).await {
+        Ok(matches) => matches,
+        Err(err) => {
+            eprintln!("[semantic-index] {}", err);
+            return None;
+        }
+    };
+    let matches: Vec<_> = matches
+        .into_iter()
+        .filter(|m| m.score >= MIN_CONTEXT_SCORE)
+        .collect();
+    if matches.is_empty() {
+        return None;
+    }
+    let mut out = String::from("Workspace code that looks relevant to this request.\n");
+    for m in matches {
+        // Chunks indexed before masking existed may still hold secrets
+        let content = redaction::mask_file(Path::new(&m.path), &m.content).text;
+        out.push_str(&format!(
+            "\n```{} (lines {}-{})\n{}\n```\n",
+            m.path, m.start_line, m.end_line, content
+        ));
+    }
+    Some(out)
+}
+
+fn status(app: &tauri::AppHandle, workspace: &str) -> Result<Option<IndexStatus>, String> {
+    let index = app.state::<SemanticIndex>();
+    let indexing = index.jobs.lock().unwrap().contains_key(workspace);
+    let conn = index.db.lock().unwrap();
+    conn.query_row(
+        "SELECT profile_id, model, dimensions, auto_context, updated_at,
+                (SELECT COUNT(*) FROM indexed_files WHERE workspace = ?1),
+                (SELECT COUNT(*) FROM chunks WHERE workspace = ?1)
+         FROM indexes WHERE workspace = ?1",
+        params![workspace],
+        |row| {
+            Ok(IndexStatus {
+                workspace: workspace.to_string(),
+                profile_id: row.get(0)?,
+                model: row.get(1)?,
+                dimensions: row.get(2)?,
+                auto_context: row.get(3)?,
+                updated_at: row.get(4)?,
+                files: row.get(5)?,
+                chunks: row.get(6)?,
+                indexing,
+            })
+        },
+    )
+    .optional()
+    .map_err(db_error)
+}
+
+/// Starts indexing `workspace` with the embedding model of `profile_id` in the background,
+/// reporting `semantic-index:progress`. Unchanged files are skipped; switching to another
+/// model rebuilds the index from scratch. With `auto_context` (the default), matches for
+/// each prompt are added to messages sent from the workspace.
+#[tauri::command]
+pub fn index_workspace(
+    app: tauri::AppHandle,
+    workspace: String,
+    profile_id: String,
+    model: Option<String>,
+    auto_context: Option<bool>,
+) -> Result<IndexStatus, String> {
+    let root = workspace_root(&workspace)?;
+    let workspace = root.to_string_lossy().into_owned();
+    let model = embeddings::model_for(&app, &profile_id, model)?;
+    {
+        let index = app.state::<SemanticIndex>();
+        let conn = index.db.lock().unwrap();
+        let previous = conn
+            .query_row(
+                "SELECT profile_id, model FROM indexes WHERE workspace = ?1",
+                params![workspace],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(db_error)?;
+        // Vectors from different models can't be compared
+        if previous.is_some_and(|(p, m)| p != profile_id || m != model) {
+            conn.execute(
+                "DELETE FROM chunks WHERE workspace = ?1",
+                params![workspace],
+            )
+            .map_err(db_error)?;
+            conn.execute(
+                "DELETE FROM indexed_files WHERE workspace = ?1",
+                params![workspace],
+            )
+            .map_err(db_error)?;
+        }
+        conn.execute(
+            "INSERT INTO indexes (workspace, profile_id, model, auto_context, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (workspace) DO UPDATE SET profile_id = ?2, model = ?3,
+                 auto_context = COALESCE(?6, auto_context)",
+            params![
+                workspace,
+                profile_id,
+                model,
+                auto_context.unwrap_or(true),
+                now_millis(),
+                auto_context
+            ],
+        )
+        .map_err(db_error)?;
+    }
+    schedule(&app, &workspace, true, Vec::new());
+    status(&app, &workspace)?.ok_or_else(|| "Index not found".to_string())
+}
+
+/// The workspace's index, or `None` if it was never indexed.
+#[tauri::command]
+pub fn get_index_status(
+    app: tauri::AppHandle,
+    workspace: String,
+) -> Result<Option<IndexStatus>, String> {
+    let root = workspace_root(&workspace)?;
+    status(&app, &root.to_string_lossy())
+}
+
+/// The `k` indexed chunks closest in meaning to `query`.
+#[tauri::command]
+pub async fn semantic_search(
+    app: tauri::AppHandle,
+    workspace: String,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<SemanticMatch>, String> {
+    let root = workspace_root(&workspace)?;
+    let workspace = root.to_string_lossy();
+    let meta = meta(&app, &workspace)?.ok_or("This workspace hasn't been indexed yet")?;
+    let k = k.unwrap_or(DEFAULT_RESULTS).clamp(1, MAX_RESULTS);
+    search(&app, &workspace, &meta, &query, k).await
+}
@@ -0,0 +1,99 @@
+use tauri::Manager;
+use tauri_plugin_shell::{process::CommandEvent, ShellExt};
+
+use crate::rpc::{
+    handle_sidecar_output, PendingRequests, RpcError, SidecarGeneration, SidecarProcess,
+    ERR_CANCELLED,
+};
+
+/// Spawns the agent sidecar and starts forwarding its output.
+pub(crate) fn spawn(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    // Spawn the sidecar
+    let (mut rx, child) = app_handle
+        .shell()
+        .sidecar("agent")
+        .map_err(|e| format!("Failed to create sidecar command: {}", e))?
+        .envs(crate::proxy::sidecar_env(app_handle))
+        .spawn()
+        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+
+    // Every process gets its own generation so stale responses can't resolve newer requests
+    let generation = uuid::Uuid::new_v4().to_string();
+
+    // Store the child process for writing
+    {
+        let sidecar_state = app_handle.state::<SidecarProcess>();
+        let mut guard = sidecar_state.lock().unwrap();
+        *guard = Some(child);
+        *app_handle.state::<SidecarGeneration>().0.lock().unwrap() = generation.clone();
+    }
+
+    // Handle sidecar output in background
+    let app_handle_clone = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut stdout_buf = String::new();
+        let mut stderr_buf = String::new();
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                CommandEvent::Stdout(line_bytes) => {
+                    if let Ok(chunk) = String::from_utf8(line_bytes) {
+                        stdout_buf.push_str(&chunk);
+                        while let Some(pos) = stdout_buf.find('\n') {
+                            let mut line = stdout_buf[..pos].to_string();
+                            stdout_buf = stdout_buf[pos + 1..].to_string();
+                            if line.ends_with('\r') {
+                                line.pop();
+                            }
+                            handle_sidecar_output(&app_handle_clone, &generation, &line);
+                        }
+                    }
+                }
+                CommandEvent::Stderr(line_bytes) => {
+                    if let Ok(chunk) = String::from_utf8(line_bytes) {
+                        stderr_buf.push_str(&chunk);
+                        while let Some(pos) = stderr_buf.find('\n') {
+                            let mut line = stderr_buf[..pos].to_string();
+                            stderr_buf = stderr_buf[pos + 1..].to_string();
+                            if line.ends_with('\r') {
+                                line.pop();
+                            }
+                            eprintln!("[sidecar stderr] {}", line);
+                        }
+                    }
+                }
+                CommandEvent::Error(err) => {
+                    eprintln!("[sidecar error] {}", err);
+                }
+                CommandEvent::Terminated(status) => {
+                    eprintln!("[sidecar terminated] {:?}", status);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Kills the running sidecar, fails every in-flight request, and starts a fresh process.
+pub(crate) fn restart(app: &tauri::AppHandle) -> Result<(), String> {
+    let previous = app.state::<SidecarProcess>().lock().unwrap().take();
+    if let Some(child) = previous {
+        let _ = child.kill();
+    }
+
+    let pending: Vec<_> = app
+        .state::<PendingRequests>()
+        .lock()
+        .unwrap()
+        .drain()
+        .collect();
+    for (_, entry) in pending {
+        let _ = entry
+            .tx
+            .send(Err(RpcError::new(ERR_CANCELLED, "Sidecar restarted")));
+    }
+
+    spawn(app)
+}
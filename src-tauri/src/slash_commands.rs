@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::a11y::{self, AnnouncementKind};
+use crate::app_data::{load_json, save_json};
+use crate::template;
+use crate::workspace::workspace_root;
+
+const SLASH_COMMANDS_FILE: &str = "slash_commands.json";
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+// Names the frontend handles itself and users may not shadow
+const RESERVED_NAMES: &[&str] = &["help", "clear", "new"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SlashAction {
+    /// Expands into a chat message using `{{placeholders}}` from the parsed arguments
+    Prompt { template: String },
+    /// Asks the agent to call a specific tool with templated JSON arguments
+    Tool { tool: String, arguments: String },
+    /// Runs a script with the arguments passed positionally and as `OMC_ARG_<NAME>` env vars
+    Shell { script: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlashArg {
+    name: String,
+    #[serde(default)]
+    required: bool,
+    #[serde(default)]
+    default: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlashCommand {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    action: SlashAction,
+    #[serde(default)]
+    args: Vec<SlashArg>,
+    /// Shell commands need an explicit per-invocation approval unless marked trusted
+    #[serde(default)]
+    trusted: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SlashCommandFile {
+    commands: Vec<SlashCommand>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SlashOutcome {
+    Message {
+        text: String,
+    },
+    Shell {
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    ApprovalRequired {
+        command: String,
+        script: String,
+    },
+}
+
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim().trim_start_matches('/').to_lowercase();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("Command names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(name)
+}
+
+/// Splits arguments shell-style: whitespace separated, with single/double quotes and
+/// backslash escapes.
+fn split_args(input: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                in_token = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_token = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_token {
+                    args.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Unterminated quote in command arguments".to_string());
+    }
+    if in_token {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Binds `key=value` tokens by name and the rest positionally, in declaration order.
+fn bind_args(
+    command: &SlashCommand,
+    raw: &str,
+    tokens: &[String],
+) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    let mut positional = Vec::new();
+
+    for token in tokens {
+        match token.split_once('=') {
+            Some((key, value)) if command.args.iter().any(|a| a.name == key) => {
+                values.insert(key.to_string(), value.to_string());
+            }
+            _ => positional.push(token.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    for arg in &command.args {
+        if values.contains_key(&arg.name) {
+            continue;
+        }
+        match positional.next().or_else(|| arg.default.clone()) {
+            Some(value) => {
+                values.insert(arg.name.clone(), value);
+            }
+            None if arg.required => {
+                return Err(format!(
+                    "Missing required argument '{}' for /{}",
+                    arg.name, command.name
+                ));
+            }
+            None => {}
+        }
+    }
+
+    let extra: Vec<String> = positional.collect();
+    values.insert("input".to_string(), raw.trim().to_string());
+    values.insert("rest".to_string(), extra.join(" "));
+    Ok(values)
+}
+
+fn truncate_output(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n… (output truncated)", &text[..end])
+}
+
+fn run_script(
+    script: &str,
+    command: &SlashCommand,
+    values: &HashMap<String, String>,
+    cwd: Option<&str>,
+) -> Result<SlashOutcome, String> {
+    let positional: Vec<&str> = command
+        .args
+        .iter()
+        .filter_map(|a| values.get(&a.name).map(String::as_str))
+        .collect();
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(script);
+        cmd
+    } else {
+        // Arguments are passed as $1..$n rather than spliced into the script text
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(script)
+            .arg(&command.name)
+            .args(&positional);
+        cmd
+    };
+
+    for (key, value) in values {
+        cmd.env(format!("OMC_ARG_{}", key.to_uppercase()), value);
+    }
+    if let Some(cwd) = cwd {
+        cmd.current_dir(workspace_root(cwd)?);
+    }
+
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run /{}: {}", command.name, e))?;
+    Ok(SlashOutcome::Shell {
+        exit_code: output.status.code(),
+        stdout: truncate_output(&output.stdout),
+        stderr: truncate_output(&output.stderr),
+    })
+}
+
+#[tauri::command]
+pub fn list_slash_commands(app: tauri::AppHandle) -> Result<Vec<SlashCommand>, String> {
+    Ok(load_json::<SlashCommandFile>(&app, SLASH_COMMANDS_FILE)?.commands)
+}
+
+#[tauri::command]
+pub fn save_slash_command(
+    app: tauri::AppHandle,
+    command: SlashCommand,
+) -> Result<SlashCommand, String> {
+    let mut command = command;
+    command.name = normalize_name(&command.name)?;
+    if RESERVED_NAMES.contains(&command.name.as_str()) {
+        return Err(format!("/{} is a built-in command", command.name));
+    }
+
+    let mut file = load_json::<SlashCommandFile>(&app, SLASH_COMMANDS_FILE)?;
+    match file.commands.iter_mut().find(|c| c.name == command.name) {
+        Some(existing) => *existing = command.clone(),
+        None => file.commands.push(command.clone()),
+    }
+    save_json(&app, SLASH_COMMANDS_FILE, &file)?;
+    Ok(command)
+}
+
+#[tauri::command]
+pub fn delete_slash_command(app: tauri::AppHandle, name: String) -> Result<bool, String> {
+    let name = normalize_name(&name)?;
+    let mut file = load_json::<SlashCommandFile>(&app, SLASH_COMMANDS_FILE)?;
+    let before = file.commands.len();
+    file.commands.retain(|c| c.name != name);
+    let removed = file.commands.len() != before;
+    if removed {
+        save_json(&app, SLASH_COMMANDS_FILE, &file)?;
+    }
+    Ok(removed)
+}
+
+/// Resolves `/command args` submitted by the frontend and executes it.
+#[tauri::command]
+pub async fn run_slash_command(
+    app: tauri::AppHandle,
+    input: String,
+    workspace: Option<String>,
+    approved: Option<bool>,
+) -> Result<SlashOutcome, String> {
+    let input = input.trim();
+    let Some(body) = input.strip_prefix('/') else {
+        return Err("Slash commands must start with '/'".to_string());
+    };
+    let (name, raw_args) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+    let name = normalize_name(name)?;
+
+    let file = load_json::<SlashCommandFile>(&app, SLASH_COMMANDS_FILE)?;
+    let command = file
+        .commands
+        .into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("Unknown command: /{}", name))?;
+
+    let tokens = split_args(raw_args)?;
+    let values = bind_args(&command, raw_args, &tokens)?;
+
+    match &command.action {
+        SlashAction::Prompt { template } => Ok(SlashOutcome::Message {
+            text: template::render(template, &values).text,
+        }),
+        SlashAction::Tool { tool, arguments } => {
+            let arguments = template::render(arguments, &values).text;
+            Ok(SlashOutcome::Message {
+                text: format!(
+                    "Call the `{}` tool with these arguments and report the result:\n```json\n{}\n```",
+                    tool, arguments
+                ),
+            })
+        }
+        SlashAction::Shell { script } => {
+            if !command.trusted && !approved.unwrap_or(false) {
+                a11y::announce(
+                    &app,
+                    AnnouncementKind::AwaitingApproval,
+                    None,
+                    format!("/{} is waiting for your approval to run", command.name),
+                );
+                return Ok(SlashOutcome::ApprovalRequired {
+                    command: format!("/{}", command.name),
+                    script: script.clone(),
+                });
+            }
+            let script = script.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                run_script(&script, &command, &values, workspace.as_deref())
+            })
+            .await
+            .map_err(|e| format!("Command task failed: {}", e))?
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::app_data::{load_json, save_json};
+use crate::template;
+
+const SNIPPETS_FILE: &str = "snippets.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    trigger: String,
+    #[serde(default)]
+    description: Option<String>,
+    template: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnippetFile {
+    snippets: Vec<Snippet>,
+}
+
+impl Default for SnippetFile {
+    // Seeded on first run so the feature is discoverable
+    fn default() -> Self {
+        Self {
+            snippets: vec![
+                Snippet {
+                    trigger: "!bug".to_string(),
+                    description: Some("Bug report skeleton".to_string()),
+                    template: "I found a bug.\n\nWhat happened: {{input}}\nExpected: {{expected|(describe the expected behavior)}}\n\nPlease find the root cause in the workspace and propose a fix.".to_string(),
+                },
+                Snippet {
+                    trigger: "!review".to_string(),
+                    description: Some("Code review request".to_string()),
+                    template: "Please review {{input|the recent changes}} for correctness, readability, and edge cases. List concrete issues with file and line references.".to_string(),
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandedSnippet {
+    trigger: String,
+    text: String,
+    missing: Vec<String>,
+}
+
+fn normalize_trigger(trigger: &str) -> Result<String, String> {
+    let trigger = trigger.trim();
+    if trigger.is_empty() || trigger.chars().any(char::is_whitespace) {
+        return Err("Snippet trigger must be a single word".to_string());
+    }
+    if trigger.starts_with('!') {
+        Ok(trigger.to_string())
+    } else {
+        Ok(format!("!{}", trigger))
+    }
+}
+
+#[tauri::command]
+pub fn list_snippets(app: tauri::AppHandle) -> Result<Vec<Snippet>, String> {
+    Ok(load_json::<SnippetFile>(&app, SNIPPETS_FILE)?.snippets)
+}
+
+#[tauri::command]
+pub fn save_snippet(
+    app: tauri::AppHandle,
+    trigger: String,
+    template: String,
+    description: Option<String>,
+) -> Result<Snippet, String> {
+    let snippet = Snippet {
+        trigger: normalize_trigger(&trigger)?,
+        description,
+        template,
+    };
+
+    let mut file = load_json::<SnippetFile>(&app, SNIPPETS_FILE)?;
+    match file
+        .snippets
+        .iter_mut()
+        .find(|s| s.trigger == snippet.trigger)
+    {
+        Some(existing) => *existing = snippet.clone(),
+        None => file.snippets.push(snippet.clone()),
+    }
+    save_json(&app, SNIPPETS_FILE, &file)?;
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub fn delete_snippet(app: tauri::AppHandle, trigger: String) -> Result<bool, String> {
+    let trigger = normalize_trigger(&trigger)?;
+    let mut file = load_json::<SnippetFile>(&app, SNIPPETS_FILE)?;
+    let before = file.snippets.len();
+    file.snippets.retain(|s| s.trigger != trigger);
+    let removed = file.snippets.len() != before;
+    if removed {
+        save_json(&app, SNIPPETS_FILE, &file)?;
+    }
+    Ok(removed)
+}
+
+/// Resolves a trigger such as `!bug` into its template, filling `{{placeholders}}` from `context`.
+#[tauri::command]
+pub fn expand_snippet(
+    app: tauri::AppHandle,
+    trigger: String,
+    context: Option<HashMap<String, String>>,
+) -> Result<ExpandedSnippet, String> {
+    let trigger = normalize_trigger(&trigger)?;
+    let file = load_json::<SnippetFile>(&app, SNIPPETS_FILE)?;
+    let snippet = file
+        .snippets
+        .into_iter()
+        .find(|s| s.trigger == trigger)
+        .ok_or_else(|| format!("Unknown snippet: {}", trigger))?;
+
+    let rendered = template::render(&snippet.template, &context.unwrap_or_default());
+    Ok(ExpandedSnippet {
+        trigger,
+        text: rendered.text,
+        missing: rendered.missing,
+    })
+}
@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::app_data::{load_json, save_json};
+
+const SPEECH_FILE: &str = "speech.json";
+// Long replies are cut rather than read for ten minutes
+const MAX_SPOKEN_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechSettings {
+    /// Read each completed reply aloud
+    #[serde(default)]
+    auto_read: bool,
+    /// Platform voice name; the system default when absent
+    #[serde(default)]
+    voice: Option<String>,
+}
+
+/// The speech process currently playing, if any.
+#[derive(Default)]
+pub(crate) struct Speech(Mutex<Option<Child>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeechEvent {
+    /// `manual` or `auto_read`
+    source: &'static str,
+    chars: usize,
+}
+
+/// Drops code blocks and markdown punctuation that engines would read out literally.
+fn spoken_text(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            if !in_code {
+                out.push_str("Code block omitted.\n");
+            }
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let line = line.trim_start_matches(|c: char| c == '#' || c == '>' || c.is_whitespace());
+        let line: String = line
+            .chars()
+            .filter(|c| !matches!(c, '*' | '_' | '`'))
+            .collect();
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.trim().chars().take(MAX_SPOKEN_CHARS).collect()
+}
+
+/// Platform TTS command reading the text from stdin. Neither the text nor the voice
+/// name is ever interpreted by a shell.
+fn tts_command(voice: Option<&str>) -> Command {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("say");
+        if let Some(voice) = voice {
+            cmd.args(["-v", voice]);
+        }
+        cmd
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = Command::new("powershell");
+        cmd.args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; \
+             $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+             if ($env:OHMYCOWORK_VOICE) { $s.SelectVoice($env:OHMYCOWORK_VOICE) }; \
+             $s.Speak([Console]::In.ReadToEnd())",
+        ]);
+        if let Some(voice) = voice {
+            cmd.env("OHMYCOWORK_VOICE", voice);
+        }
+        cmd
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let mut cmd = Command::new("espeak-ng");
+        cmd.arg("--stdin");
+        if let Some(voice) = voice {
+            cmd.args(["-v", voice]);
+        }
+        cmd
+    }
+}
+
+fn stop(app: &tauri::AppHandle) -> bool {
+    let child = app.state::<Speech>().0.lock().unwrap().take();
+    match child {
+        Some(mut child) => {
+            let running = matches!(child.try_wait(), Ok(None));
+            let _ = child.kill();
+            let _ = child.wait();
+            running
+        }
+        None => false,
+    }
+}
+
+fn speak(
+    app: &tauri::AppHandle,
+    text: &str,
+    voice: Option<&str>,
+    source: &'static str,
+) -> Result<(), String> {
+    let text = spoken_text(text);
+    if text.is_empty() {
+        return Ok(());
+    }
+    stop(app);
+
+    let mut child = tts_command(voice.map(str::trim).filter(|v| !v.is_empty()))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Text-to-speech is not available: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Dropping stdin afterwards signals the end of the text
+        if let Err(err) = stdin.write_all(text.as_bytes()) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Failed to send text to speech engine: {}", err));
+        }
+    }
+    *app.state::<Speech>().0.lock().unwrap() = Some(child);
+    let _ = app.emit(
+        "speech:started",
+        SpeechEvent {
+            source,
+            chars: text.chars().count(),
+        },
+    );
+    Ok(())
+}
+
+/// Reads a completed reply aloud when auto-read is on. Failures are only logged.
+pub(crate) fn after_response(app: &tauri::AppHandle, reply: &str) {
+    let settings = load_json::<SpeechSettings>(app, SPEECH_FILE).unwrap_or_default();
+    if !settings.auto_read {
+        return;
+    }
+    if let Err(err) = speak(app, reply, settings.voice.as_deref(), "auto_read") {
+        eprintln!("[speech] auto-read failed: {}", err);
+    }
+}
+
+/// Speaks `text` (markdown is flattened) with `voice` or the configured default voice,
+/// interrupting anything already playing.
+#[tauri::command]
+pub fn speak_text(
+    app: tauri::AppHandle,
+    text: String,
+    voice: Option<String>,
+) -> Result<(), String> {
+    let voice = match voice {
+        Some(voice) => Some(voice),
+        None => load_json::<SpeechSettings>(&app, SPEECH_FILE)?.voice,
+    };
+    speak(&app, &text, voice.as_deref(), "manual")
+}
+
+/// Returns `true` if something was playing.
+#[tauri::command]
+pub fn stop_speaking(app: tauri::AppHandle) -> bool {
+    let stopped = stop(&app);
+    if stopped {
+        let _ = app.emit("speech:stopped", ());
+    }
+    stopped
+}
+
+#[tauri::command]
+pub fn get_speech_settings(app: tauri::AppHandle) -> Result<SpeechSettings, String> {
+    load_json::<SpeechSettings>(&app, SPEECH_FILE)
+}
+
+#[tauri::command]
+pub fn set_speech_settings(
+    app: tauri::AppHandle,
+    settings: SpeechSettings,
+) -> Result<SpeechSettings, String> {
+    let mut settings = settings;
+    settings.voice = settings
+        .voice
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    save_json(&app, SPEECH_FILE, &settings)?;
+    Ok(settings)
+}
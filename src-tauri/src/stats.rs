@@ -0,0 +1,501 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::workspace::{is_binary_file, relative_path, run_git, walk_files, workspace_root};
+
+const LARGEST_FILES_LIMIT: usize = 10;
+const TOP_AUTHORS_LIMIT: usize = 5;
+// Files above this size are counted but not read for line counts
+const MAX_LOC_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStats {
+    language: String,
+    files: u64,
+    lines: u64,
+    /// `lines` split into code, comments and blank lines
+    code: u64,
+    comments: u64,
+    blanks: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionStats {
+    /// Lowercase, without the dot; empty for files without one
+    extension: String,
+    files: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSize {
+    path: String,
+    bytes: u64,
+    lines: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorActivity {
+    name: String,
+    commits: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitActivity {
+    branch: Option<String>,
+    total_commits: u64,
+    commits_last_30_days: u64,
+    last_commit_at: Option<i64>,
+    last_commit_summary: Option<String>,
+    top_authors: Vec<AuthorActivity>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStats {
+    root: String,
+    total_files: u64,
+    total_lines: u64,
+    total_bytes: u64,
+    languages: Vec<LanguageStats>,
+    /// Every file counted by extension, most bytes first
+    extensions: Vec<ExtensionStats>,
+    largest_files: Vec<FileSize>,
+    git: Option<GitActivity>,
+    /// Markdown digest of the stats, suitable as grounding context for the agent
+    summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageReport {
+    root: String,
+    total_files: u64,
+    total_lines: u64,
+    total_code: u64,
+    languages: Vec<LanguageStats>,
+    /// Markdown table of the languages, suitable as context for the agent
+    summary: String,
+}
+
+/// How a language writes comments: line prefixes and block delimiters.
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct LineCounts {
+    lines: u64,
+    code: u64,
+    comments: u64,
+    blanks: u64,
+}
+
+fn comment_syntax(language: &str) -> CommentSyntax {
+    const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+    const HTML_BLOCK: Option<(&str, &str)> = Some(("<!--", "-->"));
+    match language {
+        "Rust" | "TypeScript" | "JavaScript" | "Go" | "Java" | "Kotlin" | "Swift" | "C" | "C++"
+        | "Objective-C" | "C#" | "Scala" | "Dart" => CommentSyntax {
+            line: &["//"],
+            block: C_BLOCK,
+        },
+        "PHP" => CommentSyntax {
+            line: &["//", "#"],
+            block: C_BLOCK,
+        },
+        "CSS" => CommentSyntax {
+            line: &["//"],
+            block: C_BLOCK,
+        },
+        "Python" | "Ruby" | "Shell" | "YAML" | "TOML" | "Elixir" => CommentSyntax {
+            line: &["#"],
+            block: None,
+        },
+        "SQL" => CommentSyntax {
+            line: &["--"],
+            block: C_BLOCK,
+        },
+        "Lua" => CommentSyntax {
+            line: &["--"],
+            block: Some(("--[[", "]]")),
+        },
+        "HTML" | "Markdown" | "Vue" | "Svelte" => CommentSyntax {
+            line: &[],
+            block: HTML_BLOCK,
+        },
+        _ => CommentSyntax {
+            line: &[],
+            block: None,
+        },
+    }
+}
+
+fn language_for(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let language = match ext.as_str() {
+        "rs" => "Rust",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "py" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "m" | "mm" => "Objective-C",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "sh" | "bash" | "zsh" => "Shell",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "json" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "md" | "markdown" => "Markdown",
+        "sql" => "SQL",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "lua" => "Lua",
+        "dart" => "Dart",
+        "scala" => "Scala",
+        "ex" | "exs" => "Elixir",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Sorts each line of `text` into code, comment or blank. A line with any code on it
+/// counts as code.
+fn classify_lines(text: &str, syntax: &CommentSyntax) -> LineCounts {
+    let mut counts = LineCounts::default();
+    let mut in_block = false;
+    for line in text.lines() {
+        counts.lines += 1;
+        let line = line.trim();
+        if in_block {
+            counts.comments += 1;
+            if let Some((_, end)) = syntax.block {
+                in_block = !line.contains(end);
+            }
+            continue;
+        }
+        if line.is_empty() {
+            counts.blanks += 1;
+            continue;
+        }
+        if syntax.line.iter().any(|prefix| line.starts_with(prefix)) {
+            counts.comments += 1;
+            continue;
+        }
+        match syntax.block {
+            Some((start, end)) if line.starts_with(start) => {
+                counts.comments += 1;
+                in_block = !line[start.len()..].contains(end);
+            }
+            Some((start, end)) => {
+                counts.code += 1;
+                // A block opened after code on the same line runs on to the next ones
+                if let Some(at) = line.find(start) {
+                    in_block = !line[at + start.len()..].contains(end);
+                }
+            }
+            None => counts.code += 1,
+        }
+    }
+    counts
+}
+
+fn count_lines(path: &Path, language: Option<&str>) -> LineCounts {
+    let Ok(bytes) = std::fs::read(path) else {
+        return LineCounts::default();
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    match language {
+        Some(language) => classify_lines(&text, &comment_syntax(language)),
+        None => LineCounts {
+            lines: text.lines().count() as u64,
+            ..LineCounts::default()
+        },
+    }
+}
+
+/// Line counts for a file, or zero for binaries and files too big to read.
+fn file_lines(path: &Path, bytes: u64, language: Option<&str>) -> LineCounts {
+    if bytes <= MAX_LOC_FILE_BYTES && !is_binary_file(path) {
+        count_lines(path, language)
+    } else {
+        LineCounts::default()
+    }
+}
+
+fn add_language(
+    languages: &mut HashMap<&'static str, LanguageStats>,
+    language: &'static str,
+    counts: LineCounts,
+    bytes: u64,
+) {
+    let entry = languages.entry(language).or_insert_with(|| LanguageStats {
+        language: language.to_string(),
+        files: 0,
+        lines: 0,
+        code: 0,
+        comments: 0,
+        blanks: 0,
+        bytes: 0,
+    });
+    entry.files += 1;
+    entry.lines += counts.lines;
+    entry.code += counts.code;
+    entry.comments += counts.comments;
+    entry.blanks += counts.blanks;
+    entry.bytes += bytes;
+}
+
+fn sorted_languages(languages: HashMap<&'static str, LanguageStats>) -> Vec<LanguageStats> {
+    let mut languages: Vec<LanguageStats> = languages.into_values().collect();
+    languages.sort_by(|a, b| b.lines.cmp(&a.lines).then(a.language.cmp(&b.language)));
+    languages
+}
+
+fn collect_git_activity(root: &Path) -> Option<GitActivity> {
+    // Bail out early for non-repositories (and when git is not installed)
+    run_git(root, &["rev-parse", "--is-inside-work-tree"])?;
+
+    let branch = run_git(root, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
+    let log = run_git(root, &["log", "--format=%at%x09%an%x09%s"]).unwrap_or_default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let month_ago = now - 30 * 24 * 60 * 60;
+
+    let mut total_commits = 0;
+    let mut commits_last_30_days = 0;
+    let mut last_commit_at = None;
+    let mut last_commit_summary = None;
+    let mut authors: HashMap<String, u64> = HashMap::new();
+
+    for line in log.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let timestamp = parts.next().and_then(|t| t.parse::<i64>().ok()).unwrap_or(0);
+        let author = parts.next().unwrap_or("").to_string();
+        let summary = parts.next().unwrap_or("").to_string();
+
+        if total_commits == 0 {
+            last_commit_at = Some(timestamp);
+            last_commit_summary = Some(summary);
+        }
+        total_commits += 1;
+        if timestamp >= month_ago {
+            commits_last_30_days += 1;
+        }
+        *authors.entry(author).or_insert(0) += 1;
+    }
+
+    let mut top_authors: Vec<AuthorActivity> = authors
+        .into_iter()
+        .map(|(name, commits)| AuthorActivity { name, commits })
+        .collect();
+    top_authors.sort_by(|a, b| b.commits.cmp(&a.commits).then(a.name.cmp(&b.name)));
+    top_authors.truncate(TOP_AUTHORS_LIMIT);
+
+    Some(GitActivity {
+        branch,
+        total_commits,
+        commits_last_30_days,
+        last_commit_at,
+        last_commit_summary,
+        top_authors,
+    })
+}
+
+fn summarize(stats: &WorkspaceStats) -> String {
+    let mut out = format!(
+        "## Workspace overview\n- Files: {}\n- Lines of code: {}\n- Size: {} bytes\n",
+        stats.total_files, stats.total_lines, stats.total_bytes
+    );
+
+    if !stats.languages.is_empty() {
+        out.push_str("\n### Languages\n");
+        for lang in stats.languages.iter().take(8) {
+            out.push_str(&format!(
+                "- {}: {} files, {} lines\n",
+                lang.language, lang.files, lang.lines
+            ));
+        }
+    }
+
+    if !stats.largest_files.is_empty() {
+        out.push_str("\n### Largest files\n");
+        for file in &stats.largest_files {
+            out.push_str(&format!("- {} ({} bytes)\n", file.path, file.bytes));
+        }
+    }
+
+    if let Some(git) = &stats.git {
+        out.push_str("\n### Git activity\n");
+        if let Some(branch) = &git.branch {
+            out.push_str(&format!("- Branch: {}\n", branch));
+        }
+        out.push_str(&format!(
+            "- Commits: {} total, {} in the last 30 days\n",
+            git.total_commits, git.commits_last_30_days
+        ));
+        if let Some(summary) = &git.last_commit_summary {
+            out.push_str(&format!("- Last commit: {}\n", summary));
+        }
+        if !git.top_authors.is_empty() {
+            let authors: Vec<String> = git
+                .top_authors
+                .iter()
+                .map(|a| format!("{} ({})", a.name, a.commits))
+                .collect();
+            out.push_str(&format!("- Top authors: {}\n", authors.join(", ")));
+        }
+    }
+
+    out
+}
+
+pub(crate) fn compute_workspace_stats(workspace: &str) -> Result<WorkspaceStats, String> {
+    let root = workspace_root(workspace)?;
+
+    let mut total_files = 0;
+    let mut total_lines = 0;
+    let mut total_bytes = 0;
+    let mut languages: HashMap<&'static str, LanguageStats> = HashMap::new();
+    let mut extensions: HashMap<String, ExtensionStats> = HashMap::new();
+    let mut files: Vec<FileSize> = Vec::new();
+
+    for path in walk_files(&root) {
+        let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let language = language_for(&path);
+        let counts = file_lines(&path, bytes, language);
+
+        total_files += 1;
+        total_bytes += bytes;
+
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let entry = extensions
+            .entry(extension)
+            .or_insert_with_key(|extension| ExtensionStats {
+                extension: extension.clone(),
+                files: 0,
+                bytes: 0,
+            });
+        entry.files += 1;
+        entry.bytes += bytes;
+
+        if let Some(language) = language {
+            total_lines += counts.lines;
+            add_language(&mut languages, language, counts, bytes);
+        }
+
+        files.push(FileSize {
+            path: relative_path(&root, &path),
+            bytes,
+            lines: counts.lines,
+        });
+    }
+
+    let languages = sorted_languages(languages);
+
+    let mut extensions: Vec<ExtensionStats> = extensions.into_values().collect();
+    extensions.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.extension.cmp(&b.extension)));
+
+    files.sort_by_key(|f| std::cmp::Reverse(f.bytes));
+    files.truncate(LARGEST_FILES_LIMIT);
+
+    let mut stats = WorkspaceStats {
+        root: root.to_string_lossy().into_owned(),
+        total_files,
+        total_lines,
+        total_bytes,
+        languages,
+        extensions,
+        largest_files: files,
+        git: collect_git_activity(&root),
+        summary: String::new(),
+    };
+    stats.summary = summarize(&stats);
+    Ok(stats)
+}
+
+/// File count, size, languages, extensions, largest files and git activity for
+/// `workspace`, skipping what the file tree skips.
+#[tauri::command]
+pub async fn get_workspace_stats(workspace: String) -> Result<WorkspaceStats, String> {
+    tauri::async_runtime::spawn_blocking(move || compute_workspace_stats(&workspace))
+        .await
+        .map_err(|e| format!("Stats task failed: {}", e))?
+}
+
+fn summarize_languages(report: &LanguageReport) -> String {
+    let mut out = format!(
+        "## Languages\n{} files, {} lines of which {} are code.\n\n\
+         | Language | Files | Code | Comments | Blanks |\n|---|---|---|---|---|\n",
+        report.total_files, report.total_lines, report.total_code
+    );
+    for lang in &report.languages {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            lang.language, lang.files, lang.code, lang.comments, lang.blanks
+        ));
+    }
+    out
+}
+
+pub(crate) fn compute_language_stats(path: &str) -> Result<LanguageReport, String> {
+    let root = workspace_root(path)?;
+    let mut languages: HashMap<&'static str, LanguageStats> = HashMap::new();
+    for file in walk_files(&root) {
+        let Some(language) = language_for(&file) else {
+            continue;
+        };
+        let bytes = std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+        add_language(
+            &mut languages,
+            language,
+            file_lines(&file, bytes, Some(language)),
+            bytes,
+        );
+    }
+    let languages = sorted_languages(languages);
+    let mut report = LanguageReport {
+        root: root.to_string_lossy().into_owned(),
+        total_files: languages.iter().map(|l| l.files).sum(),
+        total_lines: languages.iter().map(|l| l.lines).sum(),
+        total_code: languages.iter().map(|l| l.code).sum(),
+        languages,
+        summary: String::new(),
+    };
+    report.summary = summarize_languages(&report);
+    Ok(report)
+}
+
+/// Lines of code, comments and blanks per language under `path`, a workspace or any
+/// folder in it, without the git and file-size work of `get_workspace_stats`.
+#[tauri::command]
+pub async fn get_language_stats(path: String) -> Result<LanguageReport, String> {
+    tauri::async_runtime::spawn_blocking(move || compute_language_stats(&path))
+        .await
+        .map_err(|e| format!("Stats task failed: {}", e))?
+}
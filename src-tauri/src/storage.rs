@@ -0,0 +1,1687 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::app_data::app_data_path;
+use crate::clock::now_millis;
+
+const DATABASE_FILE: &str = "conversations.db";
+const TITLE_MAX_CHARS: usize = 80;
+const PREVIEW_MAX_CHARS: usize = 160;
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_TAGS: usize = 20;
+const TAG_MAX_CHARS: usize = 40;
+// Separates tags in `group_concat`; can't appear in a tag since control characters are stripped
+const TAG_SEPARATOR: char = '\u{1f}';
+const MAX_PAGE_SIZE: u32 = 200;
+
+const SCHEMA: &str = "
+PRAGMA journal_mode = WAL;
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    request_id TEXT,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_messages_conversation
+    ON messages(conversation_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_conversations_updated
+    ON conversations(updated_at DESC);
+
+CREATE TABLE IF NOT EXISTS message_usage (
+    message_id TEXT PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    conversation_id TEXT NOT NULL,
+    model TEXT NOT NULL,
+    prompt_tokens INTEGER NOT NULL,
+    completion_tokens INTEGER NOT NULL,
+    cost_usd REAL,
+    estimated INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_created ON message_usage(created_at);
+
+CREATE TABLE IF NOT EXISTS conversation_reads (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations(id) ON DELETE CASCADE,
+    last_read_at INTEGER NOT NULL
+);
+";
+
+/// Schema changes applied in order on top of `SCHEMA`; `PRAGMA user_version` records
+/// how many have run.
+const MIGRATIONS: &[&str] = &[
+    // Branching: each message points at the one it follows, and each conversation
+    // remembers the leaf of the path being viewed. Existing history becomes one chain.
+    "ALTER TABLE messages ADD COLUMN parent_id TEXT REFERENCES messages(id) ON DELETE CASCADE;
+     ALTER TABLE conversations ADD COLUMN active_leaf_id TEXT;
+     UPDATE messages SET parent_id = (
+         SELECT p.id FROM messages p
+         WHERE p.conversation_id = messages.conversation_id
+           AND (p.created_at, p.rowid) < (messages.created_at, messages.rowid)
+         ORDER BY p.created_at DESC, p.rowid DESC LIMIT 1
+     );
+     UPDATE conversations SET active_leaf_id = (
+         SELECT m.id FROM messages m WHERE m.conversation_id = conversations.id
+         ORDER BY m.created_at DESC, m.rowid DESC LIMIT 1
+     );
+     CREATE INDEX IF NOT EXISTS idx_messages_parent ON messages(parent_id);",
+    // Pin and archive: timestamps rather than booleans so pinned threads keep pin order.
+    "ALTER TABLE conversations ADD COLUMN pinned_at INTEGER;
+     ALTER TABLE conversations ADD COLUMN archived_at INTEGER;",
+    // Per-conversation model overrides; NULL falls back to the app-wide settings.
+    "ALTER TABLE conversations ADD COLUMN provider_override TEXT;
+     ALTER TABLE conversations ADD COLUMN model_override TEXT;
+     ALTER TABLE conversations ADD COLUMN base_url_override TEXT;",
+    // Unsent composer text. Not tied to `conversations`: a new chat has no row yet.
+    "CREATE TABLE IF NOT EXISTS drafts (
+         conversation_id TEXT PRIMARY KEY,
+         content TEXT NOT NULL,
+         updated_at INTEGER NOT NULL
+     );",
+    "CREATE TABLE IF NOT EXISTS conversation_tags (
+         conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+         tag TEXT NOT NULL COLLATE NOCASE,
+         PRIMARY KEY (conversation_id, tag)
+     );
+     CREATE INDEX IF NOT EXISTS idx_conversation_tags_tag ON conversation_tags(tag);",
+    // Provider per usage row for reports; NULL for usage recorded before this existed.
+    "ALTER TABLE message_usage ADD COLUMN provider TEXT;",
+    // Plan mode per conversation; like drafts, a new chat has no row yet. Act mode is
+    // the absence of a row.
+    "CREATE TABLE IF NOT EXISTS conversation_modes (
+         conversation_id TEXT PRIMARY KEY,
+         mode TEXT NOT NULL,
+         updated_at INTEGER NOT NULL
+     );",
+    // Notes the agent keeps per workspace, keyed by canonical workspace root
+    "CREATE TABLE IF NOT EXISTS workspace_memories (
+         workspace TEXT NOT NULL,
+         key TEXT NOT NULL,
+         value TEXT NOT NULL,
+         created_at INTEGER NOT NULL,
+         updated_at INTEGER NOT NULL,
+         PRIMARY KEY (workspace, key)
+     );",
+];
+
+/// Connection to the conversation database in the app data dir.
+pub(crate) struct Storage(Mutex<Connection>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredMessage {
+    pub id: String,
+    pub conversation_id: String,
+    pub role: String,
+    pub content: String,
+    pub request_id: Option<String>,
+    pub created_at: i64,
+    /// The message this one follows; `None` for the first message of a conversation
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub id: String,
+    pub title: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Messages along the active branch, oldest first
+    pub messages: Vec<StoredMessage>,
+}
+
+/// One alternative continuation after a message.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Branch {
+    message_id: String,
+    role: String,
+    preview: String,
+    created_at: i64,
+    /// Whether this branch is on the conversation's active path
+    active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    id: String,
+    title: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    message_count: i64,
+    last_message_preview: Option<String>,
+    pinned: bool,
+    archived: bool,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    tag: String,
+    conversations: i64,
+}
+
+/// Model settings one conversation uses instead of the app-wide defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSettings {
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// Whether the agent may change anything. In plan mode it can only look around and
+/// propose what it would do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversationMode {
+    Plan,
+    #[default]
+    Act,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryEntry {
+    pub(crate) key: String,
+    pub(crate) value: String,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    conversation_id: String,
+    text: String,
+    updated_at: i64,
+}
+
+/// Which archived conversations a listing includes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFilter {
+    /// Hide archived conversations (the sidebar default)
+    #[default]
+    Exclude,
+    Include,
+    Only,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationFilter {
+    #[serde(default)]
+    archived: ArchiveFilter,
+    #[serde(default)]
+    pinned_only: bool,
+    /// Only conversations carrying this tag (case-insensitive)
+    #[serde(default)]
+    tag: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationPage {
+    conversations: Vec<ConversationSummary>,
+    total: i64,
+    /// Offset of the next page, absent on the last page
+    next_offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadConversation {
+    id: String,
+    title: Option<String>,
+    /// Assistant messages stored after the conversation was last read
+    unread: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadSummary {
+    /// Unread messages across all conversations; drives the dock badge
+    pub total: i64,
+    conversations: Vec<UnreadConversation>,
+}
+
+/// A conversation parsed from another app's export, ready to be inserted as-is.
+pub(crate) struct ImportedConversation {
+    pub id: String,
+    pub title: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub messages: Vec<ImportedMessage>,
+}
+
+pub(crate) struct ImportedMessage {
+    pub role: String,
+    pub content: String,
+    pub created_at: i64,
+}
+
+/// One usage row, for reports aggregated outside SQL.
+pub(crate) struct UsageRow {
+    pub provider: Option<String>,
+    pub model: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost_usd: Option<f64>,
+    pub created_at: i64,
+}
+
+/// Token counts attached to one stored assistant message.
+pub(crate) struct MessageUsage<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost_usd: Option<f64>,
+    /// Counted locally because the provider reported no usage
+    pub estimated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    /// Conversation id or model name, depending on the breakdown
+    key: String,
+    title: Option<String>,
+    messages: i64,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    cost_usd: f64,
+    /// Messages whose model had no known price
+    unpriced_messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    since: Option<i64>,
+    total: UsageTotals,
+    by_conversation: Vec<UsageTotals>,
+    by_model: Vec<UsageTotals>,
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+fn title_from(content: &str) -> Option<String> {
+    let line = content.lines().map(str::trim).find(|l| !l.is_empty())?;
+    Some(line.chars().take(TITLE_MAX_CHARS).collect())
+}
+
+fn memory_from_row(row: &rusqlite::Row) -> rusqlite::Result<MemoryEntry> {
+    Ok(MemoryEntry {
+        key: row.get(0)?,
+        value: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+    })
+}
+
+fn migrate(conn: &Connection) -> Result<(), String> {
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", params![], |row| row.get(0))
+        .map_err(db_error)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version.max(0) as usize) {
+        conn.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            migration,
+            index + 1
+        ))
+        .map_err(|e| format!("Database migration {} failed: {}", index + 1, e))?;
+    }
+    Ok(())
+}
+
+/// Last message of the conversation's active path: the stored leaf, or the newest
+/// message for conversations that never recorded one.
+fn active_leaf(conn: &Connection, conversation_id: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT COALESCE(c.active_leaf_id,
+                         (SELECT m.id FROM messages m WHERE m.conversation_id = c.id
+                          ORDER BY m.created_at DESC, m.rowid DESC LIMIT 1))
+         FROM conversations c WHERE c.id = ?1",
+        params![conversation_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(Option::flatten)
+}
+
+/// Messages from the root down to `leaf`, following parent links.
+fn path_to(
+    conn: &Connection,
+    conversation_id: &str,
+    leaf: &str,
+) -> rusqlite::Result<Vec<StoredMessage>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE path(id, depth) AS (
+             SELECT ?2, 0
+             UNION ALL
+             SELECT m.parent_id, path.depth + 1 FROM messages m
+             JOIN path ON m.id = path.id
+             WHERE m.parent_id IS NOT NULL
+         )
+         SELECT m.id, m.role, m.content, m.request_id, m.created_at, m.parent_id
+         FROM path JOIN messages m ON m.id = path.id
+         WHERE m.conversation_id = ?1
+         ORDER BY path.depth DESC",
+    )?;
+    let messages = stmt
+        .query_map(params![conversation_id, leaf], |row| {
+            Ok(StoredMessage {
+                id: row.get(0)?,
+                conversation_id: conversation_id.to_string(),
+                role: row.get(1)?,
+                content: row.get(2)?,
+                request_id: row.get(3)?,
+                created_at: row.get(4)?,
+                parent_id: row.get(5)?,
+            })
+        })?
+        .collect();
+    messages
+}
+
+fn newest_child(conn: &Connection, message_id: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM messages WHERE parent_id = ?1
+         ORDER BY created_at DESC, rowid DESC LIMIT 1",
+        params![message_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+fn split_tags(joined: Option<String>) -> Vec<String> {
+    let mut tags: Vec<String> = joined
+        .as_deref()
+        .unwrap_or_default()
+        .split(TAG_SEPARATOR)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
+    tags.sort_by_key(|t| t.to_lowercase());
+    tags
+}
+
+/// Trims tags, strips control characters and drops duplicates, keeping the first spelling.
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag: String = tag.chars().filter(|c| !c.is_control()).collect();
+        let tag = tag.trim();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > TAG_MAX_CHARS {
+            return Err(format!("Tags are limited to {} characters", TAG_MAX_CHARS));
+        }
+        if !normalized.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("A conversation can have at most {} tags", MAX_TAGS));
+    }
+    Ok(normalized)
+}
+
+/// Opens (creating if needed) the database and applies the schema.
+pub(crate) fn open(app: &tauri::AppHandle) -> Result<Storage, String> {
+    let path = app_data_path(app, DATABASE_FILE)?;
+    let conn =
+        Connection::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    init(conn)
+}
+
+/// Applies the schema and any pending migrations to an open connection.
+fn init(conn: Connection) -> Result<Storage, String> {
+    let has_reads: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master
+                           WHERE type = 'table' AND name = 'conversation_reads')",
+            params![],
+            |row| row.get(0),
+        )
+        .map_err(db_error)?;
+    conn.execute_batch(SCHEMA).map_err(db_error)?;
+    migrate(&conn)?;
+    if !has_reads {
+        // History from before read tracking existed counts as read
+        conn.execute(
+            "INSERT OR IGNORE INTO conversation_reads (conversation_id, last_read_at)
+             SELECT id, updated_at FROM conversations",
+            params![],
+        )
+        .map_err(db_error)?;
+    }
+    Ok(Storage(Mutex::new(conn)))
+}
+
+impl Storage {
+    /// Appends a message after the active leaf, creating the conversation on first use.
+    /// The first user message becomes the conversation title, and sending one marks the
+    /// conversation read up to that point.
+    pub fn append_message(
+        &self,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+        request_id: Option<&str>,
+    ) -> Result<StoredMessage, String> {
+        let mut conn = self.0.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let now = now_millis();
+        let title = if role == "user" {
+            title_from(content)
+        } else {
+            None
+        };
+
+        tx.execute(
+            "INSERT INTO conversations (id, title, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(id) DO UPDATE SET
+                 title = COALESCE(conversations.title, excluded.title),
+                 updated_at = excluded.updated_at",
+            params![conversation_id, title, now],
+        )
+        .map_err(db_error)?;
+
+        let message = StoredMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conversation_id.to_string(),
+            role: role.to_string(),
+            content: content.to_string(),
+            request_id: request_id.map(String::from),
+            created_at: now,
+            parent_id: active_leaf(&tx, conversation_id).map_err(db_error)?,
+        };
+        tx.execute(
+            "INSERT INTO messages
+                 (id, conversation_id, role, content, request_id, created_at, parent_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                message.id,
+                message.conversation_id,
+                message.role,
+                message.content,
+                message.request_id,
+                message.created_at,
+                message.parent_id
+            ],
+        )
+        .map_err(db_error)?;
+        tx.execute(
+            "UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1",
+            params![conversation_id, message.id],
+        )
+        .map_err(db_error)?;
+
+        if role == "user" {
+            tx.execute(
+                "INSERT INTO conversation_reads (conversation_id, last_read_at) VALUES (?1, ?2)
+                 ON CONFLICT(conversation_id) DO UPDATE SET last_read_at = excluded.last_read_at",
+                params![conversation_id, now],
+            )
+            .map_err(db_error)?;
+            // The prompt was the draft; anything typed after sending is a new one
+            tx.execute(
+                "DELETE FROM drafts WHERE conversation_id = ?1 AND updated_at <= ?2",
+                params![conversation_id, now],
+            )
+            .map_err(db_error)?;
+        }
+
+        tx.commit().map_err(db_error)?;
+        Ok(message)
+    }
+
+    pub fn conversation(&self, id: &str) -> Result<Option<Conversation>, String> {
+        let conn = self.0.lock().unwrap();
+        let header = conn
+            .query_row(
+                "SELECT title, created_at, updated_at FROM conversations WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(db_error)?;
+        let Some((title, created_at, updated_at)) = header else {
+            return Ok(None);
+        };
+
+        let messages = match active_leaf(&conn, id).map_err(db_error)? {
+            Some(leaf) => path_to(&conn, id, &leaf).map_err(db_error)?,
+            None => Vec::new(),
+        };
+
+        Ok(Some(Conversation {
+            id: id.to_string(),
+            title,
+            created_at,
+            updated_at,
+            messages,
+        }))
+    }
+
+    /// Conversations matching `filter`, pinned first (most recently pinned on top), then
+    /// by most recent activity.
+    pub fn list_conversations(
+        &self,
+        filter: &ConversationFilter,
+        offset: u32,
+        limit: u32,
+    ) -> Result<ConversationPage, String> {
+        let mut conditions = vec![match filter.archived {
+            ArchiveFilter::Exclude => "c.archived_at IS NULL",
+            ArchiveFilter::Include => "1",
+            ArchiveFilter::Only => "c.archived_at IS NOT NULL",
+        }];
+        if filter.pinned_only {
+            conditions.push("c.pinned_at IS NOT NULL");
+        }
+        let condition = conditions.join(" AND ");
+        // The tag is bound as ?1 when counting and ?3 when listing, after limit and offset
+        let matching = |tag_param: &str| {
+            format!(
+                "{} AND ({p} IS NULL OR EXISTS(
+                     SELECT 1 FROM conversation_tags t
+                     WHERE t.conversation_id = c.id AND t.tag = {p}))",
+                condition,
+                p = tag_param
+            )
+        };
+        let tag = filter
+            .tag
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+
+        let conn = self.0.lock().unwrap();
+        let total: i64 = conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM conversations c WHERE {}",
+                    matching("?1")
+                ),
+                params![tag],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT c.id, c.title, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id),
+                        (SELECT m.content FROM messages m
+                         WHERE m.id = COALESCE(c.active_leaf_id,
+                             (SELECT l.id FROM messages l WHERE l.conversation_id = c.id
+                              ORDER BY l.created_at DESC, l.rowid DESC LIMIT 1))),
+                        c.pinned_at IS NOT NULL, c.archived_at IS NOT NULL,
+                        (SELECT group_concat(t.tag, char(31)) FROM conversation_tags t
+                         WHERE t.conversation_id = c.id)
+                 FROM conversations c
+                 WHERE {}
+                 ORDER BY c.pinned_at IS NULL, c.pinned_at DESC, c.updated_at DESC, c.id
+                 LIMIT ?1 OFFSET ?2",
+                matching("?3")
+            ))
+            .map_err(db_error)?;
+        let conversations = stmt
+            .query_map(params![limit, offset, tag], |row| {
+                let last: Option<String> = row.get(5)?;
+                let tags: Option<String> = row.get(8)?;
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    message_count: row.get(4)?,
+                    last_message_preview: last
+                        .map(|content| content.chars().take(PREVIEW_MAX_CHARS).collect()),
+                    pinned: row.get(6)?,
+                    archived: row.get(7)?,
+                    tags: split_tags(tags),
+                })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+
+        let end = offset as i64 + conversations.len() as i64;
+        Ok(ConversationPage {
+            next_offset: (end < total).then_some(end as u32),
+            conversations,
+            total,
+        })
+    }
+
+    /// Inserts an imported conversation with its original timestamps. Returns `false`
+    /// without touching anything when a conversation with that id already exists.
+    pub fn import_conversation(&self, conversation: &ImportedConversation) -> Result<bool, String> {
+        let mut conn = self.0.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let inserted = tx
+            .execute(
+                "INSERT OR IGNORE INTO conversations (id, title, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    conversation.id,
+                    conversation.title,
+                    conversation.created_at,
+                    conversation.updated_at
+                ],
+            )
+            .map_err(db_error)?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let mut parent_id: Option<String> = None;
+        for message in &conversation.messages {
+            let id = uuid::Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO messages
+                     (id, conversation_id, role, content, request_id, created_at, parent_id)
+                 VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6)",
+                params![
+                    id,
+                    conversation.id,
+                    message.role,
+                    message.content,
+                    message.created_at,
+                    parent_id
+                ],
+            )
+            .map_err(db_error)?;
+            parent_id = Some(id);
+        }
+        tx.execute(
+            "UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1",
+            params![conversation.id, parent_id],
+        )
+        .map_err(db_error)?;
+        // Imported history was already read in the app it came from
+        tx.execute(
+            "INSERT OR REPLACE INTO conversation_reads (conversation_id, last_read_at)
+             VALUES (?1, ?2)",
+            params![conversation.id, conversation.updated_at],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        Ok(true)
+    }
+
+    /// Copies the first `upto` messages of a conversation into a new one, leaving the
+    /// original untouched. Returns `None` if the source doesn't exist.
+    pub fn fork_conversation(
+        &self,
+        source_id: &str,
+        upto: usize,
+    ) -> Result<Option<Conversation>, String> {
+        let Some(source) = self.conversation(source_id)? else {
+            return Ok(None);
+        };
+
+        let now = now_millis();
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut fork = Conversation {
+            id: id.clone(),
+            title: source.title,
+            created_at: now,
+            updated_at: now,
+            messages: Vec::with_capacity(upto),
+        };
+        for message in source.messages.into_iter().take(upto) {
+            let parent_id = fork.messages.last().map(|m| m.id.clone());
+            fork.messages.push(StoredMessage {
+                id: uuid::Uuid::new_v4().to_string(),
+                conversation_id: id.clone(),
+                parent_id,
+                ..message
+            });
+        }
+
+        let mut conn = self.0.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        tx.execute(
+            "INSERT INTO conversations
+                 (id, title, created_at, updated_at, active_leaf_id,
+                  provider_override, model_override, base_url_override)
+             SELECT ?1, ?2, ?3, ?3, ?4, provider_override, model_override, base_url_override
+             FROM conversations WHERE id = ?5",
+            params![
+                fork.id,
+                fork.title,
+                now,
+                fork.messages.last().map(|m| m.id.as_str()),
+                source_id
+            ],
+        )
+        .map_err(db_error)?;
+        for message in &fork.messages {
+            tx.execute(
+                "INSERT INTO messages
+                     (id, conversation_id, role, content, request_id, created_at, parent_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    message.id,
+                    fork.id,
+                    message.role,
+                    message.content,
+                    message.request_id,
+                    message.created_at,
+                    message.parent_id
+                ],
+            )
+            .map_err(db_error)?;
+        }
+        tx.execute(
+            "INSERT INTO conversation_reads (conversation_id, last_read_at) VALUES (?1, ?2)",
+            params![fork.id, now],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        Ok(Some(fork))
+    }
+
+    /// The last message on the active path, if the conversation has any.
+    pub fn active_message(&self, conversation_id: &str) -> Result<Option<StoredMessage>, String> {
+        let conn = self.0.lock().unwrap();
+        let Some(leaf) = active_leaf(&conn, conversation_id).map_err(db_error)? else {
+            return Ok(None);
+        };
+        conn.query_row(
+            "SELECT id, role, content, request_id, created_at, parent_id FROM messages
+             WHERE id = ?1 AND conversation_id = ?2",
+            params![leaf, conversation_id],
+            |row| {
+                Ok(StoredMessage {
+                    id: row.get(0)?,
+                    conversation_id: conversation_id.to_string(),
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    request_id: row.get(3)?,
+                    created_at: row.get(4)?,
+                    parent_id: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(db_error)
+    }
+
+    /// Alternative continuations after `parent_id` (the conversation's first messages
+    /// when `None`), oldest first.
+    pub fn branches(
+        &self,
+        conversation_id: &str,
+        parent_id: Option<&str>,
+    ) -> Result<Vec<Branch>, String> {
+        let conn = self.0.lock().unwrap();
+        let on_path: Vec<String> = match active_leaf(&conn, conversation_id).map_err(db_error)? {
+            Some(leaf) => path_to(&conn, conversation_id, &leaf)
+                .map_err(db_error)?
+                .into_iter()
+                .map(|m| m.id)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, role, content, created_at FROM messages
+                 WHERE conversation_id = ?1 AND parent_id IS ?2
+                 ORDER BY created_at, rowid",
+            )
+            .map_err(db_error)?;
+        let branches = stmt
+            .query_map(params![conversation_id, parent_id], |row| {
+                let id: String = row.get(0)?;
+                let content: String = row.get(2)?;
+                Ok(Branch {
+                    active: on_path.contains(&id),
+                    message_id: id,
+                    role: row.get(1)?,
+                    preview: content.chars().take(PREVIEW_MAX_CHARS).collect(),
+                    created_at: row.get(3)?,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error);
+        branches
+    }
+
+    /// Makes the path through `message_id` active. With `descend`, the path continues to
+    /// the newest leaf below it; otherwise it ends at the message, so the next message
+    /// appended starts a new branch there. Returns `false` for unknown messages.
+    pub fn switch_branch(
+        &self,
+        conversation_id: &str,
+        message_id: &str,
+        descend: bool,
+    ) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM messages WHERE id = ?1 AND conversation_id = ?2)",
+                params![message_id, conversation_id],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+        if !exists {
+            return Ok(false);
+        }
+
+        let mut leaf = message_id.to_string();
+        if descend {
+            while let Some(child) = newest_child(&conn, &leaf).map_err(db_error)? {
+                leaf = child;
+            }
+        }
+        conn.execute(
+            "UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1",
+            params![conversation_id, leaf],
+        )
+        .map_err(db_error)?;
+        Ok(true)
+    }
+
+    /// The model recorded in a message's usage, if any.
+    pub fn message_model(&self, message_id: &str) -> Result<Option<String>, String> {
+        let conn = self.0.lock().unwrap();
+        conn.query_row(
+            "SELECT model FROM message_usage WHERE message_id = ?1",
+            params![message_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_error)
+    }
+
+    pub fn record_usage(
+        &self,
+        message: &StoredMessage,
+        usage: &MessageUsage,
+    ) -> Result<(), String> {
+        let conn = self.0.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO message_usage
+                 (message_id, conversation_id, model, prompt_tokens, completion_tokens,
+                  cost_usd, estimated, created_at, provider)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                message.id,
+                message.conversation_id,
+                usage.model,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                usage.cost_usd,
+                usage.estimated,
+                message.created_at,
+                usage.provider
+            ],
+        )
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Token and cost totals for usage recorded at or after `since` (everything when `None`).
+    pub fn usage_summary(&self, since: Option<i64>) -> Result<UsageSummary, String> {
+        let conn = self.0.lock().unwrap();
+        let since_value = since.unwrap_or(i64::MIN);
+        let totals = |group: &str, join: &str, title: &str| -> Result<Vec<UsageTotals>, String> {
+            let sql = format!(
+                "SELECT {group}, {title}, COUNT(*), SUM(u.prompt_tokens), SUM(u.completion_tokens),
+                        COALESCE(SUM(u.cost_usd), 0), SUM(u.cost_usd IS NULL)
+                 FROM message_usage u {join}
+                 WHERE u.created_at >= ?1
+                 GROUP BY {group}
+                 ORDER BY COALESCE(SUM(u.cost_usd), 0) DESC, SUM(u.prompt_tokens) DESC"
+            );
+            let mut stmt = conn.prepare(&sql).map_err(db_error)?;
+            let rows = stmt
+                .query_map(params![since_value], |row| {
+                    Ok(UsageTotals {
+                        key: row.get(0)?,
+                        title: row.get(1)?,
+                        messages: row.get(2)?,
+                        prompt_tokens: row.get(3)?,
+                        completion_tokens: row.get(4)?,
+                        cost_usd: row.get(5)?,
+                        unpriced_messages: row.get(6)?,
+                    })
+                })
+                .map_err(db_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(db_error);
+            rows
+        };
+
+        let by_conversation = totals(
+            "u.conversation_id",
+            "LEFT JOIN conversations c ON c.id = u.conversation_id",
+            "c.title",
+        )?;
+        let by_model = totals("u.model", "", "NULL")?;
+
+        let mut total = UsageTotals {
+            key: "total".to_string(),
+            title: None,
+            messages: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost_usd: 0.0,
+            unpriced_messages: 0,
+        };
+        for row in &by_model {
+            total.messages += row.messages;
+            total.prompt_tokens += row.prompt_tokens;
+            total.completion_tokens += row.completion_tokens;
+            total.cost_usd += row.cost_usd;
+            total.unpriced_messages += row.unpriced_messages;
+        }
+
+        Ok(UsageSummary {
+            since,
+            total,
+            by_conversation,
+            by_model,
+        })
+    }
+
+    /// Every usage row recorded at or after `since`, oldest first.
+    pub fn usage_rows(&self, since: Option<i64>) -> Result<Vec<UsageRow>, String> {
+        let conn = self.0.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT provider, model, prompt_tokens, completion_tokens, cost_usd, created_at
+                 FROM message_usage WHERE created_at >= ?1 ORDER BY created_at",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![since.unwrap_or(i64::MIN)], |row| {
+                Ok(UsageRow {
+                    provider: row.get(0)?,
+                    model: row.get(1)?,
+                    prompt_tokens: row.get(2)?,
+                    completion_tokens: row.get(3)?,
+                    cost_usd: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error);
+        rows
+    }
+
+    /// Marks everything stored up to `at` as read. Returns `false` for unknown conversations.
+    pub fn mark_read(&self, conversation_id: &str, at: i64) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let updated = conn
+            .execute(
+                "INSERT INTO conversation_reads (conversation_id, last_read_at)
+                 SELECT id, ?2 FROM conversations WHERE id = ?1
+                 ON CONFLICT(conversation_id) DO UPDATE
+                     SET last_read_at = MAX(last_read_at, excluded.last_read_at)",
+                params![conversation_id, at],
+            )
+            .map_err(db_error)?;
+        Ok(updated > 0)
+    }
+
+    /// Conversations with unread assistant messages, most recently active first.
+    pub fn unread_summary(&self) -> Result<UnreadSummary, String> {
+        let conn = self.0.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.title, COUNT(*)
+                 FROM messages m
+                 JOIN conversations c ON c.id = m.conversation_id
+                 LEFT JOIN conversation_reads r ON r.conversation_id = c.id
+                 WHERE m.role = 'assistant' AND m.created_at > COALESCE(r.last_read_at, 0)
+                   AND c.archived_at IS NULL
+                 GROUP BY c.id
+                 ORDER BY c.updated_at DESC, c.id",
+            )
+            .map_err(db_error)?;
+        let conversations = stmt
+            .query_map(params![], |row| {
+                Ok(UnreadConversation {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    unread: row.get(2)?,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+
+        Ok(UnreadSummary {
+            total: conversations.iter().map(|c| c.unread).sum(),
+            conversations,
+        })
+    }
+
+    pub fn set_title(&self, conversation_id: &str, title: &str) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE conversations SET title = ?2 WHERE id = ?1",
+                params![conversation_id, title],
+            )
+            .map_err(db_error)?;
+        Ok(updated > 0)
+    }
+
+    /// Saves the composer text for a conversation; empty text clears the draft.
+    pub fn save_draft(&self, conversation_id: &str, text: &str) -> Result<Option<Draft>, String> {
+        let conn = self.0.lock().unwrap();
+        if text.trim().is_empty() {
+            conn.execute(
+                "DELETE FROM drafts WHERE conversation_id = ?1",
+                params![conversation_id],
+            )
+            .map_err(db_error)?;
+            return Ok(None);
+        }
+        let now = now_millis();
+        conn.execute(
+            "INSERT INTO drafts (conversation_id, content, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(conversation_id) DO UPDATE SET
+                 content = excluded.content,
+                 updated_at = excluded.updated_at",
+            params![conversation_id, text, now],
+        )
+        .map_err(db_error)?;
+        Ok(Some(Draft {
+            conversation_id: conversation_id.to_string(),
+            text: text.to_string(),
+            updated_at: now,
+        }))
+    }
+
+    pub fn draft(&self, conversation_id: &str) -> Result<Option<Draft>, String> {
+        let conn = self.0.lock().unwrap();
+        conn.query_row(
+            "SELECT content, updated_at FROM drafts WHERE conversation_id = ?1",
+            params![conversation_id],
+            |row| {
+                Ok(Draft {
+                    conversation_id: conversation_id.to_string(),
+                    text: row.get(0)?,
+                    updated_at: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(db_error)
+    }
+
+    pub fn conversation_settings(
+        &self,
+        conversation_id: &str,
+    ) -> Result<ConversationSettings, String> {
+        let conn = self.0.lock().unwrap();
+        let settings = conn
+            .query_row(
+                "SELECT provider_override, model_override, base_url_override
+                 FROM conversations WHERE id = ?1",
+                params![conversation_id],
+                |row| {
+                    Ok(ConversationSettings {
+                        provider: row.get(0)?,
+                        model: row.get(1)?,
+                        base_url: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(db_error)?;
+        Ok(settings.unwrap_or_default())
+    }
+
+    /// Stores the overrides; blank values clear one. Returns `false` for unknown ids.
+    pub fn set_conversation_settings(
+        &self,
+        conversation_id: &str,
+        settings: &ConversationSettings,
+    ) -> Result<bool, String> {
+        let clean = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+        };
+        let conn = self.0.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE conversations
+                 SET provider_override = ?2, model_override = ?3, base_url_override = ?4
+                 WHERE id = ?1",
+                params![
+                    conversation_id,
+                    clean(&settings.provider),
+                    clean(&settings.model),
+                    clean(&settings.base_url)
+                ],
+            )
+            .map_err(db_error)?;
+        Ok(updated > 0)
+    }
+
+    pub fn conversation_mode(&self, conversation_id: &str) -> Result<ConversationMode, String> {
+        let conn = self.0.lock().unwrap();
+        let mode: Option<String> = conn
+            .query_row(
+                "SELECT mode FROM conversation_modes WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_error)?;
+        Ok(match mode.as_deref() {
+            Some("plan") => ConversationMode::Plan,
+            _ => ConversationMode::Act,
+        })
+    }
+
+    pub fn set_conversation_mode(
+        &self,
+        conversation_id: &str,
+        mode: ConversationMode,
+    ) -> Result<(), String> {
+        let conn = self.0.lock().unwrap();
+        match mode {
+            ConversationMode::Act => conn.execute(
+                "DELETE FROM conversation_modes WHERE conversation_id = ?1",
+                params![conversation_id],
+            ),
+            ConversationMode::Plan => conn.execute(
+                "INSERT INTO conversation_modes (conversation_id, mode, updated_at)
+                 VALUES (?1, 'plan', ?2)
+                 ON CONFLICT(conversation_id) DO UPDATE SET
+                     mode = excluded.mode,
+                     updated_at = excluded.updated_at",
+                params![conversation_id, now_millis()],
+            ),
+        }
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Saves `value` under `key` in the workspace's memory, replacing what was there.
+    pub fn set_memory(
+        &self,
+        workspace: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<MemoryEntry, String> {
+        let conn = self.0.lock().unwrap();
+        let now = now_millis();
+        conn.execute(
+            "INSERT INTO workspace_memories (workspace, key, value, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)
+             ON CONFLICT(workspace, key) DO UPDATE SET
+                 value = excluded.value,
+                 updated_at = excluded.updated_at",
+            params![workspace, key, value, now],
+        )
+        .map_err(db_error)?;
+        conn.query_row(
+            "SELECT key, value, created_at, updated_at FROM workspace_memories
+             WHERE workspace = ?1 AND key = ?2",
+            params![workspace, key],
+            memory_from_row,
+        )
+        .map_err(db_error)
+    }
+
+    /// Every entry of the workspace's memory, most recently updated first.
+    pub fn memories(&self, workspace: &str) -> Result<Vec<MemoryEntry>, String> {
+        let conn = self.0.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT key, value, created_at, updated_at FROM workspace_memories
+                 WHERE workspace = ?1 ORDER BY updated_at DESC, key",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map(params![workspace], memory_from_row)
+            .map_err(db_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_error)
+    }
+
+    pub fn memory(&self, workspace: &str, key: &str) -> Result<Option<MemoryEntry>, String> {
+        let conn = self.0.lock().unwrap();
+        conn.query_row(
+            "SELECT key, value, created_at, updated_at FROM workspace_memories
+             WHERE workspace = ?1 AND key = ?2",
+            params![workspace, key],
+            memory_from_row,
+        )
+        .optional()
+        .map_err(db_error)
+    }
+
+    pub fn delete_memory(&self, workspace: &str, key: &str) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let deleted = conn
+            .execute(
+                "DELETE FROM workspace_memories WHERE workspace = ?1 AND key = ?2",
+                params![workspace, key],
+            )
+            .map_err(db_error)?;
+        Ok(deleted > 0)
+    }
+
+    /// Replaces a conversation's tags. Returns `None` for unknown conversations.
+    pub fn set_tags(
+        &self,
+        conversation_id: &str,
+        tags: &[String],
+    ) -> Result<Option<Vec<String>>, String> {
+        let tags = normalize_tags(tags)?;
+        let mut conn = self.0.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?1)",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .map_err(db_error)?;
+        if !exists {
+            return Ok(None);
+        }
+        tx.execute(
+            "DELETE FROM conversation_tags WHERE conversation_id = ?1",
+            params![conversation_id],
+        )
+        .map_err(db_error)?;
+        for tag in &tags {
+            tx.execute(
+                "INSERT INTO conversation_tags (conversation_id, tag) VALUES (?1, ?2)",
+                params![conversation_id, tag],
+            )
+            .map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)?;
+        let mut tags = tags;
+        tags.sort_by_key(|t| t.to_lowercase());
+        Ok(Some(tags))
+    }
+
+    /// Every tag in use with how many conversations carry it, most used first.
+    pub fn tags(&self) -> Result<Vec<TagCount>, String> {
+        let conn = self.0.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT MIN(tag), COUNT(*) FROM conversation_tags
+                 GROUP BY tag ORDER BY COUNT(*) DESC, tag",
+            )
+            .map_err(db_error)?;
+        let tags = stmt
+            .query_map(params![], |row| {
+                Ok(TagCount {
+                    tag: row.get(0)?,
+                    conversations: row.get(1)?,
+                })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error);
+        tags
+    }
+
+    /// Pins or unpins a conversation. Pinning an already pinned one keeps its place.
+    pub fn set_pinned(&self, conversation_id: &str, pinned: bool) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE conversations
+                 SET pinned_at = CASE WHEN ?2 THEN COALESCE(pinned_at, ?3) END
+                 WHERE id = ?1",
+                params![conversation_id, pinned, now_millis()],
+            )
+            .map_err(db_error)?;
+        Ok(updated > 0)
+    }
+
+    pub fn set_archived(&self, conversation_id: &str, archived: bool) -> Result<bool, String> {
+        let conn = self.0.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE conversations
+                 SET archived_at = CASE WHEN ?2 THEN COALESCE(archived_at, ?3) END
+                 WHERE id = ?1",
+                params![conversation_id, archived, now_millis()],
+            )
+            .map_err(db_error)?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete_conversation(&self, id: &str) -> Result<bool, String> {
+        let mut conn = self.0.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let deleted = tx
+            .execute("DELETE FROM conversations WHERE id = ?1", params![id])
+            .map_err(db_error)?;
+        tx.execute("DELETE FROM drafts WHERE conversation_id = ?1", params![id])
+            .map_err(db_error)?;
+        tx.execute(
+            "DELETE FROM conversation_modes WHERE conversation_id = ?1",
+            params![id],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+#[tauri::command]
+pub fn save_message(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    role: String,
+    content: String,
+    request_id: Option<String>,
+) -> Result<StoredMessage, String> {
+    if conversation_id.trim().is_empty() {
+        return Err("Conversation id is required".to_string());
+    }
+    app.state::<Storage>()
+        .append_message(&conversation_id, &role, &content, request_id.as_deref())
+}
+
+#[tauri::command]
+pub fn get_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<Option<Conversation>, String> {
+    app.state::<Storage>().conversation(&conversation_id)
+}
+
+#[tauri::command]
+pub fn delete_conversation(app: tauri::AppHandle, conversation_id: String) -> Result<bool, String> {
+    let deleted = app
+        .state::<Storage>()
+        .delete_conversation(&conversation_id)?;
+    if deleted {
+        crate::unread::refresh_badge(&app);
+    }
+    Ok(deleted)
+}
+
+#[tauri::command]
+pub fn list_conversations(
+    app: tauri::AppHandle,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    filter: Option<ConversationFilter>,
+) -> Result<ConversationPage, String> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    app.state::<Storage>().list_conversations(
+        &filter.unwrap_or_default(),
+        offset.unwrap_or(0),
+        limit,
+    )
+}
+
+/// Called as the user types (debounced by the webview) so the composer survives a restart.
+#[tauri::command]
+pub fn save_draft(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    text: String,
+) -> Result<Option<Draft>, String> {
+    if conversation_id.trim().is_empty() {
+        return Err("Conversation id is required".to_string());
+    }
+    app.state::<Storage>().save_draft(&conversation_id, &text)
+}
+
+#[tauri::command]
+pub fn get_draft(app: tauri::AppHandle, conversation_id: String) -> Result<Option<Draft>, String> {
+    app.state::<Storage>().draft(&conversation_id)
+}
+
+#[tauri::command]
+pub fn get_conversation_settings(
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<ConversationSettings, String> {
+    app.state::<Storage>()
+        .conversation_settings(&conversation_id)
+}
+
+/// Overrides the provider, model or base URL for one conversation; `None` fields fall
+/// back to the app-wide settings.
+#[tauri::command]
+pub fn set_conversation_settings(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    settings: ConversationSettings,
+) -> Result<ConversationSettings, String> {
+    let storage = app.state::<Storage>();
+    if !storage.set_conversation_settings(&conversation_id, &settings)? {
+        return Err(format!("Conversation not found: {}", conversation_id));
+    }
+    storage.conversation_settings(&conversation_id)
+}
+
+#[tauri::command]
+pub fn get_conversation_mode(
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<ConversationMode, String> {
+    app.state::<Storage>().conversation_mode(&conversation_id)
+}
+
+/// Switches a conversation between plan and act mode. It takes effect with the next
+/// message; in plan mode the host refuses every tool call that writes or runs anything.
+#[tauri::command]
+pub fn set_conversation_mode(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    mode: ConversationMode,
+) -> Result<ConversationMode, String> {
+    let storage = app.state::<Storage>();
+    storage.set_conversation_mode(&conversation_id, mode)?;
+    storage.conversation_mode(&conversation_id)
+}
+
+/// Sets the conversation's tags to `tags`, returning them as stored.
+#[tauri::command]
+pub fn tag_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    app.state::<Storage>()
+        .set_tags(&conversation_id, &tags)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))
+}
+
+#[tauri::command]
+pub fn list_tags(app: tauri::AppHandle) -> Result<Vec<TagCount>, String> {
+    app.state::<Storage>().tags()
+}
+
+#[tauri::command]
+pub fn pin_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    pinned: bool,
+) -> Result<bool, String> {
+    app.state::<Storage>().set_pinned(&conversation_id, pinned)
+}
+
+/// Archived conversations are hidden from the default listing but keep their history.
+#[tauri::command]
+pub fn archive_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    archived: bool,
+) -> Result<bool, String> {
+    let updated = app
+        .state::<Storage>()
+        .set_archived(&conversation_id, archived)?;
+    if updated {
+        // Archived conversations don't count towards the unread badge
+        crate::unread::refresh_badge(&app);
+    }
+    Ok(updated)
+}
+
+/// Alternative messages after `parent_message_id`, or the first messages when omitted.
+#[tauri::command]
+pub fn list_branches(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    parent_message_id: Option<String>,
+) -> Result<Vec<Branch>, String> {
+    app.state::<Storage>()
+        .branches(&conversation_id, parent_message_id.as_deref())
+}
+
+/// Switches the active path to go through `message_id` and returns the conversation
+/// along it. Pass `descend: false` to branch from that message on the next send.
+#[tauri::command]
+pub fn switch_branch(
+    app: tauri::AppHandle,
+    conversation_id: String,
+    message_id: String,
+    descend: Option<bool>,
+) -> Result<Conversation, String> {
+    let storage = app.state::<Storage>();
+    if !storage.switch_branch(&conversation_id, &message_id, descend.unwrap_or(true))? {
+        return Err(format!("Message not found: {}", message_id));
+    }
+    storage
+        .conversation(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))
+}
+
+/// Full message history for the sidebar's selected conversation.
+#[tauri::command]
+pub fn load_conversation(
+    app: tauri::AppHandle,
+    conversation_id: String,
+) -> Result<Conversation, String> {
+    app.state::<Storage>()
+        .conversation(&conversation_id)?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{init, ConversationMode, Storage, MIGRATIONS, SCHEMA};
+    use rusqlite::{params, Connection};
+
+    fn storage() -> Storage {
+        init(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    fn path(storage: &Storage, conversation_id: &str) -> Vec<String> {
+        let conversation = storage.conversation(conversation_id).unwrap().unwrap();
+        conversation.messages.into_iter().map(|m| m.content).collect()
+    }
+
+    #[test]
+    fn migrations_turn_old_history_into_one_branch() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn.execute_batch(
+            "INSERT INTO conversations (id, title, created_at, updated_at)
+                 VALUES ('c', 'Old', 1, 3);
+             INSERT INTO messages (id, conversation_id, role, content, created_at)
+                 VALUES ('m1', 'c', 'user', 'hi', 1),
+                        ('m2', 'c', 'assistant', 'hello', 2),
+                        ('m3', 'c', 'user', 'bye', 3);",
+        )
+        .unwrap();
+
+        let storage = init(conn).unwrap();
+        assert_eq!(path(&storage, "c"), ["hi", "hello", "bye"]);
+        let conn = storage.0.lock().unwrap();
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", params![], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        let parent: Option<String> = conn
+            .query_row("SELECT parent_id FROM messages WHERE id = 'm3'", params![], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(parent.as_deref(), Some("m2"));
+        // Running them again is a no-op
+        super::migrate(&conn).unwrap();
+    }
+
+    #[test]
+    fn switching_branches_changes_the_active_path() {
+        let storage = storage();
+        let prompt = storage.append_message("c", "user", "hi", None).unwrap();
+        let first = storage.append_message("c", "assistant", "first", None).unwrap();
+        storage.append_message("c", "user", "more", None).unwrap();
+
+        assert!(storage.switch_branch("c", &prompt.id, false).unwrap());
+        assert_eq!(path(&storage, "c"), ["hi"]);
+        storage.append_message("c", "assistant", "second", None).unwrap();
+        assert_eq!(path(&storage, "c"), ["hi", "second"]);
+
+        let branches = storage.branches("c", Some(&prompt.id)).unwrap();
+        let active: Vec<bool> = branches.iter().map(|b| b.active).collect();
+        assert_eq!(active, [false, true]);
+
+        // Descending follows the old branch to its newest leaf
+        assert!(storage.switch_branch("c", &first.id, true).unwrap());
+        assert_eq!(path(&storage, "c"), ["hi", "first", "more"]);
+        assert!(!storage.switch_branch("c", "missing", true).unwrap());
+    }
+
+    #[test]
+    fn deleting_a_conversation_removes_its_draft_and_mode() {
+        let storage = storage();
+        storage.append_message("c", "user", "hi", None).unwrap();
+        storage.save_draft("c", "unsent").unwrap();
+        storage.set_conversation_mode("c", ConversationMode::Plan).unwrap();
+
+        assert!(storage.delete_conversation("c").unwrap());
+        assert!(storage.conversation("c").unwrap().is_none());
+        assert!(storage.draft("c").unwrap().is_none());
+        assert_eq!(storage.conversation_mode("c").unwrap(), ConversationMode::Act);
+        assert!(!storage.delete_conversation("c").unwrap());
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Stop accumulating past this point; the final result is authoritative anyway
+const MAX_BUFFER_BYTES: usize = 4 * 1024 * 1024;
+
+/// Streamed `assistant_delta` text per frontend request id, kept so a timed-out
+/// request can still return what the user already saw.
+#[derive(Default)]
+pub(crate) struct StreamBuffers(Mutex<HashMap<String, String>>);
+
+impl StreamBuffers {
+    /// Starts (or restarts, on retry) buffering for `request_id`.
+    pub fn begin(&self, request_id: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(request_id.to_string(), String::new());
+    }
+
+    /// Appends a delta if `request_id` is being buffered; unknown ids are ignored.
+    pub fn append(&self, request_id: &str, delta: &str) {
+        let mut buffers = self.0.lock().unwrap();
+        if let Some(buffer) = buffers.get_mut(request_id) {
+            if buffer.len() + delta.len() <= MAX_BUFFER_BYTES {
+                buffer.push_str(delta);
+            }
+        }
+    }
+
+    /// Whether any text has streamed for `request_id` since it last began.
+    pub fn has_content(&self, request_id: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(request_id)
+            .is_some_and(|buffer| !buffer.is_empty())
+    }
+
+    pub fn take(&self, request_id: &str) -> Option<String> {
+        self.0.lock().unwrap().remove(request_id)
+    }
+}
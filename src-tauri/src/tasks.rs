@@ -0,0 +1,506 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::agent_tasks::{self, TaskLogEntry};
+use crate::app_data::app_data_path;
+use crate::clock::now_millis;
+use crate::workspace::workspace_root;
+
+const TASKS_DIR: &str = "tasks";
+// Only the end of longer output is kept; that's where build and test failures are reported
+const MAX_TRANSCRIPT_BYTES: usize = 1024 * 1024;
+// Older transcripts are deleted once this many are stored
+const MAX_STORED_TASKS: usize = 50;
+// How much of the output `get_task_context` hands to the agent
+const SUMMARY_TAIL_BYTES: usize = 16 * 1024;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    /// An agent task waiting for the one before it
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    /// A shell command from `run_task`
+    #[default]
+    Command,
+    /// An agent run from `submit_task`
+    Agent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOutput {
+    stream: OutputStream,
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub(crate) id: String,
+    #[serde(default)]
+    pub(crate) kind: TaskKind,
+    /// The shell command, or what the agent was asked to do
+    pub(crate) command: String,
+    pub(crate) cwd: String,
+    pub(crate) status: TaskStatus,
+    /// When the task was submitted, for queued agent tasks
+    pub(crate) started_at: i64,
+    pub(crate) finished_at: Option<i64>,
+    /// `None` while running, and when the process was ended by a signal
+    pub(crate) exit_code: Option<i32>,
+    /// Unix signal that ended the process
+    pub(crate) signal: Option<i32>,
+    /// The conversation an agent task's prompt and reply are stored in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) conversation_id: Option<String>,
+    /// The id an agent task's `agent:delta` and `agent:tool` events carry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) request_id: Option<String>,
+    /// Why an agent task failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    /// The parallel task a sub-task belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskTranscript {
+    #[serde(flatten)]
+    pub(crate) run: TaskRun,
+    pub(crate) output: Vec<TaskOutput>,
+    /// Whether the start of the output was dropped after 1 MB
+    pub(crate) truncated: bool,
+    /// What an agent task did: its reply text, tool calls and status changes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) log: Vec<TaskLogEntry>,
+    /// How each sub-task of a parallel task ended
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) subtasks: Vec<TaskRun>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskOutputEvent<'a> {
+    task_id: &'a str,
+    stream: OutputStream,
+    text: &'a str,
+}
+
+struct Transcript {
+    chunks: VecDeque<TaskOutput>,
+    bytes: usize,
+    truncated: bool,
+}
+
+impl Transcript {
+    fn push(&mut self, stream: OutputStream, text: String) {
+        self.bytes += text.len();
+        self.chunks.push_back(TaskOutput { stream, text });
+        while self.bytes > MAX_TRANSCRIPT_BYTES && self.chunks.len() > 1 {
+            if let Some(dropped) = self.chunks.pop_front() {
+                self.bytes -= dropped.text.len();
+                self.truncated = true;
+            }
+        }
+    }
+}
+
+struct RunningTask {
+    run: TaskRun,
+    child: Arc<Mutex<Child>>,
+    transcript: Arc<Mutex<Transcript>>,
+    cancelled: Arc<Mutex<bool>>,
+}
+
+#[derive(Default)]
+pub(crate) struct Tasks(Mutex<HashMap<String, RunningTask>>);
+
+fn tasks_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_data_path(app, TASKS_DIR)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create tasks folder: {}", e))?;
+    Ok(dir)
+}
+
+pub(crate) fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    }
+}
+
+/// Ends the task's whole process tree, not only the shell that started it.
+pub(crate) fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .arg("-TERM")
+            .arg(format!("-{}", child.id()))
+            .status();
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID"])
+            .arg(child.id().to_string())
+            .status();
+    }
+    let _ = child.kill();
+}
+
+fn exit_details(status: &ExitStatus) -> (Option<i32>, Option<i32>) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        (status.code(), status.signal())
+    }
+    #[cfg(not(unix))]
+    {
+        (status.code(), None)
+    }
+}
+
+/// Decodes what `pending` holds, except the bytes of a character split across reads,
+/// which stay for the next one. Invalid UTF-8 becomes replacement characters.
+pub(crate) fn take_utf8(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+    pending.drain(..valid);
+    text
+}
+
+fn pump(
+    app: tauri::AppHandle,
+    id: String,
+    stream: OutputStream,
+    mut reader: impl Read + Send + 'static,
+    transcript: Arc<Mutex<Transcript>>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        let mut pending = Vec::new();
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            pending.extend_from_slice(&buf[..n]);
+            let text = take_utf8(&mut pending);
+            if text.is_empty() {
+                continue;
+            }
+            let _ = app.emit(
+                "task:output",
+                TaskOutputEvent {
+                    task_id: &id,
+                    stream,
+                    text: &text,
+                },
+            );
+            transcript.lock().unwrap().push(stream, text);
+        }
+        if !pending.is_empty() {
+            let text = String::from_utf8_lossy(&pending).into_owned();
+            transcript.lock().unwrap().push(stream, text);
+        }
+    })
+}
+
+pub(crate) fn save_transcript(
+    app: &tauri::AppHandle,
+    transcript: &TaskTranscript,
+) -> Result<(), String> {
+    let dir = tasks_dir(app)?;
+    let content = serde_json::to_string(transcript).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{}.json", transcript.run.id)), content)
+        .map_err(|e| format!("Failed to save task transcript: {}", e))?;
+
+    let mut stored: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read tasks folder: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    if stored.len() > MAX_STORED_TASKS {
+        stored.sort();
+        for (_, path) in &stored[..stored.len() - MAX_STORED_TASKS] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn load_transcript(app: &tauri::AppHandle, id: &str) -> Result<TaskTranscript, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid task id: {}", id));
+    }
+    let content = std::fs::read_to_string(tasks_dir(app)?.join(format!("{}.json", id)))
+        .map_err(|_| format!("Task not found: {}", id))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse task transcript: {}", e))
+}
+
+/// Waits for the task to exit, then stores its transcript and announces the result.
+fn supervise(app: tauri::AppHandle, id: String, readers: Vec<std::thread::JoinHandle<()>>) {
+    let (child, transcript, cancelled) = {
+        let tasks = app.state::<Tasks>();
+        let tasks = tasks.0.lock().unwrap();
+        let Some(task) = tasks.get(&id) else {
+            return;
+        };
+        (
+            task.child.clone(),
+            task.transcript.clone(),
+            task.cancelled.clone(),
+        )
+    };
+    // Polled so `cancel_task` can take the child's lock to kill it
+    let status = loop {
+        match child.lock().unwrap().try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => {}
+            Err(_) => break None,
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    let Some(task) = app.state::<Tasks>().0.lock().unwrap().remove(&id) else {
+        return;
+    };
+    let mut run = task.run;
+    run.finished_at = Some(now_millis());
+    if let Some(status) = &status {
+        (run.exit_code, run.signal) = exit_details(status);
+    }
+    run.status = if *cancelled.lock().unwrap() {
+        TaskStatus::Cancelled
+    } else if status.is_some_and(|s| s.success()) {
+        TaskStatus::Succeeded
+    } else {
+        TaskStatus::Failed
+    };
+    let transcript = {
+        let mut transcript = transcript.lock().unwrap();
+        TaskTranscript {
+            run: run.clone(),
+            output: transcript.chunks.drain(..).collect(),
+            truncated: transcript.truncated,
+            log: Vec::new(),
+            subtasks: Vec::new(),
+        }
+    };
+    if let Err(err) = save_transcript(&app, &transcript) {
+        eprintln!("[tasks] {}", err);
+    }
+    let _ = app.emit("task:exit", run);
+}
+
+/// Runs a build or test `command` through the shell in `cwd`, a workspace folder. Output
+/// streams as `task:output` events and the result arrives as `task:exit`; the transcript
+/// is stored for `get_task_transcript`.
+#[tauri::command]
+pub fn run_task(app: tauri::AppHandle, command: String, cwd: String) -> Result<TaskRun, String> {
+    let command = command.trim().to_string();
+    if command.is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    let root = workspace_root(&cwd)?;
+    let mut cmd = shell(&command);
+    cmd.current_dir(&root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so cancelling reaches everything the shell started
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let run = TaskRun {
+        id: id.clone(),
+        kind: TaskKind::Command,
+        command,
+        cwd: root.to_string_lossy().into_owned(),
+        status: TaskStatus::Running,
+        started_at: now_millis(),
+        finished_at: None,
+        exit_code: None,
+        signal: None,
+        conversation_id: None,
+        request_id: None,
+        error: None,
+        parent_id: None,
+    };
+    let transcript = Arc::new(Mutex::new(Transcript {
+        chunks: VecDeque::new(),
+        bytes: 0,
+        truncated: false,
+    }));
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(pump(
+            app.clone(),
+            id.clone(),
+            OutputStream::Stdout,
+            stdout,
+            transcript.clone(),
+        ));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(pump(
+            app.clone(),
+            id.clone(),
+            OutputStream::Stderr,
+            stderr,
+            transcript.clone(),
+        ));
+    }
+    app.state::<Tasks>().0.lock().unwrap().insert(
+        id.clone(),
+        RunningTask {
+            run: run.clone(),
+            child: Arc::new(Mutex::new(child)),
+            transcript,
+            cancelled: Arc::new(Mutex::new(false)),
+        },
+    );
+    std::thread::spawn(move || supervise(app, id, readers));
+    Ok(run)
+}
+
+/// Stops a running task, or drops a queued agent task. Returns `false` when it had
+/// already finished.
+#[tauri::command]
+pub fn cancel_task(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let (child, cancelled) = {
+        let tasks = app.state::<Tasks>();
+        let tasks = tasks.0.lock().unwrap();
+        let Some(task) = tasks.get(&id) else {
+            return Ok(agent_tasks::cancel(&app, &id));
+        };
+        (task.child.clone(), task.cancelled.clone())
+    };
+    *cancelled.lock().unwrap() = true;
+    kill(&mut child.lock().unwrap());
+    Ok(true)
+}
+
+/// Running and queued tasks first, then stored transcripts, newest first.
+#[tauri::command]
+pub fn list_tasks(app: tauri::AppHandle) -> Result<Vec<TaskRun>, String> {
+    let mut runs: Vec<TaskRun> = app
+        .state::<Tasks>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .map(|task| task.run.clone())
+        .chain(agent_tasks::active(&app))
+        .collect();
+    let mut finished: Vec<TaskRun> = std::fs::read_dir(tasks_dir(&app)?)
+        .map_err(|e| format!("Failed to read tasks folder: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str::<TaskTranscript>(&content).ok())
+        .map(|transcript| transcript.run)
+        .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+    finished.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+    runs.extend(finished);
+    Ok(runs)
+}
+
+/// The output so far of a running task, or the stored transcript of a finished one.
+#[tauri::command]
+pub fn get_task_transcript(app: tauri::AppHandle, id: String) -> Result<TaskTranscript, String> {
+    if let Some(task) = app.state::<Tasks>().0.lock().unwrap().get(&id) {
+        let transcript = task.transcript.lock().unwrap();
+        return Ok(TaskTranscript {
+            run: task.run.clone(),
+            output: transcript.chunks.iter().cloned().collect(),
+            truncated: transcript.truncated,
+            log: Vec::new(),
+            subtasks: Vec::new(),
+        });
+    }
+    load_transcript(&app, &id)
+}
+
+/// A finished task as Markdown for the agent: the command, how it ended and the end of
+/// its output, e.g. as context for "fix the failing tests".
+#[tauri::command]
+pub fn get_task_context(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    let transcript = load_transcript(&app, &id)?;
+    let run = &transcript.run;
+    let outcome = match (run.status, run.exit_code, run.signal) {
+        (TaskStatus::Cancelled, _, _) => "was cancelled".to_string(),
+        (TaskStatus::Succeeded, _, _) if run.kind == TaskKind::Agent => "finished".to_string(),
+        (TaskStatus::Failed, _, _) if run.kind == TaskKind::Agent => format!(
+            "failed: {}",
+            run.error.as_deref().unwrap_or("unknown error")
+        ),
+        (_, Some(code), _) => format!("exited with code {}", code),
+        (_, None, Some(signal)) => format!("was killed by signal {}", signal),
+        _ => "ended without an exit status".to_string(),
+    };
+    let output: String = match run.kind {
+        TaskKind::Command => transcript.output.iter().map(|o| o.text.as_str()).collect(),
+        TaskKind::Agent => agent_tasks::log_text(&transcript.log),
+    };
+    let mut start = output.len().saturating_sub(SUMMARY_TAIL_BYTES);
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    let omitted = start > 0 || transcript.truncated;
+    Ok(format!(
+        "## Task output\n`{}` in `{}` {}.\n\n{}```\n{}\n```\n",
+        run.command,
+        run.cwd,
+        outcome,
+        if omitted {
+            "Earlier output was omitted.\n\n"
+        } else {
+            ""
+        },
+        output[start..].trim_end()
+    ))
+}
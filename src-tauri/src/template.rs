@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+pub(crate) struct Rendered {
+    pub text: String,
+    /// Placeholders that had neither a value nor a default
+    pub missing: Vec<String>,
+}
+
+/// Expands `{{name}}` and `{{name|default}}` placeholders from `vars`.
+/// Missing placeholders without a default expand to an empty string and are reported.
+pub(crate) fn render(template: &str, vars: &HashMap<String, String>) -> Rendered {
+    let mut text = String::with_capacity(template.len());
+    let mut missing = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        text.push_str(&rest[..start]);
+
+        let inner = &rest[start + 2..start + 2 + len];
+        let (name, default) = match inner.split_once('|') {
+            Some((name, default)) => (name.trim(), Some(default.trim())),
+            None => (inner.trim(), None),
+        };
+
+        match (vars.get(name), default) {
+            (Some(value), _) => text.push_str(value),
+            (None, Some(default)) => text.push_str(default),
+            (None, None) => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    text.push_str(rest);
+
+    Rendered { text, missing }
+}
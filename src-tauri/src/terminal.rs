@@ -0,0 +1,557 @@
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+use crate::approvals;
+use crate::clock::now_millis;
+use crate::permissions::{self, Access};
+use crate::rpc;
+use crate::tasks::take_utf8;
+use crate::workspace::workspace_root;
+
+const DEFAULT_COLS: u16 = 80;
+const DEFAULT_ROWS: u16 = 24;
+const MAX_WRITE_BYTES: usize = 64 * 1024;
+// Older output is dropped once a session has printed this much
+const MAX_SCROLLBACK_BYTES: usize = 256 * 1024;
+const MAX_NAME_CHARS: usize = 64;
+const DEFAULT_READ_LINES: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalInfo {
+    id: String,
+    /// Unique among the sessions of a workspace
+    name: String,
+    cwd: String,
+    /// `None` for the user's login shell
+    command: Option<String>,
+    cols: u16,
+    rows: u16,
+    pid: Option<u32>,
+    started_at: i64,
+    running: bool,
+    /// Set once a session has ended; it stays listed until killed
+    exit_code: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalOutput<'a> {
+    id: &'a str,
+    data: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalExit<'a> {
+    id: &'a str,
+    exit_code: Option<u32>,
+}
+
+/// `{event:"terminal_request", callId, workspace, action, ...}` from the agent's
+/// `terminal` tool.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalRequest {
+    call_id: String,
+    workspace: String,
+    action: String,
+    name: Option<String>,
+    /// A command line for `start`, or text to type for `send`
+    input: Option<String>,
+    lines: Option<usize>,
+    request_id: Option<String>,
+}
+
+struct Terminal {
+    info: Mutex<TerminalInfo>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    scrollback: Mutex<String>,
+}
+
+/// Terminal sessions by id. They last until killed or the app quits.
+#[derive(Default)]
+pub(crate) struct Terminals(Mutex<HashMap<String, Arc<Terminal>>>);
+
+fn terminal(app: &tauri::AppHandle, id: &str) -> Result<Arc<Terminal>, String> {
+    app.state::<Terminals>()
+        .0
+        .lock()
+        .unwrap()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("Terminal not found: {}", id))
+}
+
+fn named(app: &tauri::AppHandle, cwd: &Path, name: &str) -> Option<Arc<Terminal>> {
+    let cwd = cwd.to_string_lossy();
+    app.state::<Terminals>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .find(|t| {
+            let info = t.info.lock().unwrap();
+            info.cwd == cwd && info.name == name
+        })
+        .cloned()
+}
+
+fn size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Appends to a scrollback, dropping whole characters from the front past the limit.
+fn push_scrollback(scrollback: &mut String, data: &str) {
+    scrollback.push_str(data);
+    if scrollback.len() > MAX_SCROLLBACK_BYTES {
+        let mut cut = scrollback.len() - MAX_SCROLLBACK_BYTES;
+        while !scrollback.is_char_boundary(cut) {
+            cut += 1;
+        }
+        scrollback.drain(..cut);
+    }
+}
+
+/// Terminal output without colour and cursor escape sequences or carriage returns.
+fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                // CSI: parameters up to a final character in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => {}
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+/// Forwards everything a terminal prints as `terminal:output` events and keeps it as
+/// scrollback.
+fn pump(app: tauri::AppHandle, terminal: Arc<Terminal>, mut reader: Box<dyn Read + Send>) {
+    let id = terminal.info.lock().unwrap().id.clone();
+    let mut buf = [0u8; 8192];
+    let mut pending = Vec::new();
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        pending.extend_from_slice(&buf[..n]);
+        let data = take_utf8(&mut pending);
+        if !data.is_empty() {
+            push_scrollback(&mut terminal.scrollback.lock().unwrap(), &data);
+            let _ = app.emit(
+                "terminal:output",
+                TerminalOutput {
+                    id: &id,
+                    data: &data,
+                },
+            );
+        }
+    }
+}
+
+/// The first of `terminal-1`, `terminal-2`, ... not taken in `cwd`.
+fn default_name(app: &tauri::AppHandle, cwd: &Path) -> String {
+    (1..)
+        .map(|n| format!("terminal-{}", n))
+        .find(|name| named(app, cwd, name).is_none())
+        .unwrap()
+}
+
+fn spawn(
+    app: &tauri::AppHandle,
+    cwd: &str,
+    name: Option<String>,
+    command: Option<String>,
+    args: Vec<String>,
+    cols: u16,
+    rows: u16,
+) -> Result<TerminalInfo, String> {
+    let root = workspace_root(cwd)?;
+    let name = match name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+        Some(name) if name.chars().count() > MAX_NAME_CHARS => {
+            return Err(format!(
+                "Terminal names can be at most {} characters",
+                MAX_NAME_CHARS
+            ))
+        }
+        Some(name) => {
+            if let Some(existing) = named(app, &root, &name) {
+                let info = existing.info.lock().unwrap().clone();
+                if info.running {
+                    return Err(format!("A terminal named {} is already running", name));
+                }
+                // A finished session gives its name up to the new one
+                app.state::<Terminals>().0.lock().unwrap().remove(&info.id);
+            }
+            name
+        }
+        None => default_name(app, &root),
+    };
+    let command = command
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    let pair = native_pty_system()
+        .openpty(size(cols, rows))
+        .map_err(|e| format!("Failed to open a terminal: {}", e))?;
+
+    let mut builder = match &command {
+        Some(command) => {
+            let mut builder = CommandBuilder::new(command);
+            builder.args(args);
+            builder
+        }
+        None => CommandBuilder::new_default_prog(),
+    };
+    builder.cwd(&root);
+    builder.env("TERM", "xterm-256color");
+    let mut child = pair
+        .slave
+        .spawn_command(builder)
+        .map_err(|e| format!("Failed to start the terminal: {}", e))?;
+    // Only the child keeps the slave end open, so reads end when it exits
+    drop(pair.slave);
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from the terminal: {}", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to the terminal: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let info = TerminalInfo {
+        id: id.clone(),
+        name,
+        cwd: root.to_string_lossy().into_owned(),
+        command,
+        cols: cols.max(1),
+        rows: rows.max(1),
+        pid: child.process_id(),
+        started_at: now_millis(),
+        running: true,
+        exit_code: None,
+    };
+    let terminal = Arc::new(Terminal {
+        info: Mutex::new(info.clone()),
+        master: Mutex::new(pair.master),
+        writer: Mutex::new(writer),
+        killer: Mutex::new(child.clone_killer()),
+        scrollback: Mutex::new(String::new()),
+    });
+    app.state::<Terminals>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(id.clone(), terminal.clone());
+
+    let reader_app = app.clone();
+    let reader_terminal = terminal.clone();
+    let reader = std::thread::spawn(move || pump(reader_app, reader_terminal, reader));
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let exit_code = child.wait().ok().map(|s| s.exit_code());
+        let _ = reader.join();
+        {
+            let mut info = terminal.info.lock().unwrap();
+            info.running = false;
+            info.exit_code = exit_code;
+        }
+        let _ = app.emit("terminal:exit", TerminalExit { id: &id, exit_code });
+    });
+    Ok(info)
+}
+
+fn write(terminal: &Terminal, data: &str) -> Result<(), String> {
+    if data.len() > MAX_WRITE_BYTES {
+        return Err(format!(
+            "Input is larger than {} KB",
+            MAX_WRITE_BYTES / 1024
+        ));
+    }
+    if !terminal.info.lock().unwrap().running {
+        return Err("The terminal has exited".to_string());
+    }
+    let mut writer = terminal.writer.lock().unwrap();
+    writer
+        .write_all(data.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write to the terminal: {}", e))
+}
+
+/// Ends a session if it is still running and forgets it.
+fn kill(app: &tauri::AppHandle, terminal: &Terminal) -> Result<(), String> {
+    let info = terminal.info.lock().unwrap().clone();
+    if info.running {
+        terminal
+            .killer
+            .lock()
+            .unwrap()
+            .kill()
+            .map_err(|e| format!("Failed to kill the terminal: {}", e))?;
+    }
+    app.state::<Terminals>().0.lock().unwrap().remove(&info.id);
+    Ok(())
+}
+
+/// The last `lines` lines of a session's scrollback.
+fn tail(terminal: &Terminal, lines: usize, plain: bool) -> String {
+    let scrollback = terminal.scrollback.lock().unwrap();
+    let text = if plain {
+        plain_text(&scrollback)
+    } else {
+        scrollback.clone()
+    };
+    let trimmed = text.trim_end_matches('\n');
+    let start = trimmed
+        .rmatch_indices('\n')
+        .nth(lines.max(1) - 1)
+        .map_or(0, |(index, _)| index + 1);
+    trimmed[start..].to_string()
+}
+
+fn list(app: &tauri::AppHandle, cwd: Option<&Path>) -> Vec<TerminalInfo> {
+    let cwd = cwd.map(|c| c.to_string_lossy().into_owned());
+    let mut terminals = app
+        .state::<Terminals>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .map(|t| t.info.lock().unwrap().clone())
+        .filter(|info| cwd.as_ref().is_none_or(|cwd| &info.cwd == cwd))
+        .collect::<Vec<_>>();
+    terminals.sort_by_key(|info| info.started_at);
+    terminals
+}
+
+/// Starts `command` (the user's shell when `None`) in a pseudo-terminal in `cwd`, a
+/// workspace folder. What it prints arrives as `terminal:output` events and its end as
+/// `terminal:exit`. `name` defaults to the first free `terminal-N`.
+#[tauri::command]
+pub fn terminal_spawn(
+    app: tauri::AppHandle,
+    cwd: String,
+    name: Option<String>,
+    command: Option<String>,
+    args: Option<Vec<String>>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+) -> Result<TerminalInfo, String> {
+    spawn(
+        &app,
+        &cwd,
+        name,
+        command,
+        args.unwrap_or_default(),
+        cols.unwrap_or(DEFAULT_COLS),
+        rows.unwrap_or(DEFAULT_ROWS),
+    )
+}
+
+/// Sends keystrokes or pasted text to a terminal.
+#[tauri::command]
+pub fn terminal_write(app: tauri::AppHandle, id: String, data: String) -> Result<(), String> {
+    let terminal = terminal(&app, &id)?;
+    write(&terminal, &data)
+}
+
+/// Tells a terminal its new size so full-screen programs redraw to fit.
+#[tauri::command]
+pub fn terminal_resize(
+    app: tauri::AppHandle,
+    id: String,
+    cols: u16,
+    rows: u16,
+) -> Result<TerminalInfo, String> {
+    let terminal = terminal(&app, &id)?;
+    terminal
+        .master
+        .lock()
+        .unwrap()
+        .resize(size(cols, rows))
+        .map_err(|e| format!("Failed to resize the terminal: {}", e))?;
+    let mut info = terminal.info.lock().unwrap();
+    info.cols = cols.max(1);
+    info.rows = rows.max(1);
+    Ok(info.clone())
+}
+
+/// Sessions oldest first, including finished ones; only those in `workspace` when given.
+#[tauri::command]
+pub fn list_terminals(
+    app: tauri::AppHandle,
+    workspace: Option<String>,
+) -> Result<Vec<TerminalInfo>, String> {
+    let root = workspace.as_deref().map(workspace_root).transpose()?;
+    Ok(list(&app, root.as_deref()))
+}
+
+/// Ends a session, or forgets a finished one.
+#[tauri::command]
+pub fn kill_terminal(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let terminal = terminal(&app, &id)?;
+    kill(&app, &terminal)
+}
+
+/// What a session still holds of its output, escape sequences included, to repaint a
+/// terminal view that was closed.
+#[tauri::command]
+pub fn get_terminal_scrollback(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    Ok(terminal(&app, &id)?.scrollback.lock().unwrap().clone())
+}
+
+fn handle_action(
+    app: &tauri::AppHandle,
+    request: &TerminalRequest,
+) -> Result<serde_json::Value, String> {
+    let root = workspace_root(&request.workspace)?;
+    let session = || {
+        let name = request
+            .name
+            .as_deref()
+            .ok_or_else(|| "A terminal name is required".to_string())?;
+        named(app, &root, name).ok_or_else(|| format!("No terminal named {}", name))
+    };
+    let info = |t: &Terminal| serde_json::json!(t.info.lock().unwrap().clone());
+    match request.action.as_str() {
+        "list" => Ok(serde_json::json!(list(app, Some(&root)))),
+        "start" => {
+            // Commands run in a shell that stays open, so the session outlives them
+            let info = spawn(
+                app,
+                &request.workspace,
+                request.name.clone(),
+                None,
+                Vec::new(),
+                DEFAULT_COLS,
+                DEFAULT_ROWS,
+            )?;
+            if let Some(input) = request.input.as_deref().filter(|i| !i.trim().is_empty()) {
+                let terminal = terminal(app, &info.id)?;
+                write(&terminal, &format!("{}\r", input))?;
+            }
+            Ok(serde_json::json!(info))
+        }
+        "send" => {
+            let terminal = session()?;
+            let input = request.input.as_deref().unwrap_or("");
+            write(&terminal, &format!("{}\r", input))?;
+            Ok(info(&terminal))
+        }
+        "read" => {
+            let terminal = session()?;
+            let lines = request.lines.unwrap_or(DEFAULT_READ_LINES);
+            Ok(serde_json::json!({
+                "terminal": info(&terminal),
+                "output": tail(&terminal, lines, true),
+            }))
+        }
+        "kill" => {
+            let terminal = session()?;
+            kill(app, &terminal)?;
+            Ok(info(&terminal))
+        }
+        other => Err(format!("Unknown terminal action: {}", other)),
+    }
+}
+
+/// Carries out a `terminal` tool call from the agent, once any command in it is approved,
+/// and hands the outcome back via `terminalReply`. Writing to a busy terminal can block,
+/// so it stays off the reader.
+pub(crate) fn handle_request(app: &tauri::AppHandle, value: serde_json::Value) {
+    let request: TerminalRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("[terminal] malformed request: {}", err);
+            return;
+        }
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let call_id = request.call_id.clone();
+        // Nothing the agent types reaches a shell before the user has allowed it
+        let command = match request.action.as_str() {
+            "start" | "send" => request.input.as_deref().filter(|i| !i.trim().is_empty()),
+            _ => None,
+        };
+        let approved = match command {
+            Some(command) => match permissions::check_mode(
+                &app,
+                request.request_id.as_deref(),
+                "terminal",
+                Access::Execute,
+                "running terminal commands",
+            ) {
+                Ok(()) => {
+                    approvals::require(
+                        &app,
+                        &call_id,
+                        command,
+                        &request.workspace,
+                        request.request_id.clone(),
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            },
+            None => Ok(()),
+        };
+        let handler = app.clone();
+        let result = match approved {
+            Ok(()) => {
+                tauri::async_runtime::spawn_blocking(move || handle_action(&handler, &request))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Terminal task failed: {}", e)))
+            }
+            Err(err) => Err(err),
+        };
+        rpc::reply_to_sidecar(&app, "terminalReply", &call_id, result).await;
+    });
+}
+
+/// `{event:"terminal_cancel", callId}`: the agent gave up on a call, so a prompt for it
+/// must not start anything later.
+pub(crate) fn handle_cancel(app: &tauri::AppHandle, value: serde_json::Value) {
+    if let Some(call_id) = value.get("callId").and_then(|v| v.as_str()) {
+        approvals::cancel(app, call_id);
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Datelike, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::clock::now_millis;
+
+/// Backend timestamps are always UTC epoch milliseconds; this module converts to and
+/// from the user's system zone at the edges (scheduling, digests, usage reports).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeZoneInfo {
+    /// IANA name such as "Europe/Berlin", or "UTC" if it can't be determined
+    name: String,
+    /// Current offset from UTC in minutes, east positive
+    offset_minutes: i32,
+    dst: bool,
+}
+
+pub(crate) fn zone_name() -> String {
+    iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_string())
+}
+
+pub(crate) fn local_at(millis: i64) -> DateTime<Local> {
+    Local
+        .timestamp_millis_opt(millis)
+        .earliest()
+        .unwrap_or_else(|| Utc::now().with_timezone(&Local))
+}
+
+pub(crate) fn offset_minutes_at(millis: i64) -> i32 {
+    local_at(millis).offset().local_minus_utc() / 60
+}
+
+/// Resolves a wall-clock time in the user's zone to UTC milliseconds. Times repeated by
+/// a DST fall-back resolve to the first occurrence; times skipped by a spring-forward
+/// gap move ahead by the size of the gap, as a wall clock would.
+pub(crate) fn local_to_utc_millis(local: NaiveDateTime) -> i64 {
+    match Local.from_local_datetime(&local) {
+        LocalResult::Single(dt) => dt.timestamp_millis(),
+        LocalResult::Ambiguous(a, b) => a.timestamp_millis().min(b.timestamp_millis()),
+        LocalResult::None => {
+            // Interpret with the offset in effect just before the gap
+            let before = local - chrono::Duration::hours(3);
+            let offset = Local
+                .from_local_datetime(&before)
+                .earliest()
+                .map(|dt| dt.offset().local_minus_utc())
+                .unwrap_or(0);
+            local.and_utc().timestamp_millis() - i64::from(offset) * 1000
+        }
+    }
+}
+
+/// Start of the local calendar day `days_back` days before the one containing `millis`.
+pub(crate) fn local_day_start(millis: i64, days_back: u32) -> i64 {
+    let date: NaiveDate = local_at(millis).date_naive();
+    let date = date
+        .checked_sub_days(chrono::Days::new(u64::from(days_back)))
+        .unwrap_or(date);
+    local_to_utc_millis(date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// The local calendar date containing `millis`, as `YYYY-MM-DD`.
+pub(crate) fn local_date(millis: i64) -> String {
+    local_at(millis).format("%Y-%m-%d").to_string()
+}
+
+/// RFC 3339 rendering in the user's zone, e.g. `2024-05-01T14:30:00+02:00`.
+pub(crate) fn format_local(millis: i64) -> String {
+    local_at(millis).to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// DST is in effect when the current offset exceeds the zone's smaller seasonal offset.
+fn is_dst(millis: i64) -> bool {
+    let year = local_at(millis).year();
+    let seasonal = |month: u32| {
+        NaiveDate::from_ymd_opt(year, month, 1)
+            .and_then(|d| d.and_hms_opt(12, 0, 0))
+            .map(|dt| offset_minutes_at(local_to_utc_millis(dt)))
+    };
+    match (seasonal(1), seasonal(7)) {
+        (Some(january), Some(july)) => offset_minutes_at(millis) > january.min(july),
+        _ => false,
+    }
+}
+
+#[tauri::command]
+pub fn get_time_zone() -> TimeZoneInfo {
+    let now = now_millis();
+    TimeZoneInfo {
+        name: zone_name(),
+        offset_minutes: offset_minutes_at(now),
+        dst: is_dst(now),
+    }
+}
+
+/// Converts a local wall-clock time (`YYYY-MM-DDTHH:MM[:SS]`) to UTC epoch milliseconds.
+#[tauri::command]
+pub fn local_time_to_utc(local: String) -> Result<i64, String> {
+    let local = local.trim();
+    let parsed = NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M"))
+        .map_err(|e| format!("Invalid local time '{}': {}", local, e))?;
+    Ok(local_to_utc_millis(parsed))
+}
+
+/// Formats UTC epoch milliseconds in the user's zone.
+#[tauri::command]
+pub fn utc_to_local_time(millis: i64) -> String {
+    format_local(millis)
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::providers::ProviderOptions;
+use crate::rpc;
+use crate::storage::Storage;
+
+const TITLE_TIMEOUT: Duration = Duration::from_secs(20);
+const TITLE_MAX_WORDS: usize = 5;
+// Each message is clipped again by the sidecar; this only bounds the IPC payload
+const MESSAGE_MAX_CHARS: usize = 4000;
+
+/// Provider settings for the title request, mirroring `sendMessage` params.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TitleProvider {
+    pub provider: Option<String>,
+    pub api_key: String,
+    pub model: String,
+    pub base_url: Option<String>,
+    #[serde(flatten)]
+    pub options: ProviderOptions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TitleRequest<'a> {
+    #[serde(flatten)]
+    provider: &'a TitleProvider,
+    messages: Vec<TitleMessage>,
+}
+
+#[derive(Debug, Serialize)]
+struct TitleMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TitledEvent {
+    conversation_id: String,
+    title: String,
+}
+
+/// Models like to wrap titles in quotes or end them with a period.
+fn clean_title(raw: &str) -> Option<String> {
+    let line = raw.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line
+        .trim_start_matches(|c: char| c == '"' || c == '\'' || c == '#' || c.is_whitespace())
+        .trim_end_matches(|c: char| c == '"' || c == '\'' || c == '.' || c.is_whitespace());
+    let title = line
+        .split_whitespace()
+        .take(TITLE_MAX_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+async fn generate(
+    app: &tauri::AppHandle,
+    conversation_id: &str,
+    provider: &TitleProvider,
+) -> Result<Option<String>, String> {
+    let Some(conversation) = app.state::<Storage>().conversation(conversation_id)? else {
+        return Ok(None);
+    };
+    let messages: Vec<TitleMessage> = conversation
+        .messages
+        .into_iter()
+        .filter(|m| m.role == "user" || m.role == "assistant")
+        .map(|m| TitleMessage {
+            role: m.role,
+            content: m.content.chars().take(MESSAGE_MAX_CHARS).collect(),
+        })
+        .collect();
+
+    let request = TitleRequest { provider, messages };
+    let raw = rpc::call(app, "generateTitle", &request, TITLE_TIMEOUT)
+        .await
+        .map_err(|e| e.message)?;
+    let Some(title) = clean_title(&raw) else {
+        return Ok(None);
+    };
+    app.state::<Storage>().set_title(conversation_id, &title)?;
+    Ok(Some(title))
+}
+
+/// Titles a conversation in the background once its first exchange is stored, then
+/// emits `conversation:titled`. Failures only keep the first-line title.
+pub(crate) fn after_exchange(
+    app: &tauri::AppHandle,
+    conversation_id: &str,
+    provider: TitleProvider,
+) {
+    let first_exchange = app
+        .state::<Storage>()
+        .conversation(conversation_id)
+        .ok()
+        .flatten()
+        .is_some_and(|c| c.messages.len() == 2);
+    if !first_exchange {
+        return;
+    }
+
+    let app = app.clone();
+    let conversation_id = conversation_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        match generate(&app, &conversation_id, &provider).await {
+            Ok(Some(title)) => {
+                let _ = app.emit(
+                    "conversation:titled",
+                    TitledEvent {
+                        conversation_id,
+                        title,
+                    },
+                );
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("[titles] failed to title conversation: {}", err),
+        }
+    });
+}
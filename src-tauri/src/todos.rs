@@ -0,0 +1,189 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::workspace::{is_binary_file, relative_path, run_git, walk_files, workspace_root};
+
+const MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+const CONTEXT_LINES: usize = 2;
+const MAX_ITEMS: usize = 1000;
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    path: String,
+    line: usize,
+    marker: String,
+    text: String,
+    context: Vec<String>,
+    author: Option<String>,
+    authored_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoScan {
+    items: Vec<TodoItem>,
+    counts: BTreeMap<String, usize>,
+    truncated: bool,
+    /// Markdown checklist of the findings, ready to hand to the agent as a task list
+    task_list: String,
+}
+
+#[derive(Default, Clone)]
+struct BlameLine {
+    author: Option<String>,
+    authored_at: Option<i64>,
+}
+
+/// Finds the first marker that appears as a standalone word, returning it with its byte offset.
+fn find_marker(line: &str) -> Option<(&'static str, usize)> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    MARKERS
+        .iter()
+        .filter_map(|marker| {
+            line.match_indices(marker)
+                .find(|(idx, _)| {
+                    let before = line[..*idx].chars().next_back();
+                    let after = line[idx + marker.len()..].chars().next();
+                    !before.is_some_and(is_word) && !after.is_some_and(is_word)
+                })
+                .map(|(idx, _)| (*marker, idx))
+        })
+        .min_by_key(|(_, idx)| *idx)
+}
+
+fn marker_text(line: &str, marker: &str, idx: usize) -> String {
+    line[idx + marker.len()..]
+        .trim_start_matches(|c: char| c == ':' || c == '(' || c.is_whitespace())
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim()
+        .to_string()
+}
+
+/// Maps 1-based line numbers to their blame author using `git blame --line-porcelain`.
+fn blame_file(root: &Path, relative: &str) -> HashMap<usize, BlameLine> {
+    let mut lines = HashMap::new();
+    let Some(output) = run_git(root, &["blame", "--line-porcelain", "--", relative]) else {
+        return lines;
+    };
+
+    let mut current_line = 0;
+    let mut current = BlameLine::default();
+    for row in output.lines() {
+        if row.starts_with('\t') {
+            lines.insert(current_line, std::mem::take(&mut current));
+        } else if let Some(author) = row.strip_prefix("author ") {
+            current.author = Some(author.to_string());
+        } else if let Some(time) = row.strip_prefix("author-time ") {
+            current.authored_at = time.trim().parse().ok();
+        } else {
+            // Header rows look like "<sha> <orig-line> <final-line> [<count>]"
+            let mut parts = row.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                if sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                    current_line = final_line.parse().unwrap_or(0);
+                }
+            }
+        }
+    }
+    lines
+}
+
+fn task_list(items: &[TodoItem]) -> String {
+    let mut out = String::from("## Open TODO/FIXME/HACK markers\n");
+    for item in items {
+        let text = if item.text.is_empty() {
+            "(no description)"
+        } else {
+            &item.text
+        };
+        out.push_str(&format!(
+            "- [ ] {} {}:{} — {}",
+            item.marker, item.path, item.line, text
+        ));
+        if let Some(author) = &item.author {
+            out.push_str(&format!(" ({})", author));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub(crate) fn scan_workspace_todos(workspace: &str) -> Result<TodoScan, String> {
+    let root = workspace_root(workspace)?;
+    let in_git = run_git(&root, &["rev-parse", "--is-inside-work-tree"]).is_some();
+
+    let mut items = Vec::new();
+    let mut truncated = false;
+
+    'files: for path in walk_files(&root) {
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > MAX_FILE_BYTES || is_binary_file(&path) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let relative = relative_path(&root, &path);
+        let mut blame: Option<HashMap<usize, BlameLine>> = None;
+
+        for (index, line) in lines.iter().enumerate() {
+            let Some((marker, idx)) = find_marker(line) else {
+                continue;
+            };
+            if items.len() >= MAX_ITEMS {
+                truncated = true;
+                break 'files;
+            }
+
+            let line_number = index + 1;
+            let blame = blame.get_or_insert_with(|| {
+                if in_git {
+                    blame_file(&root, &relative)
+                } else {
+                    HashMap::new()
+                }
+            });
+            let authorship = blame.get(&line_number).cloned().unwrap_or_default();
+
+            let start = index.saturating_sub(CONTEXT_LINES);
+            let end = (index + CONTEXT_LINES + 1).min(lines.len());
+
+            items.push(TodoItem {
+                path: relative.clone(),
+                line: line_number,
+                marker: marker.to_string(),
+                text: marker_text(line, marker, idx),
+                context: lines[start..end].iter().map(|l| l.to_string()).collect(),
+                author: authorship.author,
+                authored_at: authorship.authored_at,
+            });
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    for item in &items {
+        *counts.entry(item.marker.clone()).or_insert(0) += 1;
+    }
+
+    Ok(TodoScan {
+        task_list: task_list(&items),
+        items,
+        counts,
+        truncated,
+    })
+}
+
+#[tauri::command]
+pub async fn scan_todos(workspace: String) -> Result<TodoScan, String> {
+    tauri::async_runtime::spawn_blocking(move || scan_workspace_todos(&workspace))
+        .await
+        .map_err(|e| format!("TODO scan failed: {}", e))?
+}
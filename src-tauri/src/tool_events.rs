@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::{agent_tasks, recorder};
+
+const PREVIEW_MAX_CHARS: usize = 500;
+// Arguments can carry whole file contents; the UI only needs enough to say what ran
+const ARGS_MAX_BYTES: usize = 4 * 1024;
+
+/// `{event:"tool_use_start", requestId, toolUseId, tool, args}` from the sidecar.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolUseStart {
+    #[serde(default)]
+    request_id: Option<String>,
+    tool_use_id: String,
+    tool: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+/// `{event:"tool_use_end", requestId, toolUseId, tool, durationMs, result, isError}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolUseEnd {
+    #[serde(default)]
+    request_id: Option<String>,
+    tool_use_id: String,
+    tool: String,
+    duration_ms: u64,
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    is_error: bool,
+}
+
+/// One step of a tool call, emitted to the webview as `agent:tool`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "phase", rename_all = "camelCase")]
+pub enum ToolEvent {
+    #[serde(rename_all = "camelCase")]
+    Start {
+        request_id: Option<String>,
+        tool_use_id: String,
+        tool: String,
+        args: serde_json::Value,
+        /// Whether `args` was replaced because it was too large to forward
+        args_truncated: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    End {
+        request_id: Option<String>,
+        tool_use_id: String,
+        tool: String,
+        duration_ms: u64,
+        result_preview: Option<String>,
+        is_error: bool,
+    },
+}
+
+fn preview(text: &str) -> String {
+    let mut chars = text.trim().chars();
+    let mut preview: String = chars.by_ref().take(PREVIEW_MAX_CHARS).collect();
+    if chars.next().is_some() {
+        preview.push('…');
+    }
+    preview
+}
+
+fn validate_ids(tool_use_id: &str, tool: &str) -> Result<(), String> {
+    if tool_use_id.trim().is_empty() {
+        return Err("missing toolUseId".to_string());
+    }
+    if tool.trim().is_empty() {
+        return Err("missing tool name".to_string());
+    }
+    Ok(())
+}
+
+fn start_event(start: ToolUseStart) -> Result<ToolEvent, String> {
+    validate_ids(&start.tool_use_id, &start.tool)?;
+    let args = match start.args {
+        serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+        args @ serde_json::Value::Object(_) => args,
+        _ => return Err(format!("arguments of {} are not an object", start.tool)),
+    };
+    let too_large = args.to_string().len() > ARGS_MAX_BYTES;
+    Ok(ToolEvent::Start {
+        request_id: start.request_id,
+        tool_use_id: start.tool_use_id,
+        tool: start.tool,
+        args: if too_large {
+            serde_json::Value::Object(Default::default())
+        } else {
+            args
+        },
+        args_truncated: too_large,
+    })
+}
+
+fn end_event(end: ToolUseEnd) -> Result<ToolEvent, String> {
+    validate_ids(&end.tool_use_id, &end.tool)?;
+    Ok(ToolEvent::End {
+        request_id: end.request_id,
+        tool_use_id: end.tool_use_id,
+        tool: end.tool,
+        duration_ms: end.duration_ms,
+        result_preview: end.result.as_deref().map(preview).filter(|p| !p.is_empty()),
+        is_error: end.is_error,
+    })
+}
+
+fn forward(app: &tauri::AppHandle, event_name: &str, event: Result<ToolEvent, String>) {
+    match event {
+        Ok(event) => {
+            recorder::record(app, "agent:tool", &event);
+            agent_tasks::record_tool(app, &event);
+            let _ = app.emit("agent:tool", event);
+        }
+        // Malformed events are dropped rather than passed through untyped
+        Err(err) => eprintln!("[sidecar] ignoring malformed {}: {}", event_name, err),
+    }
+}
+
+/// Validates a `tool_use_start` line and forwards it as `agent:tool`.
+pub(crate) fn handle_start(app: &tauri::AppHandle, value: serde_json::Value) {
+    let event = serde_json::from_value(value)
+        .map_err(|e| e.to_string())
+        .and_then(start_event);
+    forward(app, "tool_use_start", event);
+}
+
+/// Validates a `tool_use_end` line and forwards it as `agent:tool`.
+pub(crate) fn handle_end(app: &tauri::AppHandle, value: serde_json::Value) {
+    let event = serde_json::from_value(value)
+        .map_err(|e| e.to_string())
+        .and_then(end_event);
+    forward(app, "tool_use_end", event);
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::app_data::{load_json, save_json};
+use crate::rpc;
+
+const TOOL_TIMEOUTS_FILE: &str = "tool_timeouts.json";
+const MIN_TIMEOUT_MS: u64 = 1000;
+
+fn default_timeout_ms() -> u64 {
+    120_000
+}
+
+/// Sent with every `sendMessage` so the sidecar can bound each tool call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolTimeouts {
+    #[serde(default = "default_timeout_ms")]
+    default_ms: u64,
+    /// Overrides by tool name, e.g. `run_node` or `web_operations`
+    #[serde(default)]
+    tools: HashMap<String, u64>,
+}
+
+impl Default for ToolTimeouts {
+    fn default() -> Self {
+        Self {
+            default_ms: default_timeout_ms(),
+            tools: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KillReport {
+    execution_id: String,
+    tool: Option<String>,
+    /// False when the execution had already finished
+    killed: bool,
+    partial_output: String,
+}
+
+pub(crate) fn load(app: &tauri::AppHandle) -> ToolTimeouts {
+    load_json::<ToolTimeouts>(app, TOOL_TIMEOUTS_FILE).unwrap_or_else(|err| {
+        eprintln!("[tools] failed to load tool timeouts: {}", err);
+        ToolTimeouts::default()
+    })
+}
+
+#[tauri::command]
+pub fn get_tool_timeouts(app: tauri::AppHandle) -> Result<ToolTimeouts, String> {
+    load_json::<ToolTimeouts>(&app, TOOL_TIMEOUTS_FILE)
+}
+
+#[tauri::command]
+pub fn set_tool_timeouts(
+    app: tauri::AppHandle,
+    timeouts: ToolTimeouts,
+) -> Result<ToolTimeouts, String> {
+    if timeouts.default_ms < MIN_TIMEOUT_MS
+        || timeouts.tools.values().any(|ms| *ms < MIN_TIMEOUT_MS)
+    {
+        return Err(format!(
+            "Tool timeouts must be at least {}ms",
+            MIN_TIMEOUT_MS
+        ));
+    }
+    save_json(&app, TOOL_TIMEOUTS_FILE, &timeouts)?;
+    Ok(timeouts)
+}
+
+/// Force-terminates a running tool call (ids come from `tool:execution` events). The
+/// agent run continues with the tool's partial output.
+#[tauri::command]
+pub async fn kill_tool_execution(
+    app: tauri::AppHandle,
+    execution_id: String,
+) -> Result<KillReport, String> {
+    let result = rpc::call(
+        &app,
+        "killToolExecution",
+        &json!({ "executionId": execution_id }),
+        Duration::from_secs(5),
+    )
+    .await
+    .map_err(|e| e.message)?;
+    serde_json::from_str(&result).map_err(|e| format!("Invalid kill report: {}", e))
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::app_data::{load_json, save_json};
+use crate::{proxy, secrets};
+
+const STT_SETTINGS_FILE: &str = "stt_settings.json";
+// The Whisper API rejects uploads over 25 MB
+const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+const TRANSCRIBE_TIMEOUT: Duration = Duration::from_secs(120);
+const AUDIO_EXTENSIONS: &[(&str, &str)] = &[
+    ("wav", "audio/wav"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("mp4", "audio/mp4"),
+    ("webm", "audio/webm"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SttBackend {
+    /// OpenAI-compatible `/v1/audio/transcriptions`
+    #[default]
+    WhisperApi,
+    /// A local whisper.cpp server's `/inference` endpoint
+    WhisperCpp,
+}
+
+fn default_endpoint() -> String {
+    "https://api.openai.com/v1/audio/transcriptions".to_string()
+}
+
+fn default_model() -> String {
+    "whisper-1".to_string()
+}
+
+fn default_key_provider() -> String {
+    "openai".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SttSettings {
+    #[serde(default)]
+    backend: SttBackend,
+    #[serde(default = "default_endpoint")]
+    endpoint: String,
+    #[serde(default = "default_model")]
+    model: String,
+    /// ISO-639-1 hint such as `en`; auto-detected when absent
+    #[serde(default)]
+    language: Option<String>,
+    /// The provider whose saved key the Whisper API is called with
+    #[serde(default = "default_key_provider")]
+    key_provider: String,
+}
+
+impl Default for SttSettings {
+    fn default() -> Self {
+        Self {
+            backend: SttBackend::default(),
+            endpoint: default_endpoint(),
+            model: default_model(),
+            language: None,
+            key_provider: default_key_provider(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SttResponse {
+    text: String,
+}
+
+fn mime_for(file_name: &str) -> Option<&'static str> {
+    let extension = Path::new(file_name)
+        .extension()?
+        .to_string_lossy()
+        .to_lowercase();
+    AUDIO_EXTENSIONS
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// `multipart/form-data` body with the audio as `file` and the rest as text fields.
+fn multipart_body(
+    boundary: &str,
+    file_name: &str,
+    mime: &str,
+    audio: &[u8],
+    fields: &[(&str, &str)],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(audio.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                boundary, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+             Content-Type: {}\r\n\r\n",
+            boundary,
+            file_name.replace('"', ""),
+            mime
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(audio);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+async fn transcribe(
+    app: &tauri::AppHandle,
+    settings: &SttSettings,
+    api_key: Option<&str>,
+    file_name: &str,
+    audio: Vec<u8>,
+) -> Result<String, String> {
+    let mime =
+        mime_for(file_name).ok_or_else(|| format!("Unsupported audio format: {}", file_name))?;
+    let mut fields = vec![("response_format", "json")];
+    if settings.backend == SttBackend::WhisperApi {
+        fields.push(("model", settings.model.as_str()));
+    }
+    if let Some(language) = settings.language.as_deref() {
+        fields.push(("language", language));
+    }
+
+    let boundary = format!("ohmycowork-{}", uuid::Uuid::new_v4().simple());
+    let body = multipart_body(&boundary, file_name, mime, &audio, &fields);
+    let mut request = proxy::http_client(app)
+        .post(&settings.endpoint)
+        .timeout(TRANSCRIBE_TIMEOUT)
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .body(body);
+    if let Some(key) = api_key.filter(|k| !k.trim().is_empty()) {
+        request = request.bearer_auth(key.trim());
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Transcription failed ({}): {}",
+            status,
+            detail.chars().take(300).collect::<String>()
+        ));
+    }
+    let parsed: SttResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected transcription response: {}", e))?;
+    Ok(parsed.text.trim().to_string())
+}
+
+#[tauri::command]
+pub fn get_stt_settings(app: tauri::AppHandle) -> Result<SttSettings, String> {
+    load_json::<SttSettings>(&app, STT_SETTINGS_FILE)
+}
+
+#[tauri::command]
+pub fn set_stt_settings(
+    app: tauri::AppHandle,
+    settings: SttSettings,
+) -> Result<SttSettings, String> {
+    let mut settings = settings;
+    settings.endpoint = settings.endpoint.trim().to_string();
+    if !settings.endpoint.starts_with("https://") && !settings.endpoint.starts_with("http://") {
+        return Err("Transcription endpoint must be an http(s) URL".to_string());
+    }
+    settings.language = settings
+        .language
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty());
+    settings.key_provider = match settings.key_provider.trim() {
+        "" => default_key_provider(),
+        provider => provider.to_string(),
+    };
+    save_json(&app, STT_SETTINGS_FILE, &settings)?;
+    Ok(settings)
+}
+
+/// Transcribes audio for the composer: either `audio` recorded by the webview (named
+/// `file_name`, e.g. `recording.webm`) or an existing file at `path`. The Whisper API is
+/// called with the key saved for the settings' `keyProvider`.
+#[tauri::command]
+pub async fn transcribe_audio(
+    app: tauri::AppHandle,
+    path: Option<String>,
+    audio: Option<Vec<u8>>,
+    file_name: Option<String>,
+) -> Result<String, String> {
+    let settings = load_json::<SttSettings>(&app, STT_SETTINGS_FILE)?;
+    let (file_name, audio) = match (path, audio) {
+        (Some(path), None) => {
+            let size = std::fs::metadata(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path, e))?
+                .len();
+            if size as usize > MAX_AUDIO_BYTES {
+                return Err("Audio files are limited to 25 MB".to_string());
+            }
+            let audio =
+                std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let name = Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            (name, audio)
+        }
+        (None, Some(audio)) => (
+            file_name.unwrap_or_else(|| "recording.webm".to_string()),
+            audio,
+        ),
+        _ => return Err("Pass either a file path or recorded audio".to_string()),
+    };
+    if audio.is_empty() {
+        return Err("The recording is empty".to_string());
+    }
+    if audio.len() > MAX_AUDIO_BYTES {
+        return Err("Audio files are limited to 25 MB".to_string());
+    }
+    let api_key = match settings.backend {
+        SttBackend::WhisperApi => Some(secrets::resolve_api_key(
+            &app,
+            Some(&settings.key_provider),
+            None,
+            None,
+        )?),
+        SttBackend::WhisperCpp => None,
+    };
+    transcribe(&app, &settings, api_key.as_deref(), &file_name, audio).await
+}
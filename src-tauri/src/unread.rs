@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, WindowEvent};
+
+use crate::clock::now_millis;
+use crate::storage::{Storage, UnreadSummary};
+
+/// What each window is showing, as last reported by its frontend.
+#[derive(Debug, Clone)]
+struct ConversationView {
+    conversation_id: String,
+    /// Scrolled to the newest message
+    at_bottom: bool,
+}
+
+/// Open conversation views keyed by window label.
+#[derive(Default)]
+pub(crate) struct ReadTracker(Mutex<HashMap<String, ConversationView>>);
+
+fn is_focused(app: &tauri::AppHandle, label: &str) -> bool {
+    app.get_webview_window(label)
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Pushes the unread total to the dock badge and emits `unread:changed`.
+pub(crate) fn refresh_badge(app: &tauri::AppHandle) {
+    let summary = match app.state::<Storage>().unread_summary() {
+        Ok(summary) => summary,
+        Err(err) => {
+            eprintln!("[unread] failed to count unread messages: {}", err);
+            return;
+        }
+    };
+    // The dock badge is app-wide, so any window can set it
+    if let Some(window) = app.webview_windows().values().next() {
+        let count = (summary.total > 0).then_some(summary.total);
+        let _ = window.set_badge_count(count);
+    }
+    let _ = app.emit("unread:changed", summary);
+}
+
+fn mark_read(app: &tauri::AppHandle, conversation_id: &str) {
+    match app
+        .state::<Storage>()
+        .mark_read(conversation_id, now_millis())
+    {
+        Ok(true) => refresh_badge(app),
+        Ok(false) => {}
+        Err(err) => eprintln!("[unread] failed to mark conversation read: {}", err),
+    }
+}
+
+/// Marks the window's conversation read when it is focused and scrolled to the bottom.
+fn mark_if_visible(app: &tauri::AppHandle, label: &str) {
+    let view = app
+        .state::<ReadTracker>()
+        .0
+        .lock()
+        .unwrap()
+        .get(label)
+        .cloned();
+    if let Some(view) = view.filter(|v| v.at_bottom) {
+        if is_focused(app, label) {
+            mark_read(app, &view.conversation_id);
+        }
+    }
+}
+
+/// Called after a reply is stored: it is read at once if a focused window is showing it.
+pub(crate) fn on_message_stored(app: &tauri::AppHandle, conversation_id: &str) {
+    let watching: Vec<String> = app
+        .state::<ReadTracker>()
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, v)| v.conversation_id == conversation_id && v.at_bottom)
+        .map(|(label, _)| label.clone())
+        .collect();
+    if watching.iter().any(|label| is_focused(app, label)) {
+        mark_read(app, conversation_id);
+    } else {
+        refresh_badge(app);
+    }
+}
+
+pub(crate) fn handle_window_event(window: &tauri::Window, event: &WindowEvent) {
+    let app = window.app_handle();
+    match event {
+        WindowEvent::Focused(true) => mark_if_visible(app, window.label()),
+        WindowEvent::Destroyed => {
+            app.state::<ReadTracker>()
+                .0
+                .lock()
+                .unwrap()
+                .remove(window.label());
+        }
+        _ => {}
+    }
+}
+
+/// Reports which conversation the calling window shows and whether it is scrolled to
+/// the bottom; pass no id when the window leaves the conversation view.
+#[tauri::command]
+pub fn report_conversation_view(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    conversation_id: Option<String>,
+    at_bottom: bool,
+) {
+    let label = window.label().to_string();
+    {
+        let tracker = app.state::<ReadTracker>();
+        let mut views = tracker.0.lock().unwrap();
+        match conversation_id {
+            Some(conversation_id) => {
+                views.insert(
+                    label.clone(),
+                    ConversationView {
+                        conversation_id,
+                        at_bottom,
+                    },
+                );
+            }
+            None => {
+                views.remove(&label);
+            }
+        }
+    }
+    mark_if_visible(&app, &label);
+}
+
+#[tauri::command]
+pub fn get_unread_summary(app: tauri::AppHandle) -> Result<UnreadSummary, String> {
+    app.state::<Storage>().unread_summary()
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::capabilities;
+use crate::clock::now_millis;
+use crate::providers::DEFAULT_PROVIDER;
+use crate::storage::{MessageUsage, Storage, StoredMessage, UsageSummary};
+use crate::timezone;
+
+/// `{event:"usage", requestId, model, inputTokens, outputTokens}` from the sidecar.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReportedUsage {
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    input_tokens: i64,
+    output_tokens: i64,
+}
+
+/// Usage reported by the sidecar, held until `send_message` stores the reply.
+#[derive(Default)]
+pub(crate) struct UsageReports(Mutex<HashMap<String, ReportedUsage>>);
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageRange {
+    Day,
+    Week,
+    Month,
+    All,
+}
+
+/// Provider, model and day of a report row, each `None` when not grouped by.
+type GroupKey = (Option<String>, Option<String>, Option<String>);
+
+/// A dimension of `get_usage_report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroup {
+    Provider,
+    Model,
+    /// Local calendar day
+    Day,
+}
+
+/// Totals for one combination of the requested groups; ungrouped fields are `None`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReportRow {
+    /// `None` for usage recorded before providers were tracked
+    provider: Option<String>,
+    model: Option<String>,
+    /// `YYYY-MM-DD` in the user's zone
+    day: Option<String>,
+    messages: i64,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    cost_usd: f64,
+    /// Messages whose model had no known price
+    unpriced_messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    since: Option<i64>,
+    group_by: Vec<UsageGroup>,
+    total: UsageReportRow,
+    /// By day first when grouped by day, then by cost, highest first
+    rows: Vec<UsageReportRow>,
+}
+
+impl UsageReportRow {
+    fn add(&mut self, prompt_tokens: i64, completion_tokens: i64, cost_usd: Option<f64>) {
+        self.messages += 1;
+        self.prompt_tokens += prompt_tokens;
+        self.completion_tokens += completion_tokens;
+        match cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_messages += 1,
+        }
+    }
+}
+
+pub(crate) fn handle_report(app: &tauri::AppHandle, report: ReportedUsage) {
+    if let Some(request_id) = report.request_id.clone() {
+        app.state::<UsageReports>()
+            .0
+            .lock()
+            .unwrap()
+            .insert(request_id, report);
+    }
+}
+
+/// Rough tokenizer-free count (~4 characters per token) for providers that report nothing.
+pub(crate) fn estimate_tokens(text: &str) -> i64 {
+    (text.chars().count() as i64 + 3) / 4
+}
+
+fn estimate_cost(model: &str, prompt_tokens: i64, completion_tokens: i64) -> Option<f64> {
+    let info = capabilities::lookup(model);
+    let input = info.input_price_per_million?;
+    let output = info.output_price_per_million?;
+    Some((prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0)
+}
+
+pub(crate) fn take_report(
+    app: &tauri::AppHandle,
+    request_id: Option<&str>,
+) -> Option<ReportedUsage> {
+    let request_id = request_id?;
+    app.state::<UsageReports>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(request_id)
+}
+
+/// Stores token counts and estimated cost for an assistant reply, preferring the
+/// provider's own numbers and falling back to a local estimate.
+pub(crate) fn record_for_message(
+    app: &tauri::AppHandle,
+    message: &StoredMessage,
+    reported: Option<ReportedUsage>,
+    provider: Option<&str>,
+    model: &str,
+    prompt: &str,
+) {
+    let (model, prompt_tokens, completion_tokens, estimated) = match &reported {
+        Some(report) => (
+            report.model.as_deref().unwrap_or(model),
+            report.input_tokens,
+            report.output_tokens,
+            false,
+        ),
+        None => (
+            model,
+            estimate_tokens(prompt),
+            estimate_tokens(&message.content),
+            true,
+        ),
+    };
+
+    let usage = MessageUsage {
+        provider: provider.unwrap_or(DEFAULT_PROVIDER),
+        model,
+        prompt_tokens,
+        completion_tokens,
+        cost_usd: estimate_cost(model, prompt_tokens, completion_tokens),
+        estimated,
+    };
+    if let Err(err) = app.state::<Storage>().record_usage(message, &usage) {
+        eprintln!("[usage] failed to record usage: {}", err);
+    }
+}
+
+/// Start of `range`, following local calendar days so "today" starts at the user's midnight.
+fn range_start(range: Option<UsageRange>) -> Option<i64> {
+    let now = now_millis();
+    match range.unwrap_or(UsageRange::Month) {
+        UsageRange::Day => Some(timezone::local_day_start(now, 0)),
+        UsageRange::Week => Some(timezone::local_day_start(now, 6)),
+        UsageRange::Month => Some(timezone::local_day_start(now, 29)),
+        UsageRange::All => None,
+    }
+}
+
+#[tauri::command]
+pub fn get_usage_summary(
+    app: tauri::AppHandle,
+    range: Option<UsageRange>,
+) -> Result<UsageSummary, String> {
+    app.state::<Storage>().usage_summary(range_start(range))
+}
+
+/// Tokens and cost over `range` (default: the last 30 days), broken down by any of
+/// provider, model and day (default: provider).
+#[tauri::command]
+pub fn get_usage_report(
+    app: tauri::AppHandle,
+    range: Option<UsageRange>,
+    group_by: Option<Vec<UsageGroup>>,
+) -> Result<UsageReport, String> {
+    let since = range_start(range);
+    let mut group_by = group_by.unwrap_or_else(|| vec![UsageGroup::Provider]);
+    group_by.dedup();
+    let by = |group| group_by.contains(&group);
+
+    let mut total = UsageReportRow::default();
+    let mut groups: HashMap<GroupKey, UsageReportRow> = HashMap::new();
+    for row in app.state::<Storage>().usage_rows(since)? {
+        total.add(row.prompt_tokens, row.completion_tokens, row.cost_usd);
+        // Days are bucketed here rather than in SQL so they follow the local zone across DST
+        let key = (
+            row.provider.filter(|_| by(UsageGroup::Provider)),
+            Some(row.model).filter(|_| by(UsageGroup::Model)),
+            by(UsageGroup::Day).then(|| timezone::local_date(row.created_at)),
+        );
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| UsageReportRow {
+                provider: key.0,
+                model: key.1,
+                day: key.2,
+                ..UsageReportRow::default()
+            })
+            .add(row.prompt_tokens, row.completion_tokens, row.cost_usd);
+    }
+
+    let mut rows: Vec<UsageReportRow> = groups.into_values().collect();
+    rows.sort_by(|a, b| {
+        a.day
+            .cmp(&b.day)
+            .then(b.cost_usd.total_cmp(&a.cost_usd))
+            .then(b.prompt_tokens.cmp(&a.prompt_tokens))
+    });
+    Ok(UsageReport {
+        since,
+        group_by,
+        total,
+        rows,
+    })
+}
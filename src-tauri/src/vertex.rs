@@ -0,0 +1,233 @@
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::Mutex;
+
+use crate::app_data::{load_json, save_json};
+use crate::clock::now_millis;
+use crate::providers::ProviderKind;
+
+const VERTEX_SETTINGS_FILE: &str = "vertex.json";
+const DEFAULT_LOCATION: &str = "us-central1";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+// Google rejects assertions that live longer than an hour
+const ASSERTION_LIFETIME_SECS: i64 = 3600;
+const TOKEN_TIMEOUT: Duration = Duration::from_secs(15);
+// Tokens are refreshed this long before they expire so a running request never outlives one
+const EXPIRY_MARGIN_MS: i64 = 5 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VertexSettings {
+    /// Service-account JSON key; `GOOGLE_APPLICATION_CREDENTIALS` when absent
+    #[serde(default)]
+    credentials_path: Option<String>,
+    /// Google Cloud project; the service account's own project when absent
+    #[serde(default)]
+    project_id: Option<String>,
+    /// Vertex AI region, or `global`
+    #[serde(default)]
+    location: Option<String>,
+}
+
+/// The fields of a service-account key file that minting a token needs.
+#[derive(Debug, Deserialize)]
+struct ServiceAccount {
+    #[serde(rename = "type")]
+    kind: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    private_key_id: Option<String>,
+    #[serde(default)]
+    project_id: Option<String>,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Debug, Clone)]
+struct AccessToken {
+    token: String,
+    /// Unix millis after which a new token must be minted
+    valid_until: i64,
+}
+
+/// The last token minted, by service account. Held across the token request so
+/// concurrent sends share one refresh.
+#[derive(Default)]
+pub(crate) struct VertexTokenCache(Mutex<Option<(String, AccessToken)>>);
+
+fn credentials_path(settings: &VertexSettings) -> Result<PathBuf, String> {
+    settings
+        .credentials_path
+        .clone()
+        .or_else(|| std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok())
+        .filter(|p| !p.trim().is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            "No Vertex AI service account configured. Choose a key file or set GOOGLE_APPLICATION_CREDENTIALS.".to_string()
+        })
+}
+
+fn load_service_account(path: &PathBuf) -> Result<ServiceAccount, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let account: ServiceAccount = serde_json::from_str(&text)
+        .map_err(|e| format!("Not a service-account key file: {}", e))?;
+    if account.kind != "service_account" {
+        return Err(format!(
+            "Expected a service-account key, found type {}",
+            account.kind
+        ));
+    }
+    Ok(account)
+}
+
+/// Builds the OpenAI-compatible endpoint for the configured project and location.
+pub(crate) fn runtime_base_url(app: &tauri::AppHandle) -> Result<String, String> {
+    let settings = load_json::<VertexSettings>(app, VERTEX_SETTINGS_FILE)?;
+    let project = match settings.project_id.clone() {
+        Some(project) => project,
+        None => load_service_account(&credentials_path(&settings)?)?
+            .project_id
+            .ok_or("Set a Google Cloud project for Vertex AI")?,
+    };
+    let location = settings.location.as_deref().unwrap_or(DEFAULT_LOCATION);
+    let host = match location {
+        "global" => "aiplatform.googleapis.com".to_string(),
+        region => format!("{}-aiplatform.googleapis.com", region),
+    };
+    Ok(format!(
+        "https://{}/v1beta1/projects/{}/locations/{}/endpoints/openapi",
+        host, project, location
+    ))
+}
+
+/// Exchanges a self-signed JWT for an access token (RFC 7523).
+async fn mint(app: &tauri::AppHandle, account: &ServiceAccount) -> Result<AccessToken, String> {
+    let token_uri = account.token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+    let now = now_millis() / 1000;
+    let claims = Claims {
+        iss: &account.client_email,
+        scope: SCOPE,
+        aud: token_uri,
+        iat: now,
+        exp: now + ASSERTION_LIFETIME_SECS,
+    };
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = account.private_key_id.clone();
+    let key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+        .map_err(|e| format!("Invalid service-account private key: {}", e))?;
+    let assertion = jsonwebtoken::encode(&header, &claims, &key)
+        .map_err(|e| format!("Failed to sign the token request: {}", e))?;
+
+    let response = crate::proxy::http_client(app)
+        .post(token_uri)
+        .timeout(TOKEN_TIMEOUT)
+        .form(&[
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("Token request failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let detail: String = response
+            .text()
+            .await
+            .unwrap_or_default()
+            .chars()
+            .take(300)
+            .collect();
+        return Err(format!("Token request failed ({}): {}", status, detail));
+    }
+    let token: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected token response: {}", e))?;
+    Ok(AccessToken {
+        token: token.access_token,
+        valid_until: now_millis() + token.expires_in * 1000 - EXPIRY_MARGIN_MS,
+    })
+}
+
+/// A valid access token for the configured service account, minted when the cached one
+/// is missing or about to expire.
+pub(crate) async fn access_token(app: &tauri::AppHandle) -> Result<String, String> {
+    let settings = load_json::<VertexSettings>(app, VERTEX_SETTINGS_FILE)?;
+    let account = load_service_account(&credentials_path(&settings)?)?;
+    let cache = app.state::<VertexTokenCache>();
+    let mut cached = cache.0.lock().await;
+    if let Some((email, token)) = cached.as_ref() {
+        if *email == account.client_email && token.valid_until > now_millis() {
+            return Ok(token.token.clone());
+        }
+    }
+    let token = mint(app, &account).await?;
+    *cached = Some((account.client_email, token.clone()));
+    Ok(token.token)
+}
+
+/// Swaps the placeholder key for a fresh access token when `provider` is Vertex AI.
+pub(crate) async fn authorize(
+    app: &tauri::AppHandle,
+    provider: Option<&str>,
+    api_key: String,
+) -> Result<String, String> {
+    match ProviderKind::of(provider) {
+        ProviderKind::Vertex => access_token(app).await,
+        _ => Ok(api_key),
+    }
+}
+
+#[tauri::command]
+pub fn get_vertex_settings(app: tauri::AppHandle) -> Result<VertexSettings, String> {
+    load_json::<VertexSettings>(&app, VERTEX_SETTINGS_FILE)
+}
+
+/// Saves the settings after checking the key file, and drops any cached token.
+#[tauri::command]
+pub async fn set_vertex_settings(
+    app: tauri::AppHandle,
+    settings: VertexSettings,
+) -> Result<VertexSettings, String> {
+    let trim = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let settings = VertexSettings {
+        credentials_path: trim(settings.credentials_path),
+        project_id: trim(settings.project_id),
+        location: trim(settings.location),
+    };
+    if let Some(location) = &settings.location {
+        if !location
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(format!("Invalid Vertex AI location: {}", location));
+        }
+    }
+    if let Some(path) = &settings.credentials_path {
+        load_service_account(&PathBuf::from(path))?;
+    }
+    save_json(&app, VERTEX_SETTINGS_FILE, &settings)?;
+    *app.state::<VertexTokenCache>().0.lock().await = None;
+    Ok(settings)
+}
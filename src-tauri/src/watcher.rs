@@ -0,0 +1,193 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::semantic_index;
+use crate::workspace::{is_always_skipped, relative_path, workspace_root};
+
+// Changes are flushed once the workspace has been quiet this long...
+const QUIET_PERIOD: Duration = Duration::from_millis(300);
+// ...or after this long at most, so a long-running build still reports progress
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+// A branch switch can touch thousands of files; the explorer reloads instead
+const MAX_PATHS_PER_EVENT: usize = 1_000;
+
+/// Active watchers by canonical workspace root. Dropping one stops its debounce thread.
+#[derive(Default)]
+pub(crate) struct WorkspaceWatchers(Mutex<HashMap<PathBuf, RecommendedWatcher>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// Paths changed in one debounce window, emitted as `workspace:changed`. Paths are
+/// relative to the workspace root.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceChanged {
+    workspace: String,
+    created: Vec<String>,
+    modified: Vec<String>,
+    deleted: Vec<String>,
+    /// Whether paths were left out; the whole tree should be reloaded
+    truncated: bool,
+}
+
+/// Folds a new change for a path into what the window already saw for it.
+fn merge(previous: Option<Change>, next: Change) -> Option<Change> {
+    match (previous, next) {
+        // A file that came and went inside one window never existed as far as anyone knows
+        (Some(Change::Created), Change::Deleted) => None,
+        (Some(Change::Created), _) => Some(Change::Created),
+        // Deleted and recreated, as editors do when saving atomically
+        (Some(Change::Deleted), Change::Created) => Some(Change::Modified),
+        (_, next) => Some(next),
+    }
+}
+
+fn classify(event: &Event, path: &Path) -> Option<Change> {
+    match event.kind {
+        EventKind::Create(_) => Some(Change::Created),
+        EventKind::Remove(_) => Some(Change::Deleted),
+        // Renames arrive as one event per side; whether the path exists tells which
+        EventKind::Modify(ModifyKind::Name(_)) => Some(if path.exists() {
+            Change::Created
+        } else {
+            Change::Deleted
+        }),
+        EventKind::Modify(_) | EventKind::Any => Some(Change::Modified),
+        EventKind::Access(_) | EventKind::Other => None,
+    }
+}
+
+fn gitignore(root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    builder.add(root.join(".gitignore"));
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
+
+fn is_ignored(root: &Path, ignore: &Gitignore, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return true;
+    };
+    relative
+        .components()
+        .any(|c| is_always_skipped(c.as_os_str()))
+        || ignore
+            .matched_path_or_any_parents(relative, path.is_dir())
+            .is_ignore()
+}
+
+fn emit_batch(app: &tauri::AppHandle, root: &Path, batch: HashMap<PathBuf, Change>) {
+    semantic_index::refresh(app, root, batch.keys().cloned().collect());
+    let mut payload = WorkspaceChanged {
+        workspace: root.to_string_lossy().into_owned(),
+        truncated: batch.len() > MAX_PATHS_PER_EVENT,
+        ..WorkspaceChanged::default()
+    };
+    let mut paths: Vec<_> = batch.into_iter().collect();
+    paths.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, change) in paths.into_iter().take(MAX_PATHS_PER_EVENT) {
+        let path = relative_path(root, &path);
+        match change {
+            Change::Created => payload.created.push(path),
+            Change::Modified => payload.modified.push(path),
+            Change::Deleted => payload.deleted.push(path),
+        }
+    }
+    let _ = app.emit("workspace:changed", payload);
+}
+
+/// Collects raw filesystem events into debounced batches until the watcher is dropped.
+fn debounce(app: tauri::AppHandle, root: PathBuf, events: mpsc::Receiver<Event>) {
+    let ignore = gitignore(&root);
+    let mut batch: HashMap<PathBuf, Change> = HashMap::new();
+    let add = |batch: &mut HashMap<PathBuf, Change>, event: Event| {
+        for path in &event.paths {
+            if is_ignored(&root, &ignore, path) {
+                continue;
+            }
+            let Some(change) = classify(&event, path) else {
+                continue;
+            };
+            match merge(batch.get(path).copied(), change) {
+                Some(change) => batch.insert(path.clone(), change),
+                None => batch.remove(path),
+            };
+        }
+    };
+
+    while let Ok(first) = events.recv() {
+        let started = Instant::now();
+        add(&mut batch, first);
+        let disconnected = loop {
+            let wait = QUIET_PERIOD.min(MAX_BATCH_DELAY.saturating_sub(started.elapsed()));
+            match events.recv_timeout(wait) {
+                Ok(event) => add(&mut batch, event),
+                Err(RecvTimeoutError::Timeout) => break false,
+                Err(RecvTimeoutError::Disconnected) => break true,
+            }
+            if started.elapsed() >= MAX_BATCH_DELAY {
+                break false;
+            }
+        };
+        if !batch.is_empty() {
+            emit_batch(&app, &root, std::mem::take(&mut batch));
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// Starts emitting `workspace:changed` for edits under `path`, including ones made in
+/// other editors. Gitignored files and build output are left out. Watching a workspace
+/// that is already watched does nothing. Returns the canonical workspace root.
+#[tauri::command]
+pub fn watch_workspace(app: tauri::AppHandle, path: String) -> Result<String, String> {
+    let root = workspace_root(&path)?;
+    let watchers = app.state::<WorkspaceWatchers>();
+    let mut watchers = watchers.0.lock().unwrap();
+    if !watchers.contains_key(&root) {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) => {
+                    let _ = tx.send(event);
+                }
+                Err(err) => eprintln!("[watcher] {}", err),
+            })
+            .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+        let (app, thread_root) = (app.clone(), root.clone());
+        std::thread::spawn(move || debounce(app, thread_root, rx));
+        watchers.insert(root.clone(), watcher);
+    }
+    Ok(root.to_string_lossy().into_owned())
+}
+
+/// Stops watching `path`. Returns `false` if it wasn't watched.
+#[tauri::command]
+pub fn unwatch_workspace(app: tauri::AppHandle, path: String) -> Result<bool, String> {
+    let root = workspace_root(&path)?;
+    Ok(app
+        .state::<WorkspaceWatchers>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&root)
+        .is_some())
+}
@@ -0,0 +1,263 @@
+use ignore::WalkBuilder;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Directories that are noise for every workspace scan, even without a .gitignore
+const ALWAYS_SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target", "dist", ".next", "__pycache__"];
+
+pub(crate) const MAX_WORKSPACE_ROOTS: usize = 10;
+
+/// Whether `name` is one of the directories every scan skips.
+pub(crate) fn is_always_skipped(name: &std::ffi::OsStr) -> bool {
+    name.to_str()
+        .is_some_and(|name| ALWAYS_SKIPPED_DIRS.contains(&name))
+}
+
+pub(crate) fn workspace_root(workspace: &str) -> Result<PathBuf, String> {
+    let root = PathBuf::from(workspace);
+    if !root.is_dir() {
+        return Err(format!("Workspace not found: {}", workspace));
+    }
+    root.canonicalize()
+        .map_err(|e| format!("Failed to resolve workspace: {}", e))
+}
+
+/// The roots of a multi-root workspace: `primary` first, then the `extra` folders, all
+/// canonical and without duplicates.
+pub(crate) fn workspace_roots(primary: &str, extra: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut roots = vec![workspace_root(primary)?];
+    for root in extra.iter().filter(|r| !r.trim().is_empty()) {
+        let root = workspace_root(root)?;
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    if roots.len() > MAX_WORKSPACE_ROOTS {
+        return Err(format!(
+            "A workspace can have at most {} folders",
+            MAX_WORKSPACE_ROOTS
+        ));
+    }
+    Ok(roots)
+}
+
+/// Resolves `path` (relative to `root`, or absolute) to where it really points, following
+/// symlinks, and rejects anything outside `root`, which must be canonical. The file itself
+/// may not exist yet; then its nearest existing ancestor is what gets checked. Every
+/// command touching a workspace file goes through this.
+pub(crate) fn resolve_workspace_path(root: &Path, path: &str) -> Result<PathBuf, String> {
+    let requested = Path::new(path.trim());
+    if requested.as_os_str().is_empty() {
+        return Err("Path is empty".to_string());
+    }
+    let outside = || format!("Path is outside the workspace: {}", path);
+    let joined = root.join(requested);
+    let mut existing = joined.as_path();
+    let mut missing = Vec::new();
+    let mut resolved = loop {
+        match existing.canonicalize() {
+            Ok(real) => break real,
+            // A dangling symlink would let a write land wherever it points
+            Err(_) if existing.symlink_metadata().is_ok() => {
+                return Err(format!("Path is a broken symlink: {}", path))
+            }
+            Err(_) => {
+                // `file_name` is `None` for `..`, so the missing part can't climb back out
+                let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                    return Err(outside());
+                };
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+        }
+    };
+    for name in missing.into_iter().rev() {
+        resolved.push(name);
+    }
+    if !resolved.starts_with(root) || resolved == root {
+        return Err(outside());
+    }
+    Ok(resolved)
+}
+
+/// Walks regular files under `root`, honoring .gitignore and skipping hidden files.
+pub(crate) fn walk_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    walk(root, false)
+}
+
+/// `walk_files` with hidden files and folders, such as `.env` and `.github/`, included.
+pub(crate) fn walk_all_files(root: &Path) -> impl Iterator<Item = PathBuf> {
+    walk(root, true)
+}
+
+fn walk(root: &Path, include_hidden: bool) -> impl Iterator<Item = PathBuf> {
+    WalkBuilder::new(root)
+        .hidden(!include_hidden)
+        .git_ignore(true)
+        .require_git(false)
+        .filter_entry(|entry| !is_always_skipped(entry.file_name()))
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.into_path())
+}
+
+pub(crate) fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// A file is treated as binary if its first 8KB contain a NUL byte.
+pub(crate) fn is_binary_file(path: &Path) -> bool {
+    let mut buf = [0u8; 8192];
+    match File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(n) => buf[..n].contains(&0),
+        Err(_) => true,
+    }
+}
+
+/// Runs `git -C <root> <args>`, returning stdout on success.
+pub(crate) fn run_git(root: &Path, args: &[&str]) -> Option<String> {
+    git_output(root, args).ok()
+}
+
+/// `run_git` that reports git's own message when the command fails.
+pub(crate) fn git_output(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.trim();
+        return Err(if message.is_empty() {
+            format!("git {} failed", args.first().unwrap_or(&""))
+        } else {
+            message.to_string()
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_workspace_path;
+    use crate::test_support::TempDir;
+    use std::path::PathBuf;
+
+    /// A fresh directory holding `workspace/` and a sibling `outside/`.
+    struct Fixture {
+        base: PathBuf,
+        root: PathBuf,
+        _dir: TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = TempDir::new();
+            dir.write("workspace/src/main.rs", "fn main() {}");
+            dir.write("outside/secret.txt", "secret");
+            Fixture {
+                base: dir.path().to_path_buf(),
+                root: dir.path().join("workspace"),
+                _dir: dir,
+            }
+        }
+
+        fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+            resolve_workspace_path(&self.root, path)
+        }
+    }
+
+    #[test]
+    fn resolves_existing_and_missing_files() {
+        let fixture = Fixture::new();
+        assert_eq!(
+            fixture.resolve("src/main.rs").unwrap(),
+            fixture.root.join("src/main.rs")
+        );
+        assert_eq!(
+            fixture.resolve("./src/new/file.rs").unwrap(),
+            fixture.root.join("src/new/file.rs")
+        );
+    }
+
+    #[test]
+    fn rejects_empty_paths_and_the_root_itself() {
+        let fixture = Fixture::new();
+        assert!(fixture.resolve("  ").is_err());
+        assert!(fixture.resolve(".").is_err());
+        assert!(fixture.resolve("src/..").is_err());
+    }
+
+    #[test]
+    fn rejects_parent_traversal() {
+        let fixture = Fixture::new();
+        assert!(fixture.resolve("../outside/secret.txt").is_err());
+        assert!(fixture.resolve("src/../../outside/secret.txt").is_err());
+        assert!(fixture.resolve("missing/../../outside/new.txt").is_err());
+        assert!(fixture.resolve("..").is_err());
+        // Climbing out and back in lands inside the workspace again
+        assert_eq!(
+            fixture.resolve("src/../../workspace/src/main.rs").unwrap(),
+            fixture.root.join("src/main.rs")
+        );
+    }
+
+    #[test]
+    fn checks_absolute_paths_against_the_root() {
+        let fixture = Fixture::new();
+        let inside = fixture.root.join("src/main.rs");
+        assert_eq!(fixture.resolve(&inside.to_string_lossy()).unwrap(), inside);
+        let outside = fixture.base.join("outside/secret.txt");
+        assert!(fixture.resolve(&outside.to_string_lossy()).is_err());
+        assert!(fixture.resolve("/etc/passwd").is_err());
+        // A sibling whose name merely starts with the root's is still outside
+        let sibling = format!("{}-other/file.txt", fixture.root.display());
+        assert!(fixture.resolve(&sibling).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leading_outside() {
+        use std::os::unix::fs::symlink;
+
+        let fixture = Fixture::new();
+        let outside = fixture.base.join("outside");
+        symlink(&outside, fixture.root.join("linked_dir")).unwrap();
+        symlink(outside.join("secret.txt"), fixture.root.join("linked_file")).unwrap();
+        symlink(outside.join("missing.txt"), fixture.root.join("dangling")).unwrap();
+
+        assert!(fixture.resolve("linked_dir/secret.txt").is_err());
+        assert!(fixture.resolve("linked_dir/new/file.txt").is_err());
+        assert!(fixture.resolve("linked_file").is_err());
+        assert!(fixture.resolve("dangling").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_that_stay_inside() {
+        use std::os::unix::fs::symlink;
+
+        let fixture = Fixture::new();
+        symlink(fixture.root.join("src"), fixture.root.join("alias")).unwrap();
+        assert_eq!(
+            fixture.resolve("alias/main.rs").unwrap(),
+            fixture.root.join("src/main.rs")
+        );
+        // A root reached through a symlink is compared by where it really is
+        let linked_root = fixture.base.join("linked_workspace");
+        symlink(&fixture.root, &linked_root).unwrap();
+        let through_link = linked_root.join("src/main.rs");
+        assert_eq!(
+            fixture.resolve(&through_link.to_string_lossy()).unwrap(),
+            fixture.root.join("src/main.rs")
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use crate::workspace::workspace_root;
+
+/// Relative to the workspace root; meant to be committed with the project.
+const CONFIG_PATH: &str = ".ohmycowork/config.json";
+const MAX_CONFIG_BYTES: u64 = 256 * 1024;
+
+/// Agent settings a project ships in its repository.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceConfig {
+    /// Used instead of the app's model; a conversation's own model still wins
+    #[serde(default)]
+    pub(crate) model: Option<String>,
+    /// Appended to the system prompt of every message sent from the workspace
+    #[serde(default)]
+    pub(crate) system_prompt: Option<String>,
+    #[serde(flatten)]
+    pub(crate) rules: WorkspaceRules,
+}
+
+/// The part of the config the agent enforces itself, sent along with each request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceRules {
+    /// Tool names the agent may use; all of them when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    allowed_tools: Option<Vec<String>>,
+    /// Gitignore-style patterns the agent must not read or change
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignored_paths: Vec<String>,
+}
+
+impl WorkspaceRules {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.allowed_tools.is_none() && self.ignored_paths.is_empty()
+    }
+}
+
+/// The workspace's config file, or `None` when it has none.
+pub(crate) fn load(workspace: &str) -> Result<Option<WorkspaceConfig>, String> {
+    let path = workspace_root(workspace)?.join(CONFIG_PATH);
+    let size = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Failed to read {}: {}", CONFIG_PATH, err)),
+    };
+    if size > MAX_CONFIG_BYTES {
+        return Err(format!("{} is too large", CONFIG_PATH));
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", CONFIG_PATH, e))?;
+    let mut config: WorkspaceConfig =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", CONFIG_PATH, e))?;
+    config.model = config
+        .model
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    config.system_prompt = config
+        .system_prompt
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
+    Ok(Some(config))
+}
+
+/// `load` for an optional workspace, where a broken config file is reported but doesn't
+/// stop the message from being sent.
+pub(crate) fn load_or_default(workspace: Option<&str>) -> WorkspaceConfig {
+    match workspace.filter(|w| !w.trim().is_empty()).map(load) {
+        Some(Ok(Some(config))) => config,
+        Some(Err(err)) => {
+            eprintln!("[workspace-config] {}", err);
+            WorkspaceConfig::default()
+        }
+        _ => WorkspaceConfig::default(),
+    }
+}
+
+/// The settings in `.ohmycowork/config.json`, or `None` if the workspace has no such file.
+#[tauri::command]
+pub fn get_workspace_config(workspace: String) -> Result<Option<WorkspaceConfig>, String> {
+    load(&workspace)
+}
@@ -0,0 +1,460 @@
+use serde::Serialize;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::app_data::app_data_path;
+use crate::changesets::write_atomically;
+use crate::clock::now_millis;
+use crate::file_history;
+use crate::workspace::{relative_path, resolve_workspace_path, workspace_roots};
+
+const MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_WRITE_BYTES: usize = 5 * 1024 * 1024;
+const WRITE_LOG_FILE: &str = "file_writes.jsonl";
+const MAX_RANGE_BYTES: u64 = 1024 * 1024;
+const MAX_RANGE_LINES: usize = 10_000;
+// Longer lines are cut so a file without newlines cannot be read whole
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceFile {
+    /// The workspace folder the file is in
+    root: String,
+    /// Relative to `root`
+    path: String,
+    content: String,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWritten {
+    root: String,
+    path: String,
+    bytes: usize,
+    /// Whether the file didn't exist before
+    created: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRange {
+    root: String,
+    path: String,
+    /// Byte offset of `content`, moved forward past a character split by the request
+    offset: u64,
+    /// Byte offset to continue from
+    next_offset: u64,
+    content: String,
+    size: u64,
+    eof: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileLines {
+    root: String,
+    path: String,
+    /// 1-based number of the first line returned
+    start: usize,
+    lines: Vec<String>,
+    /// Byte offset where `start` begins, for switching to `read_file_range`
+    offset: u64,
+    /// Lines cut at 64 KB
+    truncated_lines: Vec<usize>,
+    size: u64,
+    eof: bool,
+}
+
+/// Resolves `path` within a multi-root workspace. A relative path is taken from the main
+/// folder; an absolute one may point into any of them. Returns the folder and the file.
+fn resolve_in_roots(
+    workspace: &str,
+    roots: &[String],
+    path: &str,
+) -> Result<(PathBuf, PathBuf), String> {
+    let roots = workspace_roots(workspace, roots)?;
+    if !Path::new(path.trim()).is_absolute() {
+        let file = resolve_workspace_path(&roots[0], path)?;
+        return Ok((roots[0].clone(), file));
+    }
+    roots
+        .into_iter()
+        .find_map(|root| {
+            resolve_workspace_path(&root, path)
+                .ok()
+                .map(|file| (root, file))
+        })
+        .ok_or_else(|| format!("Path is outside the workspace: {}", path))
+}
+
+fn log_write(app: &tauri::AppHandle, root: &Path, written: &FileWritten) {
+    let Ok(path) = app_data_path(app, WRITE_LOG_FILE) else {
+        return;
+    };
+    let entry = json!({
+        "timestamp": now_millis(),
+        "workspace": root.to_string_lossy(),
+        "path": written.path,
+        "bytes": written.bytes,
+        "created": written.created,
+    });
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = writeln!(file, "{}", entry);
+    }
+}
+
+/// Reads a UTF-8 text file inside `workspace` or one of its other `roots`, up to 5 MB.
+#[tauri::command]
+pub fn read_workspace_file(
+    workspace: String,
+    roots: Option<Vec<String>>,
+    path: String,
+) -> Result<WorkspaceFile, String> {
+    let (root, file) = resolve_in_roots(&workspace, &roots.unwrap_or_default(), &path)?;
+    let metadata =
+        std::fs::metadata(&file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    if metadata.len() > MAX_READ_BYTES {
+        return Err(format!(
+            "{} is larger than {} MB",
+            path,
+            MAX_READ_BYTES / 1024 / 1024
+        ));
+    }
+    let content = std::fs::read_to_string(&file)
+        .map_err(|e| format!("Failed to read {} as text: {}", path, e))?;
+    Ok(WorkspaceFile {
+        root: root.to_string_lossy().into_owned(),
+        path: relative_path(&root, &file),
+        content,
+        size: metadata.len(),
+    })
+}
+
+/// Writes a text file inside `workspace` or one of its other `roots`, creating missing
+/// folders. The write is atomic, recorded in the app's write log, and the previous
+/// content is kept in the file history.
+#[tauri::command]
+pub fn write_workspace_file(
+    app: tauri::AppHandle,
+    workspace: String,
+    roots: Option<Vec<String>>,
+    path: String,
+    content: String,
+) -> Result<FileWritten, String> {
+    if content.len() > MAX_WRITE_BYTES {
+        return Err(format!(
+            "Content is larger than {} MB",
+            MAX_WRITE_BYTES / 1024 / 1024
+        ));
+    }
+    let (root, file) = resolve_in_roots(&workspace, &roots.unwrap_or_default(), &path)?;
+    if file.is_dir() {
+        return Err(format!("Not a file: {}", path));
+    }
+    let created = !file.exists();
+    file_history::snapshot(&app, &file, "write")?;
+    write_atomically(&file, content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    let written = FileWritten {
+        root: root.to_string_lossy().into_owned(),
+        path: relative_path(&root, &file),
+        bytes: content.len(),
+        created,
+    };
+    log_write(&app, &root, &written);
+    Ok(written)
+}
+
+fn open_file(
+    workspace: &str,
+    roots: Option<Vec<String>>,
+    path: &str,
+) -> Result<(PathBuf, PathBuf, std::fs::File, u64), String> {
+    let (root, file) = resolve_in_roots(workspace, &roots.unwrap_or_default(), path)?;
+    let handle =
+        std::fs::File::open(&file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let metadata = handle
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    Ok((root, file, handle, metadata.len()))
+}
+
+/// Reads up to `length` bytes (at most 1 MB) from `offset` without loading the rest of
+/// the file. The text is cut to whole UTF-8 characters, though a range too short for the
+/// character it starts at still returns that one, and `nextOffset` says where the
+/// following page starts.
+#[tauri::command]
+pub fn read_file_range(
+    workspace: String,
+    roots: Option<Vec<String>>,
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<FileRange, String> {
+    if length > MAX_RANGE_BYTES {
+        return Err(format!(
+            "Ranges can be at most {} KB",
+            MAX_RANGE_BYTES / 1024
+        ));
+    }
+    let (root, file, mut handle, size) = open_file(&workspace, roots, &path)?;
+    let offset = offset.min(size);
+    // Three bytes past the range, in case its first character runs past the end
+    let mut bytes = Vec::with_capacity((length + 3).min(size - offset) as usize);
+    handle
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| handle.take(length + 3).read_to_end(&mut bytes))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let length = (length as usize).min(bytes.len());
+
+    // Skip the tail of a character that started before the range...
+    let skip = bytes[..length]
+        .iter()
+        .take(3)
+        .take_while(|b| (**b & 0xC0) == 0x80)
+        .count();
+    // ...and leave one that runs past its end for the next page
+    let mut end = match std::str::from_utf8(&bytes[skip..length]) {
+        Err(err) if err.error_len().is_none() => skip + err.valid_up_to(),
+        _ => length,
+    };
+    // When nothing else fits, return it anyway: paging by this length would never get past it
+    if end == skip && skip < length {
+        let width = match bytes[skip] {
+            0xF0.. => 4,
+            0xE0.. => 3,
+            0xC0.. => 2,
+            _ => 1,
+        };
+        end = (skip + width).min(bytes.len());
+    }
+    let content = String::from_utf8_lossy(&bytes[skip..end]).into_owned();
+    let next_offset = offset + end as u64;
+    Ok(FileRange {
+        root: root.to_string_lossy().into_owned(),
+        path: relative_path(&root, &file),
+        offset: offset + skip as u64,
+        next_offset,
+        content,
+        size,
+        eof: next_offset >= size,
+    })
+}
+
+/// Returns lines `start` to `end` (1-based, inclusive, at most 10,000), reading the file
+/// one line at a time.
+#[tauri::command]
+pub fn read_file_lines(
+    workspace: String,
+    roots: Option<Vec<String>>,
+    path: String,
+    start: usize,
+    end: usize,
+) -> Result<FileLines, String> {
+    let start = start.max(1);
+    if end < start {
+        return Err(format!("Line range {}-{} is empty", start, end));
+    }
+    if end - start >= MAX_RANGE_LINES {
+        return Err(format!(
+            "At most {} lines can be read at once",
+            MAX_RANGE_LINES
+        ));
+    }
+    let (root, file, handle, size) = open_file(&workspace, roots, &path)?;
+    let mut reader = BufReader::new(handle);
+    let mut lines = Vec::new();
+    let mut truncated_lines = Vec::new();
+    let mut offset = 0u64;
+    let mut position = 0u64;
+    let mut number = 0;
+    let mut eof = false;
+    let mut line = Vec::new();
+    while number < end {
+        // Lines before `start` are skipped without keeping them
+        let keep = number + 1 >= start;
+        let (read, cut) = read_line(&mut reader, &mut line, keep)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        if read == 0 {
+            eof = true;
+            break;
+        }
+        number += 1;
+        if number == start {
+            offset = position;
+        }
+        position += read;
+        if keep {
+            if cut {
+                truncated_lines.push(number);
+            }
+            let text = String::from_utf8_lossy(&line);
+            lines.push(text.trim_end_matches(['\n', '\r']).to_string());
+        }
+    }
+    if !eof {
+        eof = reader.fill_buf().map_or(true, |rest| rest.is_empty());
+    }
+    Ok(FileLines {
+        root: root.to_string_lossy().into_owned(),
+        path: relative_path(&root, &file),
+        start,
+        offset: if lines.is_empty() { position } else { offset },
+        lines,
+        truncated_lines,
+        size,
+        eof,
+    })
+}
+
+/// Reads through the next newline, keeping at most 64 KB of it in `line` (nothing when
+/// `keep` is false). Returns the bytes consumed and whether the line was cut.
+fn read_line(
+    reader: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    keep: bool,
+) -> std::io::Result<(u64, bool)> {
+    line.clear();
+    let mut read = 0u64;
+    let mut cut = false;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok((read, cut));
+        }
+        let (chunk, done) = match buffer.iter().position(|b| *b == b'\n') {
+            Some(at) => (&buffer[..=at], true),
+            None => (buffer, false),
+        };
+        if keep {
+            let room = MAX_LINE_BYTES.saturating_sub(line.len());
+            cut |= chunk.len() > room;
+            line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        }
+        let used = chunk.len();
+        reader.consume(used);
+        read += used as u64;
+        if done {
+            return Ok((read, cut));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_file_range, FileRange};
+    use crate::test_support::TempDir;
+
+    // One- to four-byte characters at byte offsets `a` 0, `é` 1, `€` 3, `😀` 6 and `b` 10
+    const TEXT: &str = "aé€😀b";
+
+    /// A fresh workspace holding `text.txt` with `TEXT` in it.
+    struct Fixture(TempDir);
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = TempDir::new();
+            dir.write("text.txt", TEXT);
+            Fixture(dir)
+        }
+
+        fn read(&self, offset: u64, length: u64) -> FileRange {
+            let workspace = self.0.path().to_string_lossy().into_owned();
+            read_file_range(workspace, None, "text.txt".to_string(), offset, length).unwrap()
+        }
+    }
+
+    #[test]
+    fn reads_whole_characters() {
+        let range = Fixture::new().read(0, 11);
+        assert_eq!(range.content, TEXT);
+        assert_eq!((range.offset, range.next_offset), (0, 11));
+        assert!(range.eof);
+    }
+
+    #[test]
+    fn skips_a_character_the_start_falls_inside() {
+        let fixture = Fixture::new();
+        let range = fixture.read(2, 9);
+        assert_eq!(range.content, "€😀b");
+        assert_eq!((range.offset, range.next_offset), (3, 11));
+
+        let range = fixture.read(7, 4);
+        assert_eq!(range.content, "b");
+        assert_eq!((range.offset, range.next_offset), (10, 11));
+    }
+
+    #[test]
+    fn leaves_a_character_the_end_falls_inside_for_the_next_page() {
+        let fixture = Fixture::new();
+        let range = fixture.read(0, 5);
+        assert_eq!(range.content, "aé");
+        assert_eq!((range.offset, range.next_offset), (0, 3));
+        assert!(!range.eof);
+
+        let range = fixture.read(3, 5);
+        assert_eq!(range.content, "€");
+        assert_eq!(range.next_offset, 6);
+    }
+
+    #[test]
+    fn both_ends_inside_characters() {
+        let range = Fixture::new().read(2, 6);
+        assert_eq!(range.content, "€");
+        assert_eq!((range.offset, range.next_offset), (3, 6));
+    }
+
+    #[test]
+    fn pages_add_up_to_the_file() {
+        let fixture = Fixture::new();
+        let mut content = String::new();
+        let mut offset = 0;
+        for _ in 0..TEXT.len() {
+            let range = fixture.read(offset, 4);
+            content.push_str(&range.content);
+            offset = range.next_offset;
+            if range.eof {
+                break;
+            }
+        }
+        assert_eq!(content, TEXT);
+        assert_eq!(offset, TEXT.len() as u64);
+    }
+
+    #[test]
+    fn ranges_shorter_than_a_character_return_it_whole() {
+        let fixture = Fixture::new();
+        let range = fixture.read(3, 1);
+        assert_eq!(range.content, "€");
+        assert_eq!((range.offset, range.next_offset), (3, 6));
+
+        let range = fixture.read(6, 2);
+        assert_eq!(range.content, "😀");
+        assert_eq!((range.offset, range.next_offset), (6, 10));
+
+        let mut content = String::new();
+        let mut offset = 0;
+        for _ in 0..TEXT.len() {
+            let range = fixture.read(offset, 1);
+            content.push_str(&range.content);
+            offset = range.next_offset;
+            if range.eof {
+                break;
+            }
+        }
+        assert_eq!(content, TEXT);
+    }
+}
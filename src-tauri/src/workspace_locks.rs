@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::clock::now_millis;
+use crate::workspace::workspace_root;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceLock {
+    workspace: String,
+    /// Label of the window whose request holds the lock
+    window: String,
+    request_id: Option<String>,
+    acquired_at: i64,
+    /// Held for a fan-out whose runs all come from `window`; they join it instead of
+    /// conflicting
+    shared: bool,
+    #[serde(skip)]
+    token: String,
+}
+
+/// Advisory locks keyed by canonical workspace path. Every window of the app shares
+/// this process, so an in-memory map is enough to keep them from overlapping.
+#[derive(Default)]
+pub(crate) struct WorkspaceLocks(Mutex<HashMap<String, WorkspaceLock>>);
+
+/// Releases the lock when the agent run finishes, unless it was force-unlocked first.
+pub(crate) struct WorkspaceLockGuard {
+    app: tauri::AppHandle,
+    key: String,
+    token: String,
+}
+
+impl Drop for WorkspaceLockGuard {
+    fn drop(&mut self) {
+        let released = {
+            let locks = self.app.state::<WorkspaceLocks>();
+            let mut locks = locks.0.lock().unwrap();
+            match locks.get(&self.key) {
+                Some(lock) if lock.token == self.token => locks.remove(&self.key),
+                _ => None,
+            }
+        };
+        if let Some(lock) = released {
+            let _ = self.app.emit("workspace:unlocked", lock);
+        }
+    }
+}
+
+fn lock_key(workspace: &str) -> String {
+    workspace_root(workspace)
+        .map(|root| root.to_string_lossy().to_string())
+        .unwrap_or_else(|_| workspace.to_string())
+}
+
+/// Takes the workspace lock for one agent run, failing if another run holds it.
+pub(crate) fn acquire(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    window: &str,
+    request_id: Option<&str>,
+) -> Result<WorkspaceLockGuard, String> {
+    take(app, workspace, window, request_id, false)
+}
+
+/// Takes the workspace lock for several runs at once, all of them from `window`.
+pub(crate) fn acquire_shared(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    window: &str,
+    request_id: Option<&str>,
+) -> Result<WorkspaceLockGuard, String> {
+    take(app, workspace, window, request_id, true)
+}
+
+fn take(
+    app: &tauri::AppHandle,
+    workspace: &str,
+    window: &str,
+    request_id: Option<&str>,
+    shared: bool,
+) -> Result<WorkspaceLockGuard, String> {
+    let key = lock_key(workspace);
+    let lock = WorkspaceLock {
+        workspace: key.clone(),
+        window: window.to_string(),
+        request_id: request_id.map(String::from),
+        acquired_at: now_millis(),
+        shared,
+        token: uuid::Uuid::new_v4().to_string(),
+    };
+    {
+        let locks = app.state::<WorkspaceLocks>();
+        let mut locks = locks.0.lock().unwrap();
+        if let Some(held) = locks.get(&key) {
+            if held.shared && held.window == window && !shared {
+                // Its guard releases nothing; the fan-out's own guard does
+                return Ok(WorkspaceLockGuard {
+                    app: app.clone(),
+                    key,
+                    token: String::new(),
+                });
+            }
+            let _ = app.emit("workspace:lock_conflict", held.clone());
+            return Err(format!(
+                "Workspace is busy with an agent run from window '{}'",
+                held.window
+            ));
+        }
+        locks.insert(key.clone(), lock.clone());
+    }
+    let _ = app.emit("workspace:locked", lock.clone());
+    Ok(WorkspaceLockGuard {
+        app: app.clone(),
+        key,
+        token: lock.token,
+    })
+}
+
+#[tauri::command]
+pub fn get_workspace_locks(app: tauri::AppHandle) -> Vec<WorkspaceLock> {
+    let mut locks: Vec<WorkspaceLock> = app
+        .state::<WorkspaceLocks>()
+        .0
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect();
+    locks.sort_by(|a, b| a.workspace.cmp(&b.workspace));
+    locks
+}
+
+/// Escape hatch for a lock left behind by a stuck run. The run itself keeps going.
+#[tauri::command]
+pub fn force_unlock(app: tauri::AppHandle, workspace: String) -> bool {
+    let released = app
+        .state::<WorkspaceLocks>()
+        .0
+        .lock()
+        .unwrap()
+        .remove(&lock_key(&workspace));
+    match released {
+        Some(lock) => {
+            let _ = app.emit("workspace:unlocked", lock);
+            true
+        }
+        None => false,
+    }
+}
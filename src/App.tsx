@@ -35,6 +35,7 @@ import {
 import appIcon from "@/assets/appicon.png";
 
 import { pingSidecar, sendMessage, warmupModel } from "@/services/agent";
+import { openWorkspace } from "@/services/workspace";
 import type { WorkspaceEntry } from "@/types";
 import { useSettings } from "@/hooks/useSettings";
 import { SkillsPanel } from "@/components/SkillsPanel";
@@ -152,6 +153,31 @@ type ToolStatusPayload = {
   detail?: unknown;
 };
 
+type AgentActivityState =
+  | "planning"
+  | "calling_tool"
+  | "reading_file"
+  | "writing_file"
+  | "tool_finished"
+  | "tool_failed"
+  | "idle";
+
+type AgentActivityPayload = {
+  requestId?: string | null;
+  state: AgentActivityState;
+  tool?: string | null;
+  detail: string;
+  data?: unknown;
+};
+
+const ACTIVITY_STAGES: Partial<Record<AgentActivityState, string>> = {
+  calling_tool: "tool_start",
+  reading_file: "tool_start",
+  writing_file: "tool_start",
+  tool_finished: "tool_end",
+  tool_failed: "tool_error",
+};
+
 type ToolCallPart = {
   type: "tool-call";
   toolCallId: string;
@@ -367,6 +393,7 @@ function StudioShell({
     });
     if (!selected || Array.isArray(selected)) return;
 
+    void openWorkspace(selected).catch((error) => console.error("Failed to record workspace", error));
     setWorkspaceByThreadId((prev) => ({ ...prev, [activeThreadId]: selected }));
     setExpandedPaths((prev) => ({ ...prev, [selected]: true }));
     setStudioOpen(true);
@@ -1071,13 +1098,18 @@ function App() {
     let disposed = false;
 
     const startListening = async () => {
-      const off = await listen("agent:status", (event) => {
-        const payload = event.payload as ToolStatusPayload;
-        const requestId = payload.requestId ?? null;
+      const off = await listen("agent:activity", (event) => {
+        const activity = event.payload as AgentActivityPayload;
+        const requestId = activity.requestId ?? null;
         if (!requestId) return;
         const controller = toolStreamByRequestIdRef.current[requestId];
         if (!controller) return;
-        controller.push(payload);
+        controller.push({
+          requestId,
+          stage: ACTIVITY_STAGES[activity.state] ?? null,
+          tool: activity.tool ?? null,
+          detail: activity.data,
+        });
       });
 
       if (disposed) off();
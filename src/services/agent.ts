@@ -6,28 +6,117 @@ export type ChatMessage = {
   content: string;
 };
 
+/** Azure OpenAI overrides; the deployment defaults to the model name. */
+export type AzureDeployment = {
+  deployment?: string;
+  apiVersion?: string;
+};
+
 export type AgentConfig = {
   provider: ProviderId;
-  apiKey: string;
+  /** Omit to use the key saved in the keychain for `provider`. */
+  apiKey?: string;
   model: string;
   baseUrl?: string;
+  /** Provider profile whose base URL, headers and key apply instead of the fields above. */
+  profileId?: string;
+  azure?: AzureDeployment;
+};
+
+/** Returned instead of a plain string when a request timed out or was cancelled mid-stream. */
+export type PartialResult = {
+  content: string;
+  truncated: true;
+  reason: "timeout" | "cancelled";
+};
+
+/** Returned when the configured provider was unavailable and a fallback answered instead. */
+export type FallbackResult = {
+  content: string;
+  profileId: string;
+  provider: string | null;
+  model: string;
+  failed: { provider: string | null; model: string; error: string }[];
 };
 
+type SendMessageResult = string | PartialResult | FallbackResult;
+
 export async function sendMessage(
   config: AgentConfig,
   messages: ChatMessage[],
   requestId: string,
-  workspacePath?: string | null
+  workspacePath?: string | null,
+  attachments?: string[],
+  workspaceRoots?: string[]
 ): Promise<string> {
-  return invoke<string>("send_message", {
+  const result = await invoke<SendMessageResult>("send_message", {
     provider: config.provider,
-    apiKey: config.apiKey,
+    apiKey: config.apiKey || null,
     model: config.model,
     baseUrl: config.baseUrl ?? null,
     messages,
     workspacePath: workspacePath ?? null,
+    workspaceRoots: workspaceRoots?.length ? workspaceRoots : null,
+    requestId,
+    attachments: attachments?.length ? attachments : null,
+    profileId: config.profileId ?? null,
+    azure: config.azure ?? null,
+  });
+  return formatResult(result);
+}
+
+/** In plan mode the agent only proposes changes; the host refuses tool calls that write or run anything. */
+export type ConversationMode = "plan" | "act";
+
+export async function getConversationMode(conversationId: string): Promise<ConversationMode> {
+  return invoke<ConversationMode>("get_conversation_mode", { conversationId });
+}
+
+/** Takes effect with the next message; works for new chats before their first message is saved. */
+export async function setConversationMode(conversationId: string, mode: ConversationMode): Promise<ConversationMode> {
+  return invoke<ConversationMode>("set_conversation_mode", { conversationId, mode });
+}
+
+export type AttachmentSecrets = {
+  path: string;
+  /** `envFile` values and whole `credentials` files are always redacted. */
+  sensitivity: "normal" | "envFile" | "credentials";
+  redacted: number;
+};
+
+/** Attachments holding secrets that will be redacted before sending, to warn the user first. */
+export async function checkAttachmentSecrets(workspace: string, paths: string[]): Promise<AttachmentSecrets[]> {
+  return invoke<AttachmentSecrets[]>("check_attachment_secrets", { workspace, paths });
+}
+
+function formatResult(result: SendMessageResult): string {
+  if (typeof result === "string") {
+    return result;
+  }
+  if ("failed" in result) {
+    const servedBy = [result.provider, result.model].filter(Boolean).join("/");
+    return `${result.content}\n\n_(Answered by ${servedBy}; the configured provider was unavailable.)_`;
+  }
+  return `${result.content}\n\n_(Response ${result.reason === "timeout" ? "timed out" : "was cancelled"} and may be incomplete.)_`;
+}
+
+/** Answers the last prompt again on a new branch; `model` defaults to the one that wrote the last reply. */
+export async function regenerateResponse(
+  config: Omit<AgentConfig, "model"> & { model?: string },
+  conversationId: string,
+  requestId: string,
+  workspacePath?: string | null
+): Promise<string> {
+  const result = await invoke<SendMessageResult>("regenerate_response", {
+    conversationId,
+    provider: config.provider,
+    apiKey: config.apiKey || null,
+    model: config.model ?? null,
+    baseUrl: config.baseUrl ?? null,
+    workspacePath: workspacePath ?? null,
     requestId,
   });
+  return formatResult(result);
 }
 
 export async function pingSidecar(): Promise<string> {
@@ -37,8 +126,19 @@ export async function pingSidecar(): Promise<string> {
 export async function warmupModel(config: AgentConfig): Promise<string> {
   return invoke<string>("warmup_model", {
     provider: config.provider,
-    apiKey: config.apiKey,
+    apiKey: config.apiKey || null,
     model: config.model,
     baseUrl: config.baseUrl ?? null,
   });
 }
+
+export type ToolKillReport = {
+  executionId: string;
+  tool: string | null;
+  killed: boolean;
+  partialOutput: string;
+};
+
+export async function killToolExecution(executionId: string): Promise<ToolKillReport> {
+  return invoke<ToolKillReport>("kill_tool_execution", { executionId });
+}
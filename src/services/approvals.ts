@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type Risk = "low" | "medium" | "high";
+
+export type PendingApproval = {
+  id: string;
+  command: string;
+  cwd: string;
+  risk: Risk;
+  /** Why the command got its risk, e.g. "Deletes or moves files". */
+  reasons: string[];
+  requestId?: string;
+  requestedAt: number;
+};
+
+export type ApprovalSettings = {
+  /** Commands that run without asking; `*` matches anything. High-risk commands always ask. */
+  autoApprove: string[];
+};
+
+export async function getApprovalSettings(): Promise<ApprovalSettings> {
+  return invoke<ApprovalSettings>("get_approval_settings");
+}
+
+export async function setApprovalSettings(settings: ApprovalSettings): Promise<ApprovalSettings> {
+  return invoke<ApprovalSettings>("set_approval_settings", { settings });
+}
+
+export async function listPendingApprovals(): Promise<PendingApproval[]> {
+  return invoke<PendingApproval[]>("list_pending_approvals");
+}
+
+/** Answers an `approval:required` prompt; false when it was already answered or expired. */
+export async function respondApproval(id: string, allow: boolean): Promise<boolean> {
+  return invoke<boolean>("respond_approval", { id, allow });
+}
+
+export function onApprovalRequired(handler: (approval: PendingApproval) => void): Promise<UnlistenFn> {
+  return listen<PendingApproval>("approval:required", (event) => handler(event.payload));
+}
+
+export function onApprovalResolved(handler: (event: { id: string; allowed: boolean }) => void): Promise<UnlistenFn> {
+  return listen<{ id: string; allowed: boolean }>("approval:resolved", (event) => handler(event.payload));
+}
@@ -0,0 +1,42 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** A tool the agent can call, backed by a local command. */
+export type CustomTool = {
+  id: string;
+  name: string;
+  description: string;
+  /** JSON Schema of the arguments, an object schema */
+  inputSchema: Record<string, unknown>;
+  command: string;
+  /** Where the command runs; the run's workspace when unset */
+  cwd?: string | null;
+  timeoutSecs: number;
+  enabled: boolean;
+  addedAt: number;
+};
+
+export type RegisterCustomToolOptions = {
+  name: string;
+  description: string;
+  inputSchema?: Record<string, unknown>;
+  command: string;
+  cwd?: string;
+  timeoutSecs?: number;
+  enabled?: boolean;
+};
+
+/**
+ * Registers a tool, replacing one with the same name. The command gets the call's
+ * arguments as JSON on stdin and in `OHMYCOWORK_TOOL_ARGS`; what it prints is the result.
+ */
+export async function registerCustomTool(options: RegisterCustomToolOptions): Promise<CustomTool> {
+  return invoke<CustomTool>("register_custom_tool", options);
+}
+
+export async function listCustomTools(): Promise<CustomTool[]> {
+  return invoke<CustomTool[]>("list_custom_tools");
+}
+
+export async function removeCustomTool(id: string): Promise<boolean> {
+  return invoke<boolean>("remove_custom_tool", { id });
+}
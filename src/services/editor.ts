@@ -0,0 +1,37 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type EditorKind = "vscode" | "cursor" | "jetbrains" | "sublime" | "zed";
+
+export type EditorSettings = {
+  /** Null picks the first editor found. */
+  editor: EditorKind | null;
+  /** Launcher to run instead of the editor's usual one, e.g. a full path. */
+  command: string | null;
+};
+
+export type DetectedEditor = {
+  editor: EditorKind;
+  program: string;
+};
+
+export async function getEditorSettings(): Promise<EditorSettings> {
+  return invoke<EditorSettings>("get_editor_settings");
+}
+
+export async function setEditorSettings(settings: EditorSettings): Promise<EditorSettings> {
+  return invoke<EditorSettings>("set_editor_settings", { settings });
+}
+
+export async function detectEditors(): Promise<DetectedEditor[]> {
+  return invoke<DetectedEditor[]>("detect_editors");
+}
+
+/** Opens an absolute path in the user's editor, at `line` (1-based) when given. */
+export async function openInEditor(path: string, line?: number): Promise<DetectedEditor> {
+  return invoke<DetectedEditor>("open_in_editor", { path, line: line ?? null });
+}
+
+/** Shows an absolute path selected in Finder, Explorer or the desktop's file manager. */
+export async function revealInFileManager(path: string): Promise<void> {
+  return invoke<void>("reveal_in_file_manager", { path });
+}
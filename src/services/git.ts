@@ -0,0 +1,78 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type GitChange = "modified" | "added" | "deleted" | "renamed" | "typechanged";
+
+export type GitFileStatus = {
+  /** Relative to the repository root. */
+  path: string;
+  originalPath: string | null;
+  staged: GitChange | null;
+  unstaged: GitChange | null;
+  untracked: boolean;
+  conflicted: boolean;
+};
+
+export type GitStatus = {
+  root: string;
+  /** Null on a detached HEAD. */
+  branch: string | null;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  files: GitFileStatus[];
+};
+
+export type GitDiff = {
+  diff: string;
+  truncated: boolean;
+};
+
+export type GitCommit = {
+  hash: string;
+  summary: string;
+};
+
+export async function gitStatus(workspace: string): Promise<GitStatus> {
+  return invoke<GitStatus>("git_status", { workspace });
+}
+
+/** Unstaged changes, or staged ones with `staged`; `path` is relative to the repository root. */
+export async function gitDiff(workspace: string, path?: string, staged = false): Promise<GitDiff> {
+  return invoke<GitDiff>("git_diff", { workspace, path: path ?? null, staged });
+}
+
+/** Stages and commits `paths`; with none, commits what is already staged. */
+export async function gitCommit(workspace: string, message: string, paths: string[]): Promise<GitCommit> {
+  return invoke<GitCommit>("git_commit", { workspace, message, paths });
+}
+
+export type GitBranch = {
+  /** Short name, e.g. `main` or `origin/main`. */
+  name: string;
+  remote: boolean;
+  current: boolean;
+  upstream: string | null;
+  commit: string;
+  subject: string;
+  /** Unix seconds. */
+  committedAt: number;
+};
+
+/** Local branches, then remote-tracking ones, each newest first. */
+export async function gitBranches(workspace: string): Promise<GitBranch[]> {
+  return invoke<GitBranch[]>("git_branches", { workspace });
+}
+
+/** Creates `name` at `startPoint` (HEAD by default), switching to it with `checkout`. */
+export async function gitCreateBranch(
+  workspace: string,
+  name: string,
+  startPoint?: string,
+  checkout = false,
+): Promise<string> {
+  return invoke<string>("git_create_branch", { workspace, name, startPoint: startPoint ?? null, checkout });
+}
+
+export async function gitCheckout(workspace: string, branch: string): Promise<string> {
+  return invoke<string>("git_checkout", { workspace, branch });
+}
@@ -0,0 +1,67 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type McpTransport =
+  | { type: "stdio"; command: string; args?: string[]; env?: Record<string, string>; cwd?: string | null }
+  | { type: "sse"; url: string; headers?: Record<string, string> };
+
+export type McpState = "disconnected" | "connecting" | "connected" | "failed";
+
+export type McpTool = {
+  name: string;
+  description?: string | null;
+  inputSchema: Record<string, unknown>;
+};
+
+export type McpResource = {
+  uri: string;
+  name: string;
+  description?: string | null;
+  mimeType?: string | null;
+};
+
+export type McpServerStatus = {
+  id: string;
+  name: string;
+  /** Only sent when adding a server: saved `env` and `headers` values stay on the host. */
+  transport: McpTransport;
+  /** Names of the environment variables or headers kept in the keychain. */
+  secretNames: string[];
+  enabled: boolean;
+  addedAt: number;
+  state: McpState;
+  /** Why the last connection attempt failed or the connection dropped. */
+  error: string | null;
+  tools: McpTool[];
+  resources: McpResource[];
+};
+
+export async function listMcpServers(): Promise<McpServerStatus[]> {
+  return invoke<McpServerStatus[]>("list_mcp_servers");
+}
+
+/** Saves a server and connects to it unless `enabled` is false; failures show on the status. */
+export async function addMcpServer(name: string, transport: McpTransport, enabled = true): Promise<McpServerStatus> {
+  return invoke<McpServerStatus>("add_mcp_server", { name, transport, enabled });
+}
+
+/** Connects or disconnects a server; enabling a failed one retries. */
+export async function toggleMcpServer(id: string, enabled: boolean): Promise<McpServerStatus> {
+  return invoke<McpServerStatus>("toggle_mcp_server", { id, enabled });
+}
+
+export async function removeMcpServer(id: string): Promise<boolean> {
+  return invoke<boolean>("remove_mcp_server", { id });
+}
+
+export type McpResourceContents = {
+  contents: { uri: string; mimeType?: string; text?: string; blob?: string }[];
+};
+
+export async function readMcpResource(id: string, uri: string): Promise<McpResourceContents> {
+  return invoke<McpResourceContents>("read_mcp_resource", { id, uri });
+}
+
+export function onMcpStatus(handler: (status: McpServerStatus) => void): Promise<UnlistenFn> {
+  return listen<McpServerStatus>("mcp:status", (event) => handler(event.payload));
+}
@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** A note the agent keeps about a workspace across sessions. */
+export type MemoryEntry = {
+  key: string;
+  value: string;
+  createdAt: number;
+  updatedAt: number;
+};
+
+/** Saves `value` under `key`, replacing an earlier value; it goes into every later run's system prompt. */
+export async function memorySet(workspace: string, key: string, value: string): Promise<MemoryEntry> {
+  return invoke<MemoryEntry>("memory_set", { workspace, key, value });
+}
+
+export async function memoryGet(workspace: string, key: string): Promise<MemoryEntry | null> {
+  return invoke<MemoryEntry | null>("memory_get", { workspace, key });
+}
+
+/** Entries containing every word of `query`; all of them, most recent first, when it is empty. */
+export async function memorySearch(workspace: string, query?: string, limit?: number): Promise<MemoryEntry[]> {
+  return invoke<MemoryEntry[]>("memory_search", { workspace, query, limit });
+}
+
+export async function memoryDelete(workspace: string, key: string): Promise<boolean> {
+  return invoke<boolean>("memory_delete", { workspace, key });
+}
@@ -0,0 +1,48 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type Access = "read" | "write" | "execute" | "network";
+
+export type PendingPermission = {
+  id: string;
+  tool: string;
+  access: Access;
+  args: Record<string, unknown>;
+  /** Files and folders the call touches. */
+  paths: string[];
+  workspace?: string;
+  requestId?: string;
+  requestedAt: number;
+  /** When the prompt is denied for lack of an answer. */
+  expiresAt: number;
+};
+
+export type PermissionSettings = {
+  /** Tools that run without asking. */
+  alwaysAllow: string[];
+};
+
+export async function getPermissionSettings(): Promise<PermissionSettings> {
+  return invoke<PermissionSettings>("get_permission_settings");
+}
+
+export async function setPermissionSettings(settings: PermissionSettings): Promise<PermissionSettings> {
+  return invoke<PermissionSettings>("set_permission_settings", { settings });
+}
+
+export async function listPendingPermissions(): Promise<PendingPermission[]> {
+  return invoke<PendingPermission[]>("list_pending_permissions");
+}
+
+/** Answers an `agent:permission` prompt; `remember` always allows the tool from now on. */
+export async function respondPermission(id: string, allow: boolean, remember = false): Promise<boolean> {
+  return invoke<boolean>("respond_permission", { id, allow, remember });
+}
+
+export function onPermissionRequired(handler: (prompt: PendingPermission) => void): Promise<UnlistenFn> {
+  return listen<PendingPermission>("agent:permission", (event) => handler(event.payload));
+}
+
+export function onPermissionResolved(handler: (event: { id: string; allowed: boolean }) => void): Promise<UnlistenFn> {
+  return listen<{ id: string; allowed: boolean }>("agent:permission-resolved", (event) => handler(event.payload));
+}
@@ -0,0 +1,209 @@
+import { invoke } from "@tauri-apps/api/core";
+import { AzureDeployment } from "@/services/agent";
+
+export type ModelInfo = {
+  id: string;
+  name: string | null;
+  contextLength: number | null;
+  ownedBy: string | null;
+};
+
+export type ProviderTarget = {
+  provider: string;
+  baseUrl?: string;
+  /** Omit to use the key saved in the keychain. */
+  apiKey?: string;
+};
+
+export async function listModels(target: ProviderTarget, refresh = false): Promise<ModelInfo[]> {
+  return invoke<ModelInfo[]>("list_models", {
+    provider: target.provider,
+    baseUrl: target.baseUrl ?? null,
+    apiKey: target.apiKey || null,
+    refresh,
+  });
+}
+
+export type ConnectionErrorKind =
+  | "config"
+  | "auth"
+  | "not_found"
+  | "rate_limited"
+  | "timeout"
+  | "tls"
+  | "network"
+  | "server"
+  | "invalid_response";
+
+export type ConnectionReport = {
+  ok: boolean;
+  baseUrl: string;
+  latencyMs: number | null;
+  modelCount: number | null;
+  modelAvailable: boolean | null;
+  error: { kind: ConnectionErrorKind; message: string; status: number | null } | null;
+};
+
+export async function testConnection(target: ProviderTarget, model?: string): Promise<ConnectionReport> {
+  return invoke<ConnectionReport>("test_connection", {
+    provider: target.provider,
+    baseUrl: target.baseUrl ?? null,
+    apiKey: target.apiKey || null,
+    model: model ?? null,
+  });
+}
+
+export type EndpointOverrides = {
+  /** Replaces `/chat/completions` after the base URL, e.g. `/v2/chat`. */
+  chatPath?: string | null;
+  /** Query parameters added to every request. */
+  query?: Record<string, string>;
+};
+
+/** An HTTP(S) or SOCKS5 proxy; its password is kept in the keychain. */
+export type ProxyConfig = {
+  /** `http://`, `https://`, `socks5://` or `socks5h://` with host and port. */
+  url: string;
+  username?: string | null;
+  /** Hosts reached directly: names, `.domain` suffixes, IP addresses, or `*`. */
+  bypass?: string[];
+};
+
+export async function getDefaultProxy(): Promise<ProxyConfig | null> {
+  return invoke<ProxyConfig | null>("get_default_proxy");
+}
+
+/** Omit `password` to keep the saved one; an empty string removes it. */
+export async function setDefaultProxy(proxy: ProxyConfig | null, password?: string): Promise<ProxyConfig | null> {
+  return invoke<ProxyConfig | null>("set_default_proxy", { proxy, password: password ?? null });
+}
+
+export type ProviderProfileInput = {
+  name: string;
+  provider: string;
+  baseUrl?: string | null;
+  defaultModel?: string | null;
+  headers?: Record<string, string>;
+  /** Keychain entry to use; defaults to the provider's own entry. */
+  keyRef?: string | null;
+  /** Only used by the `azure` provider. */
+  azure?: AzureDeployment | null;
+  /** For OpenAI-compatible gateways with a non-standard layout. */
+  endpoint?: EndpointOverrides | null;
+  /** Overrides the default proxy for this profile. */
+  proxy?: ProxyConfig | null;
+  /** Plain chat streamed by the app itself, without the sidecar's tools. */
+  nativeStreaming?: boolean;
+};
+
+export type ProviderProfile = Required<ProviderProfileInput> & {
+  id: string;
+  createdAt: number;
+  updatedAt: number;
+};
+
+export async function listProviderProfiles(): Promise<ProviderProfile[]> {
+  return invoke<ProviderProfile[]>("list_provider_profiles");
+}
+
+/** `apiKey`, when given, is saved to the keychain for this profile only. */
+export async function createProviderProfile(
+  profile: ProviderProfileInput,
+  apiKey?: string,
+  proxyPassword?: string
+): Promise<ProviderProfile> {
+  return invoke<ProviderProfile>("create_provider_profile", {
+    profile,
+    apiKey: apiKey || null,
+    proxyPassword: proxyPassword ?? null,
+  });
+}
+
+export async function updateProviderProfile(
+  id: string,
+  profile: ProviderProfileInput,
+  apiKey?: string,
+  proxyPassword?: string
+): Promise<ProviderProfile> {
+  return invoke<ProviderProfile>("update_provider_profile", {
+    id,
+    profile,
+    apiKey: apiKey || null,
+    proxyPassword: proxyPassword ?? null,
+  });
+}
+
+export async function deleteProviderProfile(id: string): Promise<boolean> {
+  return invoke<boolean>("delete_provider_profile", { id });
+}
+
+export type LocalProvider = {
+  provider: "ollama" | "lmstudio";
+  baseUrl: string;
+  models: ModelInfo[];
+};
+
+/** Ollama and LM Studio servers running on their default ports; they need no API key. */
+export async function discoverLocalModels(): Promise<LocalProvider[]> {
+  return invoke<LocalProvider[]>("discover_local_models");
+}
+
+/** AWS profile and region for the `bedrock` provider; both fall back to the AWS environment. */
+export type BedrockSettings = {
+  profile: string | null;
+  region: string | null;
+};
+
+export async function getBedrockSettings(): Promise<BedrockSettings> {
+  return invoke<BedrockSettings>("get_bedrock_settings");
+}
+
+export async function setBedrockSettings(settings: BedrockSettings): Promise<BedrockSettings> {
+  return invoke<BedrockSettings>("set_bedrock_settings", { settings });
+}
+
+/** Service-account key, project and location for the `vertex` provider. */
+export type VertexSettings = {
+  /** Falls back to `GOOGLE_APPLICATION_CREDENTIALS`. */
+  credentialsPath: string | null;
+  /** Falls back to the service account's own project. */
+  projectId: string | null;
+  /** Defaults to `us-central1`; `global` is also accepted. */
+  location: string | null;
+};
+
+export async function getVertexSettings(): Promise<VertexSettings> {
+  return invoke<VertexSettings>("get_vertex_settings");
+}
+
+export async function setVertexSettings(settings: VertexSettings): Promise<VertexSettings> {
+  return invoke<VertexSettings>("set_vertex_settings", { settings });
+}
+
+/** What a model supports, from the backend's registry. `matched` is null for unknown models. */
+export type ModelCapabilities = {
+  model: string;
+  matched: string | null;
+  contextWindow: number;
+  vision: boolean;
+  tools: boolean;
+  inputPricePerMillion: number | null;
+  outputPricePerMillion: number | null;
+};
+
+export async function getModelInfo(model: string): Promise<ModelCapabilities> {
+  return invoke<ModelCapabilities>("get_model_info", { model });
+}
+
+export type Embeddings = {
+  model: string;
+  dimensions: number;
+  /** One vector per input text, in order. */
+  vectors: number[][];
+  promptTokens: number | null;
+};
+
+/** Embeds `texts` with the profile's provider; `model` defaults to the provider's embedding model. */
+export async function embedTexts(profileId: string, texts: string[], model?: string): Promise<Embeddings> {
+  return invoke<Embeddings>("embed_texts", { profileId, texts, model: model ?? null });
+}
@@ -0,0 +1,40 @@
+import { ProviderId } from "@/lib/providers";
+import { invoke } from "@tauri-apps/api/core";
+
+/** Saves the key in the OS keychain; an empty key removes it. */
+export async function setApiKey(provider: ProviderId, apiKey: string): Promise<void> {
+  return invoke<void>("set_api_key", { provider, apiKey });
+}
+
+export async function hasApiKey(provider: ProviderId): Promise<boolean> {
+  return invoke<boolean>("has_api_key", { provider });
+}
+
+/** How requests spread over several keys for one provider. */
+export type RotationStrategy = "round_robin" | "on_rate_limit";
+
+/** A saved key, without the key itself. `id` is `primary` for the key set by `setApiKey`. */
+export interface KeyInfo {
+  id: string;
+  label?: string | null;
+  addedAt?: number | null;
+  /** Unix millis until which the key is skipped after a rate limit */
+  coolingUntil?: number | null;
+}
+
+export async function listApiKeys(provider: ProviderId): Promise<KeyInfo[]> {
+  return invoke<KeyInfo[]>("list_api_keys", { provider });
+}
+
+/** Registers another key for `provider`, rotated with the ones already saved. */
+export async function addApiKey(provider: ProviderId, apiKey: string, label?: string): Promise<KeyInfo> {
+  return invoke<KeyInfo>("add_api_key", { provider, apiKey, label: label ?? null });
+}
+
+export async function removeApiKey(provider: ProviderId, id: string): Promise<boolean> {
+  return invoke<boolean>("remove_api_key", { provider, id });
+}
+
+export async function setKeyRotation(provider: ProviderId, strategy: RotationStrategy): Promise<void> {
+  return invoke<void>("set_key_rotation", { provider, strategy });
+}
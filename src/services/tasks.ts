@@ -0,0 +1,163 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type TaskStatus = "queued" | "running" | "succeeded" | "failed" | "cancelled";
+
+export type TaskRun = {
+  id: string;
+  kind: "command" | "agent";
+  /** The shell command, or what the agent was asked to do. */
+  command: string;
+  cwd: string;
+  status: TaskStatus;
+  startedAt: number;
+  finishedAt: number | null;
+  /** Null while running, and when a signal ended the process. */
+  exitCode: number | null;
+  signal: number | null;
+  /** Agent tasks: the conversation holding the prompt and reply. */
+  conversationId?: string;
+  /** Agent tasks: the id their `agent:delta` and `agent:tool` events carry. */
+  requestId?: string;
+  error?: string;
+  /** Sub-tasks: the parallel task they belong to. */
+  parentId?: string;
+};
+
+/** A parallel task with the state of each of its sub-tasks. */
+export type TaskTree = TaskRun & {
+  subtasks: TaskRun[];
+};
+
+export type TaskLogEntry =
+  | { type: "text"; text: string; at: number }
+  | { type: "toolStart"; tool: string; toolUseId: string; at: number }
+  | { type: "toolEnd"; tool: string; toolUseId: string; durationMs: number; isError: boolean; at: number }
+  | { type: "status"; status: TaskStatus; message?: string; at: number };
+
+export type TaskModel = {
+  provider?: string;
+  model?: string;
+  baseUrl?: string;
+  profileId?: string;
+};
+
+export type TaskOutput = {
+  stream: "stdout" | "stderr";
+  text: string;
+};
+
+export type TaskTranscript = TaskRun & {
+  output: TaskOutput[];
+  /** The start of the output was dropped after 1 MB. */
+  truncated: boolean;
+};
+
+/** Starts a shell command in a workspace folder; follow it with `onTaskOutput` and `onTaskExit`. */
+export async function runTask(command: string, cwd: string): Promise<TaskRun> {
+  return invoke<TaskRun>("run_task", { command, cwd });
+}
+
+/** Queues an agent run in a workspace; it keeps going after the chat view closes. Follow it with `onTaskUpdate`. */
+export async function submitTask(description: string, workspace: string, model?: TaskModel): Promise<TaskRun> {
+  return invoke<TaskRun>("submit_task", { description, workspace, model });
+}
+
+/** Queues a task whose sub-tasks run at the same time, e.g. one per failing test. Follow it with `onTaskTree`. */
+export async function submitParallelTask(
+  description: string,
+  workspace: string,
+  subtasks: string[],
+  model?: TaskModel,
+): Promise<TaskTree> {
+  return invoke<TaskTree>("submit_parallel_task", { description, workspace, subtasks, model });
+}
+
+export async function getTaskTree(id: string): Promise<TaskTree> {
+  return invoke<TaskTree>("get_task_tree", { id });
+}
+
+export async function getTaskLog(id: string): Promise<TaskLogEntry[]> {
+  return invoke<TaskLogEntry[]>("get_task_log", { id });
+}
+
+export async function cancelTask(id: string): Promise<boolean> {
+  return invoke<boolean>("cancel_task", { id });
+}
+
+export async function listTasks(): Promise<TaskRun[]> {
+  return invoke<TaskRun[]>("list_tasks");
+}
+
+export async function getTaskTranscript(id: string): Promise<TaskTranscript> {
+  return invoke<TaskTranscript>("get_task_transcript", { id });
+}
+
+/** Markdown with the command, its outcome and the end of its output, to send to the agent. */
+export async function getTaskContext(id: string): Promise<string> {
+  return invoke<string>("get_task_context", { id });
+}
+
+export function onTaskOutput(handler: (event: TaskOutput & { taskId: string }) => void): Promise<UnlistenFn> {
+  return listen<TaskOutput & { taskId: string }>("task:output", (event) => handler(event.payload));
+}
+
+export function onTaskExit(handler: (run: TaskRun) => void): Promise<UnlistenFn> {
+  return listen<TaskRun>("task:exit", (event) => handler(event.payload));
+}
+
+export function onTaskUpdate(handler: (run: TaskRun) => void): Promise<UnlistenFn> {
+  return listen<TaskRun>("task:update", (event) => handler(event.payload));
+}
+
+export function onTaskTree(handler: (tree: TaskTree) => void): Promise<UnlistenFn> {
+  return listen<TaskTree>("task:tree", (event) => handler(event.payload));
+}
+
+export type ScheduleRun = {
+  /** Null when the task could not be queued. */
+  taskId: string | null;
+  startedAt: number;
+  status: TaskStatus;
+  finishedAt: number | null;
+  error?: string;
+};
+
+export type Schedule = {
+  id: string;
+  name: string;
+  /** The prompt each run gives the agent. */
+  description: string;
+  workspace: string;
+  cron: string;
+  model?: TaskModel;
+  paused: boolean;
+  createdAt: number;
+  /** Null while paused. */
+  nextRunAt: number | null;
+  lastRun: ScheduleRun | null;
+};
+
+/** Runs `description` as an agent task whenever `cron` matches in the local time zone, e.g. `0 9 * * 1-5`. */
+export async function createSchedule(
+  name: string,
+  description: string,
+  workspace: string,
+  cron: string,
+  model?: TaskModel,
+): Promise<Schedule> {
+  return invoke<Schedule>("create_schedule", { name, description, workspace, cron, model });
+}
+
+export async function listSchedules(): Promise<Schedule[]> {
+  return invoke<Schedule[]>("list_schedules");
+}
+
+/** Pauses a schedule, or resumes it with `paused: false`. */
+export async function pauseSchedule(id: string, paused = true): Promise<Schedule> {
+  return invoke<Schedule>("pause_schedule", { id, paused });
+}
+
+export async function deleteSchedule(id: string): Promise<boolean> {
+  return invoke<boolean>("delete_schedule", { id });
+}
@@ -0,0 +1,64 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type TerminalInfo = {
+  id: string;
+  /** Unique among the sessions of a workspace. */
+  name: string;
+  cwd: string;
+  /** Null when running the user's shell. */
+  command: string | null;
+  cols: number;
+  rows: number;
+  pid: number | null;
+  startedAt: number;
+  running: boolean;
+  /** Set once the session has ended; it stays listed until killed. */
+  exitCode: number | null;
+};
+
+export type TerminalSpawnOptions = {
+  /** Defaults to the first free `terminal-N`. */
+  name?: string;
+  /** Defaults to the user's shell. */
+  command?: string;
+  args?: string[];
+  cols?: number;
+  rows?: number;
+};
+
+/** Starts a pseudo-terminal in a workspace folder; follow it with `onTerminalOutput` and `onTerminalExit`. */
+export async function terminalSpawn(cwd: string, options: TerminalSpawnOptions = {}): Promise<TerminalInfo> {
+  return invoke<TerminalInfo>("terminal_spawn", { cwd, ...options });
+}
+
+export async function terminalWrite(id: string, data: string): Promise<void> {
+  return invoke<void>("terminal_write", { id, data });
+}
+
+export async function terminalResize(id: string, cols: number, rows: number): Promise<TerminalInfo> {
+  return invoke<TerminalInfo>("terminal_resize", { id, cols, rows });
+}
+
+/** Sessions oldest first, finished ones included; only those of `workspace` when given. */
+export async function listTerminals(workspace?: string): Promise<TerminalInfo[]> {
+  return invoke<TerminalInfo[]>("list_terminals", { workspace });
+}
+
+/** Ends a session, or forgets a finished one. */
+export async function killTerminal(id: string): Promise<void> {
+  return invoke<void>("kill_terminal", { id });
+}
+
+/** The output a session still holds, escape sequences included, to repaint a terminal view. */
+export async function getTerminalScrollback(id: string): Promise<string> {
+  return invoke<string>("get_terminal_scrollback", { id });
+}
+
+export function onTerminalOutput(handler: (event: { id: string; data: string }) => void): Promise<UnlistenFn> {
+  return listen<{ id: string; data: string }>("terminal:output", (event) => handler(event.payload));
+}
+
+export function onTerminalExit(handler: (event: { id: string; exitCode: number | null }) => void): Promise<UnlistenFn> {
+  return listen<{ id: string; exitCode: number | null }>("terminal:exit", (event) => handler(event.payload));
+}
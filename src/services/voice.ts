@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type Dictation = {
+  /** Stops recording and resolves with the transcribed text. */
+  stop(): Promise<string>;
+  /** Stops recording and throws the audio away. */
+  cancel(): void;
+};
+
+/** Records from the default microphone until `stop()`, then transcribes in the backend. */
+export async function startDictation(): Promise<Dictation> {
+  const stream = await navigator.mediaDevices.getUserMedia({ audio: true });
+  const mimeType = MediaRecorder.isTypeSupported("audio/webm") ? "audio/webm" : "audio/mp4";
+  const recorder = new MediaRecorder(stream, { mimeType });
+  const chunks: Blob[] = [];
+  recorder.ondataavailable = (event) => {
+    if (event.data.size > 0) chunks.push(event.data);
+  };
+  recorder.start();
+
+  const finish = () =>
+    new Promise<Blob>((resolve) => {
+      recorder.onstop = () => {
+        stream.getTracks().forEach((track) => track.stop());
+        resolve(new Blob(chunks, { type: mimeType }));
+      };
+      recorder.stop();
+    });
+
+  return {
+    async stop() {
+      const blob = await finish();
+      const audio = Array.from(new Uint8Array(await blob.arrayBuffer()));
+      return invoke<string>("transcribe_audio", {
+        audio,
+        fileName: mimeType === "audio/webm" ? "recording.webm" : "recording.m4a",
+      });
+    },
+    cancel() {
+      void finish();
+    },
+  };
+}
+
+export async function transcribeFile(path: string): Promise<string> {
+  return invoke<string>("transcribe_audio", { path });
+}
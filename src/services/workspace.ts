@@ -0,0 +1,531 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type TreeNode = {
+  name: string;
+  /** Absolute path. */
+  path: string;
+  kind: "file" | "dir";
+  size?: number;
+  /** Absent for files and for folders below the requested depth; list those on expand. */
+  children?: TreeNode[];
+};
+
+export type WorkspaceTree = {
+  root: string;
+  entries: TreeNode[];
+  truncated: boolean;
+};
+
+/** The workspace's files and folders, without gitignored entries or build output. */
+export async function listWorkspaceTree(path: string, depth?: number, includeHidden = false): Promise<WorkspaceTree> {
+  return invoke<WorkspaceTree>("list_workspace_tree", { path, depth: depth ?? null, includeHidden });
+}
+
+/** One tree per folder of a multi-root workspace, in order; duplicates are dropped. */
+export async function listWorkspaceTrees(
+  paths: string[],
+  depth?: number,
+  includeHidden = false,
+): Promise<WorkspaceTree[]> {
+  return invoke<WorkspaceTree[]>("list_workspace_trees", { paths, depth: depth ?? null, includeHidden });
+}
+
+export type WorkspaceStats = {
+  root: string;
+  totalFiles: number;
+  totalLines: number;
+  totalBytes: number;
+  languages: LanguageStats[];
+  /** Most bytes first; `extension` is lowercase without the dot, empty for none. */
+  extensions: { extension: string; files: number; bytes: number }[];
+  /** Biggest first; paths are relative to `root`. */
+  largestFiles: { path: string; bytes: number; lines: number }[];
+  git: {
+    branch: string | null;
+    totalCommits: number;
+    commitsLast30Days: number;
+    lastCommitAt: number | null;
+    lastCommitSummary: string | null;
+    topAuthors: { name: string; commits: number }[];
+  } | null;
+  /** Markdown digest suitable as context for the agent. */
+  summary: string;
+};
+
+/** Counts, sizes, languages and an extension breakdown, skipping what the tree skips. */
+export async function getWorkspaceStats(workspace: string): Promise<WorkspaceStats> {
+  return invoke<WorkspaceStats>("get_workspace_stats", { workspace });
+}
+
+export type LanguageStats = {
+  language: string;
+  files: number;
+  /** `code + comments + blanks`. */
+  lines: number;
+  code: number;
+  comments: number;
+  blanks: number;
+  bytes: number;
+};
+
+export type LanguageReport = {
+  root: string;
+  totalFiles: number;
+  totalLines: number;
+  totalCode: number;
+  /** Most lines first. */
+  languages: LanguageStats[];
+  /** Markdown table suitable as context for the agent. */
+  summary: string;
+};
+
+/** Lines of code per language under a workspace or any folder in it, for the header. */
+export async function getLanguageStats(path: string): Promise<LanguageReport> {
+  return invoke<LanguageReport>("get_language_stats", { path });
+}
+
+/** Payload of `workspace:changed`; paths are relative to `workspace`. */
+export type WorkspaceChanged = {
+  workspace: string;
+  created: string[];
+  modified: string[];
+  deleted: string[];
+  /** Too many paths changed to list; reload the tree. */
+  truncated: boolean;
+};
+
+/** Starts emitting debounced `workspace:changed` events for `path`; returns the resolved root. */
+export async function watchWorkspace(path: string): Promise<string> {
+  return invoke<string>("watch_workspace", { path });
+}
+
+export async function unwatchWorkspace(path: string): Promise<boolean> {
+  return invoke<boolean>("unwatch_workspace", { path });
+}
+
+/** Watches every folder of a multi-root workspace; events name the folder in `workspace`. */
+export async function watchWorkspaceRoots(paths: string[]): Promise<string[]> {
+  return Promise.all(paths.map(watchWorkspace));
+}
+
+export type SearchOptions = {
+  /** Treat the query as a regular expression instead of literal text. */
+  regex?: boolean;
+  /** Smart case when omitted: case-sensitive only if the query has an uppercase letter. */
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  /** Globs such as `*.rs` or `src/**`; only matching files are searched. */
+  include?: string[];
+  exclude?: string[];
+  contextLines?: number;
+  maxResults?: number;
+  includeHidden?: boolean;
+  /** Other folders of a multi-root workspace, searched after the main one. */
+  roots?: string[];
+};
+
+export type SearchMatch = {
+  /** The workspace folder the file is in; `path` is relative to it. */
+  root: string;
+  path: string;
+  lineNumber: number;
+  line: string;
+  /** Character ranges of the matches within `line`, end exclusive. */
+  ranges: [number, number][];
+  before: string[];
+  after: string[];
+};
+
+export type SearchResults = {
+  matches: SearchMatch[];
+  filesSearched: number;
+  filesMatched: number;
+  truncated: boolean;
+};
+
+export async function searchWorkspace(path: string, query: string, options?: SearchOptions): Promise<SearchResults> {
+  return invoke<SearchResults>("search_workspace", { path, query, options: options ?? null });
+}
+
+export type PatchFile = {
+  /** Relative to the workspace root; the new path for renames. */
+  path: string;
+  oldPath: string | null;
+  action: "create" | "modify" | "delete" | "rename";
+  additions: number;
+  deletions: number;
+  hunks: number;
+  /** Hunks whose context matched away from the line the diff gives. */
+  shiftedHunks: number;
+};
+
+export type PatchConflict = {
+  path: string;
+  /** 1-based; null when the whole file is the problem (missing, already exists, binary). */
+  hunk: number | null;
+  line: number | null;
+  reason: string;
+  expected: string[];
+  actual: string[];
+};
+
+export type PatchReport = {
+  files: PatchFile[];
+  conflicts: PatchConflict[];
+  clean: boolean;
+  applied: boolean;
+};
+
+/** Checks a unified diff against the workspace without writing anything. */
+export async function previewPatch(workspace: string, diff: string): Promise<PatchReport> {
+  return invoke<PatchReport>("preview_patch", { workspace, diff });
+}
+
+/** Applies a unified diff all-or-nothing; with conflicts nothing is written and `applied` is false. */
+export async function applyPatch(workspace: string, diff: string): Promise<PatchReport> {
+  return invoke<PatchReport>("apply_patch", { workspace, diff });
+}
+
+export type WorkspaceFile = {
+  root: string;
+  path: string;
+  content: string;
+  size: number;
+};
+
+export type FileWritten = {
+  root: string;
+  path: string;
+  bytes: number;
+  created: boolean;
+};
+
+/** Reads a text file inside the workspace; paths escaping it, symlinks included, are rejected. */
+export async function readWorkspaceFile(workspace: string, path: string, roots?: string[]): Promise<WorkspaceFile> {
+  return invoke<WorkspaceFile>("read_workspace_file", { workspace, roots: roots ?? null, path });
+}
+
+export type FileRange = {
+  root: string;
+  path: string;
+  /** Moved forward past a character split by the request. */
+  offset: number;
+  /** Where the following page starts. */
+  nextOffset: number;
+  content: string;
+  size: number;
+  eof: boolean;
+};
+
+export type FileLines = {
+  root: string;
+  path: string;
+  /** 1-based number of the first line returned. */
+  start: number;
+  lines: string[];
+  /** Byte offset of `start`, for switching to `readFileRange`. */
+  offset: number;
+  /** Numbers of lines cut at 64 KB. */
+  truncatedLines: number[];
+  size: number;
+  eof: boolean;
+};
+
+/** Up to `length` bytes (1 MB max) from `offset`, cut to whole characters. */
+export async function readFileRange(
+  workspace: string,
+  path: string,
+  offset: number,
+  length: number,
+  roots?: string[]
+): Promise<FileRange> {
+  return invoke<FileRange>("read_file_range", { workspace, roots: roots ?? null, path, offset, length });
+}
+
+/** Lines `start` to `end`, 1-based and inclusive, at most 10,000 at a time. */
+export async function readFileLines(
+  workspace: string,
+  path: string,
+  start: number,
+  end: number,
+  roots?: string[]
+): Promise<FileLines> {
+  return invoke<FileLines>("read_file_lines", { workspace, roots: roots ?? null, path, start, end });
+}
+
+export async function writeWorkspaceFile(
+  workspace: string,
+  path: string,
+  content: string,
+  roots?: string[],
+): Promise<FileWritten> {
+  return invoke<FileWritten>("write_workspace_file", { workspace, roots: roots ?? null, path, content });
+}
+
+export type FileVersion = {
+  version: number;
+  savedAt: number;
+  /** False when the file didn't exist yet; reverting to it removes the file. */
+  existed: boolean;
+  size: number;
+  /** What was about to change the file: `write`, `patch`, `changeset` or `revert`. */
+  reason: string;
+};
+
+/** Earlier versions of `path` (absolute) saved before agent edits, newest first. */
+export async function listFileHistory(path: string): Promise<FileVersion[]> {
+  return invoke<FileVersion[]>("list_file_history", { path });
+}
+
+/** Restores a saved version; the current content is saved first so the revert can be undone. */
+export async function revertFile(path: string, version: number): Promise<FileVersion> {
+  return invoke<FileVersion>("revert_file", { path, version });
+}
+
+export type IndexStatus = {
+  workspace: string;
+  profileId: string;
+  model: string;
+  dimensions: number;
+  files: number;
+  chunks: number;
+  /** Whether matching code is added to prompts sent from this workspace. */
+  autoContext: boolean;
+  updatedAt: number;
+  indexing: boolean;
+};
+
+/** Payload of `semantic-index:progress`. */
+export type IndexProgress = {
+  workspace: string;
+  indexed: number;
+  total: number;
+  done: boolean;
+  error: string | null;
+};
+
+export type SemanticMatch = {
+  path: string;
+  startLine: number;
+  endLine: number;
+  content: string;
+  score: number;
+};
+
+/** Indexes the workspace in the background with the profile's embedding model. */
+export async function indexWorkspace(
+  workspace: string,
+  profileId: string,
+  model?: string,
+  autoContext?: boolean,
+): Promise<IndexStatus> {
+  return invoke<IndexStatus>("index_workspace", {
+    workspace,
+    profileId,
+    model: model ?? null,
+    autoContext: autoContext ?? null,
+  });
+}
+
+export async function getIndexStatus(workspace: string): Promise<IndexStatus | null> {
+  return invoke<IndexStatus | null>("get_index_status", { workspace });
+}
+
+export async function semanticSearch(workspace: string, query: string, k?: number): Promise<SemanticMatch[]> {
+  return invoke<SemanticMatch[]>("semantic_search", { workspace, query, k: k ?? null });
+}
+
+export type RecentWorkspace = {
+  path: string;
+  name: string;
+  lastOpenedAt: number;
+  pinned: boolean;
+  /** False when the folder was moved or deleted since it was opened. */
+  exists: boolean;
+};
+
+/** Records that a workspace was opened, for the recent list. */
+export async function openWorkspace(path: string): Promise<RecentWorkspace> {
+  return invoke<RecentWorkspace>("open_workspace", { path });
+}
+
+export async function getRecentWorkspaces(): Promise<RecentWorkspace[]> {
+  return invoke<RecentWorkspace[]>("get_recent_workspaces");
+}
+
+export async function removeRecentWorkspace(path: string): Promise<boolean> {
+  return invoke<boolean>("remove_recent_workspace", { path });
+}
+
+export async function setWorkspacePinned(path: string, pinned: boolean): Promise<boolean> {
+  return invoke<boolean>("set_workspace_pinned", { path, pinned });
+}
+
+/** Contents of `.ohmycowork/config.json`, committed with the project. */
+export type WorkspaceConfig = {
+  model?: string | null;
+  systemPrompt?: string | null;
+  allowedTools?: string[];
+  ignoredPaths?: string[];
+};
+
+export async function getWorkspaceConfig(workspace: string): Promise<WorkspaceConfig | null> {
+  return invoke<WorkspaceConfig | null>("get_workspace_config", { workspace });
+}
+
+export type FilePreview = {
+  path: string;
+  kind: "text" | "image" | "binary" | "dir";
+  size: number;
+  modifiedAt: number | null;
+  mime?: string;
+  width?: number;
+  height?: number;
+  /** The start of a text file; never set for binaries. */
+  text?: string;
+  /** `data:` URL of an image no bigger than 2 MB. */
+  thumbnail?: string;
+  truncated: boolean;
+};
+
+/** Classifies an absolute path and returns a text excerpt or an inline image; binaries get metadata only. */
+export async function statAndPreview(path: string): Promise<FilePreview> {
+  return invoke<FilePreview>("stat_and_preview", { path });
+}
+
+export type ScaffoldTemplate = {
+  id: string;
+  name: string;
+  description: string | null;
+  builtin: boolean;
+  /** Placeholders without a default; `name` defaults to the new folder's name. */
+  variables: string[];
+};
+
+export type ScaffoldReport = {
+  root: string;
+  files: string[];
+  /** Placeholders that had no value and were left empty. */
+  missing: string[];
+};
+
+export async function listScaffoldTemplates(): Promise<ScaffoldTemplate[]> {
+  return invoke<ScaffoldTemplate[]>("list_scaffold_templates");
+}
+
+/** Creates a project in a new or empty folder from a bundled or user template. */
+export async function scaffoldFromTemplate(
+  templateId: string,
+  path: string,
+  vars?: Record<string, string>,
+): Promise<ScaffoldReport> {
+  return invoke<ScaffoldReport>("scaffold_from_template", { templateId, path, vars: vars ?? null });
+}
+
+export type Checkpoint = {
+  id: string;
+  workspace: string;
+  label: string | null;
+  /** The agent run it was taken before, for automatic checkpoints. */
+  requestId: string | null;
+  createdAt: number;
+  fileCount: number;
+  totalBytes: number;
+};
+
+export type RestoreReport = {
+  checkpoint: Checkpoint;
+  restored: string[];
+  /** Files created after the checkpoint, now removed. */
+  removed: string[];
+};
+
+export type CheckpointSettings = {
+  /** Take a checkpoint before every agent run in a workspace. */
+  auto: boolean;
+};
+
+export async function createCheckpoint(workspace: string, label?: string): Promise<Checkpoint> {
+  return invoke<Checkpoint>("create_checkpoint", { workspace, label: label ?? null });
+}
+
+/** Newest first. */
+export async function listCheckpoints(workspace: string): Promise<Checkpoint[]> {
+  return invoke<Checkpoint[]>("list_checkpoints", { workspace });
+}
+
+/** Rolls the whole workspace back; the current state is checkpointed first. */
+export async function restoreCheckpoint(id: string): Promise<RestoreReport> {
+  return invoke<RestoreReport>("restore_checkpoint", { id });
+}
+
+export async function getCheckpointSettings(): Promise<CheckpointSettings> {
+  return invoke<CheckpointSettings>("get_checkpoint_settings");
+}
+
+export async function setCheckpointSettings(settings: CheckpointSettings): Promise<CheckpointSettings> {
+  return invoke<CheckpointSettings>("set_checkpoint_settings", { settings });
+}
+
+export type EnvironmentTool = {
+  name: string;
+  kind: "runtime" | "packageManager";
+  /** Null when the program is installed but its version could not be read. */
+  version: string | null;
+  path: string;
+};
+
+export type ProjectFile = {
+  path: string;
+  /** The package manager, language or toolchain the file is for. */
+  tool: string;
+};
+
+export type EnvironmentReport = {
+  workspace: string;
+  os: string;
+  /** Only the toolchains found on PATH. */
+  tools: EnvironmentTool[];
+  lockfiles: ProjectFile[];
+  manifests: ProjectFile[];
+  versionPins: { path: string; content: string }[];
+  detectedAt: number;
+};
+
+/** Installed toolchains plus the workspace's lockfiles, manifests and version pins; the agent gets the same summary. */
+export async function detectEnvironment(workspace: string, refresh = false): Promise<EnvironmentReport> {
+  return invoke<EnvironmentReport>("detect_environment", { workspace, refresh });
+}
+
+export type ZipReport = {
+  dest: string;
+  files: number;
+  /** Uncompressed size of everything added. */
+  bytes: number;
+  archiveBytes: number;
+};
+
+export type ZipProgress = {
+  dest: string;
+  filesDone: number;
+  filesTotal: number;
+  bytesDone: number;
+  done: boolean;
+};
+
+/**
+ * Zips `paths` (the whole workspace when empty) to the absolute `dest`, skipping gitignored and hidden
+ * files plus anything matching the gitignore-style `exclude` patterns.
+ */
+export async function exportZip(
+  workspace: string,
+  dest: string,
+  options: { paths?: string[]; exclude?: string[] } = {}
+): Promise<ZipReport> {
+  return invoke<ZipReport>("export_zip", { workspace, dest, paths: options.paths ?? null, exclude: options.exclude ?? null });
+}
+
+export function onExportZipProgress(handler: (progress: ZipProgress) => void): Promise<UnlistenFn> {
+  return listen<ZipProgress>("export-zip:progress", (event) => handler(event.payload));
+}